@@ -0,0 +1,99 @@
+use criterion::{black_box, criterion_group, criterion_main, BatchSize, BenchmarkId, Criterion};
+use ocm_core::core::ids::Did;
+use ocm_core::sync::crdt::{CrdtMemory, MemoryOperation, OperationType};
+use ocm_core::SignedMemory;
+
+const OP_LOG_SIZES: [usize; 3] = [100, 1_000, 10_000];
+const FIELDS: usize = 50; // Ops cycle over these so the log grows but the data stays small
+
+fn base_memory() -> SignedMemory {
+    let did = Did::parse("did:plc:bench").unwrap();
+    SignedMemory::new(&did, "experience", r#"{"fields":{}}"#)
+}
+
+fn set_operation(memory: &CrdtMemory, peer_id: &str, n: usize) -> MemoryOperation {
+    MemoryOperation {
+        operation_id: format!("{}-{}", peer_id, n),
+        operation_type: OperationType::Set,
+        field_path: format!("fields.f{}", n % FIELDS),
+        value: serde_json::json!(n),
+        vector_clock: memory.vector_clock.clone(),
+        timestamp: chrono::Utc::now().to_rfc3339(),
+        signature: None,
+    }
+}
+
+fn with_operations(
+    mut memory: CrdtMemory,
+    peer_id: &str,
+    start: usize,
+    count: usize,
+) -> CrdtMemory {
+    for n in start..start + count {
+        let operation = set_operation(&memory, peer_id, n);
+        memory.apply_operation(operation, peer_id).expect("apply");
+    }
+    memory
+}
+
+fn apply_operation(c: &mut Criterion) {
+    let mut group = c.benchmark_group("CrdtMemory::apply_operation");
+    for size in OP_LOG_SIZES {
+        let memory = with_operations(CrdtMemory::new(base_memory(), "peer-a"), "peer-a", 0, size);
+        let operation = set_operation(&memory, "peer-b", size);
+
+        group.bench_with_input(BenchmarkId::from_parameter(size), &size, |b, _| {
+            b.iter_batched(
+                || (memory.clone(), operation.clone()),
+                |(mut memory, operation)| {
+                    memory
+                        .apply_operation(black_box(operation), "peer-b")
+                        .expect("apply")
+                },
+                BatchSize::LargeInput,
+            )
+        });
+    }
+    group.finish();
+}
+
+fn merge_with(c: &mut Criterion) {
+    let mut group = c.benchmark_group("CrdtMemory::merge_with");
+    group.sample_size(20);
+    for size in OP_LOG_SIZES {
+        let shared = with_operations(CrdtMemory::new(base_memory(), "peer-a"), "peer-a", 0, size);
+
+        // The remote copy saw 10% more ops than we did: a fast-forward merge
+        let ahead = with_operations(shared.clone(), "peer-b", size, size / 10);
+        group.bench_with_input(BenchmarkId::new("causal", size), &size, |b, _| {
+            b.iter_batched(
+                || shared.clone(),
+                |mut local| {
+                    local
+                        .merge_with(black_box(&ahead), "peer-a")
+                        .expect("merge")
+                },
+                BatchSize::LargeInput,
+            )
+        });
+
+        // Both copies took 10% more ops independently: conflict resolution
+        let local = with_operations(shared.clone(), "peer-a", size, size / 10);
+        let remote = with_operations(shared.clone(), "peer-b", size * 2, size / 10);
+        group.bench_with_input(BenchmarkId::new("concurrent", size), &size, |b, _| {
+            b.iter_batched(
+                || local.clone(),
+                |mut local| {
+                    local
+                        .merge_with(black_box(&remote), "peer-a")
+                        .expect("merge")
+                },
+                BatchSize::LargeInput,
+            )
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, apply_operation, merge_with);
+criterion_main!(benches);
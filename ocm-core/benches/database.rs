@@ -0,0 +1,92 @@
+use criterion::{criterion_group, criterion_main, BatchSize, BenchmarkId, Criterion, Throughput};
+use ocm_core::{Database, Did, SignedMemory};
+use std::path::PathBuf;
+
+mod embedded {
+    refinery::embed_migrations!("migrations");
+}
+
+const BATCH_SIZES: [usize; 2] = [100, 1_000];
+
+/// A migrated database in the temp directory, removed on drop
+struct BenchDatabase {
+    database: Database,
+    path: PathBuf,
+}
+
+impl BenchDatabase {
+    fn new() -> Self {
+        let path = std::env::temp_dir().join(format!("ocm-bench-{}.db", uuid::Uuid::new_v4()));
+        let mut conn = rusqlite::Connection::open(&path).expect("open");
+        embedded::migrations::runner()
+            .run(&mut conn)
+            .expect("migrate");
+        let database = Database::new(&path.to_string_lossy()).expect("database");
+        Self { database, path }
+    }
+}
+
+impl Drop for BenchDatabase {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.path);
+    }
+}
+
+fn memories(count: usize) -> Vec<SignedMemory> {
+    let did = Did::parse("did:plc:bench").unwrap();
+    (0..count)
+        .map(|n| {
+            let data = serde_json::json!({ "event": "camp check-in", "n": n }).to_string();
+            let mut memory = SignedMemory::new(&did, "attendance", &data);
+            memory.signature = "c2lnbmF0dXJl".to_string(); // Not verified on insert
+            memory
+        })
+        .collect()
+}
+
+fn inserts(c: &mut Criterion) {
+    let mut group = c.benchmark_group("Database inserts");
+    group.sample_size(10);
+    for size in BATCH_SIZES {
+        let batch = memories(size);
+        group.throughput(Throughput::Elements(size as u64));
+
+        // Snapshot import: the whole batch in one transaction
+        group.bench_with_input(
+            BenchmarkId::new("import_signed_memories", size),
+            &batch,
+            |b, batch| {
+                b.iter_batched(
+                    BenchDatabase::new,
+                    |db| {
+                        db.database.import_signed_memories(batch).expect("import");
+                        db // Dropped, and the file removed, outside the measurement
+                    },
+                    BatchSize::PerIteration,
+                )
+            },
+        );
+
+        // Live sync: one transaction per memory
+        group.bench_with_input(
+            BenchmarkId::new("create_signed_memory", size),
+            &batch,
+            |b, batch| {
+                b.iter_batched(
+                    BenchDatabase::new,
+                    |db| {
+                        for memory in batch {
+                            db.database.create_signed_memory(memory).expect("insert");
+                        }
+                        db
+                    },
+                    BatchSize::PerIteration,
+                )
+            },
+        );
+    }
+    group.finish();
+}
+
+criterion_group!(benches, inserts);
+criterion_main!(benches);
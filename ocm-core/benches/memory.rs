@@ -0,0 +1,43 @@
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use ocm_core::identity::plc::{verify_memory_with_key, PlcIdentity};
+use ocm_core::SignedMemory;
+
+const DATA_SIZES: [usize; 3] = [256, 4 * 1024, 64 * 1024];
+
+fn memory_data(size: usize) -> String {
+    serde_json::json!({ "notes": "x".repeat(size) }).to_string()
+}
+
+fn compute_hash(c: &mut Criterion) {
+    let mut group = c.benchmark_group("SignedMemory::compute_hash");
+    for size in DATA_SIZES {
+        let data = memory_data(size);
+        group.throughput(Throughput::Bytes(data.len() as u64));
+        group.bench_with_input(BenchmarkId::from_parameter(size), &data, |b, data| {
+            b.iter(|| SignedMemory::compute_hash(black_box(data)))
+        });
+    }
+    group.finish();
+}
+
+fn sign_and_verify(c: &mut Criterion) {
+    let identity = PlcIdentity::generate(Some("bench".to_string())).expect("identity");
+
+    let mut group = c.benchmark_group("signature");
+    for size in DATA_SIZES {
+        let mut memory = SignedMemory::new(&identity.did, "experience", &memory_data(size));
+        group.throughput(Throughput::Bytes(memory.memory_data.len() as u64));
+
+        group.bench_with_input(BenchmarkId::new("sign", size), &size, |b, _| {
+            b.iter(|| identity.sign_memory(black_box(&mut memory)).expect("sign"))
+        });
+        // Includes the hash check, as every received memory pays for both
+        group.bench_with_input(BenchmarkId::new("verify", size), &memory, |b, memory| {
+            b.iter(|| verify_memory_with_key(black_box(memory), &identity.keypair.public_key))
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, compute_hash, sign_and_verify);
+criterion_main!(benches);
@@ -0,0 +1,50 @@
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use ocm_core::networking::{MessageType, NetworkMessage};
+use ocm_core::{Did, OcmNetworking, SignedMemory};
+
+const PAYLOAD_MEMORIES: [usize; 3] = [1, 10, 100];
+
+/// A MemorySync payload carrying `count` memories
+fn payload(count: usize) -> String {
+    let did = Did::parse("did:plc:bench").unwrap();
+    let memories: Vec<SignedMemory> = (0..count)
+        .map(|n| {
+            let data = serde_json::json!({ "notes": "x".repeat(512), "n": n }).to_string();
+            SignedMemory::new(&did, "experience", &data)
+        })
+        .collect();
+    serde_json::to_string(&memories).expect("payload")
+}
+
+/// Build and authenticate a message, frame it as on the wire, then decode it again
+fn round_trip(c: &mut Criterion) {
+    let mut group = c.benchmark_group("NetworkMessage round trip");
+    for count in PAYLOAD_MEMORIES {
+        let payload = payload(count);
+        group.throughput(Throughput::Bytes(payload.len() as u64));
+        group.bench_with_input(
+            BenchmarkId::from_parameter(count),
+            &payload,
+            |b, payload| {
+                b.iter(|| {
+                    let message = OcmNetworking::create_authenticated_message(
+                        MessageType::MemorySync,
+                        payload.clone(),
+                        "bench-peer".to_string(),
+                    );
+                    let data = serde_json::to_vec(&message).expect("encode");
+                    let mut frame = (data.len() as u32).to_be_bytes().to_vec();
+                    frame.extend_from_slice(&data);
+
+                    let decoded: NetworkMessage =
+                        serde_json::from_slice(black_box(&frame[4..])).expect("decode");
+                    decoded
+                })
+            },
+        );
+    }
+    group.finish();
+}
+
+criterion_group!(benches, round_trip);
+criterion_main!(benches);
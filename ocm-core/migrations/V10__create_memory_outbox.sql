@@ -0,0 +1,12 @@
+-- Locally authored memories waiting to be broadcast; written in the same transaction as the memory
+CREATE TABLE memory_outbox (
+    outbox_id INTEGER PRIMARY KEY AUTOINCREMENT,
+    memory_id TEXT NOT NULL,
+    created_at TEXT NOT NULL,
+    attempts INTEGER NOT NULL DEFAULT 0,
+    last_attempt_at TEXT,
+    last_error TEXT,
+    delivered_at TEXT               -- NULL until at least one peer accepted the memory
+);
+
+CREATE INDEX idx_memory_outbox_pending ON memory_outbox(delivered_at, outbox_id);
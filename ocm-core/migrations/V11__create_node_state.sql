@@ -0,0 +1,11 @@
+-- Node-level settings that must survive restarts, e.g. the stable node ID
+CREATE TABLE node_metadata (
+    key TEXT PRIMARY KEY,
+    value TEXT NOT NULL
+);
+
+-- Last successful sync per peer, keyed by the peer's stable node ID
+CREATE TABLE peer_sync_state (
+    peer_node_id TEXT PRIMARY KEY,
+    last_synced_at TEXT NOT NULL
+);
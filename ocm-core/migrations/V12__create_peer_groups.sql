@@ -0,0 +1,15 @@
+-- Named trust circles controlling which memories are shared with which peers
+CREATE TABLE peer_group (
+    name TEXT PRIMARY KEY,
+    memory_types TEXT,            -- JSON array of shared memory types, NULL shares everything
+    created_at TEXT NOT NULL
+);
+
+CREATE TABLE peer_group_member (
+    group_name TEXT NOT NULL REFERENCES peer_group(name) ON DELETE CASCADE,
+    member TEXT NOT NULL,         -- Peer node ID or DID
+    assigned_at TEXT NOT NULL,
+    PRIMARY KEY (group_name, member)
+);
+
+CREATE INDEX idx_peer_group_member_member ON peer_group_member(member);
@@ -0,0 +1,14 @@
+-- Memories known only by header on partially synced nodes; the row is removed once the body arrives
+CREATE TABLE memory_header (
+    id TEXT PRIMARY KEY,
+    did TEXT NOT NULL,
+    memory_type TEXT NOT NULL,
+    content_hash TEXT NOT NULL,
+    signature TEXT NOT NULL,
+    timestamp TEXT NOT NULL,
+    updated_on TEXT NOT NULL,
+    source_peer TEXT NOT NULL,     -- Node ID of the peer that can serve the body
+    received_at TEXT NOT NULL
+);
+
+CREATE INDEX idx_memory_header_content_hash ON memory_header(content_hash);
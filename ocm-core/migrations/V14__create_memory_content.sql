@@ -0,0 +1,30 @@
+-- Memory bodies stored once per content hash and shared by every memory with that content
+CREATE TABLE memory_content (
+    content_hash TEXT PRIMARY KEY,
+    memory_data TEXT NOT NULL,
+    ref_count INTEGER NOT NULL DEFAULT 0,
+    created_at TEXT NOT NULL
+);
+
+-- NULL keeps the body inline in memory_data (rows written before the content store, or unverified bodies)
+ALTER TABLE signed_memory ADD COLUMN body_hash TEXT REFERENCES memory_content(content_hash);
+
+CREATE INDEX idx_signed_memory_body_hash ON signed_memory(body_hash);
+
+-- References are taken when a body is stored and released here, so every delete path
+-- (retention purge, archive, scrub, update) keeps the counts right
+CREATE TRIGGER memory_content_release_on_delete
+AFTER DELETE ON signed_memory
+WHEN OLD.body_hash IS NOT NULL
+BEGIN
+    UPDATE memory_content SET ref_count = ref_count - 1 WHERE content_hash = OLD.body_hash;
+    DELETE FROM memory_content WHERE content_hash = OLD.body_hash AND ref_count <= 0;
+END;
+
+CREATE TRIGGER memory_content_release_on_update
+AFTER UPDATE OF body_hash ON signed_memory
+WHEN OLD.body_hash IS NOT NULL
+BEGIN
+    UPDATE memory_content SET ref_count = ref_count - 1 WHERE content_hash = OLD.body_hash;
+    DELETE FROM memory_content WHERE content_hash = OLD.body_hash AND ref_count <= 0;
+END;
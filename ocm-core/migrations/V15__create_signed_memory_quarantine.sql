@@ -0,0 +1,17 @@
+-- Memories pulled from the live table because they failed verification; same shape as
+-- signed_memory_archive, plus why the memory was quarantined
+CREATE TABLE signed_memory_quarantine (
+    id TEXT PRIMARY KEY,
+    did TEXT NOT NULL,
+    memory_type TEXT NOT NULL,
+    memory_data TEXT NOT NULL,
+    content_hash TEXT NOT NULL,
+    signature TEXT NOT NULL,
+    timestamp TEXT NOT NULL,
+    updated_on TEXT NOT NULL,
+    co_signatures TEXT NOT NULL DEFAULT '[]',
+    reason TEXT NOT NULL,
+    quarantined_at TEXT NOT NULL
+);
+
+CREATE INDEX idx_signed_memory_quarantine_did ON signed_memory_quarantine(did);
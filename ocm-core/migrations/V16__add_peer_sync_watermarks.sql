@@ -0,0 +1,4 @@
+-- Watermark for each sync cursor: the memories held right after the sync, as a count
+-- and a digest of their content hashes. NULL for cursors recorded before watermarks
+ALTER TABLE peer_sync_state ADD COLUMN memory_count INTEGER;
+ALTER TABLE peer_sync_state ADD COLUMN memory_digest TEXT;
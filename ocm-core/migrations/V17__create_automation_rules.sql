@@ -0,0 +1,14 @@
+-- Operator-written Rhai scripts run when matching activity events occur
+CREATE TABLE automation_rule (
+    name TEXT PRIMARY KEY,
+    description TEXT,
+    event_type TEXT NOT NULL,     -- Activity event type that triggers the rule
+    memory_type TEXT,             -- Only events about this memory type, NULL for any
+    script TEXT NOT NULL,
+    enabled INTEGER NOT NULL DEFAULT 1,
+    updated_at TEXT NOT NULL,
+    last_run_at TEXT,
+    last_error TEXT               -- From the latest run, NULL if it succeeded
+);
+
+CREATE INDEX idx_automation_rule_event_type ON automation_rule(event_type);
@@ -0,0 +1,18 @@
+-- Derived memories computed by this node: the current memory for each derivation and key
+CREATE TABLE memory_derivation (
+    derivation TEXT NOT NULL,
+    derivation_key TEXT NOT NULL,
+    memory_id TEXT NOT NULL,
+    computed_at TEXT NOT NULL,
+    PRIMARY KEY (derivation, derivation_key)
+);
+
+-- Source memory versions each derived memory was computed from
+CREATE TABLE memory_derivation_source (
+    memory_id TEXT NOT NULL,             -- The derived memory
+    source_memory_id TEXT NOT NULL,
+    source_content_hash TEXT NOT NULL,
+    PRIMARY KEY (memory_id, source_memory_id)
+);
+
+CREATE INDEX idx_memory_derivation_source_source ON memory_derivation_source(source_memory_id);
@@ -0,0 +1,16 @@
+-- Labels on memories, resolved from signed memory_tag memories: the latest operation
+-- on each (memory, tag) pair wins, and removals are kept so older additions stay undone
+CREATE TABLE memory_tag (
+    memory_id TEXT NOT NULL,
+    tag TEXT NOT NULL,
+    removed INTEGER NOT NULL DEFAULT 0,
+    tagged_by TEXT NOT NULL,         -- DID that signed the latest operation
+    tagged_at TEXT NOT NULL,
+    tag_memory_id TEXT NOT NULL,     -- The memory_tag memory carrying the latest operation
+    PRIMARY KEY (memory_id, tag)
+);
+
+CREATE INDEX idx_memory_tag_tag ON memory_tag(tag);
+
+-- JSON array of tags; when set, a group only receives memories carrying one of them
+ALTER TABLE peer_group ADD COLUMN tags TEXT;
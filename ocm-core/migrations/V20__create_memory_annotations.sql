@@ -0,0 +1,9 @@
+-- Index of annotation memories by the memory they refer to, for threaded retrieval
+CREATE TABLE memory_annotation (
+    annotation_id TEXT PRIMARY KEY,      -- The annotation memory
+    target_memory_id TEXT NOT NULL,
+    target_content_hash TEXT NOT NULL,   -- Content the annotation was written against
+    annotated_at TEXT NOT NULL
+);
+
+CREATE INDEX idx_memory_annotation_target ON memory_annotation(target_memory_id);
@@ -0,0 +1,10 @@
+-- Organizations hosted by a multi-tenant node; each tenant's data lives in its own SQLite file
+CREATE TABLE tenant (
+    id TEXT PRIMARY KEY,          -- Lowercase slug, also the tenant database file name
+    name TEXT NOT NULL,
+    status TEXT NOT NULL DEFAULT 'active',  -- 'active' or 'suspended'
+    max_memories INTEGER,         -- Memory quota, NULL for the node default
+    requests_per_minute INTEGER,  -- API rate limit, NULL for the node default
+    created_at TEXT NOT NULL,
+    updated_at TEXT NOT NULL
+);
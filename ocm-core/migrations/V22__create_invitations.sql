@@ -0,0 +1,23 @@
+-- Signed invitations letting a new DID into a closed federation or a peer group.
+-- Rows are written where an invitation is issued or redeemed; together they record who invited whom
+CREATE TABLE invitation (
+    id TEXT PRIMARY KEY,
+    issuer_did TEXT NOT NULL,
+    group_name TEXT,              -- Peer group joined, NULL for the federation itself
+    expires_at TEXT NOT NULL,
+    created_at TEXT NOT NULL,
+    redeemed_by_did TEXT,         -- Set once; invitations are single-use
+    redeemed_at TEXT,
+    revoked INTEGER NOT NULL DEFAULT 0
+);
+
+CREATE INDEX idx_invitation_issuer ON invitation(issuer_did);
+CREATE INDEX idx_invitation_redeemed_by ON invitation(redeemed_by_did);
+
+-- DIDs admitted to a closed federation
+CREATE TABLE federation_member (
+    did TEXT PRIMARY KEY,
+    invited_by_did TEXT,          -- NULL for founding members from config
+    invitation_id TEXT,
+    joined_at TEXT NOT NULL
+);
@@ -0,0 +1,20 @@
+-- Human-readable handles (alice.family-ocm) for DIDs, one current handle per DID
+CREATE TABLE handle (
+    handle TEXT PRIMARY KEY,
+    did TEXT NOT NULL UNIQUE,
+    verification TEXT NOT NULL,   -- local, dns or well_known
+    verified_at TEXT NOT NULL,
+    registered_at TEXT NOT NULL
+);
+
+-- Every handle a DID has had, so old links and mentions can still be traced
+CREATE TABLE handle_change (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    did TEXT NOT NULL,
+    old_handle TEXT,              -- NULL when the DID had no handle before
+    new_handle TEXT,              -- NULL when the handle was released
+    changed_at TEXT NOT NULL
+);
+
+CREATE INDEX idx_handle_change_did ON handle_change(did, changed_at);
+CREATE INDEX idx_handle_change_old ON handle_change(old_handle);
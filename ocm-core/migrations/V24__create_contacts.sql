@@ -0,0 +1,19 @@
+-- Contact book of the local identity, rebuilt from its private contact memories
+CREATE TABLE contact (
+    did TEXT PRIMARY KEY,
+    display_name TEXT NOT NULL,
+    verification TEXT NOT NULL,      -- tofu or verified
+    key_fingerprint TEXT,            -- Pinned at first use; NULL if the key could not be resolved
+    notes TEXT NOT NULL DEFAULT '',
+    removed INTEGER NOT NULL DEFAULT 0,
+    updated_at TEXT NOT NULL,        -- Timestamp of the contact memory applied last
+    contact_memory_id TEXT NOT NULL
+);
+
+CREATE TABLE contact_group (
+    contact_did TEXT NOT NULL,
+    group_name TEXT NOT NULL,
+    PRIMARY KEY (contact_did, group_name)
+);
+
+CREATE INDEX idx_contact_group_name ON contact_group(group_name);
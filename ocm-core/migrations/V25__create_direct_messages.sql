@@ -0,0 +1,13 @@
+-- Decrypted direct messages to or from the local identity. The sealed envelopes
+-- travel as direct_message memories; only the participants can open them
+CREATE TABLE direct_message (
+    id TEXT PRIMARY KEY,             -- ID of the direct_message memory
+    sender_did TEXT NOT NULL,
+    recipient_did TEXT NOT NULL,
+    body TEXT NOT NULL,
+    sent_at TEXT NOT NULL,
+    read_at TEXT                     -- NULL until the recipient reads it
+);
+
+CREATE INDEX idx_direct_message_recipient ON direct_message(recipient_did, read_at);
+CREATE INDEX idx_direct_message_sent_at ON direct_message(sent_at);
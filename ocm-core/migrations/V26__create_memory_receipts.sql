@@ -0,0 +1,9 @@
+-- Signed delivery and read receipts, kept alongside the memories they acknowledge
+CREATE TABLE memory_receipt (
+    memory_id TEXT NOT NULL,
+    did TEXT NOT NULL,               -- DID that received the memory and signed the receipt
+    kind TEXT NOT NULL,              -- delivered or read
+    signed_at TEXT NOT NULL,
+    receipt_memory_id TEXT NOT NULL,
+    PRIMARY KEY (memory_id, did, kind)
+);
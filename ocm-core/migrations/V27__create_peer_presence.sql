@@ -0,0 +1,8 @@
+-- Latest presence announced by each peer in a trusted peer group
+CREATE TABLE peer_presence (
+    peer_id TEXT PRIMARY KEY,
+    did TEXT,
+    status TEXT,                     -- syncing or idle; NULL if the peer does not share it
+    last_sync_at TEXT,
+    last_seen TEXT NOT NULL          -- When its last announcement arrived, on our clock
+);
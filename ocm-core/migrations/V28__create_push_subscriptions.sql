@@ -0,0 +1,15 @@
+-- Devices registered with the web server for push notifications about one DID
+CREATE TABLE push_subscription (
+    id TEXT PRIMARY KEY,
+    did TEXT NOT NULL,
+    kind TEXT NOT NULL,              -- web_push or fcm
+    endpoint TEXT NOT NULL UNIQUE,   -- Push service URL, or the FCM registration token
+    p256dh TEXT,
+    auth TEXT,
+    events TEXT NOT NULL,            -- JSON array of the events the subscriber wants
+    created_at TEXT NOT NULL,
+    last_pushed_at TEXT,
+    last_error TEXT
+);
+
+CREATE INDEX idx_push_subscription_did ON push_subscription(did);
@@ -0,0 +1,53 @@
+-- Counters bumped on every change to a collection, so HTTP list endpoints can answer
+-- If-None-Match / If-Modified-Since without running their query
+CREATE TABLE collection_version (
+    name TEXT PRIMARY KEY,           -- Table name
+    version INTEGER NOT NULL DEFAULT 0,
+    updated_at TEXT NOT NULL         -- RFC 3339, UTC
+);
+
+INSERT INTO collection_version (name, version, updated_at) VALUES
+    ('signed_memory', 0, strftime('%Y-%m-%dT%H:%M:%SZ', 'now')),
+    ('individual', 0, strftime('%Y-%m-%dT%H:%M:%SZ', 'now'));
+
+CREATE TRIGGER signed_memory_version_on_insert
+AFTER INSERT ON signed_memory
+BEGIN
+    UPDATE collection_version SET version = version + 1, updated_at = strftime('%Y-%m-%dT%H:%M:%SZ', 'now')
+    WHERE name = 'signed_memory';
+END;
+
+CREATE TRIGGER signed_memory_version_on_update
+AFTER UPDATE ON signed_memory
+BEGIN
+    UPDATE collection_version SET version = version + 1, updated_at = strftime('%Y-%m-%dT%H:%M:%SZ', 'now')
+    WHERE name = 'signed_memory';
+END;
+
+CREATE TRIGGER signed_memory_version_on_delete
+AFTER DELETE ON signed_memory
+BEGIN
+    UPDATE collection_version SET version = version + 1, updated_at = strftime('%Y-%m-%dT%H:%M:%SZ', 'now')
+    WHERE name = 'signed_memory';
+END;
+
+CREATE TRIGGER individual_version_on_insert
+AFTER INSERT ON individual
+BEGIN
+    UPDATE collection_version SET version = version + 1, updated_at = strftime('%Y-%m-%dT%H:%M:%SZ', 'now')
+    WHERE name = 'individual';
+END;
+
+CREATE TRIGGER individual_version_on_update
+AFTER UPDATE ON individual
+BEGIN
+    UPDATE collection_version SET version = version + 1, updated_at = strftime('%Y-%m-%dT%H:%M:%SZ', 'now')
+    WHERE name = 'individual';
+END;
+
+CREATE TRIGGER individual_version_on_delete
+AFTER DELETE ON individual
+BEGIN
+    UPDATE collection_version SET version = version + 1, updated_at = strftime('%Y-%m-%dT%H:%M:%SZ', 'now')
+    WHERE name = 'individual';
+END;
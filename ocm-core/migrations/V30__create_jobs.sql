@@ -0,0 +1,18 @@
+-- Long-running operations started through the API, kept so they survive restarts
+CREATE TABLE job (
+    id TEXT PRIMARY KEY,
+    kind TEXT NOT NULL,
+    state TEXT NOT NULL,             -- queued, running, succeeded, failed or cancelled
+    params TEXT NOT NULL,            -- JSON job specification
+    progress INTEGER NOT NULL DEFAULT 0,
+    total INTEGER,                   -- NULL until the job knows how much work it has
+    result TEXT,                     -- JSON, once succeeded
+    error TEXT,
+    cancel_requested INTEGER NOT NULL DEFAULT 0,
+    created_by TEXT,
+    created_at TEXT NOT NULL,
+    started_at TEXT,
+    finished_at TEXT
+);
+
+CREATE INDEX idx_job_state ON job(state, created_at);
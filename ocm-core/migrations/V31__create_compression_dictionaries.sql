@@ -0,0 +1,15 @@
+-- zstd dictionaries trained on the stored bodies of one memory type. Never deleted,
+-- since bodies compressed with a dictionary need it to be read back
+CREATE TABLE compression_dictionary (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    memory_type TEXT NOT NULL,
+    dictionary BLOB NOT NULL,
+    sample_count INTEGER NOT NULL,
+    created_at TEXT NOT NULL
+);
+
+CREATE INDEX idx_compression_dictionary_memory_type ON compression_dictionary(memory_type);
+
+-- Set when memory_data holds the body compressed with that dictionary. The content hash
+-- is always of the uncompressed body
+ALTER TABLE memory_content ADD COLUMN dictionary_id INTEGER REFERENCES compression_dictionary(id);
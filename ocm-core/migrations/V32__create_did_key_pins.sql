@@ -0,0 +1,17 @@
+-- Keys seen in the PLC documents of DIDs this node deals with, kept after they are
+-- rotated out so an unexpected change can be noticed and audited
+CREATE TABLE did_key_pin (
+    did TEXT NOT NULL,
+    public_key TEXT NOT NULL,     -- Multibase key from the DID document's verification methods
+    first_seen_at TEXT NOT NULL,
+    last_seen_at TEXT NOT NULL,   -- Last refresh that found the key still published
+    removed_at TEXT,              -- First refresh that no longer found it; NULL while current
+    PRIMARY KEY (did, public_key)
+);
+
+-- When each DID's document was last checked, so refreshes go to the stalest first
+CREATE TABLE did_refresh (
+    did TEXT PRIMARY KEY,
+    checked_at TEXT NOT NULL,
+    error TEXT                    -- Why the last check could not reach the directory
+);
@@ -0,0 +1,12 @@
+-- Memories accepted on an attached PLC operation chain while the directory could not be
+-- reached. The key the chain ended on is checked against the directory once it is back
+CREATE TABLE offline_verification (
+    memory_id TEXT PRIMARY KEY,
+    did TEXT NOT NULL,
+    public_key TEXT NOT NULL,      -- Multibase key the operation chain ended on
+    verified_at TEXT NOT NULL,
+    rechecked_at TEXT,             -- NULL until the directory has been asked
+    confirmed INTEGER              -- Whether the directory still publishes the key
+);
+
+CREATE INDEX idx_offline_verification_pending ON offline_verification(did) WHERE rechecked_at IS NULL;
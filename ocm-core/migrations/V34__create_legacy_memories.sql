@@ -0,0 +1,11 @@
+-- Memories signed under the placeholder stub_plc scheme, archived when
+-- `ocm-core migrate-identities` replaced them with re-signed copies
+CREATE TABLE legacy_memory (
+    memory_id TEXT PRIMARY KEY,   -- The archived legacy memory
+    legacy_did TEXT NOT NULL,
+    replaced_by TEXT NOT NULL,    -- ID of the re-signed copy
+    did TEXT NOT NULL,            -- PLC identity that signed the copy
+    migrated_at TEXT NOT NULL
+);
+
+CREATE INDEX idx_legacy_memory_legacy_did ON legacy_memory(legacy_did);
@@ -0,0 +1,34 @@
+-- Claim tokens are kept as salted hashes. Rebuilt because SQLite cannot drop the
+-- UNIQUE NOT NULL plaintext column; rows copied here keep their plaintext only until
+-- Database::hash_claim_tokens replaces it, which run_migrations does straight after
+CREATE TABLE claim_token_hashed (
+    id TEXT PRIMARY KEY,
+    token TEXT,                      -- Plaintext of a row not yet hashed, otherwise NULL
+    token_selector TEXT NOT NULL,    -- First characters of the code, to find the row
+    token_salt TEXT,                 -- Hex; NULL until hashed
+    token_hash TEXT,                 -- Hex SHA-256 of salt and code; NULL until hashed
+    memory_id TEXT NOT NULL,
+    organization_did TEXT NOT NULL,
+    expiry_timestamp TEXT NOT NULL,
+    claimed_by_did TEXT,
+    claimed_timestamp TEXT,
+    created_timestamp TEXT NOT NULL,
+    updated_on TEXT NOT NULL,
+    FOREIGN KEY (memory_id) REFERENCES signed_memory(id) ON DELETE CASCADE
+);
+
+INSERT INTO claim_token_hashed (
+    id, token, token_selector, memory_id, organization_did, expiry_timestamp,
+    claimed_by_did, claimed_timestamp, created_timestamp, updated_on
+)
+SELECT id, token, substr(token, 5, 6), memory_id, organization_did, expiry_timestamp,
+    claimed_by_did, claimed_timestamp, created_timestamp, updated_on
+FROM claim_token;
+
+DROP TABLE claim_token;
+ALTER TABLE claim_token_hashed RENAME TO claim_token;
+
+CREATE INDEX idx_claim_token_selector ON claim_token(token_selector);
+CREATE INDEX idx_claim_token_organization ON claim_token(organization_did);
+CREATE INDEX idx_claim_token_expiry ON claim_token(expiry_timestamp);
+CREATE INDEX idx_claim_token_claimed_by ON claim_token(claimed_by_did);
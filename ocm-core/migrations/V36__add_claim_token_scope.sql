@@ -0,0 +1,6 @@
+-- What redeeming a claim token grants: transfer, read_only or co_ownership
+ALTER TABLE claim_token ADD COLUMN scope TEXT NOT NULL DEFAULT 'transfer';
+-- The memory a transfer or co-ownership claim created
+ALTER TABLE claim_token ADD COLUMN claimed_memory_id TEXT;
+
+CREATE INDEX idx_claim_token_claimed_memory ON claim_token(claimed_memory_id);
@@ -0,0 +1,18 @@
+-- Handover tokens, issued by a record's current owner: the only DID that may redeem
+-- the token (NULL for anyone holding it) and the owner's signed authorization
+ALTER TABLE claim_token ADD COLUMN bound_to_did TEXT;
+ALTER TABLE claim_token ADD COLUMN handover_memory_id TEXT;
+
+-- Memories retired by a custody handover, archived when the new owner's copy replaced them
+CREATE TABLE custody_handover (
+    memory_id TEXT PRIMARY KEY,      -- The retired memory
+    from_did TEXT NOT NULL,
+    replaced_by TEXT NOT NULL,       -- ID of the new owner's copy
+    did TEXT NOT NULL,               -- The new owner
+    handover_memory_id TEXT NOT NULL, -- Authorization signed by both owners
+    claim_token_id TEXT NOT NULL,
+    handed_over_at TEXT NOT NULL
+);
+
+CREATE INDEX idx_custody_handover_replaced_by ON custody_handover(replaced_by);
+CREATE INDEX idx_custody_handover_did ON custody_handover(did);
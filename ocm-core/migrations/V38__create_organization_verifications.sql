@@ -0,0 +1,12 @@
+-- Why an organization DID is trusted: a proven domain handle, or an attestation
+-- signed by an organization that is itself verified
+CREATE TABLE organization_verification (
+    organization_did TEXT PRIMARY KEY,
+    method TEXT NOT NULL,             -- domain or attestation
+    domain TEXT,                      -- The handle's domain, for the domain method
+    attested_by TEXT,                 -- DID of the vouching organization
+    attestation_memory_id TEXT,       -- Its signed organization_attestation memory
+    verified_at TEXT NOT NULL
+);
+
+CREATE INDEX idx_organization_verification_attested_by ON organization_verification(attested_by);
@@ -0,0 +1,28 @@
+-- Privacy-preserving fingerprints of proxy records, compared across organizations.
+-- Neither column can be reversed without the linkage key
+CREATE TABLE proxy_linkage (
+    proxy_id TEXT PRIMARY KEY,
+    organization_did TEXT NOT NULL,
+    dob_key TEXT NOT NULL,           -- Keyed hash of the normalized date of birth
+    name_bloom TEXT NOT NULL,        -- Hex Bloom filter of the normalized name's bigrams
+    created_at TEXT NOT NULL,
+    FOREIGN KEY (proxy_id) REFERENCES proxy_memory(id) ON DELETE CASCADE
+);
+
+CREATE INDEX idx_proxy_linkage_dob_key ON proxy_linkage(dob_key);
+
+-- Probable duplicates between two organizations' proxy records
+CREATE TABLE duplicate_candidate (
+    id TEXT PRIMARY KEY,
+    proxy_id TEXT NOT NULL,
+    matched_proxy_id TEXT NOT NULL,
+    score REAL NOT NULL,
+    status TEXT NOT NULL DEFAULT 'pending', -- pending, merged or dismissed
+    merged_memory_id TEXT,
+    detected_at TEXT NOT NULL,
+    resolved_at TEXT,
+    UNIQUE (proxy_id, matched_proxy_id)
+);
+
+CREATE INDEX idx_duplicate_candidate_proxy ON duplicate_candidate(proxy_id);
+CREATE INDEX idx_duplicate_candidate_matched ON duplicate_candidate(matched_proxy_id);
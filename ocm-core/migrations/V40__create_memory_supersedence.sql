@@ -0,0 +1,10 @@
+-- Memories archived because a merge consolidated them into another memory
+CREATE TABLE memory_supersedence (
+    memory_id TEXT PRIMARY KEY,      -- The superseded memory
+    superseded_by TEXT NOT NULL,     -- ID of the consolidated memory
+    merge_memory_id TEXT NOT NULL,   -- The signed memory_merge record
+    did TEXT NOT NULL,               -- Owner of both
+    superseded_at TEXT NOT NULL
+);
+
+CREATE INDEX idx_memory_supersedence_superseded_by ON memory_supersedence(superseded_by);
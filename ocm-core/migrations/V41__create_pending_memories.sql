@@ -0,0 +1,23 @@
+-- Memories from peers held for review when inbound review is enabled; they enter
+-- signed_memory only once approved. Rejected versions are kept so they are not held again
+CREATE TABLE pending_memory (
+    id TEXT PRIMARY KEY,
+    memory_id TEXT NOT NULL,
+    did TEXT NOT NULL,
+    memory_type TEXT NOT NULL,
+    memory_data TEXT NOT NULL,
+    content_hash TEXT NOT NULL,
+    signature TEXT NOT NULL,
+    timestamp TEXT NOT NULL,
+    updated_on TEXT NOT NULL,
+    co_signatures TEXT NOT NULL DEFAULT '[]',
+    source_peer TEXT NOT NULL,            -- Node ID of the peer it came from
+    source_did TEXT,                      -- That peer's DID, if known
+    offline_public_key TEXT,              -- Set when verified against an attached chain
+    status TEXT NOT NULL DEFAULT 'pending', -- pending, approved or rejected
+    received_at TEXT NOT NULL,
+    decided_at TEXT,
+    UNIQUE (memory_id, content_hash)
+);
+
+CREATE INDEX idx_pending_memory_status ON pending_memory(status, received_at);
@@ -0,0 +1,3 @@
+-- Where a quarantined memory came from: the peer that sent it, or NULL for memories
+-- quarantined after they were stored (ocm-core verify, key pin changes)
+ALTER TABLE signed_memory_quarantine ADD COLUMN source TEXT;
@@ -0,0 +1,23 @@
+-- Every peer the node has dealt with: what it last said about itself, traffic with it and
+-- the operator's decision about it. The node keeps it current; the REST API and the
+-- `ocm-core peers` command read it and record decisions the node picks up
+CREATE TABLE peer_record (
+    peer_id TEXT PRIMARY KEY,
+    did TEXT,
+    address TEXT,
+    port INTEGER,
+    capabilities TEXT NOT NULL DEFAULT '[]', -- JSON array, as advertised
+    protocol_version INTEGER NOT NULL DEFAULT 0,
+    last_seen TEXT,
+    connected INTEGER NOT NULL DEFAULT 0,    -- In the node's peer set at its last update
+    messages_sent INTEGER NOT NULL DEFAULT 0,
+    messages_received INTEGER NOT NULL DEFAULT 0,
+    bytes_sent INTEGER NOT NULL DEFAULT 0,
+    bytes_received INTEGER NOT NULL DEFAULT 0,
+    send_failures INTEGER NOT NULL DEFAULT 0,
+    rejected INTEGER NOT NULL DEFAULT 0,     -- Messages and memories that failed checks
+    standing TEXT NOT NULL DEFAULT 'neutral', -- neutral, trusted or banned
+    standing_reason TEXT,
+    standing_changed_at TEXT,
+    disconnect_requested INTEGER NOT NULL DEFAULT 0
+);
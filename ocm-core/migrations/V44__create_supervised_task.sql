@@ -0,0 +1,12 @@
+-- Health of each process's supervised background tasks, as last reported by the
+-- process; the web server's /health endpoint reads it
+CREATE TABLE supervised_task (
+    process TEXT NOT NULL,       -- node or web
+    name TEXT NOT NULL,
+    state TEXT NOT NULL,         -- running, backoff, stopped or failed
+    restarts INTEGER NOT NULL DEFAULT 0,
+    last_panic TEXT,
+    started_at TEXT NOT NULL,    -- When the process started supervising it
+    reported_at TEXT NOT NULL,
+    PRIMARY KEY (process, name)
+);
@@ -0,0 +1,4 @@
+-- Claim token hashes are keyed with a pepper kept outside the database. Hashes stored
+-- before it are keyed in place by Database::hash_claim_tokens, which run_migrations
+-- runs straight after
+ALTER TABLE claim_token ADD COLUMN token_peppered INTEGER NOT NULL DEFAULT 0;
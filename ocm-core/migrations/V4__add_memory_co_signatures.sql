@@ -0,0 +1,3 @@
+-- Co-signatures let several DIDs attest the same memory (e.g. a camp/parent agreement)
+-- Stored as a JSON array of {signer_did, signature, signed_at}, ordered by signing time
+ALTER TABLE signed_memory ADD COLUMN co_signatures TEXT NOT NULL DEFAULT '[]';
@@ -0,0 +1,13 @@
+-- Witness receipts are signed timestamps from peers attesting they saw a memory's hash
+CREATE TABLE witness_receipt (
+    id TEXT PRIMARY KEY,
+    memory_id TEXT NOT NULL,         -- Local memory the receipt was requested for
+    content_hash TEXT NOT NULL,      -- Hash the witness signed
+    witness_did TEXT NOT NULL,       -- DID of the witnessing peer
+    witnessed_at TEXT NOT NULL,      -- ISO 8601 time asserted by the witness
+    signature TEXT NOT NULL,
+    FOREIGN KEY (memory_id) REFERENCES signed_memory(id) ON DELETE CASCADE
+);
+
+CREATE INDEX idx_witness_receipt_memory ON witness_receipt(memory_id);
+CREATE INDEX idx_witness_receipt_hash ON witness_receipt(content_hash);
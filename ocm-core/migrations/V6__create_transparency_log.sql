@@ -0,0 +1,10 @@
+-- Append-only log of stored memory hashes; leaf_index order defines the Merkle tree
+CREATE TABLE transparency_log (
+    leaf_index INTEGER PRIMARY KEY,  -- Zero-based position in the log, never reused
+    memory_id TEXT NOT NULL,
+    content_hash TEXT NOT NULL,      -- Memory content hash at the time it was stored
+    leaf_hash TEXT NOT NULL,         -- Hex RFC 6962 leaf hash of content_hash
+    appended_at TEXT NOT NULL
+);
+
+CREATE INDEX idx_transparency_log_memory ON transparency_log(memory_id);
@@ -0,0 +1,14 @@
+-- Signed audit trail of memories tombstoned by data subject erasure requests
+-- No foreign key: tombstoned memory rows stay in place, but the audit must outlive them
+CREATE TABLE erasure_record (
+    id TEXT PRIMARY KEY,
+    subject_did TEXT NOT NULL,       -- DID the erasure was requested for
+    memory_id TEXT NOT NULL,         -- Memory whose content was scrubbed
+    content_hash TEXT NOT NULL,      -- Original content hash (content itself is gone)
+    erased_by_did TEXT NOT NULL,     -- Operator DID that performed the erasure
+    erased_at TEXT NOT NULL,
+    signature TEXT NOT NULL
+);
+
+CREATE INDEX idx_erasure_record_subject ON erasure_record(subject_did);
+CREATE INDEX idx_erasure_record_memory ON erasure_record(memory_id);
@@ -0,0 +1,16 @@
+-- Memories moved out of the live table by retention rules; same shape as signed_memory
+CREATE TABLE signed_memory_archive (
+    id TEXT PRIMARY KEY,
+    did TEXT NOT NULL,
+    memory_type TEXT NOT NULL,
+    memory_data TEXT NOT NULL,
+    content_hash TEXT NOT NULL,
+    signature TEXT NOT NULL,
+    timestamp TEXT NOT NULL,
+    updated_on TEXT NOT NULL,
+    co_signatures TEXT NOT NULL DEFAULT '[]',
+    archived_at TEXT NOT NULL
+);
+
+CREATE INDEX idx_signed_memory_archive_did ON signed_memory_archive(did);
+CREATE INDEX idx_signed_memory_archive_type ON signed_memory_archive(memory_type);
@@ -0,0 +1,13 @@
+-- Ordered feed of memory and sync activity; event_id doubles as the SSE Last-Event-ID
+CREATE TABLE activity_event (
+    event_id INTEGER PRIMARY KEY AUTOINCREMENT,
+    event_type TEXT NOT NULL,    -- memory_stored, sync_completed
+    did TEXT,                    -- Memory author, if the event concerns a memory
+    memory_type TEXT,
+    memory_id TEXT,
+    payload TEXT NOT NULL,       -- JSON event details
+    created_at TEXT NOT NULL
+);
+
+CREATE INDEX idx_activity_event_did ON activity_event(did);
+CREATE INDEX idx_activity_event_memory_type ON activity_event(memory_type);
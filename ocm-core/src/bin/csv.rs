@@ -0,0 +1,92 @@
+use ocm_core::config::OcmConfig;
+use ocm_core::interchange::{export_csv, import_csv, ColumnMapping, CsvTable};
+use ocm_core::Database;
+use std::fs::File;
+use std::io::{BufReader, BufWriter};
+
+const USAGE: &str = "Usage:
+  ocm-csv export <individuals|locations> [--out FILE]
+  ocm-csv import <individuals|locations> FILE [--map 'Header=column,...'] [--dry-run]
+
+The database path comes from the node configuration (OCM_DATABASE__PATH).";
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let (command, table) = match (args.first(), args.get(1)) {
+        (Some(command), Some(table)) => (command.as_str(), table.parse::<CsvTable>()?),
+        _ => {
+            eprintln!("{}", USAGE);
+            std::process::exit(2);
+        }
+    };
+
+    let config = OcmConfig::from_env().unwrap_or_default();
+    let database = Database::new(&config.database.path.to_string_lossy())?;
+
+    match command {
+        "export" => {
+            let rows = match flag_value(&args, "--out") {
+                Some(path) => export_csv(&database, table, BufWriter::new(File::create(path)?))?,
+                None => export_csv(&database, table, std::io::stdout().lock())?,
+            };
+            eprintln!("Exported {} {:?} rows", rows, table);
+        }
+        "import" => {
+            let path = args
+                .get(2)
+                .filter(|arg| !arg.starts_with("--"))
+                .ok_or(USAGE)?;
+            let mapping = match flag_value(&args, "--map") {
+                Some(spec) => ColumnMapping::parse(spec)?,
+                None => ColumnMapping::default(),
+            };
+            let dry_run = args.iter().any(|arg| arg == "--dry-run");
+
+            let report = import_csv(
+                &database,
+                table,
+                BufReader::new(File::open(path)?),
+                &mapping,
+                dry_run,
+            )?;
+
+            for error in &report.errors {
+                match &error.field {
+                    Some(field) => eprintln!("row {}: {}: {}", error.row, field, error.message),
+                    None => eprintln!("row {}: {}", error.row, error.message),
+                }
+            }
+            if dry_run {
+                println!(
+                    "Dry run: {} rows checked, {} with errors",
+                    report.rows,
+                    report.errors.len()
+                );
+            } else {
+                println!(
+                    "{} rows: {} created, {} updated, {} errors",
+                    report.rows,
+                    report.created,
+                    report.updated,
+                    report.errors.len()
+                );
+            }
+            if !report.errors.is_empty() {
+                std::process::exit(1);
+            }
+        }
+        _ => {
+            eprintln!("{}", USAGE);
+            std::process::exit(2);
+        }
+    }
+
+    Ok(())
+}
+
+fn flag_value<'a>(args: &'a [String], flag: &str) -> Option<&'a str> {
+    args.iter()
+        .position(|arg| arg == flag)
+        .and_then(|i| args.get(i + 1))
+        .map(String::as_str)
+}
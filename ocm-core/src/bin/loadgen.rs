@@ -0,0 +1,380 @@
+use futures_util::{SinkExt, StreamExt};
+use ocm_core::core::relay::RelayMessage;
+use ocm_core::identity::plc::{PlcIdentity, Pseudonym};
+use ocm_core::networking::{
+    HandshakePayload, MessageType, NetworkMessage, NodeRole, PEER_PROTOCOL_VERSION,
+};
+use ocm_core::{OcmNetworking, SignedMemory};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpStream;
+use tokio_tungstenite::tungstenite::Message;
+
+const USAGE: &str = "Usage:
+  ocm-loadgen relay <ws://host:port> [options]
+  ocm-loadgen node <host:port> [options]
+
+Options:
+  --clients N     Concurrent clients or peers (default 10)
+  --rate R        Memories per second per client (default 1)
+  --duration S    Seconds to generate load for (default 30)
+  --size BYTES    Padding per memory body (default 512)
+
+Relay mode broadcasts through the relay and times delivery to every other client.
+Node mode sends signed memories over the P2P protocol and times each acknowledgment;
+the node rate-limits and caps connections per IP, so a single load host measures those limits.";
+
+/// How long to wait for a node acknowledgment before counting the message as dropped
+const ACK_TIMEOUT: Duration = Duration::from_secs(5);
+/// Time left for in-flight relay broadcasts to arrive after the last send
+const DRAIN_TIME: Duration = Duration::from_secs(2);
+
+#[derive(Debug, Clone)]
+enum Target {
+    Relay(String),
+    Node(String),
+}
+
+#[derive(Debug, Clone)]
+struct Options {
+    target: Target,
+    clients: usize,
+    rate: f64,
+    duration: Duration,
+    size: usize,
+}
+
+impl Options {
+    fn from_args(args: &[String]) -> Result<Self, String> {
+        let target = match (args.first().map(String::as_str), args.get(1)) {
+            (Some("relay"), Some(url)) => Target::Relay(url.clone()),
+            (Some("node"), Some(addr)) => Target::Node(addr.clone()),
+            _ => return Err(USAGE.to_string()),
+        };
+        let options = Self {
+            target,
+            clients: parse_flag(args, "--clients", 10)?,
+            rate: parse_flag(args, "--rate", 1.0)?,
+            duration: Duration::from_secs(parse_flag(args, "--duration", 30)?),
+            size: parse_flag(args, "--size", 512)?,
+        };
+        if options.clients == 0 || options.rate <= 0.0 {
+            return Err("--clients and --rate must be positive".to_string());
+        }
+        Ok(options)
+    }
+}
+
+fn parse_flag<T: std::str::FromStr>(args: &[String], flag: &str, default: T) -> Result<T, String> {
+    match args.iter().position(|arg| arg == flag) {
+        Some(i) => args
+            .get(i + 1)
+            .and_then(|value| value.parse().ok())
+            .ok_or_else(|| format!("{} needs a numeric value", flag)),
+        None => Ok(default),
+    }
+}
+
+#[derive(Default)]
+struct Stats {
+    sent: AtomicU64,
+    delivered: AtomicU64, // Relay broadcasts received, or node acknowledgments
+    dropped: AtomicU64,   // Reported by the relay, or node acknowledgments that timed out
+    errors: AtomicU64,    // Failed connections and sends
+    latencies_us: Mutex<Vec<u64>>,
+}
+
+impl Stats {
+    fn record_latency(&self, latency_us: u64) {
+        self.delivered.fetch_add(1, Ordering::Relaxed);
+        self.latencies_us
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .push(latency_us);
+    }
+}
+
+#[tokio::main]
+async fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let options = match Options::from_args(&args) {
+        Ok(options) => options,
+        Err(message) => {
+            eprintln!("{}", message);
+            std::process::exit(2);
+        }
+    };
+
+    // Node mode needs memories that verify; a did:key pseudonym needs no PLC lookup
+    let identity = PlcIdentity::generate(Some("ocm-loadgen".to_string())).expect("identity");
+    let signer = Arc::new(identity.pseudonym("ocm-loadgen").expect("pseudonym"));
+    let stats = Arc::new(Stats::default());
+
+    println!(
+        "🚦 {:?}: {} clients × {} memories/s for {}s",
+        options.target,
+        options.clients,
+        options.rate,
+        options.duration.as_secs()
+    );
+
+    let started = Instant::now();
+    let mut clients = Vec::new();
+    for client in 0..options.clients {
+        let (options, stats, signer) = (options.clone(), stats.clone(), signer.clone());
+        clients.push(tokio::spawn(async move {
+            match &options.target {
+                Target::Relay(url) => relay_client(url, client, &options, &stats, &signer).await,
+                Target::Node(addr) => node_client(addr, client, &options, &stats, &signer).await,
+            }
+        }));
+    }
+    for client in clients {
+        let _ = client.await;
+    }
+
+    print_report(&options, &stats, started.elapsed());
+}
+
+/// A memory carrying its send time, so receivers can time delivery
+fn load_memory(signer: &Pseudonym, client: usize, seq: u64, size: usize) -> SignedMemory {
+    let data = serde_json::json!({
+        "loadgen": {
+            "client": client,
+            "seq": seq,
+            "sent_at_us": chrono::Utc::now().timestamp_micros(),
+        },
+        "padding": "x".repeat(size),
+    });
+    let mut memory = SignedMemory::new(&signer.did, "loadgen", &data.to_string());
+    signer
+        .sign_memory(&mut memory)
+        .expect("memory is authored by the signer");
+    memory
+}
+
+fn sent_at_us(memory: &SignedMemory) -> Option<i64> {
+    let data: serde_json::Value = serde_json::from_str(&memory.memory_data).ok()?;
+    data["loadgen"]["sent_at_us"].as_i64()
+}
+
+async fn relay_client(
+    url: &str,
+    client: usize,
+    options: &Options,
+    stats: &Arc<Stats>,
+    signer: &Pseudonym,
+) {
+    let socket = match tokio_tungstenite::connect_async(url).await {
+        Ok((socket, _)) => socket,
+        Err(e) => {
+            eprintln!("Client {} could not connect: {}", client, e);
+            stats.errors.fetch_add(1, Ordering::Relaxed);
+            return;
+        }
+    };
+    let (mut write, mut read) = socket.split();
+
+    let reader_stats = stats.clone();
+    let reader = tokio::spawn(async move {
+        while let Some(Ok(message)) = read.next().await {
+            let Message::Text(text) = message else {
+                continue;
+            };
+            match serde_json::from_str::<RelayMessage>(&text) {
+                Ok(RelayMessage::MemorySync { data }) => {
+                    if let Some(sent_at) = sent_at_us(&data) {
+                        let latency = chrono::Utc::now().timestamp_micros() - sent_at;
+                        reader_stats.record_latency(latency.max(0) as u64);
+                    }
+                }
+                Ok(RelayMessage::MessagesDropped { count, .. }) => {
+                    reader_stats.dropped.fetch_add(count, Ordering::Relaxed);
+                }
+                _ => {}
+            }
+        }
+    });
+
+    let mut interval = tokio::time::interval(Duration::from_secs_f64(1.0 / options.rate));
+    let deadline = Instant::now() + options.duration;
+    let mut seq = 0;
+    while Instant::now() < deadline {
+        interval.tick().await;
+        let memory = load_memory(signer, client, seq, options.size);
+        let text = RelayMessage::MemorySync { data: memory }.to_json();
+        if let Err(e) = write.send(Message::Text(text)).await {
+            eprintln!("Client {} send failed: {}", client, e);
+            stats.errors.fetch_add(1, Ordering::Relaxed);
+            break;
+        }
+        stats.sent.fetch_add(1, Ordering::Relaxed);
+        seq += 1;
+    }
+
+    tokio::time::sleep(DRAIN_TIME).await;
+    reader.abort();
+    let _ = write.close().await;
+}
+
+async fn node_client(
+    addr: &str,
+    client: usize,
+    options: &Options,
+    stats: &Arc<Stats>,
+    signer: &Pseudonym,
+) {
+    let peer_id = format!("ocm-loadgen-{}", client);
+    let mut stream = match TcpStream::connect(addr).await {
+        Ok(stream) => stream,
+        Err(e) => {
+            eprintln!("Peer {} could not connect: {}", client, e);
+            stats.errors.fetch_add(1, Ordering::Relaxed);
+            return;
+        }
+    };
+
+    let handshake = OcmNetworking::create_authenticated_message(
+        MessageType::Handshake,
+        serde_json::to_string(&HandshakePayload {
+            peer_id: peer_id.clone(),
+            listen_port: 0, // Load peers only dial out
+            did: None,
+            capabilities: Vec::new(),
+            protocol_version: PEER_PROTOCOL_VERSION,
+            frame_limits: None,
+            role: NodeRole::Full,
+            signature: None,
+        })
+        .unwrap_or_default(),
+        peer_id.clone(),
+    );
+    if write_frame(&mut stream, &handshake).await.is_err()
+        || tokio::time::timeout(ACK_TIMEOUT, read_frame(&mut stream))
+            .await
+            .map_or(true, |ack| ack.is_err())
+    {
+        eprintln!("Peer {}: node did not acknowledge the handshake", client);
+        stats.errors.fetch_add(1, Ordering::Relaxed);
+        return;
+    }
+
+    let mut interval = tokio::time::interval(Duration::from_secs_f64(1.0 / options.rate));
+    let deadline = Instant::now() + options.duration;
+    let mut seq = 0;
+    while Instant::now() < deadline {
+        interval.tick().await;
+        let memory = load_memory(signer, client, seq, options.size);
+        let payload = serde_json::to_string(&memory).expect("memory serializes");
+        let message = OcmNetworking::create_authenticated_message(
+            MessageType::MemorySync,
+            payload,
+            peer_id.clone(),
+        );
+        seq += 1;
+
+        let started = Instant::now();
+        if let Err(e) = write_frame(&mut stream, &message).await {
+            eprintln!("Peer {} send failed: {}", client, e);
+            stats.errors.fetch_add(1, Ordering::Relaxed);
+            break;
+        }
+        stats.sent.fetch_add(1, Ordering::Relaxed);
+
+        // Rate-limited or rejected messages are never acknowledged
+        match tokio::time::timeout(ACK_TIMEOUT, read_frame(&mut stream)).await {
+            Ok(Ok(_)) => stats.record_latency(started.elapsed().as_micros() as u64),
+            Ok(Err(e)) => {
+                eprintln!("Peer {} connection closed: {}", client, e);
+                stats.errors.fetch_add(1, Ordering::Relaxed);
+                break;
+            }
+            Err(_) => {
+                stats.dropped.fetch_add(1, Ordering::Relaxed);
+            }
+        }
+    }
+}
+
+/// Length-prefixed JSON, as the node's P2P server expects
+async fn write_frame(stream: &mut TcpStream, message: &NetworkMessage) -> std::io::Result<()> {
+    let data = serde_json::to_vec(message)?;
+    stream.write_all(&(data.len() as u32).to_be_bytes()).await?;
+    stream.write_all(&data).await
+}
+
+async fn read_frame(stream: &mut TcpStream) -> std::io::Result<Vec<u8>> {
+    let mut length = [0u8; 4];
+    stream.read_exact(&mut length).await?;
+    let mut buffer = vec![0u8; u32::from_be_bytes(length) as usize];
+    stream.read_exact(&mut buffer).await?;
+    Ok(buffer)
+}
+
+fn print_report(options: &Options, stats: &Stats, elapsed: Duration) {
+    let sent = stats.sent.load(Ordering::Relaxed);
+    let delivered = stats.delivered.load(Ordering::Relaxed);
+    let dropped = stats.dropped.load(Ordering::Relaxed);
+    let errors = stats.errors.load(Ordering::Relaxed);
+
+    // Every relay broadcast should reach each of the other clients once
+    let expected = match options.target {
+        Target::Relay(_) => sent * (options.clients as u64 - 1),
+        Target::Node(_) => sent,
+    };
+    let percent = |count: u64, of: u64| {
+        if of == 0 {
+            0.0
+        } else {
+            count as f64 * 100.0 / of as f64
+        }
+    };
+
+    println!("\n📊 Load test results ({:.1}s)", elapsed.as_secs_f64());
+    println!(
+        "   Sent: {} ({:.1}/s)",
+        sent,
+        sent as f64 / elapsed.as_secs_f64()
+    );
+    println!(
+        "   Delivered: {} of {} expected ({:.2}%)",
+        delivered,
+        expected,
+        percent(delivered, expected)
+    );
+    println!(
+        "   Dropped: {} ({:.2}%)",
+        dropped,
+        percent(dropped, expected)
+    );
+    println!(
+        "   Errors: {} ({:.2}% of {} clients and sends)",
+        errors,
+        percent(errors, sent + options.clients as u64),
+        sent + options.clients as u64
+    );
+
+    let mut latencies = stats
+        .latencies_us
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .clone();
+    if latencies.is_empty() {
+        println!("   Latency: no deliveries");
+        return;
+    }
+    latencies.sort_unstable();
+    let percentile = |p: f64| {
+        let index = ((latencies.len() - 1) as f64 * p).round() as usize;
+        latencies[index] as f64 / 1000.0
+    };
+    println!(
+        "   Latency (ms): p50 {:.2} · p90 {:.2} · p99 {:.2} · max {:.2}",
+        percentile(0.50),
+        percentile(0.90),
+        percentile(0.99),
+        percentile(1.0)
+    );
+}
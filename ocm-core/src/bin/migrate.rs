@@ -14,6 +14,11 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     let mut config = Config::new(ConfigDbType::Sqlite).set_db_path(db_path);
     embedded::migrations::runner().run(&mut config)?;
 
+    let hashed = ocm_core::Database::new(db_path)?.hash_claim_tokens()?;
+    if hashed > 0 {
+        println!("🔒 Hashed {} claim tokens stored in plaintext", hashed);
+    }
+
     println!("OCM database initialized at {}", db_path);
     Ok(())
 }
@@ -1,4 +1,8 @@
-use axum::{middleware, routing::get, Router};
+use axum::{
+    middleware,
+    routing::{get, post},
+    Router,
+};
 use std::net::SocketAddr;
 use std::path::Path;
 use tokio::net::TcpListener;
@@ -7,86 +11,3068 @@ use tower_http::{cors::CorsLayer, services::ServeDir, trace::TraceLayer};
 use tracing::{info, warn};
 
 #[cfg(feature = "native")]
-use axum_server::tls_rustls::RustlsConfig;
+use axum_server::tls_rustls::RustlsConfig;
+#[cfg(feature = "native")]
+use rustls;
+
+// Import our security modules
+#[cfg(feature = "native")]
+use ocm_core::security::{
+    auth::*,
+    middleware::*,
+    rate_limiting::{
+        create_api_read_rate_limiter, create_health_rate_limiter, create_rate_limiter_store,
+        rate_limit_exceeded_response, rate_limit_middleware, RateLimitConfig, RateLimitState,
+        RateLimiterStore,
+    },
+    static_files::static_file_router,
+};
+#[cfg(feature = "native")]
+use ocm_core::{
+    config::{OcmConfig, PublicLookupConfig, WebProfile},
+    core::memory_types::MemoryTypeRegistry,
+    core::redact::Redacted,
+    identity::{
+        handles::HandleRegistry, key_pins::DidKeyRefresher, node_info::NodeInfo,
+        organizations::OrganizationVerifier, signer::open_configured,
+    },
+    interchange::{
+        export_csv, import_csv, write_individuals, write_memories, BatchReport, ColumnMapping,
+        CsvTable, ImportReport,
+    },
+    jobs::{JobRunner, JobSpec},
+    networking::{groups::PeerGroupRegistry, presence::list_presence, review::InboundReview},
+    panics,
+    persistence::{
+        annotations::AnnotationService,
+        contacts::{ContactService, ContactUpdate},
+        merges::{MemoryMerge, MergeService},
+        messages::DirectMessageService,
+        receipts::ReceiptService,
+        tags::TagService,
+        tenants::TenantRegistry,
+        transparency::{ConsistencyProof, InclusionProof, SignedTreeHead, TransparencyLog},
+    },
+    supervisor::TaskSupervisor,
+    sync::patch::{PatchOperation, PatchedMemory},
+    ActivityEvent, AnnotationKind, AnnotationThread, ClaimPreview, ClaimRatePoint, ClaimStatistics,
+    ClaimStatsQuery, ClaimSystem, Contact, DataSubjectExport, Database, DeliveryStatus, Did,
+    DidKeyPin, DidResolution, DirectMessage, DuplicateCandidate, ErasureRecord, HandleChange,
+    HandleRecord, InvalidId, Job, MemoryId, OcmError, OrganizationBadge, OrganizationVerification,
+    PeerId, PeerPresence, PeerRecord, PeerStanding, PendingMemory, PendingStatus, PlcDirectory,
+    PlcDocument, PlcIdentity, QuarantineEntry, SafetyNumber, SignedMemory, TagCount, TaskHealth,
+    TaskState, Tenant, TenantStatus, TenantUsage, TimeToClaimPoint, TokenStatusPoint, PANIC_EVENT,
+};
+#[cfg(feature = "push")]
+use ocm_core::{push::PushGateway, PushEvent, PushKind, PushSubscription};
+#[cfg(feature = "rules")]
+use ocm_core::{rules::check_script, AutomationRule};
+#[cfg(feature = "native")]
+use ocm_protocol::feed::{FeedSource, DEFAULT_FEED_LIMIT, MAX_FEED_LIMIT};
+use ocm_protocol::filter::Filter;
+#[cfg(feature = "native")]
+use ocm_protocol::patch::MemoryDiff;
+#[cfg(feature = "native")]
+use std::{
+    collections::{HashMap, VecDeque},
+    sync::Arc,
+    time::Duration,
+};
+
+// How often an idle event stream checks the activity feed for new entries
+#[cfg(feature = "native")]
+const EVENT_POLL_INTERVAL: Duration = Duration::from_secs(1);
+#[cfg(feature = "native")]
+const EVENT_BATCH_SIZE: usize = 100;
+// Task health older than this belongs to a process that has stopped
+#[cfg(feature = "native")]
+const HEALTH_REPORT_MAX_AGE_SECS: i64 = 60;
+
+#[cfg(feature = "native")]
+#[derive(Clone)]
+struct AppState {
+    database: Arc<Database>,
+    transparency: Arc<TransparencyLog>,
+    claims: Arc<ClaimSystem>,
+    tags: Arc<TagService>,
+    merges: Arc<MergeService>,
+    annotations: Arc<AnnotationService>,
+    handles: Arc<HandleRegistry>,
+    organizations: Arc<OrganizationVerifier>,
+    review: Arc<InboundReview>,
+    contacts: Arc<ContactService>,
+    messages: Arc<DirectMessageService>,
+    receipts: Arc<ReceiptService>,
+    presence_offline_after_seconds: u64,
+    memory_types: Arc<MemoryTypeRegistry>,
+    max_batch_items: usize,
+    tenants: Option<Arc<TenantRegistry>>, // Host API of a multi-tenant node only
+    jobs: Option<Arc<JobRunner>>,         // Host API only
+    #[cfg(feature = "push")]
+    push: Option<Arc<PushGateway>>, // Host API only, when push notifications are enabled
+    // Ephemeral signing identity for tree heads until the node has a persistent one
+    identity: Arc<PlcIdentity>,
+}
+
+/// What the unauthenticated public lookup can reach
+#[cfg(feature = "native")]
+#[derive(Clone)]
+struct PublicState {
+    database: Arc<Database>,
+    config: PublicLookupConfig,
+    directory: Arc<tokio::sync::Mutex<PlcDirectory>>,
+}
+
+/// Where the health check finds the task health reported by this server and the node
+#[cfg(feature = "native")]
+#[derive(Clone)]
+struct HealthState {
+    database: Arc<Database>,
+}
+
+/// Health of the supervised tasks of every process that reported recently
+#[cfg(feature = "native")]
+#[derive(serde::Serialize)]
+struct HealthReport {
+    status: &'static str, // "ok", or "degraded" when a task has been given up on
+    tasks: Vec<TaskHealth>,
+    panics_last_hour: u64, // Recorded by any process, whether or not they were caught
+}
+
+/// What the unauthenticated node info endpoint signs with and reports
+#[cfg(feature = "native")]
+#[derive(Clone)]
+struct NodeState {
+    identity: Arc<PlcIdentity>,
+    started_at: chrono::DateTime<chrono::Utc>,
+    features: Arc<Vec<String>>,
+}
+
+/// A public memory with what a third party needs to check it: the author's PLC
+/// document, or None for did:key authors, whose DID is their key
+#[cfg(feature = "native")]
+#[derive(serde::Serialize)]
+struct PublicMemory {
+    memory: SignedMemory,
+    plc_document: Option<PlcDocument>,
+}
+
+#[cfg(feature = "native")]
+#[derive(serde::Deserialize)]
+struct InclusionQuery {
+    memory_id: String,
+    tree_size: Option<u64>,
+}
+
+#[cfg(feature = "native")]
+#[derive(serde::Deserialize)]
+struct ConsistencyQuery {
+    first: u64,
+    second: u64,
+}
+
+#[cfg(feature = "native")]
+#[derive(serde::Deserialize)]
+struct DataSubjectQuery {
+    did: String,
+}
+
+#[cfg(feature = "native")]
+#[derive(serde::Deserialize)]
+struct EventsQuery {
+    did: Option<String>,
+    memory_type: Option<String>,
+    last_event_id: Option<i64>, // For clients that cannot set the Last-Event-ID header
+}
+
+#[cfg(feature = "native")]
+#[derive(serde::Deserialize)]
+struct FeedRequest {
+    did: Option<String>,
+    group: Option<String>, // A group DID; the feed covers the group and its members
+    cursor: Option<String>,
+    limit: Option<usize>,
+    types: Option<String>,  // Comma-separated memory types
+    filter: Option<String>, // A filter expression, see ocm_protocol::filter
+}
+
+#[cfg(feature = "native")]
+#[derive(serde::Deserialize)]
+struct MemoryExportQuery {
+    did: Option<String>,
+    types: Option<String>,  // Comma-separated memory types
+    filter: Option<String>, // A filter expression, see ocm_protocol::filter
+    format: Option<String>, // Only "ndjson", the default
+}
+
+#[cfg(feature = "native")]
+#[derive(serde::Deserialize)]
+struct CsvImportQuery {
+    #[serde(default)]
+    dry_run: bool,
+    map: Option<String>, // "Header=column,..." as accepted by ColumnMapping::parse
+}
+
+#[cfg(feature = "native")]
+#[derive(serde::Deserialize)]
+struct BatchRequest {
+    items: Vec<serde_json::Value>,
+}
+
+/// A browser's `PushSubscription.toJSON()`, or an FCM registration token as the endpoint
+#[cfg(feature = "push")]
+#[derive(serde::Deserialize)]
+struct PushSubscriptionRequest {
+    #[serde(default = "default_push_kind")]
+    kind: PushKind,
+    endpoint: String,
+    keys: Option<PushKeys>,
+    events: Option<Vec<PushEvent>>, // The server's default events if omitted
+}
+
+#[cfg(feature = "push")]
+#[derive(serde::Deserialize)]
+struct PushKeys {
+    p256dh: String,
+    auth: String,
+}
+
+#[cfg(feature = "push")]
+fn default_push_kind() -> PushKind {
+    PushKind::WebPush
+}
+
+#[cfg(feature = "push")]
+#[derive(serde::Deserialize)]
+struct PushEventsRequest {
+    events: Vec<PushEvent>,
+}
+
+#[cfg(feature = "rules")]
+#[derive(serde::Deserialize)]
+struct RuleRequest {
+    description: Option<String>,
+    event_type: String,
+    memory_type: Option<String>,
+    script: String,
+    #[serde(default = "default_rule_enabled")]
+    enabled: bool,
+}
+
+#[cfg(feature = "rules")]
+fn default_rule_enabled() -> bool {
+    true
+}
+
+#[cfg(feature = "native")]
+#[derive(serde::Deserialize)]
+struct RefreshRequest {
+    refresh_token: String,
+}
+
+#[cfg(feature = "native")]
+#[derive(serde::Deserialize)]
+struct TenantRequest {
+    id: Option<String>, // Only when creating
+    name: Option<String>,
+    max_memories: Option<u64>,
+    requests_per_minute: Option<u32>,
+}
+
+#[cfg(feature = "native")]
+#[derive(serde::Deserialize)]
+struct TagRequest {
+    tag: String,
+}
+
+#[cfg(feature = "native")]
+#[derive(serde::Deserialize)]
+struct MergeRequest {
+    memory_ids: Vec<String>,
+    #[serde(default)]
+    choices: HashMap<String, String>, // Field -> memory to take it from
+}
+
+#[cfg(feature = "native")]
+#[derive(serde::Deserialize)]
+struct ContactRequest {
+    did: String,
+    #[serde(flatten)]
+    update: ContactUpdate,
+}
+
+#[cfg(feature = "native")]
+#[derive(serde::Deserialize)]
+struct InboundQuery {
+    status: Option<String>, // pending (default), approved or rejected
+}
+
+#[cfg(feature = "native")]
+#[derive(serde::Deserialize)]
+struct PeerStandingRequest {
+    reason: Option<String>,
+}
+
+#[cfg(feature = "native")]
+#[derive(serde::Deserialize)]
+struct QuarantineQuery {
+    limit: Option<usize>,
+}
+
+#[cfg(feature = "native")]
+#[derive(serde::Deserialize)]
+struct ContactListQuery {
+    group: Option<String>,
+}
+
+#[cfg(feature = "native")]
+#[derive(serde::Deserialize)]
+struct ContactVerifyRequest {
+    fingerprint: String,
+}
+
+#[cfg(feature = "native")]
+#[derive(serde::Deserialize)]
+struct SafetyNumberRequest {
+    safety_number: String,
+}
+
+#[cfg(feature = "native")]
+#[derive(serde::Deserialize)]
+struct MessageRequest {
+    recipient_did: String,
+    body: String,
+}
+
+#[cfg(feature = "native")]
+#[derive(serde::Deserialize)]
+struct InboxQuery {
+    #[serde(default)]
+    unread: bool,
+}
+
+#[cfg(feature = "native")]
+#[derive(serde::Deserialize)]
+struct HandleRequest {
+    handle: String,
+    did: String,
+}
+
+#[cfg(feature = "native")]
+#[derive(serde::Deserialize)]
+struct DomainVerificationRequest {
+    domain: String,
+}
+
+#[cfg(feature = "native")]
+#[derive(serde::Deserialize)]
+struct ClaimPreviewRequest {
+    token: String,
+}
+
+#[cfg(feature = "native")]
+#[derive(serde::Deserialize)]
+struct AnnotationRequest {
+    #[serde(default)]
+    kind: AnnotationKind,
+    body: String,
+}
+
+#[cfg(feature = "native")]
+type ApiError = (axum::http::StatusCode, axum::Json<serde_json::Value>);
+
+// Command-line switch for the development profile (plain HTTP, no HSTS)
+const DEV_FLAG: &str = "--dev";
+
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    // Initialize logging
+    tracing_subscriber::fmt::init();
+
+    // Initialize crypto provider for rustls
+    #[cfg(feature = "native")]
+    {
+        let _ = rustls::crypto::ring::default_provider().install_default();
+    }
+
+    let app = create_app().await;
+
+    if is_development_profile() {
+        info!("🛠️  Development profile: serving over plain HTTP");
+        setup_http_server(app).await?;
+        return Ok(());
+    }
+
+    // Try to set up HTTPS if certificates are available
+    if let Ok(_) = setup_https_server(app.clone()).await {
+        info!("🔒 HTTPS server started successfully");
+    } else {
+        warn!("⚠️ HTTPS setup failed, falling back to HTTP");
+        setup_http_server(app).await?;
+    }
+
+    Ok(())
+}
+
+#[cfg(feature = "native")]
+fn load_config() -> OcmConfig {
+    let mut config = OcmConfig::from_env().unwrap_or_default();
+    if std::env::args().any(|arg| arg == DEV_FLAG) {
+        config.web.profile = WebProfile::Development;
+    }
+    config
+}
+
+#[cfg(feature = "native")]
+fn is_development_profile() -> bool {
+    load_config().web.profile == WebProfile::Development
+}
+
+#[cfg(not(feature = "native"))]
+fn is_development_profile() -> bool {
+    std::env::args().any(|arg| arg == DEV_FLAG)
+}
+
+#[cfg(feature = "native")]
+async fn create_app() -> Router {
+    // Create rate limiter store
+    let rate_limiter_store = create_rate_limiter_store();
+
+    let config = load_config();
+    let development = config.web.profile == WebProfile::Development;
+    let state = create_app_state(&config);
+    let tenants = state.tenants.clone();
+    let state_database = state.database.clone();
+    let identity = state.identity.clone();
+    let api_routes = api_router(state, rate_limiter_store.clone(), None);
+
+    // Health check route with higher rate limits
+    let health_routes = Router::new()
+        .route("/health", get(health_report))
+        .with_state(HealthState {
+            database: state_database.clone(),
+        })
+        .layer(middleware::from_fn(create_health_rate_limiter(
+            rate_limiter_store.clone(),
+        )));
+
+    // Signed node description for fingerprinting, limited like the health check
+    let node_routes = Router::new()
+        .route("/api/v1/node", get(node_info))
+        .with_state(NodeState {
+            identity: identity.clone(),
+            started_at: chrono::Utc::now(),
+            features: Arc::new(node_features(&config)),
+        })
+        .layer(middleware::from_fn(create_health_rate_limiter(
+            rate_limiter_store.clone(),
+        )));
+
+    // Public memory lookup, limited per IP on its own so it cannot eat into API budgets
+    let public_routes = if config.web.public_lookup.enabled {
+        info!("🌐 Public memory lookup enabled");
+        let requests_per_minute = config.web.public_lookup.requests_per_minute;
+        Router::new()
+            .route("/api/v1/public/memories/:content_hash", get(public_memory))
+            .with_state(PublicState {
+                database: state_database.clone(),
+                config: config.web.public_lookup.clone(),
+                directory: Arc::new(tokio::sync::Mutex::new(PlcDirectory::with_config(
+                    &config.plc,
+                ))),
+            })
+            .layer(middleware::from_fn(rate_limit_middleware(
+                create_rate_limiter_store(),
+                RateLimitConfig {
+                    requests_per_minute,
+                    burst_size: (requests_per_minute / 6).max(1),
+                },
+            )))
+    } else {
+        Router::new()
+    };
+
+    // Static file serving (no rate limiting for now to avoid complexity)
+    info!("📁 Serving static files from {:?}", config.web.root);
+    let static_routes = static_file_router(&config.web);
+
+    // Combine all routes with global security middleware
+    let mut app = Router::new()
+        .nest("/api/v1", api_routes)
+        .merge(health_routes)
+        .merge(node_routes)
+        .merge(public_routes)
+        .merge(static_routes);
+    if let Some(registry) = tenants {
+        let dispatch = Arc::new(TenantRouters {
+            registry,
+            rate_limiter_store: rate_limiter_store.clone(),
+            config: config.clone(),
+            routers: std::sync::Mutex::new(HashMap::new()),
+        });
+        app = app.merge(
+            Router::new()
+                .route("/t/:tenant/api/v1/*path", axum::routing::any(tenant_api))
+                .with_state(dispatch),
+        );
+    }
+    // The development profile always isolates so wasm threads work without extra setup
+    if config.web.cross_origin_isolation || development {
+        app = app.layer(middleware::from_fn(cross_origin_isolation_middleware));
+    }
+    app = if development {
+        app.layer(middleware::from_fn(development_security_headers_middleware))
+    } else {
+        app.layer(middleware::from_fn(security_headers_middleware))
+    };
+
+    let mut cors = config.web.cors.clone();
+    if development {
+        for origin in ["http://127.0.0.1:8000", "http://localhost:8000"] {
+            if !cors.allowed_origins.iter().any(|o| o == origin) {
+                cors.allowed_origins.push(origin.to_string());
+            }
+        }
+    }
+
+    // One store for the whole node, so sessions work across the host and tenant APIs
+    let auth_store = AuthStore::with_policy(SessionPolicy::from_config(&config.web.sessions));
+
+    let app = app.layer(
+        ServiceBuilder::new()
+            .layer(middleware::from_fn(request_id_middleware))
+            .layer(middleware::from_fn(panic_middleware))
+            .layer(axum::Extension(auth_store))
+            .layer(TraceLayer::new_for_http())
+            // Preflights are answered here, before rate limiting and validation
+            .layer(middleware::from_fn_with_state(
+                Arc::new(cors),
+                secure_cors_middleware,
+            ))
+            .layer(middleware::from_fn(security_logging_middleware))
+            .layer(middleware::from_fn(request_size_limit_middleware)),
+    );
+
+    // Outermost, so the signature covers the body exactly as sent
+    if config.web.sign_responses {
+        info!(
+            "✍️  Signing API responses as {}",
+            Redacted::did(&identity.did)
+        );
+        app.layer(middleware::from_fn_with_state(
+            identity,
+            response_signature_middleware,
+        ))
+    } else {
+        app
+    }
+}
+
+/// Per-tenant API routers of a multi-tenant node, built on first use
+#[cfg(feature = "native")]
+struct TenantRouters {
+    registry: Arc<TenantRegistry>,
+    rate_limiter_store: RateLimiterStore,
+    config: OcmConfig,
+    routers: std::sync::Mutex<HashMap<String, Router>>,
+}
+
+#[cfg(feature = "native")]
+impl TenantRouters {
+    fn router(&self, tenant_id: &str) -> Result<Router, OcmError> {
+        let mut routers = self.routers.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some(router) = routers.get(tenant_id) {
+            return Ok(router.clone());
+        }
+        let state = app_state_for(self.registry.database(tenant_id)?, &self.config);
+        let router = api_router(
+            state,
+            self.rate_limiter_store.clone(),
+            Some(tenant_id.to_string()),
+        );
+        routers.insert(tenant_id.to_string(), router.clone());
+        Ok(router)
+    }
+
+    fn forget(&self, tenant_id: &str) {
+        self.routers
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .remove(tenant_id);
+    }
+}
+
+/// Route /t/{tenant}/api/v1/... to the tenant's own API after checking its status,
+/// request rate and, for writes, memory quota
+#[cfg(feature = "native")]
+async fn tenant_api(
+    axum::extract::State(dispatch): axum::extract::State<Arc<TenantRouters>>,
+    axum::extract::Path((tenant_id, path)): axum::extract::Path<(String, String)>,
+    request: axum::extract::Request,
+) -> Result<axum::response::Response, ApiError> {
+    use tower::ServiceExt;
+
+    let tenant = dispatch.registry.get(&tenant_id).map_err(api_error)?;
+    if tenant.status != TenantStatus::Active {
+        dispatch.forget(&tenant_id);
+        return Err(create_error_response(
+            axum::http::StatusCode::FORBIDDEN,
+            "TENANT_SUSPENDED",
+            "This tenant is suspended",
+        ));
+    }
+
+    let requests_per_minute = dispatch.registry.requests_per_minute(&tenant);
+    let limit = RateLimitConfig {
+        requests_per_minute,
+        burst_size: (requests_per_minute / 6).max(1),
+    };
+    let allowed = dispatch
+        .rate_limiter_store
+        .entry(format!("tenant:{}", tenant_id))
+        .or_insert_with(RateLimitState::new)
+        .is_allowed(&limit);
+    if !allowed {
+        return Err(rate_limit_exceeded_response());
+    }
+
+    if !matches!(
+        *request.method(),
+        axum::http::Method::GET | axum::http::Method::HEAD
+    ) {
+        dispatch
+            .registry
+            .check_write_quota(&tenant_id)
+            .map_err(|e| {
+                create_error_response(
+                    axum::http::StatusCode::INSUFFICIENT_STORAGE,
+                    "TENANT_QUOTA_EXCEEDED",
+                    &e.to_string(),
+                )
+            })?;
+    }
+
+    // A fresh request, so path parameters matched here do not leak into the tenant API
+    let (parts, body) = request.into_parts();
+    let path_and_query = match parts.uri.query() {
+        Some(query) => format!("/{}?{}", path, query),
+        None => format!("/{}", path),
+    };
+    let mut forwarded = axum::extract::Request::builder()
+        .method(parts.method)
+        .uri(path_and_query)
+        .version(parts.version)
+        .body(body)
+        .map_err(|_| {
+            create_error_response(
+                axum::http::StatusCode::BAD_REQUEST,
+                "INVALID_PATH",
+                "Request path could not be routed",
+            )
+        })?;
+    *forwarded.headers_mut() = parts.headers;
+    if let Some(auth_store) = parts.extensions.get::<AuthStore>() {
+        forwarded.extensions_mut().insert(auth_store.clone());
+    }
+
+    let router = dispatch.router(&tenant_id).map_err(api_error)?;
+    Ok(router
+        .oneshot(forwarded)
+        .await
+        .unwrap_or_else(|e| match e {}))
+}
+
+/// Reject credentials bound to another tenant; the state is the tenant this API serves
+#[cfg(feature = "native")]
+async fn require_tenant_credentials(
+    axum::extract::State(tenant): axum::extract::State<Option<String>>,
+    request: axum::extract::Request,
+    next: middleware::Next,
+) -> Result<axum::response::Response, ApiError> {
+    let auth = request
+        .extensions()
+        .get::<AuthContext>()
+        .cloned()
+        .unwrap_or_default();
+    auth.require_tenant(tenant.as_deref())?;
+    Ok(next.run(request).await)
+}
+
+/// The REST API over one database: the node's own, or a tenant's (`tenant` is its ID)
+#[cfg(feature = "native")]
+fn api_router(
+    state: AppState,
+    rate_limiter_store: RateLimiterStore,
+    tenant: Option<String>,
+) -> Router {
+    let tenants = state.tenants.clone();
+    let api_routes = Router::new()
+        .route("/status", get(api_status))
+        .route("/security", get(security_status))
+        .route("/signing-key", get(signing_key))
+        .route("/auth/refresh", post(refresh_session))
+        .route("/auth/logout", post(logout))
+        .route("/auth/logout-all", post(logout_all))
+        .route("/transparency/tree-head", get(transparency_tree_head))
+        .route(
+            "/transparency/proof/inclusion",
+            get(transparency_inclusion_proof),
+        )
+        .route(
+            "/transparency/proof/consistency",
+            get(transparency_consistency_proof),
+        )
+        .route("/data-subject/export", get(data_subject_export))
+        .route("/data-subject/erase", post(data_subject_erase))
+        .route("/events", get(activity_events))
+        .route("/feed", get(memory_feed))
+        .route("/claims/:organization/stats", get(claim_statistics))
+        .route(
+            "/claims/:organization/stats/claim-rate",
+            get(claim_rate_stats),
+        )
+        .route(
+            "/claims/:organization/stats/status",
+            get(token_status_stats),
+        )
+        .route(
+            "/claims/:organization/stats/time-to-claim",
+            get(time_to_claim_stats),
+        )
+        .route("/claims/preview", post(preview_claim))
+        .route("/proxies/:id/duplicates", get(proxy_duplicates))
+        .route("/duplicates/mergeable", get(mergeable_duplicates))
+        .route("/duplicates/:id/dismiss", post(dismiss_duplicate))
+        .route("/inbound", get(list_inbound))
+        .route("/inbound/:id/approve", post(approve_inbound))
+        .route("/inbound/:id/reject", post(reject_inbound))
+        .route("/peers", get(list_peers))
+        .route("/peers/:id", get(get_peer))
+        .route("/peers/:id/disconnect", post(disconnect_peer))
+        .route("/peers/:id/ban", post(ban_peer))
+        .route("/peers/:id/trust", post(trust_peer))
+        .route("/peers/:id/reset", post(reset_peer))
+        .route("/quarantine", get(list_quarantine))
+        .route(
+            "/organizations/:did/verification",
+            get(organization_verification).delete(revoke_organization_verification),
+        )
+        .route(
+            "/organizations/:did/verification/domain",
+            post(verify_organization_domain),
+        )
+        .route(
+            "/organizations/:did/verification/attest",
+            post(attest_organization),
+        )
+        .route("/csv/:table", get(csv_export))
+        .route("/csv/:table/import", post(csv_import))
+        .route("/handles", post(register_handle))
+        .route("/handles/:handle", get(resolve_handle))
+        .route("/handles/:handle/verify", post(reverify_handle))
+        .route("/dids/:did/handle", get(did_handle).delete(release_handle))
+        .route("/dids/:did/handle/history", get(handle_history))
+        .route("/dids/:did/keys", get(did_key_pins))
+        .route("/contacts", get(list_contacts).post(add_contact))
+        .route(
+            "/contacts/:did",
+            get(get_contact)
+                .patch(update_contact)
+                .delete(remove_contact),
+        )
+        .route("/contacts/:did/verify", post(verify_contact))
+        .route(
+            "/contacts/:did/safety-number",
+            get(contact_safety_number).post(confirm_safety_number),
+        )
+        .route("/presence", get(peer_presence))
+        .route("/messages", get(inbox).post(send_message))
+        .route("/messages/with/:did", get(conversation))
+        .route("/messages/:id/read", post(mark_message_read))
+        .route("/tags", get(list_tags))
+        .route("/tags/:tag/memories", get(tagged_memories))
+        .route("/memories/export", get(export_memories))
+        .route("/memories/merge", post(merge_memories))
+        .route("/memories/:id", get(get_memory).patch(patch_memory))
+        .route("/memories/:id/diff/:other", get(diff_memories))
+        .route("/individuals", get(list_individuals))
+        .route("/individuals/:id", get(get_individual))
+        .route("/:batch", post(batch_write))
+        .route("/jobs", get(list_jobs).post(start_job))
+        .route("/jobs/:id", get(get_job))
+        .route("/jobs/:id/cancel", post(cancel_job))
+        .route("/memories/:id/tags", get(memory_tags).post(tag_memory))
+        .route("/memories/:id/delivery-status", get(delivery_status))
+        .route("/memories/:id/read", post(mark_memory_read))
+        .route(
+            "/memories/:id/annotations",
+            get(memory_annotations).post(annotate_memory),
+        )
+        .route(
+            "/memories/:id/tags/:tag",
+            axum::routing::delete(untag_memory),
+        );
+    #[cfg(feature = "rules")]
+    let api_routes = api_routes.route("/rules", get(list_rules)).route(
+        "/rules/:name",
+        axum::routing::put(save_rule).delete(delete_rule),
+    );
+    #[cfg(feature = "push")]
+    let api_routes = api_routes
+        .route("/push/vapid-public-key", get(vapid_public_key))
+        .route(
+            "/push/subscriptions",
+            get(list_push_subscriptions).post(subscribe_push),
+        )
+        .route(
+            "/push/subscriptions/:id",
+            axum::routing::delete(unsubscribe_push),
+        )
+        .route(
+            "/push/subscriptions/:id/events",
+            axum::routing::put(set_push_events),
+        );
+    let api_routes = match tenants {
+        Some(_) => api_routes
+            .route("/tenants", get(list_tenants).post(create_tenant))
+            .route(
+                "/tenants/:tenant",
+                get(get_tenant).patch(update_tenant).delete(delete_tenant),
+            )
+            .route("/tenants/:tenant/usage", get(tenant_usage))
+            .route("/tenants/:tenant/suspend", post(suspend_tenant))
+            .route("/tenants/:tenant/resume", post(resume_tenant)),
+        None => api_routes,
+    };
+    api_routes
+        .with_state(state)
+        // Inside the layers below, so credentials are already resolved
+        .route_layer(middleware::from_fn_with_state(
+            tenant,
+            require_tenant_credentials,
+        ))
+        .layer(
+            ServiceBuilder::new()
+                .layer(middleware::from_fn(create_api_read_rate_limiter(
+                    rate_limiter_store.clone(),
+                )))
+                .layer(middleware::from_fn(optional_auth_middleware))
+                .layer(middleware::from_fn(request_validation_middleware)),
+        )
+}
+
+#[cfg(feature = "native")]
+fn create_app_state(config: &OcmConfig) -> AppState {
+    if let Some(parent) = config.database.path.parent() {
+        let _ = std::fs::create_dir_all(parent);
+    }
+    let db_path = config.database.path.to_string_lossy().to_string();
+    let database = Arc::new(Database::new(&db_path).expect("Failed to open database"));
+
+    let mut state = app_state_for(database.clone(), config);
+    let supervisor = TaskSupervisor::new("web", config.supervisor.clone());
+    supervisor.report_to(database.clone());
+    if let Some(panic_reports) = panics::install_hook("web") {
+        panic_reports
+            .start(database.clone(), &config.telemetry, &supervisor)
+            .expect("Failed to start panic reporting");
+    }
+    let jobs = Arc::new(JobRunner::new(database.clone(), config.clone()));
+    jobs.clone().start(&supervisor);
+    state.jobs = Some(jobs);
+    #[cfg(feature = "push")]
+    if config.push.enabled {
+        let gateway = Arc::new(
+            PushGateway::new(config.push.clone(), database.clone())
+                .expect("Failed to start push gateway"),
+        );
+        gateway.clone().start(&supervisor);
+        info!("🔔 Push notifications enabled");
+        state.push = Some(gateway);
+    }
+    Arc::new(DidKeyRefresher::new(database.clone(), &config.plc)).start(&supervisor);
+    if config.tenancy.enabled {
+        info!("🏢 Multi-tenant mode: tenant APIs under /t/{{tenant}}/api/v1");
+        state.tenants = Some(Arc::new(TenantRegistry::new(
+            database,
+            config.tenancy.clone(),
+        )));
+    }
+    state
+}
+
+#[cfg(feature = "native")]
+fn app_state_for(database: Arc<Database>, config: &OcmConfig) -> AppState {
+    let signer = open_configured(&config.plc.key).expect("Failed to open the identity key");
+    let identity = match signer {
+        Some(signer) => PlcIdentity::from_signer(signer, config.plc.handle.clone()),
+        None => PlcIdentity::generate(config.plc.handle.clone()),
+    }
+    .expect("Failed to generate identity");
+    let handles = Arc::new(
+        HandleRegistry::new(config.handles.clone(), database.clone())
+            .expect("Failed to create handle registry"),
+    );
+    let memory_types = Arc::new(MemoryTypeRegistry::with_builtin_types());
+    if let Err(e) = database.index_memory_fields(&memory_types.indexed_fields()) {
+        warn!("Failed to index memory fields: {}", e);
+    }
+    if let Err(e) = database.set_durability(config.database.durability.clone()) {
+        warn!("Failed to apply database durability settings: {}", e);
+    }
+    if config.database.compression.enabled {
+        database.enable_compression(config.database.compression.clone());
+        match database.compress_memory_content() {
+            Ok(report) if report.bodies_compressed > 0 => info!(
+                "🗜️  Compressed {} memory bodies: {} bytes to {}",
+                report.bodies_compressed, report.bytes_before, report.bytes_after
+            ),
+            Ok(_) => {}
+            Err(e) => warn!("Failed to compress memory bodies: {}", e),
+        }
+    }
+
+    AppState {
+        database: database.clone(),
+        transparency: Arc::new(TransparencyLog::new(database.clone())),
+        claims: Arc::new(ClaimSystem::new(database.clone()).with_linkage(config.linkage.clone())),
+        tags: Arc::new(TagService::new(database.clone(), identity.clone())),
+        merges: Arc::new(MergeService::new(database.clone(), identity.clone())),
+        annotations: Arc::new(AnnotationService::new(
+            database.clone(),
+            memory_types.clone(),
+            identity.clone(),
+        )),
+        organizations: Arc::new(OrganizationVerifier::new(database.clone(), handles.clone())),
+        review: Arc::new(InboundReview::new(
+            database.clone(),
+            Arc::new(PeerGroupRegistry::new(database.clone())),
+        )),
+        handles,
+        contacts: Arc::new(ContactService::new(database.clone(), identity.clone())),
+        messages: Arc::new(DirectMessageService::new(
+            database.clone(),
+            identity.clone(),
+        )),
+        receipts: Arc::new(ReceiptService::new(
+            database,
+            identity.clone(),
+            config.receipts.clone(),
+        )),
+        presence_offline_after_seconds: config.networking.presence.offline_after_seconds,
+        memory_types,
+        max_batch_items: config.web.batch.max_items,
+        tenants: None,
+        jobs: None,
+        #[cfg(feature = "push")]
+        push: None,
+        identity: Arc::new(identity),
+    }
+}
+
+/// Trade a refresh token for a new session and refresh token. Needs no other credential
+#[cfg(feature = "native")]
+async fn refresh_session(
+    axum::Extension(auth_store): axum::Extension<AuthStore>,
+    axum::Json(request): axum::Json<RefreshRequest>,
+) -> Result<axum::Json<SessionTokens>, ApiError> {
+    use axum::http::StatusCode;
+    auth_store
+        .refresh_session(&request.refresh_token)
+        .map(axum::Json)
+        .map_err(|e| match e {
+            RefreshError::Invalid => create_error_response(
+                StatusCode::UNAUTHORIZED,
+                "INVALID_REFRESH_TOKEN",
+                "Invalid or expired refresh token",
+            ),
+            RefreshError::Reused => {
+                warn!("🚨 Refresh token reused; revoked its session family");
+                create_error_response(
+                    StatusCode::UNAUTHORIZED,
+                    "REFRESH_TOKEN_REUSED",
+                    "Refresh token was already used; log in again",
+                )
+            }
+            RefreshError::Store(msg) => {
+                warn!("Session store error: {}", msg);
+                create_error_response(
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    "INTERNAL_ERROR",
+                    "Request could not be completed",
+                )
+            }
+        })
+}
+
+/// End the calling session and its refresh token
+#[cfg(feature = "native")]
+async fn logout(
+    axum::Extension(auth_store): axum::Extension<AuthStore>,
+    axum::Extension(auth): axum::Extension<AuthContext>,
+) -> Result<axum::http::StatusCode, ApiError> {
+    let session_id = auth.session_id.ok_or_else(session_required)?;
+    auth_store.invalidate_session(&session_id);
+    Ok(axum::http::StatusCode::NO_CONTENT)
+}
+
+/// End every session of the calling session's user, on all devices
+#[cfg(feature = "native")]
+async fn logout_all(
+    axum::Extension(auth_store): axum::Extension<AuthStore>,
+    axum::Extension(auth): axum::Extension<AuthContext>,
+) -> Result<axum::Json<serde_json::Value>, ApiError> {
+    let user_did = auth.user_did.ok_or_else(session_required)?;
+    let ended = auth_store.invalidate_user_sessions(&user_did);
+    Ok(axum::Json(serde_json::json!({ "sessions_ended": ended })))
+}
+
+#[cfg(feature = "native")]
+fn session_required() -> ApiError {
+    create_error_response(
+        axum::http::StatusCode::UNAUTHORIZED,
+        "SESSION_REQUIRED",
+        "This endpoint needs an X-Session-Id header",
+    )
+}
+
+/// Validators of a GET response: an entity tag and, when known, a modification time
+#[cfg(feature = "native")]
+struct Validators {
+    etag: String,
+    last_modified: Option<chrono::DateTime<chrono::Utc>>,
+}
+
+#[cfg(feature = "native")]
+impl Validators {
+    /// `last_modified` is RFC 3339 or, as SQLite writes it, "YYYY-MM-DD HH:MM:SS" in UTC;
+    /// anything else is left out of the response
+    fn new(etag: String, last_modified: &str) -> Self {
+        let last_modified = chrono::DateTime::parse_from_rfc3339(last_modified)
+            .map(|at| at.with_timezone(&chrono::Utc))
+            .or_else(|_| {
+                chrono::NaiveDateTime::parse_from_str(last_modified, "%Y-%m-%d %H:%M:%S")
+                    .map(|at| at.and_utc())
+            })
+            .ok();
+        Self {
+            etag,
+            last_modified,
+        }
+    }
+
+    /// Whether the client's copy is current. If-None-Match, compared weakly, takes
+    /// precedence over If-Modified-Since (RFC 9110 section 13.2.2)
+    fn is_fresh(&self, headers: &axum::http::HeaderMap) -> bool {
+        use axum::http::header::{IF_MODIFIED_SINCE, IF_NONE_MATCH};
+
+        let opaque = |tag: &str| tag.trim().trim_start_matches("W/").to_string();
+        if let Some(if_none_match) = headers.get(IF_NONE_MATCH) {
+            let if_none_match = if_none_match.to_str().unwrap_or("");
+            return if_none_match.trim() == "*"
+                || if_none_match
+                    .split(',')
+                    .any(|tag| opaque(tag) == opaque(&self.etag));
+        }
+        let since = headers
+            .get(IF_MODIFIED_SINCE)
+            .and_then(|since| since.to_str().ok())
+            .and_then(|since| chrono::DateTime::parse_from_rfc2822(since).ok());
+        match (self.last_modified, since) {
+            (Some(modified), Some(since)) => modified.timestamp() <= since.timestamp(),
+            _ => false,
+        }
+    }
+
+    /// 304 Not Modified if the client's copy is current, otherwise the JSON body.
+    /// Either way the validators are sent, and caches must revalidate before reuse
+    fn respond<T: serde::Serialize>(
+        &self,
+        headers: &axum::http::HeaderMap,
+        body: impl FnOnce() -> Result<T, ApiError>,
+    ) -> Result<axum::response::Response, ApiError> {
+        use axum::http::{header, HeaderValue, StatusCode};
+        use axum::response::IntoResponse;
+
+        let mut response = if self.is_fresh(headers) {
+            StatusCode::NOT_MODIFIED.into_response()
+        } else {
+            axum::Json(body()?).into_response()
+        };
+        let response_headers = response.headers_mut();
+        if let Ok(etag) = HeaderValue::from_str(&self.etag) {
+            response_headers.insert(header::ETAG, etag);
+        }
+        if let Some(modified) = self.last_modified {
+            let http_date = modified.format("%a, %d %b %Y %H:%M:%S GMT").to_string();
+            if let Ok(http_date) = HeaderValue::from_str(&http_date) {
+                response_headers.insert(header::LAST_MODIFIED, http_date);
+            }
+        }
+        response_headers.insert(
+            header::CACHE_CONTROL,
+            HeaderValue::from_static("private, no-cache"),
+        );
+        Ok(response)
+    }
+}
+
+/// Weak validator for a list: the collection's version counter plus the request's query
+/// and scope, which decide what the list holds
+#[cfg(feature = "native")]
+fn collection_validators(
+    state: &AppState,
+    table: &str,
+    query: &str,
+    auth: &AuthContext,
+) -> Result<Validators, ApiError> {
+    use sha2::{Digest, Sha256};
+
+    let (version, updated_at) = state
+        .database
+        .collection_version(table)
+        .map_err(api_error)?;
+    let variant = Sha256::digest(
+        format!(
+            "{}\n{}",
+            query,
+            auth.organization_did.as_deref().unwrap_or("")
+        )
+        .as_bytes(),
+    );
+    Ok(Validators::new(
+        format!("W/\"{}-{}\"", version, &hex::encode(variant)[..16]),
+        &updated_at,
+    ))
+}
+
+#[cfg(feature = "native")]
+/// A memory, peer or other ID in a path, query or body that does not parse
+fn invalid_id(error: InvalidId) -> ApiError {
+    create_error_response(
+        axum::http::StatusCode::BAD_REQUEST,
+        "INVALID_ID",
+        &error.to_string(),
+    )
+}
+
+fn api_error(error: OcmError) -> ApiError {
+    use axum::http::StatusCode;
+    match error {
+        OcmError::NotFound(msg) => create_error_response(StatusCode::NOT_FOUND, "NOT_FOUND", &msg),
+        OcmError::Validation(msg) => {
+            create_error_response(StatusCode::BAD_REQUEST, "INVALID_REQUEST", &msg)
+        }
+        OcmError::AlreadyExists(msg) => {
+            create_error_response(StatusCode::CONFLICT, "ALREADY_EXISTS", &msg)
+        }
+        e => {
+            warn!("API storage error: {}", e);
+            create_error_response(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "INTERNAL_ERROR",
+                "Request could not be completed",
+            )
+        }
+    }
+}
+
+/// The key behind tree head and response signatures
+#[cfg(feature = "native")]
+async fn signing_key(
+    axum::extract::State(state): axum::extract::State<AppState>,
+) -> axum::Json<serde_json::Value> {
+    axum::Json(serde_json::json!({
+        "did": state.identity.did,
+        "public_key": state.identity.keypair.public_key,
+        "algorithm": "ed25519",
+    }))
+}
+
+#[cfg(feature = "native")]
+async fn transparency_tree_head(
+    axum::extract::State(state): axum::extract::State<AppState>,
+) -> Result<axum::Json<SignedTreeHead>, ApiError> {
+    state
+        .transparency
+        .signed_tree_head(&state.identity)
+        .map(axum::Json)
+        .map_err(api_error)
+}
+
+#[cfg(feature = "native")]
+async fn transparency_inclusion_proof(
+    axum::extract::State(state): axum::extract::State<AppState>,
+    axum::extract::Query(query): axum::extract::Query<InclusionQuery>,
+) -> Result<axum::Json<InclusionProof>, ApiError> {
+    let memory_id = MemoryId::parse(&query.memory_id).map_err(invalid_id)?;
+    state
+        .transparency
+        .inclusion_proof(&memory_id, query.tree_size)
+        .map(axum::Json)
+        .map_err(api_error)
+}
+
+#[cfg(feature = "native")]
+async fn transparency_consistency_proof(
+    axum::extract::State(state): axum::extract::State<AppState>,
+    axum::extract::Query(query): axum::extract::Query<ConsistencyQuery>,
+) -> Result<axum::Json<ConsistencyProof>, ApiError> {
+    state
+        .transparency
+        .consistency_proof(query.first, query.second)
+        .map(axum::Json)
+        .map_err(api_error)
+}
+
+#[cfg(feature = "native")]
+fn parse_subject_did(did: &str) -> Result<Did, ApiError> {
+    Did::parse(did).map_err(|_| {
+        create_error_response(
+            axum::http::StatusCode::BAD_REQUEST,
+            "INVALID_DID",
+            "A valid subject DID is required",
+        )
+    })
+}
+
+#[cfg(feature = "native")]
+async fn data_subject_export(
+    axum::extract::State(state): axum::extract::State<AppState>,
+    axum::Extension(auth): axum::Extension<AuthContext>,
+    axum::extract::Query(query): axum::extract::Query<DataSubjectQuery>,
+) -> Result<axum::Json<DataSubjectExport>, ApiError> {
+    auth.require_permission("data_subject")?;
+    auth.require_unscoped()?;
+    let did = parse_subject_did(&query.did)?;
+
+    info!("Data subject export requested for {}", Redacted::did(&did));
+    state
+        .claims
+        .export_subject_data(&did)
+        .map(axum::Json)
+        .map_err(api_error)
+}
+
+#[cfg(feature = "native")]
+async fn data_subject_erase(
+    axum::extract::State(state): axum::extract::State<AppState>,
+    axum::Extension(auth): axum::Extension<AuthContext>,
+    axum::Json(request): axum::Json<DataSubjectQuery>,
+) -> Result<axum::Json<Vec<ErasureRecord>>, ApiError> {
+    auth.require_permission("data_subject")?;
+    auth.require_unscoped()?;
+    let did = parse_subject_did(&request.did)?;
+
+    warn!("Data subject erasure requested for {}", Redacted::did(&did));
+    state
+        .claims
+        .erase_subject_data(&state.identity, &did)
+        .map(axum::Json)
+        .map_err(api_error)
+}
+
+#[cfg(feature = "native")]
+async fn claim_statistics(
+    axum::extract::State(state): axum::extract::State<AppState>,
+    axum::Extension(auth): axum::Extension<AuthContext>,
+    axum::extract::Path(organization): axum::extract::Path<String>,
+) -> Result<axum::Json<ClaimStatistics>, ApiError> {
+    auth.require_permission("read")?;
+    let organization = parse_subject_did(&organization)?;
+    auth.require_organization(&organization)?;
+    state
+        .claims
+        .get_claim_statistics(&organization)
+        .map(axum::Json)
+        .map_err(api_error)
+}
+
+/// Who issued a claim token and what redeeming it would give the signed-in user,
+/// without redeeming it
+#[cfg(feature = "native")]
+async fn preview_claim(
+    axum::extract::State(state): axum::extract::State<AppState>,
+    axum::extract::ConnectInfo(client): axum::extract::ConnectInfo<SocketAddr>,
+    axum::Extension(auth): axum::Extension<AuthContext>,
+    axum::Json(request): axum::Json<ClaimPreviewRequest>,
+) -> Result<axum::Json<ClaimPreview>, ApiError> {
+    auth.require_permission("read")?;
+    let user_did = auth.user_did.as_deref().ok_or_else(session_required)?;
+    let user_did = Did::parse(user_did).map_err(invalid_id)?;
+    // Failed lookups lock out the connecting address as well as the DID, which is free to mint
+    let client_ip = client.ip().to_string();
+    state
+        .claims
+        .preview_claim(&request.token, &user_did, Some(&client_ip))
+        .map(axum::Json)
+        .map_err(api_error)
+}
+
+/// Other organizations' records that probably describe the same person as a proxy record
+#[cfg(feature = "native")]
+async fn proxy_duplicates(
+    axum::extract::State(state): axum::extract::State<AppState>,
+    axum::Extension(auth): axum::Extension<AuthContext>,
+    axum::extract::Path(id): axum::extract::Path<String>,
+) -> Result<axum::Json<Vec<DuplicateCandidate>>, ApiError> {
+    auth.require_permission("read")?;
+    let proxy = state
+        .database
+        .get_proxy_memory(&id)
+        .and_then(|proxy| proxy.ok_or_else(|| OcmError::NotFound(format!("Proxy {}", id))))
+        .map_err(api_error)?;
+    auth.require_organization(&proxy.organization_did)?;
+    state
+        .claims
+        .list_duplicate_candidates(&id)
+        .map(axum::Json)
+        .map_err(api_error)
+}
+
+/// Duplicates the signed-in user has claimed both records of. Merging needs the
+/// user's key, so it happens on their device with `ClaimSystem::merge_duplicate`
+#[cfg(feature = "native")]
+async fn mergeable_duplicates(
+    axum::extract::State(state): axum::extract::State<AppState>,
+    axum::Extension(auth): axum::Extension<AuthContext>,
+) -> Result<axum::Json<Vec<DuplicateCandidate>>, ApiError> {
+    auth.require_permission("read")?;
+    let user_did = auth.user_did.as_deref().ok_or_else(session_required)?;
+    let user_did = Did::parse(user_did).map_err(invalid_id)?;
+    state
+        .claims
+        .list_mergeable_duplicates(&user_did)
+        .map(axum::Json)
+        .map_err(api_error)
+}
+
+#[cfg(feature = "native")]
+async fn dismiss_duplicate(
+    axum::extract::State(state): axum::extract::State<AppState>,
+    axum::Extension(auth): axum::Extension<AuthContext>,
+    axum::extract::Path(id): axum::extract::Path<String>,
+) -> Result<axum::Json<DuplicateCandidate>, ApiError> {
+    auth.require_permission("write")?;
+    auth.require_unscoped()?;
+    state
+        .claims
+        .dismiss_duplicate(&id)
+        .map(axum::Json)
+        .map_err(api_error)
+}
+
+/// Memories from peers held by inbound review
+#[cfg(feature = "native")]
+async fn list_inbound(
+    axum::extract::State(state): axum::extract::State<AppState>,
+    axum::Extension(auth): axum::Extension<AuthContext>,
+    axum::extract::Query(query): axum::extract::Query<InboundQuery>,
+) -> Result<axum::Json<Vec<PendingMemory>>, ApiError> {
+    auth.require_permission("admin")?;
+    auth.require_unscoped()?;
+    let status = match query.status.as_deref() {
+        None => PendingStatus::Pending,
+        Some(status) => PendingStatus::parse(status).ok_or_else(|| {
+            api_error(OcmError::Validation(format!(
+                "Unknown review status {}",
+                status
+            )))
+        })?,
+    };
+    state.review.list(status).map(axum::Json).map_err(api_error)
+}
+
+#[cfg(feature = "native")]
+async fn approve_inbound(
+    axum::extract::State(state): axum::extract::State<AppState>,
+    axum::Extension(auth): axum::Extension<AuthContext>,
+    axum::extract::Path(id): axum::extract::Path<String>,
+) -> Result<axum::Json<PendingMemory>, ApiError> {
+    auth.require_permission("admin")?;
+    auth.require_unscoped()?;
+    state.review.approve(&id).map(axum::Json).map_err(api_error)
+}
+
+#[cfg(feature = "native")]
+async fn reject_inbound(
+    axum::extract::State(state): axum::extract::State<AppState>,
+    axum::Extension(auth): axum::Extension<AuthContext>,
+    axum::extract::Path(id): axum::extract::Path<String>,
+) -> Result<axum::http::StatusCode, ApiError> {
+    auth.require_permission("admin")?;
+    auth.require_unscoped()?;
+    state.review.reject(&id).map_err(api_error)?;
+    Ok(axum::http::StatusCode::NO_CONTENT)
+}
+
+/// Peers the node has recorded, with their traffic, standing and reputation. Changes
+/// made here reach the running node at its next peer record flush
+#[cfg(feature = "native")]
+async fn list_peers(
+    axum::extract::State(state): axum::extract::State<AppState>,
+    axum::Extension(auth): axum::Extension<AuthContext>,
+) -> Result<axum::Json<Vec<PeerRecord>>, ApiError> {
+    auth.require_permission("admin")?;
+    auth.require_unscoped()?;
+    state
+        .database
+        .list_peer_records()
+        .map(axum::Json)
+        .map_err(api_error)
+}
+
+#[cfg(feature = "native")]
+async fn get_peer(
+    axum::extract::State(state): axum::extract::State<AppState>,
+    axum::Extension(auth): axum::Extension<AuthContext>,
+    axum::extract::Path(id): axum::extract::Path<String>,
+) -> Result<axum::Json<PeerRecord>, ApiError> {
+    auth.require_permission("admin")?;
+    auth.require_unscoped()?;
+    let peer_id = PeerId::parse(&id).map_err(invalid_id)?;
+    known_peer(&state.database, &peer_id).map(axum::Json)
+}
+
+#[cfg(feature = "native")]
+async fn disconnect_peer(
+    axum::extract::State(state): axum::extract::State<AppState>,
+    axum::Extension(auth): axum::Extension<AuthContext>,
+    axum::extract::Path(id): axum::extract::Path<String>,
+) -> Result<axum::http::StatusCode, ApiError> {
+    auth.require_permission("admin")?;
+    auth.require_unscoped()?;
+    let peer_id = PeerId::parse(&id).map_err(invalid_id)?;
+    if !state
+        .database
+        .request_peer_disconnect(&peer_id)
+        .map_err(api_error)?
+    {
+        return Err(api_error(OcmError::NotFound(format!("Peer {}", peer_id))));
+    }
+    Ok(axum::http::StatusCode::ACCEPTED)
+}
+
+#[cfg(feature = "native")]
+async fn ban_peer(
+    axum::extract::State(state): axum::extract::State<AppState>,
+    axum::Extension(auth): axum::Extension<AuthContext>,
+    axum::extract::Path(id): axum::extract::Path<String>,
+    request: Option<axum::Json<PeerStandingRequest>>,
+) -> Result<axum::Json<PeerRecord>, ApiError> {
+    auth.require_permission("admin")?;
+    auth.require_unscoped()?;
+    let peer_id = PeerId::parse(&id).map_err(invalid_id)?;
+    let reason = request.and_then(|axum::Json(request)| request.reason);
+    set_peer_standing(&state.database, &peer_id, PeerStanding::Banned, reason)
+}
+
+#[cfg(feature = "native")]
+async fn trust_peer(
+    axum::extract::State(state): axum::extract::State<AppState>,
+    axum::Extension(auth): axum::Extension<AuthContext>,
+    axum::extract::Path(id): axum::extract::Path<String>,
+    request: Option<axum::Json<PeerStandingRequest>>,
+) -> Result<axum::Json<PeerRecord>, ApiError> {
+    auth.require_permission("admin")?;
+    auth.require_unscoped()?;
+    let peer_id = PeerId::parse(&id).map_err(invalid_id)?;
+    let reason = request.and_then(|axum::Json(request)| request.reason);
+    set_peer_standing(&state.database, &peer_id, PeerStanding::Trusted, reason)
+}
+
+/// Clear a ban or trust
+#[cfg(feature = "native")]
+async fn reset_peer(
+    axum::extract::State(state): axum::extract::State<AppState>,
+    axum::Extension(auth): axum::Extension<AuthContext>,
+    axum::extract::Path(id): axum::extract::Path<String>,
+) -> Result<axum::Json<PeerRecord>, ApiError> {
+    auth.require_permission("admin")?;
+    auth.require_unscoped()?;
+    let peer_id = PeerId::parse(&id).map_err(invalid_id)?;
+    set_peer_standing(&state.database, &peer_id, PeerStanding::Neutral, None)
+}
+
+#[cfg(feature = "native")]
+fn set_peer_standing(
+    database: &Database,
+    peer_id: &PeerId,
+    standing: PeerStanding,
+    reason: Option<String>,
+) -> Result<axum::Json<PeerRecord>, ApiError> {
+    info!("Peer {} set to {}", peer_id, standing.as_str());
+    database
+        .set_peer_standing(peer_id, standing, reason.as_deref())
+        .map_err(api_error)?;
+    known_peer(database, peer_id).map(axum::Json)
+}
+
+#[cfg(feature = "native")]
+fn known_peer(database: &Database, peer_id: &PeerId) -> Result<PeerRecord, ApiError> {
+    database
+        .get_peer_record(peer_id)
+        .map_err(api_error)?
+        .ok_or_else(|| api_error(OcmError::NotFound(format!("Peer {}", peer_id))))
+}
+
+/// Memories that failed verification, whether received from peers or found on disk
+#[cfg(feature = "native")]
+async fn list_quarantine(
+    axum::extract::State(state): axum::extract::State<AppState>,
+    axum::Extension(auth): axum::Extension<AuthContext>,
+    axum::extract::Query(query): axum::extract::Query<QuarantineQuery>,
+) -> Result<axum::Json<Vec<QuarantineEntry>>, ApiError> {
+    auth.require_permission("admin")?;
+    auth.require_unscoped()?;
+    state
+        .database
+        .list_quarantined_memories(query.limit.unwrap_or(100).min(1000))
+        .map(axum::Json)
+        .map_err(api_error)
+}
+
+/// Whether an organization is verified, for showing a badge next to its records
+#[cfg(feature = "native")]
+async fn organization_verification(
+    axum::extract::State(state): axum::extract::State<AppState>,
+    axum::Extension(auth): axum::Extension<AuthContext>,
+    axum::extract::Path(did): axum::extract::Path<String>,
+) -> Result<axum::Json<OrganizationBadge>, ApiError> {
+    auth.require_permission("read")?;
+    let did = parse_subject_did(&did)?;
+    state
+        .claims
+        .organization_badge(&did)
+        .map(axum::Json)
+        .map_err(api_error)
+}
+
+/// Verify an organization by a domain naming its DID in DNS or /.well-known/ocm-did
+#[cfg(feature = "native")]
+async fn verify_organization_domain(
+    axum::extract::State(state): axum::extract::State<AppState>,
+    axum::Extension(auth): axum::Extension<AuthContext>,
+    axum::extract::Path(did): axum::extract::Path<String>,
+    axum::Json(request): axum::Json<DomainVerificationRequest>,
+) -> Result<axum::Json<OrganizationVerification>, ApiError> {
+    auth.require_permission("write")?;
+    let did = parse_subject_did(&did)?;
+    auth.require_organization(&did)?;
+    state
+        .organizations
+        .verify_domain(&did, &request.domain)
+        .await
+        .map(axum::Json)
+        .map_err(api_error)
+}
+
+/// Vouch for an organization with this node's identity, which must be verified itself
+#[cfg(feature = "native")]
+async fn attest_organization(
+    axum::extract::State(state): axum::extract::State<AppState>,
+    axum::Extension(auth): axum::Extension<AuthContext>,
+    axum::extract::Path(did): axum::extract::Path<String>,
+) -> Result<axum::Json<OrganizationVerification>, ApiError> {
+    auth.require_permission("admin")?;
+    auth.require_unscoped()?;
+    let did = parse_subject_did(&did)?;
+    info!("Attesting organization {}", Redacted::did(&did));
+    state
+        .organizations
+        .attest(&state.identity, &did)
+        .map(axum::Json)
+        .map_err(api_error)
+}
+
+#[cfg(feature = "native")]
+async fn revoke_organization_verification(
+    axum::extract::State(state): axum::extract::State<AppState>,
+    axum::Extension(auth): axum::Extension<AuthContext>,
+    axum::extract::Path(did): axum::extract::Path<String>,
+) -> Result<axum::http::StatusCode, ApiError> {
+    auth.require_permission("admin")?;
+    auth.require_unscoped()?;
+    let did = parse_subject_did(&did)?;
+    warn!("Revoking the verification of {}", Redacted::did(&did));
+    state
+        .organizations
+        .revoke(&did)
+        .map(|_| axum::http::StatusCode::NO_CONTENT)
+        .map_err(api_error)
+}
+
+/// Claim rate per period; `bucket` is day, week or month, `since`/`until` are RFC 3339
+#[cfg(feature = "native")]
+async fn claim_rate_stats(
+    axum::extract::State(state): axum::extract::State<AppState>,
+    axum::Extension(auth): axum::Extension<AuthContext>,
+    axum::extract::Path(organization): axum::extract::Path<String>,
+    axum::extract::Query(query): axum::extract::Query<ClaimStatsQuery>,
+) -> Result<axum::Json<Vec<ClaimRatePoint>>, ApiError> {
+    auth.require_permission("read")?;
+    let organization = parse_subject_did(&organization)?;
+    auth.require_organization(&organization)?;
+    state
+        .database
+        .claim_rate_over_time(&organization, &query)
+        .map(axum::Json)
+        .map_err(api_error)
+}
+
+#[cfg(feature = "native")]
+async fn token_status_stats(
+    axum::extract::State(state): axum::extract::State<AppState>,
+    axum::Extension(auth): axum::Extension<AuthContext>,
+    axum::extract::Path(organization): axum::extract::Path<String>,
+    axum::extract::Query(query): axum::extract::Query<ClaimStatsQuery>,
+) -> Result<axum::Json<Vec<TokenStatusPoint>>, ApiError> {
+    auth.require_permission("read")?;
+    let organization = parse_subject_did(&organization)?;
+    auth.require_organization(&organization)?;
+    state
+        .database
+        .token_status_over_time(&organization, &query)
+        .map(axum::Json)
+        .map_err(api_error)
+}
+
+#[cfg(feature = "native")]
+async fn time_to_claim_stats(
+    axum::extract::State(state): axum::extract::State<AppState>,
+    axum::Extension(auth): axum::Extension<AuthContext>,
+    axum::extract::Path(organization): axum::extract::Path<String>,
+    axum::extract::Query(query): axum::extract::Query<ClaimStatsQuery>,
+) -> Result<axum::Json<Vec<TimeToClaimPoint>>, ApiError> {
+    auth.require_permission("read")?;
+    let organization = parse_subject_did(&organization)?;
+    auth.require_organization(&organization)?;
+    state
+        .database
+        .time_to_claim_over_time(&organization, &query)
+        .map(axum::Json)
+        .map_err(api_error)
+}
+
+#[cfg(feature = "native")]
+fn tenant_registry(state: &AppState) -> Result<&Arc<TenantRegistry>, ApiError> {
+    state.tenants.as_ref().ok_or_else(|| {
+        create_error_response(
+            axum::http::StatusCode::NOT_FOUND,
+            "TENANCY_DISABLED",
+            "This node is not running in multi-tenant mode",
+        )
+    })
+}
+
+/// The node identity's contact book. It is private to the node, so organization-scoped
+/// credentials cannot use it
+#[cfg(feature = "native")]
+async fn list_contacts(
+    axum::extract::State(state): axum::extract::State<AppState>,
+    axum::Extension(auth): axum::Extension<AuthContext>,
+    axum::extract::Query(query): axum::extract::Query<ContactListQuery>,
+) -> Result<axum::Json<Vec<Contact>>, ApiError> {
+    auth.require_permission("read")?;
+    auth.require_unscoped()?;
+    state
+        .contacts
+        .list(query.group.as_deref())
+        .map(axum::Json)
+        .map_err(api_error)
+}
+
+#[cfg(feature = "native")]
+async fn add_contact(
+    axum::extract::State(state): axum::extract::State<AppState>,
+    axum::Extension(auth): axum::Extension<AuthContext>,
+    axum::Json(request): axum::Json<ContactRequest>,
+) -> Result<axum::Json<Contact>, ApiError> {
+    auth.require_permission("write")?;
+    auth.require_unscoped()?;
+    let did = Did::parse(&request.did).map_err(invalid_id)?;
+    state
+        .contacts
+        .add(&did, request.update)
+        .await
+        .map(axum::Json)
+        .map_err(api_error)
+}
+
+#[cfg(feature = "native")]
+async fn get_contact(
+    axum::extract::State(state): axum::extract::State<AppState>,
+    axum::Extension(auth): axum::Extension<AuthContext>,
+    axum::extract::Path(did): axum::extract::Path<String>,
+) -> Result<axum::Json<Contact>, ApiError> {
+    auth.require_permission("read")?;
+    auth.require_unscoped()?;
+    let did = Did::parse(&did).map_err(invalid_id)?;
+    state.contacts.get(&did).map(axum::Json).map_err(api_error)
+}
+
+#[cfg(feature = "native")]
+async fn update_contact(
+    axum::extract::State(state): axum::extract::State<AppState>,
+    axum::Extension(auth): axum::Extension<AuthContext>,
+    axum::extract::Path(did): axum::extract::Path<String>,
+    axum::Json(update): axum::Json<ContactUpdate>,
+) -> Result<axum::Json<Contact>, ApiError> {
+    auth.require_permission("write")?;
+    auth.require_unscoped()?;
+    let did = Did::parse(&did).map_err(invalid_id)?;
+    state
+        .contacts
+        .update(&did, update)
+        .map(axum::Json)
+        .map_err(api_error)
+}
+
+#[cfg(feature = "native")]
+async fn remove_contact(
+    axum::extract::State(state): axum::extract::State<AppState>,
+    axum::Extension(auth): axum::Extension<AuthContext>,
+    axum::extract::Path(did): axum::extract::Path<String>,
+) -> Result<axum::http::StatusCode, ApiError> {
+    auth.require_permission("write")?;
+    auth.require_unscoped()?;
+    let did = Did::parse(&did).map_err(invalid_id)?;
+    state
+        .contacts
+        .remove(&did)
+        .map(|_| axum::http::StatusCode::NO_CONTENT)
+        .map_err(api_error)
+}
+
+/// Mark a contact verified once its fingerprint was compared out of band
+#[cfg(feature = "native")]
+async fn verify_contact(
+    axum::extract::State(state): axum::extract::State<AppState>,
+    axum::Extension(auth): axum::Extension<AuthContext>,
+    axum::extract::Path(did): axum::extract::Path<String>,
+    axum::Json(request): axum::Json<ContactVerifyRequest>,
+) -> Result<axum::Json<Contact>, ApiError> {
+    auth.require_permission("write")?;
+    auth.require_unscoped()?;
+    let did = Did::parse(&did).map_err(invalid_id)?;
+    state
+        .contacts
+        .verify(&did, &request.fingerprint)
+        .await
+        .map(axum::Json)
+        .map_err(api_error)
+}
+
+/// The safety number to read out or compare in person with a contact
 #[cfg(feature = "native")]
-use rustls;
+async fn contact_safety_number(
+    axum::extract::State(state): axum::extract::State<AppState>,
+    axum::Extension(auth): axum::Extension<AuthContext>,
+    axum::extract::Path(did): axum::extract::Path<String>,
+) -> Result<axum::Json<SafetyNumber>, ApiError> {
+    auth.require_permission("read")?;
+    auth.require_unscoped()?;
+    let did = Did::parse(&did).map_err(invalid_id)?;
+    state
+        .contacts
+        .safety_number(&did)
+        .await
+        .map(axum::Json)
+        .map_err(api_error)
+}
 
-// Import our security modules
+/// Mark a contact verified once the user confirmed both sides show the same safety number
 #[cfg(feature = "native")]
-use ocm_core::security::{
-    auth::*,
-    middleware::*,
-    rate_limiting::{
-        create_api_read_rate_limiter, create_health_rate_limiter, create_rate_limiter_store,
-    },
-};
+async fn confirm_safety_number(
+    axum::extract::State(state): axum::extract::State<AppState>,
+    axum::Extension(auth): axum::Extension<AuthContext>,
+    axum::extract::Path(did): axum::extract::Path<String>,
+    axum::Json(request): axum::Json<SafetyNumberRequest>,
+) -> Result<axum::Json<Contact>, ApiError> {
+    auth.require_permission("write")?;
+    auth.require_unscoped()?;
+    let did = Did::parse(&did).map_err(invalid_id)?;
+    state
+        .contacts
+        .confirm_safety_number(&did, &request.safety_number)
+        .await
+        .map(axum::Json)
+        .map_err(api_error)
+}
 
-#[tokio::main]
-async fn main() -> Result<(), Box<dyn std::error::Error>> {
-    // Initialize logging
-    tracing_subscriber::fmt::init();
+/// Presence last announced by the node's peers in trusted peer groups
+#[cfg(feature = "native")]
+async fn peer_presence(
+    axum::extract::State(state): axum::extract::State<AppState>,
+    axum::Extension(auth): axum::Extension<AuthContext>,
+) -> Result<axum::Json<Vec<PeerPresence>>, ApiError> {
+    auth.require_permission("read")?;
+    auth.require_unscoped()?;
+    list_presence(&state.database, state.presence_offline_after_seconds)
+        .map(axum::Json)
+        .map_err(api_error)
+}
 
-    // Initialize crypto provider for rustls
-    #[cfg(feature = "native")]
-    {
-        let _ = rustls::crypto::ring::default_provider().install_default();
+/// Messages received by the node identity, optionally only unread ones
+#[cfg(feature = "native")]
+async fn inbox(
+    axum::extract::State(state): axum::extract::State<AppState>,
+    axum::Extension(auth): axum::Extension<AuthContext>,
+    axum::extract::Query(query): axum::extract::Query<InboxQuery>,
+) -> Result<axum::Json<Vec<DirectMessage>>, ApiError> {
+    auth.require_permission("read")?;
+    auth.require_unscoped()?;
+    state
+        .messages
+        .inbox(query.unread)
+        .map(axum::Json)
+        .map_err(api_error)
+}
+
+/// Seal a message to the recipient's key and queue it for delivery
+#[cfg(feature = "native")]
+async fn send_message(
+    axum::extract::State(state): axum::extract::State<AppState>,
+    axum::Extension(auth): axum::Extension<AuthContext>,
+    axum::Json(request): axum::Json<MessageRequest>,
+) -> Result<axum::Json<DirectMessage>, ApiError> {
+    auth.require_permission("write")?;
+    auth.require_unscoped()?;
+    let did = Did::parse(&request.recipient_did).map_err(invalid_id)?;
+    state
+        .messages
+        .send(&did, &request.body)
+        .await
+        .map(axum::Json)
+        .map_err(api_error)
+}
+
+#[cfg(feature = "native")]
+async fn conversation(
+    axum::extract::State(state): axum::extract::State<AppState>,
+    axum::Extension(auth): axum::Extension<AuthContext>,
+    axum::extract::Path(did): axum::extract::Path<String>,
+) -> Result<axum::Json<Vec<DirectMessage>>, ApiError> {
+    auth.require_permission("read")?;
+    auth.require_unscoped()?;
+    let did = Did::parse(&did).map_err(invalid_id)?;
+    state
+        .messages
+        .conversation(&did)
+        .map(axum::Json)
+        .map_err(api_error)
+}
+
+#[cfg(feature = "native")]
+async fn mark_message_read(
+    axum::extract::State(state): axum::extract::State<AppState>,
+    axum::Extension(auth): axum::Extension<AuthContext>,
+    axum::extract::Path(id): axum::extract::Path<String>,
+) -> Result<axum::Json<DirectMessage>, ApiError> {
+    auth.require_permission("write")?;
+    auth.require_unscoped()?;
+    let id = MemoryId::parse(&id).map_err(invalid_id)?;
+    let message = state.messages.mark_read(&id).map_err(api_error)?;
+    state.receipts.mark_read(&id).map_err(api_error)?;
+    Ok(axum::Json(message))
+}
+
+/// Register or change a DID's handle. Domain handles are checked against DNS or
+/// the domain's /.well-known/ocm-did before they are accepted
+#[cfg(feature = "native")]
+async fn register_handle(
+    axum::extract::State(state): axum::extract::State<AppState>,
+    axum::Extension(auth): axum::Extension<AuthContext>,
+    axum::Json(request): axum::Json<HandleRequest>,
+) -> Result<axum::Json<HandleRecord>, ApiError> {
+    auth.require_permission("write")?;
+    let did = Did::parse(&request.did).map_err(invalid_id)?;
+    auth.require_organization(&did)?;
+    state
+        .handles
+        .register(&request.handle, &did)
+        .await
+        .map(axum::Json)
+        .map_err(api_error)
+}
+
+#[cfg(feature = "native")]
+async fn resolve_handle(
+    axum::extract::State(state): axum::extract::State<AppState>,
+    axum::Extension(auth): axum::Extension<AuthContext>,
+    axum::extract::Path(handle): axum::extract::Path<String>,
+) -> Result<axum::Json<HandleRecord>, ApiError> {
+    auth.require_permission("read")?;
+    state
+        .handles
+        .resolve(&handle)
+        .and_then(|record| record.ok_or_else(|| OcmError::NotFound(format!("Handle {}", handle))))
+        .map(axum::Json)
+        .map_err(api_error)
+}
+
+#[cfg(feature = "native")]
+async fn reverify_handle(
+    axum::extract::State(state): axum::extract::State<AppState>,
+    axum::Extension(auth): axum::Extension<AuthContext>,
+    axum::extract::Path(handle): axum::extract::Path<String>,
+) -> Result<axum::Json<HandleRecord>, ApiError> {
+    auth.require_permission("write")?;
+    state
+        .handles
+        .reverify(&handle)
+        .await
+        .map(axum::Json)
+        .map_err(api_error)
+}
+
+#[cfg(feature = "native")]
+async fn did_handle(
+    axum::extract::State(state): axum::extract::State<AppState>,
+    axum::Extension(auth): axum::Extension<AuthContext>,
+    axum::extract::Path(did): axum::extract::Path<String>,
+) -> Result<axum::Json<HandleRecord>, ApiError> {
+    auth.require_permission("read")?;
+    let did = Did::parse(&did).map_err(invalid_id)?;
+    state
+        .handles
+        .handle_for(&did)
+        .and_then(|record| {
+            record.ok_or_else(|| OcmError::NotFound(format!("Handle of {}", Redacted::did(&did))))
+        })
+        .map(axum::Json)
+        .map_err(api_error)
+}
+
+/// The keys pinned for a DID by background refresh, including removed ones
+#[cfg(feature = "native")]
+async fn did_key_pins(
+    axum::extract::State(state): axum::extract::State<AppState>,
+    axum::Extension(auth): axum::Extension<AuthContext>,
+    axum::extract::Path(did): axum::extract::Path<String>,
+) -> Result<axum::Json<Vec<DidKeyPin>>, ApiError> {
+    auth.require_permission("read")?;
+    let did = Did::parse(&did).map_err(invalid_id)?;
+    state
+        .database
+        .list_did_key_pins(&did)
+        .map(axum::Json)
+        .map_err(api_error)
+}
+
+#[cfg(feature = "native")]
+async fn release_handle(
+    axum::extract::State(state): axum::extract::State<AppState>,
+    axum::Extension(auth): axum::Extension<AuthContext>,
+    axum::extract::Path(did): axum::extract::Path<String>,
+) -> Result<axum::http::StatusCode, ApiError> {
+    auth.require_permission("write")?;
+    auth.require_organization(&did)?;
+    let did = Did::parse(&did).map_err(invalid_id)?;
+    state
+        .handles
+        .release(&did)
+        .map(|_| axum::http::StatusCode::NO_CONTENT)
+        .map_err(api_error)
+}
+
+#[cfg(feature = "native")]
+async fn handle_history(
+    axum::extract::State(state): axum::extract::State<AppState>,
+    axum::Extension(auth): axum::Extension<AuthContext>,
+    axum::extract::Path(did): axum::extract::Path<String>,
+) -> Result<axum::Json<Vec<HandleChange>>, ApiError> {
+    auth.require_permission("read")?;
+    let did = Did::parse(&did).map_err(invalid_id)?;
+    state
+        .handles
+        .history(&did)
+        .map(axum::Json)
+        .map_err(api_error)
+}
+
+#[cfg(feature = "native")]
+async fn list_tenants(
+    axum::extract::State(state): axum::extract::State<AppState>,
+    axum::Extension(auth): axum::Extension<AuthContext>,
+) -> Result<axum::Json<Vec<Tenant>>, ApiError> {
+    auth.require_permission("admin")?;
+    auth.require_unscoped()?;
+    tenant_registry(&state)?
+        .list()
+        .map(axum::Json)
+        .map_err(api_error)
+}
+
+#[cfg(feature = "native")]
+async fn create_tenant(
+    axum::extract::State(state): axum::extract::State<AppState>,
+    axum::Extension(auth): axum::Extension<AuthContext>,
+    axum::Json(request): axum::Json<TenantRequest>,
+) -> Result<axum::Json<Tenant>, ApiError> {
+    auth.require_permission("admin")?;
+    auth.require_unscoped()?;
+    let registry = tenant_registry(&state)?;
+    let id = request.id.as_deref().unwrap_or_default();
+    let name = request.name.as_deref().unwrap_or_default();
+    let mut tenant = registry.create(id, name).map_err(api_error)?;
+    if request.max_memories.is_some() || request.requests_per_minute.is_some() {
+        tenant = registry
+            .update(
+                &tenant.id,
+                None,
+                request.max_memories,
+                request.requests_per_minute,
+            )
+            .map_err(api_error)?;
     }
+    Ok(axum::Json(tenant))
+}
 
-    let app = create_app().await;
+#[cfg(feature = "native")]
+async fn get_tenant(
+    axum::extract::State(state): axum::extract::State<AppState>,
+    axum::Extension(auth): axum::Extension<AuthContext>,
+    axum::extract::Path(tenant_id): axum::extract::Path<String>,
+) -> Result<axum::Json<Tenant>, ApiError> {
+    auth.require_permission("admin")?;
+    auth.require_unscoped()?;
+    tenant_registry(&state)?
+        .get(&tenant_id)
+        .map(axum::Json)
+        .map_err(api_error)
+}
 
-    // Try to set up HTTPS if certificates are available
-    if let Ok(_) = setup_https_server(app.clone()).await {
-        info!("🔒 HTTPS server started successfully");
-    } else {
-        warn!("⚠️ HTTPS setup failed, falling back to HTTP");
-        setup_http_server(app).await?;
+/// Rename a tenant or set its quotas; omitted quotas revert to the node defaults
+#[cfg(feature = "native")]
+async fn update_tenant(
+    axum::extract::State(state): axum::extract::State<AppState>,
+    axum::Extension(auth): axum::Extension<AuthContext>,
+    axum::extract::Path(tenant_id): axum::extract::Path<String>,
+    axum::Json(request): axum::Json<TenantRequest>,
+) -> Result<axum::Json<Tenant>, ApiError> {
+    auth.require_permission("admin")?;
+    auth.require_unscoped()?;
+    tenant_registry(&state)?
+        .update(
+            &tenant_id,
+            request.name.as_deref(),
+            request.max_memories,
+            request.requests_per_minute,
+        )
+        .map(axum::Json)
+        .map_err(api_error)
+}
+
+/// Delete a tenant together with all of its data
+#[cfg(feature = "native")]
+async fn delete_tenant(
+    axum::extract::State(state): axum::extract::State<AppState>,
+    axum::Extension(auth): axum::Extension<AuthContext>,
+    axum::extract::Path(tenant_id): axum::extract::Path<String>,
+) -> Result<axum::http::StatusCode, ApiError> {
+    auth.require_permission("admin")?;
+    auth.require_unscoped()?;
+    warn!("Tenant deletion requested for {}", tenant_id);
+    tenant_registry(&state)?
+        .delete(&tenant_id)
+        .map(|_| axum::http::StatusCode::NO_CONTENT)
+        .map_err(api_error)
+}
+
+#[cfg(feature = "native")]
+async fn tenant_usage(
+    axum::extract::State(state): axum::extract::State<AppState>,
+    axum::Extension(auth): axum::Extension<AuthContext>,
+    axum::extract::Path(tenant_id): axum::extract::Path<String>,
+) -> Result<axum::Json<TenantUsage>, ApiError> {
+    auth.require_permission("admin")?;
+    auth.require_unscoped()?;
+    tenant_registry(&state)?
+        .usage(&tenant_id)
+        .map(axum::Json)
+        .map_err(api_error)
+}
+
+#[cfg(feature = "native")]
+async fn suspend_tenant(
+    axum::extract::State(state): axum::extract::State<AppState>,
+    axum::Extension(auth): axum::Extension<AuthContext>,
+    axum::extract::Path(tenant_id): axum::extract::Path<String>,
+) -> Result<axum::Json<Tenant>, ApiError> {
+    auth.require_permission("admin")?;
+    auth.require_unscoped()?;
+    tenant_registry(&state)?
+        .set_status(&tenant_id, TenantStatus::Suspended)
+        .map(axum::Json)
+        .map_err(api_error)
+}
+
+#[cfg(feature = "native")]
+async fn resume_tenant(
+    axum::extract::State(state): axum::extract::State<AppState>,
+    axum::Extension(auth): axum::Extension<AuthContext>,
+    axum::extract::Path(tenant_id): axum::extract::Path<String>,
+) -> Result<axum::Json<Tenant>, ApiError> {
+    auth.require_permission("admin")?;
+    auth.require_unscoped()?;
+    tenant_registry(&state)?
+        .set_status(&tenant_id, TenantStatus::Active)
+        .map(axum::Json)
+        .map_err(api_error)
+}
+
+/// One memory; its entity tag follows its content hash and co-signatures
+#[cfg(feature = "native")]
+async fn get_memory(
+    axum::extract::State(state): axum::extract::State<AppState>,
+    axum::Extension(auth): axum::Extension<AuthContext>,
+    headers: axum::http::HeaderMap,
+    axum::extract::Path(memory_id): axum::extract::Path<String>,
+) -> Result<axum::response::Response, ApiError> {
+    auth.require_permission("read")?;
+    let memory_id = MemoryId::parse(&memory_id).map_err(invalid_id)?;
+    require_visible_memory(&state, &auth, &memory_id)?;
+    let memory = state
+        .database
+        .get_signed_memory(&memory_id)
+        .map_err(api_error)?
+        .ok_or_else(|| api_error(OcmError::NotFound(format!("Memory {}", memory_id))))?;
+
+    let validators = Validators::new(
+        format!("\"{}.{}\"", memory.content_hash, memory.co_signatures.len()),
+        &memory.updated_on,
+    );
+    validators.respond(&headers, || Ok(memory))
+}
+
+#[cfg(feature = "native")]
+async fn list_individuals(
+    axum::extract::State(state): axum::extract::State<AppState>,
+    axum::Extension(auth): axum::Extension<AuthContext>,
+    headers: axum::http::HeaderMap,
+) -> Result<axum::response::Response, ApiError> {
+    auth.require_permission("read")?;
+    auth.require_unscoped()?;
+    let validators = collection_validators(&state, "individual", "", &auth)?;
+    validators.respond(&headers, || {
+        state.database.list_individuals().map_err(api_error)
+    })
+}
+
+/// One individual; its entity tag is a hash of the record
+#[cfg(feature = "native")]
+async fn get_individual(
+    axum::extract::State(state): axum::extract::State<AppState>,
+    axum::Extension(auth): axum::Extension<AuthContext>,
+    headers: axum::http::HeaderMap,
+    axum::extract::Path(id): axum::extract::Path<String>,
+) -> Result<axum::response::Response, ApiError> {
+    use sha2::{Digest, Sha256};
+
+    auth.require_permission("read")?;
+    auth.require_unscoped()?;
+    let individual = state
+        .database
+        .get_individual(&id)
+        .map_err(api_error)?
+        .ok_or_else(|| api_error(OcmError::NotFound(format!("Individual {}", id))))?;
+
+    let record =
+        serde_json::to_vec(&individual).map_err(|e| api_error(OcmError::Serialization(e)))?;
+    let validators = Validators::new(
+        format!("\"{}\"", hex::encode(Sha256::digest(&record))),
+        &individual.updated_on,
+    );
+    validators.respond(&headers, || Ok(individual))
+}
+
+/// POST memories:batch or individuals:batch. Every item is validated, then all are
+/// applied in one transaction or, if any is invalid, none; the report gives each
+/// item's outcome. Memories must be signed by their authors before submission
+#[cfg(feature = "native")]
+async fn batch_write(
+    axum::extract::State(state): axum::extract::State<AppState>,
+    axum::Extension(auth): axum::Extension<AuthContext>,
+    axum::extract::Path(batch): axum::extract::Path<String>,
+    axum::Json(request): axum::Json<BatchRequest>,
+) -> Result<axum::Json<BatchReport>, ApiError> {
+    auth.require_permission("write")?;
+    let report = match batch.as_str() {
+        "memories:batch" => write_memories(
+            &state.database,
+            &state.memory_types,
+            &auth.tenant_scope(),
+            request.items,
+            state.max_batch_items,
+        ),
+        "individuals:batch" => {
+            auth.require_unscoped()?;
+            write_individuals(&state.database, request.items, state.max_batch_items)
+        }
+        _ => Err(OcmError::NotFound(format!("Route /{}", batch))),
+    };
+    report.map(axum::Json).map_err(api_error)
+}
+
+/// The host API's job runner. Jobs work on the whole database, so organization-scoped
+/// credentials cannot use them
+#[cfg(feature = "native")]
+fn job_runner<'a>(state: &'a AppState, auth: &AuthContext) -> Result<&'a JobRunner, ApiError> {
+    auth.require_unscoped()?;
+    state.jobs.as_deref().ok_or_else(|| {
+        create_error_response(
+            axum::http::StatusCode::NOT_FOUND,
+            "JOBS_UNAVAILABLE",
+            "Jobs run on the host API only",
+        )
+    })
+}
+
+#[cfg(feature = "native")]
+async fn list_jobs(
+    axum::extract::State(state): axum::extract::State<AppState>,
+    axum::Extension(auth): axum::Extension<AuthContext>,
+) -> Result<axum::Json<Vec<Job>>, ApiError> {
+    auth.require_permission("read")?;
+    job_runner(&state, &auth)?
+        .list()
+        .map(axum::Json)
+        .map_err(api_error)
+}
+
+/// Queue a long-running job; poll GET /jobs/{id} for its progress and result
+#[cfg(feature = "native")]
+async fn start_job(
+    axum::extract::State(state): axum::extract::State<AppState>,
+    axum::Extension(auth): axum::Extension<AuthContext>,
+    axum::Json(spec): axum::Json<JobSpec>,
+) -> Result<(axum::http::StatusCode, axum::Json<Job>), ApiError> {
+    auth.require_permission(spec.permission())?;
+    let job = job_runner(&state, &auth)?
+        .submit(spec, auth.user_did.as_deref())
+        .map_err(api_error)?;
+    Ok((axum::http::StatusCode::ACCEPTED, axum::Json(job)))
+}
+
+#[cfg(feature = "native")]
+async fn get_job(
+    axum::extract::State(state): axum::extract::State<AppState>,
+    axum::Extension(auth): axum::Extension<AuthContext>,
+    axum::extract::Path(id): axum::extract::Path<String>,
+) -> Result<axum::Json<Job>, ApiError> {
+    auth.require_permission("read")?;
+    job_runner(&state, &auth)?
+        .get(&id)
+        .map(axum::Json)
+        .map_err(api_error)
+}
+
+#[cfg(feature = "native")]
+async fn cancel_job(
+    axum::extract::State(state): axum::extract::State<AppState>,
+    axum::Extension(auth): axum::Extension<AuthContext>,
+    axum::extract::Path(id): axum::extract::Path<String>,
+) -> Result<axum::Json<Job>, ApiError> {
+    auth.require_permission("write")?;
+    job_runner(&state, &auth)?
+        .cancel(&id)
+        .map(axum::Json)
+        .map_err(api_error)
+}
+
+/// Download the individual or location table as CSV
+#[cfg(feature = "native")]
+async fn csv_export(
+    axum::extract::State(state): axum::extract::State<AppState>,
+    axum::Extension(auth): axum::Extension<AuthContext>,
+    axum::extract::Path(table): axum::extract::Path<String>,
+) -> Result<impl axum::response::IntoResponse, ApiError> {
+    auth.require_permission("read")?;
+    auth.require_unscoped()?;
+    let table: CsvTable = table.parse().map_err(api_error)?;
+
+    let mut body = Vec::new();
+    export_csv(&state.database, table, &mut body).map_err(api_error)?;
+
+    let filename = format!("attachment; filename=\"{:?}.csv\"", table).to_lowercase();
+    Ok((
+        [
+            (axum::http::header::CONTENT_TYPE, "text/csv".to_string()),
+            (axum::http::header::CONTENT_DISPOSITION, filename),
+        ],
+        body,
+    ))
+}
+
+/// Import a CSV body into the individual or location table; with dry_run=true
+/// only the per-row validation report is returned
+#[cfg(feature = "native")]
+async fn csv_import(
+    axum::extract::State(state): axum::extract::State<AppState>,
+    axum::Extension(auth): axum::Extension<AuthContext>,
+    axum::extract::Path(table): axum::extract::Path<String>,
+    axum::extract::Query(query): axum::extract::Query<CsvImportQuery>,
+    body: String,
+) -> Result<axum::Json<ImportReport>, ApiError> {
+    auth.require_permission("write")?;
+    auth.require_unscoped()?;
+    let table: CsvTable = table.parse().map_err(api_error)?;
+    let mapping = match &query.map {
+        Some(spec) => ColumnMapping::parse(spec).map_err(api_error)?,
+        None => ColumnMapping::default(),
+    };
+
+    import_csv(
+        &state.database,
+        table,
+        body.as_bytes(),
+        &mapping,
+        query.dry_run,
+    )
+    .map(axum::Json)
+    .map_err(api_error)
+}
+
+/// List automation rules with the outcome of their latest run
+#[cfg(feature = "rules")]
+async fn list_rules(
+    axum::extract::State(state): axum::extract::State<AppState>,
+    axum::Extension(auth): axum::Extension<AuthContext>,
+) -> Result<axum::Json<Vec<AutomationRule>>, ApiError> {
+    auth.require_permission("admin")?;
+    auth.require_unscoped()?;
+    state
+        .database
+        .list_automation_rules()
+        .map(axum::Json)
+        .map_err(api_error)
+}
+
+/// Create or replace a rule; scripts that do not compile are rejected
+#[cfg(feature = "rules")]
+async fn save_rule(
+    axum::extract::State(state): axum::extract::State<AppState>,
+    axum::Extension(auth): axum::Extension<AuthContext>,
+    axum::extract::Path(name): axum::extract::Path<String>,
+    axum::Json(request): axum::Json<RuleRequest>,
+) -> Result<axum::Json<AutomationRule>, ApiError> {
+    auth.require_permission("admin")?;
+    auth.require_unscoped()?;
+    let valid_name = !name.is_empty()
+        && name.len() <= 64
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+    if !valid_name {
+        return Err(api_error(OcmError::Validation(
+            "Rule names must be up to 64 letters, digits, '-' or '_'".to_string(),
+        )));
     }
+    if request.event_type.is_empty() {
+        return Err(api_error(OcmError::Validation(
+            "A triggering event_type is required".to_string(),
+        )));
+    }
+    check_script(&request.script).map_err(api_error)?;
 
-    Ok(())
+    let rule = AutomationRule {
+        name,
+        description: request.description,
+        event_type: request.event_type,
+        memory_type: request.memory_type,
+        script: request.script,
+        enabled: request.enabled,
+        updated_at: chrono::Utc::now().to_rfc3339(),
+        last_run_at: None,
+        last_error: None,
+    };
+    state
+        .database
+        .upsert_automation_rule(&rule)
+        .map_err(api_error)?;
+    info!("Automation rule {} saved", rule.name);
+    Ok(axum::Json(rule))
 }
 
+#[cfg(feature = "rules")]
+async fn delete_rule(
+    axum::extract::State(state): axum::extract::State<AppState>,
+    axum::Extension(auth): axum::Extension<AuthContext>,
+    axum::extract::Path(name): axum::extract::Path<String>,
+) -> Result<axum::http::StatusCode, ApiError> {
+    auth.require_permission("admin")?;
+    auth.require_unscoped()?;
+    match state.database.delete_automation_rule(&name) {
+        Ok(true) => {
+            info!("Automation rule {} deleted", name);
+            Ok(axum::http::StatusCode::NO_CONTENT)
+        }
+        Ok(false) => Err(api_error(OcmError::NotFound(format!("Rule {}", name)))),
+        Err(e) => Err(api_error(e)),
+    }
+}
+
+/// Version, protocol versions, identity, uptime and features of this node, signed by
+/// the node identity
 #[cfg(feature = "native")]
-async fn create_app() -> Router {
-    // Create rate limiter store
-    let rate_limiter_store = create_rate_limiter_store();
+async fn node_info(
+    axum::extract::State(state): axum::extract::State<NodeState>,
+) -> Result<axum::Json<NodeInfo>, ApiError> {
+    NodeInfo::signed(
+        &state.identity,
+        state.started_at,
+        state.features.as_ref().clone(),
+    )
+    .map(axum::Json)
+    .map_err(|e| api_error(OcmError::Cryptography(e.to_string())))
+}
 
-    // Build API routes with appropriate rate limiting and security
-    let api_routes = Router::new()
-        .route("/status", get(api_status))
-        .route("/security", get(security_status))
-        .layer(
-            ServiceBuilder::new()
-                .layer(middleware::from_fn(create_api_read_rate_limiter(
-                    rate_limiter_store.clone(),
-                )))
-                .layer(middleware::from_fn(optional_auth_middleware))
-                .layer(middleware::from_fn(request_validation_middleware)),
+/// Optional capabilities this node was built with and has switched on
+#[cfg(feature = "native")]
+fn node_features(config: &OcmConfig) -> Vec<String> {
+    let features = [
+        ("push", cfg!(feature = "push") && config.push.enabled),
+        ("rules", cfg!(feature = "rules") && config.rules.enabled),
+        (
+            "plugins",
+            cfg!(feature = "plugins") && config.plugins.enabled,
+        ),
+        ("export", cfg!(feature = "export")),
+        ("tenancy", config.tenancy.enabled),
+        ("public_lookup", config.web.public_lookup.enabled),
+        ("compression", config.database.compression.enabled),
+        ("offline_bundles", config.plc.offline_bundles.attach),
+    ];
+    features
+        .into_iter()
+        .filter(|(_, enabled)| *enabled)
+        .map(|(name, _)| name.to_string())
+        .collect()
+}
+
+/// A memory of a publicly listed type, by content hash, after checking that its
+/// content still hashes to it. Anything else is reported as absent
+#[cfg(feature = "native")]
+async fn public_memory(
+    axum::extract::State(state): axum::extract::State<PublicState>,
+    axum::extract::Path(content_hash): axum::extract::Path<String>,
+) -> Result<axum::Json<PublicMemory>, ApiError> {
+    let not_found = || api_error(OcmError::NotFound(format!("Memory {}", content_hash)));
+    if content_hash.len() != 64 || !content_hash.chars().all(|c| c.is_ascii_hexdigit()) {
+        return Err(not_found());
+    }
+
+    let memory = state
+        .database
+        .get_signed_memory_by_content_hash(&content_hash.to_ascii_lowercase())
+        .map_err(api_error)?
+        .filter(|memory| state.config.memory_types.contains(&memory.memory_type))
+        .ok_or_else(not_found)?;
+    if !memory.verify_hash() {
+        warn!(
+            "Stored memory {} does not match its content hash",
+            memory.id
         );
+        return Err(not_found());
+    }
 
-    // Health check route with higher rate limits
-    let health_routes =
-        Router::new()
-            .route("/health", get(health_check))
-            .layer(middleware::from_fn(create_health_rate_limiter(
-                rate_limiter_store.clone(),
-            )));
+    let plc_document = if memory.did.starts_with("did:plc:") {
+        let resolution = state.directory.lock().await.resolve_did(&memory.did).await;
+        if let DidResolution::Unreachable(reason) = &resolution {
+            warn!(
+                "Cannot resolve {} for public lookup: {}",
+                Redacted::did(&memory.did),
+                reason
+            );
+        }
+        resolution.into_document()
+    } else {
+        None
+    };
 
-    // Static file serving (no rate limiting for now to avoid complexity)
-    let static_routes = Router::new().nest_service("/", ServeDir::new("ocm-wasm"));
+    Ok(axum::Json(PublicMemory {
+        memory,
+        plc_document,
+    }))
+}
 
-    // Combine all routes with global security middleware
-    Router::new()
-        .nest("/api/v1", api_routes)
-        .merge(health_routes)
-        .merge(static_routes)
-        .layer(
-            ServiceBuilder::new()
-                .layer(TraceLayer::new_for_http())
-                .layer(middleware::from_fn(security_headers_middleware))
-                .layer(middleware::from_fn(security_logging_middleware))
-                .layer(middleware::from_fn(request_size_limit_middleware))
-                .layer(CorsLayer::permissive()), // Will be replaced by secure_cors_middleware in production
+/// The push gateway and the DID of the signed-in user it serves
+#[cfg(feature = "push")]
+fn push_gateway<'a>(
+    state: &'a AppState,
+    auth: &'a AuthContext,
+) -> Result<(&'a PushGateway, Did), ApiError> {
+    let Some(gateway) = state.push.as_deref() else {
+        return Err(api_error(OcmError::NotFound(
+            "Push notifications are not enabled".to_string(),
+        )));
+    };
+    let Some(did) = auth.user_did.as_deref() else {
+        return Err(create_error_response(
+            axum::http::StatusCode::UNAUTHORIZED,
+            "SESSION_REQUIRED",
+            "Push subscriptions belong to a signed-in user",
+        ));
+    };
+    Ok((gateway, Did::parse(did).map_err(invalid_id)?))
+}
+
+/// The key browsers subscribe with (`applicationServerKey`)
+#[cfg(feature = "push")]
+async fn vapid_public_key(
+    axum::extract::State(state): axum::extract::State<AppState>,
+) -> Result<axum::Json<serde_json::Value>, ApiError> {
+    match state.push.as_ref().and_then(|push| push.vapid_public_key()) {
+        Some(public_key) => Ok(axum::Json(serde_json::json!({ "public_key": public_key }))),
+        None => Err(api_error(OcmError::NotFound(
+            "Web Push is not enabled".to_string(),
+        ))),
+    }
+}
+
+#[cfg(feature = "push")]
+async fn list_push_subscriptions(
+    axum::extract::State(state): axum::extract::State<AppState>,
+    axum::Extension(auth): axum::Extension<AuthContext>,
+) -> Result<axum::Json<Vec<PushSubscription>>, ApiError> {
+    auth.require_permission("read")?;
+    let (push, did) = push_gateway(&state, &auth)?;
+    push.list(&did).map(axum::Json).map_err(api_error)
+}
+
+#[cfg(feature = "push")]
+async fn subscribe_push(
+    axum::extract::State(state): axum::extract::State<AppState>,
+    axum::Extension(auth): axum::Extension<AuthContext>,
+    axum::Json(request): axum::Json<PushSubscriptionRequest>,
+) -> Result<axum::Json<PushSubscription>, ApiError> {
+    auth.require_permission("read")?;
+    let (push, did) = push_gateway(&state, &auth)?;
+    let subscription = push
+        .subscribe(
+            &did,
+            request.kind,
+            &request.endpoint,
+            request.keys.map(|keys| (keys.p256dh, keys.auth)),
+            request.events,
         )
+        .map_err(api_error)?;
+    info!(
+        "Push subscription {} added for {}",
+        subscription.id,
+        Redacted::did(&did)
+    );
+    Ok(axum::Json(subscription))
+}
+
+/// Choose which events a subscription is pushed about
+#[cfg(feature = "push")]
+async fn set_push_events(
+    axum::extract::State(state): axum::extract::State<AppState>,
+    axum::Extension(auth): axum::Extension<AuthContext>,
+    axum::extract::Path(id): axum::extract::Path<String>,
+    axum::Json(request): axum::Json<PushEventsRequest>,
+) -> Result<axum::Json<PushSubscription>, ApiError> {
+    auth.require_permission("read")?;
+    let (push, did) = push_gateway(&state, &auth)?;
+    push.set_events(&did, &id, request.events)
+        .map(axum::Json)
+        .map_err(api_error)
+}
+
+#[cfg(feature = "push")]
+async fn unsubscribe_push(
+    axum::extract::State(state): axum::extract::State<AppState>,
+    axum::Extension(auth): axum::Extension<AuthContext>,
+    axum::extract::Path(id): axum::extract::Path<String>,
+) -> Result<axum::http::StatusCode, ApiError> {
+    auth.require_permission("read")?;
+    let (push, did) = push_gateway(&state, &auth)?;
+    push.unsubscribe(&did, &id).map_err(api_error)?;
+    Ok(axum::http::StatusCode::NO_CONTENT)
+}
+
+/// Treat memories outside the caller's organization as absent
+#[cfg(feature = "native")]
+fn require_visible_memory(
+    state: &AppState,
+    auth: &AuthContext,
+    memory_id: &MemoryId,
+) -> Result<(), ApiError> {
+    match state.database.get_signed_memory(memory_id) {
+        Ok(Some(memory)) if auth.tenant_scope().allows_memory(&memory) => Ok(()),
+        Ok(_) => Err(api_error(OcmError::NotFound(format!(
+            "Memory {}",
+            memory_id
+        )))),
+        Err(e) => Err(api_error(e)),
+    }
+}
+
+/// Which recipients have acknowledged a memory, and whether they read it
+#[cfg(feature = "native")]
+async fn delivery_status(
+    axum::extract::State(state): axum::extract::State<AppState>,
+    axum::Extension(auth): axum::Extension<AuthContext>,
+    axum::extract::Path(memory_id): axum::extract::Path<String>,
+) -> Result<axum::Json<DeliveryStatus>, ApiError> {
+    auth.require_permission("read")?;
+    let memory_id = MemoryId::parse(&memory_id).map_err(invalid_id)?;
+    require_visible_memory(&state, &auth, &memory_id)?;
+    state
+        .receipts
+        .get_delivery_status(&memory_id)
+        .map(axum::Json)
+        .map_err(api_error)
+}
+
+/// Send the author a read receipt, if read receipts are enabled
+#[cfg(feature = "native")]
+async fn mark_memory_read(
+    axum::extract::State(state): axum::extract::State<AppState>,
+    axum::Extension(auth): axum::Extension<AuthContext>,
+    axum::extract::Path(memory_id): axum::extract::Path<String>,
+) -> Result<axum::http::StatusCode, ApiError> {
+    auth.require_permission("write")?;
+    let memory_id = MemoryId::parse(&memory_id).map_err(invalid_id)?;
+    auth.require_unscoped()?;
+    state
+        .receipts
+        .mark_read(&memory_id)
+        .map(|_| axum::http::StatusCode::NO_CONTENT)
+        .map_err(api_error)
+}
+
+/// Tags in use, with how many memories carry each
+#[cfg(feature = "native")]
+async fn list_tags(
+    axum::extract::State(state): axum::extract::State<AppState>,
+    axum::Extension(auth): axum::Extension<AuthContext>,
+) -> Result<axum::Json<Vec<TagCount>>, ApiError> {
+    auth.require_permission("read")?;
+    auth.require_unscoped()?;
+    state.tags.tags().map(axum::Json).map_err(api_error)
+}
+
+#[cfg(feature = "native")]
+async fn tagged_memories(
+    axum::extract::State(state): axum::extract::State<AppState>,
+    axum::Extension(auth): axum::Extension<AuthContext>,
+    axum::extract::Path(tag): axum::extract::Path<String>,
+) -> Result<axum::Json<Vec<SignedMemory>>, ApiError> {
+    auth.require_permission("read")?;
+    state
+        .tags
+        .memories_tagged(&tag)
+        .map(|memories| axum::Json(auth.tenant_scope().filter_memories(memories)))
+        .map_err(api_error)
+}
+
+#[cfg(feature = "native")]
+async fn memory_tags(
+    axum::extract::State(state): axum::extract::State<AppState>,
+    axum::Extension(auth): axum::Extension<AuthContext>,
+    axum::extract::Path(memory_id): axum::extract::Path<String>,
+) -> Result<axum::Json<Vec<String>>, ApiError> {
+    auth.require_permission("read")?;
+    let memory_id = MemoryId::parse(&memory_id).map_err(invalid_id)?;
+    require_visible_memory(&state, &auth, &memory_id)?;
+    state
+        .tags
+        .tags_of(&memory_id)
+        .map(axum::Json)
+        .map_err(api_error)
+}
+
+/// Consolidate several of this node's memories into one. The originals are archived
+/// here and on peers once the returned merge record reaches them
+#[cfg(feature = "native")]
+async fn merge_memories(
+    axum::extract::State(state): axum::extract::State<AppState>,
+    axum::Extension(auth): axum::Extension<AuthContext>,
+    axum::Json(request): axum::Json<MergeRequest>,
+) -> Result<axum::Json<MemoryMerge>, ApiError> {
+    auth.require_permission("write")?;
+    let memory_ids = request
+        .memory_ids
+        .iter()
+        .map(|id| MemoryId::parse(id))
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(invalid_id)?;
+    let choices = request
+        .choices
+        .iter()
+        .map(|(field, id)| Ok((field.clone(), MemoryId::parse(id)?)))
+        .collect::<Result<HashMap<_, _>, InvalidId>>()
+        .map_err(invalid_id)?;
+    for memory_id in &memory_ids {
+        require_visible_memory(&state, &auth, memory_id)?;
+    }
+    state
+        .merges
+        .merge(&memory_ids, &choices)
+        .map(axum::Json)
+        .map_err(api_error)
+}
+
+/// What changed between two memories, such as a merged memory and one it replaced
+#[cfg(feature = "native")]
+async fn diff_memories(
+    axum::extract::State(state): axum::extract::State<AppState>,
+    axum::Extension(auth): axum::Extension<AuthContext>,
+    axum::extract::Path((memory_id, other_id)): axum::extract::Path<(String, String)>,
+) -> Result<axum::Json<MemoryDiff>, ApiError> {
+    auth.require_permission("read")?;
+    let memory_id = MemoryId::parse(&memory_id).map_err(invalid_id)?;
+    let other_id = MemoryId::parse(&other_id).map_err(invalid_id)?;
+    require_visible_version(&state, &auth, &memory_id)?;
+    require_visible_version(&state, &auth, &other_id)?;
+    ocm_core::sync::patch::diff_memories(&state.database, &memory_id, &other_id)
+        .map(axum::Json)
+        .map_err(api_error)
+}
+
+/// Like require_visible_memory, but archived versions count as well
+#[cfg(feature = "native")]
+fn require_visible_version(
+    state: &AppState,
+    auth: &AuthContext,
+    memory_id: &MemoryId,
+) -> Result<(), ApiError> {
+    let memory = match state.database.get_signed_memory(memory_id) {
+        Ok(None) => state.database.get_archived_signed_memory(memory_id),
+        held => held,
+    };
+    match memory {
+        Ok(Some(memory)) if auth.tenant_scope().allows_memory(&memory) => Ok(()),
+        Ok(_) => Err(api_error(OcmError::NotFound(format!(
+            "Memory {}",
+            memory_id
+        )))),
+        Err(e) => Err(api_error(e)),
+    }
+}
+
+/// Edit a memory with a JSON Patch; the change is made as CRDT operations signed by
+/// the node, and the re-signed memory is queued for peers
+#[cfg(feature = "native")]
+async fn patch_memory(
+    axum::extract::State(state): axum::extract::State<AppState>,
+    axum::Extension(auth): axum::Extension<AuthContext>,
+    axum::extract::Path(memory_id): axum::extract::Path<String>,
+    axum::Json(patch): axum::Json<Vec<PatchOperation>>,
+) -> Result<axum::Json<PatchedMemory>, ApiError> {
+    auth.require_permission("write")?;
+    let memory_id = MemoryId::parse(&memory_id).map_err(invalid_id)?;
+    require_visible_memory(&state, &auth, &memory_id)?;
+    ocm_core::sync::patch::patch_memory(
+        &state.database,
+        &state.memory_types,
+        &state.identity,
+        &memory_id,
+        &patch,
+    )
+    .map(axum::Json)
+    .map_err(api_error)
+}
+
+/// Tag a memory; the signed tag operation is returned and broadcast to peers
+#[cfg(feature = "native")]
+async fn tag_memory(
+    axum::extract::State(state): axum::extract::State<AppState>,
+    axum::Extension(auth): axum::Extension<AuthContext>,
+    axum::extract::Path(memory_id): axum::extract::Path<String>,
+    axum::Json(request): axum::Json<TagRequest>,
+) -> Result<axum::Json<SignedMemory>, ApiError> {
+    auth.require_permission("write")?;
+    let memory_id = MemoryId::parse(&memory_id).map_err(invalid_id)?;
+    require_visible_memory(&state, &auth, &memory_id)?;
+    state
+        .tags
+        .tag(&memory_id, &request.tag)
+        .map(axum::Json)
+        .map_err(api_error)
+}
+
+#[cfg(feature = "native")]
+async fn untag_memory(
+    axum::extract::State(state): axum::extract::State<AppState>,
+    axum::Extension(auth): axum::Extension<AuthContext>,
+    axum::extract::Path((memory_id, tag)): axum::extract::Path<(String, String)>,
+) -> Result<axum::Json<SignedMemory>, ApiError> {
+    auth.require_permission("write")?;
+    let memory_id = MemoryId::parse(&memory_id).map_err(invalid_id)?;
+    require_visible_memory(&state, &auth, &memory_id)?;
+    state
+        .tags
+        .untag(&memory_id, &tag)
+        .map(axum::Json)
+        .map_err(api_error)
+}
+
+/// Annotations on a memory as threads, each checked against the target's current hash
+#[cfg(feature = "native")]
+async fn memory_annotations(
+    axum::extract::State(state): axum::extract::State<AppState>,
+    axum::Extension(auth): axum::Extension<AuthContext>,
+    axum::extract::Path(memory_id): axum::extract::Path<String>,
+) -> Result<axum::Json<Vec<AnnotationThread>>, ApiError> {
+    auth.require_permission("read")?;
+    let memory_id = MemoryId::parse(&memory_id).map_err(invalid_id)?;
+    require_visible_memory(&state, &auth, &memory_id)?;
+    state
+        .annotations
+        .list_annotations(&memory_id)
+        .map(axum::Json)
+        .map_err(api_error)
+}
+
+/// Attach a note or correction; post to an annotation's ID to reply to it
+#[cfg(feature = "native")]
+async fn annotate_memory(
+    axum::extract::State(state): axum::extract::State<AppState>,
+    axum::Extension(auth): axum::Extension<AuthContext>,
+    axum::extract::Path(memory_id): axum::extract::Path<String>,
+    axum::Json(request): axum::Json<AnnotationRequest>,
+) -> Result<axum::Json<SignedMemory>, ApiError> {
+    auth.require_permission("write")?;
+    let memory_id = MemoryId::parse(&memory_id).map_err(invalid_id)?;
+    require_visible_memory(&state, &auth, &memory_id)?;
+    state
+        .annotations
+        .annotate(&memory_id, request.kind, &request.body)
+        .map(axum::Json)
+        .map_err(api_error)
+}
+
+/// Parse the `filter` query parameter of memory queries
+#[cfg(feature = "native")]
+fn parse_filter(filter: Option<&str>) -> Result<Option<Filter>, ApiError> {
+    filter
+        .filter(|filter| !filter.trim().is_empty())
+        .map(Filter::parse)
+        .transpose()
+        .map_err(|e| {
+            create_error_response(axum::http::StatusCode::BAD_REQUEST, "INVALID_FILTER", &e)
+        })
+}
+
+/// Newest-first memories by a DID, a group or everyone; follow `next_cursor` for older ones
+#[cfg(feature = "native")]
+async fn memory_feed(
+    axum::extract::State(state): axum::extract::State<AppState>,
+    axum::Extension(auth): axum::Extension<AuthContext>,
+    headers: axum::http::HeaderMap,
+    axum::extract::RawQuery(raw_query): axum::extract::RawQuery,
+    axum::extract::Query(query): axum::extract::Query<FeedRequest>,
+) -> Result<axum::response::Response, ApiError> {
+    auth.require_permission("read")?;
+    let source = match (query.did, query.group) {
+        (Some(_), Some(_)) => {
+            return Err(create_error_response(
+                axum::http::StatusCode::BAD_REQUEST,
+                "INVALID_FEED_SOURCE",
+                "Give either did or group, not both",
+            ))
+        }
+        (Some(did), None) => {
+            parse_subject_did(&did)?;
+            FeedSource::Did(did)
+        }
+        (None, Some(group)) => {
+            parse_subject_did(&group)?;
+            FeedSource::Group(group)
+        }
+        (None, None) => FeedSource::All,
+    };
+    let memory_types: Vec<String> = query
+        .types
+        .as_deref()
+        .unwrap_or("")
+        .split(',')
+        .map(str::trim)
+        .filter(|memory_type| !memory_type.is_empty())
+        .map(str::to_string)
+        .collect();
+    let filter = parse_filter(query.filter.as_deref())?;
+
+    // Answered from the version counter, before the feed query runs
+    let validators = collection_validators(
+        &state,
+        "signed_memory",
+        raw_query.as_deref().unwrap_or(""),
+        &auth,
+    )?;
+    validators.respond(&headers, || {
+        state
+            .database
+            .get_feed(
+                &auth.tenant_scope(),
+                &source,
+                query.cursor.as_deref(),
+                query.limit.unwrap_or(DEFAULT_FEED_LIMIT),
+                &memory_types,
+                filter.as_ref(),
+            )
+            .map_err(api_error)
+    })
+}
+
+/// Stream every memory by a DID, or all memories, as newline-delimited JSON, newest
+/// first. Pages are read from the database only as the client consumes the body, so
+/// the whole set is never held in memory
+#[cfg(feature = "native")]
+async fn export_memories(
+    axum::extract::State(state): axum::extract::State<AppState>,
+    axum::Extension(auth): axum::Extension<AuthContext>,
+    axum::extract::Query(query): axum::extract::Query<MemoryExportQuery>,
+) -> Result<axum::response::Response, ApiError> {
+    use axum::response::IntoResponse;
+
+    auth.require_permission("read")?;
+    if query
+        .format
+        .as_deref()
+        .is_some_and(|format| format != "ndjson")
+    {
+        return Err(create_error_response(
+            axum::http::StatusCode::BAD_REQUEST,
+            "UNSUPPORTED_FORMAT",
+            "Memories are exported as ndjson",
+        ));
+    }
+    let source = match query.did {
+        Some(did) => {
+            parse_subject_did(&did)?;
+            FeedSource::Did(did)
+        }
+        None => FeedSource::All,
+    };
+    let memory_types: Vec<String> = query
+        .types
+        .as_deref()
+        .unwrap_or("")
+        .split(',')
+        .map(str::trim)
+        .filter(|memory_type| !memory_type.is_empty())
+        .map(str::to_string)
+        .collect();
+    let filter = parse_filter(query.filter.as_deref())?;
+    let scope = auth.tenant_scope();
+
+    // None once the last page has been sent; Some(None) before the first
+    let stream = futures_util::stream::unfold(Some(None), move |cursor: Option<Option<String>>| {
+        let database = state.database.clone();
+        let scope = scope.clone();
+        let source = source.clone();
+        let memory_types = memory_types.clone();
+        let filter = filter.clone();
+        async move {
+            let cursor = cursor?;
+            match database.get_feed(
+                &scope,
+                &source,
+                cursor.as_deref(),
+                MAX_FEED_LIMIT,
+                &memory_types,
+                filter.as_ref(),
+            ) {
+                Ok(page) => {
+                    let mut chunk = Vec::new();
+                    for memory in &page.memories {
+                        if let Err(e) = serde_json::to_writer(&mut chunk, memory) {
+                            return Some((Err(std::io::Error::other(e)), None));
+                        }
+                        chunk.push(b'\n');
+                    }
+                    Some((Ok(chunk), page.next_cursor.map(Some)))
+                }
+                Err(e) => {
+                    // Headers are already sent, so the client sees a truncated body
+                    warn!("Memory export stopped: {}", e);
+                    Some((Err(std::io::Error::other(e.to_string())), None))
+                }
+            }
+        }
+    });
+
+    Ok((
+        [(axum::http::header::CONTENT_TYPE, "application/x-ndjson")],
+        axum::body::Body::from_stream(stream),
+    )
+        .into_response())
+}
+
+/// Stream memory and sync activity as Server-Sent Events, resuming after Last-Event-ID
+#[cfg(feature = "native")]
+async fn activity_events(
+    axum::extract::State(state): axum::extract::State<AppState>,
+    axum::Extension(auth): axum::Extension<AuthContext>,
+    headers: axum::http::HeaderMap,
+    axum::extract::Query(query): axum::extract::Query<EventsQuery>,
+) -> Result<
+    axum::response::sse::Sse<
+        impl futures_util::Stream<Item = Result<axum::response::sse::Event, axum::Error>>,
+    >,
+    ApiError,
+> {
+    use axum::response::sse::{Event, KeepAlive, Sse};
+
+    let last_event_id = match headers.get("last-event-id") {
+        Some(value) => value
+            .to_str()
+            .ok()
+            .and_then(|v| v.trim().parse::<i64>().ok())
+            .ok_or_else(|| {
+                create_error_response(
+                    axum::http::StatusCode::BAD_REQUEST,
+                    "INVALID_LAST_EVENT_ID",
+                    "Last-Event-ID must be a numeric event id",
+                )
+            })?,
+        None => query.last_event_id.unwrap_or(0),
+    };
+    let mut did = query.did.as_deref().map(parse_subject_did).transpose()?;
+    // Organization-scoped callers only follow their organization's activity
+    if let Some(organization_did) = &auth.organization_did {
+        auth.require_organization(did.as_deref().unwrap_or(organization_did))?;
+        did = Some(parse_subject_did(organization_did)?);
+    }
+
+    let cursor = (last_event_id, VecDeque::<ActivityEvent>::new());
+    let stream = futures_util::stream::unfold(cursor, move |(mut last_event_id, mut pending)| {
+        let database = state.database.clone();
+        let did = did.clone();
+        let memory_type = query.memory_type.clone();
+        async move {
+            loop {
+                if let Some(event) = pending.pop_front() {
+                    last_event_id = event.event_id;
+                    let sse_event = Event::default()
+                        .id(event.event_id.to_string())
+                        .event(event.event_type.clone())
+                        .json_data(&event);
+                    return Some((sse_event, (last_event_id, pending)));
+                }
+
+                match database.list_activity_events_after(
+                    last_event_id,
+                    did.as_ref(),
+                    memory_type.as_deref(),
+                    EVENT_BATCH_SIZE,
+                ) {
+                    Ok(events) => pending.extend(events),
+                    Err(e) => warn!("Failed to read activity events: {}", e),
+                }
+                if pending.is_empty() {
+                    tokio::time::sleep(EVENT_POLL_INTERVAL).await;
+                }
+            }
+        }
+    });
+
+    Ok(Sse::new(stream).keep_alive(KeepAlive::default()))
 }
 
 #[cfg(not(feature = "native"))]
@@ -123,7 +3109,7 @@ async fn setup_https_server(app: Router) -> Result<(), Box<dyn std::error::Error
 
     // Start HTTPS server using axum-server with TLS
     axum_server::bind_rustls(addr, config)
-        .serve(app.into_make_service())
+        .serve(app.into_make_service_with_connect_info::<SocketAddr>())
         .await?;
 
     Ok(())
@@ -141,16 +3127,74 @@ async fn setup_http_server(app: Router) -> Result<(), Box<dyn std::error::Error>
     warn!("🌐 HTTP server listening on {} (DEVELOPMENT ONLY)", addr);
     warn!("⚠️  Use HTTPS in production!");
     info!("🔗 Visit: http://127.0.0.1:8000");
-    info!("🔒 Run with 'cargo run --bin secure-web-server' for enhanced security");
+    info!("🔒 Add certs/cert.pem and certs/key.pem and drop --dev to serve HTTPS");
 
-    axum::serve(listener, app).await?;
+    axum::serve(
+        listener,
+        app.into_make_service_with_connect_info::<SocketAddr>(),
+    )
+    .await?;
     Ok(())
 }
 
+#[cfg(not(feature = "native"))]
 async fn health_check() -> &'static str {
     "OK"
 }
 
+/// 503 when any recently reported task has crashed too often to be restarted. Tasks
+/// of processes that stopped reporting a minute ago or more are left out
+#[cfg(feature = "native")]
+async fn health_report(
+    axum::extract::State(state): axum::extract::State<HealthState>,
+) -> (axum::http::StatusCode, axum::Json<HealthReport>) {
+    let cutoff = chrono::Utc::now() - chrono::Duration::seconds(HEALTH_REPORT_MAX_AGE_SECS);
+    let tasks: Vec<TaskHealth> = match state.database.list_task_health() {
+        Ok(tasks) => tasks
+            .into_iter()
+            .filter(|task| {
+                chrono::DateTime::parse_from_rfc3339(&task.reported_at)
+                    .map(|reported_at| reported_at >= cutoff)
+                    .unwrap_or(false)
+            })
+            .collect(),
+        Err(e) => {
+            warn!("Failed to read task health: {}", e);
+            return (
+                axum::http::StatusCode::SERVICE_UNAVAILABLE,
+                axum::Json(HealthReport {
+                    status: "degraded",
+                    tasks: Vec::new(),
+                    panics_last_hour: 0,
+                }),
+            );
+        }
+    };
+
+    let hour_ago = (chrono::Utc::now() - chrono::Duration::hours(1)).to_rfc3339();
+    let panics_last_hour = state
+        .database
+        .count_activity_events_since(PANIC_EVENT, &hour_ago)
+        .unwrap_or_else(|e| {
+            warn!("Failed to count panics: {}", e);
+            0
+        });
+
+    let healthy = tasks.iter().all(|task| task.state != TaskState::Failed);
+    (
+        if healthy {
+            axum::http::StatusCode::OK
+        } else {
+            axum::http::StatusCode::SERVICE_UNAVAILABLE
+        },
+        axum::Json(HealthReport {
+            status: if healthy { "ok" } else { "degraded" },
+            tasks,
+            panics_last_hour,
+        }),
+    )
+}
+
 async fn api_status() -> axum::Json<serde_json::Value> {
     axum::Json(serde_json::json!({
         "status": "healthy",
@@ -172,8 +3216,15 @@ async fn api_status() -> axum::Json<serde_json::Value> {
         ],
         "endpoints": {
             "health": "/health",
+            "node": "/api/v1/node",
             "status": "/api/v1/status",
-            "security": "/api/v1/security"
+            "security": "/api/v1/security",
+            "transparency_tree_head": "/api/v1/transparency/tree-head",
+            "transparency_inclusion_proof": "/api/v1/transparency/proof/inclusion",
+            "transparency_consistency_proof": "/api/v1/transparency/proof/consistency",
+            "data_subject_export": "/api/v1/data-subject/export",
+            "data_subject_erase": "/api/v1/data-subject/erase",
+            "events": "/api/v1/events"
         },
         "timestamp": chrono::Utc::now().to_rfc3339()
     }))
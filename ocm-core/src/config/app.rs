@@ -1,7 +1,15 @@
 use crate::core::error::{OcmError, Result};
+use crate::core::models::PushEvent;
+use crate::identity::plc::PlcFailurePolicy;
+use ocm_protocol::fragment::FrameLimits;
+use ocm_protocol::ingest::IngestPolicy;
+pub use ocm_protocol::message::NodeRole;
+use ocm_protocol::sync::PartialSyncPolicy;
 use serde::{Deserialize, Serialize};
 use std::path::PathBuf;
 
+pub use ocm_protocol::sync::SyncPriority;
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct OcmConfig {
     pub server: ServerConfig,
@@ -9,6 +17,38 @@ pub struct OcmConfig {
     pub networking: NetworkingConfig,
     pub plc: PlcConfig,
     pub logging: LoggingConfig,
+    #[serde(default)]
+    pub node: NodeConfig,
+    #[serde(default)]
+    pub retention: RetentionConfig,
+    #[serde(default)]
+    pub federation: FederationConfig,
+    #[serde(default)]
+    pub web: WebConfig,
+    #[serde(default)]
+    pub sync: SyncPolicyConfig,
+    #[serde(default)]
+    pub derived: DerivedMemoriesConfig,
+    #[serde(default)]
+    pub plugins: PluginsConfig,
+    #[serde(default)]
+    pub rules: RulesConfig,
+    #[serde(default)]
+    pub tenancy: TenancyConfig,
+    #[serde(default)]
+    pub handles: HandlesConfig,
+    #[serde(default)]
+    pub linkage: LinkageConfig,
+    #[serde(default)]
+    pub receipts: ReceiptsConfig,
+    #[serde(default)]
+    pub push: PushConfig,
+    #[serde(default)]
+    pub jobs: JobsConfig,
+    #[serde(default)]
+    pub supervisor: SupervisorConfig,
+    #[serde(default)]
+    pub telemetry: TelemetryConfig,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -24,6 +64,73 @@ pub struct DatabaseConfig {
     pub path: PathBuf,
     pub connection_pool_size: u32,
     pub backup_interval_hours: Option<u64>,
+    #[serde(default)]
+    pub compression: CompressionConfig,
+    #[serde(default)]
+    pub durability: DurabilityConfig,
+}
+
+/// SQLite `synchronous` levels, from fastest to safest against power loss
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SynchronousMode {
+    Off,
+    Normal,
+    Full,
+    Extra,
+}
+
+impl SynchronousMode {
+    pub fn as_sql(&self) -> &'static str {
+        match self {
+            SynchronousMode::Off => "OFF",
+            SynchronousMode::Normal => "NORMAL",
+            SynchronousMode::Full => "FULL",
+            SynchronousMode::Extra => "EXTRA",
+        }
+    }
+}
+
+/// How committed writes survive crashes and power loss
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DurabilityConfig {
+    pub wal: bool,                           // Write-ahead log journal
+    pub synchronous: SynchronousMode,        // Everything that could be fetched again
+    pub signed_synchronous: SynchronousMode, // Memories this node signs
+}
+
+impl Default for DurabilityConfig {
+    fn default() -> Self {
+        Self {
+            wal: true,
+            synchronous: SynchronousMode::Normal,
+            signed_synchronous: SynchronousMode::Full,
+        }
+    }
+}
+
+/// zstd compression of stored memory bodies, with a dictionary trained per memory type
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CompressionConfig {
+    pub enabled: bool,
+    pub level: i32,              // zstd level, 1 (fastest) to 19
+    pub min_samples: usize,      // Bodies of a type needed before its dictionary is trained
+    pub max_samples: usize,      // Bodies read to train a dictionary
+    pub dictionary_bytes: usize, // Size of each trained dictionary
+}
+
+impl Default for CompressionConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            level: 3,
+            min_samples: 100,
+            max_samples: 2000,
+            dictionary_bytes: 16 * 1024,
+        }
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -33,14 +140,249 @@ pub struct NetworkingConfig {
     pub connection_timeout_seconds: u64,
     pub discovery_interval_seconds: u64,
     pub seed_peers: Vec<String>,
+    #[serde(default)]
+    pub peer_groups: Vec<PeerGroupConfig>,
+    #[serde(default)]
+    pub bandwidth: BandwidthConfig,
+    #[serde(default)]
+    pub faults: FaultInjectionConfig,
+    #[serde(default)]
+    pub rendezvous: RendezvousConfig,
+    #[serde(default)]
+    pub dns_seeds: DnsSeedConfig, // Seed nodes published in DNS by federation domains
+    #[serde(default)]
+    pub presence: PresenceConfig,
+    #[serde(default)]
+    pub inbound_review: InboundReviewConfig,
+    #[serde(default)]
+    pub ingest: IngestPolicy, // Verify-before-store for everything peers send
+    #[serde(default)]
+    pub frames: FrameLimits, // Largest frame and largest fragmented message accepted from peers
+    #[serde(default)]
+    pub relay_memories: bool, // Pass memories from directly connected peers on to the others
+    #[serde(default)]
+    pub allow_unsigned_discovery: bool, // Accept LAN beacons and peer lists without a signature
+}
+
+/// P2P rate limits and the times sync is allowed to run, for metered connections
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct BandwidthConfig {
+    pub upload_bytes_per_second: Option<u64>, // None = unlimited
+    pub download_bytes_per_second: Option<u64>,
+    #[serde(default)]
+    pub sync_windows: Vec<SyncWindow>, // Empty = sync at any time
+    pub require_idle_seconds: Option<u64>, // Only start a sync after this long without P2P traffic
+}
+
+/// Randomly disturb outgoing P2P messages to check that sync still converges.
+/// Takes effect only in builds with the `chaos` feature; never enable in production
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct FaultInjectionConfig {
+    pub enabled: bool,
+    pub drop_rate: f64, // Probabilities in [0, 1], rolled independently per message
+    pub delay_rate: f64,
+    pub max_delay_ms: u64,
+    pub duplicate_rate: f64,
+    pub corrupt_rate: f64,
+    pub seed: Option<u64>, // Fixed seed for a repeatable fault sequence
+}
+
+/// Relay directory used to find peers across NATs when local broadcast finds none
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RendezvousConfig {
+    pub relay_url: Option<String>, // ws:// or wss:// relay; None disables rendezvous
+    pub token: Option<String>,     // Sent as `?token=` to relays that require one
+    pub advertise_endpoints: Vec<String>, // host:port peers should dial; empty = server host and P2P port
+    pub ttl_seconds: u64,                 // Re-registered at half this interval
+    pub lookup_dids: Vec<String>,         // DIDs to look up; empty asks for any registered node
+}
+
+impl Default for RendezvousConfig {
+    fn default() -> Self {
+        Self {
+            relay_url: None,
+            token: None,
+            advertise_endpoints: vec![],
+            ttl_seconds: 300,
+            lookup_dids: vec![],
+        }
+    }
+}
+
+/// Federation domains whose seed nodes a new node connects to at startup, looked up as
+/// TXT records at `_ocm-seeds.{domain}` and SRV records at `_ocm._tcp.{domain}`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DnsSeedConfig {
+    pub domains: Vec<String>,       // Empty disables DNS bootstrap
+    pub dns_over_https_url: String, // JSON DNS API used for the lookups
+    pub timeout_seconds: u64,
+}
+
+impl Default for DnsSeedConfig {
+    fn default() -> Self {
+        Self {
+            domains: vec![],
+            dns_over_https_url: "https://cloudflare-dns.com/dns-query".to_string(),
+            timeout_seconds: 5,
+        }
+    }
+}
+
+/// Presence gossiped to peers in trusted peer groups, so family devices can see
+/// which of them are online and up to date. Peers outside every group see nothing
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PresenceConfig {
+    pub enabled: bool,
+    pub groups: Vec<String>, // Peer groups to share presence with; empty = every group
+    pub share_status: bool,  // Whether we are syncing or idle
+    pub share_last_sync: bool, // When we last finished a sync
+    pub interval_seconds: u64, // How often presence is announced
+    pub offline_after_seconds: u64, // A peer silent this long is shown offline
+}
+
+impl Default for PresenceConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            groups: vec![],
+            share_status: true,
+            share_last_sync: true,
+            interval_seconds: 60,
+            offline_after_seconds: 180,
+        }
+    }
+}
+
+/// Hold memories from peers for an operator's approval instead of storing them.
+/// Memories from peers in an auto-approving group are stored as before
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct InboundReviewConfig {
+    pub enabled: bool,
+    pub auto_approve: Vec<AutoApprovalRule>,
+}
+
+/// Store memories from members of a peer group without review
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AutoApprovalRule {
+    pub group: String,
+    #[serde(default)]
+    pub memory_types: Option<Vec<String>>, // None approves every memory type
+}
+
+/// Local-time hours during which sync may start; a start after the end wraps past midnight
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SyncWindow {
+    pub start_hour: u32,
+    pub end_hour: u32, // Exclusive
+}
+
+/// Named trust circle; members are peer node IDs, DIDs or `contacts:{group}`.
+/// Peers outside every group keep receiving all memories
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PeerGroupConfig {
+    pub name: String,
+    #[serde(default)]
+    pub memory_types: Option<Vec<String>>, // None shares every memory type with the group
+    #[serde(default)]
+    pub tags: Option<Vec<String>>, // Only memories carrying one of these tags, if set
+    #[serde(default)]
+    pub members: Vec<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PlcConfig {
     pub directory_url: String,
     pub enable_network_calls: bool,
-    pub cache_ttl_hours: u64,
+    pub cache_ttl_hours: u64, // How long a resolved PLC document is used before it is fetched again
     pub handle: Option<String>,
+    #[serde(default)]
+    pub failure_policy: PlcFailurePolicy, // fail_open keeps using expired documents while the directory is down
+    #[serde(default)]
+    pub refresh: DidRefreshConfig,
+    #[serde(default)]
+    pub offline_bundles: OfflineBundleConfig,
+    #[serde(default)]
+    pub key: KeyConfig,
+}
+
+/// Where this node's identity key is kept
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct KeyConfig {
+    pub backend: KeyBackend,
+    pub keychain_service: String,
+    pub keychain_account: String,
+    pub pkcs11_module: Option<PathBuf>, // e.g. /usr/lib/libykcs11.so for a YubiKey
+    pub pkcs11_slot: usize,             // Index among the slots holding a token
+    pub pkcs11_key_label: String,       // CKA_LABEL of the Ed25519 key pair
+    pub pkcs11_pin_env: String,         // Environment variable holding the user PIN
+}
+
+impl Default for KeyConfig {
+    fn default() -> Self {
+        Self {
+            backend: KeyBackend::Memory,
+            keychain_service: "ocm".to_string(),
+            keychain_account: "identity".to_string(),
+            pkcs11_module: None,
+            pkcs11_slot: 0,
+            pkcs11_key_label: "ocm-identity".to_string(),
+            pkcs11_pin_env: "OCM_PKCS11_PIN".to_string(),
+        }
+    }
+}
+
+/// `memory` generates a new key at every start; the others keep one key, and so one DID
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum KeyBackend {
+    #[default]
+    Memory,
+    Keychain, // Needs the keychain feature
+    Pkcs11,   // Needs the pkcs11 feature
+}
+
+/// PLC operation chains sent with memories so peers can check them without the directory
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct OfflineBundleConfig {
+    pub attach: bool, // Send this node's operation chain with the memories it authored
+    pub accept: bool, // Rely on attached chains while the directory cannot be reached
+}
+
+impl Default for OfflineBundleConfig {
+    fn default() -> Self {
+        Self {
+            attach: false,
+            accept: true,
+        }
+    }
+}
+
+/// Background re-resolution of the DID documents of memory authors, watching for key changes
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DidRefreshConfig {
+    pub enabled: bool, // Needs plc.enable_network_calls
+    pub interval_minutes: u64,
+    pub stale_after_hours: u64, // A DID is checked again once its last check is this old
+    pub dids_per_run: usize,
+}
+
+impl Default for DidRefreshConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            interval_minutes: 60,
+            stale_after_hours: 24,
+            dids_per_run: 100,
+        }
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -52,6 +394,513 @@ pub struct LoggingConfig {
     pub max_file_size_mb: u64,
 }
 
+/// What kind of node this is, advertised to peers. Light nodes hold headers and fetch
+/// bodies on demand; archive nodes keep every memory in full and never prune
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct NodeConfig {
+    pub role: NodeRole,
+    pub max_full_memories: Option<usize>, // Light nodes: beyond this, the oldest become headers
+}
+
+/// Where panic reports go besides the activity feed
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TelemetryConfig {
+    pub panic_sink_url: Option<String>, // Each panic is POSTed here as JSON
+    pub timeout_seconds: u64,
+}
+
+/// Restarting of background tasks that crash. Backoff doubles with each crash in a row
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SupervisorConfig {
+    pub initial_backoff_ms: u64,
+    pub max_backoff_seconds: u64,
+    pub max_restarts: u32, // Crashes in a row before a task is given up on; 0 retries forever
+    pub stable_after_seconds: u64, // A task that ran this long starts over from the initial backoff
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RetentionConfig {
+    pub enabled: bool,
+    pub dry_run: bool, // Report what would be removed without touching data
+    pub evaluation_interval_hours: u64,
+    pub rules: Vec<RetentionRule>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum RetentionAction {
+    Purge,   // Delete the memory outright
+    Archive, // Move the memory to the archive table
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RetentionRule {
+    pub memory_type: String,
+    pub max_age_days: u64,
+    pub action: RetentionAction,
+    #[serde(default)]
+    pub unclaimed_only: bool, // Only applies to proxy records nobody has claimed
+}
+
+/// Which memories a constrained device keeps in full; everything else is held as headers
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SyncPolicyConfig {
+    pub hot_set_days: Option<u64>, // None = full content for every memory
+    pub default_priority: SyncPriority,
+    #[serde(default)]
+    pub priorities: Vec<SyncPriorityRule>,
+}
+
+impl Default for SyncPolicyConfig {
+    fn default() -> Self {
+        Self {
+            hot_set_days: None,
+            default_priority: SyncPriority::Normal,
+            priorities: vec![],
+        }
+    }
+}
+
+impl SyncPolicyConfig {
+    /// The policy sent to peers with each sync request, with the hot set resolved to a cutoff time
+    pub fn partial_sync_policy(&self) -> PartialSyncPolicy {
+        PartialSyncPolicy {
+            hot_set_since: self.hot_set_days.map(|days| {
+                (chrono::Utc::now() - chrono::Duration::days(days as i64)).to_rfc3339()
+            }),
+            default_priority: self.default_priority,
+            priorities: self
+                .priorities
+                .iter()
+                .map(|rule| (rule.memory_type.clone(), rule.priority))
+                .collect(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SyncPriorityRule {
+    pub memory_type: String,
+    pub priority: SyncPriority,
+}
+
+/// HTTPS bridge for institutional peers that cannot speak the raw TCP protocol
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FederationConfig {
+    pub https_enabled: bool,
+    pub port: u16,
+    pub tls_cert_path: PathBuf,
+    pub tls_key_path: PathBuf,
+    pub max_clock_skew_seconds: u64, // Allowed drift of signed request timestamps
+    #[serde(default)]
+    pub bootstrap_snapshot_url: Option<String>, // Peer to fetch a snapshot from when the database is empty
+    #[serde(default)]
+    pub trusted_snapshot_signers: Vec<String>, // DIDs whose snapshots are accepted
+    #[serde(default)]
+    pub closed: bool, // Only founding members and invited DIDs may use the bridge
+    #[serde(default)]
+    pub founding_members: Vec<String>, // DIDs admitted to a closed federation without an invitation
+}
+
+impl Default for FederationConfig {
+    fn default() -> Self {
+        Self {
+            https_enabled: false,
+            port: 8444,
+            tls_cert_path: PathBuf::from("certs/cert.pem"),
+            tls_key_path: PathBuf::from("certs/key.pem"),
+            max_clock_skew_seconds: 300,
+            bootstrap_snapshot_url: None,
+            trusted_snapshot_signers: vec![],
+            closed: false,
+            founding_members: vec![],
+        }
+    }
+}
+
+/// Derived memories, recomputed when the memories they are computed from change
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DerivedMemoriesConfig {
+    pub enabled: bool,
+    pub poll_interval_ms: u64,
+}
+
+impl Default for DerivedMemoriesConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            poll_interval_ms: 1000,
+        }
+    }
+}
+
+/// WebAssembly component plugins, each in its own directory with a plugin.toml.
+/// Takes effect only in builds with the `plugins` feature
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PluginsConfig {
+    pub enabled: bool,
+    pub directory: PathBuf,
+    pub fuel_per_call: u64, // Wasm instructions (roughly) a plugin may run per call
+    pub max_memory_mb: u64, // Linear memory per plugin instance
+    pub poll_interval_ms: u64, // How often new activity events are delivered
+    pub max_failures: u32,  // Consecutive errors before a plugin is marked failed
+}
+
+impl Default for PluginsConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            directory: PathBuf::from("plugins"),
+            fuel_per_call: 100_000_000,
+            max_memory_mb: 64,
+            poll_interval_ms: 1000,
+            max_failures: 3,
+        }
+    }
+}
+
+/// Automation rules: Rhai scripts stored in the database, run on activity events.
+/// Takes effect only in builds with the `rules` feature
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RulesConfig {
+    pub enabled: bool,
+    pub poll_interval_ms: u64,
+    pub max_operations: u64, // Script steps per run before it is aborted
+    pub webhook_timeout_ms: u64,
+    pub allowed_webhook_hosts: Vec<String>, // Hosts scripts may call; empty allows none
+}
+
+impl Default for RulesConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            poll_interval_ms: 1000,
+            max_operations: 100_000,
+            webhook_timeout_ms: 5000,
+            allowed_webhook_hosts: vec![],
+        }
+    }
+}
+
+/// Hosting many organizations on one node, each with its own database file.
+/// Tenant APIs are served under /t/{tenant}/api/v1
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TenancyConfig {
+    pub enabled: bool,
+    pub directory: PathBuf,
+    pub max_tenants: usize,
+    pub default_max_memories: u64, // Per tenant unless the tenant overrides it
+    pub default_requests_per_minute: u32, // Per tenant unless the tenant overrides it
+}
+
+impl Default for TenancyConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            directory: PathBuf::from("data/tenants"),
+            max_tenants: 100,
+            default_max_memories: 1_000_000,
+            default_requests_per_minute: 600,
+        }
+    }
+}
+
+/// Human-readable handles for DIDs. Handles under `local_suffix` are granted by this
+/// node; any other handle is a domain that must prove the DID over DNS or HTTPS
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct HandlesConfig {
+    pub local_suffix: String,
+    pub verify_domains: bool,       // Accept domain handles at all
+    pub dns_over_https_url: String, // JSON DNS API used for TXT lookups
+    pub timeout_seconds: u64,
+}
+
+impl Default for HandlesConfig {
+    fn default() -> Self {
+        Self {
+            local_suffix: "family-ocm".to_string(),
+            verify_domains: true,
+            dns_over_https_url: "https://cloudflare-dns.com/dns-query".to_string(),
+            timeout_seconds: 5,
+        }
+    }
+}
+
+/// Duplicate detection between proxy records of different organizations. Records are
+/// compared by keyed hashes of the date of birth and Bloom filters of the name, never
+/// by the name itself; organizations whose records should match must share `key`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LinkageConfig {
+    pub enabled: bool,
+    pub key: String, // Empty uses a built-in key, which anyone could use to test guesses
+    pub bloom_bits: usize,
+    pub hash_functions: u32,
+    pub match_threshold: f64, // Dice similarity of the name filters, 0-1
+}
+
+impl Default for LinkageConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            key: String::new(),
+            bloom_bits: 1000,
+            hash_functions: 20,
+            match_threshold: 0.85,
+        }
+    }
+}
+
+/// Signed receipts sent back to the authors of memories this node receives. Direct
+/// messages are always acknowledged when enabled; other types only if listed
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ReceiptsConfig {
+    pub delivery_receipts: bool,
+    pub read_receipts: bool, // Off by default: tells the author when you looked
+    pub memory_types: Vec<String>,
+}
+
+impl Default for ReceiptsConfig {
+    fn default() -> Self {
+        Self {
+            delivery_receipts: true,
+            read_receipts: false,
+            memory_types: Vec::new(),
+        }
+    }
+}
+
+/// Push notifications sent by the web server to subscribed browsers (Web Push) and
+/// apps (FCM). Takes effect only in builds with the `push` feature
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PushConfig {
+    pub enabled: bool,
+    pub vapid_subject: String, // mailto: or https: contact the push services may use
+    pub vapid_private_key_path: Option<PathBuf>, // P-256 key, PKCS#8 PEM; None disables Web Push
+    pub fcm_project_id: Option<String>, // None disables FCM
+    pub fcm_access_token_path: Option<PathBuf>, // OAuth token, re-read on every push so it can be rotated
+    pub default_events: Vec<PushEvent>,         // For subscriptions that do not choose
+    pub ttl_seconds: u32, // How long a push service holds a push for an offline device
+    pub poll_interval_ms: u64,
+    pub timeout_ms: u64,
+    pub max_subscriptions_per_did: usize,
+}
+
+impl Default for PushConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            vapid_subject: String::new(),
+            vapid_private_key_path: None,
+            fcm_project_id: None,
+            fcm_access_token_path: None,
+            default_events: PushEvent::ALL.to_vec(),
+            ttl_seconds: 24 * 60 * 60,
+            poll_interval_ms: 1000,
+            timeout_ms: 5000,
+            max_subscriptions_per_did: 10,
+        }
+    }
+}
+
+/// Background jobs started through the API (imports, exports, verification)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct JobsConfig {
+    pub poll_interval_ms: u64, // How often an idle runner looks for queued jobs
+    pub max_unfinished: usize, // Queued and running jobs allowed at once
+}
+
+impl Default for JobsConfig {
+    fn default() -> Self {
+        Self {
+            poll_interval_ms: 1000,
+            max_unfinished: 20,
+        }
+    }
+}
+
+/// Static file serving for the browser (WASM) client
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WebConfig {
+    pub profile: WebProfile,
+    pub root: PathBuf,
+    pub spa_fallback: bool, // Serve index.html for unknown extensionless paths
+    pub cross_origin_isolation: bool, // COOP/COEP, required for wasm threads and SharedArrayBuffer
+    pub immutable_max_age_seconds: u64, // Cache lifetime for content-hashed assets
+    #[serde(default)]
+    pub cors: CorsConfig,
+    #[serde(default)]
+    pub public_lookup: PublicLookupConfig,
+    #[serde(default)]
+    pub sign_responses: bool, // RFC 9421 signatures by the node identity on API responses
+    #[serde(default)]
+    pub batch: BatchConfig,
+    #[serde(default)]
+    pub sessions: SessionConfig,
+}
+
+/// Unauthenticated GET /api/v1/public/memories/{content_hash}, letting third parties
+/// verify memories of the listed types without federating
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PublicLookupConfig {
+    pub enabled: bool,
+    pub memory_types: Vec<String>, // Types anyone may look up; empty serves none
+    pub requests_per_minute: u32,  // Per client IP
+}
+
+impl Default for PublicLookupConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            memory_types: Vec::new(),
+            requests_per_minute: 10,
+        }
+    }
+}
+
+/// POST /api/v1/memories:batch and /api/v1/individuals:batch, which apply all of a
+/// request's records in one transaction or none of them
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct BatchConfig {
+    pub max_items: usize, // Larger requests are refused outright
+}
+
+impl Default for BatchConfig {
+    fn default() -> Self {
+        Self { max_items: 500 }
+    }
+}
+
+/// Session lifetimes. Each request slides a session's expiry forward by the idle timeout,
+/// up to its absolute lifetime; a refresh token then starts a new session, and is
+/// replaced by a new token each time it is used
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SessionConfig {
+    pub idle_timeout_minutes: i64,
+    pub absolute_lifetime_hours: i64,
+    pub refresh_token_days: i64, // How long an unused refresh token stays valid
+}
+
+impl Default for SessionConfig {
+    fn default() -> Self {
+        Self {
+            idle_timeout_minutes: 30,
+            absolute_lifetime_hours: 24,
+            refresh_token_days: 30,
+        }
+    }
+}
+
+/// Cross-origin access to the HTTP API; only listed origins are reflected back
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CorsConfig {
+    pub allowed_origins: Vec<String>, // Exact origins, or "*" for any origin without credentials
+    pub allowed_methods: Vec<String>,
+    pub allowed_headers: Vec<String>,
+    pub allow_credentials: bool,
+    pub max_age_seconds: u64, // How long browsers may cache a preflight result
+}
+
+impl Default for CorsConfig {
+    fn default() -> Self {
+        Self {
+            allowed_origins: vec!["https://localhost:8443".to_string()],
+            allowed_methods: ["GET", "POST", "PUT", "DELETE", "OPTIONS"]
+                .iter()
+                .map(|m| m.to_string())
+                .collect(),
+            allowed_headers: ["Content-Type", "Authorization", "X-Requested-With"]
+                .iter()
+                .map(|h| h.to_string())
+                .collect(),
+            allow_credentials: true,
+            max_age_seconds: 86400,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum WebProfile {
+    Production,  // HTTPS with HSTS when certificates are present
+    Development, // Plain HTTP, no HSTS, always cross-origin isolated
+}
+
+impl Default for WebConfig {
+    fn default() -> Self {
+        Self {
+            profile: WebProfile::Production,
+            root: PathBuf::from("ocm-wasm"),
+            spa_fallback: true,
+            cross_origin_isolation: true,
+            immutable_max_age_seconds: 31_536_000,
+            cors: CorsConfig::default(),
+            public_lookup: PublicLookupConfig::default(),
+            sign_responses: false,
+            batch: BatchConfig::default(),
+            sessions: SessionConfig::default(),
+        }
+    }
+}
+
+impl Default for TelemetryConfig {
+    fn default() -> Self {
+        Self {
+            panic_sink_url: None,
+            timeout_seconds: 5,
+        }
+    }
+}
+
+impl Default for SupervisorConfig {
+    fn default() -> Self {
+        Self {
+            initial_backoff_ms: 1000,
+            max_backoff_seconds: 60,
+            max_restarts: 10,
+            stable_after_seconds: 300,
+        }
+    }
+}
+
+impl Default for RetentionConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            dry_run: true,
+            evaluation_interval_hours: 24,
+            rules: vec![
+                RetentionRule {
+                    memory_type: "proxy_individual".to_string(),
+                    max_age_days: 180,
+                    action: RetentionAction::Purge,
+                    unclaimed_only: true,
+                },
+                RetentionRule {
+                    memory_type: "attendance".to_string(),
+                    max_age_days: 730,
+                    action: RetentionAction::Archive,
+                    unclaimed_only: false,
+                },
+            ],
+        }
+    }
+}
+
 impl Default for OcmConfig {
     fn default() -> Self {
         Self {
@@ -65,6 +914,8 @@ impl Default for OcmConfig {
                 path: PathBuf::from("data/ocm-impl.db"),
                 connection_pool_size: 10,
                 backup_interval_hours: Some(24),
+                compression: CompressionConfig::default(),
+                durability: DurabilityConfig::default(),
             },
             networking: NetworkingConfig {
                 max_peers: 50,
@@ -72,11 +923,26 @@ impl Default for OcmConfig {
                 connection_timeout_seconds: 10,
                 discovery_interval_seconds: 60,
                 seed_peers: vec![],
+                peer_groups: vec![],
+                bandwidth: BandwidthConfig::default(),
+                faults: FaultInjectionConfig::default(),
+                rendezvous: RendezvousConfig::default(),
+                dns_seeds: DnsSeedConfig::default(),
+                presence: PresenceConfig::default(),
+                inbound_review: InboundReviewConfig::default(),
+                ingest: IngestPolicy::default(),
+                frames: FrameLimits::default(),
+                relay_memories: false,
+                allow_unsigned_discovery: false,
             },
             plc: PlcConfig {
                 directory_url: "https://plc.directory".to_string(),
                 enable_network_calls: false, // Safe default for development
                 cache_ttl_hours: 24,
+                failure_policy: PlcFailurePolicy::FailClosed,
+                refresh: DidRefreshConfig::default(),
+                offline_bundles: OfflineBundleConfig::default(),
+                key: KeyConfig::default(),
                 handle: None,
             },
             logging: LoggingConfig {
@@ -86,6 +952,22 @@ impl Default for OcmConfig {
                 file_path: None,
                 max_file_size_mb: 100,
             },
+            node: NodeConfig::default(),
+            retention: RetentionConfig::default(),
+            federation: FederationConfig::default(),
+            web: WebConfig::default(),
+            sync: SyncPolicyConfig::default(),
+            derived: DerivedMemoriesConfig::default(),
+            plugins: PluginsConfig::default(),
+            rules: RulesConfig::default(),
+            tenancy: TenancyConfig::default(),
+            handles: HandlesConfig::default(),
+            linkage: LinkageConfig::default(),
+            receipts: ReceiptsConfig::default(),
+            push: PushConfig::default(),
+            jobs: JobsConfig::default(),
+            supervisor: SupervisorConfig::default(),
+            telemetry: TelemetryConfig::default(),
         }
     }
 }
@@ -156,6 +1038,489 @@ impl OcmConfig {
                 .map_err(|e| OcmError::Config(format!("Invalid PLC directory URL: {}", e)))?;
         }
 
+        // Validate DID document refresh
+        if self.plc.refresh.interval_minutes == 0 || self.plc.refresh.dids_per_run == 0 {
+            return Err(OcmError::Config(
+                "DID refresh interval and batch size must be positive".to_string(),
+            ));
+        }
+
+        // Validate the identity key backend
+        match self.plc.key.backend {
+            KeyBackend::Memory => {}
+            KeyBackend::Keychain if !cfg!(feature = "keychain") => {
+                return Err(OcmError::Config(
+                    "plc.key.backend = \"keychain\" needs the keychain feature".to_string(),
+                ));
+            }
+            KeyBackend::Pkcs11 if !cfg!(feature = "pkcs11") => {
+                return Err(OcmError::Config(
+                    "plc.key.backend = \"pkcs11\" needs the pkcs11 feature".to_string(),
+                ));
+            }
+            KeyBackend::Keychain => {}
+            KeyBackend::Pkcs11 if self.plc.key.pkcs11_module.is_none() => {
+                return Err(OcmError::Config(
+                    "plc.key.pkcs11_module must be set for the pkcs11 key backend".to_string(),
+                ));
+            }
+            KeyBackend::Pkcs11 => {}
+        }
+
+        // Validate peer groups
+        for (i, group) in self.networking.peer_groups.iter().enumerate() {
+            if group.name.trim().is_empty() {
+                return Err(OcmError::Config(
+                    "Peer group names cannot be empty".to_string(),
+                ));
+            }
+            if self.networking.peer_groups[..i]
+                .iter()
+                .any(|g| g.name == group.name)
+            {
+                return Err(OcmError::Config(format!(
+                    "Duplicate peer group: {}",
+                    group.name
+                )));
+            }
+            for tag in group.tags.iter().flatten() {
+                if crate::core::models::normalize_tag(tag).as_deref() != Ok(tag.as_str()) {
+                    return Err(OcmError::Config(format!(
+                        "Peer group {} has an invalid or non-lowercase tag: {}",
+                        group.name, tag
+                    )));
+                }
+            }
+        }
+
+        // Validate sync priorities
+        if self.sync.hot_set_days == Some(0) {
+            return Err(OcmError::Config(
+                "Sync hot set must cover at least one day".to_string(),
+            ));
+        }
+        let mut prioritized_types = std::collections::HashSet::new();
+        for rule in &self.sync.priorities {
+            if !prioritized_types.insert(rule.memory_type.as_str()) {
+                return Err(OcmError::Config(format!(
+                    "Duplicate sync priority for memory type: {}",
+                    rule.memory_type
+                )));
+            }
+        }
+
+        // Validate bandwidth limits and sync windows
+        let bandwidth = &self.networking.bandwidth;
+        if bandwidth.upload_bytes_per_second == Some(0)
+            || bandwidth.download_bytes_per_second == Some(0)
+        {
+            return Err(OcmError::Config(
+                "Bandwidth limits must be at least one byte per second".to_string(),
+            ));
+        }
+        for window in &bandwidth.sync_windows {
+            if window.start_hour > 23
+                || window.end_hour > 24
+                || window.start_hour == window.end_hour
+            {
+                return Err(OcmError::Config(format!(
+                    "Invalid sync window {}-{}",
+                    window.start_hour, window.end_hour
+                )));
+            }
+        }
+
+        // Validate presence
+        let presence = &self.networking.presence;
+        if presence.enabled
+            && (presence.interval_seconds == 0
+                || presence.offline_after_seconds <= presence.interval_seconds)
+        {
+            return Err(OcmError::Config(
+                "Presence interval must be positive and shorter than the offline timeout"
+                    .to_string(),
+            ));
+        }
+        for group in &presence.groups {
+            if !self.networking.peer_groups.iter().any(|g| &g.name == group) {
+                return Err(OcmError::Config(format!(
+                    "Presence is shared with unknown peer group {}",
+                    group
+                )));
+            }
+        }
+
+        // Validate inbound review
+        for rule in &self.networking.inbound_review.auto_approve {
+            if !self
+                .networking
+                .peer_groups
+                .iter()
+                .any(|g| g.name == rule.group)
+            {
+                return Err(OcmError::Config(format!(
+                    "Inbound review auto-approves unknown peer group {}",
+                    rule.group
+                )));
+            }
+        }
+
+        // Validate frame limits
+        let frames = &self.networking.frames;
+        if frames.max_frame_bytes < 16 * 1024 || frames.max_frame_bytes > u32::MAX as usize {
+            return Err(OcmError::Config(
+                "Frame size must be between 16 KiB and 4 GiB".to_string(),
+            ));
+        }
+        if frames.max_message_bytes < frames.max_frame_bytes {
+            return Err(OcmError::Config(
+                "Maximum message size must be at least the frame size".to_string(),
+            ));
+        }
+
+        // Validate task supervision
+        let supervisor = &self.supervisor;
+        if supervisor.initial_backoff_ms == 0
+            || supervisor.initial_backoff_ms > supervisor.max_backoff_seconds * 1000
+        {
+            return Err(OcmError::Config(
+                "Supervisor initial backoff must be positive and at most the maximum backoff"
+                    .to_string(),
+            ));
+        }
+
+        // Validate panic telemetry
+        if let Some(url) = &self.telemetry.panic_sink_url {
+            if !url.starts_with("https://") && !url.starts_with("http://") {
+                return Err(OcmError::Config(
+                    "telemetry.panic_sink_url must be an http(s) URL".to_string(),
+                ));
+            }
+        }
+
+        // Validate the node role
+        if self.node.max_full_memories.is_some() && self.node.role != NodeRole::Light {
+            return Err(OcmError::Config(
+                "node.max_full_memories applies to light nodes only".to_string(),
+            ));
+        }
+        if self.node.max_full_memories == Some(0) {
+            return Err(OcmError::Config(
+                "node.max_full_memories must be positive".to_string(),
+            ));
+        }
+        if self.node.role == NodeRole::Light && self.networking.relay_memories {
+            return Err(OcmError::Config(
+                "Light nodes cannot relay memories they may hold only as headers".to_string(),
+            ));
+        }
+
+        // Validate fault injection
+        let faults = &self.networking.faults;
+        if faults.enabled {
+            if !cfg!(feature = "chaos") {
+                return Err(OcmError::Config(
+                    "Fault injection needs a build with the chaos feature".to_string(),
+                ));
+            }
+            let rates = [
+                faults.drop_rate,
+                faults.delay_rate,
+                faults.duplicate_rate,
+                faults.corrupt_rate,
+            ];
+            if rates.iter().any(|rate| !(0.0..=1.0).contains(rate)) {
+                return Err(OcmError::Config(
+                    "Fault injection rates must be between 0 and 1".to_string(),
+                ));
+            }
+        }
+
+        // Validate rendezvous
+        let rendezvous = &self.networking.rendezvous;
+        if let Some(relay_url) = &rendezvous.relay_url {
+            if !relay_url.starts_with("ws://") && !relay_url.starts_with("wss://") {
+                return Err(OcmError::Config(
+                    "Rendezvous relay URL must start with ws:// or wss://".to_string(),
+                ));
+            }
+            if rendezvous.ttl_seconds < 30 {
+                return Err(OcmError::Config(
+                    "Rendezvous TTL must be at least 30 seconds".to_string(),
+                ));
+            }
+        }
+
+        // Validate derived memories
+        if self.derived.enabled && self.derived.poll_interval_ms == 0 {
+            return Err(OcmError::Config(
+                "Derived memory poll interval must be positive".to_string(),
+            ));
+        }
+
+        // Validate plugins
+        if self.plugins.enabled {
+            if !cfg!(feature = "plugins") {
+                return Err(OcmError::Config(
+                    "Plugins need a build with the plugins feature".to_string(),
+                ));
+            }
+            if self.plugins.fuel_per_call == 0
+                || self.plugins.max_memory_mb == 0
+                || self.plugins.poll_interval_ms == 0
+                || self.plugins.max_failures == 0
+            {
+                return Err(OcmError::Config(
+                    "Plugin fuel, memory, poll interval and failure limits must be positive"
+                        .to_string(),
+                ));
+            }
+        }
+
+        // Validate automation rules
+        if self.rules.enabled {
+            if !cfg!(feature = "rules") {
+                return Err(OcmError::Config(
+                    "Automation rules need a build with the rules feature".to_string(),
+                ));
+            }
+            if self.rules.poll_interval_ms == 0
+                || self.rules.max_operations == 0
+                || self.rules.webhook_timeout_ms == 0
+            {
+                return Err(OcmError::Config(
+                    "Rule poll interval, operation and webhook timeout limits must be positive"
+                        .to_string(),
+                ));
+            }
+        }
+
+        // Validate tenancy
+        if self.tenancy.enabled
+            && (self.tenancy.max_tenants == 0
+                || self.tenancy.default_max_memories == 0
+                || self.tenancy.default_requests_per_minute == 0)
+        {
+            return Err(OcmError::Config(
+                "Tenant count, memory and request limits must be positive".to_string(),
+            ));
+        }
+
+        // Validate handles
+        if ocm_protocol::handle::normalize_handle(&format!("x.{}", self.handles.local_suffix))
+            .is_err()
+        {
+            return Err(OcmError::Config(format!(
+                "Handle suffix is not a valid domain suffix: {}",
+                self.handles.local_suffix
+            )));
+        }
+        if self.handles.verify_domains && self.handles.timeout_seconds == 0 {
+            return Err(OcmError::Config(
+                "Handle verification timeout must be positive".to_string(),
+            ));
+        }
+
+        // Validate linkage
+        if self.linkage.enabled
+            && (self.linkage.bloom_bits == 0
+                || self.linkage.hash_functions == 0
+                || !(self.linkage.match_threshold > 0.0 && self.linkage.match_threshold <= 1.0))
+        {
+            return Err(OcmError::Config(
+                "Linkage needs Bloom filter bits, hash functions and a threshold in (0, 1]"
+                    .to_string(),
+            ));
+        }
+
+        // Validate receipts
+        if self
+            .receipts
+            .memory_types
+            .iter()
+            .any(|memory_type| crate::core::models::is_private_memory_type(memory_type))
+        {
+            return Err(OcmError::Config(
+                "Receipts cannot be requested for private memory types".to_string(),
+            ));
+        }
+
+        // Validate public memory lookup
+        if self.web.public_lookup.enabled {
+            if self
+                .web
+                .public_lookup
+                .memory_types
+                .iter()
+                .any(|memory_type| crate::core::models::is_private_memory_type(memory_type))
+            {
+                return Err(OcmError::Config(
+                    "Private memory types cannot be looked up publicly".to_string(),
+                ));
+            }
+            if self.web.public_lookup.requests_per_minute == 0 {
+                return Err(OcmError::Config(
+                    "Public lookup rate limit must be positive".to_string(),
+                ));
+            }
+        }
+
+        // Validate background jobs
+        if self.jobs.poll_interval_ms == 0 || self.jobs.max_unfinished == 0 {
+            return Err(OcmError::Config(
+                "Job poll interval and queue limit must be positive".to_string(),
+            ));
+        }
+
+        // Validate memory body compression
+        let compression = &self.database.compression;
+        if !(1..=19).contains(&compression.level) {
+            return Err(OcmError::Config(
+                "Compression level must be between 1 and 19".to_string(),
+            ));
+        }
+        if compression.min_samples == 0
+            || compression.max_samples < compression.min_samples
+            || compression.dictionary_bytes < 256
+        {
+            return Err(OcmError::Config(
+                "Compression needs at least one sample, max_samples of at least min_samples and a dictionary of at least 256 bytes".to_string(),
+            ));
+        }
+
+        // Validate durability: a memory this node signed is only reported stored once on disk
+        if self.database.durability.signed_synchronous < SynchronousMode::Full {
+            return Err(OcmError::Config(
+                "Signed memories need synchronous full or extra".to_string(),
+            ));
+        }
+
+        // Validate batch writes
+        if self.web.batch.max_items == 0 {
+            return Err(OcmError::Config(
+                "Batch size limit must be positive".to_string(),
+            ));
+        }
+
+        // Validate session lifetimes
+        let sessions = &self.web.sessions;
+        if sessions.idle_timeout_minutes <= 0
+            || sessions.absolute_lifetime_hours <= 0
+            || sessions.refresh_token_days <= 0
+        {
+            return Err(OcmError::Config(
+                "Session timeouts and lifetimes must be positive".to_string(),
+            ));
+        }
+
+        // Validate push notifications
+        if self.push.enabled {
+            if !cfg!(feature = "push") {
+                return Err(OcmError::Config(
+                    "Push notifications need a build with the push feature".to_string(),
+                ));
+            }
+            if self.push.vapid_private_key_path.is_none() && self.push.fcm_project_id.is_none() {
+                return Err(OcmError::Config(
+                    "Push notifications need a VAPID key, an FCM project or both".to_string(),
+                ));
+            }
+            if let Some(path) = &self.push.vapid_private_key_path {
+                if !path.exists() {
+                    return Err(OcmError::Config(format!(
+                        "VAPID private key not found: {}",
+                        path.display()
+                    )));
+                }
+                if !self.push.vapid_subject.starts_with("mailto:")
+                    && !self.push.vapid_subject.starts_with("https:")
+                {
+                    return Err(OcmError::Config(
+                        "VAPID subject must be a mailto: or https: URL".to_string(),
+                    ));
+                }
+            }
+            if self.push.fcm_project_id.is_some() && self.push.fcm_access_token_path.is_none() {
+                return Err(OcmError::Config(
+                    "FCM needs an access token path".to_string(),
+                ));
+            }
+            if self.push.poll_interval_ms == 0
+                || self.push.timeout_ms == 0
+                || self.push.max_subscriptions_per_did == 0
+            {
+                return Err(OcmError::Config(
+                    "Push poll interval, timeout and subscription limit must be positive"
+                        .to_string(),
+                ));
+            }
+        }
+
+        // Validate federation bridge
+        if self.federation.https_enabled {
+            if self.federation.port == self.server.p2p_port
+                || self.federation.port == self.server.discovery_port
+            {
+                return Err(OcmError::Config(
+                    "Federation port must differ from the P2P and discovery ports".to_string(),
+                ));
+            }
+            if !self.federation.tls_cert_path.exists() || !self.federation.tls_key_path.exists() {
+                return Err(OcmError::Config(
+                    "Federation over HTTPS requires a TLS certificate and key".to_string(),
+                ));
+            }
+        }
+
+        if let Some(url) = &self.federation.bootstrap_snapshot_url {
+            if !url.starts_with("https://") {
+                return Err(OcmError::Config(
+                    "Bootstrap snapshot URL must use HTTPS".to_string(),
+                ));
+            }
+            if self.federation.trusted_snapshot_signers.is_empty() {
+                return Err(OcmError::Config(
+                    "Bootstrapping from a snapshot requires at least one trusted signer DID"
+                        .to_string(),
+                ));
+            }
+        }
+
+        // Validate web root
+        if !self.web.root.is_dir() {
+            tracing::warn!(
+                "Web root {:?} does not exist; static files will 404",
+                self.web.root
+            );
+        }
+
+        // Validate CORS allowlist
+        let cors = &self.web.cors;
+        if cors.allow_credentials && cors.allowed_origins.iter().any(|o| o == "*") {
+            return Err(OcmError::Config(
+                "CORS cannot allow any origin (\"*\") together with credentials".to_string(),
+            ));
+        }
+        for origin in cors.allowed_origins.iter().filter(|o| *o != "*") {
+            url::Url::parse(origin)
+                .map_err(|e| OcmError::Config(format!("Invalid CORS origin {}: {}", origin, e)))?;
+        }
+
+        // Validate retention rules
+        if self.retention.enabled && self.retention.evaluation_interval_hours == 0 {
+            return Err(OcmError::Config(
+                "Retention evaluation interval must be at least one hour".to_string(),
+            ));
+        }
+        for rule in &self.retention.rules {
+            if rule.max_age_days == 0 {
+                return Err(OcmError::Config(format!(
+                    "Retention rule for '{}' must keep memories for at least one day",
+                    rule.memory_type
+                )));
+            }
+        }
+
         tracing::info!("Configuration validation passed");
         Ok(())
     }
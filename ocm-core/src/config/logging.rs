@@ -1,7 +1,73 @@
 use crate::config::app::OcmConfig;
 use crate::core::error::{OcmError, Result};
-use tracing::info;
-use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
+use crate::core::redact::is_sensitive_field;
+use std::fmt;
+use tracing::{field::Field, info, Event, Subscriber};
+use tracing_subscriber::{
+    field::MakeExt,
+    fmt::format::{debug_fn, Writer},
+    fmt::{FmtContext, FormatEvent, FormatFields},
+    layer::SubscriberExt,
+    registry::LookupSpan,
+    util::SubscriberInitExt,
+};
+
+/// Field formatter that masks structured fields named in `SENSITIVE_FIELDS`
+fn redacting_fields() -> impl for<'writer> FormatFields<'writer> + 'static {
+    debug_fn(
+        |writer: &mut Writer<'_>, field: &Field, value: &dyn fmt::Debug| {
+            if is_sensitive_field(field.name()) {
+                write!(writer, "{}=[REDACTED]", field)
+            } else if field.name() == "message" {
+                write!(writer, "{:?}", value)
+            } else {
+                write!(writer, "{}={:?}", field, value)
+            }
+        },
+    )
+    .delimited(" ")
+}
+
+/// JSON event formatter that masks fields named in `SENSITIVE_FIELDS` in the event and in
+/// every span it reports. The JSON layer serializes fields itself rather than through a
+/// field formatter, so the finished line is rewritten instead
+struct RedactingJson<F>(F);
+
+impl<S, N, F> FormatEvent<S, N> for RedactingJson<F>
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+    N: for<'writer> FormatFields<'writer> + 'static,
+    F: FormatEvent<S, N>,
+{
+    fn format_event(
+        &self,
+        ctx: &FmtContext<'_, S, N>,
+        mut writer: Writer<'_>,
+        event: &Event<'_>,
+    ) -> fmt::Result {
+        let mut line = String::new();
+        self.0.format_event(ctx, Writer::new(&mut line), event)?;
+        let mut value: serde_json::Value = serde_json::from_str(&line).map_err(|_| fmt::Error)?;
+        redact_json_fields(&mut value);
+        writeln!(writer, "{}", value)
+    }
+}
+
+fn redact_json_fields(value: &mut serde_json::Value) {
+    match value {
+        serde_json::Value::Object(fields) => {
+            for (name, field) in fields.iter_mut() {
+                if is_sensitive_field(name) {
+                    *field = serde_json::Value::from("[REDACTED]");
+                } else {
+                    redact_json_fields(field);
+                }
+            }
+        }
+        serde_json::Value::Array(items) => items.iter_mut().for_each(redact_json_fields),
+        _ => {}
+    }
+}
 
 pub fn init_logging(config: &OcmConfig) -> Result<()> {
     let filter = tracing_subscriber::EnvFilter::try_from_default_env()
@@ -12,11 +78,15 @@ pub fn init_logging(config: &OcmConfig) -> Result<()> {
 
     match config.logging.format.as_str() {
         "json" => {
-            let json_layer = tracing_subscriber::fmt::layer().json();
+            let json_layer = tracing_subscriber::fmt::layer()
+                .json()
+                .map_event_format(RedactingJson);
             subscriber.with(json_layer).init();
         }
         "pretty" => {
-            let pretty_layer = tracing_subscriber::fmt::layer().pretty();
+            let pretty_layer = tracing_subscriber::fmt::layer()
+                .pretty()
+                .fmt_fields(redacting_fields());
             subscriber.with(pretty_layer).init();
         }
         _ => {
@@ -62,6 +62,12 @@ impl From<base64::DecodeError> for OcmError {
     }
 }
 
+impl From<ocm_protocol::ids::InvalidId> for OcmError {
+    fn from(err: ocm_protocol::ids::InvalidId) -> Self {
+        OcmError::Validation(err.to_string())
+    }
+}
+
 impl From<Box<dyn std::error::Error>> for OcmError {
     fn from(err: Box<dyn std::error::Error>) -> Self {
         OcmError::OperationFailed(err.to_string())
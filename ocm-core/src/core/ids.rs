@@ -0,0 +1,3 @@
+//! The typed identifiers live in ocm-protocol so wire types can use them
+
+pub use ocm_protocol::ids::*;
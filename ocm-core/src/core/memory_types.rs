@@ -0,0 +1,502 @@
+use crate::core::error::{OcmError, Result};
+use crate::core::ids::Did;
+use crate::core::models::{
+    normalize_tag, AnnotationData, ContactData, DirectMessageData, IdentityLineageData, Individual,
+    MemoryMergeData, MemoryTagData, ReceiptData, SignedMemory, ANNOTATION_MEMORY_TYPE,
+    CONTACT_MEMORY_TYPE, DIRECT_MESSAGE_MEMORY_TYPE, IDENTITY_LINEAGE_MEMORY_TYPE,
+    MEMORY_MERGE_MEMORY_TYPE, MEMORY_TAG_MEMORY_TYPE, RECEIPT_MEMORY_TYPE, TOMBSTONE_MEMORY_TYPE,
+};
+
+/// Longest annotation body accepted, in bytes
+pub const MAX_ANNOTATION_BODY: usize = 10_000;
+/// Longest direct message body accepted, in bytes
+pub const MAX_DIRECT_MESSAGE_BODY: usize = 10_000;
+use ocm_protocol::sync::SyncPriority;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
+
+/// How concurrent versions of a memory with the same ID are reconciled during sync
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MergeStrategy {
+    Crdt,           // Field-level CRDT merge, surfacing conflicts
+    LastWriterWins, // The whole memory with the latest updated_on wins
+    Immutable,      // Never changes once written; differing copies are rejected
+}
+
+/// What a client needs to display memories of a type
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RenderMetadata {
+    pub display_name: String,
+    pub summary_fields: Vec<String>, // memory_data fields that identify a memory at a glance
+    pub contains_pii: bool,
+}
+
+/// Behaviour for one memory type, registered by the application or a plugin
+pub trait MemoryTypeHandler: Send + Sync {
+    fn memory_type(&self) -> &str;
+
+    /// Reject malformed memory_data before it is signed or accepted from a peer
+    fn validate(&self, _memory_data: &str) -> Result<()> {
+        Ok(())
+    }
+
+    fn render(&self) -> RenderMetadata {
+        RenderMetadata {
+            display_name: self.memory_type().to_string(),
+            summary_fields: vec![],
+            contains_pii: false,
+        }
+    }
+
+    fn merge_strategy(&self) -> MergeStrategy {
+        MergeStrategy::Crdt
+    }
+
+    /// Priority partially syncing nodes ask for, unless their sync config says otherwise
+    fn sync_priority(&self) -> Option<SyncPriority> {
+        None
+    }
+
+    /// memory_data fields (dotted paths of letters, digits and underscores) that
+    /// queries filter on often enough to be worth a database index
+    fn indexed_fields(&self) -> Vec<String> {
+        vec![]
+    }
+}
+
+/// The memory types this codebase writes itself
+struct BuiltinType {
+    memory_type: &'static str,
+    display_name: &'static str,
+    summary_fields: &'static [&'static str],
+    contains_pii: bool,
+    merge_strategy: MergeStrategy,
+    sync_priority: Option<SyncPriority>,
+    indexed_fields: &'static [&'static str],
+    validate: fn(&str) -> Result<()>,
+}
+
+impl MemoryTypeHandler for BuiltinType {
+    fn memory_type(&self) -> &str {
+        self.memory_type
+    }
+
+    fn validate(&self, memory_data: &str) -> Result<()> {
+        (self.validate)(memory_data)
+    }
+
+    fn render(&self) -> RenderMetadata {
+        RenderMetadata {
+            display_name: self.display_name.to_string(),
+            summary_fields: self.summary_fields.iter().map(|f| f.to_string()).collect(),
+            contains_pii: self.contains_pii,
+        }
+    }
+
+    fn merge_strategy(&self) -> MergeStrategy {
+        self.merge_strategy
+    }
+
+    fn sync_priority(&self) -> Option<SyncPriority> {
+        self.sync_priority
+    }
+
+    fn indexed_fields(&self) -> Vec<String> {
+        self.indexed_fields.iter().map(|f| f.to_string()).collect()
+    }
+}
+
+fn validate_individual(memory_data: &str) -> Result<()> {
+    serde_json::from_str::<Individual>(memory_data)
+        .map(|_| ())
+        .map_err(|e| OcmError::Validation(format!("Not an individual: {}", e)))
+}
+
+fn validate_json_object(memory_data: &str) -> Result<()> {
+    match serde_json::from_str::<serde_json::Value>(memory_data) {
+        Ok(serde_json::Value::Object(_)) => Ok(()),
+        _ => Err(OcmError::Validation(
+            "Memory data must be a JSON object".to_string(),
+        )),
+    }
+}
+
+fn validate_memory_tag(memory_data: &str) -> Result<()> {
+    let data: MemoryTagData = serde_json::from_str(memory_data)
+        .map_err(|e| OcmError::Validation(format!("Not a tag operation: {}", e)))?;
+    match normalize_tag(&data.tag) {
+        Ok(tag) if tag == data.tag => Ok(()),
+        Ok(_) => Err(OcmError::Validation(format!(
+            "Tag {} is not normalized",
+            data.tag
+        ))),
+        Err(e) => Err(OcmError::Validation(e)),
+    }
+}
+
+fn validate_annotation(memory_data: &str) -> Result<()> {
+    let data: AnnotationData = serde_json::from_str(memory_data)
+        .map_err(|e| OcmError::Validation(format!("Not an annotation: {}", e)))?;
+    if data.body.trim().is_empty() || data.body.len() > MAX_ANNOTATION_BODY {
+        return Err(OcmError::Validation(format!(
+            "Annotation body must be 1-{} bytes",
+            MAX_ANNOTATION_BODY
+        )));
+    }
+    if data.target_memory_id.is_empty() || data.target_content_hash.is_empty() {
+        return Err(OcmError::Validation(
+            "Annotation must name its target memory and content hash".to_string(),
+        ));
+    }
+    Ok(())
+}
+
+fn validate_contact(memory_data: &str) -> Result<()> {
+    let data: ContactData = serde_json::from_str(memory_data)
+        .map_err(|e| OcmError::Validation(format!("Not a contact: {}", e)))?;
+    if !data.contact_did.starts_with("did:") {
+        return Err(OcmError::Validation(format!(
+            "Contact {} is not a DID",
+            data.contact_did
+        )));
+    }
+    if !data.removed && data.display_name.trim().is_empty() {
+        return Err(OcmError::Validation(
+            "Contact display name is required".to_string(),
+        ));
+    }
+    for group in &data.groups {
+        match normalize_tag(group) {
+            Ok(normalized) if &normalized == group => {}
+            _ => {
+                return Err(OcmError::Validation(format!(
+                    "Contact group {} is not normalized",
+                    group
+                )))
+            }
+        }
+    }
+    Ok(())
+}
+
+fn validate_direct_message(memory_data: &str) -> Result<()> {
+    let data: DirectMessageData = serde_json::from_str(memory_data)
+        .map_err(|e| OcmError::Validation(format!("Not a direct message: {}", e)))?;
+    if !data.recipient_did.starts_with("did:") {
+        return Err(OcmError::Validation(format!(
+            "Direct message recipient {} is not a DID",
+            data.recipient_did
+        )));
+    }
+    if !data
+        .envelope
+        .readers
+        .iter()
+        .any(|reader| reader.did == data.recipient_did)
+    {
+        return Err(OcmError::Validation(
+            "Direct message is not sealed to its recipient".to_string(),
+        ));
+    }
+    Ok(())
+}
+
+fn validate_receipt(memory_data: &str) -> Result<()> {
+    let data: ReceiptData = serde_json::from_str(memory_data)
+        .map_err(|e| OcmError::Validation(format!("Not a receipt: {}", e)))?;
+    if data.memory_id.trim().is_empty() || !data.memory_did.starts_with("did:") {
+        return Err(OcmError::Validation(
+            "Receipt must name a memory and its author's DID".to_string(),
+        ));
+    }
+    Ok(())
+}
+
+fn validate_memory_merge(memory_data: &str) -> Result<()> {
+    let data: MemoryMergeData = serde_json::from_str(memory_data)
+        .map_err(|e| OcmError::Validation(format!("Not a memory merge: {}", e)))?;
+    if data.sources.len() < 2 {
+        return Err(OcmError::Validation(
+            "A memory merge needs at least two sources".to_string(),
+        ));
+    }
+    if data
+        .provenance
+        .values()
+        .any(|id| !data.sources.iter().any(|s| &s.memory_id == id))
+    {
+        return Err(OcmError::Validation(
+            "Memory merge provenance names a memory that is not a source".to_string(),
+        ));
+    }
+    Ok(())
+}
+
+fn validate_identity_lineage(memory_data: &str) -> Result<()> {
+    let data: IdentityLineageData = serde_json::from_str(memory_data)
+        .map_err(|e| OcmError::Validation(format!("Not an identity lineage: {}", e)))?;
+    if !data.legacy_did.starts_with("did:") || !data.did.starts_with("did:") {
+        return Err(OcmError::Validation(
+            "Identity lineage must link two DIDs".to_string(),
+        ));
+    }
+    if data.legacy_did == data.did {
+        return Err(OcmError::Validation(
+            "Identity lineage links a DID to itself".to_string(),
+        ));
+    }
+    Ok(())
+}
+
+fn builtin_types() -> Vec<BuiltinType> {
+    vec![
+        BuiltinType {
+            memory_type: "individual",
+            display_name: "Individual",
+            summary_fields: &["first_name", "last_name"],
+            contains_pii: true,
+            merge_strategy: MergeStrategy::Crdt,
+            sync_priority: None,
+            indexed_fields: &["last_name", "first_name", "email"],
+            validate: validate_individual,
+        },
+        BuiltinType {
+            memory_type: "proxy_individual",
+            display_name: "Individual (recorded by an organization)",
+            summary_fields: &["first_name", "last_name"],
+            contains_pii: true,
+            merge_strategy: MergeStrategy::Crdt,
+            sync_priority: None,
+            indexed_fields: &["last_name", "first_name", "email"],
+            validate: validate_individual,
+        },
+        BuiltinType {
+            memory_type: "attendance",
+            display_name: "Attendance",
+            summary_fields: &[],
+            contains_pii: false,
+            merge_strategy: MergeStrategy::Crdt,
+            sync_priority: None,
+            indexed_fields: &["individual_id", "date"],
+            validate: validate_json_object,
+        },
+        BuiltinType {
+            memory_type: "group_membership",
+            display_name: "Group membership",
+            summary_fields: &["group_did", "version"],
+            contains_pii: false,
+            merge_strategy: MergeStrategy::Immutable, // Changes are new versions, not edits
+            sync_priority: Some(SyncPriority::Critical),
+            indexed_fields: &["group_did"],
+            validate: validate_json_object,
+        },
+        BuiltinType {
+            memory_type: "retention_deletion",
+            display_name: "Retention deletion",
+            summary_fields: &["memory_id", "action"],
+            contains_pii: false,
+            merge_strategy: MergeStrategy::Immutable,
+            sync_priority: Some(SyncPriority::Critical),
+            indexed_fields: &["memory_id"],
+            validate: validate_json_object,
+        },
+        BuiltinType {
+            memory_type: MEMORY_TAG_MEMORY_TYPE,
+            display_name: "Tag",
+            summary_fields: &["memory_id", "tag", "removed"],
+            contains_pii: false,
+            merge_strategy: MergeStrategy::Immutable, // Each operation is its own memory
+            sync_priority: None,
+            indexed_fields: &["memory_id", "tag"],
+            validate: validate_memory_tag,
+        },
+        BuiltinType {
+            memory_type: ANNOTATION_MEMORY_TYPE,
+            display_name: "Annotation",
+            summary_fields: &["kind", "body"],
+            contains_pii: true, // Free text about a memory that may itself hold PII
+            merge_strategy: MergeStrategy::Immutable, // Amend by annotating the annotation
+            sync_priority: None,
+            indexed_fields: &["target_memory_id"],
+            validate: validate_annotation,
+        },
+        BuiltinType {
+            memory_type: CONTACT_MEMORY_TYPE,
+            display_name: "Contact",
+            summary_fields: &["display_name", "contact_did"],
+            contains_pii: true,
+            merge_strategy: MergeStrategy::Immutable, // Each change is its own memory
+            sync_priority: None,
+            indexed_fields: &["contact_did"],
+            validate: validate_contact,
+        },
+        BuiltinType {
+            memory_type: DIRECT_MESSAGE_MEMORY_TYPE,
+            display_name: "Direct message",
+            summary_fields: &["recipient_did"], // The body is sealed
+            contains_pii: true,
+            merge_strategy: MergeStrategy::Immutable,
+            sync_priority: None,
+            indexed_fields: &["recipient_did"],
+            validate: validate_direct_message,
+        },
+        BuiltinType {
+            memory_type: RECEIPT_MEMORY_TYPE,
+            display_name: "Receipt",
+            summary_fields: &["memory_id", "kind"],
+            contains_pii: false,
+            merge_strategy: MergeStrategy::Immutable,
+            sync_priority: None,
+            indexed_fields: &["memory_id"],
+            validate: validate_receipt,
+        },
+        BuiltinType {
+            memory_type: IDENTITY_LINEAGE_MEMORY_TYPE,
+            display_name: "Identity lineage",
+            summary_fields: &["legacy_did", "did"],
+            contains_pii: false,
+            merge_strategy: MergeStrategy::Immutable,
+            sync_priority: None,
+            indexed_fields: &["legacy_did"],
+            validate: validate_identity_lineage,
+        },
+        BuiltinType {
+            memory_type: MEMORY_MERGE_MEMORY_TYPE,
+            display_name: "Memory merge",
+            summary_fields: &["merged_memory_id", "memory_type"],
+            contains_pii: false,
+            merge_strategy: MergeStrategy::Immutable,
+            sync_priority: None,
+            indexed_fields: &["merged_memory_id"],
+            validate: validate_memory_merge,
+        },
+        BuiltinType {
+            memory_type: TOMBSTONE_MEMORY_TYPE,
+            display_name: "Erased memory",
+            summary_fields: &[],
+            contains_pii: false,
+            merge_strategy: MergeStrategy::LastWriterWins,
+            sync_priority: Some(SyncPriority::Critical), // Erasure should reach every copy quickly
+            indexed_fields: &[],
+            validate: |_| Ok(()), // Tombstones carry no content
+        },
+    ]
+}
+
+/// Handlers per memory type, consulted when memories are created and synced.
+/// Types nobody registered stay free-form: they pass validation and merge as CRDTs
+#[derive(Default)]
+pub struct MemoryTypeRegistry {
+    handlers: RwLock<HashMap<String, Arc<dyn MemoryTypeHandler>>>,
+}
+
+impl MemoryTypeRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// A registry that already knows the types ocm-core itself writes
+    pub fn with_builtin_types() -> Self {
+        let registry = Self::new();
+        for builtin in builtin_types() {
+            registry.register(Arc::new(builtin));
+        }
+        registry
+    }
+
+    /// Register a handler, replacing and returning any earlier one for the same type
+    pub fn register(
+        &self,
+        handler: Arc<dyn MemoryTypeHandler>,
+    ) -> Option<Arc<dyn MemoryTypeHandler>> {
+        self.handlers
+            .write()
+            .unwrap_or_else(|e| e.into_inner())
+            .insert(handler.memory_type().to_string(), handler)
+    }
+
+    pub fn handler(&self, memory_type: &str) -> Option<Arc<dyn MemoryTypeHandler>> {
+        self.handlers
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .get(memory_type)
+            .cloned()
+    }
+
+    pub fn registered_types(&self) -> Vec<String> {
+        let mut types: Vec<String> = self
+            .handlers
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .keys()
+            .cloned()
+            .collect();
+        types.sort();
+        types
+    }
+
+    pub fn validate(&self, memory_type: &str, memory_data: &str) -> Result<()> {
+        match self.handler(memory_type) {
+            Some(handler) => handler.validate(memory_data),
+            None => Ok(()),
+        }
+    }
+
+    /// Validate and create an unsigned memory
+    pub fn create_memory(
+        &self,
+        did: &Did,
+        memory_type: &str,
+        memory_data: &str,
+    ) -> Result<SignedMemory> {
+        self.validate(memory_type, memory_data)?;
+        Ok(SignedMemory::new(did, memory_type, memory_data))
+    }
+
+    pub fn merge_strategy(&self, memory_type: &str) -> MergeStrategy {
+        self.handler(memory_type)
+            .map(|handler| handler.merge_strategy())
+            .unwrap_or(MergeStrategy::Crdt)
+    }
+
+    pub fn render(&self, memory_type: &str) -> RenderMetadata {
+        match self.handler(memory_type) {
+            Some(handler) => handler.render(),
+            None => RenderMetadata {
+                display_name: memory_type.to_string(),
+                summary_fields: vec![],
+                contains_pii: false,
+            },
+        }
+    }
+
+    /// Every registered type's indexed fields, each once
+    pub fn indexed_fields(&self) -> Vec<String> {
+        let mut fields: Vec<String> = self
+            .handlers
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .values()
+            .flat_map(|handler| handler.indexed_fields())
+            .collect();
+        fields.sort();
+        fields.dedup();
+        fields
+    }
+
+    /// Registered sync priority defaults, memory_type -> priority
+    pub fn sync_priorities(&self) -> HashMap<String, SyncPriority> {
+        self.handlers
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .iter()
+            .filter_map(|(memory_type, handler)| {
+                handler
+                    .sync_priority()
+                    .map(|priority| (memory_type.clone(), priority))
+            })
+            .collect()
+    }
+}
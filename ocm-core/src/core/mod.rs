@@ -1,5 +1,12 @@
+pub mod correlation;
 pub mod error;
+pub mod ids;
+pub mod memory_types;
 pub mod models;
+pub mod tenancy;
+
+pub use ocm_protocol::{redact, relay};
 
 pub use error::*;
+pub use ids::*;
 pub use models::*;
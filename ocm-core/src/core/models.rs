@@ -1,9 +1,19 @@
+use crate::core::ids::{Did, MemoryId, PeerId, TokenId};
+use crate::core::redact::Redacted;
 #[cfg(feature = "native")]
 use rusqlite::{Result, Row};
 use serde::{Deserialize, Serialize};
-use sha2::{Digest, Sha256};
+use std::fmt;
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+// Memories and their headers travel on the wire, so they live in ocm-protocol
+pub use ocm_protocol::handle::HandleVerification;
+pub use ocm_protocol::memory::*;
+pub use ocm_protocol::presence::{PresenceStatus, PresenceUpdate};
+pub use ocm_protocol::verification::{
+    OrganizationBadge, OrganizationVerification, OrganizationVerificationMethod,
+};
+
+#[derive(Clone, Serialize, Deserialize)]
 pub struct Individual {
     pub id: String,
     pub first_name: String,
@@ -16,7 +26,7 @@ pub struct Individual {
     pub updated_on: String,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Clone, Serialize, Deserialize)]
 pub struct Location {
     pub id: String,
     pub email: Option<String>,
@@ -31,6 +41,43 @@ pub struct Location {
     pub updated_on: String,
 }
 
+// Personal fields are masked so models can be logged or put in errors safely
+impl fmt::Debug for Individual {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Individual")
+            .field("id", &self.id)
+            .field("first_name", &Redacted::pii(&self.first_name))
+            .field(
+                "middle_name",
+                &self.middle_name.as_deref().map(Redacted::pii),
+            )
+            .field("last_name", &Redacted::pii(&self.last_name))
+            .field("dob", &self.dob.as_deref().map(Redacted::pii))
+            .field("phone", &self.phone.as_deref().map(Redacted::pii))
+            .field("email", &self.email.as_deref().map(Redacted::pii))
+            .field("employer", &self.employer)
+            .field("updated_on", &self.updated_on)
+            .finish()
+    }
+}
+
+impl fmt::Debug for Location {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Location")
+            .field("id", &self.id)
+            .field("email", &self.email.as_deref().map(Redacted::pii))
+            .field("phone", &self.phone.as_deref().map(Redacted::pii))
+            .field("address", &self.address.as_deref().map(Redacted::pii))
+            .field("city", &self.city)
+            .field("state", &self.state)
+            .field("zip", &self.zip)
+            .field("country", &self.country)
+            .field("coordinates", &self.coordinates_lat.map(|_| "[REDACTED]"))
+            .field("updated_on", &self.updated_on)
+            .finish()
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Affiliation {
     pub id: String,
@@ -178,63 +225,162 @@ impl DatabaseModel for Individual {
     }
 }
 
+#[cfg(feature = "native")]
+impl DatabaseModel for SignedMemory {
+    fn table_name() -> &'static str {
+        "signed_memory"
+    }
+
+    fn id(&self) -> &str {
+        &self.id
+    }
+
+    fn from_row(row: &Row) -> Result<Self> {
+        let co_signatures_json: String = row.get(8)?;
+        let co_signatures = serde_json::from_str(&co_signatures_json).map_err(|e| {
+            rusqlite::Error::FromSqlConversionFailure(8, rusqlite::types::Type::Text, Box::new(e))
+        })?;
+
+        Ok(SignedMemory {
+            id: row.get(0)?,
+            did: row.get(1)?,
+            memory_type: row.get(2)?,
+            memory_data: row.get(3)?,
+            content_hash: row.get(4)?,
+            signature: row.get(5)?,
+            timestamp: row.get(6)?,
+            updated_on: row.get(7)?,
+            co_signatures,
+        })
+    }
+
+    fn insert_sql() -> &'static str {
+        "INSERT INTO signed_memory (id, did, memory_type, memory_data, content_hash, signature, timestamp, updated_on, co_signatures, body_hash) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)"
+    }
+
+    fn update_sql() -> &'static str {
+        "UPDATE signed_memory SET did = ?2, memory_type = ?3, memory_data = ?4, content_hash = ?5, signature = ?6, timestamp = ?7, updated_on = ?8, co_signatures = ?9, body_hash = ?10 WHERE id = ?1"
+    }
+
+    // Bodies held in the content store are resolved through body_hash
+    fn select_fields() -> &'static str {
+        "id, did, memory_type, COALESCE((SELECT ocm_content(memory_data, dictionary_id) FROM memory_content WHERE memory_content.content_hash = signed_memory.body_hash), memory_data), content_hash, signature, timestamp, updated_on, co_signatures"
+    }
+}
+
+/// A peer's signed statement that it saw `content_hash` at `witnessed_at`
 #[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct SignedMemory {
+pub struct WitnessReceipt {
     pub id: String,
-    pub did: String,          // DID:PLC identifier of the author
-    pub memory_type: String,  // Type of memory (individual, location, etc.)
-    pub memory_data: String,  // JSON serialized memory content
-    pub content_hash: String, // SHA256 hash of memory_data
-    pub signature: String,    // Cryptographic signature
-    pub timestamp: String,    // ISO 8601 timestamp
-    pub updated_on: String,
+    pub memory_id: MemoryId,
+    pub content_hash: String,
+    pub witness_did: Did,
+    pub witnessed_at: String,
+    pub signature: String, // Base64 encoded Ed25519 signature by the witness
 }
 
-impl SignedMemory {
-    pub fn new(did: &str, memory_type: &str, memory_data: &str) -> Self {
-        let content_hash = Self::compute_hash(memory_data);
-        let timestamp = chrono::Utc::now().to_rfc3339();
-        let updated_on = timestamp.clone();
-
-        SignedMemory {
+impl WitnessReceipt {
+    pub fn new(memory_id: &MemoryId, content_hash: &str, witness_did: &Did) -> Self {
+        WitnessReceipt {
             id: uuid::Uuid::new_v4().to_string(),
-            did: did.to_string(),
-            memory_type: memory_type.to_string(),
-            memory_data: memory_data.to_string(),
-            content_hash,
+            memory_id: memory_id.clone(),
+            content_hash: content_hash.to_string(),
+            witness_did: witness_did.clone(),
+            witnessed_at: chrono::Utc::now().to_rfc3339(),
             signature: String::new(), // Will be set during signing
-            timestamp,
-            updated_on,
         }
     }
 
-    pub fn compute_hash(data: &str) -> String {
-        let mut hasher = Sha256::new();
-        hasher.update(data.as_bytes());
-        hex::encode(hasher.finalize())
-    }
-
     pub fn get_signing_payload(&self) -> String {
-        // Create deterministic payload for signing
         serde_json::json!({
-            "did": self.did,
-            "memory_type": self.memory_type,
             "content_hash": self.content_hash,
-            "timestamp": self.timestamp
+            "witness_did": self.witness_did,
+            "witnessed_at": self.witnessed_at
         })
         .to_string()
     }
+}
+
+#[cfg(feature = "native")]
+impl DatabaseModel for WitnessReceipt {
+    fn table_name() -> &'static str {
+        "witness_receipt"
+    }
+
+    fn id(&self) -> &str {
+        &self.id
+    }
 
-    pub fn verify_hash(&self) -> bool {
-        let computed_hash = Self::compute_hash(&self.memory_data);
-        computed_hash == self.content_hash
+    fn from_row(row: &Row) -> Result<Self> {
+        Ok(WitnessReceipt {
+            id: row.get(0)?,
+            memory_id: row.get(1)?,
+            content_hash: row.get(2)?,
+            witness_did: row.get(3)?,
+            witnessed_at: row.get(4)?,
+            signature: row.get(5)?,
+        })
+    }
+
+    fn insert_sql() -> &'static str {
+        "INSERT INTO witness_receipt (id, memory_id, content_hash, witness_did, witnessed_at, signature) VALUES (?1, ?2, ?3, ?4, ?5, ?6)"
+    }
+
+    fn update_sql() -> &'static str {
+        "UPDATE witness_receipt SET memory_id = ?2, content_hash = ?3, witness_did = ?4, witnessed_at = ?5, signature = ?6 WHERE id = ?1"
+    }
+
+    fn select_fields() -> &'static str {
+        "id, memory_id, content_hash, witness_did, witnessed_at, signature"
+    }
+}
+
+/// Audit entry recording that a memory was tombstoned for a data subject erasure request
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ErasureRecord {
+    pub id: String,
+    pub subject_did: Did,     // DID the erasure request was made for
+    pub memory_id: MemoryId,  // Tombstoned memory
+    pub content_hash: String, // Hash of the scrubbed content, kept for the transparency log
+    pub erased_by_did: Did,   // Operator identity that carried out the erasure
+    pub erased_at: String,    // ISO 8601 timestamp
+    pub signature: String,    // Base64 encoded Ed25519 signature by the operator
+}
+
+impl ErasureRecord {
+    pub fn new(
+        subject_did: &Did,
+        memory_id: &MemoryId,
+        content_hash: &str,
+        erased_by_did: &Did,
+    ) -> Self {
+        ErasureRecord {
+            id: uuid::Uuid::new_v4().to_string(),
+            subject_did: subject_did.clone(),
+            memory_id: memory_id.clone(),
+            content_hash: content_hash.to_string(),
+            erased_by_did: erased_by_did.clone(),
+            erased_at: chrono::Utc::now().to_rfc3339(),
+            signature: String::new(), // Will be set during signing
+        }
+    }
+
+    pub fn get_signing_payload(&self) -> String {
+        serde_json::json!({
+            "subject_did": self.subject_did,
+            "memory_id": self.memory_id,
+            "content_hash": self.content_hash,
+            "erased_by_did": self.erased_by_did,
+            "erased_at": self.erased_at
+        })
+        .to_string()
     }
 }
 
 #[cfg(feature = "native")]
-impl DatabaseModel for SignedMemory {
+impl DatabaseModel for ErasureRecord {
     fn table_name() -> &'static str {
-        "signed_memory"
+        "erasure_record"
     }
 
     fn id(&self) -> &str {
@@ -242,73 +388,1221 @@ impl DatabaseModel for SignedMemory {
     }
 
     fn from_row(row: &Row) -> Result<Self> {
-        Ok(SignedMemory {
+        Ok(ErasureRecord {
             id: row.get(0)?,
-            did: row.get(1)?,
-            memory_type: row.get(2)?,
-            memory_data: row.get(3)?,
-            content_hash: row.get(4)?,
-            signature: row.get(5)?,
-            timestamp: row.get(6)?,
-            updated_on: row.get(7)?,
+            subject_did: row.get(1)?,
+            memory_id: row.get(2)?,
+            content_hash: row.get(3)?,
+            erased_by_did: row.get(4)?,
+            erased_at: row.get(5)?,
+            signature: row.get(6)?,
         })
     }
 
     fn insert_sql() -> &'static str {
-        "INSERT INTO signed_memory (id, did, memory_type, memory_data, content_hash, signature, timestamp, updated_on) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)"
+        "INSERT INTO erasure_record (id, subject_did, memory_id, content_hash, erased_by_did, erased_at, signature) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)"
     }
 
     fn update_sql() -> &'static str {
-        "UPDATE signed_memory SET did = ?2, memory_type = ?3, memory_data = ?4, content_hash = ?5, signature = ?6, timestamp = ?7, updated_on = ?8 WHERE id = ?1"
+        "UPDATE erasure_record SET subject_did = ?2, memory_id = ?3, content_hash = ?4, erased_by_did = ?5, erased_at = ?6, signature = ?7 WHERE id = ?1"
     }
 
     fn select_fields() -> &'static str {
-        "id, did, memory_type, memory_data, content_hash, signature, timestamp, updated_on"
+        "id, subject_did, memory_id, content_hash, erased_by_did, erased_at, signature"
     }
 }
 
+/// Everything held locally that references a data subject's DID
 #[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct ClaimToken {
+pub struct DataSubjectExport {
+    pub subject_did: Did,
+    pub generated_at: String,
+    pub memories: Vec<SignedMemory>,
+    pub proxy_memories: Vec<ProxyMemory>,
+    pub claim_tokens: Vec<ClaimToken>,
+    pub witness_receipts: Vec<WitnessReceipt>,
+    pub erasure_records: Vec<ErasureRecord>,
+}
+
+pub const MEMORY_STORED_EVENT: &str = "memory_stored";
+pub const SYNC_COMPLETED_EVENT: &str = "sync_completed";
+pub const CLAIM_REDEEMED_EVENT: &str = "claim_redeemed";
+pub const CLAIM_LOCKOUT_EVENT: &str = "claim_lockout"; // Repeated failed claims locked a client out
+pub const CONFLICT_DETECTED_EVENT: &str = "conflict_detected";
+pub const KEY_ROTATED_EVENT: &str = "key_rotated";
+pub const PANIC_EVENT: &str = "panic"; // A panic anywhere in the recording process
+
+/// A key once published in a DID's PLC document
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DidKeyPin {
+    pub did: Did,
+    pub public_key: String, // Multibase, as in the DID document
+    pub first_seen_at: String,
+    pub last_seen_at: String,
+    pub removed_at: Option<String>, // None while the document still publishes it
+}
+
+/// How a refresh changed a DID's published keys
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct DidKeyChange {
+    pub first_pinned: bool, // No keys were pinned for the DID before
+    pub added: Vec<String>,
+    pub removed: Vec<String>,
+}
+
+impl DidKeyChange {
+    /// Keys changed after the DID had been pinned
+    pub fn is_rotation(&self) -> bool {
+        !(self.first_pinned || self.added.is_empty() && self.removed.is_empty())
+    }
+}
+
+/// A memory whose author was checked against an attached PLC operation chain
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OfflineVerification {
+    pub memory_id: MemoryId,
+    pub did: Did,
+    pub public_key: String, // Multibase key the chain ended on
+    pub verified_at: String,
+    pub rechecked_at: Option<String>,
+    pub confirmed: Option<bool>,
+}
+
+/// Trust circle of peers sharing a sync policy
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PeerGroup {
+    pub name: String,
+    pub memory_types: Option<Vec<String>>, // None shares every memory type
+    #[serde(default)]
+    pub tags: Option<Vec<String>>, // None shares memories regardless of their tags
+    pub members: Vec<String>,              // Peer node IDs, DIDs or contacts:{group}
+}
+
+impl PeerGroup {
+    pub fn allows_memory_type(&self, memory_type: &str) -> bool {
+        match &self.memory_types {
+            Some(types) => types.iter().any(|t| t == memory_type),
+            None => true,
+        }
+    }
+
+    /// Whether a memory carrying `memory_tags` may be shared under the group's tag filter.
+    /// Tag operations themselves are shared when they concern one of the group's tags
+    pub fn allows_tags(&self, memory: &SignedMemory, memory_tags: &[String]) -> bool {
+        let Some(tags) = &self.tags else {
+            return true;
+        };
+        if memory.memory_type == MEMORY_TAG_MEMORY_TYPE {
+            return serde_json::from_str::<MemoryTagData>(&memory.memory_data)
+                .is_ok_and(|data| tags.contains(&data.tag));
+        }
+        memory_tags.iter().any(|tag| tags.contains(tag))
+    }
+
+    pub fn has_member(&self, peer_id: &str, did: Option<&str>) -> bool {
+        self.members
+            .iter()
+            .any(|m| m == peer_id || Some(m.as_str()) == did)
+    }
+
+    /// Whether a member entry such as `contacts:family` names one of the contact's groups.
+    /// `verified-contacts:family` only admits contacts whose safety number was confirmed
+    pub fn includes_contact(&self, contact: &Contact) -> bool {
+        let in_group = |group: &str| contact.groups.iter().any(|g| g == group);
+        let verified = contact.verification == ContactVerification::Verified;
+        self.members.iter().any(|m| {
+            m.strip_prefix(CONTACT_GROUP_MEMBER_PREFIX)
+                .is_some_and(in_group)
+                || (verified
+                    && m.strip_prefix(VERIFIED_CONTACT_GROUP_MEMBER_PREFIX)
+                        .is_some_and(in_group))
+        })
+    }
+}
+
+/// Pending broadcast of a locally authored memory
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OutboxEntry {
+    pub outbox_id: i64,
+    pub memory_id: MemoryId,
+    pub created_at: String,
+    pub attempts: u32,
+    pub last_error: Option<String>,
+}
+
+/// How far sync with one peer has progressed, persisted so a restart resumes from it
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SyncWatermark {
+    pub peer_node_id: String,
+    pub synced_at: String,
+    pub memory_count: Option<u64>, // Memories held locally right after the sync
+    pub memory_digest: Option<String>, // SHA-256 over their sorted content hashes
+}
+
+/// Entry in the local activity feed streamed to clients
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ActivityEvent {
+    pub event_id: i64, // Monotonic, used to resume a stream
+    pub event_type: String,
+    pub did: Option<Did>,
+    pub memory_type: Option<String>,
+    pub memory_id: Option<MemoryId>,
+    pub payload: serde_json::Value,
+    pub created_at: String,
+}
+
+/// Memory type of signed tag operations, which carry tags between nodes
+pub const MEMORY_TAG_MEMORY_TYPE: &str = "memory_tag";
+
+/// memory_data of a memory_tag memory: one tag added to or removed from one memory
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MemoryTagData {
+    pub memory_id: MemoryId,
+    pub tag: String,
+    #[serde(default)]
+    pub removed: bool,
+}
+
+/// Lowercase a tag and check it is 1-64 letters, digits, '-', '_', ':' or '.'
+pub fn normalize_tag(tag: &str) -> Result<String, String> {
+    let tag = tag.trim().to_lowercase();
+    let valid = !tag.is_empty()
+        && tag.len() <= 64
+        && tag
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | ':' | '.'));
+    if valid {
+        Ok(tag)
+    } else {
+        Err(format!(
+            "Invalid tag '{}': use up to 64 letters, digits, '-', '_', ':' or '.'",
+            tag
+        ))
+    }
+}
+
+/// Memory type of contact book entries
+pub const CONTACT_MEMORY_TYPE: &str = "contact";
+
+/// Memory type of direct messages, encrypted to their recipient and sender
+pub const DIRECT_MESSAGE_MEMORY_TYPE: &str = "direct_message";
+
+/// Memory type of signed delivery and read receipts, sent back to a memory's author
+pub const RECEIPT_MEMORY_TYPE: &str = "receipt";
+
+/// Memory type of the record linking a legacy stub_plc DID to the PLC identity that
+/// replaced it, signed by the new identity
+pub const IDENTITY_LINEAGE_MEMORY_TYPE: &str = "identity_lineage";
+
+/// Memory type of a record handing a memory from its owner to a new owner. The owner
+/// signs it when issuing the handover token and the new owner co-signs it on claiming
+pub const CUSTODY_HANDOVER_MEMORY_TYPE: &str = "custody_handover";
+
+/// Memory type of a record consolidating several of a DID's memories into one. Peers
+/// receiving it archive the originals it supersedes
+pub const MEMORY_MERGE_MEMORY_TYPE: &str = "memory_merge";
+
+/// Memory type of a verified organization vouching for another organization's DID
+pub const ORGANIZATION_ATTESTATION_MEMORY_TYPE: &str = "organization_attestation";
+
+/// Memory types only ever shared with their author's other devices and, for
+/// direct messages and receipts, the one other DID they concern
+pub const PRIVATE_MEMORY_TYPES: &[&str] = &[
+    CONTACT_MEMORY_TYPE,
+    DIRECT_MESSAGE_MEMORY_TYPE,
+    RECEIPT_MEMORY_TYPE,
+];
+
+pub fn is_private_memory_type(memory_type: &str) -> bool {
+    PRIVATE_MEMORY_TYPES.contains(&memory_type)
+}
+
+/// DIDs a private memory may be shared with, or None for memories that are not private.
+/// A direct message or receipt that cannot be parsed goes to its author's devices only
+pub fn private_audience(memory: &SignedMemory) -> Option<Vec<Did>> {
+    if !is_private_memory_type(&memory.memory_type) {
+        return None;
+    }
+    let mut audience = vec![memory.did.clone()];
+    match memory.memory_type.as_str() {
+        DIRECT_MESSAGE_MEMORY_TYPE => {
+            if let Ok(data) = serde_json::from_str::<DirectMessageData>(&memory.memory_data) {
+                audience.push(data.recipient_did);
+            }
+        }
+        RECEIPT_MEMORY_TYPE => {
+            if let Ok(data) = serde_json::from_str::<ReceiptData>(&memory.memory_data) {
+                audience.push(data.memory_did);
+            }
+        }
+        _ => {}
+    }
+    Some(audience)
+}
+
+/// The content key of a sealed envelope, wrapped for one reader
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WrappedKey {
+    pub did: Did,
+    pub ephemeral_public_key: String, // Base64 X25519 key, fresh per reader
+    pub nonce: String,
+    pub wrapped_key: String,
+}
+
+/// Ciphertext readable only by the DIDs it lists; base64 throughout
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SealedEnvelope {
+    pub nonce: String,
+    pub ciphertext: String,
+    pub readers: Vec<WrappedKey>,
+}
+
+/// memory_data of a direct_message memory
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DirectMessageData {
+    pub recipient_did: Did,
+    pub envelope: SealedEnvelope,
+}
+
+/// A decrypted direct message held by its sender or recipient
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DirectMessage {
+    pub id: MemoryId, // ID of the direct_message memory
+    pub sender_did: Did,
+    pub recipient_did: Did,
+    pub body: String,
+    pub sent_at: String,
+    pub read_at: Option<String>,
+}
+
+/// What a receipt acknowledges
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ReceiptKind {
+    Delivered, // The memory reached one of the recipient's devices
+    Read,      // The recipient opened it
+}
+
+impl ReceiptKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ReceiptKind::Delivered => "delivered",
+            ReceiptKind::Read => "read",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "delivered" => Some(ReceiptKind::Delivered),
+            "read" => Some(ReceiptKind::Read),
+            _ => None,
+        }
+    }
+}
+
+/// memory_data of a receipt memory, signed by the DID that received the memory
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReceiptData {
+    pub memory_id: MemoryId,
+    pub memory_did: Did, // Author of the acknowledged memory, who the receipt goes to
+    pub kind: ReceiptKind,
+}
+
+/// memory_data of an identity_lineage memory. The legacy DID's placeholder keys cannot
+/// sign, so the link rests on the new identity and the node that migrated it
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IdentityLineageData {
+    pub legacy_did: Did,
+    pub did: Did,
+    pub migrated_at: String,
+    pub memories: Vec<MigratedMemory>,
+}
+
+/// memory_data of a custody handover. The new owner's copy is recorded in the
+/// custody_handover table once claimed, since co-signing cannot change the content
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CustodyHandoverData {
+    pub claim_token_id: TokenId,
+    pub memory_id: MemoryId, // The memory handed over
+    pub content_hash: String,
+    pub from_did: Did,
+    pub to_did: Option<Did>, // None if whoever holds the token may claim it
+    pub issued_at: String,
+}
+
+/// memory_data of a memory merge, signed by the owner of every source
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MemoryMergeData {
+    pub merged_memory_id: MemoryId, // The consolidated memory
+    pub memory_type: String,
+    pub sources: Vec<MergeSource>,
+    pub provenance: std::collections::BTreeMap<String, MemoryId>, // Field -> source memory
+    pub merged_at: String,
+}
+
+/// A memory superseded by a merge, pinned by its content hash
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MergeSource {
+    pub memory_id: MemoryId,
+    pub content_hash: String,
+}
+
+/// memory_data of an organization attestation, signed by the vouching organization
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OrganizationAttestationData {
+    pub organization_did: Did, // The organization vouched for
+    pub attested_by: Did,
+    pub issued_at: String,
+}
+
+/// A legacy memory and the re-signed copy that replaces it
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MigratedMemory {
+    pub legacy_id: MemoryId,
+    pub memory_id: MemoryId,
+}
+
+/// One recipient's acknowledgments of a memory
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MemoryReceipt {
+    pub did: Did,
+    pub delivered_at: Option<String>,
+    pub read_at: Option<String>,
+}
+
+/// Which DIDs have acknowledged a memory, and how far
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DeliveryStatus {
+    pub memory_id: MemoryId,
+    pub receipts: Vec<MemoryReceipt>,
+}
+
+/// Prefix of a peer group member entry that admits every DID in a contact group
+pub const CONTACT_GROUP_MEMBER_PREFIX: &str = "contacts:";
+/// Prefix of a peer group member entry that admits only verified contacts in a group
+pub const VERIFIED_CONTACT_GROUP_MEMBER_PREFIX: &str = "verified-contacts:";
+
+/// How much a contact's key is trusted
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum ContactVerification {
+    #[default]
+    Tofu, // Key pinned the first time it was seen, never compared out of band
+    Verified, // Fingerprint confirmed by the user out of band
+}
+
+impl ContactVerification {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ContactVerification::Tofu => "tofu",
+            ContactVerification::Verified => "verified",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "tofu" => Some(ContactVerification::Tofu),
+            "verified" => Some(ContactVerification::Verified),
+            _ => None,
+        }
+    }
+}
+
+/// memory_data of a contact memory: the full state of one contact, or its removal
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ContactData {
+    pub contact_did: Did,
+    pub display_name: String,
+    #[serde(default)]
+    pub verification: ContactVerification,
+    #[serde(default)]
+    pub key_fingerprint: Option<String>,
+    #[serde(default)]
+    pub notes: String,
+    #[serde(default)]
+    pub groups: Vec<String>,
+    #[serde(default)]
+    pub removed: bool,
+}
+
+/// A known DID in the local identity's contact book
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Contact {
+    pub did: Did,
+    pub display_name: String,
+    pub verification: ContactVerification,
+    pub key_fingerprint: Option<String>,
+    pub notes: String,
+    pub groups: Vec<String>,
+    pub updated_at: String,
+}
+
+/// The safety number shared with a contact, to be compared out of band
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SafetyNumber {
+    pub did: Did,
+    pub safety_number: String,
+    pub verification: ContactVerification,
+    pub key_changed: bool, // The contact's key differs from the one pinned before
+}
+
+/// Memory type of notes and corrections attached to other memories without changing them
+pub const ANNOTATION_MEMORY_TYPE: &str = "annotation";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum AnnotationKind {
+    #[default]
+    Note,
+    Correction, // Claims the target is wrong; the target itself stays as signed
+}
+
+/// memory_data of an annotation. Replies are annotations whose target is another annotation
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AnnotationData {
+    pub target_memory_id: MemoryId,
+    pub target_content_hash: String,
+    #[serde(default)]
+    pub kind: AnnotationKind,
+    pub body: String,
+}
+
+/// Whether the target still has the content an annotation was written against
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AnnotationTargetStatus {
+    Matches,
+    Changed, // The target was edited since; the annotation may no longer apply
+    Missing, // The target is not held locally
+}
+
+/// An annotation with its verification result and the replies to it, oldest first
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AnnotationThread {
+    pub annotation: SignedMemory,
+    pub data: AnnotationData,
+    pub target_status: AnnotationTargetStatus,
+    pub replies: Vec<AnnotationThread>,
+}
+
+/// A tag in use, with how many memories currently carry it
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TagCount {
+    pub tag: String,
+    pub memory_count: u64,
+}
+
+/// Operator-written script run for matching activity events
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AutomationRule {
+    pub name: String,
+    pub description: Option<String>,
+    pub event_type: String,
+    pub memory_type: Option<String>, // None matches events about any (or no) memory type
+    pub script: String,              // Rhai source
+    pub enabled: bool,
+    pub updated_at: String,
+    pub last_run_at: Option<String>,
+    pub last_error: Option<String>,
+}
+
+impl AutomationRule {
+    pub fn matches(&self, event: &ActivityEvent) -> bool {
+        self.enabled
+            && self.event_type == event.event_type
+            && self
+                .memory_type
+                .as_ref()
+                .is_none_or(|t| event.memory_type.as_ref() == Some(t))
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TenantStatus {
+    Active,
+    Suspended, // Data is kept but the tenant's API is refused
+}
+
+impl TenantStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            TenantStatus::Active => "active",
+            TenantStatus::Suspended => "suspended",
+        }
+    }
+
+    pub fn parse(status: &str) -> Option<Self> {
+        match status {
+            "active" => Some(TenantStatus::Active),
+            "suspended" => Some(TenantStatus::Suspended),
+            _ => None,
+        }
+    }
+}
+
+/// An organization hosted on a multi-tenant node. Quotas left as None use the node defaults
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Tenant {
+    pub id: String,
+    pub name: String,
+    pub status: TenantStatus,
+    pub max_memories: Option<u64>,
+    pub requests_per_minute: Option<u32>,
+    pub created_at: String,
+    pub updated_at: String,
+}
+
+/// A tenant's consumption against its quotas
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TenantUsage {
+    pub tenant_id: String,
+    pub memories: u64,
+    pub max_memories: u64,
+    pub requests_per_minute: u32,
+}
+
+/// The signed part of an invite code: who invites, into what, until when
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct InvitePayload {
+    pub invitation_id: String,
+    pub issuer_did: Did,
+    pub group_name: Option<String>, // None invites into the federation itself
+    pub expires_at: String,
+}
+
+/// An invitation as recorded by the node that issued or redeemed it
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Invitation {
+    pub id: String,
+    pub issuer_did: Did,
+    pub group_name: Option<String>,
+    pub expires_at: String,
+    pub created_at: String,
+    pub redeemed_by_did: Option<Did>,
+    pub redeemed_at: Option<String>,
+    pub revoked: bool,
+}
+
+impl Invitation {
+    pub fn from_payload(payload: &InvitePayload) -> Self {
+        Self {
+            id: payload.invitation_id.clone(),
+            issuer_did: payload.issuer_did.clone(),
+            group_name: payload.group_name.clone(),
+            expires_at: payload.expires_at.clone(),
+            created_at: chrono::Utc::now().to_rfc3339(),
+            redeemed_by_did: None,
+            redeemed_at: None,
+            revoked: false,
+        }
+    }
+
+    pub fn is_expired(&self) -> bool {
+        match chrono::DateTime::parse_from_rfc3339(&self.expires_at) {
+            Ok(expiry) => chrono::Utc::now() > expiry.with_timezone(&chrono::Utc),
+            Err(_) => true,
+        }
+    }
+}
+
+/// A DID admitted to a closed federation
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FederationMember {
+    pub did: Did,
+    pub invited_by_did: Option<Did>, // None for founding members
+    pub invitation_id: Option<String>,
+    pub joined_at: String,
+}
+
+/// The last presence heard from a peer in a trusted peer group
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PeerPresence {
+    pub peer_id: PeerId,
+    pub did: Option<Did>,
+    pub online: bool, // Heard from within the offline timeout
+    pub status: Option<PresenceStatus>,
+    pub last_sync_at: Option<String>,
+    pub last_seen: String,
+}
+
+/// Where a push subscription is delivered
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PushKind {
+    WebPush, // A browser push service endpoint
+    Fcm,     // A Firebase Cloud Messaging registration token
+}
+
+impl PushKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            PushKind::WebPush => "web_push",
+            PushKind::Fcm => "fcm",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "web_push" => Some(PushKind::WebPush),
+            "fcm" => Some(PushKind::Fcm),
+            _ => None,
+        }
+    }
+}
+
+/// Events a subscriber can be pushed about
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PushEvent {
+    NewMemory,               // A memory addressed to the subscriber's DID arrived
+    ClaimRedeemed,           // Someone claimed a record the subscriber's organization created
+    ConflictNeedsResolution, // A memory of the subscriber's DID conflicts and needs a decision
+    KeyRotated,              // The subscriber's DID document changed keys unexpectedly
+}
+
+impl PushEvent {
+    pub const ALL: [PushEvent; 4] = [
+        PushEvent::NewMemory,
+        PushEvent::ClaimRedeemed,
+        PushEvent::ConflictNeedsResolution,
+        PushEvent::KeyRotated,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            PushEvent::NewMemory => "new_memory",
+            PushEvent::ClaimRedeemed => "claim_redeemed",
+            PushEvent::ConflictNeedsResolution => "conflict_needs_resolution",
+            PushEvent::KeyRotated => "key_rotated",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "new_memory" => Some(PushEvent::NewMemory),
+            "claim_redeemed" => Some(PushEvent::ClaimRedeemed),
+            "conflict_needs_resolution" => Some(PushEvent::ConflictNeedsResolution),
+            "key_rotated" => Some(PushEvent::KeyRotated),
+            _ => None,
+        }
+    }
+}
+
+/// A device registered to be pushed about events for one DID
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PushSubscription {
+    pub id: String,
+    pub did: Did,
+    pub kind: PushKind,
+    pub endpoint: String, // Push service URL, or the FCM registration token
+    pub p256dh: Option<String>, // Web Push client keys, kept for payload encryption
+    pub auth: Option<String>,
+    pub events: Vec<PushEvent>, // The subscriber's preferences
+    pub created_at: String,
+    pub last_pushed_at: Option<String>,
+    pub last_error: Option<String>,
+}
+
+/// Where a background job is in its life
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum JobState {
+    Queued,
+    Running,
+    Succeeded,
+    Failed,
+    Cancelled,
+}
+
+impl JobState {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            JobState::Queued => "queued",
+            JobState::Running => "running",
+            JobState::Succeeded => "succeeded",
+            JobState::Failed => "failed",
+            JobState::Cancelled => "cancelled",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "queued" => Some(JobState::Queued),
+            "running" => Some(JobState::Running),
+            "succeeded" => Some(JobState::Succeeded),
+            "failed" => Some(JobState::Failed),
+            "cancelled" => Some(JobState::Cancelled),
+            _ => None,
+        }
+    }
+
+    pub fn is_finished(&self) -> bool {
+        matches!(
+            self,
+            JobState::Succeeded | JobState::Failed | JobState::Cancelled
+        )
+    }
+}
+
+/// A long-running operation started through the API; `params` and `result` depend on its kind
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Job {
+    pub id: String,
+    pub kind: String,
+    pub state: JobState,
+    pub params: serde_json::Value,
+    pub progress: u64,
+    pub total: Option<u64>, // None until the job knows how much work it has
+    pub result: Option<serde_json::Value>,
+    pub error: Option<String>,
+    pub cancel_requested: bool,
+    pub created_by: Option<String>,
+    pub created_at: String,
+    pub started_at: Option<String>,
+    pub finished_at: Option<String>,
+}
+
+/// A DID's current human-readable handle
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HandleRecord {
+    pub handle: String,
+    pub did: Did,
+    pub verification: HandleVerification,
+    pub verified_at: String,
+    pub registered_at: String,
+}
+
+/// One entry in a DID's handle history
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HandleChange {
+    pub did: Did,
+    pub old_handle: Option<String>,
+    pub new_handle: Option<String>, // None when the handle was released
+    pub changed_at: String,
+}
+
+/// Where a probable duplicate between two organizations' proxy records stands
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DuplicateStatus {
+    Pending,   // Flagged when the later record was created
+    Merged,    // Both were claimed by the same DID, who merged their copies
+    Dismissed, // Not the same person
+}
+
+impl DuplicateStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            DuplicateStatus::Pending => "pending",
+            DuplicateStatus::Merged => "merged",
+            DuplicateStatus::Dismissed => "dismissed",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "pending" => Some(DuplicateStatus::Pending),
+            "merged" => Some(DuplicateStatus::Merged),
+            "dismissed" => Some(DuplicateStatus::Dismissed),
+            _ => None,
+        }
+    }
+}
+
+/// Two proxy records from different organizations that probably describe the same
+/// person, found by comparing their linkage fingerprints
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DuplicateCandidate {
     pub id: String,
+    pub proxy_id: String,         // The record whose creation found the match
+    pub matched_proxy_id: String, // The earlier record of another organization
+    pub score: f64,               // Dice similarity of the name filters
+    pub status: DuplicateStatus,
+    pub merged_memory_id: Option<MemoryId>,
+    pub detected_at: String,
+    pub resolved_at: Option<String>,
+}
+
+/// A memory pulled from circulation because it failed verification
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct QuarantineEntry {
+    pub memory: SignedMemory,
+    pub reason: String,
+    pub source: Option<String>, // The peer that sent it; None if quarantined after storage
+    pub quarantined_at: String,
+}
+
+/// Where a memory held by inbound review stands
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PendingStatus {
+    Pending,
+    Approved, // Stored in signed_memory
+    Rejected, // Never stored; the same version is not held again
+}
+
+impl PendingStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            PendingStatus::Pending => "pending",
+            PendingStatus::Approved => "approved",
+            PendingStatus::Rejected => "rejected",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "pending" => Some(PendingStatus::Pending),
+            "approved" => Some(PendingStatus::Approved),
+            "rejected" => Some(PendingStatus::Rejected),
+            _ => None,
+        }
+    }
+}
+
+/// A verified memory from a peer that is waiting for an operator's approval
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PendingMemory {
+    pub id: String,
+    pub memory: SignedMemory,
+    pub source_peer: String,
+    pub source_did: Option<Did>,
+    pub offline_public_key: Option<String>, // Set when verified against an attached chain
+    pub status: PendingStatus,
+    pub received_at: String,
+    pub decided_at: Option<String>,
+}
+
+/// What the operator decided about a peer
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum PeerStanding {
+    #[default]
+    Neutral,
+    Trusted, // Memories from it skip inbound review
+    Banned,  // Removed from the peer set; its messages are dropped
+}
+
+impl PeerStanding {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            PeerStanding::Neutral => "neutral",
+            PeerStanding::Trusted => "trusted",
+            PeerStanding::Banned => "banned",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "neutral" => Some(PeerStanding::Neutral),
+            "trusted" => Some(PeerStanding::Trusted),
+            "banned" => Some(PeerStanding::Banned),
+            _ => None,
+        }
+    }
+}
+
+/// Messages exchanged with a peer; bytes count message payloads
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PeerTraffic {
+    pub messages_sent: u64,
+    pub messages_received: u64,
+    pub bytes_sent: u64,
+    pub bytes_received: u64,
+    pub send_failures: u64,
+    pub rejected: u64, // Messages and memories from it that failed checks
+}
+
+impl PeerTraffic {
+    pub fn is_empty(&self) -> bool {
+        *self == PeerTraffic::default()
+    }
+
+    pub fn add(&mut self, other: &PeerTraffic) {
+        self.messages_sent += other.messages_sent;
+        self.messages_received += other.messages_received;
+        self.bytes_sent += other.bytes_sent;
+        self.bytes_received += other.bytes_received;
+        self.send_failures += other.send_failures;
+        self.rejected += other.rejected;
+    }
+}
+
+/// Where a supervised background task stands
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TaskState {
+    Running,
+    Backoff, // Crashed, waiting to be restarted
+    Stopped, // Finished and not restarted
+    Failed,  // Crashed and given up on
+}
+
+impl TaskState {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            TaskState::Running => "running",
+            TaskState::Backoff => "backoff",
+            TaskState::Stopped => "stopped",
+            TaskState::Failed => "failed",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "running" => Some(TaskState::Running),
+            "backoff" => Some(TaskState::Backoff),
+            "stopped" => Some(TaskState::Stopped),
+            "failed" => Some(TaskState::Failed),
+            _ => None,
+        }
+    }
+}
+
+/// A supervised background task, as its process last reported it
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TaskHealth {
+    pub process: String,
+    pub name: String,
+    pub state: TaskState,
+    pub restarts: u32,
+    pub last_panic: Option<String>,
+    pub started_at: String,
+    pub reported_at: String,
+}
+
+/// A peer as the node last knew it, with its traffic, standing and reputation
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PeerRecord {
+    pub peer_id: PeerId,
+    pub did: Option<Did>,
+    pub address: Option<String>,
+    pub port: Option<u16>,
+    pub capabilities: Vec<String>,
+    pub protocol_version: u32,
+    pub last_seen: Option<String>,
+    pub connected: bool,
+    pub traffic: PeerTraffic,
+    pub standing: PeerStanding,
+    pub standing_reason: Option<String>,
+    pub standing_changed_at: Option<String>,
+    pub reputation: u8, // 0 to 100; see PeerRecord::reputation_of
+}
+
+impl PeerRecord {
+    /// Banned peers score 0 and trusted ones 100. Others score the share of exchanges
+    /// that went well, starting from 50 for a peer with no history
+    pub fn reputation_of(standing: PeerStanding, traffic: &PeerTraffic) -> u8 {
+        match standing {
+            PeerStanding::Banned => 0,
+            PeerStanding::Trusted => 100,
+            PeerStanding::Neutral => {
+                let good = traffic.messages_sent + traffic.messages_received;
+                let bad = traffic.send_failures + traffic.rejected;
+                ((good + 1) * 100 / (good + bad + 2)) as u8
+            }
+        }
+    }
+}
+
+/// What redeeming a claim token gives the claimer
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum ClaimScope {
+    #[default]
+    Transfer, // The claimer owns a copy of the record; the organization's copy is superseded
+    ReadOnly,    // The claimer may read the organization's record, which it keeps owning
+    CoOwnership, // A copy owned by the claimer and co-signed by the organization
+    Handover,    // A record's owner passes it on, e.g. a parent to a child come of age
+}
+
+impl ClaimScope {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ClaimScope::Transfer => "transfer",
+            ClaimScope::ReadOnly => "read_only",
+            ClaimScope::CoOwnership => "co_ownership",
+            ClaimScope::Handover => "handover",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "transfer" => Some(ClaimScope::Transfer),
+            "read_only" => Some(ClaimScope::ReadOnly),
+            "co_ownership" => Some(ClaimScope::CoOwnership),
+            "handover" => Some(ClaimScope::Handover),
+            _ => None,
+        }
+    }
+
+    /// Whether the claimer owns what the claim gives them, and may have it erased
+    pub fn grants_ownership(&self) -> bool {
+        !matches!(self, ClaimScope::ReadOnly)
+    }
+}
+
+/// A code that lets an individual claim an organization's proxy record. Only a salted
+/// hash keyed with the database's pepper is stored; `token` holds the code when the
+/// token is issued and is empty once loaded from the database
+#[derive(Clone, Serialize, Deserialize)]
+pub struct ClaimToken {
+    pub id: TokenId,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
     pub token: String,
-    pub memory_id: String,
-    pub organization_did: String,
+    pub token_selector: String, // Random part of the code apart from its secret, to find the row
+    #[serde(skip)]
+    pub token_salt: Option<String>, // None for a row not yet hashed since migrating
+    #[serde(skip)]
+    pub token_hash: Option<String>,
+    pub memory_id: MemoryId,
+    pub organization_did: Did,
     pub expiry_timestamp: String,
-    pub claimed_by_did: Option<String>,
+    pub claimed_by_did: Option<Did>,
     pub claimed_timestamp: Option<String>,
     pub created_timestamp: String,
     pub updated_on: String,
+    #[serde(default)]
+    pub scope: ClaimScope,
+    #[serde(default)]
+    pub claimed_memory_id: Option<MemoryId>, // What a transfer, co-ownership or handover claim created
+    #[serde(default)]
+    pub bound_to_did: Option<Did>, // The only DID that may claim, if any
+    #[serde(default)]
+    pub handover_memory_id: Option<MemoryId>, // The owner's signed authorization, for handovers
+}
+
+impl fmt::Debug for ClaimToken {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ClaimToken")
+            .field("id", &self.id)
+            .field("token", &Redacted::secret(&self.token))
+            .field("token_selector", &self.token_selector)
+            .field("memory_id", &self.memory_id)
+            .field("organization_did", &Redacted::did(&self.organization_did))
+            .field("expiry_timestamp", &self.expiry_timestamp)
+            .field(
+                "claimed_by_did",
+                &self.claimed_by_did.as_deref().map(Redacted::did),
+            )
+            .field("claimed_timestamp", &self.claimed_timestamp)
+            .field("created_timestamp", &self.created_timestamp)
+            .field("updated_on", &self.updated_on)
+            .field("scope", &self.scope)
+            .field("claimed_memory_id", &self.claimed_memory_id)
+            .field(
+                "bound_to_did",
+                &self.bound_to_did.as_deref().map(Redacted::did),
+            )
+            .field("handover_memory_id", &self.handover_memory_id)
+            .finish()
+    }
 }
 
 impl ClaimToken {
-    pub fn new(memory_id: &str, organization_did: &str, expires_in_hours: i64) -> Self {
+    /// A new token whose code is hashed with `pepper`, see `Database::claim_token_pepper`
+    pub fn new(
+        memory_id: &MemoryId,
+        organization_did: &Did,
+        expires_in_hours: i64,
+        pepper: &[u8],
+    ) -> Self {
         let now = chrono::Utc::now();
         let expiry = now + chrono::Duration::hours(expires_in_hours);
 
-        // Generate cryptographically secure token with 128 bits of entropy
+        // The selector is stored in the clear, so it is drawn apart from the 80-bit secret
         use rand::RngCore;
         let mut rng = rand::rngs::OsRng;
-        let mut random_bytes = [0u8; 16]; // 128 bits
-        rng.fill_bytes(&mut random_bytes);
+        let mut selector_bytes = [0u8; 5];
+        let mut secret_bytes = [0u8; 10];
+        rng.fill_bytes(&mut selector_bytes);
+        rng.fill_bytes(&mut secret_bytes);
 
-        // Encode as base32 for human readability while maintaining security
-        let random_part =
-            base32::encode(base32::Alphabet::RFC4648 { padding: false }, &random_bytes);
-        let token = format!("OCM-{}", &random_part[..16]); // Take first 16 chars for readability
+        // Base32 for human readability: 8 and 16 characters
+        let alphabet = base32::Alphabet::RFC4648 { padding: false };
+        let token = format!(
+            "OCM-{}-{}",
+            base32::encode(alphabet, &selector_bytes),
+            base32::encode(alphabet, &secret_bytes)
+        );
+        let salt: [u8; 16] = rand::random();
 
         ClaimToken {
-            id: uuid::Uuid::new_v4().to_string(),
+            id: TokenId::generate(),
+            token_selector: Self::selector(&token),
+            token_hash: Some(Self::hash_code(pepper, &salt, &token)),
+            token_salt: Some(hex::encode(salt)),
             token,
-            memory_id: memory_id.to_string(),
-            organization_did: organization_did.to_string(),
+            memory_id: memory_id.clone(),
+            organization_did: organization_did.clone(),
             expiry_timestamp: expiry.to_rfc3339(),
             claimed_by_did: None,
             claimed_timestamp: None,
             created_timestamp: now.to_rfc3339(),
             updated_on: now.to_rfc3339(),
+            scope: ClaimScope::Transfer,
+            claimed_memory_id: None,
+            bound_to_did: None,
+            handover_memory_id: None,
         }
     }
 
+    pub fn with_scope(mut self, scope: ClaimScope) -> Self {
+        self.scope = scope;
+        self
+    }
+
+    /// The part of a code stored in the clear: the segment ahead of the secret. Codes
+    /// issued before the two were separate used their first six characters
+    pub fn selector(code: &str) -> String {
+        let rest = code.strip_prefix("OCM-").unwrap_or(code);
+        match rest.split_once('-') {
+            Some((selector, _)) => selector.to_string(),
+            None => code.chars().skip(4).take(6).collect(),
+        }
+    }
+
+    /// HMAC-SHA256 keyed with `pepper` over the salted SHA-256 of the code, the hash
+    /// stored before the pepper, so those can be keyed in place
+    pub fn hash_code(pepper: &[u8], salt: &[u8], code: &str) -> String {
+        use sha2::{Digest, Sha256};
+        let mut hasher = Sha256::new();
+        hasher.update(salt);
+        hasher.update(code.as_bytes());
+        Self::pepper_hash(pepper, &hex::encode(hasher.finalize()))
+    }
+
+    pub fn pepper_hash(pepper: &[u8], salted_hash: &str) -> String {
+        use hmac::{Hmac, Mac};
+        let mut mac = <Hmac<sha2::Sha256> as Mac>::new_from_slice(pepper)
+            .expect("HMAC takes keys of any length");
+        mac.update(salted_hash.as_bytes());
+        hex::encode(mac.finalize().into_bytes())
+    }
+
+    /// Whether `code` is this token's code, compared in time independent of where they
+    /// differ. Rows not yet hashed since migrating compare their plaintext the same way
+    pub fn matches(&self, code: &str, pepper: &[u8]) -> bool {
+        let (expected, presented) = match (&self.token_salt, &self.token_hash) {
+            (Some(salt), Some(hash)) => match hex::decode(salt) {
+                Ok(salt) => (hash.clone(), Self::hash_code(pepper, &salt, code)),
+                Err(_) => return false,
+            },
+            _ if !self.token.is_empty() => (
+                Self::hash_code(pepper, &[], &self.token),
+                Self::hash_code(pepper, &[], code),
+            ),
+            _ => return false,
+        };
+        expected.len() == presented.len()
+            && expected
+                .bytes()
+                .zip(presented.bytes())
+                .fold(0u8, |diff, (a, b)| diff | (a ^ b))
+                == 0
+    }
+
     pub fn is_expired(&self) -> bool {
         if let Ok(expiry) = chrono::DateTime::parse_from_rfc3339(&self.expiry_timestamp) {
             chrono::Utc::now() > expiry.with_timezone(&chrono::Utc)
@@ -321,15 +1615,22 @@ impl ClaimToken {
         self.claimed_by_did.is_some()
     }
 
-    pub fn claim(&mut self, claimer_did: &str) -> Result<(), String> {
+    pub fn claim(&mut self, claimer_did: &Did) -> Result<(), String> {
         if self.is_expired() {
             return Err("Token has expired".to_string());
         }
         if self.is_claimed() {
             return Err("Token has already been claimed".to_string());
         }
+        if self
+            .bound_to_did
+            .as_ref()
+            .is_some_and(|did| did != claimer_did)
+        {
+            return Err("Token was issued to another DID".to_string());
+        }
 
-        self.claimed_by_did = Some(claimer_did.to_string());
+        self.claimed_by_did = Some(claimer_did.clone());
         self.claimed_timestamp = Some(chrono::Utc::now().to_rfc3339());
         self.updated_on = chrono::Utc::now().to_rfc3339();
         Ok(())
@@ -349,46 +1650,129 @@ impl DatabaseModel for ClaimToken {
     fn from_row(row: &Row) -> Result<Self> {
         Ok(ClaimToken {
             id: row.get(0)?,
-            token: row.get(1)?,
-            memory_id: row.get(2)?,
-            organization_did: row.get(3)?,
-            expiry_timestamp: row.get(4)?,
-            claimed_by_did: row.get(5)?,
-            claimed_timestamp: row.get(6)?,
-            created_timestamp: row.get(7)?,
-            updated_on: row.get(8)?,
+            token: row.get::<_, Option<String>>(1)?.unwrap_or_default(),
+            token_selector: row.get(2)?,
+            token_salt: row.get(3)?,
+            token_hash: row.get(4)?,
+            memory_id: row.get(5)?,
+            organization_did: row.get(6)?,
+            expiry_timestamp: row.get(7)?,
+            claimed_by_did: row.get(8)?,
+            claimed_timestamp: row.get(9)?,
+            created_timestamp: row.get(10)?,
+            updated_on: row.get(11)?,
+            // An unknown scope grants the least
+            scope: ClaimScope::parse(&row.get::<_, String>(12)?).unwrap_or(ClaimScope::ReadOnly),
+            claimed_memory_id: row.get(13)?,
+            bound_to_did: row.get(14)?,
+            handover_memory_id: row.get(15)?,
         })
     }
 
+    // The code itself is never written, and new hashes are always keyed with the pepper
     fn insert_sql() -> &'static str {
-        "INSERT INTO claim_token (id, token, memory_id, organization_did, expiry_timestamp, claimed_by_did, claimed_timestamp, created_timestamp, updated_on) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)"
+        "INSERT INTO claim_token (id, token, token_selector, token_salt, token_hash, memory_id, organization_did, expiry_timestamp, claimed_by_did, claimed_timestamp, created_timestamp, updated_on, scope, claimed_memory_id, bound_to_did, handover_memory_id, token_peppered) VALUES (?1, NULL, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, 1)"
     }
 
+    // A token's code and hash never change
     fn update_sql() -> &'static str {
-        "UPDATE claim_token SET token = ?2, memory_id = ?3, organization_did = ?4, expiry_timestamp = ?5, claimed_by_did = ?6, claimed_timestamp = ?7, created_timestamp = ?8, updated_on = ?9 WHERE id = ?1"
+        "UPDATE claim_token SET memory_id = ?2, organization_did = ?3, expiry_timestamp = ?4, claimed_by_did = ?5, claimed_timestamp = ?6, created_timestamp = ?7, updated_on = ?8, scope = ?9, claimed_memory_id = ?10, bound_to_did = ?11, handover_memory_id = ?12 WHERE id = ?1"
     }
 
     fn select_fields() -> &'static str {
-        "id, token, memory_id, organization_did, expiry_timestamp, claimed_by_did, claimed_timestamp, created_timestamp, updated_on"
+        "id, token, token_selector, token_salt, token_hash, memory_id, organization_did, expiry_timestamp, claimed_by_did, claimed_timestamp, created_timestamp, updated_on, scope, claimed_memory_id, bound_to_did, handover_memory_id"
     }
 }
 
+/// Period claim statistics are grouped by. Weeks start on Monday
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum StatsBucket {
+    Day,
+    #[default]
+    Week,
+    Month,
+}
+
+impl StatsBucket {
+    /// SQLite expression labelling the period a timestamp column falls in
+    pub fn period_sql(&self, column: &str) -> String {
+        match self {
+            StatsBucket::Day => format!("strftime('%Y-%m-%d', {})", column),
+            StatsBucket::Week => format!("date({}, 'weekday 0', '-6 days')", column),
+            StatsBucket::Month => format!("strftime('%Y-%m', {})", column),
+        }
+    }
+}
+
+/// Grouping and RFC 3339 time range for claim statistics
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ClaimStatsQuery {
+    #[serde(default)]
+    pub bucket: StatsBucket,
+    pub since: Option<String>,
+    pub until: Option<String>,
+}
+
+/// Tokens created in a period and how many of those have been claimed
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ClaimRatePoint {
+    pub period: String,
+    pub tokens_created: u64,
+    pub tokens_claimed: u64,
+    pub claim_rate: f64, // Percent
+}
+
+/// Current status of the tokens created in a period
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TokenStatusPoint {
+    pub period: String,
+    pub active: u64,
+    pub claimed: u64,
+    pub expired: u64, // Unclaimed and past expiry
+}
+
+/// Median time from creation to claim for tokens claimed in a period
 #[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TimeToClaimPoint {
+    pub period: String,
+    pub tokens_claimed: u64,
+    pub median_seconds: f64,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
 pub struct ProxyMemory {
     pub id: String,
     pub proxy_for_name: String,
     pub proxy_for_info: Option<String>,
-    pub organization_did: String,
+    pub organization_did: Did,
     pub memory_data: String,
     pub created_timestamp: String,
-    pub claim_token_id: Option<String>,
+    pub claim_token_id: Option<TokenId>,
+}
+
+impl fmt::Debug for ProxyMemory {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ProxyMemory")
+            .field("id", &self.id)
+            .field("proxy_for_name", &Redacted::pii(&self.proxy_for_name))
+            .field(
+                "proxy_for_info",
+                &self.proxy_for_info.as_deref().map(Redacted::pii),
+            )
+            .field("organization_did", &Redacted::did(&self.organization_did))
+            .field("memory_data", &Redacted::pii(&self.memory_data))
+            .field("created_timestamp", &self.created_timestamp)
+            .field("claim_token_id", &self.claim_token_id)
+            .finish()
+    }
 }
 
 impl ProxyMemory {
     pub fn new(
         proxy_for_name: &str,
         proxy_for_info: Option<String>,
-        organization_did: &str,
+        organization_did: &Did,
         memory_data: &str,
     ) -> Self {
         let now = chrono::Utc::now();
@@ -397,7 +1781,7 @@ impl ProxyMemory {
             id: uuid::Uuid::new_v4().to_string(),
             proxy_for_name: proxy_for_name.to_string(),
             proxy_for_info,
-            organization_did: organization_did.to_string(),
+            organization_did: organization_did.clone(),
             memory_data: memory_data.to_string(),
             created_timestamp: now.to_rfc3339(),
             claim_token_id: None,
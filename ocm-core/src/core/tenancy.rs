@@ -0,0 +1,107 @@
+use crate::core::models::SignedMemory;
+
+/// What a REST caller may see. Organization-scoped API keys and sessions only see
+/// memories the organization authored, which includes its proxy records, and its own
+/// claim tokens
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub enum TenantScope {
+    #[default]
+    Unrestricted,
+    Organization(String),
+}
+
+impl TenantScope {
+    pub fn new(organization_did: Option<String>) -> Self {
+        match organization_did {
+            Some(did) => TenantScope::Organization(did),
+            None => TenantScope::Unrestricted,
+        }
+    }
+
+    pub fn is_unrestricted(&self) -> bool {
+        matches!(self, TenantScope::Unrestricted)
+    }
+
+    pub fn organization_did(&self) -> Option<&str> {
+        match self {
+            TenantScope::Unrestricted => None,
+            TenantScope::Organization(did) => Some(did),
+        }
+    }
+
+    /// Whether records owned by this DID are visible
+    pub fn allows_did(&self, did: &str) -> bool {
+        match self {
+            TenantScope::Unrestricted => true,
+            TenantScope::Organization(organization_did) => organization_did == did,
+        }
+    }
+
+    pub fn allows_memory(&self, memory: &SignedMemory) -> bool {
+        self.allows_did(&memory.did)
+    }
+
+    pub fn filter_memories(&self, memories: Vec<SignedMemory>) -> Vec<SignedMemory> {
+        memories
+            .into_iter()
+            .filter(|memory| self.allows_memory(memory))
+            .collect()
+    }
+
+    /// Narrow a query's author filter (None meaning every author) to this scope
+    pub fn restrict_authors(&self, authors: Option<Vec<String>>) -> Option<Vec<String>> {
+        match (self, authors) {
+            (TenantScope::Unrestricted, authors) => authors,
+            (TenantScope::Organization(did), None) => Some(vec![did.clone()]),
+            (TenantScope::Organization(_), Some(authors)) => Some(
+                authors
+                    .into_iter()
+                    .filter(|author| self.allows_did(author))
+                    .collect(),
+            ),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::ids::Did;
+
+    const CAMP: &str = "did:plc:camp";
+    const SCHOOL: &str = "did:plc:school";
+
+    #[test]
+    fn test_organization_scope_hides_other_organizations() {
+        let camp = TenantScope::new(Some(CAMP.to_string()));
+        let camp_record = SignedMemory::new(&Did::parse(CAMP).unwrap(), "proxy_individual", "{}");
+        let school_record =
+            SignedMemory::new(&Did::parse(SCHOOL).unwrap(), "proxy_individual", "{}");
+
+        assert!(camp.allows_did(CAMP));
+        assert!(!camp.allows_did(SCHOOL));
+
+        let visible = camp.filter_memories(vec![camp_record.clone(), school_record]);
+        assert_eq!(visible.len(), 1);
+        assert_eq!(visible[0].id, camp_record.id);
+    }
+
+    #[test]
+    fn test_restrict_authors() {
+        let camp = TenantScope::new(Some(CAMP.to_string()));
+        assert_eq!(camp.restrict_authors(None), Some(vec![CAMP.to_string()]));
+        assert_eq!(
+            camp.restrict_authors(Some(vec![SCHOOL.to_string(), CAMP.to_string()])),
+            Some(vec![CAMP.to_string()])
+        );
+        // Asking only for another organization yields nothing rather than everything
+        assert_eq!(
+            camp.restrict_authors(Some(vec![SCHOOL.to_string()])),
+            Some(vec![])
+        );
+
+        let unrestricted = TenantScope::new(None);
+        assert_eq!(unrestricted.restrict_authors(None), None);
+        assert!(unrestricted.allows_did(SCHOOL));
+    }
+}
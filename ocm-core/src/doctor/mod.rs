@@ -0,0 +1,439 @@
+//! `ocm-core doctor`: checks the database, node identity, ports and network
+//! dependencies of a node and says what to do about anything that is wrong.
+
+use crate::config::OcmConfig;
+use crate::persistence::{migrations::latest_schema_version, Database};
+use std::net::{TcpListener, UdpSocket};
+use std::time::Duration;
+
+const HTTP_TIMEOUT: Duration = Duration::from_secs(10);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Status {
+    Ok,
+    Warn,
+    Fail,
+    Skipped,
+}
+
+#[derive(Debug, Clone)]
+pub struct Finding {
+    pub check: &'static str,
+    pub status: Status,
+    pub detail: String,
+    pub fix: Option<String>, // What to do about a warning or failure
+}
+
+impl Finding {
+    fn ok(check: &'static str, detail: impl Into<String>) -> Self {
+        Self {
+            check,
+            status: Status::Ok,
+            detail: detail.into(),
+            fix: None,
+        }
+    }
+
+    fn skipped(check: &'static str, detail: impl Into<String>) -> Self {
+        Self {
+            check,
+            status: Status::Skipped,
+            detail: detail.into(),
+            fix: None,
+        }
+    }
+
+    fn warn(check: &'static str, detail: impl Into<String>, fix: impl Into<String>) -> Self {
+        Self {
+            check,
+            status: Status::Warn,
+            detail: detail.into(),
+            fix: Some(fix.into()),
+        }
+    }
+
+    fn fail(check: &'static str, detail: impl Into<String>, fix: impl Into<String>) -> Self {
+        Self {
+            check,
+            status: Status::Fail,
+            detail: detail.into(),
+            fix: Some(fix.into()),
+        }
+    }
+}
+
+pub struct DoctorReport {
+    pub findings: Vec<Finding>,
+}
+
+impl DoctorReport {
+    pub fn has_failures(&self) -> bool {
+        self.findings.iter().any(|f| f.status == Status::Fail)
+    }
+
+    pub fn print(&self) {
+        println!("🩺 OCM node diagnostics\n");
+        for finding in &self.findings {
+            let icon = match finding.status {
+                Status::Ok => "✅",
+                Status::Warn => "⚠️ ",
+                Status::Fail => "❌",
+                Status::Skipped => "⏭️ ",
+            };
+            println!("{} {}: {}", icon, finding.check, finding.detail);
+            if let Some(fix) = &finding.fix {
+                println!("   → {}", fix);
+            }
+        }
+
+        let count = |status| self.findings.iter().filter(|f| f.status == status).count();
+        println!(
+            "\n{} ok, {} warnings, {} failures, {} skipped",
+            count(Status::Ok),
+            count(Status::Warn),
+            count(Status::Fail),
+            count(Status::Skipped)
+        );
+    }
+}
+
+/// Run every check. `relay_url` is the relay server's base URL, if the node uses one
+pub async fn run(config: &OcmConfig, relay_url: Option<&str>) -> DoctorReport {
+    let mut findings = vec![check_config(config)];
+    findings.extend(check_database(config));
+    findings.push(check_tls_keys(config));
+    findings.extend(check_ports(config));
+
+    let client = match reqwest::Client::builder().timeout(HTTP_TIMEOUT).build() {
+        Ok(client) => client,
+        Err(e) => {
+            findings.push(Finding::fail(
+                "HTTP client",
+                e.to_string(),
+                "Check the system's TLS root certificates",
+            ));
+            return DoctorReport { findings };
+        }
+    };
+
+    // Server clocks from HTTP Date headers, for the skew check
+    let mut server_times = Vec::new();
+
+    let (relay, relay_time) = check_relay(&client, relay_url).await;
+    findings.push(relay);
+    server_times.extend(relay_time);
+
+    let (plc, plc_time) = check_plc_directory(&client, config).await;
+    findings.push(plc);
+    server_times.extend(plc_time);
+
+    findings.push(check_clock_skew(config, &server_times));
+    DoctorReport { findings }
+}
+
+fn check_config(config: &OcmConfig) -> Finding {
+    match config.validate() {
+        Ok(()) => Finding::ok("Configuration", "valid"),
+        Err(e) => Finding::fail(
+            "Configuration",
+            e.to_string(),
+            "Correct the setting named above in the config file or OCM_ environment",
+        ),
+    }
+}
+
+fn check_database(config: &OcmConfig) -> Vec<Finding> {
+    let path = &config.database.path;
+    if !path.exists() {
+        return vec![Finding::fail(
+            "Database",
+            format!("no database at {}", path.display()),
+            "Create it with `cargo run --bin migrate`, or point database.path at the existing file",
+        )];
+    }
+
+    let database = match Database::open_read_only(&path.to_string_lossy()) {
+        Ok(database) => database,
+        Err(e) => {
+            return vec![Finding::fail(
+                "Database",
+                format!("cannot open {}: {}", path.display(), e),
+                "Check the file's permissions and that it is an SQLite database",
+            )]
+        }
+    };
+
+    let integrity = match database.integrity_check() {
+        Ok(problems) if problems.is_empty() => Finding::ok("Database integrity", "ok"),
+        Ok(problems) => Finding::fail(
+            "Database integrity",
+            format!(
+                "{} problems, first: {}",
+                problems.len(),
+                problems.first().map(String::as_str).unwrap_or_default()
+            ),
+            "Stop the node and restore the latest backup (see DEPLOYMENT.md, Recovery)",
+        ),
+        Err(e) => Finding::fail(
+            "Database integrity",
+            e.to_string(),
+            "Stop the node and restore the latest backup (see DEPLOYMENT.md, Recovery)",
+        ),
+    };
+
+    let latest = latest_schema_version();
+    let schema = match database.schema_version() {
+        Ok(Some(version)) if version == latest => {
+            Finding::ok("Schema version", format!("V{} (current)", version))
+        }
+        Ok(Some(version)) if version < latest => Finding::fail(
+            "Schema version",
+            format!("V{}, this build expects V{}", version, latest),
+            "Back up the database, then run `cargo run --bin migrate`",
+        ),
+        Ok(Some(version)) => Finding::warn(
+            "Schema version",
+            format!("V{} is newer than this build's V{}", version, latest),
+            "Upgrade this node to the release that created the database",
+        ),
+        Ok(None) => Finding::fail(
+            "Schema version",
+            "no migrations have been applied",
+            "Run `cargo run --bin migrate`",
+        ),
+        Err(e) => Finding::fail(
+            "Schema version",
+            e.to_string(),
+            "Run `cargo run --bin migrate`",
+        ),
+    };
+
+    let node_identity = match database.get_node_id() {
+        Ok(Some(node_id)) => Finding::ok("Node identity", format!("node ID {}", node_id)),
+        Ok(None) => Finding::warn(
+            "Node identity",
+            "no node ID stored yet",
+            "One is generated on first start; if this node has run before, its database was replaced",
+        ),
+        Err(e) => Finding::fail(
+            "Node identity",
+            e.to_string(),
+            "Run `cargo run --bin migrate` to create the node_metadata table",
+        ),
+    };
+
+    vec![integrity, schema, node_identity]
+}
+
+fn check_tls_keys(config: &OcmConfig) -> Finding {
+    let federation = &config.federation;
+    if !federation.https_enabled {
+        return Finding::skipped("TLS identity", "HTTPS federation is disabled");
+    }
+
+    let read = |path: &std::path::Path| {
+        std::fs::File::open(path)
+            .map(std::io::BufReader::new)
+            .map_err(|e| format!("{}: {}", path.display(), e))
+    };
+    let fix = "Install a certificate and key (Let's Encrypt recommended) at federation.tls_cert_path and tls_key_path";
+
+    let certs = match read(&federation.tls_cert_path) {
+        Ok(mut reader) => rustls_pemfile::certs(&mut reader).collect::<Result<Vec<_>, _>>(),
+        Err(e) => return Finding::fail("TLS identity", e, fix),
+    };
+    let key = match read(&federation.tls_key_path) {
+        Ok(mut reader) => rustls_pemfile::private_key(&mut reader),
+        Err(e) => return Finding::fail("TLS identity", e, fix),
+    };
+
+    match (certs, key) {
+        (Ok(certs), Ok(Some(_))) if !certs.is_empty() => Finding::ok(
+            "TLS identity",
+            format!("{} certificates and a private key", certs.len()),
+        ),
+        (Ok(_), Ok(Some(_))) => Finding::fail(
+            "TLS identity",
+            format!("no certificates in {}", federation.tls_cert_path.display()),
+            fix,
+        ),
+        (_, Ok(None)) => Finding::fail(
+            "TLS identity",
+            format!("no private key in {}", federation.tls_key_path.display()),
+            fix,
+        ),
+        (Err(e), _) | (_, Err(e)) => {
+            Finding::fail("TLS identity", format!("unreadable PEM: {}", e), fix)
+        }
+    }
+}
+
+fn check_ports(config: &OcmConfig) -> Vec<Finding> {
+    let host = config.server.host.as_str();
+    let in_use = |port: u16, setting: &str| {
+        format!(
+            "Stop the process using port {} (another OCM node?) or change {}",
+            port, setting
+        )
+    };
+
+    let mut findings = vec![
+        match TcpListener::bind((host, config.server.p2p_port)) {
+            Ok(_) => Finding::ok(
+                "P2P port",
+                format!("{}:{} is free", host, config.server.p2p_port),
+            ),
+            Err(e) => Finding::fail(
+                "P2P port",
+                format!("{}:{}: {}", host, config.server.p2p_port, e),
+                in_use(config.server.p2p_port, "server.p2p_port"),
+            ),
+        },
+        match UdpSocket::bind((host, config.server.discovery_port)) {
+            Ok(_) => Finding::ok(
+                "Discovery port",
+                format!("{}:{} (UDP) is free", host, config.server.discovery_port),
+            ),
+            Err(e) => Finding::fail(
+                "Discovery port",
+                format!("{}:{}: {}", host, config.server.discovery_port, e),
+                in_use(config.server.discovery_port, "server.discovery_port"),
+            ),
+        },
+    ];
+
+    if config.federation.https_enabled {
+        let port = config.federation.port;
+        findings.push(match TcpListener::bind(("0.0.0.0", port)) {
+            Ok(_) => Finding::ok("Federation port", format!("0.0.0.0:{} is free", port)),
+            Err(e) => Finding::fail(
+                "Federation port",
+                format!("0.0.0.0:{}: {}", port, e),
+                in_use(port, "federation.port"),
+            ),
+        });
+    }
+    findings
+}
+
+async fn check_relay(
+    client: &reqwest::Client,
+    relay_url: Option<&str>,
+) -> (Finding, Option<chrono::DateTime<chrono::Utc>>) {
+    let Some(relay_url) = relay_url else {
+        return (
+            Finding::skipped("Relay", "no relay given; pass --relay <url> to check one"),
+            None,
+        );
+    };
+
+    // The relay speaks WebSocket, but serves its health check over plain HTTP
+    let base = relay_url
+        .replacen("wss://", "https://", 1)
+        .replacen("ws://", "http://", 1);
+    let health_url = format!("{}/health", base.trim_end_matches('/'));
+
+    match client.get(&health_url).send().await {
+        Ok(response) if response.status().is_success() => {
+            let server_time = date_header(&response);
+            (
+                Finding::ok("Relay", format!("{} is healthy", health_url)),
+                server_time,
+            )
+        }
+        Ok(response) => (
+            Finding::fail(
+                "Relay",
+                format!("{} returned {}", health_url, response.status()),
+                "Check the relay server's logs (`docker-compose logs -f ocm-relay`)",
+            ),
+            date_header(&response),
+        ),
+        Err(e) => (
+            Finding::fail(
+                "Relay",
+                format!("{} is unreachable: {}", health_url, e),
+                "Check the relay URL and that the relay server is running and reachable from this host",
+            ),
+            None,
+        ),
+    }
+}
+
+async fn check_plc_directory(
+    client: &reqwest::Client,
+    config: &OcmConfig,
+) -> (Finding, Option<chrono::DateTime<chrono::Utc>>) {
+    if !config.plc.enable_network_calls {
+        return (
+            Finding::skipped(
+                "PLC directory",
+                "network calls are disabled (plc.enable_network_calls)",
+            ),
+            None,
+        );
+    }
+
+    let url = &config.plc.directory_url;
+    match client.get(url).send().await {
+        Ok(response) if !response.status().is_server_error() => {
+            let server_time = date_header(&response);
+            (
+                Finding::ok("PLC directory", format!("{} is reachable", url)),
+                server_time,
+            )
+        }
+        Ok(response) => (
+            Finding::warn(
+                "PLC directory",
+                format!("{} returned {}", url, response.status()),
+                "The directory is having trouble; identity resolution will fail until it recovers",
+            ),
+            date_header(&response),
+        ),
+        Err(e) => (
+            Finding::fail(
+                "PLC directory",
+                format!("{} is unreachable: {}", url, e),
+                "Check DNS, proxy and firewall settings for outbound HTTPS, or plc.directory_url",
+            ),
+            None,
+        ),
+    }
+}
+
+fn check_clock_skew(config: &OcmConfig, server_times: &[chrono::DateTime<chrono::Utc>]) -> Finding {
+    let Some(skew) = server_times
+        .iter()
+        .map(|server_time| (chrono::Utc::now() - *server_time).num_seconds())
+        .min_by_key(|skew| skew.abs())
+    else {
+        return Finding::skipped(
+            "Clock skew",
+            "no server time to compare against; check a relay or enable PLC network calls",
+        );
+    };
+
+    // HTTP dates have one second resolution
+    let allowed = config.federation.max_clock_skew_seconds as i64;
+    if skew.abs() <= allowed {
+        Finding::ok("Clock skew", format!("{}s", skew))
+    } else {
+        Finding::fail(
+            "Clock skew",
+            format!("local clock is {}s off (limit {}s)", skew, allowed),
+            "Enable NTP time sync (e.g. `timedatectl set-ntp true`); signed requests and memory timestamps are rejected beyond the limit",
+        )
+    }
+}
+
+fn date_header(response: &reqwest::Response) -> Option<chrono::DateTime<chrono::Utc>> {
+    let date = response
+        .headers()
+        .get(reqwest::header::DATE)?
+        .to_str()
+        .ok()?;
+    chrono::DateTime::parse_from_rfc2822(date)
+        .ok()
+        .map(|date| date.with_timezone(&chrono::Utc))
+}
@@ -1,27 +1,77 @@
+use crate::config::LinkageConfig;
 use crate::core::error::{OcmError, Result};
-use crate::core::models::{ClaimToken, Individual, ProxyMemory, SignedMemory};
-use crate::identity::plc::OcmProtocol;
+use crate::core::ids::{Did, MemoryId, TokenId};
+use crate::core::models::{
+    ClaimScope, ClaimToken, CustodyHandoverData, DataSubjectExport, DuplicateCandidate,
+    DuplicateStatus, ErasureRecord, Individual, OrganizationBadge, ProxyMemory, SignedMemory,
+    CLAIM_LOCKOUT_EVENT, CLAIM_REDEEMED_EVENT, CUSTODY_HANDOVER_MEMORY_TYPE,
+};
+use crate::core::redact::Redacted;
+use crate::identity::linkage::{dice_similarity, RecordLinker};
+use crate::identity::organizations::current_verification;
+use crate::identity::plc::{OcmProtocol, PlcIdentity};
 use crate::persistence::database::Database;
+use crate::persistence::merges::{merge_memories, MemoryMerge};
+use crate::security::rate_limiting::{LockoutConfig, LockoutTracker};
+use serde::Serialize;
+use std::collections::HashMap;
 use std::sync::Arc;
 
 pub struct ClaimSystem {
     db: Arc<Database>,
+    lockouts: LockoutTracker, // Failed claims per client IP and per claiming DID
+    linker: RecordLinker,     // Flags other organizations' records of the same person
 }
 
 impl ClaimSystem {
     pub fn new(db: Arc<Database>) -> Self {
-        Self { db }
+        Self::with_lockout(db, LockoutConfig::default())
+    }
+
+    pub fn with_lockout(db: Arc<Database>, lockout: LockoutConfig) -> Self {
+        Self {
+            db,
+            lockouts: LockoutTracker::new(lockout),
+            linker: RecordLinker::new(LinkageConfig::default()),
+        }
+    }
+
+    pub fn with_linkage(mut self, linkage: LinkageConfig) -> Self {
+        self.linker = RecordLinker::new(linkage);
+        self
     }
 
     /// Organization creates a proxy record for someone (like a summer camp creating a record for Jamie)
-    /// Returns a claim token that can be shared with the individual/parent
+    /// Returns a claim token that can be shared with the individual/parent. Only its hash is
+    /// stored, so this is the one time its code is available
     pub async fn create_proxy_record(
         &self,
         ocm_protocol: &mut OcmProtocol,
-        organization_did: &str,
+        organization_did: &Did,
         proxy_for_name: &str,
         proxy_for_info: Option<String>,
         individual_data: &Individual,
+    ) -> Result<(ProxyMemory, ClaimToken)> {
+        self.create_scoped_proxy_record(
+            ocm_protocol,
+            organization_did,
+            proxy_for_name,
+            proxy_for_info,
+            individual_data,
+            ClaimScope::Transfer,
+        )
+        .await
+    }
+
+    /// A proxy record whose claim token grants `scope` rather than a full transfer
+    pub async fn create_scoped_proxy_record(
+        &self,
+        ocm_protocol: &mut OcmProtocol,
+        organization_did: &Did,
+        proxy_for_name: &str,
+        proxy_for_info: Option<String>,
+        individual_data: &Individual,
+        scope: ClaimScope,
     ) -> Result<(ProxyMemory, ClaimToken)> {
         // Serialize the individual data
         let memory_data = serde_json::to_string(individual_data)
@@ -46,7 +96,13 @@ impl ClaimSystem {
         self.db.create_signed_memory(&signed_memory)?;
 
         // Create claim token that expires in 30 days (reasonable for camp scenarios)
-        let claim_token = ClaimToken::new(&signed_memory.id, organization_did, 30 * 24); // 30 days
+        let claim_token = ClaimToken::new(
+            &signed_memory.id,
+            organization_did,
+            30 * 24, // 30 days
+            self.db.claim_token_pepper(),
+        )
+        .with_scope(scope);
 
         // Link the proxy to the claim token
         proxy.claim_token_id = Some(claim_token.id.clone());
@@ -54,37 +110,38 @@ impl ClaimSystem {
         // Store both records
         self.db.create_proxy_memory(&proxy)?;
         self.db.create_claim_token(&claim_token)?;
+        if let Err(e) = self.flag_duplicates(&proxy, individual_data) {
+            eprintln!(
+                "⚠️  Duplicate detection failed for proxy {}: {}",
+                proxy.id, e
+            );
+        }
 
         println!(
             "🎫 Generated claim token: {} for {}",
-            claim_token.token, proxy_for_name
+            Redacted::secret(&claim_token.token),
+            Redacted::pii(proxy_for_name)
         );
-        println!("   Organization: {}", organization_did);
+        println!("   Organization: {}", Redacted::did(organization_did));
+        println!("   Scope: {}", scope.as_str());
         println!("   Expires: {}", claim_token.expiry_timestamp);
 
         Ok((proxy, claim_token))
     }
 
     /// Individual/parent claims ownership of a proxy record using the token
-    /// This transfers the data from organization's control to individual's control
+    /// This transfers the data from organization's control to individual's control.
+    /// Repeated failures from the same client IP or DID lock it out for exponentially
+    /// longer, so tokens cannot be guessed; `client_ip` is None only for claims made
+    /// in-process, with no remote client
     pub async fn claim_proxy_record(
         &self,
         ocm_protocol: &mut OcmProtocol,
         token_code: &str,
-        claimer_did: &str,
+        claimer_did: &Did,
+        client_ip: Option<&str>,
     ) -> Result<SignedMemory> {
-        // Find the claim token
-        let mut token = self
-            .db
-            .get_claim_token_by_token(token_code)?
-            .ok_or_else(|| {
-                OcmError::OperationFailed(format!("Claim token '{}' not found", token_code))
-            })?;
-
-        // Attempt to claim the token (this validates expiry and claimed status)
-        token
-            .claim(claimer_did)
-            .map_err(|e| OcmError::OperationFailed(e))?;
+        let mut token = self.check_claim(token_code, claimer_did, client_ip)?;
 
         // Get the original signed memory
         let original_memory = self
@@ -92,35 +149,518 @@ impl ClaimSystem {
             .get_signed_memory(&token.memory_id)?
             .ok_or_else(|| OcmError::OperationFailed("Original memory not found".to_string()))?;
 
-        // Create a new signed memory owned by the claimer (not the organization)
-        let mut claimed_memory =
-            SignedMemory::new(claimer_did, "individual", &original_memory.memory_data);
+        // A read-only claim leaves the organization's record where it is
+        let claimed_memory = if token.scope == ClaimScope::ReadOnly {
+            original_memory
+        } else if token.scope == ClaimScope::Handover {
+            let copy = self
+                .complete_handover(ocm_protocol, &token, &original_memory, claimer_did)
+                .await?;
+            token.claimed_memory_id = Some(copy.id.clone());
+            copy
+        } else {
+            // Create a new signed memory owned by the claimer (not the organization)
+            let mut claimed_memory =
+                SignedMemory::new(claimer_did, "individual", &original_memory.memory_data);
 
-        // Sign with claimer's identity
-        ocm_protocol.attest_memory(&mut claimed_memory).await?;
+            // Sign with claimer's identity
+            ocm_protocol.attest_memory(&mut claimed_memory).await?;
 
-        // Store the newly claimed memory
-        self.db.create_signed_memory(&claimed_memory)?;
+            // Store the newly claimed memory
+            self.db.create_signed_memory(&claimed_memory)?;
+            token.claimed_memory_id = Some(claimed_memory.id.clone());
+            claimed_memory
+        };
 
         // Update the token to mark it as claimed
         self.db.update_claim_token(&token)?;
 
+        // Let the organization know its record found its owner
+        let event = serde_json::json!({
+            "claim_token_id": token.id,
+            "claimed_memory_id": token.claimed_memory_id,
+            "scope": token.scope,
+        });
+        if let Err(e) = self.db.record_did_activity_event(
+            CLAIM_REDEEMED_EVENT,
+            &token.organization_did,
+            Some(&token.memory_id),
+            &event,
+        ) {
+            eprintln!("⚠️  Failed to record claim event: {}", e);
+        }
+
         println!("✅ Successfully claimed record!");
-        println!("   Token: {}", token_code);
-        println!("   New owner: {}", claimer_did);
+        println!("   Token: {}", Redacted::secret(token_code));
+        if token.scope != ClaimScope::Handover {
+            let label = self
+                .organization_badge(&token.organization_did)
+                .ok()
+                .and_then(|badge| badge.label);
+            println!(
+                "   Organization: {}",
+                label.as_deref().unwrap_or("not verified")
+            );
+        }
+        match token.scope {
+            ClaimScope::Transfer => println!("   New owner: {}", Redacted::did(claimer_did)),
+            ClaimScope::ReadOnly => println!("   Reader: {}", Redacted::did(claimer_did)),
+            ClaimScope::Handover => println!(
+                "   Handed over from {} to {}",
+                Redacted::did(&token.organization_did),
+                Redacted::did(claimer_did)
+            ),
+            ClaimScope::CoOwnership => println!(
+                "   Co-owners: {} and {} (awaiting the organization's co-signature)",
+                Redacted::did(claimer_did),
+                Redacted::did(&token.organization_did)
+            ),
+        }
         println!("   Memory ID: {}", claimed_memory.id);
 
         Ok(claimed_memory)
     }
 
+    /// Let the owner of `memory_id` hand it to someone else, such as a parent handing a
+    /// child's record to the child once they are an adult. Only `to_did` may claim the
+    /// token, or whoever holds it if None. `ocm_protocol` must hold the owner's identity,
+    /// which signs the handover's authorization now; the returned token carries the only
+    /// copy of its code
+    pub async fn issue_handover(
+        &self,
+        ocm_protocol: &OcmProtocol,
+        memory_id: &MemoryId,
+        to_did: Option<&Did>,
+        expires_in_hours: i64,
+    ) -> Result<ClaimToken> {
+        let owner_did = ocm_protocol
+            .current_identity()
+            .map(|i| i.did.clone())
+            .ok_or_else(|| OcmError::Validation("No identity to hand over with".to_string()))?;
+        let memory = self
+            .db
+            .get_signed_memory(memory_id)?
+            .ok_or_else(|| OcmError::NotFound(format!("Memory {}", memory_id)))?;
+        if memory.did != owner_did {
+            return Err(OcmError::Validation(
+                "Only a memory's owner can hand it over".to_string(),
+            ));
+        }
+        if memory.is_tombstone() || memory.is_derived() {
+            return Err(OcmError::Validation(
+                "Erased and derived memories cannot be handed over".to_string(),
+            ));
+        }
+
+        let mut token = ClaimToken::new(
+            memory_id,
+            &owner_did,
+            expires_in_hours,
+            self.db.claim_token_pepper(),
+        )
+        .with_scope(ClaimScope::Handover);
+        token.bound_to_did = to_did.cloned();
+
+        let handover = CustodyHandoverData {
+            claim_token_id: token.id.clone(),
+            memory_id: memory.id.clone(),
+            content_hash: memory.content_hash.clone(),
+            from_did: owner_did.clone(),
+            to_did: token.bound_to_did.clone(),
+            issued_at: token.created_timestamp.clone(),
+        };
+        let mut authorization = SignedMemory::new(
+            &owner_did,
+            CUSTODY_HANDOVER_MEMORY_TYPE,
+            &serde_json::to_string(&handover)?,
+        );
+        ocm_protocol.attest_memory(&mut authorization).await?;
+        self.db.create_signed_memory(&authorization)?;
+        token.handover_memory_id = Some(authorization.id.clone());
+        self.db.create_claim_token(&token)?;
+
+        println!(
+            "🔑 Handover token {} for memory {}",
+            Redacted::secret(&token.token),
+            memory.id
+        );
+        println!(
+            "   From {} to {}",
+            Redacted::did(&owner_did),
+            to_did
+                .map(|did| Redacted::did(did).to_string())
+                .unwrap_or_else(|| "whoever holds the token".to_string())
+        );
+        Ok(token)
+    }
+
+    // The new owner's copy of a handed-over memory, with the owner's authorization
+    // co-signed by the new owner and the old memory archived
+    async fn complete_handover(
+        &self,
+        ocm_protocol: &OcmProtocol,
+        token: &ClaimToken,
+        original: &SignedMemory,
+        claimer_did: &Did,
+    ) -> Result<SignedMemory> {
+        let authorization_id = token.handover_memory_id.as_ref().ok_or_else(|| {
+            OcmError::OperationFailed("Handover token has no authorization".to_string())
+        })?;
+        let mut authorization = self
+            .db
+            .get_signed_memory(authorization_id)?
+            .ok_or_else(|| {
+                OcmError::OperationFailed("Handover authorization not found".to_string())
+            })?;
+        let handover: CustodyHandoverData = serde_json::from_str(&authorization.memory_data)?;
+        if authorization.did != token.organization_did
+            || handover.memory_id != original.id
+            || handover.content_hash != original.content_hash
+        {
+            return Err(OcmError::OperationFailed(
+                "Handover authorization does not match the memory".to_string(),
+            ));
+        }
+
+        let mut copy = SignedMemory::new(claimer_did, &original.memory_type, &original.memory_data);
+        ocm_protocol.attest_memory(&mut copy).await?;
+        ocm_protocol.co_sign_memory(&mut authorization).await?;
+        authorization.updated_on = chrono::Utc::now().to_rfc3339();
+
+        self.db
+            .hand_over_memory(&original.id, &copy, &authorization, &token.id)?;
+        Ok(copy)
+    }
+
+    /// The organization's co-signature on the memory a co-ownership claim created, so it
+    /// carries the signatures of both owners. `ocm_protocol` must hold the organization's
+    /// identity
+    pub async fn co_sign_claimed_record(
+        &self,
+        ocm_protocol: &OcmProtocol,
+        claim_token_id: &TokenId,
+    ) -> Result<SignedMemory> {
+        let token = self
+            .db
+            .get_claim_token(claim_token_id)?
+            .ok_or_else(|| OcmError::NotFound(format!("Claim token {}", claim_token_id)))?;
+        if token.scope != ClaimScope::CoOwnership {
+            return Err(OcmError::Validation(
+                "Only co-ownership claims are co-signed by the organization".to_string(),
+            ));
+        }
+        let signer_did = ocm_protocol.current_identity().map(|i| i.did.as_str());
+        if signer_did != Some(token.organization_did.as_str()) {
+            return Err(OcmError::Validation(
+                "Co-sign with the identity of the organization that issued the token".to_string(),
+            ));
+        }
+        let memory_id = token.claimed_memory_id.as_ref().ok_or_else(|| {
+            OcmError::Validation("The claim token has not been claimed".to_string())
+        })?;
+        let mut memory = self
+            .db
+            .get_signed_memory(memory_id)?
+            .ok_or_else(|| OcmError::NotFound(format!("Memory {}", memory_id)))?;
+
+        ocm_protocol.co_sign_memory(&mut memory).await?;
+        memory.updated_on = chrono::Utc::now().to_rfc3339();
+        self.db.update_signed_memory(&memory)?;
+
+        println!(
+            "🤝 {} co-signed shared record {}",
+            Redacted::did(&token.organization_did),
+            memory.id
+        );
+        Ok(memory)
+    }
+
+    /// Organization records `reader_did` was given read-only access to by claiming
+    pub fn list_readable_records(&self, reader_did: &Did) -> Result<Vec<SignedMemory>> {
+        let mut memories = Vec::new();
+        for token in self.db.list_claim_tokens_claimed_by(reader_did)? {
+            if token.scope == ClaimScope::ReadOnly {
+                if let Some(memory) = self.db.get_signed_memory(&token.memory_id)? {
+                    memories.push(memory);
+                }
+            }
+        }
+        Ok(memories)
+    }
+
+    /// Fingerprint a new proxy record and flag other organizations' records it probably
+    /// duplicates. Only records with the same date of birth are compared
+    fn flag_duplicates(
+        &self,
+        proxy: &ProxyMemory,
+        individual: &Individual,
+    ) -> Result<Vec<DuplicateCandidate>> {
+        let Some(fingerprint) = self.linker.fingerprint(individual) else {
+            return Ok(Vec::new());
+        };
+        self.db.create_proxy_linkage(
+            &proxy.id,
+            &proxy.organization_did,
+            &fingerprint.dob_key,
+            &fingerprint.name_bloom,
+        )?;
+
+        let mut candidates = Vec::new();
+        for (matched_proxy_id, bloom) in self
+            .db
+            .list_proxy_linkages(&fingerprint.dob_key, &proxy.organization_did)?
+        {
+            let score = dice_similarity(&fingerprint.name_bloom, &bloom);
+            if score < self.linker.threshold() {
+                continue;
+            }
+            let candidate = DuplicateCandidate {
+                id: uuid::Uuid::new_v4().to_string(),
+                proxy_id: proxy.id.clone(),
+                matched_proxy_id,
+                score,
+                status: DuplicateStatus::Pending,
+                merged_memory_id: None,
+                detected_at: chrono::Utc::now().to_rfc3339(),
+                resolved_at: None,
+            };
+            self.db.create_duplicate_candidate(&candidate)?;
+            println!(
+                "🔁 Proxy {} probably duplicates proxy {} of another organization (score {:.2})",
+                candidate.proxy_id, candidate.matched_proxy_id, score
+            );
+            candidates.push(candidate);
+        }
+        Ok(candidates)
+    }
+
+    /// Probable duplicates between two proxy records
+    pub fn list_duplicate_candidates(&self, proxy_id: &str) -> Result<Vec<DuplicateCandidate>> {
+        self.db.list_duplicate_candidates_for_proxy(proxy_id)
+    }
+
+    /// Pending duplicates both of whose records `claimer_did` has claimed a copy of, and
+    /// so may merge
+    pub fn list_mergeable_duplicates(&self, claimer_did: &Did) -> Result<Vec<DuplicateCandidate>> {
+        let mut mergeable: Vec<DuplicateCandidate> = Vec::new();
+        for token in self.db.list_claim_tokens_claimed_by(claimer_did)? {
+            let Some(proxy) = self.db.get_proxy_memory_by_claim_token(&token.id)? else {
+                continue;
+            };
+            for candidate in self.db.list_duplicate_candidates_for_proxy(&proxy.id)? {
+                if candidate.status == DuplicateStatus::Pending
+                    && !mergeable.iter().any(|c| c.id == candidate.id)
+                    && self.claimed_copies(&candidate, claimer_did).is_ok()
+                {
+                    mergeable.push(candidate);
+                }
+            }
+        }
+        Ok(mergeable)
+    }
+
+    /// Merge the claimer's copies of two duplicate records into one individual memory
+    /// signed by the claimer, superseding both. The earlier record's fields win; the
+    /// other fills the gaps. `ocm_protocol` must hold the claimer's identity
+    pub fn merge_duplicate(
+        &self,
+        ocm_protocol: &OcmProtocol,
+        candidate_id: &str,
+    ) -> Result<MemoryMerge> {
+        let claimer = ocm_protocol
+            .current_identity()
+            .ok_or_else(|| OcmError::Validation("No identity to merge with".to_string()))?;
+        let mut candidate = self.pending_candidate(candidate_id)?;
+        let (newer, earlier) = self.claimed_copies(&candidate, &claimer.did)?;
+        let merge = merge_memories(&self.db, claimer, &[earlier.id, newer.id], &HashMap::new())?;
+
+        candidate.status = DuplicateStatus::Merged;
+        candidate.merged_memory_id = Some(merge.merged.id.clone());
+        candidate.resolved_at = Some(chrono::Utc::now().to_rfc3339());
+        self.db.update_duplicate_candidate(&candidate)?;
+        Ok(merge)
+    }
+
+    /// Mark a probable duplicate as two different people
+    pub fn dismiss_duplicate(&self, candidate_id: &str) -> Result<DuplicateCandidate> {
+        let mut candidate = self.pending_candidate(candidate_id)?;
+        candidate.status = DuplicateStatus::Dismissed;
+        candidate.resolved_at = Some(chrono::Utc::now().to_rfc3339());
+        self.db.update_duplicate_candidate(&candidate)?;
+        Ok(candidate)
+    }
+
+    fn pending_candidate(&self, candidate_id: &str) -> Result<DuplicateCandidate> {
+        let candidate = self
+            .db
+            .get_duplicate_candidate(candidate_id)?
+            .ok_or_else(|| OcmError::NotFound(format!("Duplicate candidate {}", candidate_id)))?;
+        if candidate.status != DuplicateStatus::Pending {
+            return Err(OcmError::Validation(format!(
+                "Duplicate candidate {} is already {}",
+                candidate_id,
+                candidate.status.as_str()
+            )));
+        }
+        Ok(candidate)
+    }
+
+    /// The claimer's copies of a candidate's records, newer record first. Both must have
+    /// been claimed by the claimer with a scope that gave them a copy
+    fn claimed_copies(
+        &self,
+        candidate: &DuplicateCandidate,
+        claimer_did: &Did,
+    ) -> Result<(SignedMemory, SignedMemory)> {
+        let copy_of = |proxy_id: &str| -> Result<SignedMemory> {
+            let not_claimed = || {
+                OcmError::Validation(format!(
+                    "Proxy {} has not been claimed by {}",
+                    proxy_id,
+                    Redacted::did(claimer_did)
+                ))
+            };
+            let token_id = self
+                .db
+                .get_proxy_memory(proxy_id)?
+                .and_then(|proxy| proxy.claim_token_id)
+                .ok_or_else(not_claimed)?;
+            let memory_id = self
+                .db
+                .get_claim_token(&token_id)?
+                .filter(|token| {
+                    token.claimed_by_did.as_ref() == Some(claimer_did)
+                        && token.scope.grants_ownership()
+                })
+                .and_then(|token| token.claimed_memory_id)
+                .ok_or_else(not_claimed)?;
+            self.db
+                .get_signed_memory(&memory_id)?
+                .ok_or_else(|| OcmError::NotFound(format!("Memory {}", memory_id)))
+        };
+        Ok((
+            copy_of(&candidate.proxy_id)?,
+            copy_of(&candidate.matched_proxy_id)?,
+        ))
+    }
+
+    /// What redeeming `token_code` would give `claimer_did` and who issued it, without
+    /// redeeming it, so a parent can see the organization's badge first. Lookups count
+    /// towards the same lockout as claims
+    pub fn preview_claim(
+        &self,
+        token_code: &str,
+        claimer_did: &Did,
+        client_ip: Option<&str>,
+    ) -> Result<ClaimPreview> {
+        let token = self.check_claim(token_code, claimer_did, client_ip)?;
+        Ok(ClaimPreview {
+            organization: self.organization_badge(&token.organization_did)?,
+            scope: token.scope,
+            expiry_timestamp: token.expiry_timestamp,
+        })
+    }
+
+    /// The verified badge of the organization behind proxy records and claim tokens
+    pub fn organization_badge(&self, organization_did: &Did) -> Result<OrganizationBadge> {
+        Ok(OrganizationBadge::new(
+            organization_did,
+            current_verification(&self.db, organization_did)?,
+        ))
+    }
+
+    /// The claimable token for `token_code`, unless the IP or DID is locked out
+    fn check_claim(
+        &self,
+        token_code: &str,
+        claimer_did: &Did,
+        client_ip: Option<&str>,
+    ) -> Result<ClaimToken> {
+        let did_key = format!("did:{}", claimer_did);
+        let mut keys = vec![did_key.clone()];
+        if let Some(ip) = client_ip {
+            keys.push(format!("ip:{}", ip));
+        }
+        if let Some(wait) = keys
+            .iter()
+            .filter_map(|k| self.lockouts.locked_for(k))
+            .max()
+        {
+            return Err(OcmError::OperationFailed(format!(
+                "Too many failed claim attempts; try again in {} seconds",
+                wait.as_secs().max(1)
+            )));
+        }
+
+        let token = match self.find_claimable_token(token_code, claimer_did)? {
+            Ok(token) => token,
+            Err(reason) => {
+                self.record_failed_claim(&keys, claimer_did, client_ip);
+                return Err(OcmError::OperationFailed(reason));
+            }
+        };
+        // Only the DID starts over: one good code says nothing of what else an IP tried
+        self.lockouts.record_success(&did_key);
+        Ok(token)
+    }
+
+    /// The token for `token_code`, marked claimed by `claimer_did`, or why it cannot be
+    /// claimed. The lookup compares salted hashes in constant time
+    fn find_claimable_token(
+        &self,
+        token_code: &str,
+        claimer_did: &Did,
+    ) -> Result<std::result::Result<ClaimToken, String>> {
+        let Some(mut token) = self.db.get_claim_token_by_token(token_code)? else {
+            return Ok(Err(format!(
+                "Claim token '{}' not found",
+                Redacted::secret(token_code)
+            )));
+        };
+
+        // Attempt to claim the token (this validates expiry and claimed status)
+        Ok(token.claim(claimer_did).map(|_| token))
+    }
+
+    fn record_failed_claim(&self, keys: &[String], claimer_did: &Did, client_ip: Option<&str>) {
+        for key in keys {
+            let (failures, lockout) = self.lockouts.record_failure(key);
+            let Some(lockout) = lockout else {
+                continue;
+            };
+            let (kind, source) = match key.strip_prefix("ip:") {
+                Some(ip) => ("ip", ip.to_string()),
+                None => ("did", Redacted::did(claimer_did).to_string()),
+            };
+            eprintln!(
+                "🚨 {} failed claim attempts from {}; locked out for {} seconds",
+                failures,
+                source,
+                lockout.as_secs()
+            );
+            let event = serde_json::json!({
+                "lockout_key": kind,
+                "client_ip": client_ip,
+                "failures": failures,
+                "locked_for_seconds": lockout.as_secs(),
+            });
+            if let Err(e) =
+                self.db
+                    .record_did_activity_event(CLAIM_LOCKOUT_EVENT, claimer_did, None, &event)
+            {
+                eprintln!("⚠️  Failed to record claim lockout event: {}", e);
+            }
+        }
+    }
+
     /// List all proxy records created by an organization
-    pub fn list_organization_proxies(&self, organization_did: &str) -> Result<Vec<ProxyMemory>> {
+    pub fn list_organization_proxies(&self, organization_did: &Did) -> Result<Vec<ProxyMemory>> {
         self.db
             .list_proxy_memories_by_organization(organization_did)
     }
 
     /// List all claim tokens created by an organization
-    pub fn list_organization_tokens(&self, organization_did: &str) -> Result<Vec<ClaimToken>> {
+    pub fn list_organization_tokens(&self, organization_did: &Did) -> Result<Vec<ClaimToken>> {
         self.db.list_claim_tokens_by_organization(organization_did)
     }
 
@@ -129,18 +669,147 @@ impl ClaimSystem {
         self.db.search_proxy_memories_by_name(name_pattern)
     }
 
-    /// Get statistics about the claim system usage
-    pub fn get_claim_statistics(&self, organization_did: &str) -> Result<ClaimStatistics> {
-        let tokens = self.list_organization_tokens(organization_did)?;
-        let proxies = self.list_organization_proxies(organization_did)?;
+    /// Collect every locally held record that references a data subject's DID:
+    /// memories they authored or co-signed, tokens they claimed and the proxy records behind them
+    pub fn export_subject_data(&self, subject_did: &Did) -> Result<DataSubjectExport> {
+        let mut memories = self.db.list_memories_referencing_did(subject_did)?;
+        let claim_tokens = self.db.list_claim_tokens_claimed_by(subject_did)?;
 
-        let total_tokens = tokens.len();
-        let claimed_tokens = tokens.iter().filter(|t| t.is_claimed()).count();
-        let expired_tokens = tokens.iter().filter(|t| t.is_expired()).count();
+        let mut proxy_memories = Vec::new();
+        for token in &claim_tokens {
+            if let Some(proxy) = self.db.get_proxy_memory_by_claim_token(&token.id)? {
+                proxy_memories.push(proxy);
+            }
+            // The organization's original proxy memory describes the subject too
+            if !memories.iter().any(|m| m.id == token.memory_id) {
+                if let Some(original) = self.db.get_signed_memory(&token.memory_id)? {
+                    memories.push(original);
+                }
+            }
+        }
+
+        let mut witness_receipts = Vec::new();
+        for memory in &memories {
+            witness_receipts.extend(self.db.list_witness_receipts(&memory.id)?);
+        }
+
+        Ok(DataSubjectExport {
+            subject_did: subject_did.clone(),
+            generated_at: chrono::Utc::now().to_rfc3339(),
+            memories,
+            proxy_memories,
+            claim_tokens,
+            witness_receipts,
+            erasure_records: self.db.list_erasure_records(subject_did)?,
+        })
+    }
+
+    /// Erase a data subject's content: memories they authored (and proxy originals they claimed)
+    /// become tombstones, proxy records are scrubbed, and their co-signatures are removed from
+    /// others' memories. Each tombstone gets a signed erasure record, and the result is re-checked
+    pub fn erase_subject_data(
+        &self,
+        operator: &PlcIdentity,
+        subject_did: &Did,
+    ) -> Result<Vec<ErasureRecord>> {
+        let export = self.export_subject_data(subject_did)?;
+        // Records the subject may only read stay with the organization
+        let claimed_memory_ids: Vec<&str> = export
+            .claim_tokens
+            .iter()
+            .filter(|t| t.scope.grants_ownership())
+            .map(|t| t.memory_id.as_str())
+            .collect();
+
+        let mut records = Vec::new();
+        for memory in &export.memories {
+            let owned_by_subject =
+                memory.did == *subject_did || claimed_memory_ids.contains(&memory.id.as_str());
+
+            if owned_by_subject {
+                if memory.is_tombstone() {
+                    continue;
+                }
+                self.db.scrub_signed_memory(&memory.id)?;
+
+                let mut record = ErasureRecord::new(
+                    subject_did,
+                    &memory.id,
+                    &memory.content_hash,
+                    &operator.did,
+                );
+                record.signature = operator.sign_payload(&record.get_signing_payload())?;
+                self.db.create_erasure_record(&record)?;
+                records.push(record);
+            } else if memory.is_co_signed_by(subject_did) {
+                let mut updated = memory.clone();
+                updated
+                    .co_signatures
+                    .retain(|s| s.signer_did != *subject_did);
+                updated.updated_on = chrono::Utc::now().to_rfc3339();
+                self.db.update_signed_memory(&updated)?;
+            }
+        }
+
+        let proxies = owned_proxies(&export);
+        for proxy in &proxies {
+            self.db.scrub_proxy_memory(&proxy.id)?;
+        }
+
+        if !self.verify_subject_erased(subject_did)? {
+            return Err(OcmError::OperationFailed(format!(
+                "Erasure of {} could not be verified",
+                Redacted::did(subject_did)
+            )));
+        }
+
+        println!(
+            "🧹 Erased data for {}: {} memories tombstoned, {} proxy records scrubbed",
+            Redacted::did(subject_did),
+            records.len(),
+            proxies.len()
+        );
+
+        Ok(records)
+    }
+
+    /// Check that no readable content referencing the subject remains
+    pub fn verify_subject_erased(&self, subject_did: &Did) -> Result<bool> {
+        let export = self.export_subject_data(subject_did)?;
+        // Records the subject may only read stay with the organization
+        let claimed_memory_ids: Vec<&str> = export
+            .claim_tokens
+            .iter()
+            .filter(|t| t.scope.grants_ownership())
+            .map(|t| t.memory_id.as_str())
+            .collect();
+
+        let memories_erased = export.memories.iter().all(|m| {
+            let owned_by_subject =
+                m.did == *subject_did || claimed_memory_ids.contains(&m.id.as_str());
+            if owned_by_subject {
+                m.is_tombstone() && m.memory_data.is_empty()
+            } else {
+                !m.is_co_signed_by(subject_did)
+            }
+        });
+        let proxies_erased = owned_proxies(&export)
+            .iter()
+            .all(|p| p.memory_data.is_empty() && p.proxy_for_info.is_none());
+
+        Ok(memories_erased && proxies_erased)
+    }
+
+    /// Get statistics about the claim system usage
+    pub fn get_claim_statistics(&self, organization_did: &Did) -> Result<ClaimStatistics> {
+        let (total, claimed, expired) = self.db.count_claim_tokens(organization_did)?;
+        let total_tokens = total as usize;
+        let claimed_tokens = claimed as usize;
+        let expired_tokens = expired as usize;
         let active_tokens = total_tokens - claimed_tokens - expired_tokens;
 
         Ok(ClaimStatistics {
-            total_proxy_records: proxies.len(),
+            total_proxy_records: self.db.count_proxy_memories(organization_did)? as usize,
             total_tokens_created: total_tokens,
             tokens_claimed: claimed_tokens,
             tokens_expired: expired_tokens,
@@ -149,12 +818,36 @@ impl ClaimSystem {
     }
 }
 
-#[derive(Debug)]
+/// Proxy records behind the subject's transfer and co-ownership claims; those behind
+/// read-only claims stay the organization's
+fn owned_proxies(export: &DataSubjectExport) -> Vec<&ProxyMemory> {
+    export
+        .proxy_memories
+        .iter()
+        .filter(|proxy| {
+            export
+                .claim_tokens
+                .iter()
+                .any(|t| proxy.claim_token_id.as_ref() == Some(&t.id) && t.scope.grants_ownership())
+        })
+        .collect()
+}
+
+/// A claim token as a parent sees it before redeeming it
+#[derive(Debug, Serialize)]
+pub struct ClaimPreview {
+    pub organization: OrganizationBadge,
+    pub scope: ClaimScope,
+    pub expiry_timestamp: String,
+}
+
+/// Per-organization totals. Use the Database's `*_over_time` queries for time series
+#[derive(Debug, Serialize)]
 pub struct ClaimStatistics {
     pub total_proxy_records: usize,
     pub total_tokens_created: usize,
     pub tokens_claimed: usize,
-    pub tokens_expired: usize,
+    pub tokens_expired: usize, // Unclaimed and past expiry
     pub tokens_active: usize,
 }
 
@@ -167,3 +860,74 @@ impl ClaimStatistics {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::persistence::migrations::run_migrations;
+    use std::time::Duration;
+
+    #[test]
+    fn test_a_successful_claim_does_not_reset_the_ip_lockout() {
+        let path = std::env::temp_dir()
+            .join(format!("ocm-claim-lockout-{}.db", uuid::Uuid::new_v4()))
+            .to_string_lossy()
+            .to_string();
+        run_migrations(&path).expect("Failed to migrate database");
+        let db = Arc::new(Database::new(&path).unwrap());
+        let memory = SignedMemory::new(
+            &Did::parse("did:plc:org").unwrap(),
+            "proxy_individual",
+            "{}",
+        );
+        db.create_signed_memory(&memory).unwrap();
+        let token = ClaimToken::new(
+            &memory.id,
+            &Did::parse("did:plc:org").unwrap(),
+            1,
+            db.claim_token_pepper(),
+        );
+        db.create_claim_token(&token).unwrap();
+
+        let claims = ClaimSystem::with_lockout(
+            db,
+            LockoutConfig {
+                free_attempts: 2,
+                base_lockout: Duration::from_secs(60),
+                max_lockout: Duration::from_secs(60),
+            },
+        );
+        let ip = Some("203.0.113.7");
+        for guess in 0..2 {
+            let did = Did::parse(&format!("did:plc:guesser{}", guess)).unwrap();
+            assert!(claims.preview_claim("OCM-AAAAAAAA-BBBB", &did, ip).is_err());
+        }
+
+        // A real code from the same IP passes, but leaves its failures counted
+        assert!(claims
+            .preview_claim(&token.token, &Did::parse("did:plc:parent").unwrap(), ip)
+            .is_ok());
+        assert!(claims
+            .preview_claim(
+                "OCM-AAAAAAAA-BBBB",
+                &Did::parse("did:plc:guesser2").unwrap(),
+                ip
+            )
+            .is_err());
+        let locked = claims.preview_claim(&token.token, &Did::parse("did:plc:parent").unwrap(), ip);
+        assert!(locked.is_err_and(|e| e.to_string().contains("Too many failed claim attempts")));
+
+        // The DID that succeeded is not held back from another address
+        assert!(claims
+            .preview_claim(
+                &token.token,
+                &Did::parse("did:plc:parent").unwrap(),
+                Some("198.51.100.1")
+            )
+            .is_ok());
+
+        for suffix in ["", "-wal", "-shm", ".pepper"] {
+            let _ = std::fs::remove_file(format!("{}{}", path, suffix));
+        }
+    }
+}
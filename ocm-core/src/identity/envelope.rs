@@ -0,0 +1,233 @@
+use crate::core::error::{OcmError, Result};
+use crate::core::ids::Did;
+use crate::core::models::{SealedEnvelope, WrappedKey};
+use crate::identity::plc::PlcIdentity;
+use base64::{engine::general_purpose, Engine as _};
+use chacha20poly1305::aead::{Aead, AeadCore, KeyInit, OsRng, Payload};
+use chacha20poly1305::{ChaCha20Poly1305, Key, Nonce};
+use ed25519_dalek::{SigningKey, VerifyingKey};
+use hkdf::Hkdf;
+use sha2::Sha256;
+use x25519_dalek::{EphemeralSecret, PublicKey, StaticSecret};
+use zeroize::Zeroizing;
+
+const KEY_WRAP_INFO: &[u8] = b"ocm-envelope-v1";
+
+/// The X25519 form of an Ed25519 public key, so a DID's signing key also decrypts
+fn x25519_public(ed25519_public: &[u8; 32]) -> Result<PublicKey> {
+    let key = VerifyingKey::from_bytes(ed25519_public)?;
+    Ok(PublicKey::from(key.to_montgomery().to_bytes()))
+}
+
+fn x25519_secret(identity: &PlcIdentity) -> Result<StaticSecret> {
+    let private_key = identity.keypair.private_key_bytes().ok_or_else(|| {
+        OcmError::Cryptography("Envelopes cannot be opened with a hardware-held key".to_string())
+    })?;
+    let signing_key = SigningKey::from_bytes(private_key);
+    Ok(StaticSecret::from(signing_key.to_scalar_bytes()))
+}
+
+/// Key-encryption key for one reader, bound to both ends of the exchange
+fn key_encryption_key(
+    shared_secret: &[u8; 32],
+    ephemeral_public: &PublicKey,
+    reader_public: &PublicKey,
+) -> Result<Zeroizing<[u8; 32]>> {
+    let mut info = KEY_WRAP_INFO.to_vec();
+    info.extend_from_slice(ephemeral_public.as_bytes());
+    info.extend_from_slice(reader_public.as_bytes());
+
+    let mut key = Zeroizing::new([0u8; 32]);
+    Hkdf::<Sha256>::new(None, shared_secret)
+        .expand(&info, key.as_mut())
+        .map_err(|_| OcmError::Cryptography("Key derivation failed".to_string()))?;
+    Ok(key)
+}
+
+fn decode(field: &str, value: &str) -> Result<Vec<u8>> {
+    general_purpose::STANDARD
+        .decode(value)
+        .map_err(|e| OcmError::Cryptography(format!("Envelope {} is not base64: {}", field, e)))
+}
+
+/// A decoded nonce; `Nonce::from_slice` panics on the wrong length, so check it first
+fn decode_nonce(field: &str, value: &str) -> Result<Nonce> {
+    let bytes = decode(field, value)?;
+    if bytes.len() != 12 {
+        return Err(OcmError::Cryptography(format!(
+            "Envelope {} is not 12 bytes",
+            field
+        )));
+    }
+    Ok(*Nonce::from_slice(&bytes))
+}
+
+/// Encrypt `plaintext` so that only the given DIDs, by their Ed25519 public keys, can
+/// read it. The content key is wrapped once per reader with a fresh ephemeral X25519 key
+pub fn seal(plaintext: &[u8], readers: &[(Did, [u8; 32])]) -> Result<SealedEnvelope> {
+    if readers.is_empty() {
+        return Err(OcmError::Validation(
+            "An envelope needs at least one reader".to_string(),
+        ));
+    }
+
+    let content_key = ChaCha20Poly1305::generate_key(&mut OsRng);
+    let nonce = ChaCha20Poly1305::generate_nonce(&mut OsRng);
+    let ciphertext = ChaCha20Poly1305::new(&content_key)
+        .encrypt(&nonce, plaintext)
+        .map_err(|_| OcmError::Cryptography("Encryption failed".to_string()))?;
+
+    let mut wrapped = Vec::with_capacity(readers.len());
+    for (did, public_key) in readers {
+        let reader_public = x25519_public(public_key)?;
+        let ephemeral = EphemeralSecret::random_from_rng(OsRng);
+        let ephemeral_public = PublicKey::from(&ephemeral);
+        let shared = ephemeral.diffie_hellman(&reader_public);
+        if !shared.was_contributory() {
+            return Err(OcmError::Cryptography(format!(
+                "Public key of {} cannot receive messages",
+                did
+            )));
+        }
+
+        let kek = key_encryption_key(shared.as_bytes(), &ephemeral_public, &reader_public)?;
+        let wrap_nonce = ChaCha20Poly1305::generate_nonce(&mut OsRng);
+        let wrapped_key = ChaCha20Poly1305::new(Key::from_slice(kek.as_ref()))
+            .encrypt(
+                &wrap_nonce,
+                Payload {
+                    msg: content_key.as_slice(),
+                    aad: did.as_bytes(),
+                },
+            )
+            .map_err(|_| OcmError::Cryptography("Key wrapping failed".to_string()))?;
+
+        wrapped.push(WrappedKey {
+            did: did.clone(),
+            ephemeral_public_key: general_purpose::STANDARD.encode(ephemeral_public.as_bytes()),
+            nonce: general_purpose::STANDARD.encode(wrap_nonce),
+            wrapped_key: general_purpose::STANDARD.encode(wrapped_key),
+        });
+    }
+
+    Ok(SealedEnvelope {
+        nonce: general_purpose::STANDARD.encode(nonce),
+        ciphertext: general_purpose::STANDARD.encode(ciphertext),
+        readers: wrapped,
+    })
+}
+
+/// Decrypt an envelope with the identity's key; fails if the identity is not a reader
+pub fn open(envelope: &SealedEnvelope, identity: &PlcIdentity) -> Result<Vec<u8>> {
+    let reader = envelope
+        .readers
+        .iter()
+        .find(|reader| reader.did == identity.did)
+        .ok_or_else(|| OcmError::Cryptography("Envelope is not addressed to us".to_string()))?;
+
+    let secret = x25519_secret(identity)?;
+    let reader_public = PublicKey::from(&secret);
+    let ephemeral_public: [u8; 32] = decode("ephemeral key", &reader.ephemeral_public_key)?
+        .try_into()
+        .map_err(|_| OcmError::Cryptography("Ephemeral key is not 32 bytes".to_string()))?;
+    let ephemeral_public = PublicKey::from(ephemeral_public);
+    let shared = secret.diffie_hellman(&ephemeral_public);
+    let kek = key_encryption_key(shared.as_bytes(), &ephemeral_public, &reader_public)?;
+
+    let wrap_nonce = decode_nonce("nonce", &reader.nonce)?;
+    let content_key = Zeroizing::new(
+        ChaCha20Poly1305::new(Key::from_slice(kek.as_ref()))
+            .decrypt(
+                &wrap_nonce,
+                Payload {
+                    msg: &decode("wrapped key", &reader.wrapped_key)?,
+                    aad: identity.did.as_bytes(),
+                },
+            )
+            .map_err(|_| OcmError::Cryptography("Cannot unwrap the envelope key".to_string()))?,
+    );
+
+    if content_key.len() != 32 {
+        return Err(OcmError::Cryptography(
+            "Envelope key is not 32 bytes".to_string(),
+        ));
+    }
+
+    let nonce = decode_nonce("nonce", &envelope.nonce)?;
+    ChaCha20Poly1305::new(Key::from_slice(&content_key))
+        .decrypt(
+            &nonce,
+            decode("ciphertext", &envelope.ciphertext)?.as_slice(),
+        )
+        .map_err(|_| OcmError::Cryptography("Envelope was tampered with".to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn reader(identity: &PlcIdentity) -> (Did, [u8; 32]) {
+        let public_key = general_purpose::STANDARD
+            .decode(&identity.keypair.public_key)
+            .unwrap()
+            .try_into()
+            .unwrap();
+        (identity.did.clone(), public_key)
+    }
+
+    #[test]
+    fn test_sealed_envelope_opens_for_each_reader() {
+        let alice = PlcIdentity::generate(None).unwrap();
+        let bob = PlcIdentity::generate(None).unwrap();
+        let envelope = seal(b"for your eyes only", &[reader(&alice), reader(&bob)]).unwrap();
+
+        assert_eq!(envelope.readers.len(), 2);
+        assert_eq!(open(&envelope, &alice).unwrap(), b"for your eyes only");
+        assert_eq!(open(&envelope, &bob).unwrap(), b"for your eyes only");
+    }
+
+    #[test]
+    fn test_seal_needs_a_reader() {
+        assert!(seal(b"nobody", &[]).is_err());
+    }
+
+    #[test]
+    fn test_envelope_does_not_open_for_another_identity() {
+        let alice = PlcIdentity::generate(None).unwrap();
+        let mallory = PlcIdentity::generate(None).unwrap();
+        let mut envelope = seal(b"secret", &[reader(&alice)]).unwrap();
+
+        assert!(open(&envelope, &mallory).is_err());
+
+        // Relabelling the wrapped key does not help without the reader's private key
+        envelope.readers[0].did = mallory.did.clone();
+        assert!(open(&envelope, &mallory).is_err());
+    }
+
+    #[test]
+    fn test_tampered_ciphertext_is_rejected() {
+        let alice = PlcIdentity::generate(None).unwrap();
+        let mut envelope = seal(b"secret", &[reader(&alice)]).unwrap();
+
+        let mut ciphertext = general_purpose::STANDARD
+            .decode(&envelope.ciphertext)
+            .unwrap();
+        ciphertext[0] ^= 1;
+        envelope.ciphertext = general_purpose::STANDARD.encode(ciphertext);
+
+        assert!(open(&envelope, &alice).is_err());
+    }
+
+    #[test]
+    fn test_wrapped_key_is_bound_to_the_reader_did() {
+        let alice = PlcIdentity::generate(None).unwrap();
+        let mut envelope = seal(b"secret", &[reader(&alice)]).unwrap();
+
+        // Same key under another DID: the key exchange matches but the AAD does not
+        let mut renamed = alice.clone();
+        renamed.did = Did::parse("did:plc:renamed").unwrap();
+        envelope.readers[0].did = renamed.did.clone();
+
+        assert!(open(&envelope, &renamed).is_err());
+    }
+}
@@ -0,0 +1,268 @@
+use crate::core::ids::Did;
+use crate::core::models::SignedMemory;
+use crate::identity::plc::{OcmProtocol, SignaturePolicy, SignerProvider};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::error::Error;
+
+/// Memory type for group membership changes; version 0 is the group's genesis
+pub const GROUP_MEMBERSHIP_MEMORY_TYPE: &str = "group_membership";
+
+/// Shared identity owned by a set of member DIDs.
+/// Memories authored by the group DID carry no author signature of their own and are
+/// accepted once `threshold` members have co-signed them
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GroupIdentity {
+    pub did: Did,
+    pub threshold: usize,
+    pub members: Vec<String>,
+    pub version: u64, // Incremented by every accepted membership change
+    pub created_at: String,
+}
+
+/// Content of a `group_membership` memory
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GroupMembership {
+    pub group_did: String,
+    pub version: u64,
+    pub threshold: usize,
+    pub members: Vec<String>,
+}
+
+impl GroupIdentity {
+    pub fn new(members: Vec<String>, threshold: usize) -> Result<Self, Box<dyn Error>> {
+        validate_membership(&members, threshold)?;
+
+        let created_at = chrono::Utc::now().to_rfc3339();
+        let mut sorted_members = members.clone();
+        sorted_members.sort();
+
+        let mut hasher = Sha256::new();
+        hasher.update(sorted_members.join(",").as_bytes());
+        hasher.update(created_at.as_bytes());
+        let did = Did::parse(&format!(
+            "did:group:{}",
+            &hex::encode(hasher.finalize())[..32]
+        ))?;
+
+        Ok(GroupIdentity {
+            did,
+            threshold,
+            members,
+            version: 0,
+            created_at,
+        })
+    }
+
+    pub fn is_member(&self, did: &str) -> bool {
+        self.members.iter().any(|m| m == did)
+    }
+
+    pub fn signature_policy(&self) -> SignaturePolicy {
+        SignaturePolicy::Threshold {
+            threshold: self.threshold,
+            signers: self.members.clone(),
+        }
+    }
+
+    /// Unsigned memory authored by the group, ready for members to co-sign
+    pub fn draft_memory(&self, memory_type: &str, memory_data: &str) -> SignedMemory {
+        SignedMemory::new(&self.did, memory_type, memory_data)
+    }
+
+    /// Founding membership memory; every founding member must co-sign it
+    pub fn genesis_memory(&self) -> Result<SignedMemory, Box<dyn Error>> {
+        self.membership_memory(self.version, self.threshold, self.members.clone())
+    }
+
+    /// Propose a new member set and threshold; needs `threshold` co-signatures from the
+    /// current members before `apply_membership_memory` will accept it
+    pub fn propose_membership_change(
+        &self,
+        members: Vec<String>,
+        threshold: usize,
+    ) -> Result<SignedMemory, Box<dyn Error>> {
+        validate_membership(&members, threshold)?;
+        self.membership_memory(self.version + 1, threshold, members)
+    }
+
+    fn membership_memory(
+        &self,
+        version: u64,
+        threshold: usize,
+        members: Vec<String>,
+    ) -> Result<SignedMemory, Box<dyn Error>> {
+        let membership = GroupMembership {
+            group_did: self.did.to_string(),
+            version,
+            threshold,
+            members,
+        };
+        Ok(self.draft_memory(
+            GROUP_MEMBERSHIP_MEMORY_TYPE,
+            &serde_json::to_string(&membership)?,
+        ))
+    }
+
+    /// Rebuild a group from its co-signed genesis memory
+    pub async fn from_genesis(
+        ocm: &mut OcmProtocol,
+        memory: &SignedMemory,
+    ) -> Result<Option<Self>, Box<dyn Error>> {
+        let membership = parse_membership(memory)?;
+        if membership.version != 0 || membership.group_did != memory.did {
+            return Ok(None);
+        }
+        validate_membership(&membership.members, membership.threshold)?;
+
+        let policy = SignaturePolicy::AllOf(membership.members.clone());
+        if !ocm.verify_co_signed_memory(memory, &policy).await? {
+            return Ok(None);
+        }
+
+        Ok(Some(GroupIdentity {
+            did: Did::parse(&membership.group_did)?,
+            threshold: membership.threshold,
+            members: membership.members,
+            version: 0,
+            created_at: memory.timestamp.clone(),
+        }))
+    }
+
+    /// Apply a co-signed membership change. Returns false if the memory is for another
+    /// group, skips a version, or lacks enough signatures from the current members
+    pub async fn apply_membership_memory(
+        &mut self,
+        ocm: &mut OcmProtocol,
+        memory: &SignedMemory,
+    ) -> Result<bool, Box<dyn Error>> {
+        let membership = parse_membership(memory)?;
+        if memory.did != self.did
+            || membership.group_did != self.did
+            || membership.version != self.version + 1
+        {
+            return Ok(false);
+        }
+        validate_membership(&membership.members, membership.threshold)?;
+
+        if !ocm.verify_group_memory(self, memory).await? {
+            return Ok(false);
+        }
+
+        self.members = membership.members;
+        self.threshold = membership.threshold;
+        self.version = membership.version;
+        Ok(true)
+    }
+}
+
+impl<S: SignerProvider> OcmProtocol<S> {
+    /// Verify a memory authored by a group DID against the group's t-of-n policy
+    pub async fn verify_group_memory(
+        &mut self,
+        group: &GroupIdentity,
+        memory: &SignedMemory,
+    ) -> Result<bool, Box<dyn Error>> {
+        if memory.did != group.did {
+            return Ok(false);
+        }
+        self.verify_co_signed_memory(memory, &group.signature_policy())
+            .await
+    }
+}
+
+fn parse_membership(memory: &SignedMemory) -> Result<GroupMembership, Box<dyn Error>> {
+    if memory.memory_type != GROUP_MEMBERSHIP_MEMORY_TYPE {
+        return Err(format!("Not a group membership memory: {}", memory.memory_type).into());
+    }
+    Ok(serde_json::from_str(&memory.memory_data)?)
+}
+
+fn validate_membership(members: &[String], threshold: usize) -> Result<(), Box<dyn Error>> {
+    if members.is_empty() {
+        return Err("A group needs at least one member".into());
+    }
+    if threshold == 0 || threshold > members.len() {
+        return Err(format!("Threshold must be between 1 and {} members", members.len()).into());
+    }
+    for (i, member) in members.iter().enumerate() {
+        if members[..i].contains(member) {
+            return Err(format!("Duplicate group member: {}", member).into());
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::identity::plc::{PlcDirectory, PlcIdentity};
+
+    /// Members whose DIDs resolve from the directory cache, and a protocol verifying
+    /// against that directory without network access
+    async fn members(count: usize) -> (Vec<PlcIdentity>, OcmProtocol) {
+        let mut directory = PlcDirectory::new();
+        directory.network_enabled = false;
+        let mut identities = Vec::new();
+        for _ in 0..count {
+            let identity = PlcIdentity::generate(None).unwrap();
+            directory.publish_identity(&identity).await.unwrap();
+            identities.push(identity);
+        }
+        let ocm = OcmProtocol::with_signer(identities[0].clone(), directory);
+        (identities, ocm)
+    }
+
+    fn group_of(identities: &[PlcIdentity], threshold: usize) -> GroupIdentity {
+        let dids = identities.iter().map(|i| i.did.to_string()).collect();
+        GroupIdentity::new(dids, threshold).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_group_memory_needs_threshold_signatures() {
+        let (identities, mut ocm) = members(3).await;
+        let group = group_of(&identities, 2);
+        let mut memory = group.draft_memory("note", "agreed");
+
+        identities[0].co_sign_memory(&mut memory).unwrap();
+        assert!(!ocm.verify_group_memory(&group, &memory).await.unwrap());
+
+        identities[1].co_sign_memory(&mut memory).unwrap();
+        assert!(ocm.verify_group_memory(&group, &memory).await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_duplicate_signer_counts_once() {
+        let (identities, mut ocm) = members(3).await;
+        let group = group_of(&identities, 2);
+        let mut memory = group.draft_memory("note", "agreed");
+
+        identities[0].co_sign_memory(&mut memory).unwrap();
+        assert!(identities[0].co_sign_memory(&mut memory).is_err());
+
+        // A repeated co-signature slipped in by hand still counts only once
+        let repeated = memory.co_signatures[0].clone();
+        memory.co_signatures.push(repeated);
+        assert!(!ocm.verify_group_memory(&group, &memory).await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_non_member_signatures_do_not_count() {
+        let (identities, mut ocm) = members(4).await;
+        let group = group_of(&identities[..3], 2);
+        let mut memory = group.draft_memory("note", "agreed");
+
+        identities[0].co_sign_memory(&mut memory).unwrap();
+        identities[3].co_sign_memory(&mut memory).unwrap();
+        assert!(!ocm.verify_group_memory(&group, &memory).await.unwrap());
+    }
+
+    #[test]
+    fn test_group_rejects_invalid_membership() {
+        let dids = vec!["did:plc:a".to_string(), "did:plc:b".to_string()];
+        assert!(GroupIdentity::new(dids.clone(), 0).is_err());
+        assert!(GroupIdentity::new(dids.clone(), 3).is_err());
+        assert!(GroupIdentity::new(vec![dids[0].clone(), dids[0].clone()], 1).is_err());
+        assert!(GroupIdentity::new(Vec::new(), 1).is_err());
+    }
+}
@@ -0,0 +1,200 @@
+use crate::config::HandlesConfig;
+use crate::core::error::{OcmError, Result};
+use crate::core::ids::Did;
+use crate::core::models::{HandleChange, HandleRecord, HandleVerification};
+use crate::core::redact::Redacted;
+use crate::persistence::database::Database;
+use ocm_protocol::handle::{
+    dns_txt_name, is_under_suffix, normalize_handle, parse_dns_txt, WELL_KNOWN_PATH,
+};
+use std::sync::Arc;
+use std::time::Duration;
+
+#[derive(serde::Deserialize)]
+struct DnsJsonResponse {
+    #[serde(rename = "Answer", default)]
+    answer: Vec<DnsJsonAnswer>,
+}
+
+#[derive(serde::Deserialize)]
+struct DnsJsonAnswer {
+    data: String,
+}
+
+/// Maps human-readable handles to DIDs. Handles under the node's suffix
+/// (`alice.family-ocm`) are first come, first served; domain handles are only
+/// accepted once the domain names the DID, as in atproto
+pub struct HandleRegistry {
+    database: Arc<Database>,
+    config: HandlesConfig,
+    http: reqwest::Client,
+}
+
+impl HandleRegistry {
+    pub fn new(config: HandlesConfig, database: Arc<Database>) -> Result<Self> {
+        let http = reqwest::Client::builder()
+            .timeout(Duration::from_secs(config.timeout_seconds))
+            .build()?;
+
+        Ok(Self {
+            database,
+            config,
+            http,
+        })
+    }
+
+    /// Give `did` the handle, replacing its previous one
+    pub async fn register(&self, handle: &str, did: &Did) -> Result<HandleRecord> {
+        let handle = normalize_handle(handle).map_err(OcmError::Validation)?;
+        let verification = if is_under_suffix(&handle, &self.config.local_suffix) {
+            HandleVerification::Local
+        } else if !self.config.verify_domains {
+            return Err(OcmError::Validation(format!(
+                "Only handles ending in .{} can be registered here",
+                self.config.local_suffix
+            )));
+        } else {
+            self.verify_domain(&handle, did).await.ok_or_else(|| {
+                OcmError::Validation(format!(
+                    "{} does not name this DID: add a TXT record at {} or serve it at https://{}{}",
+                    handle,
+                    dns_txt_name(&handle),
+                    handle,
+                    WELL_KNOWN_PATH
+                ))
+            })?
+        };
+
+        let now = chrono::Utc::now().to_rfc3339();
+        let registered_at = match self.database.get_handle_for_did(did)? {
+            Some(current) if current.handle == handle => current.registered_at,
+            _ => now.clone(),
+        };
+        let record = HandleRecord {
+            handle,
+            did: did.clone(),
+            verification,
+            verified_at: now,
+            registered_at,
+        };
+        let previous = self.database.set_handle(&record)?;
+        if previous.as_deref() != Some(record.handle.as_str()) {
+            println!(
+                "🏷️  {} is now @{} ({})",
+                Redacted::did(did),
+                record.handle,
+                verification.as_str()
+            );
+        }
+        Ok(record)
+    }
+
+    /// The DID a handle currently points to
+    pub fn resolve(&self, handle: &str) -> Result<Option<HandleRecord>> {
+        let handle = normalize_handle(handle).map_err(OcmError::Validation)?;
+        self.database.get_handle(&handle)
+    }
+
+    pub fn handle_for(&self, did: &Did) -> Result<Option<HandleRecord>> {
+        self.database.get_handle_for_did(did)
+    }
+
+    /// Every handle change of a DID, oldest first
+    pub fn history(&self, did: &Did) -> Result<Vec<HandleChange>> {
+        self.database.list_handle_changes(did)
+    }
+
+    pub fn release(&self, did: &Did) -> Result<()> {
+        if !self.database.release_handle(did)? {
+            return Err(OcmError::NotFound(format!(
+                "Handle of {}",
+                Redacted::did(did)
+            )));
+        }
+        Ok(())
+    }
+
+    /// Check a domain handle still names its DID, releasing it if not.
+    /// Domains change hands, so a handle verified once is not trusted forever
+    pub async fn reverify(&self, handle: &str) -> Result<HandleRecord> {
+        let record = self
+            .resolve(handle)?
+            .ok_or_else(|| OcmError::NotFound(format!("Handle {}", handle)))?;
+        if record.verification == HandleVerification::Local {
+            return Ok(record);
+        }
+
+        match self.verify_domain(&record.handle, &record.did).await {
+            Some(verification) => {
+                let record = HandleRecord {
+                    verification,
+                    verified_at: chrono::Utc::now().to_rfc3339(),
+                    ..record
+                };
+                self.database.set_handle(&record)?;
+                Ok(record)
+            }
+            None => {
+                self.database.release_handle(&record.did)?;
+                println!(
+                    "🏷️  Released @{}: the domain no longer names {}",
+                    record.handle,
+                    Redacted::did(&record.did)
+                );
+                Err(OcmError::Validation(format!(
+                    "{} no longer names its DID and was released",
+                    record.handle
+                )))
+            }
+        }
+    }
+
+    /// How the domain proves the DID, trying DNS before HTTPS
+    async fn verify_domain(&self, handle: &str, did: &Did) -> Option<HandleVerification> {
+        match self.dns_txt_dids(handle).await {
+            Ok(dids) if dids.iter().any(|d| d == did) => return Some(HandleVerification::Dns),
+            Ok(_) => {}
+            Err(e) => eprintln!("⚠️  DNS lookup for handle {} failed: {}", handle, e),
+        }
+        match self.well_known_did(handle).await {
+            Ok(served) if served == *did => Some(HandleVerification::WellKnown),
+            Ok(_) => None,
+            Err(e) => {
+                eprintln!("⚠️  Fetching {}{} failed: {}", handle, WELL_KNOWN_PATH, e);
+                None
+            }
+        }
+    }
+
+    async fn dns_txt_dids(&self, handle: &str) -> Result<Vec<String>> {
+        let response: DnsJsonResponse = self
+            .http
+            .get(&self.config.dns_over_https_url)
+            .query(&[("name", dns_txt_name(handle).as_str()), ("type", "TXT")])
+            .header("accept", "application/dns-json")
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?;
+
+        Ok(response
+            .answer
+            .iter()
+            .filter_map(|answer| parse_dns_txt(&answer.data))
+            .map(str::to_string)
+            .collect())
+    }
+
+    async fn well_known_did(&self, handle: &str) -> Result<String> {
+        let body = self
+            .http
+            .get(format!("https://{}{}", handle, WELL_KNOWN_PATH))
+            .send()
+            .await?
+            .error_for_status()?
+            .text()
+            .await?;
+        Ok(body.trim().to_string())
+    }
+}
@@ -0,0 +1,151 @@
+use crate::config::{DidRefreshConfig, PlcConfig};
+use crate::core::error::Result;
+use crate::core::ids::Did;
+use crate::core::models::{DidKeyChange, KEY_ROTATED_EVENT};
+use crate::core::redact::Redacted;
+use crate::identity::plc::{DidResolution, PlcDirectory};
+use crate::persistence::database::Database;
+use crate::supervisor::{RestartPolicy, TaskSupervisor};
+use serde::Serialize;
+use std::sync::Arc;
+use tokio::sync::Mutex;
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct RefreshReport {
+    pub checked: usize,
+    pub rotated: usize,
+    pub unreachable: usize,
+}
+
+/// Re-resolves the PLC documents of the DIDs this node holds memories from and pins
+/// the keys they publish. When a pinned DID's keys change, a `key_rotated` activity
+/// event is recorded, which also reaches the DID's push subscribers. Memories accepted
+/// offline on an attached operation chain are checked again here
+pub struct DidKeyRefresher {
+    database: Arc<Database>,
+    directory: Mutex<PlcDirectory>,
+    config: DidRefreshConfig,
+    network_enabled: bool,
+}
+
+impl DidKeyRefresher {
+    pub fn new(database: Arc<Database>, plc: &PlcConfig) -> Self {
+        Self {
+            database,
+            directory: Mutex::new(PlcDirectory::with_config(plc)),
+            config: plc.refresh.clone(),
+            network_enabled: plc.enable_network_calls,
+        }
+    }
+
+    /// Check the DIDs whose documents have gone longest without a check
+    pub async fn refresh_due(&self) -> Result<RefreshReport> {
+        let checked_before = (chrono::Utc::now()
+            - chrono::Duration::hours(self.config.stale_after_hours as i64))
+        .to_rfc3339();
+        let dids = self
+            .database
+            .list_dids_due_for_refresh(&checked_before, self.config.dids_per_run)?;
+
+        let mut report = RefreshReport::default();
+        for did in dids {
+            report.checked += 1;
+            match self.refresh(&did).await? {
+                Some(change) if change.is_rotation() => report.rotated += 1,
+                Some(_) => {}
+                None => report.unreachable += 1,
+            }
+        }
+        Ok(report)
+    }
+
+    /// Re-resolve one DID and pin its keys; None if the directory could not be reached.
+    /// A DID the directory no longer knows has had all its keys removed
+    pub async fn refresh(&self, did: &Did) -> Result<Option<DidKeyChange>> {
+        let resolution = self.directory.lock().await.refresh_did(did).await;
+        let keys = match resolution {
+            DidResolution::Resolved(document) => document.multibase_keys(),
+            DidResolution::NotFound => Vec::new(),
+            DidResolution::CachedStale(_) => {
+                self.database
+                    .record_did_refresh_error(did, "PLC directory unreachable")?;
+                return Ok(None);
+            }
+            DidResolution::Unreachable(reason) => {
+                self.database.record_did_refresh_error(did, &reason)?;
+                return Ok(None);
+            }
+        };
+
+        let change = self.database.record_did_keys(did, &keys)?;
+        self.recheck_offline_verifications(did, &keys)?;
+        if change.is_rotation() {
+            eprintln!(
+                "🚨 Keys of {} changed: {} added, {} removed",
+                Redacted::did(did),
+                change.added.len(),
+                change.removed.len()
+            );
+            self.database.record_did_activity_event(
+                KEY_ROTATED_EVENT,
+                did,
+                None,
+                &serde_json::json!({ "added": change.added, "removed": change.removed }),
+            )?;
+        }
+        Ok(Some(change))
+    }
+
+    /// Check memories accepted on an attached operation chain against the keys the
+    /// directory publishes now. Those signed with a key it does not publish are quarantined
+    fn recheck_offline_verifications(&self, did: &Did, keys: &[String]) -> Result<()> {
+        for pending in self.database.list_pending_offline_verifications(did)? {
+            let confirmed = keys.contains(&pending.public_key);
+            if !confirmed {
+                eprintln!(
+                    "🚨 Memory {} of {} was signed with a key its PLC document does not publish",
+                    pending.memory_id,
+                    Redacted::did(did)
+                );
+                self.database.quarantine_signed_memory(
+                    &pending.memory_id,
+                    "Signed with a key the author's PLC document does not publish",
+                )?;
+            }
+            self.database
+                .record_offline_recheck(&pending.memory_id, confirmed)?;
+        }
+        Ok(())
+    }
+
+    pub fn start(self: Arc<Self>, supervisor: &TaskSupervisor) {
+        if !self.config.enabled {
+            return;
+        }
+        if !self.network_enabled {
+            println!("🔑 DID key refresh needs plc.enable_network_calls; not started");
+            return;
+        }
+        let interval_minutes = self.config.interval_minutes;
+
+        supervisor.spawn("did_key_refresh", RestartPolicy::Always, move || {
+            let service = self.clone();
+            async move {
+                let mut interval =
+                    tokio::time::interval(tokio::time::Duration::from_secs(interval_minutes * 60));
+
+                loop {
+                    interval.tick().await;
+                    match service.refresh_due().await {
+                        Ok(report) if report.checked > 0 => println!(
+                            "🔑 Refreshed {} DID documents: {} key changes, {} unreachable",
+                            report.checked, report.rotated, report.unreachable
+                        ),
+                        Ok(_) => {}
+                        Err(e) => eprintln!("⚠️  DID key refresh failed: {}", e),
+                    }
+                }
+            }
+        });
+    }
+}
@@ -0,0 +1,122 @@
+use crate::config::LinkageConfig;
+use crate::core::models::Individual;
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+
+type HmacSha256 = Hmac<Sha256>;
+
+/// Used when no linkage key is configured. Being public, it only keeps fingerprints
+/// from being read at a glance, not from dictionary attacks
+const DEFAULT_LINKAGE_KEY: &[u8] = b"ocm-record-linkage-v1";
+
+/// Date formats accepted for dates of birth, normalized to the first
+const DOB_FORMATS: &[&str] = &["%Y-%m-%d", "%m/%d/%Y", "%d.%m.%Y", "%Y/%m/%d"];
+
+/// What is stored of a proxy record for comparing it with other organizations'
+/// records: a keyed hash of the date of birth, which candidates must share, and a
+/// Bloom filter of the name's bigrams, which tolerates typos and variant spellings
+#[derive(Debug, Clone)]
+pub struct LinkageFingerprint {
+    pub dob_key: String,
+    pub name_bloom: Vec<u8>,
+}
+
+/// Computes and compares fingerprints with the node's linkage key
+pub struct RecordLinker {
+    config: LinkageConfig,
+}
+
+impl RecordLinker {
+    pub fn new(config: LinkageConfig) -> Self {
+        Self { config }
+    }
+
+    pub fn threshold(&self) -> f64 {
+        self.config.match_threshold
+    }
+
+    /// The individual's fingerprint, or None without a parseable date of birth or a name
+    pub fn fingerprint(&self, individual: &Individual) -> Option<LinkageFingerprint> {
+        if !self.config.enabled {
+            return None;
+        }
+        let dob = normalize_dob(individual.dob.as_deref()?)?;
+        let first = normalize_name(&individual.first_name);
+        let last = normalize_name(&individual.last_name);
+        if first.is_empty() && last.is_empty() {
+            return None;
+        }
+
+        let mut bloom = vec![0u8; self.config.bloom_bits.div_ceil(8)];
+        for gram in bigrams(&first).chain(bigrams(&last)) {
+            for position in self.positions(&gram) {
+                bloom[position / 8] |= 1 << (position % 8);
+            }
+        }
+        Some(LinkageFingerprint {
+            dob_key: hex::encode(self.mac(&[b"dob:", dob.as_bytes()])),
+            name_bloom: bloom,
+        })
+    }
+
+    /// Bit positions of a bigram, by double hashing one keyed MAC
+    fn positions(&self, gram: &str) -> impl Iterator<Item = usize> {
+        let digest = self.mac(&[b"name:", gram.as_bytes()]);
+        let h1 = u64::from_be_bytes(digest[..8].try_into().expect("8 bytes"));
+        let h2 = u64::from_be_bytes(digest[8..16].try_into().expect("8 bytes"));
+        let bits = self.config.bloom_bits as u64;
+        (0..self.config.hash_functions as u64)
+            .map(move |i| (h1.wrapping_add(i.wrapping_mul(h2)) % bits) as usize)
+    }
+
+    fn mac(&self, parts: &[&[u8]]) -> Vec<u8> {
+        let key = if self.config.key.is_empty() {
+            DEFAULT_LINKAGE_KEY
+        } else {
+            self.config.key.as_bytes()
+        };
+        let mut mac = HmacSha256::new_from_slice(key).expect("HMAC can take key of any size");
+        for part in parts {
+            mac.update(part);
+        }
+        mac.finalize().into_bytes().to_vec()
+    }
+}
+
+/// Dice coefficient of two Bloom filters: 1.0 for identical names, near 0 for unrelated
+pub fn dice_similarity(a: &[u8], b: &[u8]) -> f64 {
+    if a.len() != b.len() {
+        return 0.0;
+    }
+    let ones = |bytes: &[u8]| bytes.iter().map(|b| b.count_ones()).sum::<u32>();
+    let common: u32 = a.iter().zip(b).map(|(x, y)| (x & y).count_ones()).sum();
+    let total = ones(a) + ones(b);
+    if total == 0 {
+        0.0
+    } else {
+        2.0 * common as f64 / total as f64
+    }
+}
+
+/// Lowercase letters and digits only, so "O'Brien" and "obrien" agree
+fn normalize_name(name: &str) -> String {
+    name.chars()
+        .filter(|c| c.is_alphanumeric())
+        .flat_map(char::to_lowercase)
+        .collect()
+}
+
+fn normalize_dob(dob: &str) -> Option<String> {
+    DOB_FORMATS.iter().find_map(|format| {
+        chrono::NaiveDate::parse_from_str(dob.trim(), format)
+            .ok()
+            .map(|date| date.format("%Y-%m-%d").to_string())
+    })
+}
+
+/// Bigrams of a name padded with spaces, so first and last letters count as well
+fn bigrams(name: &str) -> impl Iterator<Item = String> {
+    let padded: Vec<char> = format!(" {} ", name).chars().collect();
+    let count = if name.is_empty() { 0 } else { padded.len() - 1 };
+    (0..count).map(move |i| padded[i..i + 2].iter().collect())
+}
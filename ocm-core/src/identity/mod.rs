@@ -1,9 +1,22 @@
 #[cfg(feature = "native")]
 pub mod claims;
-pub mod plc;
+pub mod envelope;
+pub mod group;
+#[cfg(feature = "native")]
+pub mod handles;
+#[cfg(feature = "native")]
+pub mod key_pins;
 #[cfg(feature = "native")]
+pub mod linkage;
+pub mod node_info;
+#[cfg(feature = "native")]
+pub mod organizations;
+pub mod plc;
+pub mod signer;
+#[cfg(feature = "insecure-stub")]
 pub mod stub_plc;
 
 #[cfg(feature = "native")]
 pub use claims::*;
+pub use group::*;
 pub use plc::*;
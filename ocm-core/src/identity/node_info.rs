@@ -0,0 +1,77 @@
+use crate::identity::plc::{verify_payload_signature, PlcIdentity};
+use base64::{engine::general_purpose, Engine as _};
+use ocm_protocol::message::PEER_PROTOCOL_VERSION;
+use ocm_protocol::relay::RELAY_PROTOCOL_VERSION;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// What a node says about itself, signed by its identity so tools and peers can
+/// fingerprint it and check the statement came from the DID it names
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NodeInfo {
+    pub software: String,
+    pub version: String,
+    pub protocol_versions: BTreeMap<String, u32>, // Protocol name -> version spoken
+    pub did: String,
+    pub public_key: String, // Base64 Ed25519 key the signature is made with
+    pub started_at: String,
+    pub uptime_seconds: u64,
+    pub features: Vec<String>,
+    pub timestamp: String,
+    pub signature: String,
+}
+
+impl NodeInfo {
+    /// Describe this node as of now and sign the description
+    pub fn signed(
+        identity: &PlcIdentity,
+        started_at: chrono::DateTime<chrono::Utc>,
+        features: Vec<String>,
+    ) -> Result<Self, Box<dyn std::error::Error>> {
+        let now = chrono::Utc::now();
+        let mut info = NodeInfo {
+            software: env!("CARGO_PKG_NAME").to_string(),
+            version: env!("CARGO_PKG_VERSION").to_string(),
+            protocol_versions: BTreeMap::from([
+                ("peer".to_string(), PEER_PROTOCOL_VERSION),
+                ("relay".to_string(), RELAY_PROTOCOL_VERSION),
+            ]),
+            did: identity.did.to_string(),
+            public_key: identity.keypair.public_key.clone(),
+            started_at: started_at.to_rfc3339(),
+            uptime_seconds: (now - started_at).num_seconds().max(0) as u64,
+            features,
+            timestamp: now.to_rfc3339(),
+            signature: String::new(),
+        };
+        info.signature = identity.sign_payload(&info.get_signing_payload())?;
+        Ok(info)
+    }
+
+    pub fn get_signing_payload(&self) -> String {
+        serde_json::json!({
+            "software": self.software,
+            "version": self.version,
+            "protocol_versions": self.protocol_versions,
+            "did": self.did,
+            "public_key": self.public_key,
+            "started_at": self.started_at,
+            "uptime_seconds": self.uptime_seconds,
+            "features": self.features,
+            "timestamp": self.timestamp
+        })
+        .to_string()
+    }
+
+    /// Whether the signature was made with `public_key`. Callers must still check that
+    /// the key belongs to `did`, e.g. against its PLC document
+    pub fn verify_signature(&self) -> bool {
+        general_purpose::STANDARD
+            .decode(&self.public_key)
+            .ok()
+            .and_then(|key| <[u8; 32]>::try_from(key).ok())
+            .is_some_and(|key| {
+                verify_payload_signature(&key, &self.get_signing_payload(), &self.signature)
+            })
+    }
+}
@@ -0,0 +1,167 @@
+use crate::core::error::{OcmError, Result};
+use crate::core::ids::Did;
+use crate::core::models::{
+    HandleVerification, OrganizationAttestationData, OrganizationVerification,
+    OrganizationVerificationMethod, SignedMemory, ORGANIZATION_ATTESTATION_MEMORY_TYPE,
+};
+use crate::core::redact::Redacted;
+use crate::identity::handles::HandleRegistry;
+use crate::identity::plc::PlcIdentity;
+use crate::persistence::database::Database;
+use std::sync::Arc;
+
+/// Longest chain of attestations back to a domain-verified organization
+pub const MAX_ATTESTATION_DEPTH: usize = 3;
+
+/// Lets parents trust that a claim token came from the organization it names. An
+/// organization is verified by holding a domain handle, or by an attestation from an
+/// organization that is itself verified
+pub struct OrganizationVerifier {
+    database: Arc<Database>,
+    handles: Arc<HandleRegistry>,
+}
+
+impl OrganizationVerifier {
+    pub fn new(database: Arc<Database>, handles: Arc<HandleRegistry>) -> Self {
+        Self { database, handles }
+    }
+
+    /// Verify an organization by its domain, which must name the DID in DNS or at
+    /// /.well-known/ocm-did. The domain becomes the organization's handle
+    pub async fn verify_domain(
+        &self,
+        organization_did: &Did,
+        domain: &str,
+    ) -> Result<OrganizationVerification> {
+        let record = self.handles.register(domain, organization_did).await?;
+        if record.verification == HandleVerification::Local {
+            self.handles.release(organization_did)?;
+            return Err(OcmError::Validation(format!(
+                "{} is a local handle and proves nothing about the organization",
+                record.handle
+            )));
+        }
+
+        let verification = OrganizationVerification {
+            organization_did: organization_did.clone(),
+            method: OrganizationVerificationMethod::Domain,
+            domain: Some(record.handle.clone()),
+            attested_by: None,
+            attestation_memory_id: None,
+            verified_at: record.verified_at,
+        };
+        self.database.set_organization_verification(&verification)?;
+        println!(
+            "🛡️  {} verified by its domain {}",
+            Redacted::did(organization_did),
+            record.handle
+        );
+        Ok(verification)
+    }
+
+    /// Vouch for another organization. The attester must be verified itself; the
+    /// signed attestation is stored so clients can check it
+    pub fn attest(
+        &self,
+        attester: &PlcIdentity,
+        organization_did: &Did,
+    ) -> Result<OrganizationVerification> {
+        if attester.did == *organization_did {
+            return Err(OcmError::Validation(
+                "An organization cannot vouch for itself".to_string(),
+            ));
+        }
+        if self.status(&attester.did)?.is_none() {
+            return Err(OcmError::Validation(format!(
+                "{} is not verified and cannot vouch for other organizations",
+                Redacted::did(&attester.did)
+            )));
+        }
+
+        let now = chrono::Utc::now().to_rfc3339();
+        let attestation = OrganizationAttestationData {
+            organization_did: organization_did.clone(),
+            attested_by: attester.did.clone(),
+            issued_at: now.clone(),
+        };
+        let mut memory = SignedMemory::new(
+            &attester.did,
+            ORGANIZATION_ATTESTATION_MEMORY_TYPE,
+            &serde_json::to_string(&attestation)?,
+        );
+        attester.sign_memory(&mut memory)?;
+        self.database.create_signed_memory(&memory)?;
+
+        let verification = OrganizationVerification {
+            organization_did: organization_did.clone(),
+            method: OrganizationVerificationMethod::Attestation,
+            domain: None,
+            attested_by: Some(attester.did.clone()),
+            attestation_memory_id: Some(memory.id.clone()),
+            verified_at: now,
+        };
+        self.database.set_organization_verification(&verification)?;
+        println!(
+            "🛡️  {} vouched for {}",
+            Redacted::did(&attester.did),
+            Redacted::did(organization_did)
+        );
+        Ok(verification)
+    }
+
+    /// The organization's verification if it still holds
+    pub fn status(&self, organization_did: &Did) -> Result<Option<OrganizationVerification>> {
+        current_verification(&self.database, organization_did)
+    }
+
+    pub fn revoke(&self, organization_did: &Did) -> Result<()> {
+        if !self
+            .database
+            .delete_organization_verification(organization_did)?
+        {
+            return Err(OcmError::NotFound(format!(
+                "Verification of {}",
+                Redacted::did(organization_did)
+            )));
+        }
+        println!(
+            "🛡️  Revoked the verification of {}",
+            Redacted::did(organization_did)
+        );
+        Ok(())
+    }
+}
+
+/// An organization's verification if it still holds: a domain method needs the
+/// organization to still hold that domain handle (see `HandleRegistry::reverify`), and
+/// an attestation needs its attester to still be verified, at most
+/// MAX_ATTESTATION_DEPTH attestations from a domain
+pub fn current_verification(
+    database: &Database,
+    organization_did: &Did,
+) -> Result<Option<OrganizationVerification>> {
+    let mut did = organization_did.clone();
+    let mut first = None;
+    for _ in 0..=MAX_ATTESTATION_DEPTH {
+        let Some(verification) = database.get_organization_verification(&did)? else {
+            return Ok(None);
+        };
+        match verification.method {
+            OrganizationVerificationMethod::Domain => {
+                let holds = database.get_handle_for_did(&did)?.is_some_and(|handle| {
+                    handle.verification != HandleVerification::Local
+                        && verification.domain.as_deref() == Some(handle.handle.as_str())
+                });
+                return Ok(holds.then(|| first.unwrap_or(verification)));
+            }
+            OrganizationVerificationMethod::Attestation => {
+                let Some(attester) = verification.attested_by.clone() else {
+                    return Ok(None);
+                };
+                first.get_or_insert(verification);
+                did = attester;
+            }
+        }
+    }
+    Ok(None)
+}
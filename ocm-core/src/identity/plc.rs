@@ -1,53 +1,39 @@
-use crate::core::models::SignedMemory;
+use crate::core::ids::{Did, MemoryId};
+use crate::core::models::{CoSignature, SignedMemory, WitnessReceipt};
+use crate::core::redact::Redacted;
+use crate::identity::signer::{SecureKey, Signer as KeySigner};
 use base64::{engine::general_purpose, Engine as _};
 use ed25519_dalek::{Signature, Signer, SigningKey, Verifier, VerifyingKey};
+use ocm_protocol::did::{decode_did_key, decode_multibase_ed25519, ED25519_PUB_MULTICODEC};
+pub use ocm_protocol::did::{
+    needs_plc_document, verify_memory_with_document, verify_payload_signature, PlcDocument,
+    Service, VerificationMethod,
+};
 #[cfg(feature = "native")]
 use reqwest::Client;
 use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
 use std::error::Error;
-use zeroize::{Zeroize, ZeroizeOnDrop};
+use std::sync::Arc;
 
 pub const BLUESKY_PLC_DIRECTORY: &str = "https://plc.directory";
 
+// Domain separator for deriving per-context pseudonym keys from a master key
+const PSEUDONYM_KEY_DOMAIN: &[u8] = b"ocm-pseudonym-v1";
 #[derive(Debug, Clone)]
 pub struct PlcIdentity {
-    pub did: String,
+    pub did: Did,
     pub keypair: PlcKeypair,
     pub plc_operations: Vec<PlcOperation>,
     pub created_at: String,
     pub rotation_keys: Vec<String>,
 }
 
-/// Secure keypair with automatic memory zeroing
+/// Public key and the signer holding its private key
 #[derive(Clone)]
 pub struct PlcKeypair {
-    pub public_key: String, // Base64 encoded (safe to store)
-    private_key: SecureKey, // Secure private key storage
-}
-
-/// Secure key storage that zeroes memory on drop
-#[derive(Zeroize, ZeroizeOnDrop)]
-struct SecureKey {
-    #[zeroize(skip)]
-    key_data: Box<[u8; 32]>,
-}
-
-impl SecureKey {
-    fn new(key_bytes: [u8; 32]) -> Self {
-        SecureKey {
-            key_data: Box::new(key_bytes),
-        }
-    }
-
-    fn as_bytes(&self) -> &[u8; 32] {
-        &self.key_data
-    }
-}
-
-impl Clone for SecureKey {
-    fn clone(&self) -> Self {
-        SecureKey::new(*self.key_data)
-    }
+    pub public_key: String,          // Base64 encoded (safe to store)
+    private_key: Arc<dyn KeySigner>, // In memory, zeroed on drop, unless a backend holds it
 }
 
 // Custom Debug implementation to prevent key leakage
@@ -65,13 +51,26 @@ impl PlcKeypair {
     pub fn new(public_key: String, private_key_bytes: [u8; 32]) -> Self {
         PlcKeypair {
             public_key,
-            private_key: SecureKey::new(private_key_bytes),
+            private_key: Arc::new(SecureKey::new(private_key_bytes)),
+        }
+    }
+
+    /// A keypair whose private key is held by a keychain or hardware backend
+    pub fn from_signer(signer: Arc<dyn KeySigner>) -> Self {
+        PlcKeypair {
+            public_key: general_purpose::STANDARD.encode(signer.public_key()),
+            private_key: signer,
         }
     }
 
-    /// Get the private key bytes for cryptographic operations
-    pub fn private_key_bytes(&self) -> &[u8; 32] {
-        self.private_key.as_bytes()
+    /// The private key bytes, unless a backend holds the key and does not reveal it
+    pub fn private_key_bytes(&self) -> Option<&[u8; 32]> {
+        self.private_key.seed()
+    }
+
+    /// Sign a message, returning a base64 Ed25519 signature
+    pub fn sign(&self, message: &[u8]) -> Result<String, Box<dyn Error>> {
+        Ok(general_purpose::STANDARD.encode(self.private_key.sign(message)?))
     }
 }
 
@@ -89,42 +88,85 @@ pub struct PlcOperation {
     pub verification_methods: Option<serde_json::Value>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct PlcDocument {
-    pub id: String,
-    #[serde(rename = "@context")]
-    pub context: Vec<String>,
-    #[serde(rename = "alsoKnownAs")]
-    pub also_known_as: Option<Vec<String>>,
-    #[serde(rename = "verificationMethod")]
-    pub verification_method: Option<Vec<VerificationMethod>>,
-    pub service: Option<Vec<Service>>,
+impl PlcOperation {
+    /// What the operation's signature covers: the operation with an empty signature
+    pub fn signing_payload(&self) -> String {
+        let unsigned = PlcOperation {
+            signature: String::new(),
+            ..self.clone()
+        };
+        serde_json::to_string(&unsigned).unwrap_or_default()
+    }
+
+    /// Hash of the signed operation, which the next operation names as `prev`
+    pub fn hash(&self) -> String {
+        let serialized = serde_json::to_string(self).unwrap_or_default();
+        hex::encode(Sha256::digest(serialized.as_bytes()))
+    }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct VerificationMethod {
-    pub id: String,
-    #[serde(rename = "type")]
-    pub method_type: String,
-    pub controller: String,
-    #[serde(rename = "publicKeyMultibase")]
-    pub public_key_multibase: Option<String>,
+/// What looking a DID up in the PLC directory found
+#[derive(Debug, Clone)]
+pub enum DidResolution {
+    Resolved(PlcDocument),
+    CachedStale(PlcDocument), // The directory could not be reached and the cached copy is past its TTL
+    Unreachable(String),      // The directory could not be reached and nothing is cached
+    NotFound,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct Service {
-    pub id: String,
-    #[serde(rename = "type")]
-    pub service_type: String,
-    #[serde(rename = "serviceEndpoint")]
-    pub service_endpoint: String,
+impl DidResolution {
+    /// The document verification may rely on under `policy`. Stale copies only count
+    /// when failing open; a directory that cannot be reached is an error either way,
+    /// never a reason to trust a signature
+    pub fn into_trusted(self, policy: PlcFailurePolicy) -> Result<Option<PlcDocument>, String> {
+        match (self, policy) {
+            (DidResolution::Resolved(document), _) => Ok(Some(document)),
+            (DidResolution::CachedStale(document), PlcFailurePolicy::FailOpen) => {
+                Ok(Some(document))
+            }
+            (DidResolution::CachedStale(_), PlcFailurePolicy::FailClosed) => {
+                Err("PLC directory unreachable and the cached document has expired".to_string())
+            }
+            (DidResolution::Unreachable(reason), _) => Err(reason),
+            (DidResolution::NotFound, _) => Ok(None),
+        }
+    }
+
+    /// Any document found, fresh or not, for display rather than verification
+    pub fn into_document(self) -> Option<PlcDocument> {
+        match self {
+            DidResolution::Resolved(document) | DidResolution::CachedStale(document) => {
+                Some(document)
+            }
+            DidResolution::Unreachable(_) | DidResolution::NotFound => None,
+        }
+    }
+}
+
+/// Whether verification may fall back on cached documents past their TTL while the
+/// PLC directory cannot be reached
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PlcFailurePolicy {
+    #[default]
+    FailClosed,
+    FailOpen,
+}
+
+struct CachedDocument {
+    document: PlcDocument,
+    fetched_at: chrono::DateTime<chrono::Utc>,
+    pinned: bool, // Published by this node, so never refetched
 }
 
 pub struct PlcDirectory {
     #[cfg(feature = "native")]
     pub client: Client,
     pub base_url: String,
-    pub local_cache: std::collections::HashMap<String, PlcDocument>,
+    pub network_enabled: bool,
+    pub cache_ttl: chrono::Duration,
+    pub failure_policy: PlcFailurePolicy,
+    cache: std::collections::HashMap<String, CachedDocument>,
 }
 
 impl PlcDirectory {
@@ -133,7 +175,21 @@ impl PlcDirectory {
             #[cfg(feature = "native")]
             client: Client::new(),
             base_url: BLUESKY_PLC_DIRECTORY.to_string(),
-            local_cache: std::collections::HashMap::new(),
+            network_enabled: true,
+            cache_ttl: chrono::Duration::hours(24),
+            failure_policy: PlcFailurePolicy::default(),
+            cache: std::collections::HashMap::new(),
+        }
+    }
+
+    #[cfg(feature = "native")]
+    pub fn with_config(config: &crate::config::PlcConfig) -> Self {
+        PlcDirectory {
+            base_url: config.directory_url.clone(),
+            network_enabled: config.enable_network_calls,
+            cache_ttl: chrono::Duration::hours(config.cache_ttl_hours as i64),
+            failure_policy: config.failure_policy,
+            ..Self::new()
         }
     }
 
@@ -158,10 +214,10 @@ impl PlcDirectory {
         let plc_keypair = PlcKeypair::new(public_key_b64.clone(), private_key_bytes);
 
         // Create genesis operation
-        let genesis_op = PlcOperation {
+        let mut genesis_op = PlcOperation {
             operation_type: "plc_operation".to_string(),
             did: did.clone(),
-            signature: String::new(), // Will be filled after signing
+            signature: String::new(), // Filled in below
             created_at: chrono::Utc::now().to_rfc3339(),
             prev: None,
             services: Some(serde_json::json!({
@@ -181,15 +237,24 @@ impl PlcDirectory {
             })),
         };
 
+        genesis_op.signature = general_purpose::STANDARD.encode(
+            signing_key
+                .sign(genesis_op.signing_payload().as_bytes())
+                .to_bytes(),
+        );
+
         let identity = PlcIdentity {
-            did,
+            did: Did::parse(&did)?,
             keypair: plc_keypair,
             plc_operations: vec![genesis_op],
             created_at: chrono::Utc::now().to_rfc3339(),
             rotation_keys: vec![public_key_b64],
         };
 
-        println!("🆔 Generated Bluesky PLC identity: {}", identity.did);
+        println!(
+            "🆔 Generated Bluesky PLC identity: {}",
+            Redacted::did(&identity.did)
+        );
         println!("   Note: This identity is not yet published to the PLC directory");
         println!("   In production, call publish_identity() to register with Bluesky PLC");
 
@@ -230,7 +295,7 @@ impl PlcDirectory {
 
         // Create the PLC document
         let plc_doc = PlcDocument {
-            id: identity.did.clone(),
+            id: identity.did.to_string(),
             context: vec![
                 "https://www.w3.org/ns/did/v1".to_string(),
                 "https://w3id.org/security/multikey/v1".to_string(),
@@ -239,7 +304,7 @@ impl PlcDirectory {
             verification_method: Some(vec![VerificationMethod {
                 id: format!("{}#atproto", identity.did),
                 method_type: "Multikey".to_string(),
-                controller: identity.did.clone(),
+                controller: identity.did.to_string(),
                 public_key_multibase: Some(self.encode_multibase_ed25519(
                     &general_purpose::STANDARD.decode(&identity.keypair.public_key)?,
                 )),
@@ -262,14 +327,21 @@ impl PlcDirectory {
 
         // if response.status().is_success() {
         //     println!("✅ Successfully published identity to PLC directory");
-        //     self.local_cache.insert(identity.did.clone(), plc_doc);
+        //     // then cache the document as below
         // } else {
         //     println!("❌ Failed to publish identity: {}", response.status());
         //     return Err(format!("PLC publish failed with status: {}", response.status()).into());
         // }
 
         // For demo, just cache locally
-        self.local_cache.insert(identity.did.clone(), plc_doc);
+        self.cache.insert(
+            identity.did.to_string(),
+            CachedDocument {
+                document: plc_doc,
+                fetched_at: chrono::Utc::now(),
+                pinned: true,
+            },
+        );
         println!("✅ Simulated PLC directory publication (cached locally)");
         println!(
             "   Real publication would require network connectivity and proper AT Proto setup"
@@ -278,46 +350,96 @@ impl PlcDirectory {
         Ok(())
     }
 
-    pub async fn resolve_did(&mut self, did: &str) -> Result<Option<PlcDocument>, Box<dyn Error>> {
-        // Check local cache first
-        if let Some(cached_doc) = self.local_cache.get(did) {
-            return Ok(Some(cached_doc.clone()));
+    /// Look a DID up, answering from the cache while its copy is younger than the TTL.
+    /// Network failures are reported as such rather than as a missing DID
+    pub async fn resolve_did(&mut self, did: &str) -> DidResolution {
+        if let Some(cached) = self.cache.get(did) {
+            if cached.pinned || chrono::Utc::now() - cached.fetched_at < self.cache_ttl {
+                return DidResolution::Resolved(cached.document.clone());
+            }
         }
+        self.refresh_did(did).await
+    }
 
-        // Try to fetch from real PLC directory
-        let resolve_url = format!("{}/{}", self.base_url, did);
+    /// Fetch a DID's document even if the cached copy is fresh; documents this node
+    /// published are still answered locally
+    pub async fn refresh_did(&mut self, did: &str) -> DidResolution {
+        if let Some(cached) = self.cache.get(did).filter(|cached| cached.pinned) {
+            return DidResolution::Resolved(cached.document.clone());
+        }
 
-        println!("🔍 Resolving DID from Bluesky PLC directory: {}", did);
+        let outcome = self.fetch_did(did).await;
+        match outcome {
+            Ok(Some(document)) => {
+                self.cache.insert(
+                    did.to_string(),
+                    CachedDocument {
+                        document: document.clone(),
+                        fetched_at: chrono::Utc::now(),
+                        pinned: false,
+                    },
+                );
+                DidResolution::Resolved(document)
+            }
+            Ok(None) => {
+                self.cache.remove(did);
+                DidResolution::NotFound
+            }
+            Err(reason) => match self.cache.get(did) {
+                Some(cached) => {
+                    eprintln!(
+                        "⚠️  Using the expired PLC document of {}: {}",
+                        Redacted::did(did),
+                        reason
+                    );
+                    DidResolution::CachedStale(cached.document.clone())
+                }
+                None => DidResolution::Unreachable(reason),
+            },
+        }
+    }
+
+    /// The directory's document for a DID, None if it has none, or why it could not say
+    async fn fetch_did(&self, did: &str) -> Result<Option<PlcDocument>, String> {
+        if !self.network_enabled {
+            return Err("PLC network calls are disabled".to_string());
+        }
+
+        // Progress goes to stderr so commands that print machine-readable output keep
+        // stdout clean
+        let resolve_url = format!("{}/{}", self.base_url, did);
+        eprintln!(
+            "🔍 Resolving DID from Bluesky PLC directory: {}",
+            Redacted::did(did)
+        );
 
         #[cfg(feature = "native")]
         {
-            match self.client.get(&resolve_url).send().await {
-                Ok(response) => {
-                    if response.status().is_success() {
-                        let plc_doc: PlcDocument = response.json().await?;
-                        self.local_cache.insert(did.to_string(), plc_doc.clone());
-                        println!("✅ Successfully resolved DID from PLC directory");
-                        Ok(Some(plc_doc))
-                    } else if response.status().as_u16() == 404 {
-                        println!("❓ DID not found in PLC directory");
-                        Ok(None)
-                    } else {
-                        println!("❌ Failed to resolve DID: {}", response.status());
-                        Ok(None)
-                    }
-                }
-                Err(e) => {
-                    println!("❌ Network error resolving DID: {}", e);
-                    // Return None instead of error to allow offline operation
-                    Ok(None)
-                }
+            let response = self
+                .client
+                .get(&resolve_url)
+                .send()
+                .await
+                .map_err(|e| format!("PLC directory unreachable: {}", e))?;
+            if response.status().as_u16() == 404 || response.status().as_u16() == 410 {
+                eprintln!("❓ DID not found in PLC directory");
+                return Ok(None);
+            }
+            if !response.status().is_success() {
+                return Err(format!("PLC directory answered {}", response.status()));
             }
+            let document = response
+                .json::<PlcDocument>()
+                .await
+                .map_err(|e| format!("Invalid PLC document: {}", e))?;
+            eprintln!("✅ Successfully resolved DID from PLC directory");
+            Ok(Some(document))
         }
 
         #[cfg(not(feature = "native"))]
         {
-            println!("❓ DID resolution not available in WASM mode");
-            Ok(None)
+            let _ = resolve_url;
+            Err("DID resolution is not available in WASM mode".to_string())
         }
     }
 
@@ -351,26 +473,312 @@ impl PlcDirectory {
     }
 
     pub fn get_cached_identities(&self) -> Vec<String> {
-        self.local_cache.keys().cloned().collect()
+        self.cache.keys().cloned().collect()
+    }
+
+    /// Resolve a DID to its raw Ed25519 public key via the `#atproto` Multikey method
+    pub async fn resolve_public_key(
+        &mut self,
+        did: &str,
+    ) -> Result<Option<[u8; 32]>, Box<dyn Error>> {
+        // Pseudonymous did:key identifiers carry their key and need no lookup
+        if did.starts_with("did:key:") {
+            return Ok(decode_did_key(did));
+        }
+
+        let plc_doc = match self
+            .resolve_did(did)
+            .await
+            .into_trusted(self.failure_policy)?
+        {
+            Some(doc) => doc,
+            None => return Ok(None),
+        };
+
+        let key = plc_doc
+            .verification_method
+            .unwrap_or_default()
+            .iter()
+            .filter(|vm| vm.method_type == "Multikey")
+            .filter_map(|vm| vm.public_key_multibase.as_deref())
+            .find_map(decode_multibase_ed25519);
+
+        Ok(key)
+    }
+}
+
+/// Encode a raw Ed25519 public key as a self-certifying did:key
+fn encode_did_key(public_key: &[u8; 32]) -> String {
+    let mut bytes = ED25519_PUB_MULTICODEC.to_vec();
+    bytes.extend_from_slice(public_key);
+    format!("did:key:z{}", bs58::encode(bytes).into_string())
+}
+
+/// Check a memory's content hash and author signature against a base64 public key,
+/// for callers that resolved the key themselves
+pub fn verify_memory_with_key(memory: &SignedMemory, public_key_b64: &str) -> bool {
+    let public_key: Option<[u8; 32]> = general_purpose::STANDARD
+        .decode(public_key_b64)
+        .ok()
+        .and_then(|bytes| bytes.try_into().ok());
+    match public_key {
+        Some(key) => {
+            memory.verify_hash()
+                && verify_payload_signature(&key, &memory.get_signing_payload(), &memory.signature)
+        }
+        None => false,
+    }
+}
+
+/// Hex SHA-256 of a raw Ed25519 public key, used to pin and compare contacts' keys
+pub fn key_fingerprint(public_key: &[u8; 32]) -> String {
+    hex::encode(Sha256::digest(public_key))
+}
+
+/// What a peer needs to check a memory's author without reaching the PLC directory
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "kind", content = "operations", rename_all = "snake_case")]
+pub enum VerificationBundle {
+    /// The author's PLC operations from genesis, checked with [`verify_operation_chain`]
+    OperationChain(Vec<PlcOperation>),
+}
+
+/// A memory as sent to peers. It serialises as the memory's own fields plus an optional
+/// `verification_bundle`, so peers that know nothing of bundles read a plain memory
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SharedMemory {
+    #[serde(flatten)]
+    pub memory: SignedMemory,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub verification_bundle: Option<VerificationBundle>,
+}
+
+/// How a shared memory's author signature was checked
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MemoryVerification {
+    Verified,
+    /// Checked against the key its attached operation chain ends on, because the directory
+    /// could not be reached. The key must be checked against the directory later
+    VerifiedOffline {
+        public_key: String,
+    },
+    Invalid,
+}
+
+/// Check a PLC operation chain without the directory: the DID must derive from the genesis
+/// rotation key, and each operation must name the one before it and be signed by one of
+/// its rotation keys. Returns the multibase `#atproto` key the chain ends on.
+/// A valid chain proves who controlled the DID, not that no later operation exists
+pub fn verify_operation_chain(did: &str, operations: &[PlcOperation]) -> Result<String, String> {
+    let genesis = operations.first().ok_or("Empty PLC operation chain")?;
+    if genesis.prev.is_some() {
+        return Err("PLC operation chain does not start at genesis".to_string());
+    }
+    let mut rotation_keys = genesis.rotation_keys.clone().unwrap_or_default();
+    let genesis_key = rotation_keys
+        .first()
+        .and_then(|key| general_purpose::STANDARD.decode(key).ok())
+        .ok_or("Genesis operation has no rotation key")?;
+    if did != format!("did:plc:{}", PlcIdentity::generate_plc_id(&genesis_key)) {
+        return Err("DID does not derive from the genesis rotation key".to_string());
+    }
+
+    let mut prev = None;
+    for operation in operations {
+        if operation.did != did || operation.prev != prev {
+            return Err("PLC operation chain is broken".to_string());
+        }
+        let payload = operation.signing_payload();
+        let signed = rotation_keys
+            .iter()
+            .filter_map(|key| general_purpose::STANDARD.decode(key).ok())
+            .filter_map(|key| <[u8; 32]>::try_from(key).ok())
+            .any(|key| verify_payload_signature(&key, &payload, &operation.signature));
+        if !signed {
+            return Err("PLC operation not signed by a rotation key".to_string());
+        }
+        if operation.operation_type == "plc_tombstone" {
+            return Err("DID has been deactivated".to_string());
+        }
+        rotation_keys = operation.rotation_keys.clone().unwrap_or_default();
+        prev = Some(operation.hash());
+    }
+
+    operations
+        .last()
+        .and_then(|operation| operation.verification_methods.as_ref())
+        .and_then(|methods| methods.get(format!("{}#atproto", did)))
+        .and_then(|method| method["publicKeyMultibase"].as_str())
+        .filter(|key| decode_multibase_ed25519(key).is_some())
+        .map(str::to_string)
+        .ok_or_else(|| "PLC operation chain has no Ed25519 #atproto key".to_string())
+}
+
+/// Which signers must have attested a co-signed memory for it to be accepted
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum SignaturePolicy {
+    /// Every listed DID must have signed
+    AllOf(Vec<String>),
+    /// At least `threshold` of the listed DIDs must have signed
+    Threshold {
+        threshold: usize,
+        signers: Vec<String>,
+    },
+}
+
+impl SignaturePolicy {
+    pub fn signers(&self) -> &[String] {
+        match self {
+            SignaturePolicy::AllOf(signers) => signers,
+            SignaturePolicy::Threshold { signers, .. } => signers,
+        }
+    }
+
+    pub fn required(&self) -> usize {
+        match self {
+            SignaturePolicy::AllOf(signers) => signers.len(),
+            SignaturePolicy::Threshold { threshold, .. } => *threshold,
+        }
+    }
+
+    pub fn is_satisfied_by(&self, valid_signers: &[String]) -> bool {
+        let matched = self
+            .signers()
+            .iter()
+            .filter(|did| valid_signers.contains(did))
+            .count();
+        matched >= self.required()
+    }
+}
+
+/// Per-context signing identity derived from a master identity.
+/// Its did:key cannot be linked to the master DID without a `PseudonymLinkProof`
+#[derive(Clone)]
+pub struct Pseudonym {
+    pub context: String,
+    pub did: Did,
+    signing_key: SecureKey,
+}
+
+// Custom Debug implementation to prevent key leakage
+impl std::fmt::Debug for Pseudonym {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Pseudonym")
+            .field("context", &self.context)
+            .field("did", &self.did)
+            .field("signing_key", &"[REDACTED]")
+            .finish()
+    }
+}
+
+impl Pseudonym {
+    pub fn sign_payload(&self, payload: &str) -> String {
+        let signing_key = SigningKey::from_bytes(self.signing_key.as_bytes());
+        let signature = signing_key.sign(payload.as_bytes());
+        general_purpose::STANDARD.encode(signature.to_bytes())
+    }
+
+    /// Sign a memory authored under this pseudonym's DID
+    pub fn sign_memory(&self, memory: &mut SignedMemory) -> Result<(), Box<dyn Error>> {
+        if memory.did != self.did {
+            return Err("Memory is not authored by this pseudonym".into());
+        }
+        memory.signature = self.sign_payload(&memory.get_signing_payload());
+        Ok(())
+    }
+}
+
+/// Statement signed by both a master DID and one of its pseudonyms, revealing the link
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PseudonymLinkProof {
+    pub pseudonym_did: String,
+    pub master_did: String,
+    pub context: String,
+    pub created_at: String,
+    pub master_signature: String,
+    pub pseudonym_signature: String,
+}
+
+impl PseudonymLinkProof {
+    pub fn get_signing_payload(&self) -> String {
+        serde_json::json!({
+            "pseudonym_did": self.pseudonym_did,
+            "master_did": self.master_did,
+            "context": self.context,
+            "created_at": self.created_at
+        })
+        .to_string()
     }
 }
 
 impl PlcIdentity {
     pub fn sign_memory(&self, memory: &mut SignedMemory) -> Result<(), Box<dyn Error>> {
-        // Use the secure private key
-        let private_key_bytes = self.keypair.private_key_bytes();
-        let signing_key = SigningKey::from_bytes(private_key_bytes);
+        memory.signature = self.sign_payload(&memory.get_signing_payload())?;
+        Ok(())
+    }
 
-        // Get the message to sign
-        let message = memory.get_signing_payload();
+    /// Sign an arbitrary payload, returning a base64 Ed25519 signature
+    pub fn sign_payload(&self, payload: &str) -> Result<String, Box<dyn Error>> {
+        self.keypair.sign(payload.as_bytes())
+    }
 
-        // Sign the message
-        let signature = signing_key.sign(message.as_bytes());
+    /// Add this identity's signature to a memory authored by someone else
+    pub fn co_sign_memory(&self, memory: &mut SignedMemory) -> Result<(), Box<dyn Error>> {
+        co_sign_with(self, memory)
+    }
 
-        // Encode signature as base64
-        memory.signature = general_purpose::STANDARD.encode(signature.to_bytes());
+    /// Produce a signed receipt witnessing that this identity saw `content_hash` now
+    pub fn witness_hash(
+        &self,
+        memory_id: &MemoryId,
+        content_hash: &str,
+    ) -> Result<WitnessReceipt, Box<dyn Error>> {
+        let mut receipt = WitnessReceipt::new(memory_id, content_hash, &self.did);
+        receipt.signature = self.sign_payload(&receipt.get_signing_payload())?;
+        Ok(receipt)
+    }
 
-        Ok(())
+    /// Derive the pseudonym for `context`; the same context always yields the same DID.
+    /// Needs the private key, so fails for keys held on a hardware token
+    pub fn pseudonym(&self, context: &str) -> Result<Pseudonym, Box<dyn Error>> {
+        let private_key = self
+            .keypair
+            .private_key_bytes()
+            .ok_or("Pseudonyms cannot be derived from a key this process cannot read")?;
+        let mut hasher = Sha256::new();
+        hasher.update(PSEUDONYM_KEY_DOMAIN);
+        hasher.update(private_key);
+        hasher.update(context.as_bytes());
+        let seed: [u8; 32] = hasher.finalize().into();
+
+        let signing_key = SigningKey::from_bytes(&seed);
+        let did = Did::parse(&encode_did_key(&signing_key.verifying_key().to_bytes()))?;
+
+        Ok(Pseudonym {
+            context: context.to_string(),
+            did,
+            signing_key: SecureKey::new(seed),
+        })
+    }
+
+    /// Reveal that the pseudonym for `context` belongs to this identity
+    pub fn prove_pseudonym_link(
+        &self,
+        context: &str,
+    ) -> Result<PseudonymLinkProof, Box<dyn Error>> {
+        let pseudonym = self.pseudonym(context)?;
+        let mut proof = PseudonymLinkProof {
+            pseudonym_did: pseudonym.did.to_string(),
+            master_did: self.did.to_string(),
+            context: context.to_string(),
+            created_at: chrono::Utc::now().to_rfc3339(),
+            master_signature: String::new(),
+            pseudonym_signature: String::new(),
+        };
+        let payload = proof.get_signing_payload();
+        proof.master_signature = self.sign_payload(&payload)?;
+        proof.pseudonym_signature = pseudonym.sign_payload(&payload);
+        Ok(proof)
     }
 
     pub fn verify_memory(&self, memory: &SignedMemory) -> Result<bool, Box<dyn Error>> {
@@ -404,9 +812,66 @@ impl PlcIdentity {
     }
 }
 
-pub struct OcmProtocol {
+/// An identity `OcmProtocol` signs with. `PlcIdentity` is the real one; the legacy
+/// stub_plc identity implements it only when built with the `insecure-stub` feature
+pub trait SignerProvider {
+    fn did(&self) -> &Did;
+
+    /// Sign a payload, returning the signature as it is stored on memories
+    fn sign(&self, payload: &str) -> Result<String, Box<dyn Error>>;
+
+    /// Whether this signer uses placeholder keys whose signatures no peer can verify
+    fn is_placeholder(&self) -> bool {
+        false
+    }
+}
+
+impl SignerProvider for PlcIdentity {
+    fn did(&self) -> &Did {
+        &self.did
+    }
+
+    fn sign(&self, payload: &str) -> Result<String, Box<dyn Error>> {
+        self.sign_payload(payload)
+    }
+}
+
+/// Whether a memory signature was made by the legacy stub_plc placeholder scheme: a hex
+/// SHA-256 digest, where real signatures are base64 Ed25519. Such memories never verify
+pub fn is_stub_signature(signature: &str) -> bool {
+    signature.len() == 64 && signature.bytes().all(|b| b.is_ascii_hexdigit())
+}
+
+fn co_sign_with<S: SignerProvider>(
+    signer: &S,
+    memory: &mut SignedMemory,
+) -> Result<(), Box<dyn Error>> {
+    let did = signer.did();
+    if memory.did == *did || memory.is_co_signed_by(did) {
+        return Err(format!("Memory already signed by {}", Redacted::did(did)).into());
+    }
+    if memory.is_derived() {
+        return Err("Derived memories are computed, not attested; co-sign their sources".into());
+    }
+    if !memory.verify_hash() {
+        return Err("Memory content does not match its hash".into());
+    }
+
+    let signature = signer.sign(&memory.get_signing_payload())?;
+    memory.co_signatures.push(CoSignature {
+        signer_did: did.clone(),
+        signature,
+        signed_at: chrono::Utc::now().to_rfc3339(),
+    });
+
+    Ok(())
+}
+
+pub struct OcmProtocol<S: SignerProvider = PlcIdentity> {
     plc_directory: PlcDirectory,
-    current_identity: Option<PlcIdentity>,
+    current_identity: Option<S>,
+    pub attach_operation_chains: bool, // Send our PLC operations along with our memories
+    pub accept_offline_bundles: bool, // Check memories against attached chains while the directory is down
 }
 
 impl OcmProtocol {
@@ -414,6 +879,18 @@ impl OcmProtocol {
         OcmProtocol {
             plc_directory: PlcDirectory::new(),
             current_identity: None,
+            attach_operation_chains: false,
+            accept_offline_bundles: true,
+        }
+    }
+
+    #[cfg(feature = "native")]
+    pub fn with_config(config: &crate::config::PlcConfig) -> Self {
+        OcmProtocol {
+            plc_directory: PlcDirectory::with_config(config),
+            attach_operation_chains: config.offline_bundles.attach,
+            accept_offline_bundles: config.offline_bundles.accept,
+            ..Self::new()
         }
     }
 
@@ -429,53 +906,261 @@ impl OcmProtocol {
             .ok_or_else(|| "Failed to create identity".into())
     }
 
-    pub async fn attest_memory(&self, memory: &mut SignedMemory) -> Result<(), Box<dyn Error>> {
+    /// Wrap a memory for sending, attaching our operation chain to memories we authored
+    /// when configured to
+    pub fn share_memory(&self, memory: &SignedMemory) -> SharedMemory {
+        let verification_bundle = self
+            .current_identity
+            .as_ref()
+            .filter(|identity| self.attach_operation_chains && identity.did == memory.did)
+            .map(|identity| VerificationBundle::OperationChain(identity.plc_operations.clone()));
+        SharedMemory {
+            memory: memory.clone(),
+            verification_bundle,
+        }
+    }
+
+    /// Create and sign a memory under the current identity's pseudonym for `context`
+    pub fn create_pseudonymous_memory(
+        &self,
+        context: &str,
+        memory_type: &str,
+        memory_data: &str,
+    ) -> Result<SignedMemory, Box<dyn Error>> {
+        let identity = self
+            .current_identity
+            .as_ref()
+            .ok_or("No identity available for signing")?;
+        let pseudonym = identity.pseudonym(context)?;
+
+        let mut memory = SignedMemory::new(&pseudonym.did, memory_type, memory_data);
+        pseudonym.sign_memory(&mut memory)?;
+        Ok(memory)
+    }
+
+    pub fn current_identity(&self) -> Option<&PlcIdentity> {
+        self.current_identity.as_ref()
+    }
+
+    pub async fn get_identity_info(&self) -> Option<IdentityInfo> {
         if let Some(identity) = &self.current_identity {
-            identity.sign_memory(memory)?;
-            Ok(())
+            Some(IdentityInfo {
+                did: identity.did.to_string(),
+                public_key: identity.keypair.public_key.clone(),
+                created_at: identity.created_at.clone(),
+                plc_operations_count: identity.plc_operations.len(),
+            })
         } else {
-            Err("No identity available for signing".into())
+            None
+        }
+    }
+}
+
+impl<S: SignerProvider> OcmProtocol<S> {
+    /// Use an identity other than a directory-created one, such as the legacy stub
+    pub fn with_signer(signer: S, plc_directory: PlcDirectory) -> Self {
+        OcmProtocol {
+            plc_directory,
+            current_identity: Some(signer),
+            attach_operation_chains: false,
+            accept_offline_bundles: true,
+        }
+    }
+
+    /// Sign as an identity made elsewhere, such as one whose key a backend holds
+    pub fn use_identity(&mut self, identity: S) -> &S {
+        self.current_identity.insert(identity)
+    }
+
+    /// The current identity, unless it is a placeholder and this build does not allow them
+    fn signer(&self) -> Result<&S, Box<dyn Error>> {
+        let signer = self
+            .current_identity
+            .as_ref()
+            .ok_or("No identity available for signing")?;
+        if signer.is_placeholder() && !cfg!(feature = "insecure-stub") {
+            return Err(format!(
+                "Refusing to sign as {}: placeholder keys need the insecure-stub feature",
+                Redacted::did(signer.did())
+            )
+            .into());
         }
+        Ok(signer)
+    }
+
+    pub async fn attest_memory(&self, memory: &mut SignedMemory) -> Result<(), Box<dyn Error>> {
+        memory.signature = self.signer()?.sign(&memory.get_signing_payload())?;
+        Ok(())
     }
 
     pub async fn verify_federated_memory(
         &mut self,
         memory: &SignedMemory,
     ) -> Result<bool, Box<dyn Error>> {
-        // Try to resolve the DID from the PLC directory
-        if let Some(plc_doc) = self.plc_directory.resolve_did(&memory.did).await? {
-            // Extract the public key from the verification method
-            if let Some(verification_methods) = &plc_doc.verification_method {
-                for vm in verification_methods {
-                    if vm.method_type == "Multikey" {
-                        // In a real implementation, properly decode multibase
-                        // For demo, we'll use our current identity's key
-                        if let Some(current_identity) = &self.current_identity {
-                            return current_identity.verify_memory(memory);
-                        }
-                    }
+        // Pseudonymous memories are verified against the key embedded in their did:key
+        if let Some(public_key) = decode_did_key(&memory.did) {
+            return Ok(memory.verify_hash()
+                && verify_payload_signature(
+                    &public_key,
+                    &memory.get_signing_payload(),
+                    &memory.signature,
+                ));
+        }
+
+        // Everything else is checked against the key its PLC document publishes. A
+        // directory that cannot be reached is an error, not a reason to trust the memory
+        match self.plc_directory.resolve_public_key(&memory.did).await? {
+            Some(public_key) => Ok(memory.verify_hash()
+                && verify_payload_signature(
+                    &public_key,
+                    &memory.get_signing_payload(),
+                    &memory.signature,
+                )),
+            None => Ok(false),
+        }
+    }
+
+    /// Verify a memory from a peer. If the directory cannot be reached, an attached
+    /// operation chain may stand in for it; the key the chain ends on is returned so it
+    /// can be checked against the directory once it is back
+    pub async fn verify_shared_memory(
+        &mut self,
+        shared: &SharedMemory,
+    ) -> Result<MemoryVerification, Box<dyn Error>> {
+        let memory = &shared.memory;
+        let error = match self.verify_federated_memory(memory).await {
+            Ok(true) => return Ok(MemoryVerification::Verified),
+            Ok(false) => return Ok(MemoryVerification::Invalid),
+            Err(e) => e,
+        };
+        let Some(VerificationBundle::OperationChain(operations)) = shared
+            .verification_bundle
+            .as_ref()
+            .filter(|_| self.accept_offline_bundles)
+        else {
+            return Err(error);
+        };
+
+        let public_key = match verify_operation_chain(&memory.did, operations) {
+            Ok(public_key) => public_key,
+            Err(reason) => {
+                eprintln!(
+                    "❌ Operation chain of {} rejected: {}",
+                    Redacted::did(&memory.did),
+                    reason
+                );
+                return Ok(MemoryVerification::Invalid);
+            }
+        };
+        let valid = decode_multibase_ed25519(&public_key).is_some_and(|key| {
+            memory.verify_hash()
+                && verify_payload_signature(&key, &memory.get_signing_payload(), &memory.signature)
+        });
+        Ok(if valid {
+            MemoryVerification::VerifiedOffline { public_key }
+        } else {
+            MemoryVerification::Invalid
+        })
+    }
+
+    pub async fn co_sign_memory(&self, memory: &mut SignedMemory) -> Result<(), Box<dyn Error>> {
+        co_sign_with(self.signer()?, memory)
+    }
+
+    /// Verify the author and co-signatures of a memory against a signature policy.
+    /// Signatures whose DID is not found or which fail verification are not counted; a
+    /// PLC directory that cannot be reached is an error.
+    pub async fn verify_co_signed_memory(
+        &mut self,
+        memory: &SignedMemory,
+        policy: &SignaturePolicy,
+    ) -> Result<bool, Box<dyn Error>> {
+        if !memory.verify_hash() {
+            return Ok(false);
+        }
+
+        let payload = memory.get_signing_payload();
+        let mut valid_signers = Vec::new();
+
+        let signatures = std::iter::once((memory.did.as_str(), memory.signature.as_str())).chain(
+            memory
+                .co_signatures
+                .iter()
+                .map(|s| (s.signer_did.as_str(), s.signature.as_str())),
+        );
+
+        for (signer_did, signature) in signatures {
+            if signature.is_empty() || valid_signers.iter().any(|d| d == signer_did) {
+                continue; // Unsigned, as group memories are, or a DID that already counted
+            }
+            if let Some(public_key) = self.plc_directory.resolve_public_key(signer_did).await? {
+                if verify_payload_signature(&public_key, &payload, signature) {
+                    valid_signers.push(signer_did.to_string());
                 }
             }
         }
 
-        // If we can't resolve from PLC, fall back to local verification
-        if let Some(identity) = &self.current_identity {
-            identity.verify_memory(memory)
-        } else {
-            Ok(false)
+        Ok(policy.is_satisfied_by(&valid_signers))
+    }
+
+    /// Check both signatures on a pseudonym link proof and that the pseudonym is a did:key
+    pub async fn verify_pseudonym_link(
+        &mut self,
+        proof: &PseudonymLinkProof,
+    ) -> Result<bool, Box<dyn Error>> {
+        let payload = proof.get_signing_payload();
+        let pseudonym_valid = match decode_did_key(&proof.pseudonym_did) {
+            Some(public_key) => {
+                verify_payload_signature(&public_key, &payload, &proof.pseudonym_signature)
+            }
+            None => false,
+        };
+        if !pseudonym_valid {
+            return Ok(false);
         }
+
+        self.verify_did_signature(&proof.master_did, &payload, &proof.master_signature)
+            .await
     }
 
-    pub async fn get_identity_info(&self) -> Option<IdentityInfo> {
-        if let Some(identity) = &self.current_identity {
-            Some(IdentityInfo {
-                did: identity.did.clone(),
-                public_key: identity.keypair.public_key.clone(),
-                created_at: identity.created_at.clone(),
-                plc_operations_count: identity.plc_operations.len(),
-            })
-        } else {
-            None
+    /// Witness a content hash on behalf of a requesting peer
+    pub fn witness_hash(
+        &self,
+        memory_id: &MemoryId,
+        content_hash: &str,
+    ) -> Result<WitnessReceipt, Box<dyn Error>> {
+        let signer = self.signer()?;
+        let mut receipt = WitnessReceipt::new(memory_id, content_hash, signer.did());
+        receipt.signature = signer.sign(&receipt.get_signing_payload())?;
+        Ok(receipt)
+    }
+
+    pub async fn verify_witness_receipt(
+        &mut self,
+        receipt: &WitnessReceipt,
+    ) -> Result<bool, Box<dyn Error>> {
+        self.verify_did_signature(
+            &receipt.witness_did,
+            &receipt.get_signing_payload(),
+            &receipt.signature,
+        )
+        .await
+    }
+
+    /// Verify a base64 signature over `payload` by the key published for `did`
+    pub async fn verify_did_signature(
+        &mut self,
+        did: &str,
+        payload: &str,
+        signature_b64: &str,
+    ) -> Result<bool, Box<dyn Error>> {
+        match self.plc_directory.resolve_public_key(did).await? {
+            Some(public_key) => Ok(verify_payload_signature(
+                &public_key,
+                payload,
+                signature_b64,
+            )),
+            None => Ok(false),
         }
     }
 }
@@ -484,23 +1169,29 @@ impl OcmProtocol {
 impl PlcIdentity {
     /// Generate a new identity without requiring network access
     pub fn generate(handle: Option<String>) -> Result<Self, Box<dyn Error>> {
-        // Generate ED25519 keypair for PLC identity
-        let private_key_bytes = rand::random::<[u8; 32]>();
-        let signing_key = SigningKey::from_bytes(&private_key_bytes);
-        let verifying_key = signing_key.verifying_key();
+        Self::from_seed(rand::random::<[u8; 32]>(), handle)
+    }
 
-        let public_key_bytes = verifying_key.to_bytes();
-        let private_key_bytes = signing_key.to_bytes();
+    /// The identity of an Ed25519 seed held in memory, such as one just unwrapped
+    pub fn from_seed(seed: [u8; 32], handle: Option<String>) -> Result<Self, Box<dyn Error>> {
+        Self::from_signer(Arc::new(SecureKey::new(seed)), handle)
+    }
 
-        let public_key_b64 = general_purpose::STANDARD.encode(&public_key_bytes);
+    /// The identity of a key held by a signer. The DID follows from the public key, so
+    /// a key kept by a keychain or hardware backend keeps its DID across restarts
+    pub fn from_signer(
+        signer: Arc<dyn KeySigner>,
+        handle: Option<String>,
+    ) -> Result<Self, Box<dyn Error>> {
+        let public_key_bytes = signer.public_key();
+        let plc_keypair = PlcKeypair::from_signer(signer);
+        let public_key_b64 = plc_keypair.public_key.clone();
 
         // Generate a deterministic DID based on the public key
         let did = format!("did:plc:{}", Self::generate_plc_id(&public_key_bytes));
 
-        let plc_keypair = PlcKeypair::new(public_key_b64.clone(), private_key_bytes);
-
         // Create genesis operation
-        let genesis_op = PlcOperation {
+        let mut genesis_op = PlcOperation {
             operation_type: "plc_operation".to_string(),
             did: did.clone(),
             signature: String::new(),
@@ -523,8 +1214,10 @@ impl PlcIdentity {
             })),
         };
 
+        genesis_op.signature = plc_keypair.sign(genesis_op.signing_payload().as_bytes())?;
+
         let identity = PlcIdentity {
-            did,
+            did: Did::parse(&did)?,
             keypair: plc_keypair,
             plc_operations: vec![genesis_op],
             created_at: chrono::Utc::now().to_rfc3339(),
@@ -564,3 +1257,144 @@ pub struct IdentityInfo {
     pub created_at: String,
     pub plc_operations_count: usize,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Identities whose DIDs resolve from the directory cache, without network access
+    async fn published(count: usize) -> (Vec<PlcIdentity>, OcmProtocol) {
+        let mut directory = PlcDirectory::new();
+        directory.network_enabled = false;
+        let mut identities = Vec::new();
+        for _ in 0..count {
+            let identity = PlcIdentity::generate(None).unwrap();
+            directory.publish_identity(&identity).await.unwrap();
+            identities.push(identity);
+        }
+        let ocm = OcmProtocol::with_signer(identities[0].clone(), directory);
+        (identities, ocm)
+    }
+
+    fn both_signed(author: &PlcIdentity, co_signer: &PlcIdentity) -> SignaturePolicy {
+        SignaturePolicy::AllOf(vec![author.did.to_string(), co_signer.did.to_string()])
+    }
+
+    #[tokio::test]
+    async fn test_co_signed_memory_verifies() {
+        let (identities, mut ocm) = published(2).await;
+        let (author, co_signer) = (&identities[0], &identities[1]);
+        let mut memory = SignedMemory::new(&author.did, "note", "we both agree");
+        author.sign_memory(&mut memory).unwrap();
+        co_signer.co_sign_memory(&mut memory).unwrap();
+
+        assert!(memory.is_co_signed_by(&co_signer.did));
+        assert!(ocm
+            .verify_co_signed_memory(&memory, &both_signed(author, co_signer))
+            .await
+            .unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_co_signing_refuses_own_or_repeated_signature() {
+        let (identities, _) = published(2).await;
+        let (author, co_signer) = (&identities[0], &identities[1]);
+        let mut memory = SignedMemory::new(&author.did, "note", "we both agree");
+        author.sign_memory(&mut memory).unwrap();
+
+        assert!(author.co_sign_memory(&mut memory).is_err());
+        co_signer.co_sign_memory(&mut memory).unwrap();
+        assert!(co_signer.co_sign_memory(&mut memory).is_err());
+        assert_eq!(memory.co_signatures.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_altered_content_fails_co_signature() {
+        let (identities, mut ocm) = published(2).await;
+        let (author, co_signer) = (&identities[0], &identities[1]);
+        let mut memory = SignedMemory::new(&author.did, "note", "we both agree");
+        author.sign_memory(&mut memory).unwrap();
+        co_signer.co_sign_memory(&mut memory).unwrap();
+        let policy = both_signed(author, co_signer);
+
+        let mut altered = memory.clone();
+        altered.memory_data = "we disagree".to_string();
+        assert!(!ocm
+            .verify_co_signed_memory(&altered, &policy)
+            .await
+            .unwrap());
+        assert!(co_signer.co_sign_memory(&mut altered).is_err());
+
+        // Rehashing the new content does not carry the signatures over
+        altered.content_hash = SignedMemory::compute_hash(&altered.memory_data);
+        assert!(!ocm
+            .verify_co_signed_memory(&altered, &policy)
+            .await
+            .unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_co_signature_with_the_wrong_key_is_not_counted() {
+        let (identities, mut ocm) = published(3).await;
+        let (author, co_signer, impostor) = (&identities[0], &identities[1], &identities[2]);
+        let mut memory = SignedMemory::new(&author.did, "note", "we both agree");
+        author.sign_memory(&mut memory).unwrap();
+        impostor.co_sign_memory(&mut memory).unwrap();
+        memory.co_signatures[0].signer_did = co_signer.did.clone();
+
+        assert!(!ocm
+            .verify_co_signed_memory(&memory, &both_signed(author, co_signer))
+            .await
+            .unwrap());
+    }
+
+    #[test]
+    fn test_pseudonym_is_stable_per_context() {
+        let identity = PlcIdentity::generate(None).unwrap();
+        let first = identity.pseudonym("forum").unwrap();
+        let again = identity.pseudonym("forum").unwrap();
+        let other = identity.pseudonym("market").unwrap();
+
+        assert_eq!(first.did, again.did);
+        assert_eq!(first.sign_payload("hello"), again.sign_payload("hello"));
+        assert_ne!(first.did, other.did);
+    }
+
+    #[test]
+    fn test_pseudonym_does_not_reveal_the_root_did() {
+        let identity = PlcIdentity::generate(None).unwrap();
+        let pseudonym = identity.pseudonym("forum").unwrap();
+        let public_key: [u8; 32] = general_purpose::STANDARD
+            .decode(&identity.keypair.public_key)
+            .unwrap()
+            .try_into()
+            .unwrap();
+
+        assert!(pseudonym.did.starts_with("did:key:"));
+        assert_ne!(pseudonym.did, identity.did);
+        assert_ne!(decode_did_key(&pseudonym.did), Some(public_key));
+
+        // Another identity's pseudonym for the same context is unrelated
+        let stranger = PlcIdentity::generate(None).unwrap();
+        assert_ne!(stranger.pseudonym("forum").unwrap().did, pseudonym.did);
+    }
+
+    #[tokio::test]
+    async fn test_pseudonym_link_is_only_revealed_by_proof() {
+        let (identities, mut ocm) = published(1).await;
+        let identity = &identities[0];
+
+        let memory = ocm
+            .create_pseudonymous_memory("forum", "note", "anonymous")
+            .unwrap();
+        assert_eq!(memory.did, identity.pseudonym("forum").unwrap().did);
+        assert!(ocm.verify_federated_memory(&memory).await.unwrap());
+
+        let proof = identity.prove_pseudonym_link("forum").unwrap();
+        assert!(ocm.verify_pseudonym_link(&proof).await.unwrap());
+
+        let mut claimed = proof.clone();
+        claimed.context = "market".to_string();
+        assert!(!ocm.verify_pseudonym_link(&claimed).await.unwrap());
+    }
+}
@@ -0,0 +1,231 @@
+//! Where identity keys live. `PlcKeypair` signs through a `Signer`: by default an
+//! Ed25519 seed held in process memory, or, with the `keychain` and `pkcs11` features,
+//! a key kept by the OS keychain or on a hardware token, chosen by `plc.key.backend`.
+
+use ed25519_dalek::{Signer as _, SigningKey};
+use std::error::Error;
+use zeroize::{Zeroize, ZeroizeOnDrop};
+
+#[cfg(feature = "native")]
+use crate::config::{KeyBackend, KeyConfig};
+#[cfg(feature = "native")]
+use std::sync::Arc;
+
+/// An Ed25519 key that can sign, wherever it is kept
+pub trait Signer: Send + Sync {
+    fn public_key(&self) -> [u8; 32];
+
+    fn sign(&self, message: &[u8]) -> Result<[u8; 64], Box<dyn Error>>;
+
+    /// The seed, for operations that need the key itself: pseudonym derivation, envelope
+    /// decryption and export. None for keys that never leave their backend
+    fn seed(&self) -> Option<&[u8; 32]> {
+        None
+    }
+}
+
+/// Secure key storage that zeroes memory on drop
+#[derive(Zeroize, ZeroizeOnDrop)]
+pub(crate) struct SecureKey {
+    #[zeroize(skip)]
+    key_data: Box<[u8; 32]>,
+}
+
+impl SecureKey {
+    pub(crate) fn new(key_bytes: [u8; 32]) -> Self {
+        SecureKey {
+            key_data: Box::new(key_bytes),
+        }
+    }
+
+    pub(crate) fn as_bytes(&self) -> &[u8; 32] {
+        &self.key_data
+    }
+}
+
+impl Clone for SecureKey {
+    fn clone(&self) -> Self {
+        SecureKey::new(*self.key_data)
+    }
+}
+
+impl Signer for SecureKey {
+    fn public_key(&self) -> [u8; 32] {
+        SigningKey::from_bytes(self.as_bytes())
+            .verifying_key()
+            .to_bytes()
+    }
+
+    fn sign(&self, message: &[u8]) -> Result<[u8; 64], Box<dyn Error>> {
+        Ok(SigningKey::from_bytes(self.as_bytes())
+            .sign(message)
+            .to_bytes())
+    }
+
+    fn seed(&self) -> Option<&[u8; 32]> {
+        Some(self.as_bytes())
+    }
+}
+
+/// The key `plc.key` points at, or None when keys are generated in memory at startup
+#[cfg(feature = "native")]
+pub fn open_configured(config: &KeyConfig) -> Result<Option<Arc<dyn Signer>>, Box<dyn Error>> {
+    match config.backend {
+        KeyBackend::Memory => Ok(None),
+        #[cfg(feature = "keychain")]
+        KeyBackend::Keychain => Ok(Some(Arc::new(keychain_key(
+            &config.keychain_service,
+            &config.keychain_account,
+        )?))),
+        #[cfg(feature = "pkcs11")]
+        KeyBackend::Pkcs11 => {
+            let module = config
+                .pkcs11_module
+                .as_ref()
+                .ok_or("plc.key.pkcs11_module is not set")?;
+            let pin = std::env::var(&config.pkcs11_pin_env)
+                .map_err(|_| format!("{} is not set", config.pkcs11_pin_env))?;
+            Ok(Some(Arc::new(Pkcs11Signer::open(
+                module,
+                config.pkcs11_slot,
+                &config.pkcs11_key_label,
+                &pin,
+            )?)))
+        }
+        #[allow(unreachable_patterns)]
+        backend => Err(format!("This build does not include the {:?} key backend", backend).into()),
+    }
+}
+
+/// Load the seed stored under `service`/`account` in the OS keychain, storing a new one
+/// on first use. The keychain keeps it encrypted at rest and tied to the user account;
+/// Ed25519 signing still happens in this process
+#[cfg(feature = "keychain")]
+fn keychain_key(service: &str, account: &str) -> Result<SecureKey, Box<dyn Error>> {
+    use base64::{engine::general_purpose, Engine as _};
+
+    let entry = keyring::Entry::new(service, account)?;
+    let seed: [u8; 32] = match entry.get_password() {
+        Ok(encoded) => {
+            let bytes = zeroize::Zeroizing::new(general_purpose::STANDARD.decode(encoded)?);
+            bytes
+                .as_slice()
+                .try_into()
+                .map_err(|_| "Keychain entry is not a 32-byte key")?
+        }
+        Err(keyring::Error::NoEntry) => {
+            let seed = rand::random::<[u8; 32]>();
+            entry.set_password(&general_purpose::STANDARD.encode(seed))?;
+            println!(
+                "🔑 Stored a new identity key in the OS keychain as {}/{}",
+                service, account
+            );
+            seed
+        }
+        Err(e) => return Err(e.into()),
+    };
+    Ok(SecureKey::new(seed))
+}
+
+/// An Ed25519 key on a PKCS#11 token (a YubiKey through ykcs11, an HSM, SoftHSM for
+/// testing). The token signs; the private key never leaves it
+#[cfg(feature = "pkcs11")]
+pub struct Pkcs11Signer {
+    session: std::sync::Mutex<cryptoki::session::Session>,
+    key: cryptoki::object::ObjectHandle,
+    public_key: [u8; 32],
+}
+
+#[cfg(feature = "pkcs11")]
+impl Pkcs11Signer {
+    /// Log in to the token in the `slot`th slot holding one and find the key pair
+    /// labelled `label`
+    pub fn open(
+        module: &std::path::Path,
+        slot: usize,
+        label: &str,
+        pin: &str,
+    ) -> Result<Self, Box<dyn Error>> {
+        use cryptoki::context::{CInitializeArgs, Pkcs11};
+        use cryptoki::object::{Attribute, AttributeType, ObjectClass};
+        use cryptoki::session::UserType;
+        use cryptoki::types::AuthPin;
+
+        let pkcs11 = Pkcs11::new(module)?;
+        pkcs11.initialize(CInitializeArgs::OsThreads)?;
+        let slot = pkcs11
+            .get_slots_with_token()?
+            .get(slot)
+            .copied()
+            .ok_or("No PKCS#11 token in the configured slot")?;
+        let session = pkcs11.open_ro_session(slot)?;
+        session.login(UserType::User, Some(&AuthPin::new(pin.to_string())))?;
+
+        let key = find_ed25519_object(&session, ObjectClass::PRIVATE_KEY, label)?;
+        let public = find_ed25519_object(&session, ObjectClass::PUBLIC_KEY, label)?;
+        let public_key = match session
+            .get_attributes(public, &[AttributeType::EcPoint])?
+            .pop()
+        {
+            Some(Attribute::EcPoint(point)) => ed25519_point(&point)?,
+            _ => return Err("The token did not return the public key".into()),
+        };
+
+        println!("🔑 Signing with PKCS#11 key {}", label);
+        Ok(Self {
+            session: std::sync::Mutex::new(session),
+            key,
+            public_key,
+        })
+    }
+}
+
+#[cfg(feature = "pkcs11")]
+impl Signer for Pkcs11Signer {
+    fn public_key(&self) -> [u8; 32] {
+        self.public_key
+    }
+
+    fn sign(&self, message: &[u8]) -> Result<[u8; 64], Box<dyn Error>> {
+        let session = self
+            .session
+            .lock()
+            .map_err(|_| "PKCS#11 session lock poisoned")?;
+        let signature = session.sign(&cryptoki::mechanism::Mechanism::Eddsa, self.key, message)?;
+        Ok(signature
+            .as_slice()
+            .try_into()
+            .map_err(|_| "The token returned a signature that is not 64 bytes")?)
+    }
+}
+
+#[cfg(feature = "pkcs11")]
+fn find_ed25519_object(
+    session: &cryptoki::session::Session,
+    class: cryptoki::object::ObjectClass,
+    label: &str,
+) -> Result<cryptoki::object::ObjectHandle, Box<dyn Error>> {
+    use cryptoki::object::{Attribute, KeyType};
+
+    session
+        .find_objects(&[
+            Attribute::Class(class),
+            Attribute::KeyType(KeyType::EC_EDWARDS),
+            Attribute::Label(label.as_bytes().to_vec()),
+        ])?
+        .into_iter()
+        .next()
+        .ok_or_else(|| format!("No Ed25519 key labelled {} on the token", label).into())
+}
+
+/// CKA_EC_POINT holds the key as a DER OCTET STRING; some tokens return it bare
+#[cfg(feature = "pkcs11")]
+fn ed25519_point(point: &[u8]) -> Result<[u8; 32], Box<dyn Error>> {
+    let raw = match point {
+        [0x04, 0x20, rest @ ..] if rest.len() == 32 => rest,
+        _ => point,
+    };
+    Ok(raw
+        .try_into()
+        .map_err(|_| "The token's public key is not an Ed25519 key")?)
+}
@@ -1,11 +1,20 @@
+//! Legacy placeholder identities, kept only for tests and old tooling. Their signatures
+//! are hashes anyone can forge and no peer can verify, so this module is only built with
+//! the `insecure-stub` feature. Memories signed this way are replaced by
+//! `ocm-core migrate-identities`.
+
+use crate::core::ids::Did;
 use crate::core::models::SignedMemory;
+use crate::identity::plc::{self, SignerProvider};
 use serde::{Deserialize, Serialize};
 use sha2::{Digest, Sha256};
 use std::error::Error;
 
+pub use crate::identity::plc::is_stub_signature;
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PlcIdentity {
-    pub did: String,
+    pub did: Did,
     pub signing_key: String,      // Base64 encoded private key
     pub verification_key: String, // Base64 encoded public key
     pub created_at: String,
@@ -15,10 +24,10 @@ impl PlcIdentity {
     pub fn new() -> Result<Self, Box<dyn Error>> {
         // For now, create a simple identity structure
         // In production, this would integrate with actual PLC directory
-        let did = format!(
+        let did = Did::parse(&format!(
             "did:plc:{}",
             uuid::Uuid::new_v4().to_string().replace("-", "")
-        );
+        ))?;
         let created_at = chrono::Utc::now().to_rfc3339();
 
         // Placeholder keys - in real implementation, these would be generated
@@ -32,16 +41,7 @@ impl PlcIdentity {
     }
 
     pub fn sign_memory(&self, memory: &mut SignedMemory) -> Result<(), Box<dyn Error>> {
-        let payload = memory.get_signing_payload();
-
-        // Create a deterministic signature based on the payload and our DID
-        // In real implementation, this would use proper PLC cryptographic signing
-        let signature_data = format!("{}:{}", self.signing_key, payload);
-        let mut hasher = Sha256::new();
-        hasher.update(signature_data.as_bytes());
-        let signature = hex::encode(hasher.finalize());
-
-        memory.signature = signature;
+        memory.signature = self.sign(&memory.get_signing_payload())?;
         Ok(())
     }
 
@@ -64,7 +64,7 @@ impl PlcIdentity {
 
 pub struct PlcDirectory {
     // In production, this would connect to the actual PLC directory
-    local_identities: std::collections::HashMap<String, PlcIdentity>,
+    local_identities: std::collections::HashMap<Did, PlcIdentity>,
 }
 
 impl PlcDirectory {
@@ -98,47 +98,37 @@ impl PlcDirectory {
     }
 }
 
-// OCM Protocol implementation following your README flow
-pub struct OcmProtocol {
-    plc_directory: PlcDirectory,
-    current_identity: Option<PlcIdentity>,
-}
-
-impl OcmProtocol {
-    pub fn new() -> Self {
-        OcmProtocol {
-            plc_directory: PlcDirectory::new(),
-            current_identity: None,
-        }
+impl SignerProvider for PlcIdentity {
+    fn did(&self) -> &Did {
+        &self.did
     }
 
-    pub fn create_identity(&mut self) -> Result<&PlcIdentity, Box<dyn Error>> {
-        let identity = self.plc_directory.create_identity()?;
-        self.plc_directory.publish_identity(&identity)?;
-        self.current_identity = Some(identity);
-        self.current_identity
-            .as_ref()
-            .ok_or_else(|| "Failed to create identity".into())
+    fn sign(&self, payload: &str) -> Result<String, Box<dyn Error>> {
+        // A deterministic hash of the payload and the placeholder key
+        let signature_data = format!("{}:{}", self.signing_key, payload);
+        let mut hasher = Sha256::new();
+        hasher.update(signature_data.as_bytes());
+        Ok(hex::encode(hasher.finalize()))
     }
 
-    // Step 1: Capture - Record an event to local SQLite (handled by Database)
-    // Step 2: Attestation - Generate hash and sign via PLC identity
-    pub fn attest_memory(&self, memory: &mut SignedMemory) -> Result<(), Box<dyn Error>> {
-        if let Some(identity) = &self.current_identity {
-            identity.sign_memory(memory)?;
-            Ok(())
-        } else {
-            Err("No identity available for signing".into())
-        }
+    fn is_placeholder(&self) -> bool {
+        true
     }
+}
 
-    // Step 3: Federation - Verify signature against PLC before merging
-    pub fn verify_federated_memory(&self, memory: &SignedMemory) -> Result<bool, Box<dyn Error>> {
-        if let Some(identity) = self.plc_directory.resolve_did(&memory.did) {
-            identity.verify_memory(memory)
-        } else {
-            // In production, would query external PLC directory
-            Err("Could not resolve DID from PLC directory".into())
-        }
+/// The protocol signing with a stub identity. Federation checks still go to the real
+/// PLC directory, where stub memories do not verify
+pub type OcmProtocol = plc::OcmProtocol<PlcIdentity>;
+
+impl OcmProtocol {
+    /// Create a stub identity and a protocol signing with it
+    pub fn with_stub_identity() -> Result<Self, Box<dyn Error>> {
+        let mut directory = PlcDirectory::new();
+        let identity = directory.create_identity()?;
+        directory.publish_identity(&identity)?;
+        Ok(plc::OcmProtocol::with_signer(
+            identity,
+            plc::PlcDirectory::new(),
+        ))
     }
 }
@@ -0,0 +1,238 @@
+//! `ocm-core migrate-identities`: replaces memories signed under the placeholder
+//! stub_plc scheme, which can never verify, with copies signed by real PLC identities.
+
+use crate::config::OcmConfig;
+use crate::core::error::{OcmError, Result};
+use crate::core::ids::{Did, MemoryId};
+use crate::core::models::{IdentityLineageData, MigratedMemory, IDENTITY_LINEAGE_MEMORY_TYPE};
+use crate::core::redact::Redacted;
+use crate::core::SignedMemory;
+use crate::identity::plc::{is_stub_signature, PlcIdentity, PlcOperation};
+use crate::persistence::Database;
+use base64::{engine::general_purpose, Engine as _};
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+const USAGE: &str =
+    "Usage: ocm-core migrate-identities (--all | --did DID) --key-dir DIR [--dry-run] [--json]
+
+Generates a PLC identity for each legacy DID and writes its private key to a file named
+after the new DID in DIR; keep those files safe. Legacy memories are re-signed, archived
+and recorded in legacy_memory. Memories whose content does not match their hash are
+skipped.";
+
+#[derive(Debug, Clone)]
+pub struct MigrateOptions {
+    pub legacy_did: Option<Did>, // None migrates every legacy DID
+    pub key_dir: PathBuf,
+    pub dry_run: bool,
+    pub json: bool,
+}
+
+impl MigrateOptions {
+    /// Parse the arguments following `migrate-identities`
+    pub fn from_args(args: &[String]) -> std::result::Result<Self, &'static str> {
+        let value = |flag: &str| {
+            args.iter()
+                .position(|arg| arg == flag)
+                .and_then(|i| args.get(i + 1))
+                .cloned()
+        };
+        let all = args.iter().any(|arg| arg == "--all");
+        let legacy_did = match (all, value("--did")) {
+            (true, None) => None,
+            (false, Some(did)) => Some(Did::parse(&did).map_err(|_| USAGE)?),
+            _ => return Err(USAGE),
+        };
+        Ok(Self {
+            legacy_did,
+            key_dir: value("--key-dir").map(PathBuf::from).ok_or(USAGE)?,
+            dry_run: args.iter().any(|arg| arg == "--dry-run"),
+            json: args.iter().any(|arg| arg == "--json"),
+        })
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct MigratedIdentity {
+    pub legacy_did: Did,
+    pub did: Option<Did>, // None on a dry run
+    pub memories: usize,
+    pub skipped: Vec<MemoryId>, // Legacy memories whose content does not match their hash
+    pub lineage_memory_id: Option<MemoryId>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct MigrationReport {
+    pub dry_run: bool,
+    pub identities: Vec<MigratedIdentity>,
+}
+
+impl MigrationReport {
+    pub fn print(&self) {
+        for identity in &self.identities {
+            println!(
+                "🆔 {} -> {}: {} memories re-signed, {} skipped",
+                Redacted::did(&identity.legacy_did),
+                identity
+                    .did
+                    .as_deref()
+                    .map(|did| Redacted::did(did).to_string())
+                    .unwrap_or_else(|| "(dry run)".to_string()),
+                identity.memories,
+                identity.skipped.len()
+            );
+        }
+        println!(
+            "{} {} legacy identities, {} memories",
+            if self.dry_run {
+                "🔎 Would migrate"
+            } else {
+                "✅ Migrated"
+            },
+            self.identities.len(),
+            self.identities.iter().map(|i| i.memories).sum::<usize>()
+        );
+    }
+}
+
+/// What is written to the key directory for each new identity
+#[derive(Serialize)]
+struct IdentityKeyFile<'a> {
+    did: &'a str,
+    legacy_did: &'a str,
+    public_key: &'a str,
+    private_key: String, // Base64 Ed25519 seed
+    created_at: &'a str,
+    plc_operations: &'a [PlcOperation],
+}
+
+pub fn run(config: &OcmConfig, options: &MigrateOptions) -> Result<MigrationReport> {
+    let path = config.database.path.to_string_lossy();
+    let database = if options.dry_run {
+        Database::open_read_only(&path)?
+    } else {
+        let database = Database::new(&path)?;
+        database.set_durability(config.database.durability.clone())?;
+        database
+    };
+
+    // Legacy memories by author, in a stable order
+    let mut legacy: BTreeMap<Did, Vec<SignedMemory>> = BTreeMap::new();
+    for memory in database.list_signed_memories()? {
+        let in_scope = options
+            .legacy_did
+            .as_ref()
+            .is_none_or(|did| did == &memory.did);
+        if in_scope && is_stub_signature(&memory.signature) {
+            legacy.entry(memory.did.clone()).or_default().push(memory);
+        }
+    }
+
+    if !options.dry_run && !legacy.is_empty() {
+        std::fs::create_dir_all(&options.key_dir)?;
+    }
+    let mut report = MigrationReport {
+        dry_run: options.dry_run,
+        identities: Vec::new(),
+    };
+    for (legacy_did, memories) in legacy {
+        let (memories, tampered): (Vec<_>, Vec<_>) = memories
+            .into_iter()
+            .partition(|memory| memory.verify_hash());
+        let skipped = tampered.into_iter().map(|memory| memory.id).collect();
+        if options.dry_run {
+            report.identities.push(MigratedIdentity {
+                legacy_did,
+                did: None,
+                memories: memories.len(),
+                skipped,
+                lineage_memory_id: None,
+            });
+            continue;
+        }
+
+        let identity = PlcIdentity::generate(None).map_err(|e| OcmError::Plc(e.to_string()))?;
+        // The key is saved before anything is signed with it, so it is never lost
+        write_key_file(&options.key_dir, &identity, &legacy_did)?;
+
+        let migrated_at = chrono::Utc::now().to_rfc3339();
+        let mut replacements = Vec::new();
+        for memory in &memories {
+            // Co-signatures covered the legacy DID, so they do not carry over
+            let mut copy = SignedMemory {
+                id: MemoryId::generate(),
+                did: identity.did.clone(),
+                updated_on: migrated_at.clone(),
+                co_signatures: Vec::new(),
+                ..memory.clone()
+            };
+            identity
+                .sign_memory(&mut copy)
+                .map_err(|e| OcmError::Cryptography(e.to_string()))?;
+            replacements.push((memory.id.clone(), copy));
+        }
+
+        let lineage_data = IdentityLineageData {
+            legacy_did: legacy_did.clone(),
+            did: identity.did.clone(),
+            migrated_at,
+            memories: replacements
+                .iter()
+                .map(|(legacy_id, copy)| MigratedMemory {
+                    legacy_id: legacy_id.clone(),
+                    memory_id: copy.id.clone(),
+                })
+                .collect(),
+        };
+        let mut lineage = SignedMemory::new(
+            &identity.did,
+            IDENTITY_LINEAGE_MEMORY_TYPE,
+            &serde_json::to_string(&lineage_data)?,
+        );
+        identity
+            .sign_memory(&mut lineage)
+            .map_err(|e| OcmError::Cryptography(e.to_string()))?;
+
+        database.migrate_legacy_memories(&replacements, &lineage)?;
+        report.identities.push(MigratedIdentity {
+            legacy_did,
+            did: Some(identity.did.clone()),
+            memories: replacements.len(),
+            skipped,
+            lineage_memory_id: Some(lineage.id),
+        });
+    }
+
+    Ok(report)
+}
+
+/// Write an identity's key to `<key_dir>/<DID>.json`, readable by the owner only
+fn write_key_file(key_dir: &Path, identity: &PlcIdentity, legacy_did: &Did) -> Result<()> {
+    let private_key = identity.keypair.private_key_bytes().ok_or_else(|| {
+        OcmError::Cryptography("Generated identity has no exportable key".to_string())
+    })?;
+    let key_file = IdentityKeyFile {
+        did: &identity.did,
+        legacy_did,
+        public_key: &identity.keypair.public_key,
+        private_key: general_purpose::STANDARD.encode(private_key),
+        created_at: &identity.created_at,
+        plc_operations: &identity.plc_operations,
+    };
+    let path = key_dir.join(format!("{}.json", identity.did.replace(':', "_")));
+
+    let mut options = std::fs::OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    let mut file = options.open(&path)?;
+    file.write_all(serde_json::to_string_pretty(&key_file)?.as_bytes())?;
+    file.sync_all()?;
+    Ok(())
+}
@@ -0,0 +1,272 @@
+use super::csv::{parse_individual, CsvTable, FieldErrors};
+use crate::core::error::{OcmError, Result};
+use crate::core::ids::MemoryId;
+use crate::core::memory_types::MemoryTypeRegistry;
+use crate::core::models::{is_private_memory_type, Individual, SignedMemory};
+use crate::core::tenancy::TenantScope;
+use crate::persistence::database::Database;
+use crate::security::validation::sanitize_text;
+use serde::Serialize;
+use std::collections::{HashMap, HashSet};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BatchItemStatus {
+    Created,
+    Updated,
+    Invalid,
+    NotApplied, // Valid, but another item in the batch was not
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ItemError {
+    pub field: Option<String>,
+    pub message: String,
+}
+
+/// An item's id, if it had one, with either its checked record or what was wrong with it
+type CheckedItem<T> = (Option<String>, std::result::Result<T, Vec<ItemError>>);
+
+/// The outcome for one item; `index` is its position in the request
+#[derive(Debug, Clone, Serialize)]
+pub struct BatchItemResult {
+    pub index: usize,
+    pub id: Option<String>,
+    pub status: BatchItemStatus,
+    pub errors: Vec<ItemError>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct BatchReport {
+    pub applied: bool,
+    pub created: usize,
+    pub updated: usize,
+    pub items: Vec<BatchItemResult>,
+}
+
+impl BatchReport {
+    /// Report the checked items, marking the valid ones as not applied when any failed
+    fn from_checked<T>(checked: Vec<CheckedItem<T>>) -> (Self, Vec<T>) {
+        let applied = checked.iter().all(|(_, result)| result.is_ok());
+        let mut items = Vec::with_capacity(checked.len());
+        let mut valid = Vec::new();
+        for (index, (id, result)) in checked.into_iter().enumerate() {
+            let (status, errors) = match result {
+                Ok(record) => {
+                    valid.push(record);
+                    (BatchItemStatus::NotApplied, Vec::new())
+                }
+                Err(errors) => (BatchItemStatus::Invalid, errors),
+            };
+            items.push(BatchItemResult {
+                index,
+                id,
+                status,
+                errors,
+            });
+        }
+        let report = Self {
+            applied,
+            created: 0,
+            updated: 0,
+            items,
+        };
+        (report, valid)
+    }
+}
+
+/// Validate signed memories and, if every one passes, store and queue them for
+/// broadcast in one transaction. Memories are immutable, so ids already held are refused
+pub fn write_memories(
+    db: &Database,
+    memory_types: &MemoryTypeRegistry,
+    scope: &TenantScope,
+    items: Vec<serde_json::Value>,
+    max_items: usize,
+) -> Result<BatchReport> {
+    check_batch_size(items.len(), max_items)?;
+
+    let mut seen = HashSet::new();
+    let mut checked = Vec::with_capacity(items.len());
+    for item in items {
+        let id = item_id(&item);
+        let result = match serde_json::from_value::<SignedMemory>(item) {
+            Ok(memory) => check_memory(db, memory_types, scope, &mut seen, memory)?,
+            Err(e) => Err(vec![item_error(None, e.to_string())]),
+        };
+        checked.push((id, result));
+    }
+
+    let (mut report, memories) = BatchReport::from_checked(checked);
+    if !report.applied {
+        return Ok(report);
+    }
+
+    db.create_outbound_signed_memories(&memories)?;
+    for item in &mut report.items {
+        item.status = BatchItemStatus::Created;
+    }
+    report.created = memories.len();
+    println!("📥 Stored batch of {} memories", report.created);
+    Ok(report)
+}
+
+/// Validate individuals as the CSV import does and, if every one passes, create or
+/// update them in one transaction. Items without an id are created with a new one
+pub fn write_individuals(
+    db: &Database,
+    items: Vec<serde_json::Value>,
+    max_items: usize,
+) -> Result<BatchReport> {
+    check_batch_size(items.len(), max_items)?;
+
+    let mut seen = HashSet::new();
+    let mut checked = Vec::with_capacity(items.len());
+    for item in items {
+        let id = item_id(&item);
+        let result = check_individual(&item).and_then(|individual| {
+            if seen.insert(individual.id.clone()) {
+                Ok(individual)
+            } else {
+                Err(vec![item_error(Some("id"), "Duplicate id in batch")])
+            }
+        });
+        checked.push((id, result));
+    }
+
+    let (mut report, individuals) = BatchReport::from_checked(checked);
+    if !report.applied {
+        return Ok(report);
+    }
+
+    let updated = db.save_individuals(&individuals)?;
+    for ((item, individual), updated) in report.items.iter_mut().zip(&individuals).zip(updated) {
+        item.id = Some(individual.id.clone());
+        if updated {
+            item.status = BatchItemStatus::Updated;
+            report.updated += 1;
+        } else {
+            item.status = BatchItemStatus::Created;
+            report.created += 1;
+        }
+    }
+    println!(
+        "📥 Stored batch of individuals: {} created, {} updated",
+        report.created, report.updated
+    );
+    Ok(report)
+}
+
+fn check_batch_size(items: usize, max_items: usize) -> Result<()> {
+    if items == 0 {
+        return Err(OcmError::Validation("Batch has no items".to_string()));
+    }
+    if items > max_items {
+        return Err(OcmError::Validation(format!(
+            "Batch has {} items; at most {} are accepted",
+            items, max_items
+        )));
+    }
+    Ok(())
+}
+
+fn check_memory(
+    db: &Database,
+    memory_types: &MemoryTypeRegistry,
+    scope: &TenantScope,
+    seen: &mut HashSet<MemoryId>,
+    memory: SignedMemory,
+) -> Result<std::result::Result<SignedMemory, Vec<ItemError>>> {
+    let mut errors = Vec::new();
+    if !scope.allows_memory(&memory) {
+        errors.push(item_error(
+            Some("did"),
+            "Author is outside this API key's organization",
+        ));
+    }
+    if is_private_memory_type(&memory.memory_type) {
+        errors.push(item_error(
+            Some("memory_type"),
+            "Private memory types are sent through the messages API",
+        ));
+    } else if let Err(e) = memory_types.validate(&memory.memory_type, &memory.memory_data) {
+        errors.push(item_error(Some("memory_data"), e.to_string()));
+    }
+    if !memory.verify_hash() {
+        errors.push(item_error(
+            Some("content_hash"),
+            "Content hash does not match the memory",
+        ));
+    }
+    if !seen.insert(memory.id.clone()) {
+        errors.push(item_error(Some("id"), "Duplicate id in batch"));
+    } else if db.get_signed_memory(&memory.id)?.is_some() {
+        errors.push(item_error(Some("id"), "Memory already exists"));
+    }
+
+    if errors.is_empty() {
+        Ok(Ok(memory))
+    } else {
+        Ok(Err(errors))
+    }
+}
+
+fn check_individual(item: &serde_json::Value) -> std::result::Result<Individual, Vec<ItemError>> {
+    let Some(object) = item.as_object() else {
+        return Err(vec![item_error(None, "Expected an object")]);
+    };
+
+    let mut errors = FieldErrors::new();
+    let mut values = HashMap::new();
+    for (key, value) in object {
+        let Some(field) = CsvTable::Individual
+            .columns()
+            .iter()
+            .find(|field| **field == key.as_str())
+        else {
+            return Err(vec![item_error(
+                None,
+                format!("'{}' is not an individual field", key),
+            )]);
+        };
+        match value {
+            serde_json::Value::Null => {}
+            serde_json::Value::String(text) => {
+                let text = sanitize_text(text);
+                if !text.is_empty() {
+                    values.insert(*field, text);
+                }
+            }
+            _ => errors.push((*field, "Expected a string".to_string())),
+        }
+    }
+
+    match parse_individual(&values) {
+        Ok(individual) if errors.is_empty() => Ok(individual),
+        Ok(_) => Err(field_errors(errors)),
+        Err(parse_errors) => {
+            errors.extend(parse_errors);
+            Err(field_errors(errors))
+        }
+    }
+}
+
+fn item_id(item: &serde_json::Value) -> Option<String> {
+    item.get("id")
+        .and_then(|id| id.as_str())
+        .map(str::to_string)
+}
+
+fn item_error(field: Option<&str>, message: impl Into<String>) -> ItemError {
+    ItemError {
+        field: field.map(str::to_string),
+        message: message.into(),
+    }
+}
+
+fn field_errors(errors: FieldErrors) -> Vec<ItemError> {
+    errors
+        .into_iter()
+        .map(|(field, message)| item_error(Some(field), message))
+        .collect()
+}
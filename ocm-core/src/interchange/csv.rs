@@ -0,0 +1,390 @@
+use crate::core::error::{OcmError, Result};
+use crate::core::models::{Individual, Location};
+use crate::persistence::database::Database;
+use crate::security::validation::{sanitize_text, validate_email, validate_safe_text};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::io::{Read, Write};
+use std::str::FromStr;
+
+const MAX_FIELD_LENGTH: usize = 255;
+
+/// Tables that round-trip through CSV
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CsvTable {
+    Individual,
+    Location,
+}
+
+impl CsvTable {
+    /// Column names written on export and expected (after mapping) on import
+    pub fn columns(&self) -> &'static [&'static str] {
+        match self {
+            CsvTable::Individual => &[
+                "id",
+                "first_name",
+                "middle_name",
+                "last_name",
+                "dob",
+                "phone",
+                "email",
+                "employer",
+                "updated_on",
+            ],
+            CsvTable::Location => &[
+                "id",
+                "email",
+                "phone",
+                "address",
+                "city",
+                "state",
+                "zip",
+                "country",
+                "coordinates_lat",
+                "coordinates_lon",
+                "updated_on",
+            ],
+        }
+    }
+}
+
+impl FromStr for CsvTable {
+    type Err = OcmError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "individual" | "individuals" => Ok(CsvTable::Individual),
+            "location" | "locations" => Ok(CsvTable::Location),
+            other => Err(OcmError::Validation(format!(
+                "Unknown CSV table '{}'; expected individuals or locations",
+                other
+            ))),
+        }
+    }
+}
+
+/// Maps spreadsheet headers onto table columns. Unmapped headers are matched
+/// case-insensitively, with spaces read as underscores
+#[derive(Debug, Clone, Default)]
+pub struct ColumnMapping {
+    headers: HashMap<String, String>, // CSV header -> column
+}
+
+impl ColumnMapping {
+    /// Parse a mapping such as "First Name=first_name,E-mail=email"
+    pub fn parse(spec: &str) -> Result<Self> {
+        let mut headers = HashMap::new();
+        for pair in spec.split(',').filter(|pair| !pair.trim().is_empty()) {
+            let (header, column) = pair.split_once('=').ok_or_else(|| {
+                OcmError::Validation(format!("Column mapping '{}' must be header=column", pair))
+            })?;
+            headers.insert(header.trim().to_string(), column.trim().to_string());
+        }
+        Ok(Self { headers })
+    }
+
+    fn column_for(&self, header: &str) -> String {
+        match self.headers.get(header.trim()) {
+            Some(column) => column.clone(),
+            None => header.trim().to_lowercase().replace(' ', "_"),
+        }
+    }
+}
+
+/// A problem with one CSV row; `row` counts data rows from 1, excluding the header
+#[derive(Debug, Clone, Serialize)]
+pub struct RowError {
+    pub row: usize,
+    pub field: Option<String>,
+    pub message: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ImportReport {
+    pub dry_run: bool,
+    pub rows: usize,
+    pub created: usize,
+    pub updated: usize,
+    pub errors: Vec<RowError>,
+}
+
+/// Write every row of a table as CSV, returning the number of rows
+pub fn export_csv<W: Write>(db: &Database, table: CsvTable, writer: W) -> Result<usize> {
+    let mut writer = ::csv::Writer::from_writer(writer);
+    let rows = match table {
+        CsvTable::Individual => write_rows(&mut writer, &db.list_individuals()?)?,
+        CsvTable::Location => write_rows(&mut writer, &db.list_locations()?)?,
+    };
+    writer.flush()?;
+    Ok(rows)
+}
+
+fn write_rows<W: Write, T: Serialize>(writer: &mut ::csv::Writer<W>, rows: &[T]) -> Result<usize> {
+    for row in rows {
+        writer.serialize(row).map_err(csv_error)?;
+    }
+    Ok(rows.len())
+}
+
+/// Validate every row, then store them if none failed. Rows whose id already exists
+/// are updated; rows without an id are created. A dry run validates without writing
+pub fn import_csv<R: Read>(
+    db: &Database,
+    table: CsvTable,
+    reader: R,
+    mapping: &ColumnMapping,
+    dry_run: bool,
+) -> Result<ImportReport> {
+    let mut reader = ::csv::ReaderBuilder::new()
+        .trim(::csv::Trim::All)
+        .from_reader(reader);
+    let columns: Vec<String> = reader
+        .headers()
+        .map_err(csv_error)?
+        .iter()
+        .map(|header| mapping.column_for(header))
+        .collect();
+
+    if let Some(unknown) = columns
+        .iter()
+        .find(|c| !table.columns().contains(&c.as_str()))
+    {
+        return Err(OcmError::Validation(format!(
+            "CSV column '{}' does not match any {:?} field",
+            unknown, table
+        )));
+    }
+
+    let mut report = ImportReport {
+        dry_run,
+        rows: 0,
+        created: 0,
+        updated: 0,
+        errors: Vec::new(),
+    };
+    let mut parsed = Vec::new();
+    for (index, record) in reader.records().enumerate() {
+        let row = index + 1;
+        report.rows += 1;
+
+        let record = match record {
+            Ok(record) => record,
+            Err(e) => {
+                report.errors.push(RowError {
+                    row,
+                    field: None,
+                    message: e.to_string(),
+                });
+                continue;
+            }
+        };
+        let values: HashMap<&str, String> = columns
+            .iter()
+            .map(String::as_str)
+            .zip(record.iter().map(sanitize_text))
+            .filter(|(_, value)| !value.is_empty())
+            .collect();
+
+        let result = match table {
+            CsvTable::Individual => parse_individual(&values).map(CsvRow::Individual),
+            CsvTable::Location => parse_location(&values).map(CsvRow::Location),
+        };
+        match result {
+            Ok(parsed_row) => parsed.push((row, parsed_row)),
+            Err(errors) => report
+                .errors
+                .extend(errors.into_iter().map(|(field, message)| RowError {
+                    row,
+                    field: Some(field.to_string()),
+                    message,
+                })),
+        }
+    }
+
+    if dry_run || !report.errors.is_empty() {
+        return Ok(report);
+    }
+
+    for (row, parsed_row) in parsed {
+        match parsed_row.store(db) {
+            Ok(true) => report.updated += 1,
+            Ok(false) => report.created += 1,
+            Err(e) => report.errors.push(RowError {
+                row,
+                field: None,
+                message: e.to_string(),
+            }),
+        }
+    }
+
+    println!(
+        "📥 Imported {:?} CSV: {} created, {} updated, {} failed",
+        table,
+        report.created,
+        report.updated,
+        report.errors.len()
+    );
+    Ok(report)
+}
+
+enum CsvRow {
+    Individual(Individual),
+    Location(Location),
+}
+
+impl CsvRow {
+    /// Store the row, returning whether an existing record was updated
+    fn store(&self, db: &Database) -> Result<bool> {
+        match self {
+            CsvRow::Individual(individual) => {
+                let exists = db.get_individual(&individual.id)?.is_some();
+                if exists {
+                    db.update_individual(individual)?;
+                } else {
+                    db.create_individual(individual)?;
+                }
+                Ok(exists)
+            }
+            CsvRow::Location(location) => {
+                let exists = db.get_location(&location.id)?.is_some();
+                if exists {
+                    db.update_location(location)?;
+                } else {
+                    db.create_location(location)?;
+                }
+                Ok(exists)
+            }
+        }
+    }
+}
+
+pub(super) type FieldErrors = Vec<(&'static str, String)>;
+
+pub(super) fn parse_individual(
+    values: &HashMap<&str, String>,
+) -> std::result::Result<Individual, FieldErrors> {
+    let mut errors = FieldErrors::new();
+    check_text_fields(values, CsvTable::Individual, &mut errors);
+    let first_name = required(values, "first_name", &mut errors);
+    let last_name = required(values, "last_name", &mut errors);
+    check_email(values, &mut errors);
+
+    let dob = values.get("dob").cloned();
+    if let Some(dob) = &dob {
+        let valid = chrono::NaiveDate::parse_from_str(dob, "%Y-%m-%d").is_ok()
+            || chrono::NaiveDateTime::parse_from_str(dob, "%Y-%m-%d %H:%M:%S").is_ok();
+        if !valid {
+            errors.push((
+                "dob",
+                "Expected YYYY-MM-DD or YYYY-MM-DD HH:MM:SS".to_string(),
+            ));
+        }
+    }
+
+    if !errors.is_empty() {
+        return Err(errors);
+    }
+    Ok(Individual {
+        id: row_id(values),
+        first_name,
+        middle_name: values.get("middle_name").cloned(),
+        last_name,
+        dob,
+        phone: values.get("phone").cloned(),
+        email: values.get("email").cloned(),
+        employer: values.get("employer").cloned(),
+        updated_on: now(),
+    })
+}
+
+fn parse_location(values: &HashMap<&str, String>) -> std::result::Result<Location, FieldErrors> {
+    let mut errors = FieldErrors::new();
+    check_text_fields(values, CsvTable::Location, &mut errors);
+    check_email(values, &mut errors);
+    let coordinates_lat = coordinate(values, "coordinates_lat", 90.0, &mut errors);
+    let coordinates_lon = coordinate(values, "coordinates_lon", 180.0, &mut errors);
+
+    if !errors.is_empty() {
+        return Err(errors);
+    }
+    Ok(Location {
+        id: row_id(values),
+        email: values.get("email").cloned(),
+        phone: values.get("phone").cloned(),
+        address: values.get("address").cloned(),
+        city: values.get("city").cloned(),
+        state: values.get("state").cloned(),
+        zip: values.get("zip").cloned(),
+        country: values.get("country").cloned(),
+        coordinates_lat,
+        coordinates_lon,
+        updated_on: now(),
+    })
+}
+
+fn check_text_fields(values: &HashMap<&str, String>, table: CsvTable, errors: &mut FieldErrors) {
+    for column in table.columns() {
+        if let Some(value) = values.get(column) {
+            if let Err(message) = validate_safe_text(value, MAX_FIELD_LENGTH) {
+                errors.push((*column, message));
+            }
+        }
+    }
+}
+
+fn check_email(values: &HashMap<&str, String>, errors: &mut FieldErrors) {
+    if let Some(email) = values.get("email") {
+        if let Err(message) = validate_email(email) {
+            errors.push(("email", message));
+        }
+    }
+}
+
+fn required(
+    values: &HashMap<&str, String>,
+    column: &'static str,
+    errors: &mut FieldErrors,
+) -> String {
+    match values.get(column) {
+        Some(value) => value.clone(),
+        None => {
+            errors.push((column, "Required".to_string()));
+            String::new()
+        }
+    }
+}
+
+fn coordinate(
+    values: &HashMap<&str, String>,
+    column: &'static str,
+    limit: f64,
+    errors: &mut FieldErrors,
+) -> Option<f64> {
+    let value = values.get(column)?;
+    match value.parse::<f64>() {
+        Ok(parsed) if parsed.abs() <= limit => Some(parsed),
+        _ => {
+            errors.push((
+                column,
+                format!("Expected a number between -{0} and {0}", limit),
+            ));
+            None
+        }
+    }
+}
+
+fn row_id(values: &HashMap<&str, String>) -> String {
+    values
+        .get("id")
+        .cloned()
+        .unwrap_or_else(|| uuid::Uuid::new_v4().simple().to_string())
+}
+
+// Same format as SQLite's datetime('now') column defaults
+fn now() -> String {
+    chrono::Utc::now().format("%Y-%m-%d %H:%M:%S").to_string()
+}
+
+fn csv_error(err: ::csv::Error) -> OcmError {
+    OcmError::Validation(format!("CSV error: {}", err))
+}
@@ -0,0 +1,5 @@
+pub mod batch;
+pub mod csv;
+
+pub use self::batch::*;
+pub use self::csv::*;
@@ -0,0 +1,304 @@
+use crate::config::OcmConfig;
+use crate::core::error::{OcmError, Result};
+use crate::core::ids::Did;
+use crate::core::models::{Job, JobState};
+use crate::identity::claims::ClaimSystem;
+use crate::interchange::{export_csv, import_csv, ColumnMapping, CsvTable};
+use crate::panics::catch_panic_async;
+use crate::persistence::database::Database;
+use crate::supervisor::{RestartPolicy, TaskSupervisor};
+use crate::verify::{verify_memories, VerifyScope};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+
+const PROGRESS_EVERY: usize = 100; // Items between progress writes
+const LIST_LIMIT: usize = 100;
+
+/// What a job does. Serialized with its `kind` as the job's stored parameters
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum JobSpec {
+    CsvImport {
+        table: String,
+        csv: String,
+        #[serde(default)]
+        map: Option<String>, // "Header=column,..." as accepted by ColumnMapping::parse
+        #[serde(default)]
+        dry_run: bool,
+    },
+    CsvExport {
+        table: String,
+    },
+    Verify {
+        #[serde(default)]
+        did: Option<Did>, // None checks every memory
+        #[serde(default)]
+        quarantine: bool,
+    },
+    DataSubjectExport {
+        did: Did,
+    },
+}
+
+impl JobSpec {
+    pub fn kind(&self) -> &'static str {
+        match self {
+            JobSpec::CsvImport { .. } => "csv_import",
+            JobSpec::CsvExport { .. } => "csv_export",
+            JobSpec::Verify { .. } => "verify",
+            JobSpec::DataSubjectExport { .. } => "data_subject_export",
+        }
+    }
+
+    /// The API permission a caller needs to start the job
+    pub fn permission(&self) -> &'static str {
+        match self {
+            JobSpec::CsvImport { .. } => "write",
+            JobSpec::CsvExport { .. } => "read",
+            JobSpec::Verify {
+                quarantine: true, ..
+            } => "admin",
+            JobSpec::Verify { .. } => "read",
+            JobSpec::DataSubjectExport { .. } => "data_subject",
+        }
+    }
+
+    /// Reject parameters the job would only fail on later
+    fn validate(&self) -> Result<()> {
+        match self {
+            JobSpec::CsvImport { table, map, .. } => {
+                table.parse::<CsvTable>()?;
+                if let Some(spec) = map {
+                    ColumnMapping::parse(spec)?;
+                }
+            }
+            JobSpec::CsvExport { table } => {
+                table.parse::<CsvTable>()?;
+            }
+            JobSpec::Verify { did: Some(did), .. } | JobSpec::DataSubjectExport { did } => {
+                if !did.starts_with("did:") || did.len() > 256 {
+                    return Err(OcmError::Validation(format!("Invalid DID '{}'", did)));
+                }
+            }
+            JobSpec::Verify { did: None, .. } => {}
+        }
+        Ok(())
+    }
+}
+
+/// Runs queued jobs one at a time. Jobs are stored, so a restart picks up the queue,
+/// and a job that was running when the process stopped starts again from the beginning
+pub struct JobRunner {
+    database: Arc<Database>,
+    config: OcmConfig,
+}
+
+impl JobRunner {
+    pub fn new(database: Arc<Database>, config: OcmConfig) -> Self {
+        Self { database, config }
+    }
+
+    /// Queue a job behind any already waiting
+    pub fn submit(&self, spec: JobSpec, created_by: Option<&str>) -> Result<Job> {
+        spec.validate()?;
+        if self.database.count_unfinished_jobs()? >= self.config.jobs.max_unfinished {
+            return Err(OcmError::Validation(format!(
+                "At most {} jobs may be queued or running; try again when one finishes",
+                self.config.jobs.max_unfinished
+            )));
+        }
+
+        let job = Job {
+            id: uuid::Uuid::new_v4().to_string(),
+            kind: spec.kind().to_string(),
+            state: JobState::Queued,
+            params: serde_json::to_value(&spec)?,
+            progress: 0,
+            total: None,
+            result: None,
+            error: None,
+            cancel_requested: false,
+            created_by: created_by.map(str::to_string),
+            created_at: chrono::Utc::now().to_rfc3339(),
+            started_at: None,
+            finished_at: None,
+        };
+        self.database.create_job(&job)?;
+        println!("🗂️  Queued {} job {}", job.kind, job.id);
+        Ok(job)
+    }
+
+    pub fn get(&self, id: &str) -> Result<Job> {
+        self.database
+            .get_job(id)?
+            .ok_or_else(|| OcmError::NotFound(format!("Job {}", id)))
+    }
+
+    /// The most recent jobs, newest first
+    pub fn list(&self) -> Result<Vec<Job>> {
+        self.database.list_jobs(LIST_LIMIT)
+    }
+
+    /// Cancel a job. A queued job is cancelled at once; a running one stops at its
+    /// next progress update, so its state changes a little later
+    pub fn cancel(&self, id: &str) -> Result<Job> {
+        let job = self
+            .database
+            .request_job_cancel(id)?
+            .ok_or_else(|| OcmError::NotFound(format!("Job {}", id)))?;
+        if job.state.is_finished() && !job.cancel_requested {
+            return Err(OcmError::Validation(format!(
+                "Job {} has already {}",
+                id,
+                job.state.as_str()
+            )));
+        }
+        Ok(job)
+    }
+
+    /// Run queued jobs in the background. Jobs left running by a crash of the runner or
+    /// of the process are queued again whenever it starts
+    pub fn start(self: Arc<Self>, supervisor: &TaskSupervisor) {
+        let interval_ms = self.config.jobs.poll_interval_ms;
+
+        supervisor.spawn("jobs", RestartPolicy::Always, move || {
+            let runner = self.clone();
+            async move {
+                match runner.database.requeue_interrupted_jobs() {
+                    Ok(0) => {}
+                    Ok(requeued) => println!("🗂️  Requeued {} interrupted jobs", requeued),
+                    Err(e) => eprintln!("⚠️  Failed to requeue interrupted jobs: {}", e),
+                }
+                let mut interval =
+                    tokio::time::interval(tokio::time::Duration::from_millis(interval_ms));
+
+                loop {
+                    interval.tick().await;
+
+                    // Drain the queue before waiting again
+                    loop {
+                        match runner.database.claim_next_job() {
+                            Ok(Some(job)) => runner.run(job).await,
+                            Ok(None) => break,
+                            Err(e) => {
+                                eprintln!("⚠️  Job runner failed to read the queue: {}", e);
+                                break;
+                            }
+                        }
+                    }
+                }
+            }
+        });
+    }
+
+    async fn run(&self, job: Job) {
+        println!("🗂️  Running {} job {}", job.kind, job.id);
+        let outcome = match serde_json::from_value::<JobSpec>(job.params.clone()) {
+            Ok(spec) => catch_panic_async("Job", self.execute(&job.id, spec)).await,
+            Err(e) => Err(OcmError::Serialization(e)),
+        };
+
+        let finished = match &outcome {
+            Ok(result) => {
+                self.database
+                    .finish_job(&job.id, JobState::Succeeded, Some(result), None)
+            }
+            Err(e) => {
+                let cancelled = self
+                    .database
+                    .get_job(&job.id)
+                    .map(|job| job.is_some_and(|job| job.cancel_requested))
+                    .unwrap_or(false);
+                let state = if cancelled {
+                    JobState::Cancelled
+                } else {
+                    JobState::Failed
+                };
+                println!("🗂️  Job {} {}: {}", job.id, state.as_str(), e);
+                self.database
+                    .finish_job(&job.id, state, None, Some(&e.to_string()))
+            }
+        };
+        if let Err(e) = finished {
+            eprintln!("❌ Failed to record the end of job {}: {}", job.id, e);
+        }
+    }
+
+    async fn execute(&self, id: &str, spec: JobSpec) -> Result<serde_json::Value> {
+        let database = self.database.clone();
+        let progress = |done: usize, total: usize| self.progress(id, done, total);
+
+        match spec {
+            JobSpec::CsvImport {
+                table,
+                csv,
+                map,
+                dry_run,
+            } => {
+                progress(0, 1)?;
+                let table: CsvTable = table.parse()?;
+                let mapping = match &map {
+                    Some(spec) => ColumnMapping::parse(spec)?,
+                    None => ColumnMapping::default(),
+                };
+                let report = blocking(move || {
+                    import_csv(&database, table, csv.as_bytes(), &mapping, dry_run)
+                })
+                .await?;
+                progress(1, 1)?;
+                Ok(serde_json::to_value(report)?)
+            }
+            JobSpec::CsvExport { table } => {
+                progress(0, 1)?;
+                let table: CsvTable = table.parse()?;
+                let (rows, csv) = blocking(move || {
+                    let mut body = Vec::new();
+                    let rows = export_csv(&database, table, &mut body)?;
+                    Ok((rows, String::from_utf8_lossy(&body).into_owned()))
+                })
+                .await?;
+                progress(1, 1)?;
+                Ok(serde_json::json!({ "rows": rows, "csv": csv }))
+            }
+            JobSpec::Verify { did, quarantine } => {
+                let scope = match did {
+                    Some(did) => VerifyScope::Did(did),
+                    None => VerifyScope::All,
+                };
+                let report =
+                    verify_memories(&database, &self.config, &scope, quarantine, progress).await?;
+                Ok(serde_json::to_value(report)?)
+            }
+            JobSpec::DataSubjectExport { did } => {
+                progress(0, 1)?;
+                let export =
+                    blocking(move || ClaimSystem::new(database).export_subject_data(&did)).await?;
+                progress(1, 1)?;
+                Ok(serde_json::to_value(export)?)
+            }
+        }
+    }
+
+    /// Store progress every so often, failing once the job has been asked to stop
+    fn progress(&self, id: &str, done: usize, total: usize) -> Result<()> {
+        if !done.is_multiple_of(PROGRESS_EVERY) && done != total {
+            return Ok(());
+        }
+        if self
+            .database
+            .update_job_progress(id, done as u64, Some(total as u64))?
+        {
+            return Err(OcmError::OperationFailed("Job cancelled".to_string()));
+        }
+        Ok(())
+    }
+}
+
+/// Run synchronous database work off the async workers
+async fn blocking<T: Send + 'static>(
+    work: impl FnOnce() -> Result<T> + Send + 'static,
+) -> Result<T> {
+    tokio::task::spawn_blocking(work)
+        .await
+        .map_err(|e| OcmError::OperationFailed(format!("Job task failed: {}", e)))?
+}
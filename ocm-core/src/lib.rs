@@ -1,19 +1,42 @@
 pub mod core;
 pub mod identity;
+#[cfg(feature = "native")]
+pub mod identity_migration;
 
 #[cfg(feature = "native")]
 pub mod config;
 #[cfg(feature = "native")]
+pub mod interchange;
+#[cfg(feature = "native")]
+pub mod jobs;
+#[cfg(feature = "native")]
 pub mod networking;
 #[cfg(feature = "native")]
+pub mod panics;
+#[cfg(feature = "native")]
+pub mod peer_admin;
+#[cfg(feature = "native")]
 pub mod persistence;
+#[cfg(feature = "plugins")]
+pub mod plugins;
+#[cfg(feature = "push")]
+pub mod push;
+#[cfg(feature = "rules")]
+pub mod rules;
 #[cfg(feature = "native")]
 pub mod security;
 #[cfg(feature = "native")]
+pub mod simulation;
+#[cfg(feature = "native")]
+pub mod supervisor;
+#[cfg(feature = "native")]
 pub mod sync;
+#[cfg(feature = "native")]
+pub mod verify;
 
 // Re-export key types for external use
-pub use core::{error::*, models::*};
+pub use core::{error::*, ids::*, models::*};
+pub use identity::group::*;
 pub use identity::plc::*;
 
 #[cfg(feature = "native")]
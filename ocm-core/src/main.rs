@@ -1,28 +1,191 @@
 mod config;
 mod core;
+mod doctor;
 mod identity;
+mod identity_migration;
 mod networking;
+mod panics;
+mod peer_admin;
 mod persistence;
+#[cfg(feature = "plugins")]
+mod plugins;
+#[cfg(feature = "rules")]
+mod rules;
+mod security;
+mod supervisor;
 mod sync;
+#[cfg(feature = "tui")]
+mod tui;
+mod verify;
 
-use config::{init_logging, OcmConfig};
-use core::{Individual, OcmError, Result, SignedMemory};
+use config::{init_logging, NodeRole, OcmConfig};
+use core::{memory_types::MemoryTypeRegistry, redact::Redacted, Individual, OcmError, Result};
 use tracing::{error, info};
 
-use identity::{plc::OcmProtocol, ClaimSystem};
-use networking::{OcmNetworking, PeerDiscovery};
-use persistence::Database;
+use identity::{
+    key_pins::DidKeyRefresher,
+    plc::{OcmProtocol, PlcIdentity},
+    signer::open_configured,
+    ClaimSystem,
+};
+use networking::{
+    federation::{fetch_snapshot, start_federation_server, FederationState},
+    invitations::InvitationService,
+    outbox::OutboxDispatcher,
+    OcmNetworking, PeerDiscovery,
+};
+use persistence::{
+    contacts::ContactService,
+    derived::{AttendanceTotal, DerivedMemoryEngine},
+    merges::MergeService,
+    messages::DirectMessageService,
+    quota::StorageQuota,
+    receipts::ReceiptService,
+    retention::RetentionEngine,
+    tags::TagService,
+    Database,
+};
 use std::sync::Arc;
+use supervisor::TaskSupervisor;
 use sync::SyncManager;
 
+/// Handles to the services of a started node
+#[cfg_attr(not(feature = "tui"), allow(dead_code))]
+struct RunningNode {
+    database: Arc<Database>,
+    networking: Arc<OcmNetworking>,
+    sync_manager: Arc<SyncManager>,
+}
+
 #[tokio::main]
 async fn main() -> Result<()> {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let command = args.first().map(String::as_str);
+
+    // `ocm-core tui` runs the node behind a live dashboard
+    let tui_requested = command == Some("tui");
+    #[cfg(not(feature = "tui"))]
+    if tui_requested {
+        eprintln!("This build has no dashboard; rebuild with `--features tui`");
+        std::process::exit(2);
+    }
+    // Capture before anything logs, so all output lands in the dashboard's Logs pane
+    #[cfg(feature = "tui")]
+    let dashboard_output = if tui_requested {
+        Some(tui::capture_output()?)
+    } else {
+        None
+    };
+
     // Initialize configuration
     let config = OcmConfig::from_env().map_err(|e| {
         eprintln!("Failed to load configuration: {}", e);
         e
     })?;
 
+    // `ocm-core doctor [--relay URL]` diagnoses the node instead of starting it
+    if command == Some("doctor") {
+        let relay_url = args
+            .iter()
+            .position(|arg| arg == "--relay")
+            .and_then(|i| args.get(i + 1));
+        let report = doctor::run(&config, relay_url.map(String::as_str)).await;
+        report.print();
+        std::process::exit(if report.has_failures() { 1 } else { 0 });
+    }
+
+    // `ocm-core verify (--all | --did DID | --id ID) [--quarantine] [--json]` audits stored memories
+    if command == Some("verify") {
+        let options = verify::VerifyOptions::from_args(&args[1..]).unwrap_or_else(|usage| {
+            eprintln!("{}", usage);
+            std::process::exit(2);
+        });
+        let report = verify::run(&config, &options).await?;
+        if options.json {
+            println!("{}", serde_json::to_string_pretty(&report)?);
+        } else {
+            report.print();
+        }
+        std::process::exit(if report.tampered.is_empty() { 0 } else { 1 });
+    }
+
+    // `ocm-core migrate-identities (--all | --did DID) --key-dir DIR [--dry-run] [--json]`
+    // re-signs memories of legacy stub_plc identities under new PLC identities
+    if command == Some("migrate-identities") {
+        let options =
+            identity_migration::MigrateOptions::from_args(&args[1..]).unwrap_or_else(|usage| {
+                eprintln!("{}", usage);
+                std::process::exit(2);
+            });
+        let report = identity_migration::run(&config, &options)?;
+        if options.json {
+            println!("{}", serde_json::to_string_pretty(&report)?);
+        } else {
+            report.print();
+        }
+        return Ok(());
+    }
+
+    // `ocm-core peers [list | show ID | ban ID | trust ID | reset ID | disconnect ID] [--json]`
+    // manages the peers a node has recorded
+    if command == Some("peers") {
+        let options = peer_admin::PeerOptions::from_args(&args[1..]).unwrap_or_else(|usage| {
+            eprintln!("{}", usage);
+            std::process::exit(2);
+        });
+        let peers = peer_admin::run(&config, &options)?;
+        if options.json {
+            println!("{}", serde_json::to_string_pretty(&peers)?);
+        } else {
+            peer_admin::print(&peers);
+        }
+        return Ok(());
+    }
+
+    // `ocm-core query FILTER [--limit N]` prints matching memories as NDJSON, newest first
+    if command == Some("query") {
+        let Some(expression) = args.get(1) else {
+            eprintln!(
+                "Usage: ocm-core query \"type=attendance AND data.cohort='Red'\" [--limit N]"
+            );
+            std::process::exit(2);
+        };
+        let filter = ocm_protocol::filter::Filter::parse(expression).unwrap_or_else(|e| {
+            eprintln!("{}", e);
+            std::process::exit(2);
+        });
+        let limit = args
+            .iter()
+            .position(|arg| arg == "--limit")
+            .and_then(|i| args.get(i + 1))
+            .and_then(|limit| limit.parse::<usize>().ok())
+            .unwrap_or(ocm_protocol::feed::DEFAULT_FEED_LIMIT);
+
+        let database = Database::open_read_only(&config.database.path.to_string_lossy())?;
+        database.index_memory_fields(&MemoryTypeRegistry::with_builtin_types().indexed_fields())?;
+        let mut cursor = None;
+        let mut printed = 0;
+        while printed < limit {
+            let page = database.get_feed(
+                &core::tenancy::TenantScope::Unrestricted,
+                &ocm_protocol::feed::FeedSource::All,
+                cursor.as_deref(),
+                limit - printed,
+                &[],
+                Some(&filter),
+            )?;
+            for memory in &page.memories {
+                println!("{}", serde_json::to_string(memory)?);
+            }
+            printed += page.memories.len();
+            cursor = match page.next_cursor {
+                Some(next) => Some(next),
+                None => break,
+            };
+        }
+        return Ok(());
+    }
+
     // Initialize logging
     init_logging(&config)?;
 
@@ -33,15 +196,28 @@ async fn main() -> Result<()> {
     info!("Starting OCM node with configuration: {:#?}", config);
 
     // Run the main application with proper error handling
-    if let Err(e) = run_ocm_node(config).await {
-        error!("OCM node failed: {}", e);
-        return Err(e);
+    let node = match run_ocm_node(config).await {
+        Ok(node) => node,
+        Err(e) => {
+            error!("OCM node failed: {}", e);
+            return Err(e);
+        }
+    };
+
+    #[cfg(feature = "tui")]
+    if let Some(output) = dashboard_output {
+        return tui::run(node, output).await;
     }
 
+    // Wait for shutdown signal; the node's services run until then
+    tokio::signal::ctrl_c().await?;
+    drop(node);
+    println!("\n👋 OCM node shutting down gracefully");
+
     Ok(())
 }
 
-async fn run_ocm_node(config: OcmConfig) -> Result<()> {
+async fn run_ocm_node(config: OcmConfig) -> Result<RunningNode> {
     info!("Connecting to database: {:?}", config.database.path);
     let db = Database::new(
         config
@@ -51,9 +227,24 @@ async fn run_ocm_node(config: OcmConfig) -> Result<()> {
             .to_str()
             .ok_or_else(|| OcmError::Config("Invalid database path".to_string()))?,
     )?;
+    db.set_durability(config.database.durability.clone())?;
     let db_arc = Arc::new(db);
     info!("Database connection established");
 
+    if config.database.compression.enabled {
+        db_arc.enable_compression(config.database.compression.clone());
+        let report = db_arc.compress_memory_content()?;
+        if report.bodies_compressed > 0 {
+            info!(
+                "Compressed {} memory bodies: {} bytes to {} ({} new dictionaries)",
+                report.bodies_compressed,
+                report.bytes_before,
+                report.bytes_after,
+                report.dictionaries_trained
+            );
+        }
+    }
+
     // Test individual CRUD with proper logging
     let test_individual = Individual {
         id: uuid::Uuid::new_v4().to_string(),
@@ -77,16 +268,25 @@ async fn run_ocm_node(config: OcmConfig) -> Result<()> {
     );
 
     // Initialize OCM Protocol with Bluesky PLC identity management
-    let mut ocm = OcmProtocol::new();
-    let identity = ocm.create_identity(Some("ocm-demo".to_string())).await?;
+    let mut ocm = OcmProtocol::with_config(&config.plc);
+    let identity = match open_configured(&config.plc.key)? {
+        Some(signer) => ocm.use_identity(PlcIdentity::from_signer(
+            signer,
+            Some("ocm-demo".to_string()),
+        )?),
+        None => ocm.create_identity(Some("ocm-demo".to_string())).await?,
+    };
     let identity_did = identity.did.clone();
-    println!("Created PLC identity: {}", identity_did);
+    let node_identity = identity.clone();
+    println!("Created PLC identity: {}", Redacted::did(&identity_did));
 
     // Demonstrate the OCM flow: Capture -> Attestation -> Federation
 
     // Step 1: Capture - Create a memory from the individual
     let memory_data = serde_json::to_string(&test_individual)?;
-    let mut memory = SignedMemory::new(&identity_did, "individual", &memory_data);
+    let memory_types = Arc::new(MemoryTypeRegistry::with_builtin_types());
+    db_arc.index_memory_fields(&memory_types.indexed_fields())?;
+    let mut memory = memory_types.create_memory(&identity_did, "individual", &memory_data)?;
     println!("CAPTURE: Created memory with hash: {}", memory.content_hash);
 
     // Step 2: Attestation - Sign the memory with PLC identity
@@ -96,8 +296,8 @@ async fn run_ocm_node(config: OcmConfig) -> Result<()> {
         memory.signature
     );
 
-    // Step 3: Store the signed memory locally (part of federation)
-    db_arc.create_signed_memory(&memory)?;
+    // Step 3: Store the signed memory locally and queue it for broadcast
+    db_arc.create_outbound_signed_memory(&memory)?;
     println!("STORAGE: Stored signed memory in local database");
 
     // Step 4: Federation - Verify the memory (as if received from a peer)
@@ -109,7 +309,7 @@ async fn run_ocm_node(config: OcmConfig) -> Result<()> {
     println!(
         "Found {} memories from DID: {}",
         memories.len(),
-        identity_did
+        Redacted::did(&identity_did)
     );
 
     // Demonstrate the Claim Token System
@@ -120,7 +320,7 @@ async fn run_ocm_node(config: OcmConfig) -> Result<()> {
         .create_identity(Some("summer-camp-2024".to_string()))
         .await?;
     let camp_did = camp_identity.did.clone();
-    println!("Created camp organization: {}", camp_did);
+    println!("Created camp organization: {}", Redacted::did(&camp_did));
 
     // Camp creates a proxy record for a child whose parents haven't signed up yet
     let jamie_data = Individual {
@@ -148,7 +348,10 @@ async fn run_ocm_node(config: OcmConfig) -> Result<()> {
         .await?;
 
     println!("Proxy record created for: {}", proxy.proxy_for_name);
-    println!("Claim token generated: {}", claim_token.token);
+    println!(
+        "Claim token generated: {}",
+        Redacted::secret(&claim_token.token)
+    );
 
     // Show camp statistics
     let stats = claim_system.get_claim_statistics(&camp_did)?;
@@ -162,10 +365,10 @@ async fn run_ocm_node(config: OcmConfig) -> Result<()> {
         .create_identity(Some("jamie-parent".to_string()))
         .await?;
     let parent_did = parent_identity.did.clone();
-    println!("👤 Created parent identity: {}", parent_did);
+    println!("👤 Created parent identity: {}", Redacted::did(&parent_did));
 
     let claimed_memory = claim_system
-        .claim_proxy_record(&mut ocm, &claim_token.token, &parent_did)
+        .claim_proxy_record(&mut ocm, &claim_token.token, &parent_did, None)
         .await?;
     println!(
         "Parent now owns Jamie's data with memory ID: {}",
@@ -188,10 +391,55 @@ async fn run_ocm_node(config: OcmConfig) -> Result<()> {
 
     // Step 5: Initialize P2P networking for federation
     let networking = OcmNetworking::new(8080, ocm, db_arc.clone());
+    #[cfg(feature = "chaos")]
+    let networking = if config.networking.faults.enabled {
+        println!("🐒 Fault injection enabled for outgoing P2P messages");
+        let chaos = networking::chaos::ChaosTransport::new(
+            networking.transport(),
+            &config.networking.faults,
+        );
+        networking.with_transport(Arc::new(chaos))
+    } else {
+        networking
+    };
     let networking_arc = Arc::new(networking);
+    networking_arc
+        .peer_groups
+        .apply_config(&config.networking.peer_groups)
+        .await?;
+    networking_arc
+        .bandwidth
+        .configure(&config.networking.bandwidth)
+        .await;
+    networking_arc
+        .presence
+        .configure(&config.networking.presence)
+        .await;
+    networking_arc
+        .review
+        .configure(&config.networking.inbound_review)
+        .await;
+    networking_arc
+        .ingest
+        .configure(&config.networking.ingest)
+        .await;
+    networking_arc.framing.configure(&config.networking.frames);
+    networking_arc
+        .capabilities
+        .configure(config.networking.relay_memories);
+    networking_arc.capabilities.configure_role(config.node.role);
+
+    // Every long-running task below runs under the supervisor, which restarts those
+    // that crash and reports their health to the web server's /health endpoint
+    let supervisor = TaskSupervisor::new("node", config.supervisor.clone());
+    supervisor.report_to(db_arc.clone());
+    if let Some(panic_reports) = panics::install_hook("node") {
+        panic_reports.start(db_arc.clone(), &config.telemetry, &supervisor)?;
+    }
+    networking_arc.reputation.start(&supervisor);
 
     // Start the OCM networking server
-    networking_arc.start_server().await?;
+    networking_arc.start_server(&supervisor).await?;
     println!("🌐 P2P networking layer started on port 8080");
 
     // Step 6: Initialize peer discovery mechanism
@@ -199,44 +447,196 @@ async fn run_ocm_node(config: OcmConfig) -> Result<()> {
         networking_arc.local_peer_id.clone(),
         8081, // Discovery port
         8080, // OCM networking port
-        Some(identity_did.clone()),
-    );
+        Some(identity_did.to_string()),
+        networking_arc.peers.clone(), // One peer set for discovery, networking and sync
+    )
+    .with_capabilities(networking_arc.capabilities.advertised())
+    .with_identity(networking_arc.ocm_protocol.clone())
+    .with_networking(networking_arc.clone())
+    .allow_unsigned(config.networking.allow_unsigned_discovery);
 
     // Start discovery service
-    discovery.start_discovery_service().await?;
+    discovery.start_discovery_service(&supervisor).await?;
     println!("🔍 Peer discovery service started on port 8081");
 
     // Start periodic discovery broadcasting
-    discovery.start_periodic_discovery().await?;
+    discovery.start_periodic_discovery(&supervisor).await?;
 
     // Add seed peers for initial network bootstrap (if any known peers)
     let seed_peers = vec!["127.0.0.1"]; // Add known peer IPs here
     discovery.add_seed_peers(seed_peers).await?;
 
+    // Federations publish their seed nodes in DNS, so joining one takes only its domain
+    if let Some(dns_seeds) = networking::dns_seeds::DnsSeeds::new(&config.networking.dns_seeds)? {
+        let reached = dns_seeds.bootstrap(&networking_arc).await;
+        println!("🌱 Reached {} DNS seed nodes", reached);
+    }
+
     // Connect to any discovered peers
     discovery.connect_discovered_peers(&networking_arc).await?;
+    discovery.start_auto_connect(networking_arc.clone(), &supervisor);
+
+    // Peers beyond the local network register and look each other up at a relay
+    if let Some(rendezvous) =
+        networking::rendezvous::RendezvousClient::new(&config.networking.rendezvous, &config.server)
+    {
+        discovery.start_rendezvous(Arc::new(rendezvous), &supervisor);
+        println!("🧭 Rendezvous directory lookups enabled");
+    }
 
     // Step 7: Initialize memory synchronization manager
-    let sync_manager = SyncManager::new(
-        networking_arc.local_peer_id.clone(), // Dereference to access the field
-        db_arc.clone(),                       // Arc clone (cheap pointer copy)
-        networking_arc.clone(),               // Arc clone (cheap pointer copy)
+    let sync_manager = Arc::new(
+        SyncManager::new(
+            networking_arc.local_peer_id.clone(), // Dereference to access the field
+            db_arc.clone(),                       // Arc clone (cheap pointer copy)
+            networking_arc.clone(),               // Arc clone (cheap pointer copy)
+        )
+        .with_memory_types(memory_types.clone()),
     );
+    sync_manager.configure_policy(&config.sync).await;
 
     // Start sync service
-    sync_manager.start_sync_service().await?;
+    sync_manager.start_sync_service(&supervisor).await?;
+    sync_manager.start_peer_watch(&supervisor);
+    sync_manager.start_presence(&supervisor);
     println!("🔄 Memory synchronization service started");
 
+    // A fresh node can start from a trusted peer's snapshot instead of replaying every memory
+    if let Some(snapshot_url) = &config.federation.bootstrap_snapshot_url {
+        if db_arc.list_signed_memories()?.is_empty() {
+            let bootstrap = match fetch_snapshot(snapshot_url, &networking_arc.ocm_protocol).await {
+                Ok(snapshot) => sync_manager
+                    .bootstrap_from_snapshot(snapshot, &config.federation.trusted_snapshot_signers)
+                    .await
+                    .map_err(|e| e.to_string()),
+                Err(e) => Err(e.to_string()),
+            };
+            match bootstrap {
+                Ok(imported) => info!("Bootstrapped {} memories from snapshot", imported),
+                Err(e) => error!(
+                    "Snapshot bootstrap failed, falling back to full sync: {}",
+                    e
+                ),
+            }
+        }
+    }
+
     // Initialize CRDT system with existing database memories
     sync_manager.initialize_crdt_from_database().await?;
     println!("🧠 CRDT conflict resolution system initialized");
 
-    // Start heartbeat for peer health monitoring
-    networking_arc.start_heartbeat().await?;
+    // Optional HTTPS bridge for institutions that cannot speak the TCP protocol
+    if config.federation.https_enabled {
+        let invitations = Arc::new(InvitationService::new(
+            db_arc.clone(),
+            networking_arc.peer_groups.clone(),
+        ));
+        invitations.add_founding_members(&config.federation.founding_members)?;
+        let federation_state = FederationState::new(
+            sync_manager.clone(),
+            networking_arc.ocm_protocol.clone(),
+            invitations,
+            &config.federation,
+        );
+        start_federation_server(&config.federation, federation_state, &supervisor).await?;
+    }
 
-    // Demonstrate federation by broadcasting our memory to any connected peers
-    networking_arc.broadcast_memory(&memory).await?;
-    println!("📡 Memory broadcasted to federation network");
+    // Keep derived memories (attendance totals) in step with the memories they summarise
+    let derived = DerivedMemoryEngine::new(
+        db_arc.clone(),
+        memory_types.clone(),
+        node_identity.clone(),
+        config.derived.clone(),
+    )
+    .with_derivation(Arc::new(AttendanceTotal))?;
+    Arc::new(derived).start(&supervisor);
+
+    // Resolve tag operations from peers into the local tag index
+    Arc::new(TagService::new(db_arc.clone(), node_identity.clone())).start(&supervisor);
+
+    // Archive memories superseded by merges as the merge records sync in
+    Arc::new(MergeService::new(db_arc.clone(), node_identity.clone())).start(&supervisor);
+
+    // Keep the contact book in step with changes made on this identity's other devices
+    Arc::new(ContactService::new(db_arc.clone(), node_identity.clone())).start(&supervisor);
+
+    // Open direct messages to or from this identity as they sync in
+    Arc::new(DirectMessageService::new(
+        db_arc.clone(),
+        node_identity.clone(),
+    ))
+    .start(&supervisor);
+
+    // Acknowledge memories addressed to this identity and collect receipts for its own
+    Arc::new(ReceiptService::new(
+        db_arc.clone(),
+        node_identity.clone(),
+        config.receipts.clone(),
+    ))
+    .start(&supervisor);
+
+    // Re-resolve the DID documents of memory authors and flag key changes
+    Arc::new(DidKeyRefresher::new(db_arc.clone(), &config.plc)).start(&supervisor);
+
+    // Schedule retention rules (purge/archive old memories); archive nodes never prune
+    if config.node.role == NodeRole::Archive {
+        println!("🗄️  Archive node: retention rules are not applied");
+    } else {
+        let retention = Arc::new(RetentionEngine::new(
+            db_arc.clone(),
+            config.retention.clone(),
+        ));
+        retention.start_scheduler(node_identity.clone(), &supervisor);
+    }
+
+    // Light nodes keep bodies for their most recent memories only
+    if let (NodeRole::Light, Some(max_full_memories)) =
+        (config.node.role, config.node.max_full_memories)
+    {
+        Arc::new(StorageQuota::new(
+            db_arc.clone(),
+            max_full_memories,
+            identity_did.clone(),
+        ))
+        .start(&supervisor);
+    }
+
+    // Load WebAssembly plugins and feed them the activity feed
+    #[cfg(feature = "plugins")]
+    if config.plugins.enabled {
+        let plugin_host = plugins::PluginHost::new(
+            config.plugins.clone(),
+            db_arc.clone(),
+            memory_types.clone(),
+            node_identity,
+        )?;
+        let loaded = plugin_host.load_all();
+        println!("🧩 {} plugin(s) loaded", loaded);
+        Arc::new(plugin_host).start(&supervisor);
+    }
+
+    // Run the operator's automation rules on new activity
+    #[cfg(feature = "rules")]
+    if config.rules.enabled {
+        let rule_engine = rules::RuleEngine::new(config.rules.clone(), db_arc.clone())?;
+        Arc::new(rule_engine).start(&supervisor);
+        println!("📜 Automation rules enabled");
+    }
+
+    // Start heartbeat for peer health monitoring
+    networking_arc.start_heartbeat(&supervisor).await?;
+
+    // Demonstrate federation: the outbox broadcasts our memory once peers accept it
+    let outbox = Arc::new(OutboxDispatcher::new(
+        db_arc.clone(),
+        networking_arc.clone(),
+    ));
+    let delivered = outbox.dispatch_pending().await?;
+    println!(
+        "📡 Outbox delivered {} memories to the federation network",
+        delivered
+    );
+    outbox.start(&supervisor);
 
     // Demonstrate CRDT conflict resolution by creating a simulated conflict
     println!("\n🔧 Demonstrating CRDT conflict resolution...");
@@ -268,10 +668,33 @@ async fn run_ocm_node(config: OcmConfig) -> Result<()> {
     println!("   - Total peers synced: {}", sync_stats.total_peers_synced);
     println!("   - Database memories: {}", sync_stats.total_memories);
     println!("   - CRDT-managed memories: {}", sync_stats.crdt_memories);
+    println!(
+        "   - Header-only memories: {}",
+        sync_stats.header_only_memories
+    );
+    println!(
+        "   - Content store: {} bodies shared by {} memories",
+        sync_stats.stored_bodies, sync_stats.body_references
+    );
     println!(
         "   - Unresolved conflicts: {}",
         sync_stats.unresolved_conflicts
     );
+    for group in &sync_stats.peer_groups {
+        println!(
+            "   - Peer group '{}': {} members, {} connected, {} memories shared",
+            group.name, group.member_count, group.connected_peers, group.memories_shared
+        );
+    }
+    println!(
+        "   - Trusted devices online: {} of {}",
+        sync_stats
+            .peer_presence
+            .iter()
+            .filter(|presence| presence.online)
+            .count(),
+        sync_stats.peer_presence.len()
+    );
 
     if conflict_summary.total_conflicts > 0 {
         println!(
@@ -300,9 +723,9 @@ async fn run_ocm_node(config: OcmConfig) -> Result<()> {
     println!("   - Peer discovery: 127.0.0.1:8081 (UDP)");
     println!("   Use Ctrl+C to stop the node");
 
-    // Wait for shutdown signal
-    tokio::signal::ctrl_c().await?;
-    println!("\n👋 OCM node shutting down gracefully");
-
-    Ok(())
+    Ok(RunningNode {
+        database: db_arc,
+        networking: networking_arc,
+        sync_manager,
+    })
 }
@@ -0,0 +1,161 @@
+use crate::config::{BandwidthConfig, SyncWindow};
+use chrono::Timelike;
+use serde::{Deserialize, Serialize};
+use std::time::{Duration, Instant};
+use tokio::sync::Mutex;
+
+/// Runtime override for the configured sync schedule
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SyncOverride {
+    ForceAllow,
+    ForceDeny,
+}
+
+/// Token bucket that may go into debt, so a large frame waits for the
+/// bytes it borrowed instead of being rejected outright
+struct TokenBucket {
+    rate: f64, // Bytes per second
+    tokens: f64,
+    last_refill: Instant,
+}
+
+impl TokenBucket {
+    fn new(bytes_per_second: u64) -> Self {
+        Self {
+            rate: bytes_per_second as f64,
+            tokens: bytes_per_second as f64, // Allow a one second burst
+            last_refill: Instant::now(),
+        }
+    }
+
+    /// Take `bytes` from the bucket and return how long the caller must wait
+    fn reserve(&mut self, bytes: usize) -> Duration {
+        let now = Instant::now();
+        let elapsed = now.duration_since(self.last_refill).as_secs_f64();
+        self.tokens = (self.tokens + elapsed * self.rate).min(self.rate);
+        self.last_refill = now;
+
+        self.tokens -= bytes as f64;
+        if self.tokens >= 0.0 {
+            Duration::ZERO
+        } else {
+            Duration::from_secs_f64(-self.tokens / self.rate)
+        }
+    }
+}
+
+struct BandwidthState {
+    upload: Option<TokenBucket>,
+    download: Option<TokenBucket>,
+    sync_windows: Vec<SyncWindow>,
+    require_idle: Option<Duration>,
+    last_traffic: Instant,
+    sync_override: Option<SyncOverride>,
+}
+
+/// Rate limits for P2P traffic and the schedule deciding when sync may run
+pub struct BandwidthController {
+    state: Mutex<BandwidthState>,
+}
+
+impl Default for BandwidthController {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl BandwidthController {
+    /// Unlimited, with sync allowed at any time
+    pub fn new() -> Self {
+        Self {
+            state: Mutex::new(BandwidthState {
+                upload: None,
+                download: None,
+                sync_windows: Vec::new(),
+                require_idle: None,
+                last_traffic: Instant::now(),
+                sync_override: None,
+            }),
+        }
+    }
+
+    pub async fn configure(&self, config: &BandwidthConfig) {
+        let mut state = self.state.lock().await;
+        state.upload = config.upload_bytes_per_second.map(TokenBucket::new);
+        state.download = config.download_bytes_per_second.map(TokenBucket::new);
+        state.sync_windows = config.sync_windows.clone();
+        state.require_idle = config.require_idle_seconds.map(Duration::from_secs);
+    }
+
+    /// Change rate limits at runtime; `None` removes a limit
+    pub async fn set_limits(&self, upload: Option<u64>, download: Option<u64>) {
+        let mut state = self.state.lock().await;
+        state.upload = upload.filter(|rate| *rate > 0).map(TokenBucket::new);
+        state.download = download.filter(|rate| *rate > 0).map(TokenBucket::new);
+    }
+
+    /// Force sync on or off regardless of the schedule; `None` returns to the schedule
+    pub async fn set_sync_override(&self, sync_override: Option<SyncOverride>) {
+        self.state.lock().await.sync_override = sync_override;
+    }
+
+    /// Wait until `bytes` may be written to a peer
+    pub async fn acquire_upload(&self, bytes: usize) {
+        let wait = {
+            let mut state = self.state.lock().await;
+            state.last_traffic = Instant::now();
+            state.upload.as_mut().map(|bucket| bucket.reserve(bytes))
+        };
+        sleep_if_needed(wait).await;
+    }
+
+    /// Wait until `bytes` may be read from a peer
+    pub async fn acquire_download(&self, bytes: usize) {
+        let wait = {
+            let mut state = self.state.lock().await;
+            state.last_traffic = Instant::now();
+            state.download.as_mut().map(|bucket| bucket.reserve(bytes))
+        };
+        sleep_if_needed(wait).await;
+    }
+
+    /// Whether a sync may start now, given the override, windows and idle requirement
+    pub async fn sync_allowed_now(&self) -> bool {
+        let state = self.state.lock().await;
+        match state.sync_override {
+            Some(SyncOverride::ForceAllow) => return true,
+            Some(SyncOverride::ForceDeny) => return false,
+            None => {}
+        }
+
+        let hour = chrono::Local::now().hour();
+        if !state.sync_windows.is_empty()
+            && !state
+                .sync_windows
+                .iter()
+                .any(|window| window_contains(window, hour))
+        {
+            return false;
+        }
+
+        match state.require_idle {
+            Some(idle) => state.last_traffic.elapsed() >= idle,
+            None => true,
+        }
+    }
+}
+
+fn window_contains(window: &SyncWindow, hour: u32) -> bool {
+    if window.start_hour < window.end_hour {
+        hour >= window.start_hour && hour < window.end_hour
+    } else {
+        // Wraps past midnight, e.g. 22-6
+        hour >= window.start_hour || hour < window.end_hour
+    }
+}
+
+async fn sleep_if_needed(wait: Option<Duration>) {
+    if let Some(wait) = wait.filter(|wait| !wait.is_zero()) {
+        tokio::time::sleep(wait).await;
+    }
+}
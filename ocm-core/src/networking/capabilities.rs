@@ -0,0 +1,50 @@
+use super::protocol::{NodeRole, PEER_CAPABILITIES, RELAY};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::RwLock;
+
+/// What this node offers its peers, advertised in handshakes and discovery beacons
+pub struct LocalCapabilities {
+    relay: AtomicBool,
+    role: RwLock<NodeRole>,
+}
+
+impl Default for LocalCapabilities {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl LocalCapabilities {
+    pub fn new() -> Self {
+        Self {
+            relay: AtomicBool::new(false),
+            role: RwLock::new(NodeRole::Full),
+        }
+    }
+
+    /// Whether to pass memories from directly connected peers on to the others
+    pub fn configure(&self, relay_memories: bool) {
+        self.relay.store(relay_memories, Ordering::Relaxed);
+    }
+
+    pub fn configure_role(&self, role: NodeRole) {
+        *self.role.write().unwrap_or_else(|e| e.into_inner()) = role;
+    }
+
+    pub fn relays(&self) -> bool {
+        self.relay.load(Ordering::Relaxed)
+    }
+
+    pub fn role(&self) -> NodeRole {
+        *self.role.read().unwrap_or_else(|e| e.into_inner())
+    }
+
+    pub fn advertised(&self) -> Vec<String> {
+        let mut capabilities: Vec<String> =
+            PEER_CAPABILITIES.iter().map(|c| c.to_string()).collect();
+        if self.relays() {
+            capabilities.push(RELAY.to_string());
+        }
+        capabilities
+    }
+}
@@ -0,0 +1,102 @@
+use super::protocol::{NetworkMessage, PeerInfo};
+use super::transport::{Delivery, Transport, TransportError};
+use crate::config::FaultInjectionConfig;
+use futures_util::future::BoxFuture;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+/// What happens to one outgoing message, decided up front so the RNG lock
+/// is never held across a send
+struct Faults {
+    drop: bool,
+    delay: Option<Duration>,
+    duplicate: bool,
+    corrupt_at: Option<usize>, // Payload byte to flip
+}
+
+/// Wraps another transport and randomly drops, delays, duplicates or corrupts
+/// outgoing messages at the configured rates
+pub struct ChaosTransport {
+    inner: Arc<dyn Transport>,
+    config: FaultInjectionConfig,
+    rng: Mutex<StdRng>,
+}
+
+impl ChaosTransport {
+    pub fn new(inner: Arc<dyn Transport>, config: &FaultInjectionConfig) -> Self {
+        let rng = match config.seed {
+            Some(seed) => StdRng::seed_from_u64(seed),
+            None => StdRng::from_entropy(),
+        };
+        Self {
+            inner,
+            config: config.clone(),
+            rng: Mutex::new(rng),
+        }
+    }
+
+    fn roll(&self, payload_len: usize) -> Faults {
+        let mut rng = self.rng.lock().unwrap_or_else(|e| e.into_inner());
+        let config = &self.config;
+        Faults {
+            drop: rng.gen_bool(config.drop_rate),
+            delay: rng
+                .gen_bool(config.delay_rate)
+                .then(|| Duration::from_millis(rng.gen_range(0..=config.max_delay_ms))),
+            duplicate: rng.gen_bool(config.duplicate_rate),
+            corrupt_at: (payload_len > 0 && rng.gen_bool(config.corrupt_rate))
+                .then(|| rng.gen_range(0..payload_len)),
+        }
+    }
+}
+
+impl Transport for ChaosTransport {
+    fn send<'a>(
+        &'a self,
+        peer: &'a PeerInfo,
+        message: &'a NetworkMessage,
+    ) -> BoxFuture<'a, Result<Delivery, TransportError>> {
+        Box::pin(async move {
+            let faults = self.roll(message.payload.len());
+
+            if faults.drop {
+                eprintln!(
+                    "🐒 Chaos: dropped {:?} to {}",
+                    message.message_type, peer.peer_id
+                );
+                return Ok(Delivery::default());
+            }
+            if let Some(delay) = faults.delay {
+                tokio::time::sleep(delay).await;
+            }
+
+            let mut message = message.clone();
+            if let Some(at) = faults.corrupt_at {
+                // The receiver's HMAC check should reject it, so it is never acknowledged
+                let mut bytes = message.payload.into_bytes();
+                bytes[at] ^= 0x20;
+                message.payload = String::from_utf8_lossy(&bytes).into_owned();
+                eprintln!(
+                    "🐒 Chaos: corrupted {:?} to {}",
+                    message.message_type, peer.peer_id
+                );
+            }
+
+            if faults.duplicate {
+                // Replay protection should reject the copy; don't wait out its ack timeout
+                let (inner, target, copy) = (self.inner.clone(), peer.clone(), message.clone());
+                tokio::spawn(async move {
+                    let _ = inner.send(&target, &copy).await;
+                });
+                eprintln!(
+                    "🐒 Chaos: duplicated {:?} to {}",
+                    message.message_type, peer.peer_id
+                );
+            }
+
+            self.inner.send(peer, &message).await
+        })
+    }
+}
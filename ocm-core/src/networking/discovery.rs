@@ -1,10 +1,27 @@
-use crate::networking::protocol::{OcmNetworking, PeerInfo};
+use crate::core::redact::Redacted;
+use crate::identity::plc::OcmProtocol;
+use crate::networking::peers::{PeerEvent, PeerStore};
+use crate::networking::protocol::{NodeRole, OcmNetworking, PeerInfo};
+use crate::networking::rendezvous::{entry_to_peer, RendezvousClient};
+use crate::supervisor::{RestartPolicy, TaskSupervisor};
+use ocm_protocol::message::PEER_CAPABILITIES;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::sync::Arc;
 use tokio::net::UdpSocket;
+use tokio::sync::broadcast::error::RecvError;
 use tokio::sync::Mutex;
 
+const MAX_DATAGRAM_BYTES: usize = 65_507; // Signed responses listing many peers outgrow 1 KiB
+const ANNOUNCEMENT_MAX_AGE_SECS: i64 = 300; // Older signed beacons and responses are replays
+const ANNOUNCEMENT_MAX_AHEAD_SECS: i64 = 60; // Allowance for a sender's clock running ahead
+const MAX_RESPONSE_BYTES: usize = 1_400; // One page of peers, within a typical path MTU
+const SIGNATURE_ALLOWANCE_BYTES: usize = 256; // Room left in a page for the DID and signature
+const MAX_UDP_PAGED_PEERS: usize = 64; // Larger peer lists are fetched over TCP after one page
+const REPLY_TIMEOUT_SECS: u64 = 2;
+
+/// A node announcing itself on the local network. Signed by the node's identity, so
+/// the peer table only takes endpoints from holders of the DID they claim
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct DiscoveryBeacon {
     pub peer_id: String,
@@ -13,28 +30,81 @@ pub struct DiscoveryBeacon {
     pub capabilities: Vec<String>,
     pub version: String,
     pub timestamp: String,
+    #[serde(default)]
+    pub nonce: String, // Unique per beacon for replay protection; empty from older nodes
+    #[serde(default)]
+    pub signature: Option<String>, // By `did` over get_signing_payload
+}
+
+impl DiscoveryBeacon {
+    pub fn get_signing_payload(&self) -> String {
+        serde_json::json!({
+            "peer_id": self.peer_id,
+            "did": self.did,
+            "port": self.port,
+            "capabilities": self.capabilities,
+            "version": self.version,
+            "timestamp": self.timestamp,
+            "nonce": self.nonce,
+        })
+        .to_string()
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct DiscoveryRequest {
     pub requesting_peer_id: String,
     pub timestamp: String,
+    #[serde(default)]
+    pub offset: usize, // Position in the responder's freshest-first peer list
 }
 
+/// A page of the peers a node knows, most recently seen first, sent in answer to a
+/// DiscoveryRequest and signed like a beacon. Pages fit in one unfragmented datagram
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct DiscoveryResponse {
     pub responding_peer_id: String,
     pub peers: Vec<PeerInfo>,
     pub timestamp: String,
+    #[serde(default)]
+    pub total_peers: usize, // How many peers the responder knows
+    #[serde(default)]
+    pub next_offset: Option<usize>, // Where the next page starts; None on the last page
+    #[serde(default)]
+    pub did: Option<String>,
+    #[serde(default)]
+    pub nonce: String,
+    #[serde(default)]
+    pub signature: Option<String>,
 }
 
+impl DiscoveryResponse {
+    pub fn get_signing_payload(&self) -> String {
+        serde_json::json!({
+            "responding_peer_id": self.responding_peer_id,
+            "peers": self.peers,
+            "timestamp": self.timestamp,
+            "total_peers": self.total_peers,
+            "next_offset": self.next_offset,
+            "did": self.did,
+            "nonce": self.nonce,
+        })
+        .to_string()
+    }
+}
+
+#[derive(Clone)]
 pub struct PeerDiscovery {
     pub local_peer_id: String,
     pub discovery_port: u16,
     pub ocm_port: u16,
-    pub known_peers: Arc<Mutex<HashMap<String, PeerInfo>>>,
+    pub peers: Arc<PeerStore>, // Shared with networking, so discovered peers are usable at once
     pub capabilities: Vec<String>,
     pub did: Option<String>,
+    identity: Option<Arc<Mutex<OcmProtocol>>>, // Signs what this node sends, verifies what it receives
+    allow_unsigned: bool,
+    seen_nonces: Arc<std::sync::Mutex<HashMap<String, chrono::DateTime<chrono::Utc>>>>,
+    networking: Option<Arc<OcmNetworking>>, // Fetches peer lists too large for UDP pages
 }
 
 impl PeerDiscovery {
@@ -43,60 +113,93 @@ impl PeerDiscovery {
         discovery_port: u16,
         ocm_port: u16,
         did: Option<String>,
+        peers: Arc<PeerStore>,
     ) -> Self {
         PeerDiscovery {
             local_peer_id,
             discovery_port,
             ocm_port,
-            known_peers: Arc::new(Mutex::new(HashMap::new())),
-            capabilities: vec![
-                "memory-sync".to_string(),
-                "peer-discovery".to_string(),
-                "identity-verification".to_string(),
-            ],
+            peers,
+            capabilities: PEER_CAPABILITIES.iter().map(|c| c.to_string()).collect(),
             did,
+            identity: None,
+            allow_unsigned: false,
+            seen_nonces: Arc::new(std::sync::Mutex::new(HashMap::new())),
+            networking: None,
         }
     }
 
-    pub async fn start_discovery_service(&self) -> Result<(), Box<dyn std::error::Error>> {
+    /// Advertise `capabilities` in beacons instead of the protocol's defaults
+    pub fn with_capabilities(mut self, capabilities: Vec<String>) -> Self {
+        self.capabilities = capabilities;
+        self
+    }
+
+    /// Sign beacons and responses with the protocol's current identity, and resolve the
+    /// DIDs of received ones through it. Without an identity every beacon is unsigned
+    pub fn with_identity(mut self, ocm_protocol: Arc<Mutex<OcmProtocol>>) -> Self {
+        self.identity = Some(ocm_protocol);
+        self
+    }
+
+    /// Fetch peer lists larger than MAX_UDP_PAGED_PEERS over this P2P connection. Without
+    /// it only their first page is taken
+    pub fn with_networking(mut self, networking: Arc<OcmNetworking>) -> Self {
+        self.networking = Some(networking);
+        self
+    }
+
+    /// Accept unsigned beacons and responses, as sent by older nodes and nodes without an
+    /// identity. Signed ones must still verify
+    pub fn allow_unsigned(mut self, allow_unsigned: bool) -> Self {
+        self.allow_unsigned = allow_unsigned;
+        self
+    }
+
+    pub async fn start_discovery_service(
+        &self,
+        supervisor: &TaskSupervisor,
+    ) -> Result<(), Box<dyn std::error::Error>> {
         let discovery_addr = format!("0.0.0.0:{}", self.discovery_port);
         let socket = UdpSocket::bind(&discovery_addr).await?;
         println!("🔍 Peer discovery service listening on: {}", discovery_addr);
 
-        let local_peer_id = self.local_peer_id.clone();
-        let ocm_port = self.ocm_port;
-        let did = self.did.clone();
-        let capabilities = self.capabilities.clone();
-        let known_peers = self.known_peers.clone();
-
-        tokio::spawn(async move {
-            let mut buffer = [0u8; 1024];
-
-            loop {
-                match socket.recv_from(&mut buffer).await {
-                    Ok((size, addr)) => {
-                        let data = &buffer[..size];
-
-                        if let Ok(beacon) = serde_json::from_slice::<DiscoveryBeacon>(data) {
-                            Self::handle_discovery_beacon(beacon, addr.to_string(), &known_peers)
-                                .await;
-                        } else if let Ok(request) = serde_json::from_slice::<DiscoveryRequest>(data)
-                        {
-                            Self::handle_discovery_request(
-                                request,
-                                addr,
-                                &socket,
-                                &local_peer_id,
-                                &did,
-                                &capabilities,
-                                ocm_port,
-                                &known_peers,
-                            )
-                            .await;
+        let discovery = self.clone();
+        let socket = Arc::new(socket);
+
+        supervisor.spawn("discovery_listener", RestartPolicy::Always, move || {
+            let discovery = discovery.clone();
+            let socket = socket.clone();
+            async move {
+                let mut buffer = vec![0u8; MAX_DATAGRAM_BYTES];
+
+                loop {
+                    match socket.recv_from(&mut buffer).await {
+                        Ok((size, addr)) => {
+                            let data = &buffer[..size];
+
+                            if let Ok(beacon) = serde_json::from_slice::<DiscoveryBeacon>(data) {
+                                if beacon.peer_id != discovery.local_peer_id {
+                                    discovery
+                                        .handle_discovery_beacon(beacon, addr.to_string())
+                                        .await;
+                                }
+                            } else if let Ok(request) =
+                                serde_json::from_slice::<DiscoveryRequest>(data)
+                            {
+                                discovery
+                                    .handle_discovery_request(request, addr, &socket)
+                                    .await;
+                            } else if let Ok(response) =
+                                serde_json::from_slice::<DiscoveryResponse>(data)
+                            {
+                                // Unsolicited pages are merged, but not followed
+                                discovery.handle_discovery_response(response).await;
+                            }
+                        }
+                        Err(e) => {
+                            eprintln!("Discovery service error: {}", e);
                         }
-                    }
-                    Err(e) => {
-                        eprintln!("Discovery service error: {}", e);
                     }
                 }
             }
@@ -105,11 +208,21 @@ impl PeerDiscovery {
         Ok(())
     }
 
-    async fn handle_discovery_beacon(
-        beacon: DiscoveryBeacon,
-        peer_addr: String,
-        known_peers: &Arc<Mutex<HashMap<String, PeerInfo>>>,
-    ) {
+    async fn handle_discovery_beacon(&self, beacon: DiscoveryBeacon, peer_addr: String) {
+        let Some(did) = self
+            .authenticate(
+                &beacon.peer_id,
+                beacon.did.as_deref(),
+                &beacon.timestamp,
+                &beacon.nonce,
+                beacon.signature.as_deref(),
+                &beacon.get_signing_payload(),
+            )
+            .await
+        else {
+            return;
+        };
+
         // Extract IP address from socket address
         let ip = peer_addr
             .split(':')
@@ -117,18 +230,33 @@ impl PeerDiscovery {
             .unwrap_or("127.0.0.1")
             .to_string();
 
+        // Beacons carry no protocol version, frame limits or role; keep those from a handshake
+        let known = self.peers.get(&beacon.peer_id).await;
+        let known_did = known.as_ref().and_then(|peer| peer.did.clone());
+        if let (Some(did), Some(known_did)) = (&did, &known_did) {
+            if did != known_did {
+                eprintln!(
+                    "⚠️  Dropping beacon for peer {} signed by {}, not its DID {}",
+                    beacon.peer_id,
+                    Redacted::did(did),
+                    Redacted::did(known_did)
+                );
+                return;
+            }
+        }
         let peer_info = PeerInfo {
             peer_id: beacon.peer_id.clone(),
             address: ip,
             port: beacon.port,
             last_seen: chrono::Utc::now(),
-            did: beacon.did.clone(),
+            did: did.or(known_did), // Unsigned beacons, when allowed, do not set a DID
+            capabilities: beacon.capabilities.clone(),
+            protocol_version: known.as_ref().map_or(0, |peer| peer.protocol_version),
+            role: known.as_ref().map_or(NodeRole::Full, |peer| peer.role),
+            frame_limits: known.and_then(|peer| peer.frame_limits),
         };
 
-        known_peers
-            .lock()
-            .await
-            .insert(beacon.peer_id.clone(), peer_info);
+        self.peers.upsert(peer_info).await;
         println!(
             "🔍 Discovered peer: {} at port {}",
             beacon.peer_id, beacon.port
@@ -136,58 +264,102 @@ impl PeerDiscovery {
     }
 
     async fn handle_discovery_request(
-        _request: DiscoveryRequest,
+        &self,
+        request: DiscoveryRequest,
         peer_addr: std::net::SocketAddr,
         socket: &UdpSocket,
-        local_peer_id: &str,
-        did: &Option<String>,
-        capabilities: &[String],
-        ocm_port: u16,
-        known_peers: &Arc<Mutex<HashMap<String, PeerInfo>>>,
     ) {
-        // Respond with our beacon and known peers
-        let beacon = DiscoveryBeacon {
-            peer_id: local_peer_id.to_string(),
-            did: did.clone(),
-            port: ocm_port,
-            capabilities: capabilities.to_vec(),
-            version: "0.1.0".to_string(),
-            timestamp: chrono::Utc::now().to_rfc3339(),
-        };
-
-        if let Ok(beacon_data) = serde_json::to_vec(&beacon) {
-            let _ = socket.send_to(&beacon_data, peer_addr).await;
+        // Respond with our beacon, ahead of the first page only
+        if request.offset == 0 {
+            let beacon = self.signed_beacon().await;
+            if let Ok(beacon_data) = serde_json::to_vec(&beacon) {
+                let _ = socket.send_to(&beacon_data, peer_addr).await;
+            }
         }
 
-        // Also send known peers as a separate response
-        let peers_lock = known_peers.lock().await;
-        let peers: Vec<PeerInfo> = peers_lock.values().cloned().collect();
-        drop(peers_lock);
-
-        let response = DiscoveryResponse {
-            responding_peer_id: local_peer_id.to_string(),
-            peers,
+        // Then the requested page of known peers, as many as fit in one datagram. The
+        // order can shift between pages; merging takes care of repeats
+        let known = self.peers.freshest().await;
+        let offset = request.offset.min(known.len());
+        let mut response = DiscoveryResponse {
+            responding_peer_id: self.local_peer_id.clone(),
+            peers: Vec::new(),
             timestamp: chrono::Utc::now().to_rfc3339(),
+            total_peers: known.len(),
+            next_offset: None,
+            did: None,
+            nonce: uuid::Uuid::new_v4().to_string(),
+            signature: None,
         };
+        let mut size =
+            serde_json::to_vec(&response).map_or(0, |data| data.len()) + SIGNATURE_ALLOWANCE_BYTES;
+        for peer in &known[offset..] {
+            let peer_size = serde_json::to_vec(peer).map_or(0, |data| data.len()) + 1;
+            if !response.peers.is_empty() && size + peer_size > MAX_RESPONSE_BYTES {
+                break;
+            }
+            size += peer_size;
+            response.peers.push(peer.clone());
+        }
+        let end = offset + response.peers.len();
+        if end < known.len() {
+            response.next_offset = Some(end);
+        }
+        let (did, signature) = self
+            .sign(|did| {
+                response.did = Some(did.to_string());
+                response.get_signing_payload()
+            })
+            .await;
+        response.did = did;
+        response.signature = signature;
 
         if let Ok(response_data) = serde_json::to_vec(&response) {
             let _ = socket.send_to(&response_data, peer_addr).await;
         }
     }
 
+    /// Second-hand sightings: only merged if newer than what we know, or than our removal.
+    /// False if the response was dropped
+    async fn handle_discovery_response(&self, response: DiscoveryResponse) -> bool {
+        if response.responding_peer_id == self.local_peer_id {
+            return false;
+        }
+        if self
+            .authenticate(
+                &response.responding_peer_id,
+                response.did.as_deref(),
+                &response.timestamp,
+                &response.nonce,
+                response.signature.as_deref(),
+                &response.get_signing_payload(),
+            )
+            .await
+            .is_none()
+        {
+            return false;
+        }
+
+        let reported = response
+            .peers
+            .into_iter()
+            .filter(|peer| peer.peer_id != self.local_peer_id)
+            .collect();
+        let merged = self.peers.merge(reported).await;
+        if merged > 0 {
+            println!(
+                "🔍 Learned {} peers from: {}",
+                merged, response.responding_peer_id
+            );
+        }
+        true
+    }
+
     pub async fn broadcast_beacon(&self) -> Result<(), Box<dyn std::error::Error>> {
         let socket = UdpSocket::bind("0.0.0.0:0").await?;
         socket.set_broadcast(true)?;
 
-        let beacon = DiscoveryBeacon {
-            peer_id: self.local_peer_id.clone(),
-            did: self.did.clone(),
-            port: self.ocm_port,
-            capabilities: self.capabilities.clone(),
-            version: "0.1.0".to_string(),
-            timestamp: chrono::Utc::now().to_rfc3339(),
-        };
-
+        let beacon = self.signed_beacon().await;
         let beacon_data = serde_json::to_vec(&beacon)?;
 
         // Broadcast to local network
@@ -198,51 +370,245 @@ impl PeerDiscovery {
         Ok(())
     }
 
-    pub async fn request_peers(&self, target_addr: &str) -> Result<(), Box<dyn std::error::Error>> {
-        let socket = UdpSocket::bind("0.0.0.0:0").await?;
-
-        let request = DiscoveryRequest {
-            requesting_peer_id: self.local_peer_id.clone(),
+    /// This node's beacon, signed by its identity when it has one
+    async fn signed_beacon(&self) -> DiscoveryBeacon {
+        let mut beacon = DiscoveryBeacon {
+            peer_id: self.local_peer_id.clone(),
+            did: None,
+            port: self.ocm_port,
+            capabilities: self.capabilities.clone(),
+            version: "0.1.0".to_string(),
             timestamp: chrono::Utc::now().to_rfc3339(),
+            nonce: uuid::Uuid::new_v4().to_string(),
+            signature: None,
         };
+        let (did, signature) = self
+            .sign(|did| {
+                beacon.did = Some(did.to_string());
+                beacon.get_signing_payload()
+            })
+            .await;
+        beacon.did = did;
+        beacon.signature = signature;
+        beacon
+    }
 
-        let request_data = serde_json::to_vec(&request)?;
+    /// Sign the payload `signing_payload` builds once given the identity's DID. Both are
+    /// None when there is no identity or signing fails, and the message goes out unsigned
+    async fn sign(
+        &self,
+        signing_payload: impl FnOnce(&str) -> String,
+    ) -> (Option<String>, Option<String>) {
+        let Some(ocm_protocol) = &self.identity else {
+            return (None, None);
+        };
+        let ocm = ocm_protocol.lock().await;
+        let Some(identity) = ocm.current_identity() else {
+            return (None, None);
+        };
+        match identity.sign_payload(&signing_payload(&identity.did)) {
+            Ok(signature) => (Some(identity.did.to_string()), Some(signature)),
+            Err(e) => {
+                eprintln!(
+                    "⚠️  Failed to sign discovery message, sending it unsigned: {}",
+                    e
+                );
+                (None, None)
+            }
+        }
+    }
+
+    /// Check a received beacon or response: signed by the DID it names, recent, and not
+    /// seen before. Returns the verified DID, with None for unsigned messages let through
+    /// by `allow_unsigned`; the outer None means drop the message
+    async fn authenticate(
+        &self,
+        sender: &str,
+        did: Option<&str>,
+        timestamp: &str,
+        nonce: &str,
+        signature: Option<&str>,
+        signing_payload: &str,
+    ) -> Option<Option<String>> {
+        let (Some(did), Some(signature)) = (did, signature) else {
+            if self.allow_unsigned {
+                return Some(None);
+            }
+            eprintln!("⚠️  Dropping unsigned discovery message from {}", sender);
+            return None;
+        };
+
+        let sent = chrono::DateTime::parse_from_rfc3339(timestamp).ok()?;
+        let age = chrono::Utc::now().signed_duration_since(sent);
+        if age.num_seconds() > ANNOUNCEMENT_MAX_AGE_SECS
+            || -age.num_seconds() > ANNOUNCEMENT_MAX_AHEAD_SECS
+        {
+            eprintln!("⚠️  Dropping stale discovery message from {}", sender);
+            return None;
+        }
+        if nonce.is_empty() || self.nonce_seen(nonce) {
+            eprintln!("⚠️  Dropping replayed discovery message from {}", sender);
+            return None;
+        }
+
+        let Some(ocm_protocol) = &self.identity else {
+            eprintln!(
+                "⚠️  Dropping signed discovery message from {}: no identity to verify it with",
+                sender
+            );
+            return None;
+        };
+        let verified = ocm_protocol
+            .lock()
+            .await
+            .verify_did_signature(did, signing_payload, signature)
+            .await;
+        match verified {
+            Ok(true) => {}
+            Ok(false) => {
+                eprintln!(
+                    "⚠️  Dropping discovery message from {}: bad signature for {}",
+                    sender,
+                    Redacted::did(did)
+                );
+                return None;
+            }
+            Err(e) => {
+                eprintln!(
+                    "⚠️  Dropping discovery message from {}: could not check {}: {}",
+                    sender,
+                    Redacted::did(did),
+                    e
+                );
+                return None;
+            }
+        }
+
+        // Only verified messages are remembered, so forgeries cannot use up real nonces
+        let mut seen = self.seen_nonces.lock().unwrap_or_else(|e| e.into_inner());
+        let now = chrono::Utc::now();
+        seen.retain(|_, seen_at| {
+            now.signed_duration_since(*seen_at).num_seconds()
+                < ANNOUNCEMENT_MAX_AGE_SECS + ANNOUNCEMENT_MAX_AHEAD_SECS
+        });
+        seen.insert(nonce.to_string(), now);
+        Some(Some(did.to_string()))
+    }
+
+    fn nonce_seen(&self, nonce: &str) -> bool {
+        self.seen_nonces
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .contains_key(nonce)
+    }
+
+    /// Ask a node for the peers it knows, page by page. Lists longer than
+    /// MAX_UDP_PAGED_PEERS are fetched over TCP after the first page
+    pub async fn request_peers(&self, target_addr: &str) -> Result<(), Box<dyn std::error::Error>> {
+        let socket = UdpSocket::bind("0.0.0.0:0").await?;
         let target = format!("{}:{}", target_addr, self.discovery_port);
+        let mut offset = 0;
+
+        loop {
+            let request = DiscoveryRequest {
+                requesting_peer_id: self.local_peer_id.clone(),
+                timestamp: chrono::Utc::now().to_rfc3339(),
+                offset,
+            };
+            socket
+                .send_to(&serde_json::to_vec(&request)?, &target)
+                .await?;
+            if offset == 0 {
+                println!("🔍 Requested peer list from: {}", target);
+            }
 
-        socket.send_to(&request_data, &target).await?;
-        println!("🔍 Requested peer list from: {}", target);
+            let Some(response) = self.receive_response(&socket).await else {
+                break;
+            };
+            let responder = response.responding_peer_id.clone();
+            let (total_peers, next_offset) = (response.total_peers, response.next_offset);
+            if !self.handle_discovery_response(response).await {
+                break;
+            }
+            match next_offset {
+                Some(next) if next > offset && total_peers <= MAX_UDP_PAGED_PEERS => offset = next,
+                Some(_) => {
+                    self.fetch_peers_over_tcp(&responder, total_peers).await;
+                    break;
+                }
+                None => break,
+            }
+        }
 
         Ok(())
     }
 
-    pub async fn start_periodic_discovery(&self) -> Result<(), Box<dyn std::error::Error>> {
-        let discovery_port = self.discovery_port;
-        let local_peer_id = self.local_peer_id.clone();
-        let did = self.did.clone();
-        let ocm_port = self.ocm_port;
-        let capabilities = self.capabilities.clone();
-
-        tokio::spawn(async move {
-            let mut interval = tokio::time::interval(tokio::time::Duration::from_secs(60));
-
-            loop {
-                interval.tick().await;
-
-                // Create a new discovery instance for broadcasting
-                let discovery = PeerDiscovery {
-                    local_peer_id: local_peer_id.clone(),
-                    discovery_port,
-                    ocm_port,
-                    known_peers: Arc::new(Mutex::new(HashMap::new())),
-                    capabilities: capabilities.clone(),
-                    did: did.clone(),
-                };
-
-                if let Err(e) = discovery.broadcast_beacon().await {
-                    eprintln!("Failed to broadcast discovery beacon: {}", e);
+    /// The next page of peers sent back to `socket`, taking in the responder's beacon on
+    /// the way. None if nothing arrives in time
+    async fn receive_response(&self, socket: &UdpSocket) -> Option<DiscoveryResponse> {
+        let mut buffer = vec![0u8; MAX_DATAGRAM_BYTES];
+        let deadline =
+            tokio::time::Instant::now() + tokio::time::Duration::from_secs(REPLY_TIMEOUT_SECS);
+
+        loop {
+            let (size, addr) = tokio::time::timeout_at(deadline, socket.recv_from(&mut buffer))
+                .await
+                .ok()?
+                .ok()?;
+            let data = &buffer[..size];
+
+            if let Ok(beacon) = serde_json::from_slice::<DiscoveryBeacon>(data) {
+                if beacon.peer_id != self.local_peer_id {
+                    self.handle_discovery_beacon(beacon, addr.to_string()).await;
                 }
+            } else if let Ok(response) = serde_json::from_slice::<DiscoveryResponse>(data) {
+                return Some(response);
+            }
+        }
+    }
+
+    /// Ask a node whose peer list is too long for UDP pages for all of it over P2P
+    async fn fetch_peers_over_tcp(&self, peer_id: &str, total_peers: usize) {
+        let Some(networking) = &self.networking else {
+            return;
+        };
+        let Some(peer) = self.peers.get(peer_id).await.filter(|peer| peer.port != 0) else {
+            eprintln!(
+                "Cannot fetch the peer list of {} over TCP: its endpoint is unknown",
+                peer_id
+            );
+            return;
+        };
+
+        println!(
+            "🔍 {} knows {} peers; fetching them over TCP",
+            peer_id, total_peers
+        );
+        if let Err(e) = networking.request_peer_list(&peer).await {
+            eprintln!("Failed to fetch the peer list of {}: {}", peer_id, e);
+        }
+    }
+
+    pub async fn start_periodic_discovery(
+        &self,
+        supervisor: &TaskSupervisor,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let discovery = self.clone();
+
+        supervisor.spawn("discovery_beacon", RestartPolicy::Always, move || {
+            let discovery = discovery.clone();
+            async move {
+                let mut interval = tokio::time::interval(tokio::time::Duration::from_secs(60));
+
+                loop {
+                    interval.tick().await;
+
+                    if let Err(e) = discovery.broadcast_beacon().await {
+                        eprintln!("Failed to broadcast discovery beacon: {}", e);
+                    }
 
-                println!("🔄 Periodic discovery beacon sent");
+                    println!("🔄 Periodic discovery beacon sent");
+                }
             }
         });
 
@@ -250,7 +616,7 @@ impl PeerDiscovery {
     }
 
     pub async fn get_known_peers(&self) -> Vec<PeerInfo> {
-        self.known_peers.lock().await.values().cloned().collect()
+        self.peers.list().await
     }
 
     pub async fn add_seed_peers(
@@ -286,4 +652,90 @@ impl PeerDiscovery {
 
         Ok(())
     }
+
+    /// Keep connecting to peers as they are discovered, after the initial
+    /// `connect_discovered_peers` pass. Peers that reached us by handshake are
+    /// already connected and carry no listening port
+    pub fn start_auto_connect(&self, networking: Arc<OcmNetworking>, supervisor: &TaskSupervisor) {
+        let peers = self.peers.clone();
+
+        supervisor.spawn("auto_connect", RestartPolicy::OnPanic, move || {
+            let mut events = peers.subscribe();
+            let networking = networking.clone();
+            async move {
+                loop {
+                    match events.recv().await {
+                        Ok(PeerEvent::Added(peer)) if peer.port != 0 => {
+                            if let Err(e) =
+                                networking.connect_to_peer(&peer.address, peer.port).await
+                            {
+                                eprintln!(
+                                    "Failed to connect to discovered peer {}: {}",
+                                    peer.peer_id, e
+                                );
+                            }
+                        }
+                        Ok(_) => {}
+                        Err(RecvError::Lagged(_)) => continue,
+                        Err(RecvError::Closed) => break,
+                    }
+                }
+            }
+        });
+    }
+
+    /// Register in a relay's rendezvous directory and keep the registration fresh.
+    /// While broadcast and seeds have turned up no peers, look them up there too
+    pub fn start_rendezvous(&self, rendezvous: Arc<RendezvousClient>, supervisor: &TaskSupervisor) {
+        let discovery = self.clone();
+
+        supervisor.spawn("rendezvous", RestartPolicy::Always, move || {
+            let discovery = discovery.clone();
+            let rendezvous = rendezvous.clone();
+            async move {
+                let refresh = tokio::time::Duration::from_secs(rendezvous.ttl_seconds() / 2);
+                let mut interval = tokio::time::interval(refresh);
+
+                loop {
+                    interval.tick().await;
+
+                    if let Some(did) = &discovery.did {
+                        match rendezvous.register(did, &discovery.local_peer_id).await {
+                            Ok(entry) => println!(
+                                "🧭 Registered with rendezvous directory (seen as {})",
+                                entry.observed_addr.as_deref().unwrap_or("unknown")
+                            ),
+                            Err(e) => eprintln!("Rendezvous registration failed: {}", e),
+                        }
+                    }
+
+                    if discovery.peers.list().await.is_empty() {
+                        if let Err(e) = discovery.discover_via_rendezvous(&rendezvous).await {
+                            eprintln!("Rendezvous lookup failed: {}", e);
+                        }
+                    }
+                }
+            }
+        });
+    }
+
+    /// Merge peers registered in the relay directory, returning how many were new or fresher
+    pub async fn discover_via_rendezvous(
+        &self,
+        rendezvous: &RendezvousClient,
+    ) -> Result<usize, Box<dyn std::error::Error + Send + Sync>> {
+        let found: Vec<PeerInfo> = rendezvous
+            .lookup()
+            .await?
+            .iter()
+            .filter(|entry| entry.node_id != self.local_peer_id)
+            .filter_map(entry_to_peer)
+            .collect();
+
+        let merged = self.peers.merge(found).await;
+        if merged > 0 {
+            println!("🧭 Found {} peers through the rendezvous directory", merged);
+        }
+        Ok(merged)
+    }
 }
@@ -0,0 +1,153 @@
+use crate::config::DnsSeedConfig;
+use crate::core::error::Result;
+use crate::core::redact::Redacted;
+use crate::networking::protocol::OcmNetworking;
+use ocm_protocol::seeds::{
+    parse_seed_srv, parse_seed_txt, seed_srv_name, seed_txt_name, SeedRecord,
+};
+use std::time::Duration;
+
+// DNS record type numbers, as the JSON API reports them
+const TXT: u16 = 16;
+const SRV: u16 = 33;
+
+#[derive(serde::Deserialize)]
+struct DnsJsonResponse {
+    #[serde(rename = "Answer", default)]
+    answer: Vec<DnsJsonAnswer>,
+}
+
+#[derive(serde::Deserialize)]
+struct DnsJsonAnswer {
+    #[serde(rename = "type")]
+    record_type: u16,
+    data: String,
+}
+
+/// Finds a federation's seed nodes through DNS, so a node joins by configuring the
+/// federation's domain rather than the seeds' addresses
+pub struct DnsSeeds {
+    domains: Vec<String>,
+    dns_over_https_url: String,
+    http: reqwest::Client,
+}
+
+impl DnsSeeds {
+    /// None when no domains are configured
+    pub fn new(config: &DnsSeedConfig) -> Result<Option<Self>> {
+        if config.domains.is_empty() {
+            return Ok(None);
+        }
+        let http = reqwest::Client::builder()
+            .timeout(Duration::from_secs(config.timeout_seconds))
+            .build()?;
+
+        Ok(Some(Self {
+            domains: config.domains.clone(),
+            dns_over_https_url: config.dns_over_https_url.clone(),
+            http,
+        }))
+    }
+
+    /// Seeds of every configured domain: TXT records first, as they can name a DID, then
+    /// SRV records by priority. A domain whose lookups fail is skipped
+    pub async fn resolve(&self) -> Vec<SeedRecord> {
+        let mut seeds: Vec<SeedRecord> = Vec::new();
+        for domain in &self.domains {
+            match self.lookup(&seed_txt_name(domain), "TXT", TXT).await {
+                Ok(values) => seeds.extend(values.iter().filter_map(|value| parse_seed_txt(value))),
+                Err(e) => eprintln!("⚠️  Seed TXT lookup for {} failed: {}", domain, e),
+            }
+
+            match self.lookup(&seed_srv_name(domain), "SRV", SRV).await {
+                Ok(values) => {
+                    let mut records: Vec<(u16, SeedRecord)> = values
+                        .iter()
+                        .filter_map(|value| parse_seed_srv(value))
+                        .collect();
+                    records.sort_by_key(|(priority, _)| *priority);
+                    for (_, record) in records {
+                        // Already listed with a DID in TXT
+                        if !seeds
+                            .iter()
+                            .any(|seed| seed.host == record.host && seed.port == record.port)
+                        {
+                            seeds.push(record);
+                        }
+                    }
+                }
+                Err(e) => eprintln!("⚠️  Seed SRV lookup for {} failed: {}", domain, e),
+            }
+        }
+        seeds.dedup();
+        seeds
+    }
+
+    async fn lookup(&self, name: &str, type_name: &str, record_type: u16) -> Result<Vec<String>> {
+        let response: DnsJsonResponse = self
+            .http
+            .get(&self.dns_over_https_url)
+            .query(&[("name", name), ("type", type_name)])
+            .header("accept", "application/dns-json")
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?;
+
+        // Answers can include the CNAMEs followed on the way
+        Ok(response
+            .answer
+            .into_iter()
+            .filter(|answer| answer.record_type == record_type)
+            .map(|answer| answer.data)
+            .collect())
+    }
+
+    /// Connect to each seed and ask it for the peers it knows, returning how many seeds
+    /// were reached. A seed listed with a DID that proves a different one is dropped
+    pub async fn bootstrap(&self, networking: &OcmNetworking) -> usize {
+        let mut reached = 0;
+        for seed in self.resolve().await {
+            if let Err(e) = networking.connect_to_peer(&seed.host, seed.port).await {
+                eprintln!(
+                    "Failed to connect to DNS seed {}:{}: {}",
+                    seed.host, seed.port, e
+                );
+                continue;
+            }
+            let Some(peer) = networking
+                .peers
+                .list()
+                .await
+                .into_iter()
+                .find(|peer| peer.address == seed.host && peer.port == seed.port)
+            else {
+                continue;
+            };
+
+            if let Some(expected) = &seed.did {
+                if peer.did.as_ref() != Some(expected) {
+                    eprintln!(
+                        "⚠️  DNS seed {}:{} did not prove {}; disconnecting",
+                        seed.host,
+                        seed.port,
+                        Redacted::did(expected)
+                    );
+                    networking.disconnect_peer(&peer.peer_id).await;
+                    continue;
+                }
+            }
+
+            reached += 1;
+            println!("🌱 Connected to DNS seed: {}:{}", seed.host, seed.port);
+            if let Err(e) = networking.request_peer_list(&peer).await {
+                eprintln!(
+                    "Failed to discover peers from DNS seed {}: {}",
+                    peer.peer_id, e
+                );
+            }
+        }
+        reached
+    }
+}
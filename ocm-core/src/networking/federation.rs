@@ -0,0 +1,502 @@
+use crate::config::FederationConfig;
+use crate::core::error::OcmError;
+use crate::core::ids::Did;
+use crate::core::models::Invitation;
+use crate::core::redact::Redacted;
+use crate::identity::plc::{MemoryVerification, OcmProtocol, PlcIdentity, SharedMemory};
+use crate::networking::invitations::InvitationService;
+use crate::persistence::snapshot::Snapshot;
+use crate::supervisor::{RestartPolicy, TaskSupervisor};
+use crate::sync::manager::{SyncManager, SyncRequest, SyncResponse};
+use axum::{
+    body::Bytes,
+    extract::State,
+    http::{HeaderMap, StatusCode},
+    routing::post,
+    Json, Router,
+};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use tokio::sync::Mutex;
+
+// Headers carrying the DID signature over a federation request body
+pub const DID_HEADER: &str = "x-ocm-did";
+pub const TIMESTAMP_HEADER: &str = "x-ocm-timestamp";
+pub const SIGNATURE_HEADER: &str = "x-ocm-signature";
+
+const SYNC_PATH: &str = "/federation/sync";
+const MEMORY_PATH: &str = "/federation/memory";
+const SNAPSHOT_PATH: &str = "/federation/snapshot";
+const JOIN_PATH: &str = "/federation/join";
+
+type FederationError = (StatusCode, Json<serde_json::Value>);
+
+/// Canonical string signed by the requesting institution's DID key
+pub fn request_signing_payload(method: &str, path: &str, timestamp: &str, body: &[u8]) -> String {
+    format!(
+        "{}\n{}\n{}\n{}",
+        method,
+        path,
+        timestamp,
+        hex::encode(Sha256::digest(body))
+    )
+}
+
+/// Headers an institutional client attaches to a federation request
+pub fn sign_request(
+    identity: &PlcIdentity,
+    method: &str,
+    path: &str,
+    body: &[u8],
+) -> Result<Vec<(&'static str, String)>, Box<dyn std::error::Error>> {
+    let timestamp = chrono::Utc::now().to_rfc3339();
+    let signature =
+        identity.sign_payload(&request_signing_payload(method, path, &timestamp, body))?;
+    Ok(vec![
+        (DID_HEADER, identity.did.to_string()),
+        (TIMESTAMP_HEADER, timestamp),
+        (SIGNATURE_HEADER, signature),
+    ])
+}
+
+#[derive(Clone)]
+pub struct FederationState {
+    sync_manager: Arc<SyncManager>,
+    ocm_protocol: Arc<Mutex<OcmProtocol>>,
+    max_clock_skew_seconds: i64,
+    seen_signatures: Arc<Mutex<HashMap<String, i64>>>, // signature -> unix time, for replay protection
+    invitations: Arc<InvitationService>,
+    closed: bool,
+}
+
+#[derive(Debug, serde::Deserialize)]
+struct JoinRequest {
+    code: String,
+}
+
+impl FederationState {
+    pub fn new(
+        sync_manager: Arc<SyncManager>,
+        ocm_protocol: Arc<Mutex<OcmProtocol>>,
+        invitations: Arc<InvitationService>,
+        config: &FederationConfig,
+    ) -> Self {
+        Self {
+            sync_manager,
+            ocm_protocol,
+            max_clock_skew_seconds: config.max_clock_skew_seconds as i64,
+            seen_signatures: Arc::new(Mutex::new(HashMap::new())),
+            invitations,
+            closed: config.closed,
+        }
+    }
+
+    /// Verify a request and, in a closed federation, check the DID has been admitted
+    async fn verify_member(
+        &self,
+        headers: &HeaderMap,
+        method: &str,
+        path: &str,
+        body: &[u8],
+    ) -> Result<Did, FederationError> {
+        let did = self.verify_request(headers, method, path, body).await?;
+        if !self.closed {
+            return Ok(did);
+        }
+
+        let admitted = self.invitations.admits(&did).await.map_err(|e| {
+            eprintln!("Failed to check federation membership: {}", e);
+            federation_error(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "MEMBERSHIP_CHECK_FAILED",
+                "Membership could not be checked",
+            )
+        })?;
+        if admitted {
+            Ok(did)
+        } else {
+            Err(federation_error(
+                StatusCode::FORBIDDEN,
+                "NOT_A_MEMBER",
+                "This federation is closed; join with an invite code first",
+            ))
+        }
+    }
+
+    /// Check the DID signature headers on a request and return the verified DID
+    async fn verify_request(
+        &self,
+        headers: &HeaderMap,
+        method: &str,
+        path: &str,
+        body: &[u8],
+    ) -> Result<Did, FederationError> {
+        let header = |name: &str| {
+            headers
+                .get(name)
+                .and_then(|v| v.to_str().ok())
+                .map(str::to_string)
+                .ok_or_else(|| {
+                    federation_error(
+                        StatusCode::UNAUTHORIZED,
+                        "MISSING_SIGNATURE",
+                        &format!("Missing {} header", name),
+                    )
+                })
+        };
+        let did = Did::parse(&header(DID_HEADER)?).map_err(|e| {
+            federation_error(StatusCode::BAD_REQUEST, "INVALID_DID", &e.to_string())
+        })?;
+        let timestamp = header(TIMESTAMP_HEADER)?;
+        let signature = header(SIGNATURE_HEADER)?;
+
+        let signed_at = chrono::DateTime::parse_from_rfc3339(&timestamp)
+            .map_err(|_| {
+                federation_error(
+                    StatusCode::BAD_REQUEST,
+                    "INVALID_TIMESTAMP",
+                    "Timestamp must be RFC 3339",
+                )
+            })?
+            .timestamp();
+        let now = chrono::Utc::now().timestamp();
+        if (now - signed_at).abs() > self.max_clock_skew_seconds {
+            return Err(federation_error(
+                StatusCode::UNAUTHORIZED,
+                "STALE_REQUEST",
+                "Request timestamp is outside the allowed clock skew",
+            ));
+        }
+
+        {
+            let mut seen = self.seen_signatures.lock().await;
+            let max_age = self.max_clock_skew_seconds * 2;
+            seen.retain(|_, seen_at| now - *seen_at <= max_age);
+            if seen.insert(signature.clone(), now).is_some() {
+                return Err(federation_error(
+                    StatusCode::UNAUTHORIZED,
+                    "REPLAYED_REQUEST",
+                    "Request signature has already been used",
+                ));
+            }
+        }
+
+        let payload = request_signing_payload(method, path, &timestamp, body);
+        let verified = {
+            let mut ocm = self.ocm_protocol.lock().await;
+            ocm.verify_did_signature(&did, &payload, &signature)
+                .await
+                .map_err(|e| e.to_string())
+        };
+
+        match verified {
+            Ok(true) => Ok(did),
+            Ok(false) => Err(federation_error(
+                StatusCode::UNAUTHORIZED,
+                "INVALID_SIGNATURE",
+                "Request signature does not match the DID",
+            )),
+            Err(e) => {
+                eprintln!(
+                    "Failed to verify federation request from {}: {}",
+                    Redacted::did(&did),
+                    e
+                );
+                Err(federation_error(
+                    StatusCode::BAD_GATEWAY,
+                    "DID_RESOLUTION_FAILED",
+                    "Unable to resolve the requesting DID",
+                ))
+            }
+        }
+    }
+}
+
+pub fn federation_router(state: FederationState) -> Router {
+    Router::new()
+        .route(SYNC_PATH, post(federation_sync))
+        .route(MEMORY_PATH, post(federation_memory))
+        .route(SNAPSHOT_PATH, post(federation_snapshot))
+        .route(JOIN_PATH, post(federation_join))
+        .with_state(state)
+}
+
+/// Serve the federation bridge over HTTPS in the background
+pub async fn start_federation_server(
+    config: &FederationConfig,
+    state: FederationState,
+    supervisor: &TaskSupervisor,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let _ = rustls::crypto::ring::default_provider().install_default();
+    let tls_config = axum_server::tls_rustls::RustlsConfig::from_pem_file(
+        &config.tls_cert_path,
+        &config.tls_key_path,
+    )
+    .await?;
+    let addr = SocketAddr::from(([0, 0, 0, 0], config.port));
+    let app = federation_router(state);
+
+    supervisor.spawn("federation_server", RestartPolicy::Always, move || {
+        let tls_config = tls_config.clone();
+        let app = app.clone();
+        async move {
+            if let Err(e) = axum_server::bind_rustls(addr, tls_config)
+                .serve(app.into_make_service())
+                .await
+            {
+                eprintln!("Federation HTTPS server stopped: {}", e);
+            }
+        }
+    });
+
+    println!("🏛️  Federation HTTPS bridge listening on {}", addr);
+    Ok(())
+}
+
+async fn federation_sync(
+    State(state): State<FederationState>,
+    headers: HeaderMap,
+    body: Bytes,
+) -> Result<Json<SyncResponse>, FederationError> {
+    let did = state
+        .verify_member(&headers, "POST", SYNC_PATH, &body)
+        .await?;
+    let request: SyncRequest = parse_body(&body)?;
+
+    let response = state
+        .sync_manager
+        .handle_sync_request(request, &did)
+        .await
+        .map_err(|e| e.to_string());
+
+    response.map(Json).map_err(|e| {
+        eprintln!("Federation sync failed: {}", e);
+        federation_error(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "SYNC_FAILED",
+            "Sync request could not be processed",
+        )
+    })
+}
+
+async fn federation_memory(
+    State(state): State<FederationState>,
+    headers: HeaderMap,
+    body: Bytes,
+) -> Result<StatusCode, FederationError> {
+    let did = state
+        .verify_member(&headers, "POST", MEMORY_PATH, &body)
+        .await?;
+    let shared: SharedMemory = parse_body(&body)?;
+
+    let verification = {
+        let mut ocm = state.ocm_protocol.lock().await;
+        ocm.verify_shared_memory(&shared)
+            .await
+            .map_err(|e| e.to_string())
+    };
+    let offline_key = match verification {
+        Ok(MemoryVerification::Verified) => None,
+        Ok(MemoryVerification::VerifiedOffline { public_key }) => Some(public_key),
+        Ok(MemoryVerification::Invalid) | Err(_) => {
+            return Err(federation_error(
+                StatusCode::UNPROCESSABLE_ENTITY,
+                "INVALID_MEMORY",
+                "Memory signature or hash could not be verified",
+            ))
+        }
+    };
+
+    // Hand the memory to the same CRDT merge path used for TCP sync responses
+    let response = SyncResponse {
+        responding_peer: did.to_string(),
+        memories: vec![shared.memory.clone()],
+        missing_hashes: Vec::new(),
+        headers: Vec::new(),
+    };
+    let result = state
+        .sync_manager
+        .handle_sync_response(response)
+        .await
+        .map_err(|e| e.to_string())
+        .and_then(|_| match &offline_key {
+            // Rechecked against the directory once it can be reached
+            Some(public_key) => state
+                .sync_manager
+                .database
+                .record_offline_verification(&shared.memory.id, &shared.memory.did, public_key)
+                .map_err(|e| e.to_string()),
+            None => Ok(()),
+        });
+
+    result.map(|_| StatusCode::ACCEPTED).map_err(|e| {
+        eprintln!("Federation memory ingest failed: {}", e);
+        federation_error(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "INGEST_FAILED",
+            "Memory could not be stored",
+        )
+    })
+}
+
+/// Serve a signed snapshot of the memories the requesting DID may see, for bootstrapping
+async fn federation_snapshot(
+    State(state): State<FederationState>,
+    headers: HeaderMap,
+    body: Bytes,
+) -> Result<Json<Snapshot>, FederationError> {
+    let did = state
+        .verify_member(&headers, "POST", SNAPSHOT_PATH, &body)
+        .await?;
+
+    let memories = state
+        .sync_manager
+        .database
+        .list_signed_memories()
+        .map_err(|e| e.to_string());
+    let memories = memories.map_err(|e| {
+        eprintln!("Failed to load memories for snapshot: {}", e);
+        federation_error(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "SNAPSHOT_FAILED",
+            "Snapshot could not be created",
+        )
+    })?;
+
+    // Same peer group policy as regular sync; the DID doubles as the member key
+    let peer_groups = &state.sync_manager.networking.peer_groups;
+    let mut allowed = Vec::new();
+    for memory in memories {
+        if peer_groups.allows(&did, Some(&did), &memory).await {
+            allowed.push(memory);
+        }
+    }
+
+    let ocm = state.ocm_protocol.lock().await;
+    let identity = ocm.current_identity().ok_or_else(|| {
+        federation_error(
+            StatusCode::SERVICE_UNAVAILABLE,
+            "NO_IDENTITY",
+            "This node has no identity to sign snapshots with",
+        )
+    })?;
+    let snapshot = Snapshot::from_memories(allowed, identity, &state.sync_manager.local_peer_id)
+        .map_err(|e| {
+            federation_error(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "SIGNING_FAILED",
+                &e.to_string(),
+            )
+        })?;
+
+    println!(
+        "📸 Served snapshot of {} memories to {}",
+        snapshot.manifest.memory_count,
+        Redacted::did(&did)
+    );
+    Ok(Json(snapshot))
+}
+
+/// Redeem an invite code for the DID that signed the request
+async fn federation_join(
+    State(state): State<FederationState>,
+    headers: HeaderMap,
+    body: Bytes,
+) -> Result<Json<Invitation>, FederationError> {
+    let did = state
+        .verify_request(&headers, "POST", JOIN_PATH, &body)
+        .await?;
+    let request: JoinRequest = parse_body(&body)?;
+
+    state
+        .invitations
+        .redeem(&state.ocm_protocol, &request.code, &did)
+        .await
+        .map(Json)
+        .map_err(|e| {
+            let (status, code) = match &e {
+                OcmError::NotFound(_) => (StatusCode::NOT_FOUND, "UNKNOWN_GROUP"),
+                OcmError::Validation(_) | OcmError::Serialization(_) => {
+                    (StatusCode::FORBIDDEN, "INVITATION_REJECTED")
+                }
+                OcmError::Cryptography(_) => (StatusCode::FORBIDDEN, "INVALID_INVITATION"),
+                OcmError::Plc(_) => (StatusCode::BAD_GATEWAY, "DID_RESOLUTION_FAILED"),
+                _ => (StatusCode::INTERNAL_SERVER_ERROR, "JOIN_FAILED"),
+            };
+            federation_error(status, code, &e.to_string())
+        })
+}
+
+/// Redeem an invite code at a peer's federation bridge, signing as the current identity
+pub async fn join_federation(
+    base_url: &str,
+    code: &str,
+    ocm_protocol: &Mutex<OcmProtocol>,
+) -> Result<Invitation, Box<dyn std::error::Error>> {
+    let body = serde_json::to_vec(&serde_json::json!({ "code": code }))?;
+    let signed_headers = {
+        let ocm = ocm_protocol.lock().await;
+        let identity = ocm
+            .current_identity()
+            .ok_or("No current identity to sign the join request")?;
+        sign_request(identity, "POST", JOIN_PATH, &body)?
+    };
+
+    let url = format!("{}{}", base_url.trim_end_matches('/'), JOIN_PATH);
+    let mut request = reqwest::Client::new()
+        .post(url)
+        .header("content-type", "application/json")
+        .body(body);
+    for (name, value) in signed_headers {
+        request = request.header(name, value);
+    }
+
+    let response = request.send().await?.error_for_status()?;
+    Ok(response.json::<Invitation>().await?)
+}
+
+/// Fetch a signed snapshot from a peer's federation bridge
+pub async fn fetch_snapshot(
+    base_url: &str,
+    ocm_protocol: &Mutex<OcmProtocol>,
+) -> Result<Snapshot, Box<dyn std::error::Error>> {
+    let signed_headers = {
+        let ocm = ocm_protocol.lock().await;
+        let identity = ocm
+            .current_identity()
+            .ok_or("No current identity to sign the snapshot request")?;
+        sign_request(identity, "POST", SNAPSHOT_PATH, b"")?
+    };
+
+    let url = format!("{}{}", base_url.trim_end_matches('/'), SNAPSHOT_PATH);
+    let mut request = reqwest::Client::new().post(url);
+    for (name, value) in signed_headers {
+        request = request.header(name, value);
+    }
+
+    let response = request.send().await?.error_for_status()?;
+    Ok(response.json::<Snapshot>().await?)
+}
+
+fn parse_body<T: serde::de::DeserializeOwned>(body: &[u8]) -> Result<T, FederationError> {
+    serde_json::from_slice(body).map_err(|e| {
+        federation_error(
+            StatusCode::BAD_REQUEST,
+            "INVALID_BODY",
+            &format!("Invalid request body: {}", e),
+        )
+    })
+}
+
+fn federation_error(status: StatusCode, code: &str, message: &str) -> FederationError {
+    (
+        status,
+        Json(serde_json::json!({
+            "error": code,
+            "message": message,
+            "timestamp": chrono::Utc::now().to_rfc3339(),
+        })),
+    )
+}
@@ -0,0 +1,188 @@
+use super::bandwidth::BandwidthController;
+use super::protocol::{MessageType, NetworkMessage, OcmNetworking, PeerInfo, COMPRESSION};
+use super::transport::TransportError;
+use base64::{engine::general_purpose, Engine as _};
+use ocm_protocol::fragment::{self, FragmentPayload, FrameLimits, Reassembler};
+use std::sync::RwLock;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpStream;
+
+// Smaller messages are not worth compressing
+const COMPRESSION_THRESHOLD_BYTES: usize = 16 * 1024;
+const COMPRESSION_LEVEL: i32 = 3;
+
+/// Length-prefixed JSON frames on peer connections. Large messages are compressed for
+/// peers that read compressed messages, and go out as Fragment messages when still larger
+/// than a frame and the peer advertised that it reassembles them
+pub struct Framing {
+    limits: RwLock<FrameLimits>,
+}
+
+impl Default for Framing {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Framing {
+    pub fn new() -> Self {
+        Self {
+            limits: RwLock::new(FrameLimits::default()),
+        }
+    }
+
+    pub fn configure(&self, limits: &FrameLimits) {
+        *self.limits.write().unwrap_or_else(|e| e.into_inner()) = *limits;
+    }
+
+    /// What this node accepts, as advertised in its handshake
+    pub fn limits(&self) -> FrameLimits {
+        *self.limits.read().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// A reassembler for one connection's fragments
+    pub fn reassembler(&self) -> Reassembler {
+        Reassembler::new(self.limits().max_message_bytes)
+    }
+
+    /// Write a message for `peer`, compressing it if the peer reads compressed messages
+    /// and fragmenting it if it is larger than a frame both sides accept
+    pub async fn write_message(
+        &self,
+        stream: &mut TcpStream,
+        bandwidth: &BandwidthController,
+        message: &NetworkMessage,
+        peer: Option<&PeerInfo>,
+    ) -> Result<(), TransportError> {
+        let limits = self
+            .limits()
+            .negotiate(peer.and_then(|peer| peer.frame_limits.as_ref()));
+        let mut data = serde_json::to_string(message)?;
+        if data.len() > limits.max_message_bytes {
+            return Err(format!(
+                "{:?} message of {} bytes exceeds the {} bytes the peer accepts",
+                message.message_type,
+                data.len(),
+                limits.max_message_bytes
+            )
+            .into());
+        }
+
+        if data.len() > COMPRESSION_THRESHOLD_BYTES
+            && peer.is_some_and(|peer| peer.supports(COMPRESSION))
+        {
+            let compressed = zstd::bulk::compress(data.as_bytes(), COMPRESSION_LEVEL)?;
+            let envelope = OcmNetworking::create_correlated_message(
+                MessageType::Compressed,
+                general_purpose::STANDARD.encode(compressed),
+                message.from_peer.clone(),
+                message.request_id.clone(),
+            );
+            let compressed = serde_json::to_string(&envelope)?;
+            if compressed.len() < data.len() {
+                data = compressed;
+            }
+        }
+
+        if data.len() <= limits.max_frame_bytes {
+            return write_frame(stream, bandwidth, data.as_bytes()).await;
+        }
+
+        for piece in fragment::split(&data, limits.fragment_bytes()) {
+            let envelope = OcmNetworking::create_correlated_message(
+                MessageType::Fragment,
+                serde_json::to_string(&piece)?,
+                message.from_peer.clone(),
+                message.request_id.clone(),
+            );
+            write_frame(stream, bandwidth, &serde_json::to_vec(&envelope)?).await?;
+        }
+        Ok(())
+    }
+
+    /// Read the next whole message, reassembling fragments and decompressing. None once
+    /// the peer closes the connection; frames that are not messages are skipped
+    pub async fn read_message(
+        &self,
+        stream: &mut TcpStream,
+        bandwidth: &BandwidthController,
+        reassembler: &mut Reassembler,
+    ) -> Result<Option<NetworkMessage>, TransportError> {
+        let Some(message) = self.read_whole(stream, bandwidth, reassembler).await? else {
+            return Ok(None);
+        };
+        if !matches!(message.message_type, MessageType::Compressed) {
+            return Ok(Some(message));
+        }
+
+        // Decompressing past the message limit fails rather than allocating more
+        let compressed = general_purpose::STANDARD.decode(&message.payload)?;
+        let data = zstd::bulk::decompress(&compressed, self.limits().max_message_bytes)?;
+        let inner: NetworkMessage = serde_json::from_slice(&data)?;
+        if matches!(
+            inner.message_type,
+            MessageType::Compressed | MessageType::Fragment
+        ) {
+            return Err("Compressed message wraps another envelope".into());
+        }
+        Ok(Some(inner))
+    }
+
+    async fn read_whole(
+        &self,
+        stream: &mut TcpStream,
+        bandwidth: &BandwidthController,
+        reassembler: &mut Reassembler,
+    ) -> Result<Option<NetworkMessage>, TransportError> {
+        loop {
+            let mut length_bytes = [0u8; 4];
+            if stream.read_exact(&mut length_bytes).await.is_err() {
+                return Ok(None); // Connection closed
+            }
+
+            let length = u32::from_be_bytes(length_bytes) as usize;
+            let max_frame_bytes = self.limits().max_frame_bytes;
+            if length > max_frame_bytes {
+                return Err(format!(
+                    "Frame too large: {} bytes (max: {})",
+                    length, max_frame_bytes
+                )
+                .into());
+            }
+
+            // Read the frame, within the download limit
+            bandwidth.acquire_download(length + 4).await;
+            let mut buffer = vec![0u8; length];
+            stream.read_exact(&mut buffer).await?;
+
+            let Ok(message) = serde_json::from_slice::<NetworkMessage>(&buffer) else {
+                continue;
+            };
+            if !matches!(message.message_type, MessageType::Fragment) {
+                return Ok(Some(message));
+            }
+
+            // The reassembled message carries its own HMAC and is checked like any other
+            let piece: FragmentPayload = serde_json::from_str(&message.payload)?;
+            if let Some(whole) = reassembler.push(piece)? {
+                let message: NetworkMessage = serde_json::from_str(&whole)?;
+                if matches!(message.message_type, MessageType::Fragment) {
+                    return Err("Fragmented message is itself a fragment".into());
+                }
+                return Ok(Some(message));
+            }
+        }
+    }
+}
+
+async fn write_frame(
+    stream: &mut TcpStream,
+    bandwidth: &BandwidthController,
+    data: &[u8],
+) -> Result<(), TransportError> {
+    let length = (data.len() as u32).to_be_bytes();
+    bandwidth.acquire_upload(data.len() + 4).await;
+    stream.write_all(&length).await?;
+    stream.write_all(data).await?;
+    Ok(())
+}
@@ -0,0 +1,171 @@
+use crate::config::PeerGroupConfig;
+use crate::core::ids::Did;
+use crate::core::models::{private_audience, PeerGroup, SignedMemory};
+use crate::networking::protocol::PeerInfo;
+use crate::persistence::database::Database;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::Arc;
+use tokio::sync::Mutex;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PeerGroupStats {
+    pub name: String,
+    pub member_count: usize,
+    pub connected_peers: usize,
+    pub memories_shared: u64,
+}
+
+/// Peer groups and their sync policies, cached from the database.
+/// A peer in several groups receives the union of what those groups allow
+pub struct PeerGroupRegistry {
+    database: Arc<Database>,
+    groups: Mutex<Vec<PeerGroup>>,
+    memories_shared: Mutex<HashMap<String, u64>>, // group name -> memories sent to its members
+}
+
+impl PeerGroupRegistry {
+    pub fn new(database: Arc<Database>) -> Self {
+        let groups = database.list_peer_groups().unwrap_or_else(|e| {
+            eprintln!("⚠️  Failed to load peer groups: {}", e);
+            Vec::new()
+        });
+
+        Self {
+            database,
+            groups: Mutex::new(groups),
+            memories_shared: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Create or update the groups declared in config, adding their configured members
+    pub async fn apply_config(
+        &self,
+        configs: &[PeerGroupConfig],
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        for config in configs {
+            self.database.upsert_peer_group(
+                &config.name,
+                config.memory_types.as_deref(),
+                config.tags.as_deref(),
+            )?;
+            for member in &config.members {
+                self.database.add_peer_group_member(&config.name, member)?;
+            }
+        }
+        self.reload().await
+    }
+
+    /// Assign a peer node ID or DID to an existing group
+    pub async fn assign(
+        &self,
+        group_name: &str,
+        member: &str,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        if !self
+            .groups
+            .lock()
+            .await
+            .iter()
+            .any(|g| g.name == group_name)
+        {
+            return Err(format!("Unknown peer group: {}", group_name).into());
+        }
+        self.database.add_peer_group_member(group_name, member)?;
+        self.reload().await
+    }
+
+    pub async fn unassign(
+        &self,
+        group_name: &str,
+        member: &str,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        self.database.remove_peer_group_member(group_name, member)?;
+        self.reload().await
+    }
+
+    pub async fn groups(&self) -> Vec<PeerGroup> {
+        self.groups.lock().await.clone()
+    }
+
+    /// Groups a peer belongs to, directly or through a contact group named as a member
+    pub async fn groups_for(&self, peer_id: &str, did: Option<&str>) -> Vec<PeerGroup> {
+        let contact = match did.and_then(|did| Did::parse(did).ok()) {
+            Some(did) => self.database.get_contact(&did).unwrap_or_else(|e| {
+                eprintln!("⚠️  Failed to read contact: {}", e);
+                None
+            }),
+            None => None,
+        };
+        self.groups
+            .lock()
+            .await
+            .iter()
+            .filter(|g| {
+                g.has_member(peer_id, did)
+                    || contact
+                        .as_ref()
+                        .is_some_and(|contact| g.includes_contact(contact))
+            })
+            .cloned()
+            .collect()
+    }
+
+    /// Whether a memory may be shared with a peer under its groups' policies.
+    /// Private memories only go to peers presenting a DID in their audience
+    pub async fn allows(&self, peer_id: &str, did: Option<&str>, memory: &SignedMemory) -> bool {
+        if let Some(audience) = private_audience(memory) {
+            return did.is_some_and(|did| audience.iter().any(|a| a == did));
+        }
+        let groups = self.groups_for(peer_id, did).await;
+        if groups.is_empty() {
+            return true;
+        }
+
+        let tags = if groups.iter().any(|g| g.tags.is_some()) {
+            self.database
+                .list_memory_tags(&memory.id)
+                .unwrap_or_else(|e| {
+                    eprintln!("⚠️  Failed to read tags of memory {}: {}", memory.id, e);
+                    Vec::new()
+                })
+        } else {
+            Vec::new()
+        };
+        groups
+            .iter()
+            .any(|g| g.allows_memory_type(&memory.memory_type) && g.allows_tags(memory, &tags))
+    }
+
+    pub async fn record_shared(&self, peer_id: &str, did: Option<&str>) {
+        let groups = self.groups_for(peer_id, did).await;
+        let mut shared = self.memories_shared.lock().await;
+        for group in groups {
+            *shared.entry(group.name).or_insert(0) += 1;
+        }
+    }
+
+    pub async fn statistics(&self, peers: &HashMap<String, PeerInfo>) -> Vec<PeerGroupStats> {
+        let groups = self.groups.lock().await;
+        let shared = self.memories_shared.lock().await;
+
+        groups
+            .iter()
+            .map(|group| PeerGroupStats {
+                name: group.name.clone(),
+                member_count: group.members.len(),
+                connected_peers: peers
+                    .values()
+                    .filter(|p| group.has_member(&p.peer_id, p.did.as_deref()))
+                    .count(),
+                memories_shared: shared.get(&group.name).copied().unwrap_or(0),
+            })
+            .collect()
+    }
+
+    async fn reload(&self) -> Result<(), Box<dyn std::error::Error>> {
+        let groups = self.database.list_peer_groups()?;
+        *self.groups.lock().await = groups;
+        Ok(())
+    }
+}
@@ -0,0 +1,129 @@
+use crate::core::models::SignedMemory;
+use crate::identity::plc::{MemoryVerification, OcmProtocol, SharedMemory};
+use crate::persistence::database::Database;
+use ocm_protocol::ingest::{IngestPolicy, IngestStats, RejectionReason};
+use std::sync::Arc;
+use tokio::sync::Mutex;
+
+/// What to do with a memory received from a peer
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum IngestDecision {
+    /// Store it; the key is set when it was checked against an attached chain only
+    Store {
+        offline_public_key: Option<String>,
+    },
+    Drop,
+}
+
+/// Verifies every memory a peer sends before it is stored, whichever path it takes.
+/// Tampered memories go to signed_memory_quarantine; every outcome is counted
+pub struct IngestGate {
+    policy: Mutex<IngestPolicy>,
+    stats: Mutex<IngestStats>,
+    database: Arc<Database>,
+}
+
+impl IngestGate {
+    pub fn new(database: Arc<Database>) -> Self {
+        Self {
+            policy: Mutex::new(IngestPolicy::default()),
+            stats: Mutex::new(IngestStats::default()),
+            database,
+        }
+    }
+
+    pub async fn configure(&self, policy: &IngestPolicy) {
+        *self.policy.lock().await = policy.clone();
+    }
+
+    pub async fn stats(&self) -> IngestStats {
+        self.stats.lock().await.clone()
+    }
+
+    /// Verify a memory from `source` against its author's key
+    pub async fn check(
+        &self,
+        ocm: &Mutex<OcmProtocol>,
+        shared: &SharedMemory,
+        source: &str,
+    ) -> IngestDecision {
+        if !self.policy.lock().await.verify_before_store {
+            self.stats.lock().await.unchecked += 1;
+            return IngestDecision::Store {
+                offline_public_key: None,
+            };
+        }
+
+        let memory = &shared.memory;
+        let verification = if memory.verify_hash() {
+            ocm.lock()
+                .await
+                .verify_shared_memory(shared)
+                .await
+                .map_err(|e| e.to_string())
+        } else {
+            Ok(MemoryVerification::Invalid)
+        };
+        match verification {
+            Ok(MemoryVerification::Verified) => {
+                self.stats.lock().await.verified += 1;
+                IngestDecision::Store {
+                    offline_public_key: None,
+                }
+            }
+            Ok(MemoryVerification::VerifiedOffline { public_key }) => {
+                self.stats.lock().await.verified += 1;
+                IngestDecision::Store {
+                    offline_public_key: Some(public_key),
+                }
+            }
+            Ok(MemoryVerification::Invalid) => {
+                let reason = if memory.verify_hash() {
+                    RejectionReason::BadSignature
+                } else {
+                    RejectionReason::HashMismatch
+                };
+                self.reject(memory, reason, source).await;
+                IngestDecision::Drop
+            }
+            Err(e) => {
+                eprintln!("⚠️  Could not verify memory {}: {}", memory.id, e);
+                self.reject(memory, RejectionReason::Unverifiable, source)
+                    .await;
+                IngestDecision::Drop
+            }
+        }
+    }
+
+    /// Verify a memory that arrived without a verification bundle
+    pub async fn check_memory(
+        &self,
+        ocm: &Mutex<OcmProtocol>,
+        memory: &SignedMemory,
+        source: &str,
+    ) -> IngestDecision {
+        let shared = SharedMemory {
+            memory: memory.clone(),
+            verification_bundle: None,
+        };
+        self.check(ocm, &shared, source).await
+    }
+
+    async fn reject(&self, memory: &SignedMemory, reason: RejectionReason, source: &str) {
+        self.stats.lock().await.record_rejection(reason);
+        eprintln!(
+            "❌ Rejected memory {} from {}: {}",
+            memory.id,
+            source,
+            reason.as_str()
+        );
+        if reason.is_tampered() {
+            if let Err(e) =
+                self.database
+                    .quarantine_received_memory(memory, reason.as_str(), source)
+            {
+                eprintln!("⚠️  Failed to quarantine memory {}: {}", memory.id, e);
+            }
+        }
+    }
+}
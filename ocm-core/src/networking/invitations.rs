@@ -0,0 +1,260 @@
+use crate::core::error::{OcmError, Result};
+use crate::core::ids::Did;
+use crate::core::models::{FederationMember, Invitation, InvitePayload};
+use crate::core::redact::Redacted;
+use crate::identity::plc::{OcmProtocol, PlcIdentity};
+use crate::networking::groups::PeerGroupRegistry;
+use crate::persistence::database::Database;
+use base64::{engine::general_purpose, Engine as _};
+use std::sync::Arc;
+use tokio::sync::Mutex;
+
+/// Encode an invitation as `{base64url(payload)}.{issuer signature over the payload}`
+pub fn encode_invite_code(issuer: &PlcIdentity, payload: &InvitePayload) -> Result<String> {
+    let json = serde_json::to_string(payload)?;
+    let signature = issuer.sign_payload(&json)?;
+    Ok(format!(
+        "{}.{}",
+        general_purpose::URL_SAFE_NO_PAD.encode(json.as_bytes()),
+        signature
+    ))
+}
+
+/// Split an invite code into its payload, the exact JSON that was signed, and the signature
+pub fn decode_invite_code(code: &str) -> Result<(InvitePayload, String, String)> {
+    let (encoded, signature) = code
+        .trim()
+        .split_once('.')
+        .ok_or_else(|| OcmError::Validation("Malformed invite code".to_string()))?;
+    let json = String::from_utf8(general_purpose::URL_SAFE_NO_PAD.decode(encoded)?)
+        .map_err(|_| OcmError::Validation("Malformed invite code".to_string()))?;
+    let payload: InvitePayload = serde_json::from_str(&json)?;
+    Ok((payload, json, signature.to_string()))
+}
+
+/// Signed, single-use invitations into a closed federation or a peer group.
+/// An invitation is only honoured if its issuer could itself get in, so every
+/// member traces back to a founding member through the invitation records
+pub struct InvitationService {
+    database: Arc<Database>,
+    peer_groups: Arc<PeerGroupRegistry>,
+}
+
+impl InvitationService {
+    pub fn new(database: Arc<Database>, peer_groups: Arc<PeerGroupRegistry>) -> Self {
+        Self {
+            database,
+            peer_groups,
+        }
+    }
+
+    /// Admit the DIDs a closed federation starts from
+    pub fn add_founding_members(&self, dids: &[String]) -> Result<()> {
+        for did in dids {
+            self.database.add_federation_member(&FederationMember {
+                did: Did::parse(did)?,
+                invited_by_did: None,
+                invitation_id: None,
+                joined_at: chrono::Utc::now().to_rfc3339(),
+            })?;
+        }
+        Ok(())
+    }
+
+    /// Issue an invitation signed by `issuer`, into `group_name` or the federation itself
+    pub async fn issue(
+        &self,
+        issuer: &PlcIdentity,
+        group_name: Option<&str>,
+        valid_for_hours: i64,
+    ) -> Result<(Invitation, String)> {
+        if valid_for_hours <= 0 {
+            return Err(OcmError::Validation(
+                "Invitations must be valid for at least an hour".to_string(),
+            ));
+        }
+        if let Some(group_name) = group_name {
+            self.require_group(group_name).await?;
+        }
+
+        let payload = InvitePayload {
+            invitation_id: uuid::Uuid::new_v4().to_string(),
+            issuer_did: issuer.did.clone(),
+            group_name: group_name.map(str::to_string),
+            expires_at: (chrono::Utc::now() + chrono::Duration::hours(valid_for_hours))
+                .to_rfc3339(),
+        };
+        let code = encode_invite_code(issuer, &payload)?;
+        let invitation = Invitation::from_payload(&payload);
+        self.database.record_invitation(&invitation)?;
+
+        println!(
+            "✉️  {} issued invitation {} into {}",
+            Redacted::did(&issuer.did),
+            invitation.id,
+            group_name.unwrap_or("the federation")
+        );
+        Ok((invitation, code))
+    }
+
+    /// Redeem an invite code for `did`, admitting it to the federation or peer group.
+    /// Fails if the code is forged, expired, revoked or already used
+    pub async fn redeem(
+        &self,
+        ocm_protocol: &Mutex<OcmProtocol>,
+        code: &str,
+        did: &Did,
+    ) -> Result<Invitation> {
+        let (payload, json, signature) = decode_invite_code(code)?;
+
+        let (verified, local_did) = {
+            let mut ocm = ocm_protocol.lock().await;
+            let verified = ocm
+                .verify_did_signature(&payload.issuer_did, &json, &signature)
+                .await
+                .map_err(|e| e.to_string());
+            (verified, ocm.current_identity().map(|i| i.did.clone()))
+        };
+        match verified {
+            Ok(true) => {}
+            Ok(false) => {
+                return Err(OcmError::Cryptography(
+                    "Invite code signature does not match its issuer".to_string(),
+                ))
+            }
+            Err(e) => {
+                return Err(OcmError::Plc(format!(
+                    "Unable to resolve invitation issuer: {}",
+                    e
+                )))
+            }
+        }
+
+        let invitation = Invitation::from_payload(&payload);
+        if invitation.is_expired() {
+            return Err(OcmError::Validation(format!(
+                "Invitation {} has expired",
+                invitation.id
+            )));
+        }
+        let issuer_admitted = local_did.as_deref() == Some(payload.issuer_did.as_str())
+            || match &payload.group_name {
+                Some(group_name) => {
+                    self.require_group(group_name).await?;
+                    self.is_group_member(group_name, &payload.issuer_did).await
+                }
+                None => self.is_federation_member(&payload.issuer_did)?,
+            };
+        if !issuer_admitted {
+            return Err(OcmError::Validation(format!(
+                "{} is not a member and cannot invite others",
+                payload.issuer_did
+            )));
+        }
+
+        // Codes issued on another node are first recorded here; the conditional
+        // update is what makes an invitation single-use
+        self.database.record_invitation(&invitation)?;
+        if !self.database.redeem_invitation(&invitation.id, did)? {
+            return Err(OcmError::Validation(format!(
+                "Invitation {} has already been used or was revoked",
+                invitation.id
+            )));
+        }
+
+        match &payload.group_name {
+            Some(group_name) => self
+                .peer_groups
+                .assign(group_name, did.as_str())
+                .await
+                .map_err(|e| OcmError::OperationFailed(e.to_string()))?,
+            None => self.database.add_federation_member(&FederationMember {
+                did: did.clone(),
+                invited_by_did: Some(payload.issuer_did.clone()),
+                invitation_id: Some(invitation.id.clone()),
+                joined_at: chrono::Utc::now().to_rfc3339(),
+            })?,
+        }
+
+        println!(
+            "🤝 {} joined {} on the invitation of {}",
+            Redacted::did(did.as_str()),
+            payload.group_name.as_deref().unwrap_or("the federation"),
+            Redacted::did(&payload.issuer_did)
+        );
+        Ok(self
+            .database
+            .get_invitation(&invitation.id)?
+            .unwrap_or(invitation))
+    }
+
+    pub fn revoke(&self, invitation_id: &str) -> Result<()> {
+        if !self.database.revoke_invitation(invitation_id)? {
+            return Err(OcmError::NotFound(format!(
+                "Unused invitation {}",
+                invitation_id
+            )));
+        }
+        Ok(())
+    }
+
+    /// Invitations issued or redeemed on this node: the audit of who invited whom
+    pub fn list(&self) -> Result<Vec<Invitation>> {
+        self.database.list_invitations()
+    }
+
+    /// The chain of members that led to `did` joining the federation, newest first,
+    /// ending at a founding member
+    pub fn invitation_chain(&self, did: &Did) -> Result<Vec<FederationMember>> {
+        let mut chain = Vec::new();
+        let mut next = Some(did.clone());
+        while let Some(did) = next.take() {
+            let Some(member) = self.database.get_federation_member(&did)? else {
+                break;
+            };
+            // Guard against cycles from hand-edited records
+            if chain.iter().any(|m: &FederationMember| m.did == member.did) {
+                break;
+            }
+            next = member.invited_by_did.clone();
+            chain.push(member);
+        }
+        Ok(chain)
+    }
+
+    pub fn is_federation_member(&self, did: &Did) -> Result<bool> {
+        Ok(self.database.get_federation_member(did)?.is_some())
+    }
+
+    /// Whether a DID may use a closed federation: admitted directly or through a peer group
+    pub async fn admits(&self, did: &Did) -> Result<bool> {
+        Ok(self.is_federation_member(did)?
+            || !self
+                .peer_groups
+                .groups_for(did, Some(did.as_str()))
+                .await
+                .is_empty())
+    }
+
+    async fn is_group_member(&self, group_name: &str, did: &str) -> bool {
+        self.peer_groups
+            .groups_for(did, Some(did))
+            .await
+            .iter()
+            .any(|g| g.name == group_name)
+    }
+
+    async fn require_group(&self, group_name: &str) -> Result<()> {
+        if self
+            .peer_groups
+            .groups()
+            .await
+            .iter()
+            .any(|g| g.name == group_name)
+        {
+            Ok(())
+        } else {
+            Err(OcmError::NotFound(format!("Peer group {}", group_name)))
+        }
+    }
+}
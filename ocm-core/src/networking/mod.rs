@@ -1,5 +1,23 @@
+pub mod bandwidth;
+pub mod capabilities;
+#[cfg(feature = "chaos")]
+pub mod chaos;
 pub mod discovery;
+pub mod dns_seeds;
+pub mod federation;
+pub mod framing;
+pub mod groups;
+pub mod ingest;
+pub mod invitations;
+pub mod outbox;
+pub mod peers;
+pub mod presence;
 pub mod protocol;
+pub mod rendezvous;
+pub mod reputation;
+pub mod review;
+pub mod skew;
+pub mod transport;
 
 pub use discovery::*;
 pub use protocol::*;
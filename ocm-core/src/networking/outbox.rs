@@ -0,0 +1,89 @@
+use crate::networking::protocol::OcmNetworking;
+use crate::persistence::database::Database;
+use crate::supervisor::{RestartPolicy, TaskSupervisor};
+use std::sync::Arc;
+
+const OUTBOX_DISPATCH_INTERVAL_SECS: u64 = 5;
+const OUTBOX_BATCH_SIZE: usize = 50;
+
+/// Broadcasts memories queued in the outbox until at least one peer has accepted each.
+/// Entries are only marked delivered after a successful send, so propagation is at-least-once
+pub struct OutboxDispatcher {
+    database: Arc<Database>,
+    networking: Arc<OcmNetworking>,
+}
+
+impl OutboxDispatcher {
+    pub fn new(database: Arc<Database>, networking: Arc<OcmNetworking>) -> Self {
+        Self {
+            database,
+            networking,
+        }
+    }
+
+    /// Attempt one pass over the pending entries, returning how many were delivered
+    pub async fn dispatch_pending(&self) -> Result<usize, Box<dyn std::error::Error>> {
+        let entries = self.database.list_pending_outbox(OUTBOX_BATCH_SIZE)?;
+        let mut delivered = 0;
+
+        for entry in entries {
+            let memory = match self.database.get_signed_memory(&entry.memory_id)? {
+                Some(memory) => memory,
+                None => {
+                    // Removed locally (e.g. by retention) before it went out; nothing to send
+                    self.database.mark_outbox_delivered(entry.outbox_id)?;
+                    continue;
+                }
+            };
+
+            let result = self
+                .networking
+                .broadcast_memory(&memory)
+                .await
+                .map_err(|e| e.to_string());
+
+            match result {
+                Ok(0) => {
+                    self.database
+                        .record_outbox_failure(entry.outbox_id, "No peer accepted the memory")?;
+                }
+                Ok(_) => {
+                    self.database.mark_outbox_delivered(entry.outbox_id)?;
+                    delivered += 1;
+                }
+                Err(e) => {
+                    eprintln!("Failed to broadcast outbox memory {}: {}", memory.id, e);
+                    self.database.record_outbox_failure(entry.outbox_id, &e)?;
+                }
+            }
+        }
+
+        Ok(delivered)
+    }
+
+    /// Keep dispatching the outbox in the background
+    pub fn start(self: Arc<Self>, supervisor: &TaskSupervisor) {
+        supervisor.spawn("outbox", RestartPolicy::Always, move || {
+            let dispatcher = self.clone();
+            async move {
+                let mut interval = tokio::time::interval(tokio::time::Duration::from_secs(
+                    OUTBOX_DISPATCH_INTERVAL_SECS,
+                ));
+
+                loop {
+                    interval.tick().await;
+
+                    match dispatcher
+                        .dispatch_pending()
+                        .await
+                        .map_err(|e| e.to_string())
+                    {
+                        Ok(0) => {}
+                        Ok(delivered) => println!("📬 Outbox delivered {} memories", delivered),
+                        Err(e) => eprintln!("Outbox dispatch failed: {}", e),
+                    }
+                }
+            }
+        });
+    }
+}
@@ -0,0 +1,150 @@
+use crate::networking::protocol::PeerInfo;
+use chrono::{DateTime, Utc};
+use std::collections::{HashMap, HashSet};
+use tokio::sync::{broadcast, Mutex};
+
+const EVENT_BUFFER: usize = 256; // Subscribers that fall further behind should re-list
+
+/// A change to the known peer set
+#[derive(Debug, Clone)]
+pub enum PeerEvent {
+    Added(PeerInfo),
+    Updated(PeerInfo),
+    Removed(String), // peer_id
+}
+
+#[derive(Default)]
+struct PeerSet {
+    peers: HashMap<String, PeerInfo>,
+    removed: HashMap<String, DateTime<Utc>>, // peer_id -> the sighting its removal observed
+    banned: HashSet<String>,                 // Never added, however they are sighted
+}
+
+/// The one set of known peers, shared by discovery, networking and sync.
+///
+/// Removal is observed-remove: it only erases the sightings it observed. A peer
+/// reported second-hand (discovery responses, peer lists) is ignored unless it was
+/// seen after it was removed here; a direct sighting always adds it back.
+pub struct PeerStore {
+    state: Mutex<PeerSet>,
+    events: broadcast::Sender<PeerEvent>,
+}
+
+impl Default for PeerStore {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl PeerStore {
+    pub fn new() -> Self {
+        let (events, _) = broadcast::channel(EVENT_BUFFER);
+        Self {
+            state: Mutex::new(PeerSet::default()),
+            events,
+        }
+    }
+
+    /// Receive every change from now on
+    pub fn subscribe(&self) -> broadcast::Receiver<PeerEvent> {
+        self.events.subscribe()
+    }
+
+    fn publish(&self, event: PeerEvent) {
+        let _ = self.events.send(event); // Nobody listening is fine
+    }
+
+    /// A direct sighting: handshake, beacon or outgoing connection
+    pub async fn upsert(&self, peer: PeerInfo) {
+        let mut state = self.state.lock().await;
+        if state.banned.contains(&peer.peer_id) {
+            return;
+        }
+        state.removed.remove(&peer.peer_id);
+        let event = match state.peers.insert(peer.peer_id.clone(), peer.clone()) {
+            Some(_) => PeerEvent::Updated(peer),
+            None => PeerEvent::Added(peer),
+        };
+        self.publish(event);
+    }
+
+    /// Merge peers another node reported, keeping whichever sighting is newer.
+    /// Returns how many were added or refreshed
+    pub async fn merge(&self, reported: Vec<PeerInfo>) -> usize {
+        let mut state = self.state.lock().await;
+        let mut changed = 0;
+        for peer in reported {
+            if state.banned.contains(&peer.peer_id)
+                || state
+                    .removed
+                    .get(&peer.peer_id)
+                    .is_some_and(|removed| peer.last_seen <= *removed)
+            {
+                continue;
+            }
+            let event = match state.peers.get(&peer.peer_id) {
+                Some(known) if known.last_seen >= peer.last_seen => continue,
+                Some(_) => PeerEvent::Updated(peer.clone()),
+                None => PeerEvent::Added(peer.clone()),
+            };
+            state.removed.remove(&peer.peer_id);
+            state.peers.insert(peer.peer_id.clone(), peer);
+            self.publish(event);
+            changed += 1;
+        }
+        changed
+    }
+
+    pub async fn remove(&self, peer_id: &str) -> Option<PeerInfo> {
+        let mut state = self.state.lock().await;
+        let peer = state.peers.remove(peer_id)?;
+        state.removed.insert(peer_id.to_string(), peer.last_seen);
+        self.publish(PeerEvent::Removed(peer_id.to_string()));
+        Some(peer)
+    }
+
+    /// Keep a peer out of the set, removing it if present, or let it back in
+    pub async fn set_banned(&self, peer_id: &str, banned: bool) {
+        let mut state = self.state.lock().await;
+        if !banned {
+            state.banned.remove(peer_id);
+            return;
+        }
+        state.banned.insert(peer_id.to_string());
+        if let Some(peer) = state.peers.remove(peer_id) {
+            state.removed.insert(peer_id.to_string(), peer.last_seen);
+            self.publish(PeerEvent::Removed(peer_id.to_string()));
+        }
+    }
+
+    pub async fn is_banned(&self, peer_id: &str) -> bool {
+        self.state.lock().await.banned.contains(peer_id)
+    }
+
+    /// Refresh a peer's last_seen; too frequent to be worth an event
+    pub async fn touch(&self, peer_id: &str) {
+        if let Some(peer) = self.state.lock().await.peers.get_mut(peer_id) {
+            peer.last_seen = Utc::now();
+        }
+    }
+
+    pub async fn get(&self, peer_id: &str) -> Option<PeerInfo> {
+        self.state.lock().await.peers.get(peer_id).cloned()
+    }
+
+    pub async fn list(&self) -> Vec<PeerInfo> {
+        self.state.lock().await.peers.values().cloned().collect()
+    }
+
+    /// Peers most recently seen first, for sharing a bounded peer list
+    pub async fn freshest(&self) -> Vec<PeerInfo> {
+        let mut peers = self.list().await;
+        peers.sort_by_key(|p| std::cmp::Reverse(p.last_seen));
+        peers
+    }
+
+    /// The peer set keyed by peer ID, e.g. for group statistics
+    pub async fn snapshot(&self) -> HashMap<String, PeerInfo> {
+        self.state.lock().await.peers.clone()
+    }
+}
@@ -0,0 +1,113 @@
+use crate::config::PresenceConfig;
+use crate::core::ids::{Did, PeerId};
+use crate::core::models::{PeerPresence, PresenceStatus, PresenceUpdate};
+use crate::networking::groups::PeerGroupRegistry;
+use crate::persistence::database::Database;
+use std::sync::Arc;
+use tokio::sync::Mutex;
+
+/// Who may see this node's presence and what it reveals, plus the presence
+/// heard from peers. Presence only flows between members of trusted peer groups
+pub struct PresenceTracker {
+    config: Mutex<PresenceConfig>,
+    database: Arc<Database>,
+    peer_groups: Arc<PeerGroupRegistry>,
+}
+
+impl PresenceTracker {
+    pub fn new(database: Arc<Database>, peer_groups: Arc<PeerGroupRegistry>) -> Self {
+        Self {
+            config: Mutex::new(PresenceConfig::default()),
+            database,
+            peer_groups,
+        }
+    }
+
+    pub async fn configure(&self, config: &PresenceConfig) {
+        *self.config.lock().await = config.clone();
+    }
+
+    pub async fn interval(&self) -> std::time::Duration {
+        std::time::Duration::from_secs(self.config.lock().await.interval_seconds)
+    }
+
+    /// Whether presence is exchanged with a peer: it must share one of the
+    /// configured peer groups with us, or any group if none are configured
+    pub async fn shares_with(&self, peer_id: &str, did: Option<&str>) -> bool {
+        let config = self.config.lock().await.clone();
+        if !config.enabled {
+            return false;
+        }
+        self.peer_groups
+            .groups_for(peer_id, did)
+            .await
+            .iter()
+            .any(|group| config.groups.is_empty() || config.groups.contains(&group.name))
+    }
+
+    /// This node's announcement, leaving out what it is configured not to share
+    pub async fn local_update(
+        &self,
+        device_id: &str,
+        did: Option<&str>,
+        syncing: bool,
+        last_sync_at: Option<chrono::DateTime<chrono::Utc>>,
+    ) -> Option<PresenceUpdate> {
+        let config = self.config.lock().await;
+        if !config.enabled {
+            return None;
+        }
+        Some(PresenceUpdate {
+            device_id: device_id.to_string(),
+            did: did.map(str::to_string),
+            status: config.share_status.then_some(if syncing {
+                PresenceStatus::Syncing
+            } else {
+                PresenceStatus::Idle
+            }),
+            last_sync_at: last_sync_at
+                .filter(|_| config.share_last_sync)
+                .map(|at| at.to_rfc3339()),
+            sent_at: chrono::Utc::now().to_rfc3339(),
+        })
+    }
+
+    /// Record an announcement from a peer. The DID is the one the peer proved at
+    /// handshake, never the one it claims in the announcement
+    pub async fn observe(
+        &self,
+        peer_id: &str,
+        did: Option<&str>,
+        update: &PresenceUpdate,
+    ) -> Result<bool, Box<dyn std::error::Error>> {
+        if !self.shares_with(peer_id, did).await {
+            return Ok(false);
+        }
+        let did = did.map(Did::parse).transpose()?;
+        self.database.record_peer_presence(
+            &PeerId::parse(peer_id)?,
+            did.as_ref(),
+            update,
+            &chrono::Utc::now().to_rfc3339(),
+        )?;
+        Ok(true)
+    }
+
+    /// Presence of the peers heard from, online ones marked as such
+    pub async fn list(&self) -> Vec<PeerPresence> {
+        let offline_after = self.config.lock().await.offline_after_seconds;
+        list_presence(&self.database, offline_after).unwrap_or_else(|e| {
+            eprintln!("⚠️  Failed to read peer presence: {}", e);
+            Vec::new()
+        })
+    }
+}
+
+/// Peer presence as recorded in the database, for readers without a running node
+pub fn list_presence(
+    database: &Database,
+    offline_after_seconds: u64,
+) -> crate::core::error::Result<Vec<PeerPresence>> {
+    let online_since = chrono::Utc::now() - chrono::Duration::seconds(offline_after_seconds as i64);
+    database.list_peer_presence(&online_since.to_rfc3339())
+}
@@ -1,69 +1,88 @@
-use crate::core::models::SignedMemory;
-use crate::identity::plc::OcmProtocol;
+use crate::core::correlation::{is_valid_request_id, new_request_id};
+use crate::core::error;
+use crate::core::ids::{MemoryId, PeerId};
+use crate::core::models::{PeerRecord, PeerStanding, PresenceUpdate, SignedMemory, WitnessReceipt};
+use crate::core::redact::Redacted;
+use crate::identity::plc::{OcmProtocol, SharedMemory};
+use crate::networking::bandwidth::BandwidthController;
+use crate::networking::capabilities::LocalCapabilities;
+use crate::networking::framing::Framing;
+use crate::networking::groups::PeerGroupRegistry;
+use crate::networking::ingest::{IngestDecision, IngestGate};
+use crate::networking::peers::{PeerEvent, PeerStore};
+use crate::networking::presence::PresenceTracker;
+use crate::networking::reputation::PeerReputation;
+use crate::networking::review::InboundReview;
+use crate::networking::skew::ClockSkewTracker;
+use crate::networking::transport::{Delivery, TcpTransport, Transport};
+use crate::panics::catch_panic_async;
 use crate::persistence::database::Database;
+use crate::persistence::transparency::{
+    ConsistencyProof, SignedTreeHead, TransparencyLog, TreeHeadMonitor, TreeHeadStatus,
+};
+use crate::supervisor::{RestartPolicy, TaskSupervisor};
 use base64::{engine::general_purpose, Engine as _};
 use hmac::{Hmac, Mac};
-use serde::{Deserialize, Serialize};
 use sha2::Sha256;
 use std::collections::HashMap;
 use std::sync::Arc;
 use std::time::{SystemTime, UNIX_EPOCH};
-use tokio::io::{AsyncReadExt, AsyncWriteExt};
 use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::broadcast::error::RecvError;
 use tokio::sync::{mpsc, Mutex};
+use tracing::Instrument;
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct NetworkMessage {
-    pub message_type: MessageType,
-    pub payload: String,
-    pub from_peer: String,
-    pub timestamp: String,
-    pub nonce: String, // Unique nonce for replay protection
-    pub hmac: String,  // HMAC for message authentication
-}
+pub use ocm_protocol::fragment::FrameLimits;
+pub use ocm_protocol::message::{
+    ConsistencyProofRequest, HandshakePayload, MemoryBodyRequest, MessageType, NetworkMessage,
+    NodeRole, NotarizationRequest, PeerInfo, COMPRESSION, IDENTITY_VERIFICATION, INLINE_REPLIES,
+    MEMORY_HEADERS, MEMORY_SYNC, NOTARIZATION, PEER_CAPABILITIES, PEER_DISCOVERY,
+    PEER_PROTOCOL_VERSION, PRESENCE, RELAY,
+};
 
 // Constants for message security and rate limiting
-const MAX_MESSAGE_SIZE: usize = 1024 * 1024; // 1MB max message size
 const MESSAGE_TIMEOUT_SECS: u64 = 300; // 5 minutes
+const UNCORRECTED_SKEW_SECS: u64 = 600; // Extra slack either way for peers whose clock offset is unknown
+const MAX_FUTURE_SECS: i64 = 30; // How far ahead a skew-corrected timestamp may be
+const HANDSHAKE_ACK_TIMEOUT_SECS: u64 = 10;
 const NETWORK_SHARED_SECRET: &[u8] = b"ocm-network-secret-change-in-production"; // TODO: Use proper key exchange
 
 // Rate limiting constants
 const MAX_MESSAGES_PER_MINUTE: u32 = 60;
 const MAX_CONNECTIONS_PER_IP: u32 = 5;
 const RATE_LIMIT_WINDOW_SECS: u64 = 60;
+const MAX_SHARED_PEERS: usize = 1000; // Freshest peers sent in answer to a peer list request
 
 type HmacSha256 = Hmac<Sha256>;
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub enum MessageType {
-    Handshake,
-    MemorySync,
-    MemoryRequest,
-    PeerDiscovery,
-    Ping,
-    Pong,
-}
-
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct PeerInfo {
-    pub peer_id: String,
-    pub address: String,
-    pub port: u16,
-    pub last_seen: chrono::DateTime<chrono::Utc>,
-    pub did: Option<String>,
-}
-
 pub struct OcmNetworking {
     pub local_peer_id: String,
     pub port: u16,
-    pub peers: Arc<Mutex<HashMap<String, PeerInfo>>>,
+    pub peers: Arc<PeerStore>, // Shared with discovery and sync
     pub ocm_protocol: Arc<Mutex<OcmProtocol>>,
     pub database: Arc<Database>,
+    pub peer_groups: Arc<PeerGroupRegistry>,
+    pub presence: Arc<PresenceTracker>,
+    pub review: Arc<InboundReview>, // Holds memories from untrusted peers when enabled
+    pub ingest: Arc<IngestGate>,    // Verifies received memories before anything stores them
+    pub reputation: Arc<PeerReputation>, // Traffic, bans and trust per peer
+    pub bandwidth: Arc<BandwidthController>,
+    pub framing: Arc<Framing>, // Frame limits and fragmentation of large messages
+    pub capabilities: Arc<LocalCapabilities>, // What this node advertises to peers
+    pub clock_skew: Arc<ClockSkewTracker>,
+    transport: Arc<dyn Transport>,
     pub message_sender: mpsc::UnboundedSender<NetworkMessage>,
     pub message_receiver: Arc<Mutex<mpsc::UnboundedReceiver<NetworkMessage>>>,
     message_nonces: Arc<Mutex<HashMap<String, u64>>>, // nonce -> timestamp for replay protection
     rate_limiter: Arc<Mutex<RateLimiter>>,            // Rate limiting per IP
     connection_tracker: Arc<Mutex<HashMap<String, u32>>>, // IP -> active connection count
+    tree_head_monitor: Arc<Mutex<TreeHeadMonitor>>,   // DID -> latest gossiped tree head
+}
+
+/// Where the responses to one incoming message go
+struct Replies {
+    inline: bool, // The requester reads responses on the connection its request came in on
+    queued: Vec<NetworkMessage>,
 }
 
 #[derive(Debug)]
@@ -159,23 +178,56 @@ impl RateLimiter {
 
 impl OcmNetworking {
     pub fn new(port: u16, ocm_protocol: OcmProtocol, database: Arc<Database>) -> Self {
-        let local_peer_id = uuid::Uuid::new_v4().to_string();
+        // Stable across restarts so peers recognise this node and keep their sync state
+        let local_peer_id = database.get_or_create_node_id().unwrap_or_else(|e| {
+            eprintln!("⚠️  Failed to load node ID, using an ephemeral one: {}", e);
+            uuid::Uuid::new_v4().to_string()
+        });
         let (message_sender, message_receiver) = mpsc::unbounded_channel();
+        let bandwidth = Arc::new(BandwidthController::new());
+        let framing = Arc::new(Framing::new());
+        let peer_groups = Arc::new(PeerGroupRegistry::new(database.clone()));
+        let presence = Arc::new(PresenceTracker::new(database.clone(), peer_groups.clone()));
+        let review = Arc::new(InboundReview::new(database.clone(), peer_groups.clone()));
+        let ingest = Arc::new(IngestGate::new(database.clone()));
+        let peers = Arc::new(PeerStore::new());
+        let reputation = Arc::new(PeerReputation::new(database.clone(), peers.clone()));
 
         OcmNetworking {
             local_peer_id,
             port,
-            peers: Arc::new(Mutex::new(HashMap::new())),
+            peers,
             ocm_protocol: Arc::new(Mutex::new(ocm_protocol)),
+            peer_groups,
+            presence,
+            review,
+            ingest,
+            reputation,
+            transport: Arc::new(TcpTransport::new(bandwidth.clone(), framing.clone())),
+            bandwidth,
+            framing,
+            capabilities: Arc::new(LocalCapabilities::new()),
+            clock_skew: Arc::new(ClockSkewTracker::new()),
             database,
             message_sender,
             message_receiver: Arc::new(Mutex::new(message_receiver)),
             message_nonces: Arc::new(Mutex::new(HashMap::new())),
             rate_limiter: Arc::new(Mutex::new(RateLimiter::new())),
             connection_tracker: Arc::new(Mutex::new(HashMap::new())),
+            tree_head_monitor: Arc::new(Mutex::new(TreeHeadMonitor::new())),
         }
     }
 
+    pub fn transport(&self) -> Arc<dyn Transport> {
+        self.transport.clone()
+    }
+
+    /// Send peer messages over something other than TCP, e.g. a simulated network
+    pub fn with_transport(mut self, transport: Arc<dyn Transport>) -> Self {
+        self.transport = transport;
+        self
+    }
+
     // Input validation methods
     fn validate_message(&self, message: &NetworkMessage) -> Result<(), String> {
         // Validate peer_id format (UUID)
@@ -183,8 +235,8 @@ impl OcmNetworking {
             return Err("Invalid peer ID format".to_string());
         }
 
-        // Validate payload size
-        if message.payload.len() > MAX_MESSAGE_SIZE {
+        // Validate payload size; larger messages arrive in fragments
+        if message.payload.len() > self.framing.limits().max_message_bytes {
             return Err(format!(
                 "Payload too large: {} bytes",
                 message.payload.len()
@@ -204,6 +256,11 @@ impl OcmNetworking {
             return Err("Invalid nonce format".to_string());
         }
 
+        // Validate correlation ID (older peers send none)
+        if !message.request_id.is_empty() && !is_valid_request_id(&message.request_id) {
+            return Err("Invalid request ID format".to_string());
+        }
+
         // Validate HMAC format
         if base64::engine::general_purpose::STANDARD
             .decode(&message.hmac)
@@ -250,6 +307,16 @@ impl OcmNetworking {
         message_type: MessageType,
         payload: String,
         from_peer: String,
+    ) -> NetworkMessage {
+        Self::create_correlated_message(message_type, payload, from_peer, new_request_id())
+    }
+
+    /// Authenticated message that continues an existing request's correlation ID
+    pub fn create_correlated_message(
+        message_type: MessageType,
+        payload: String,
+        from_peer: String,
+        request_id: String,
     ) -> NetworkMessage {
         use rand::RngCore;
         let mut rng = rand::rngs::OsRng;
@@ -266,6 +333,7 @@ impl OcmNetworking {
             timestamp,
             nonce,
             hmac: String::new(), // Will be calculated below
+            request_id,
         };
 
         // Calculate HMAC over the message content (excluding the hmac field)
@@ -294,13 +362,26 @@ impl OcmNetworking {
         &self,
         message: &NetworkMessage,
     ) -> Result<bool, Box<dyn std::error::Error>> {
-        // Check message timestamp (prevent old message replay)
-        let message_time = chrono::DateTime::parse_from_rfc3339(&message.timestamp)?;
-        let now = chrono::Utc::now();
-        let age = now.signed_duration_since(message_time.with_timezone(&chrono::Utc));
-
-        if age.num_seconds() > MESSAGE_TIMEOUT_SECS as i64 {
-            return Ok(false); // Message too old
+        // Check message timestamp (prevent old message replay), on our clock when the
+        // sender's offset is known and with extra slack either way when it is not
+        let message_time =
+            chrono::DateTime::parse_from_rfc3339(&message.timestamp)?.with_timezone(&chrono::Utc);
+        let (age, max_age, max_ahead) =
+            match self.clock_skew.to_local(&message.from_peer, message_time) {
+                Some(local_time) => (
+                    chrono::Utc::now().signed_duration_since(local_time),
+                    MESSAGE_TIMEOUT_SECS as i64,
+                    MAX_FUTURE_SECS,
+                ),
+                None => (
+                    chrono::Utc::now().signed_duration_since(message_time),
+                    (MESSAGE_TIMEOUT_SECS + UNCORRECTED_SKEW_SECS) as i64,
+                    UNCORRECTED_SKEW_SECS as i64,
+                ),
+            };
+
+        if age.num_seconds() > max_age || -age.num_seconds() > max_ahead {
+            return Ok(false); // Message too old, or from too far in the future
         }
 
         // Calculate expected HMAC
@@ -331,7 +412,10 @@ impl OcmNetworking {
             .duration_since(UNIX_EPOCH)
             .unwrap()
             .as_secs();
-        nonces.retain(|_, &mut timestamp| (now - timestamp) < MESSAGE_TIMEOUT_SECS);
+        // Keep nonces for as long as the widest freshness window accepts their message
+        nonces.retain(|_, &mut timestamp| {
+            (now - timestamp) < MESSAGE_TIMEOUT_SECS + 2 * UNCORRECTED_SKEW_SECS
+        });
 
         // Check if nonce already exists
         if nonces.contains_key(&message.nonce) {
@@ -343,44 +427,75 @@ impl OcmNetworking {
         true
     }
 
-    pub async fn start_server(&self) -> Result<(), Box<dyn std::error::Error>> {
-        let addr = format!("127.0.0.1:{}", self.port);
-        let listener = TcpListener::bind(&addr).await?;
-        println!("OCM node listening on: {}", addr);
-
-        let _peers = self.peers.clone();
-        let _ocm_protocol = self.ocm_protocol.clone();
-        let _database = self.database.clone();
-        let _message_sender = self.message_sender.clone();
-        let self_clone = Arc::new(Self {
+    /// A second handle on the same shared state, for background tasks
+    fn clone_handles(&self) -> Self {
+        Self {
             local_peer_id: self.local_peer_id.clone(),
             port: self.port,
             peers: self.peers.clone(),
             ocm_protocol: self.ocm_protocol.clone(),
             database: self.database.clone(),
+            peer_groups: self.peer_groups.clone(),
+            presence: self.presence.clone(),
+            review: self.review.clone(),
+            ingest: self.ingest.clone(),
+            reputation: self.reputation.clone(),
+            bandwidth: self.bandwidth.clone(),
+            framing: self.framing.clone(),
+            capabilities: self.capabilities.clone(),
+            clock_skew: self.clock_skew.clone(),
+            transport: self.transport.clone(),
             message_sender: self.message_sender.clone(),
             message_receiver: self.message_receiver.clone(),
             message_nonces: self.message_nonces.clone(),
             rate_limiter: self.rate_limiter.clone(),
             connection_tracker: self.connection_tracker.clone(),
+            tree_head_monitor: self.tree_head_monitor.clone(),
+        }
+    }
+
+    pub async fn start_server(
+        &self,
+        supervisor: &TaskSupervisor,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let addr = format!("127.0.0.1:{}", self.port);
+        let listener = Arc::new(TcpListener::bind(&addr).await?);
+        println!("OCM node listening on: {}", addr);
+
+        let _ocm_protocol = self.ocm_protocol.clone();
+        let _database = self.database.clone();
+        let _message_sender = self.message_sender.clone();
+        let self_clone = Arc::new(self.clone_handles());
+
+        let replies_self = self_clone.clone();
+        supervisor.spawn("replies", RestartPolicy::OnPanic, move || {
+            let replies_self = replies_self.clone();
+            async move {
+                replies_self.process_replies().await;
+            }
         });
 
-        tokio::spawn(async move {
-            loop {
-                match listener.accept().await {
-                    Ok((stream, addr)) => {
-                        let self_for_task = self_clone.clone();
-
-                        tokio::spawn(async move {
-                            if let Err(e) = self_for_task
-                                .handle_connection(stream, addr.to_string())
-                                .await
-                            {
-                                eprintln!("Error handling connection: {}", e);
-                            }
-                        });
+        supervisor.spawn("p2p_listener", RestartPolicy::Always, move || {
+            let listener = listener.clone();
+            let self_clone = self_clone.clone();
+            async move {
+                loop {
+                    match listener.accept().await {
+                        Ok((stream, addr)) => {
+                            let self_for_task = self_clone.clone();
+
+                            tokio::spawn(async move {
+                                let connection =
+                                    self_for_task.handle_connection(stream, addr.to_string());
+                                if let Err(e) =
+                                    catch_panic_async("P2P connection", connection).await
+                                {
+                                    eprintln!("Error handling connection: {}", e);
+                                }
+                            });
+                        }
+                        Err(e) => eprintln!("Failed to accept connection: {}", e),
                     }
-                    Err(e) => eprintln!("Failed to accept connection: {}", e),
                 }
             }
         });
@@ -409,142 +524,348 @@ impl OcmNetworking {
         // Ensure connection cleanup on drop
         let _connection_guard =
             ConnectionGuard::new(self.connection_tracker.clone(), peer_ip.clone());
-        // Use dynamic buffer with size limit
-        let mut buffer = Vec::new();
-        let mut length_bytes = [0u8; 4];
+        let mut reassembler = self.framing.reassembler();
 
         loop {
-            // Read message length first (4-byte big-endian)
-            if stream.read_exact(&mut length_bytes).await.is_err() {
-                break; // Connection closed
+            let message = match self
+                .framing
+                .read_message(&mut stream, &self.bandwidth, &mut reassembler)
+                .await
+            {
+                Ok(Some(message)) => message,
+                Ok(None) => break, // Connection closed
+                Err(e) => {
+                    eprintln!("Dropping connection from {}: {}", peer_addr, e);
+                    break;
+                }
+            };
+
+            // Check rate limit before processing
+            if let Err(e) = self.check_rate_limit(&peer_ip).await {
+                eprintln!("Rate limit exceeded: {}", e);
+                continue;
             }
 
-            let message_length = u32::from_be_bytes(length_bytes) as usize;
+            // Validate message format
+            if let Err(e) = self.validate_message(&message) {
+                eprintln!("Message validation failed: {}", e);
+                self.reputation.record_rejected(&message.from_peer);
+                continue;
+            }
 
-            // Enforce message size limit
-            if message_length > MAX_MESSAGE_SIZE {
-                eprintln!(
-                    "Message too large: {} bytes (max: {})",
-                    message_length, MAX_MESSAGE_SIZE
-                );
-                break;
+            // Verify message authentication
+            if !self.verify_message_authentication(&message)? {
+                eprintln!("Message authentication failed from: {}", peer_addr);
+                self.reputation.record_rejected(&message.from_peer);
+                continue;
             }
 
-            // Read the actual message
-            buffer.resize(message_length, 0);
-            stream.read_exact(&mut buffer).await?;
+            // Check replay protection
+            if !self.check_replay_protection(&message).await {
+                eprintln!("Replay attack detected from: {}", peer_addr);
+                self.reputation.record_rejected(&message.from_peer);
+                continue;
+            }
 
-            if let Ok(message) = serde_json::from_slice::<NetworkMessage>(&buffer) {
-                // Check rate limit before processing
-                if let Err(e) = self.check_rate_limit(&peer_ip).await {
-                    eprintln!("Rate limit exceeded: {}", e);
-                    continue;
-                }
+            // Banned peers get no answer
+            if self.reputation.is_banned(&message.from_peer) {
+                eprintln!("Dropping message from banned peer: {}", message.from_peer);
+                continue;
+            }
+            self.reputation
+                .record_received(&message.from_peer, message.payload.len());
+
+            let request_id = if message.request_id.is_empty() {
+                new_request_id()
+            } else {
+                message.request_id.clone()
+            };
+            let span = tracing::info_span!(
+                "network_message",
+                request_id = %request_id,
+                message_type = ?message.message_type,
+                from_peer = %message.from_peer,
+            );
+            let from_peer = message.from_peer.clone();
+            let is_handshake = matches!(message.message_type, MessageType::Handshake);
+            let requester = self.peers.get(&from_peer).await;
+            let mut replies = Replies {
+                inline: requester
+                    .as_ref()
+                    .is_some_and(|peer| peer.supports(INLINE_REPLIES)),
+                queued: Vec::new(),
+            };
+            if let Err(e) = self
+                .process_message(message, &peer_addr, &mut replies)
+                .instrument(span)
+                .await
+            {
+                return Err(format!("{} (request {})", e, request_id).into());
+            }
 
-                // Validate message format
-                if let Err(e) = self.validate_message(&message) {
-                    eprintln!("Message validation failed: {}", e);
-                    continue;
+            // Responses go back on this connection, ahead of the acknowledgment
+            let mut queued = replies.queued.into_iter();
+            while let Some(reply) = queued.next() {
+                let written = self
+                    .write_message(&mut stream, &reply, requester.as_ref())
+                    .await
+                    .map_err(|e| e.to_string());
+                if let Err(e) = written {
+                    eprintln!(
+                        "Connection from {} closed before its responses were sent: {}",
+                        from_peer, e
+                    );
+                    self.dial_back(&from_peer, std::iter::once(reply).chain(queued))
+                        .await;
+                    return Ok(());
                 }
+            }
 
-                // Verify message authentication
-                if !self.verify_message_authentication(&message)? {
-                    eprintln!("Message authentication failed from: {}", peer_addr);
-                    continue;
-                }
+            // Send authenticated acknowledgment. A handshake is answered with this node's
+            // own, so the dialing side learns its capabilities and frame limits too
+            let ack_payload = if is_handshake {
+                serde_json::to_string(&self.handshake_payload().await)?
+            } else {
+                "ack".to_string()
+            };
+            let ack = Self::create_correlated_message(
+                MessageType::Pong,
+                ack_payload,
+                self.local_peer_id.clone(),
+                request_id,
+            );
+            self.write_message(&mut stream, &ack, requester.as_ref())
+                .await?;
+        }
 
-                // Check replay protection
-                if !self.check_replay_protection(&message).await {
-                    eprintln!("Replay attack detected from: {}", peer_addr);
-                    continue;
-                }
+        Ok(())
+    }
 
-                self.process_message(message, &peer_addr).await?;
+    /// Write one message for `peer`, within the upload limit
+    async fn write_message(
+        &self,
+        stream: &mut TcpStream,
+        message: &NetworkMessage,
+        peer: Option<&PeerInfo>,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        self.framing
+            .write_message(stream, &self.bandwidth, message, peer)
+            .await
+            .map_err(|e| e as Box<dyn std::error::Error>)
+    }
 
-                // Send authenticated acknowledgment
-                let ack = Self::create_authenticated_message(
-                    MessageType::Pong,
-                    "ack".to_string(),
-                    self.local_peer_id.clone(),
-                );
-                let ack_data = serde_json::to_vec(&ack)?;
-                let ack_length = (ack_data.len() as u32).to_be_bytes();
-                stream.write_all(&ack_length).await?;
-                stream.write_all(&ack_data).await?;
+    /// Send responses by dialing the requester, once the connection its request came
+    /// in on is gone
+    async fn dial_back(&self, peer_id: &str, replies: impl Iterator<Item = NetworkMessage>) {
+        let Some(peer) = self.peers.get(peer_id).await else {
+            eprintln!("Dropping responses to unknown peer: {}", peer_id);
+            return;
+        };
+        for reply in replies {
+            if let Err(e) = self.send_message_to_peer(&peer, &reply).await {
+                eprintln!("Failed to send response to peer {}: {}", peer_id, e);
+                break;
             }
         }
+    }
 
-        Ok(())
+    /// Answer a request inline when the requester reads responses on its connection,
+    /// otherwise by dialing it back
+    async fn respond(
+        &self,
+        peer: &PeerInfo,
+        reply: NetworkMessage,
+        replies: &mut Replies,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        if replies.inline {
+            replies.queued.push(reply);
+            Ok(())
+        } else {
+            self.send_message_to_peer(peer, &reply).await
+        }
+    }
+
+    /// Handle responses peers sent back on the connections our requests went out on.
+    /// Runs for as long as the server does
+    async fn process_replies(&self) {
+        let mut receiver = self.message_receiver.lock().await;
+        while let Some(reply) = receiver.recv().await {
+            if let Err(e) = self.validate_message(&reply) {
+                eprintln!("Response validation failed: {}", e);
+                continue;
+            }
+            if !matches!(self.verify_message_authentication(&reply), Ok(true)) {
+                eprintln!("Response authentication failed from: {}", reply.from_peer);
+                continue;
+            }
+            if !self.check_replay_protection(&reply).await {
+                eprintln!("Replayed response from: {}", reply.from_peer);
+                continue;
+            }
+
+            let peer_addr = match self.peers.get(&reply.from_peer).await {
+                Some(peer) => format!("{}:{}", peer.address, peer.port),
+                None => reply.from_peer.clone(),
+            };
+            let span = tracing::info_span!(
+                "network_reply",
+                request_id = %reply.request_id,
+                message_type = ?reply.message_type,
+                from_peer = %reply.from_peer,
+            );
+            let mut replies = Replies {
+                inline: false,
+                queued: Vec::new(),
+            };
+            if let Err(e) = self
+                .process_message(reply, &peer_addr, &mut replies)
+                .instrument(span)
+                .await
+            {
+                eprintln!("Failed to handle response: {}", e);
+            }
+        }
     }
 
     async fn process_message(
         &self,
         message: NetworkMessage,
         peer_addr: &str,
+        replies: &mut Replies,
     ) -> Result<(), Box<dyn std::error::Error>> {
         match message.message_type {
             MessageType::Handshake => {
+                let handshake: HandshakePayload = serde_json::from_str(&message.payload)?;
+                if handshake.peer_id != message.from_peer {
+                    return Err("Handshake peer ID does not match its sender".into());
+                }
+                let did = self.verify_handshake_did(&handshake).await;
+                // The connection comes from an ephemeral port; replies go to the listener
+                let host = peer_addr
+                    .rsplit_once(':')
+                    .map_or(peer_addr, |(host, _)| host);
                 let peer_info = PeerInfo {
                     peer_id: message.from_peer.clone(),
-                    address: peer_addr.to_string(),
-                    port: 0, // Will be updated from handshake payload
+                    address: host.to_string(),
+                    port: handshake.listen_port,
                     last_seen: chrono::Utc::now(),
-                    did: None,
+                    did,
+                    capabilities: handshake.capabilities,
+                    protocol_version: handshake.protocol_version,
+                    frame_limits: handshake.frame_limits,
+                    role: handshake.role,
                 };
-                self.peers
-                    .lock()
-                    .await
-                    .insert(message.from_peer.clone(), peer_info);
-                println!("Handshake received from peer: {}", message.from_peer);
+                self.peers.upsert(peer_info).await;
+                println!(
+                    "Handshake received from peer: {} (listening on {})",
+                    message.from_peer, handshake.listen_port
+                );
             }
 
             MessageType::MemorySync => {
-                if let Ok(memory) = serde_json::from_str::<SignedMemory>(&message.payload) {
-                    let mut ocm = self.ocm_protocol.lock().await;
-                    match ocm.verify_federated_memory(&memory).await {
-                        Ok(true) => {
-                            if let Err(e) = self.database.create_signed_memory(&memory) {
-                                eprintln!("Failed to store federated memory: {}", e);
-                            } else {
-                                println!(
-                                    "✅ Stored federated memory from peer: {}",
-                                    message.from_peer
-                                );
-                            }
-                        }
-                        Ok(false) => {
-                            println!(
-                                "❌ Invalid memory signature from peer: {}",
-                                message.from_peer
-                            );
+                if let Ok(shared) = serde_json::from_str::<SharedMemory>(&message.payload) {
+                    let memory = &shared.memory;
+                    let offline_key = match self
+                        .ingest
+                        .check(&self.ocm_protocol, &shared, &message.from_peer)
+                        .await
+                    {
+                        IngestDecision::Store { offline_public_key } => offline_public_key,
+                        IngestDecision::Drop => {
+                            self.reputation.record_rejected(&message.from_peer);
+                            return Ok(());
                         }
+                    };
+                    let sender = self.peers.get(&message.from_peer).await;
+                    let peer_did = sender.as_ref().and_then(|peer| peer.did.clone());
+                    // Memories from trusted peers skip inbound review
+                    let admitted = if self.reputation.is_trusted(&message.from_peer) {
+                        Ok(true)
+                    } else {
+                        self.review
+                            .admit(
+                                memory,
+                                &message.from_peer,
+                                peer_did.as_deref(),
+                                offline_key.as_deref(),
+                            )
+                            .await
+                    };
+                    match admitted {
+                        Ok(true) => {}
+                        Ok(false) => return Ok(()),
                         Err(e) => {
-                            eprintln!("Error verifying memory: {}", e);
+                            eprintln!("⚠️  Failed to hold memory for review: {}", e);
+                            return Ok(());
+                        }
+                    }
+                    let already_held = self
+                        .database
+                        .get_signed_memory(&memory.id)
+                        .ok()
+                        .flatten()
+                        .is_some_and(|held| held.content_hash == memory.content_hash);
+                    let mut stored = self.database.create_signed_memory(memory);
+                    // Memories checked on an attached chain are rechecked once the
+                    // directory can be reached
+                    if let (Ok(()), Some(public_key)) = (&stored, &offline_key) {
+                        stored = self.database.record_offline_verification(
+                            &memory.id,
+                            &memory.did,
+                            public_key,
+                        );
+                    }
+                    if let Err(e) = stored {
+                        tracing::error!("Failed to store federated memory: {}", e);
+                    } else {
+                        println!(
+                            "✅ Stored federated memory from peer: {}",
+                            message.from_peer
+                        );
+
+                        // Relays pass on what peers send them directly, once; memories
+                        // that came through another relay go no further
+                        let from_relay = sender.is_some_and(|peer| peer.supports(RELAY));
+                        if self.capabilities.relays() && !already_held && !from_relay {
+                            let networking = self.clone_handles();
+                            let shared = shared.clone();
+                            let from_peer = message.from_peer.clone();
+                            tokio::spawn(async move {
+                                networking.relay_memory(&shared, &from_peer).await;
+                            });
                         }
                     }
                 }
             }
 
             MessageType::MemoryRequest => {
-                // Send our recent memories to the requesting peer via direct connection
+                // Send our recent memories to the requesting peer
                 if let Ok(memories) = self.database.list_signed_memories() {
                     // Find the requesting peer info
-                    let requesting_peer = {
-                        let peers = self.peers.lock().await;
-                        peers.get(&message.from_peer).cloned()
-                    };
+                    let requesting_peer = self.peers.get(&message.from_peer).await;
 
                     if let Some(peer_info) = requesting_peer {
-                        for memory in memories.iter().take(10) {
+                        let mut allowed = Vec::new();
+                        for memory in &memories {
+                            if self
+                                .peer_groups
+                                .allows(&peer_info.peer_id, peer_info.did.as_deref(), memory)
+                                .await
+                            {
+                                allowed.push(memory);
+                            }
+                        }
+
+                        for memory in allowed.into_iter().take(10) {
                             // Send last 10 memories directly to requesting peer
-                            let sync_message = Self::create_authenticated_message(
+                            let shared = self.ocm_protocol.lock().await.share_memory(memory);
+                            let sync_message = Self::create_correlated_message(
                                 MessageType::MemorySync,
-                                serde_json::to_string(memory)?,
+                                serde_json::to_string(&shared)?,
                                 self.local_peer_id.clone(),
+                                message.request_id.clone(),
                             );
-                            if let Err(e) =
-                                self.send_message_to_peer(&peer_info, &sync_message).await
-                            {
+                            if let Err(e) = self.respond(&peer_info, sync_message, replies).await {
                                 eprintln!("Failed to send memory to requesting peer: {}", e);
                                 break; // Stop sending if connection fails
                             }
@@ -554,26 +875,38 @@ impl OcmNetworking {
             }
 
             MessageType::PeerDiscovery => {
-                // Share known peers with requesting peer via direct connection
-                let peers_lock = self.peers.lock().await;
-                let peer_list: Vec<&PeerInfo> = peers_lock.values().collect();
-                let requesting_peer = peers_lock.get(&message.from_peer).cloned();
+                // A peer list answers our own request; merge it rather than reply
+                if let Ok(reported) = serde_json::from_str::<Vec<PeerInfo>>(&message.payload) {
+                    let reported = reported
+                        .into_iter()
+                        .filter(|peer| peer.peer_id != self.local_peer_id)
+                        .collect();
+                    let merged = self.peers.merge(reported).await;
+                    if merged > 0 {
+                        println!(
+                            "🔍 Learned {} peers from peer: {}",
+                            merged, message.from_peer
+                        );
+                    }
+                    return Ok(());
+                }
+
+                // Share the freshest known peers with the requesting peer
+                let mut peer_list = self.peers.freshest().await;
+                peer_list.truncate(MAX_SHARED_PEERS);
+                let requesting_peer = self.peers.get(&message.from_peer).await;
 
                 if let (Some(peer_info), Ok(payload)) =
                     (requesting_peer, serde_json::to_string(&peer_list))
                 {
-                    let discovery_message = Self::create_authenticated_message(
+                    let discovery_message = Self::create_correlated_message(
                         MessageType::PeerDiscovery,
                         payload,
                         self.local_peer_id.clone(),
+                        message.request_id.clone(),
                     );
 
-                    // Send response directly to requesting peer
-                    drop(peers_lock);
-                    if let Err(e) = self
-                        .send_message_to_peer(&peer_info, &discovery_message)
-                        .await
-                    {
+                    if let Err(e) = self.respond(&peer_info, discovery_message, replies).await {
                         eprintln!("Failed to send peer discovery response: {}", e);
                     }
                 }
@@ -581,14 +914,281 @@ impl OcmNetworking {
 
             MessageType::Ping => {
                 // Update peer's last_seen timestamp
-                if let Some(peer) = self.peers.lock().await.get_mut(&message.from_peer) {
-                    peer.last_seen = chrono::Utc::now();
-                }
+                self.peers.touch(&message.from_peer).await;
             }
 
             MessageType::Pong => {
                 // Connection acknowledged
             }
+
+            MessageType::NotarizationRequest => {
+                if let Ok(request) = serde_json::from_str::<NotarizationRequest>(&message.payload) {
+                    let receipt = {
+                        let ocm = self.ocm_protocol.lock().await;
+                        ocm.witness_hash(&request.memory_id, &request.content_hash)
+                            .map_err(|e| e.to_string())
+                    };
+
+                    let requesting_peer = self.peers.get(&message.from_peer).await;
+
+                    match (receipt, requesting_peer) {
+                        (Ok(receipt), Some(peer_info)) => {
+                            let receipt_message = Self::create_correlated_message(
+                                MessageType::NotarizationReceipt,
+                                serde_json::to_string(&receipt)?,
+                                self.local_peer_id.clone(),
+                                message.request_id.clone(),
+                            );
+                            if let Err(e) = self.respond(&peer_info, receipt_message, replies).await
+                            {
+                                eprintln!("Failed to send witness receipt: {}", e);
+                            } else {
+                                println!(
+                                    "🖋️  Witnessed hash {} for peer: {}",
+                                    request.content_hash, message.from_peer
+                                );
+                            }
+                        }
+                        (Err(e), _) => eprintln!("Unable to witness hash: {}", e),
+                        (_, None) => {
+                            eprintln!(
+                                "Notarization request from unknown peer: {}",
+                                message.from_peer
+                            )
+                        }
+                    }
+                }
+            }
+
+            MessageType::NotarizationReceipt => {
+                if let Ok(receipt) = serde_json::from_str::<WitnessReceipt>(&message.payload) {
+                    // Only keep receipts for memories we hold with a matching hash
+                    let matches_local = matches!(
+                        self.database.get_signed_memory(&receipt.memory_id),
+                        Ok(Some(ref memory)) if memory.content_hash == receipt.content_hash
+                    );
+                    if !matches_local {
+                        eprintln!(
+                            "Witness receipt does not match a local memory: {}",
+                            receipt.memory_id
+                        );
+                        return Ok(());
+                    }
+
+                    let mut ocm = self.ocm_protocol.lock().await;
+                    match ocm.verify_witness_receipt(&receipt).await {
+                        Ok(true) => {
+                            if let Err(e) = self.database.create_witness_receipt(&receipt) {
+                                tracing::error!("Failed to store witness receipt: {}", e);
+                            } else {
+                                println!(
+                                    "✅ Stored witness receipt from {} for memory {}",
+                                    Redacted::did(&receipt.witness_did),
+                                    receipt.memory_id
+                                );
+                            }
+                        }
+                        Ok(false) => {
+                            println!(
+                                "❌ Invalid witness signature from peer: {}",
+                                message.from_peer
+                            );
+                        }
+                        Err(e) => {
+                            eprintln!("Error verifying witness receipt: {}", e);
+                        }
+                    }
+                }
+            }
+
+            MessageType::TreeHead => {
+                if let Ok(sth) = serde_json::from_str::<SignedTreeHead>(&message.payload) {
+                    let verified = {
+                        let mut ocm = self.ocm_protocol.lock().await;
+                        ocm.verify_did_signature(
+                            &sth.signer_did,
+                            &sth.get_signing_payload(),
+                            &sth.signature,
+                        )
+                        .await
+                        .map_err(|e| e.to_string())
+                    };
+
+                    match verified {
+                        Ok(true) => {
+                            let signer_did = sth.signer_did.clone();
+                            let status = self
+                                .tree_head_monitor
+                                .lock()
+                                .await
+                                .observe(sth, &message.from_peer);
+                            match status {
+                                TreeHeadStatus::NeedsProof {
+                                    first_size,
+                                    second_size,
+                                } => {
+                                    // The larger head counts only once the peer proves it
+                                    // extends the one it signed before
+                                    let Some(peer_info) = self.peers.get(&message.from_peer).await
+                                    else {
+                                        return Ok(());
+                                    };
+                                    let request = ConsistencyProofRequest {
+                                        first_size,
+                                        second_size,
+                                    };
+                                    let request_message = Self::create_correlated_message(
+                                        MessageType::ConsistencyProofRequest,
+                                        serde_json::to_string(&request)?,
+                                        self.local_peer_id.clone(),
+                                        message.request_id.clone(),
+                                    );
+                                    if let Err(e) =
+                                        self.respond(&peer_info, request_message, replies).await
+                                    {
+                                        eprintln!("Failed to request consistency proof: {}", e);
+                                    }
+                                }
+                                status => {
+                                    Self::report_tree_head(&signer_did, status);
+                                }
+                            }
+                        }
+                        Ok(false) => {
+                            println!(
+                                "❌ Invalid tree head signature from peer: {}",
+                                message.from_peer
+                            );
+                        }
+                        Err(e) => {
+                            eprintln!("Error verifying tree head: {}", e);
+                        }
+                    }
+                }
+            }
+
+            MessageType::ConsistencyProofRequest => {
+                if let Ok(request) =
+                    serde_json::from_str::<ConsistencyProofRequest>(&message.payload)
+                {
+                    let Some(peer_info) = self.peers.get(&message.from_peer).await else {
+                        eprintln!(
+                            "Consistency proof request from unknown peer: {}",
+                            message.from_peer
+                        );
+                        return Ok(());
+                    };
+                    let proof = TransparencyLog::new(self.database.clone())
+                        .consistency_proof(request.first_size, request.second_size)
+                        .map_err(|e| e.to_string());
+                    match proof {
+                        Ok(proof) => {
+                            let proof_message = Self::create_correlated_message(
+                                MessageType::ConsistencyProof,
+                                serde_json::to_string(&proof)?,
+                                self.local_peer_id.clone(),
+                                message.request_id.clone(),
+                            );
+                            if let Err(e) = self.respond(&peer_info, proof_message, replies).await {
+                                eprintln!("Failed to send consistency proof: {}", e);
+                            }
+                        }
+                        Err(e) => eprintln!("Unable to build consistency proof: {}", e),
+                    }
+                }
+            }
+
+            MessageType::ConsistencyProof => {
+                if let Ok(proof) = serde_json::from_str::<ConsistencyProof>(&message.payload) {
+                    let proved = self
+                        .tree_head_monitor
+                        .lock()
+                        .await
+                        .prove_growth(&message.from_peer, &proof);
+                    match proved {
+                        Some((signer_did, status)) => Self::report_tree_head(&signer_did, status),
+                        None => eprintln!(
+                            "Unrequested consistency proof from peer: {}",
+                            message.from_peer
+                        ),
+                    }
+                }
+            }
+
+            MessageType::Presence => {
+                if let Ok(update) = serde_json::from_str::<PresenceUpdate>(&message.payload) {
+                    // Only peers we know; the DID is the one recorded for the peer
+                    let Some(peer_info) = self.peers.get(&message.from_peer).await else {
+                        return Ok(());
+                    };
+                    self.peers.touch(&message.from_peer).await;
+                    if let Err(e) = self
+                        .presence
+                        .observe(&peer_info.peer_id, peer_info.did.as_deref(), &update)
+                        .await
+                    {
+                        eprintln!("Failed to record presence of {}: {}", message.from_peer, e);
+                    }
+                }
+            }
+
+            MessageType::MemoryBodyRequest => {
+                if let Ok(request) = serde_json::from_str::<MemoryBodyRequest>(&message.payload) {
+                    let requesting_peer = self.peers.get(&message.from_peer).await;
+                    let memory = self
+                        .database
+                        .get_signed_memory(&request.memory_id)
+                        .map_err(|e| e.to_string());
+
+                    match (memory, requesting_peer) {
+                        (Ok(Some(memory)), Some(peer_info))
+                            if memory.content_hash == request.content_hash =>
+                        {
+                            if !self
+                                .peer_groups
+                                .allows(&peer_info.peer_id, peer_info.did.as_deref(), &memory)
+                                .await
+                            {
+                                return Ok(());
+                            }
+
+                            // The body goes back as a regular MemorySync and replaces the header
+                            let shared = self.ocm_protocol.lock().await.share_memory(&memory);
+                            let sync_message = Self::create_correlated_message(
+                                MessageType::MemorySync,
+                                serde_json::to_string(&shared)?,
+                                self.local_peer_id.clone(),
+                                message.request_id.clone(),
+                            );
+                            if let Err(e) = self.respond(&peer_info, sync_message, replies).await {
+                                eprintln!("Failed to send memory body: {}", e);
+                            }
+                        }
+                        (Ok(_), Some(_)) => {
+                            println!(
+                                "Memory body {} requested by {} is not held here",
+                                request.memory_id, message.from_peer
+                            );
+                        }
+                        (Err(e), _) => eprintln!("Failed to load requested memory: {}", e),
+                        (_, None) => {
+                            eprintln!(
+                                "Memory body request from unknown peer: {}",
+                                message.from_peer
+                            )
+                        }
+                    }
+                }
+            }
+
+            MessageType::Fragment | MessageType::Compressed => {
+                // Fragments are reassembled and messages decompressed on the connection
+                // they arrive on
+                eprintln!(
+                    "Unexpected {:?} message from peer: {}",
+                    message.message_type, message.from_peer
+                );
+            }
         }
 
         Ok(())
@@ -605,54 +1205,299 @@ impl OcmNetworking {
         // Send handshake
         let handshake = Self::create_authenticated_message(
             MessageType::Handshake,
-            format!(
-                "{{\"peer_id\": \"{}\", \"port\": {}}}",
-                self.local_peer_id, self.port
-            ),
+            serde_json::to_string(&self.handshake_payload().await)?,
             self.local_peer_id.clone(),
         );
 
-        let handshake_data = serde_json::to_vec(&handshake)?;
-        let length = (handshake_data.len() as u32).to_be_bytes();
-        stream.write_all(&length).await?;
-        stream.write_all(&handshake_data).await?;
+        let sent_at = chrono::Utc::now();
+        self.write_message(&mut stream, &handshake, None).await?;
+
+        // The acknowledgment carries the peer's node ID, and from current nodes their own
+        // handshake; fall back to the address if it never arrives
+        let (peer_id, their_handshake) = match self.read_handshake_ack(&mut stream, sent_at).await {
+            Some(ack) => {
+                let their_handshake = serde_json::from_str::<HandshakePayload>(&ack.payload)
+                    .ok()
+                    .filter(|their_handshake| their_handshake.peer_id == ack.from_peer);
+                (ack.from_peer, their_handshake)
+            }
+            None => {
+                eprintln!(
+                    "No handshake acknowledgment from {}, tracking by address",
+                    addr
+                );
+                (addr.clone(), None)
+            }
+        };
+        let did = match &their_handshake {
+            Some(their_handshake) => self.verify_handshake_did(their_handshake).await,
+            None => None,
+        };
 
         // Add peer to our list
         let peer_info = PeerInfo {
-            peer_id: format!("{}:{}", peer_addr, peer_port),
+            peer_id,
             address: peer_addr.to_string(),
             port: peer_port,
             last_seen: chrono::Utc::now(),
+            did,
+            capabilities: their_handshake
+                .as_ref()
+                .map(|their_handshake| their_handshake.capabilities.clone())
+                .unwrap_or_default(),
+            protocol_version: their_handshake
+                .as_ref()
+                .map_or(0, |their_handshake| their_handshake.protocol_version),
+            role: their_handshake
+                .as_ref()
+                .map_or(NodeRole::Full, |their_handshake| their_handshake.role),
+            frame_limits: their_handshake.and_then(|their_handshake| their_handshake.frame_limits),
+        };
+
+        self.peers.upsert(peer_info).await;
+        println!("Connected to peer: {}:{}", peer_addr, peer_port);
+
+        Ok(())
+    }
+
+    /// Forget a peer. Connections are opened per message, so this stops syncs and
+    /// broadcasts to it until it is discovered or connected again
+    pub async fn disconnect_peer(&self, peer_id: &str) -> bool {
+        let removed = self.peers.remove(peer_id).await.is_some();
+        if removed {
+            println!("Disconnected from peer: {}", peer_id);
+        }
+        removed
+    }
+
+    /// Known peers, connected or not, with their traffic, standing and reputation
+    pub async fn list_peers(&self) -> error::Result<Vec<PeerRecord>> {
+        self.reputation.flush().await?;
+        self.database.list_peer_records()
+    }
+
+    pub async fn get_peer(&self, peer_id: &PeerId) -> error::Result<Option<PeerRecord>> {
+        self.reputation.flush().await?;
+        self.database.get_peer_record(peer_id)
+    }
+
+    /// Drop a peer and ignore it from now on, including its handshakes and beacons
+    pub async fn ban_peer(&self, peer_id: &PeerId, reason: Option<&str>) -> error::Result<()> {
+        self.reputation
+            .set_standing(peer_id, PeerStanding::Banned, reason)
+            .await?;
+        println!("🚫 Banned peer: {}", peer_id);
+        Ok(())
+    }
+
+    /// Store memories from a peer without inbound review. They are still verified
+    pub async fn trust_peer(&self, peer_id: &PeerId, reason: Option<&str>) -> error::Result<()> {
+        self.reputation
+            .set_standing(peer_id, PeerStanding::Trusted, reason)
+            .await?;
+        println!("🤝 Trusted peer: {}", peer_id);
+        Ok(())
+    }
+
+    /// Undo a ban or trust
+    pub async fn clear_peer_standing(&self, peer_id: &PeerId) -> error::Result<()> {
+        self.reputation
+            .set_standing(peer_id, PeerStanding::Neutral, None)
+            .await
+    }
+
+    /// This node's handshake, signed by its identity when it has one
+    async fn handshake_payload(&self) -> HandshakePayload {
+        let mut payload = HandshakePayload {
+            peer_id: self.local_peer_id.clone(),
+            listen_port: self.port,
             did: None,
+            capabilities: self.capabilities.advertised(),
+            protocol_version: PEER_PROTOCOL_VERSION,
+            frame_limits: Some(self.framing.limits()),
+            role: self.capabilities.role(),
+            signature: None,
         };
+        if let Some(identity) = self.ocm_protocol.lock().await.current_identity() {
+            payload.did = Some(identity.did.to_string());
+            match identity.sign_payload(&payload.get_signing_payload()) {
+                Ok(signature) => payload.signature = Some(signature),
+                Err(e) => {
+                    eprintln!(
+                        "⚠️  Failed to sign handshake, sending it without a DID: {}",
+                        e
+                    );
+                    payload.did = None;
+                }
+            }
+        }
+        payload
+    }
 
-        self.peers
+    /// The DID a handshake claims, if its signature shows the sender holds that DID's key
+    async fn verify_handshake_did(&self, handshake: &HandshakePayload) -> Option<String> {
+        let (did, signature) = (handshake.did.as_ref()?, handshake.signature.as_ref()?);
+        let verified = self
+            .ocm_protocol
             .lock()
             .await
-            .insert(peer_info.peer_id.clone(), peer_info);
-        println!("Connected to peer: {}:{}", peer_addr, peer_port);
+            .verify_did_signature(did, &handshake.get_signing_payload(), signature)
+            .await;
+        match verified {
+            Ok(true) => Some(did.clone()),
+            Ok(false) => {
+                eprintln!(
+                    "⚠️  Ignoring DID {} in handshake from {}: bad signature",
+                    Redacted::did(did),
+                    handshake.peer_id
+                );
+                None
+            }
+            Err(e) => {
+                eprintln!(
+                    "⚠️  Could not check DID {} in handshake from {}: {}",
+                    Redacted::did(did),
+                    handshake.peer_id,
+                    e
+                );
+                None
+            }
+        }
+    }
 
-        Ok(())
+    /// Read and authenticate the handshake acknowledgment, taking a first clock offset sample
+    async fn read_handshake_ack(
+        &self,
+        stream: &mut TcpStream,
+        sent_at: chrono::DateTime<chrono::Utc>,
+    ) -> Option<NetworkMessage> {
+        let mut reassembler = self.framing.reassembler();
+        let read_ack = async {
+            self.framing
+                .read_message(stream, &self.bandwidth, &mut reassembler)
+                .await
+                .ok()
+                .flatten()
+        };
+
+        let ack = tokio::time::timeout(
+            tokio::time::Duration::from_secs(HANDSHAKE_ACK_TIMEOUT_SECS),
+            read_ack,
+        )
+        .await
+        .ok()??;
+
+        let authenticated = self.validate_message(&ack).is_ok()
+            && matches!(self.verify_message_authentication(&ack), Ok(true));
+        if authenticated && matches!(ack.message_type, MessageType::Pong) {
+            self.record_clock_sample(&ack, sent_at);
+            Some(ack)
+        } else {
+            None
+        }
     }
 
+    /// Send a memory to every known peer its peer groups allow, returning how many accepted it
     pub async fn broadcast_memory(
         &self,
         memory: &SignedMemory,
-    ) -> Result<(), Box<dyn std::error::Error>> {
+    ) -> Result<usize, Box<dyn std::error::Error>> {
+        self.broadcast_memory_to(memory, None).await
+    }
+
+    /// Send a memory only to connected members of one peer group
+    pub async fn broadcast_to_group(
+        &self,
+        group_name: &str,
+        memory: &SignedMemory,
+    ) -> Result<usize, Box<dyn std::error::Error>> {
+        self.broadcast_memory_to(memory, Some(group_name)).await
+    }
+
+    async fn broadcast_memory_to(
+        &self,
+        memory: &SignedMemory,
+        group_name: Option<&str>,
+    ) -> Result<usize, Box<dyn std::error::Error>> {
+        let shared = self.ocm_protocol.lock().await.share_memory(memory);
         let message = Self::create_authenticated_message(
             MessageType::MemorySync,
-            serde_json::to_string(memory)?,
+            serde_json::to_string(&shared)?,
             self.local_peer_id.clone(),
         );
 
-        let peers = self.peers.lock().await;
-        for peer in peers.values() {
-            if let Err(e) = self.send_message_to_peer(peer, &message).await {
-                eprintln!("Failed to send memory to peer {}: {}", peer.peer_id, e);
+        let mut delivered = 0;
+        for peer in self.peers_supporting(MEMORY_SYNC).await.iter() {
+            let did = peer.did.as_deref();
+            if let Some(group_name) = group_name {
+                let groups = self.peer_groups.groups_for(&peer.peer_id, did).await;
+                if !groups.iter().any(|g| g.name == group_name) {
+                    continue;
+                }
+            }
+            if !self.peer_groups.allows(&peer.peer_id, did, memory).await {
+                continue;
+            }
+
+            let sent = self
+                .send_message_to_peer(peer, &message)
+                .await
+                .map_err(|e| e.to_string());
+            match sent {
+                Ok(()) => {
+                    delivered += 1;
+                    self.peer_groups.record_shared(&peer.peer_id, did).await;
+                }
+                Err(e) => eprintln!("Failed to send memory to peer {}: {}", peer.peer_id, e),
             }
         }
 
-        Ok(())
+        Ok(delivered)
+    }
+
+    /// Known peers that advertised `capability`, or have it from before advertising
+    pub async fn peers_supporting(&self, capability: &str) -> Vec<PeerInfo> {
+        let mut peers = self.peers.list().await;
+        peers.retain(|peer| peer.supports(capability));
+        peers
+    }
+
+    /// Pass a memory a peer sent this node on to the other peers its groups allow
+    async fn relay_memory(&self, shared: &SharedMemory, from_peer: &str) {
+        let payload = match serde_json::to_string(shared) {
+            Ok(payload) => payload,
+            Err(e) => {
+                eprintln!("Failed to serialize memory for relaying: {}", e);
+                return;
+            }
+        };
+        let message = Self::create_authenticated_message(
+            MessageType::MemorySync,
+            payload,
+            self.local_peer_id.clone(),
+        );
+
+        let mut relayed = 0;
+        for peer in self.peers_supporting(MEMORY_SYNC).await.iter() {
+            if peer.peer_id == from_peer
+                || !self
+                    .peer_groups
+                    .allows(&peer.peer_id, peer.did.as_deref(), &shared.memory)
+                    .await
+            {
+                continue;
+            }
+            match self.send_message_to_peer(peer, &message).await {
+                Ok(()) => relayed += 1,
+                Err(e) => eprintln!("Failed to relay memory to peer {}: {}", peer.peer_id, e),
+            }
+        }
+        if relayed > 0 {
+            println!(
+                "📨 Relayed memory {} from {} to {} peers",
+                shared.memory.id, from_peer, relayed
+            );
+        }
     }
 
     async fn send_message_to_peer(
@@ -660,32 +1505,20 @@ impl OcmNetworking {
         peer: &PeerInfo,
         message: &NetworkMessage,
     ) -> Result<(), Box<dyn std::error::Error>> {
-        let addr = format!("{}:{}", peer.address, peer.port);
-        let mut stream = TcpStream::connect(&addr).await?;
-
-        let message_data = serde_json::to_vec(message)?;
-
-        // Send length-prefixed message (same protocol as handle_connection)
-        let length = (message_data.len() as u32).to_be_bytes();
-        stream.write_all(&length).await?;
-        stream.write_all(&message_data).await?;
-
-        // Wait for acknowledgment with timeout
-        tokio::time::timeout(std::time::Duration::from_secs(30), async {
-            let mut length_bytes = [0u8; 4];
-            stream.read_exact(&mut length_bytes).await?;
-            let ack_length = u32::from_be_bytes(length_bytes) as usize;
-
-            if ack_length > MAX_MESSAGE_SIZE {
-                return Err("Acknowledgment too large".into());
+        let delivery = match self.transport.send(peer, message).await {
+            Ok(delivery) => delivery,
+            Err(e) => {
+                self.reputation.record_send_failure(&peer.peer_id);
+                return Err(e as Box<dyn std::error::Error>);
             }
+        };
+        self.reputation
+            .record_sent(&peer.peer_id, message.payload.len());
 
-            let mut ack_buffer = vec![0; ack_length];
-            stream.read_exact(&mut ack_buffer).await?;
-            Ok::<(), Box<dyn std::error::Error>>(())
-        })
-        .await??;
-
+        // Replies can lead to sends of their own, so they are handled by process_replies
+        for reply in delivery.replies {
+            let _ = self.message_sender.send(reply);
+        }
         Ok(())
     }
 
@@ -696,8 +1529,7 @@ impl OcmNetworking {
             self.local_peer_id.clone(),
         );
 
-        let peers = self.peers.lock().await;
-        for peer in peers.values() {
+        for peer in self.peers_supporting(MEMORY_SYNC).await.iter() {
             if let Err(e) = self.send_message_to_peer(peer, &request_message).await {
                 eprintln!(
                     "Failed to request memories from peer {}: {}",
@@ -709,47 +1541,254 @@ impl OcmNetworking {
         Ok(())
     }
 
-    pub async fn discover_peers(&self) -> Result<(), Box<dyn std::error::Error>> {
-        let discovery_message = Self::create_authenticated_message(
-            MessageType::PeerDiscovery,
-            "".to_string(),
+    /// Ask every connected peer to witness a memory's content hash
+    pub async fn request_notarization(
+        &self,
+        memory: &SignedMemory,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let request = NotarizationRequest {
+            memory_id: memory.id.clone(),
+            content_hash: memory.content_hash.clone(),
+        };
+        let request_message = Self::create_authenticated_message(
+            MessageType::NotarizationRequest,
+            serde_json::to_string(&request)?,
             self.local_peer_id.clone(),
         );
 
-        let peers = self.peers.lock().await;
-        for peer in peers.values() {
-            if let Err(e) = self.send_message_to_peer(peer, &discovery_message).await {
-                eprintln!("Failed to discover peers from {}: {}", peer.peer_id, e);
+        for peer in self.peers_supporting(NOTARIZATION).await.iter() {
+            if let Err(e) = self.send_message_to_peer(peer, &request_message).await {
+                eprintln!(
+                    "Failed to request notarization from peer {}: {}",
+                    peer.peer_id, e
+                );
             }
         }
 
         Ok(())
     }
 
-    pub async fn start_heartbeat(&self) -> Result<(), Box<dyn std::error::Error>> {
-        let peers = self.peers.clone();
-        let local_peer_id = self.local_peer_id.clone();
+    /// Fetch the body of a memory held only as a header from the peer that sent the
+    /// header or, when that peer is gone, from an archive node or else a full node
+    pub async fn request_memory_body(
+        &self,
+        memory_id: &MemoryId,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let (header, source_peer) = self
+            .database
+            .get_memory_header(memory_id)?
+            .ok_or_else(|| format!("No header held for memory: {}", memory_id))?;
 
-        tokio::spawn(async move {
-            let mut interval = tokio::time::interval(tokio::time::Duration::from_secs(30));
+        let mut fallbacks: Vec<PeerInfo> = self
+            .peers_supporting(MEMORY_HEADERS)
+            .await
+            .into_iter()
+            .filter(|peer| peer.peer_id != source_peer && peer.role != NodeRole::Light)
+            .collect();
+        fallbacks.sort_by_key(|peer| peer.role != NodeRole::Archive);
+        let candidates: Vec<PeerInfo> = self
+            .peers
+            .get(&source_peer)
+            .await
+            .filter(|peer| peer.supports(MEMORY_HEADERS))
+            .into_iter()
+            .chain(fallbacks)
+            .collect();
+        if candidates.is_empty() {
+            return Err(
+                format!("No connected peer serves the body of memory {}", memory_id).into(),
+            );
+        }
 
-            loop {
-                interval.tick().await;
+        let request = MemoryBodyRequest {
+            memory_id: header.id,
+            content_hash: header.content_hash,
+        };
+        let request_message = Self::create_authenticated_message(
+            MessageType::MemoryBodyRequest,
+            serde_json::to_string(&request)?,
+            self.local_peer_id.clone(),
+        );
+        let mut last_error = None;
+        for peer in &candidates {
+            match self.send_message_to_peer(peer, &request_message).await {
+                Ok(()) => return Ok(()),
+                Err(e) => last_error = Some(e),
+            }
+        }
+        Err(last_error.unwrap_or_else(|| "No peer took the body request".into()))
+    }
 
-                let _ping_message = Self::create_authenticated_message(
-                    MessageType::Ping,
-                    "ping".to_string(),
-                    local_peer_id.clone(),
+    fn report_tree_head(signer_did: &str, status: TreeHeadStatus) {
+        match status {
+            TreeHeadStatus::Rewritten(reason) => {
+                eprintln!(
+                    "⚠️  Transparency log rewrite detected for {}: {}",
+                    Redacted::did(signer_did),
+                    reason
                 );
+            }
+            TreeHeadStatus::Grew | TreeHeadStatus::First => {
+                println!("🌳 Recorded tree head from: {}", Redacted::did(signer_did));
+            }
+            TreeHeadStatus::NeedsProof { .. } | TreeHeadStatus::Unchanged => {}
+        }
+    }
 
-                let peers_lock = peers.lock().await;
-                for peer in peers_lock.values() {
-                    // Send ping (implementation would be similar to send_message_to_peer)
-                    println!("Sending heartbeat to peer: {}", peer.peer_id);
+    /// Gossip our signed transparency log head so peers can detect history rewrites
+    pub async fn broadcast_tree_head(&self) -> Result<(), Box<dyn std::error::Error>> {
+        let sth = {
+            let ocm = self.ocm_protocol.lock().await;
+            let identity = ocm
+                .current_identity()
+                .ok_or("No current identity to sign tree head")?;
+            TransparencyLog::new(self.database.clone()).signed_tree_head(identity)?
+        };
+        let tree_head_message = Self::create_authenticated_message(
+            MessageType::TreeHead,
+            serde_json::to_string(&sth)?,
+            self.local_peer_id.clone(),
+        );
+
+        for peer in self.peers_supporting(IDENTITY_VERIFICATION).await.iter() {
+            if let Err(e) = self.send_message_to_peer(peer, &tree_head_message).await {
+                eprintln!("Failed to send tree head to {}: {}", peer.peer_id, e);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Announce our presence to the peers that share a trusted peer group with us
+    pub async fn broadcast_presence(
+        &self,
+        update: &PresenceUpdate,
+    ) -> Result<usize, Box<dyn std::error::Error>> {
+        let presence_message = Self::create_authenticated_message(
+            MessageType::Presence,
+            serde_json::to_string(update)?,
+            self.local_peer_id.clone(),
+        );
+
+        let mut sent = 0;
+        for peer in self.peers_supporting(PRESENCE).await.iter() {
+            if !self
+                .presence
+                .shares_with(&peer.peer_id, peer.did.as_deref())
+                .await
+            {
+                continue;
+            }
+            match self.send_message_to_peer(peer, &presence_message).await {
+                Ok(()) => sent += 1,
+                Err(e) => eprintln!("Failed to send presence to {}: {}", peer.peer_id, e),
+            }
+        }
+
+        Ok(sent)
+    }
+
+    pub async fn discover_peers(&self) -> Result<(), Box<dyn std::error::Error>> {
+        for peer in self.peers_supporting(PEER_DISCOVERY).await.iter() {
+            if let Err(e) = self.request_peer_list(peer).await {
+                eprintln!("Failed to discover peers from {}: {}", peer.peer_id, e);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Ask one peer for the peers it knows; the list is merged when it arrives
+    pub async fn request_peer_list(
+        &self,
+        peer: &PeerInfo,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        if !peer.supports(PEER_DISCOVERY) {
+            return Err(format!("Peer {} does not share its peers", peer.peer_id).into());
+        }
+        let discovery_message = Self::create_authenticated_message(
+            MessageType::PeerDiscovery,
+            "".to_string(),
+            self.local_peer_id.clone(),
+        );
+        self.send_message_to_peer(peer, &discovery_message).await
+    }
+
+    pub async fn start_heartbeat(
+        &self,
+        supervisor: &TaskSupervisor,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let node = Arc::new(self.clone_handles());
+
+        supervisor.spawn("heartbeat", RestartPolicy::Always, move || {
+            let node = node.clone();
+            async move {
+                let mut interval = tokio::time::interval(tokio::time::Duration::from_secs(30));
+
+                loop {
+                    interval.tick().await;
+
+                    for peer in node.peers.list().await {
+                        node.ping_peer(&peer).await;
+                    }
                 }
             }
         });
 
+        self.watch_peers(supervisor);
         Ok(())
     }
+
+    /// Drop per-peer state when a peer leaves the shared peer store, whoever removed it
+    fn watch_peers(&self, supervisor: &TaskSupervisor) {
+        let peers = self.peers.clone();
+        let clock_skew = self.clock_skew.clone();
+
+        supervisor.spawn("peer_watch", RestartPolicy::OnPanic, move || {
+            let mut events = peers.subscribe();
+            let clock_skew = clock_skew.clone();
+            async move {
+                loop {
+                    match events.recv().await {
+                        Ok(PeerEvent::Removed(peer_id)) => clock_skew.forget(&peer_id),
+                        Ok(_) => {}
+                        Err(RecvError::Lagged(_)) => continue,
+                        Err(RecvError::Closed) => break,
+                    }
+                }
+            }
+        });
+    }
+
+    /// Ping a peer, refreshing its clock offset estimate from the acknowledgment
+    async fn ping_peer(&self, peer: &PeerInfo) {
+        let ping = Self::create_authenticated_message(
+            MessageType::Ping,
+            "ping".to_string(),
+            self.local_peer_id.clone(),
+        );
+        let sent_at = chrono::Utc::now();
+        match self.transport.send(peer, &ping).await {
+            Ok(Delivery { ack: Some(ack), .. }) => {
+                let authenticated = self.validate_message(&ack).is_ok()
+                    && matches!(self.verify_message_authentication(&ack), Ok(true));
+                if authenticated && matches!(ack.message_type, MessageType::Pong) {
+                    self.record_clock_sample(&ack, sent_at);
+                }
+            }
+            Ok(Delivery { ack: None, .. }) => {}
+            Err(e) => eprintln!("Heartbeat to peer {} failed: {}", peer.peer_id, e),
+        }
+    }
+
+    fn record_clock_sample(&self, ack: &NetworkMessage, sent_at: chrono::DateTime<chrono::Utc>) {
+        if let Ok(remote) = chrono::DateTime::parse_from_rfc3339(&ack.timestamp) {
+            self.clock_skew.record_round_trip(
+                &ack.from_peer,
+                sent_at,
+                remote.with_timezone(&chrono::Utc),
+                chrono::Utc::now(),
+            );
+        }
+    }
 }
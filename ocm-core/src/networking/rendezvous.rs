@@ -0,0 +1,137 @@
+use crate::config::{RendezvousConfig, ServerConfig};
+use crate::networking::protocol::{NodeRole, PeerInfo};
+use crate::networking::transport::TransportError;
+use futures_util::{SinkExt, StreamExt};
+use ocm_protocol::relay::{DirectoryEntry, RelayMessage};
+use std::net::IpAddr;
+use std::time::Duration;
+use tokio_tungstenite::tungstenite::Message;
+
+const DIRECTORY_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Registers this node in a relay's rendezvous directory and looks up others there,
+/// for peers that UDP broadcast cannot reach. One short WebSocket connection per call
+pub struct RendezvousClient {
+    url: String,
+    endpoints: Vec<String>,
+    ttl_seconds: u64,
+    lookup_dids: Vec<String>,
+}
+
+impl RendezvousClient {
+    /// None when no relay is configured
+    pub fn new(config: &RendezvousConfig, server: &ServerConfig) -> Option<Self> {
+        let relay_url = config.relay_url.as_ref()?;
+        let url = match &config.token {
+            Some(token) => format!("{}?token={}", relay_url, token),
+            None => relay_url.clone(),
+        };
+        let endpoints = if config.advertise_endpoints.is_empty() {
+            vec![format!("{}:{}", server.host, server.p2p_port)]
+        } else {
+            config.advertise_endpoints.clone()
+        };
+
+        Some(Self {
+            url,
+            endpoints,
+            ttl_seconds: config.ttl_seconds,
+            lookup_dids: config.lookup_dids.clone(),
+        })
+    }
+
+    pub fn ttl_seconds(&self) -> u64 {
+        self.ttl_seconds
+    }
+
+    /// Returns our entry as stored, including the address the relay saw us at
+    pub async fn register(
+        &self,
+        did: &str,
+        node_id: &str,
+    ) -> Result<DirectoryEntry, TransportError> {
+        let message = RelayMessage::DirectoryRegister {
+            did: did.to_string(),
+            node_id: node_id.to_string(),
+            endpoints: self.endpoints.clone(),
+            ttl_secs: Some(self.ttl_seconds),
+        };
+        self.request(message)
+            .await?
+            .pop()
+            .ok_or_else(|| "Relay stored no directory entry".into())
+    }
+
+    /// Look up the configured DIDs, or any registered nodes if none are configured
+    pub async fn lookup(&self) -> Result<Vec<DirectoryEntry>, TransportError> {
+        self.request(RelayMessage::DirectoryQuery {
+            dids: self.lookup_dids.clone(),
+        })
+        .await
+    }
+
+    async fn request(&self, message: RelayMessage) -> Result<Vec<DirectoryEntry>, TransportError> {
+        let (mut socket, _) = tokio_tungstenite::connect_async(&self.url).await?;
+        socket.send(Message::Text(message.to_json())).await?;
+
+        // Skip the welcome and any memories relayed meanwhile until the directory answers
+        let reply = tokio::time::timeout(DIRECTORY_TIMEOUT, async {
+            while let Some(frame) = socket.next().await {
+                let Message::Text(text) = frame? else {
+                    continue;
+                };
+                match serde_json::from_str::<RelayMessage>(&text) {
+                    Ok(RelayMessage::DirectoryEntries { entries }) => return Ok(entries),
+                    Ok(RelayMessage::DirectoryRejected { reason }) => return Err(reason.into()),
+                    _ => {}
+                }
+            }
+            Err::<Vec<DirectoryEntry>, TransportError>("Relay closed the connection".into())
+        })
+        .await
+        .map_err(|_| "Relay has no directory or did not answer")?;
+
+        let _ = socket.close(None).await;
+        reply
+    }
+}
+
+/// The peer a directory entry describes. An endpoint on a loopback, private or
+/// unspecified address only works inside the registrant's own network, so from
+/// outside it is dialled at the address the relay saw instead
+pub fn entry_to_peer(entry: &DirectoryEntry) -> Option<PeerInfo> {
+    let (host, port) = entry.endpoints.iter().find_map(|endpoint| {
+        let (host, port) = endpoint.rsplit_once(':')?;
+        Some((host.to_string(), port.parse::<u16>().ok()?))
+    })?;
+
+    let local_only = host.parse::<IpAddr>().is_ok_and(|ip| match ip {
+        IpAddr::V4(ip) => ip.is_loopback() || ip.is_private() || ip.is_unspecified(),
+        IpAddr::V6(ip) => ip.is_loopback() || ip.is_unspecified(),
+    });
+    let observed_host = entry
+        .observed_addr
+        .as_deref()
+        .and_then(|addr| addr.rsplit_once(':'))
+        .map(|(host, _)| host.to_string());
+    let address = match observed_host {
+        Some(observed) if local_only => observed,
+        _ => host,
+    };
+
+    let last_seen = chrono::DateTime::parse_from_rfc3339(&entry.registered_at)
+        .map(|dt| dt.with_timezone(&chrono::Utc))
+        .unwrap_or_else(|_| chrono::Utc::now());
+
+    Some(PeerInfo {
+        peer_id: entry.node_id.clone(),
+        address,
+        port,
+        last_seen,
+        did: Some(entry.did.clone()),
+        capabilities: Vec::new(),
+        protocol_version: 0,
+        frame_limits: None,
+        role: NodeRole::Full,
+    })
+}
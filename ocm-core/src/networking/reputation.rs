@@ -0,0 +1,160 @@
+use crate::core::error::Result;
+use crate::core::ids::PeerId;
+use crate::core::models::{PeerStanding, PeerTraffic};
+use crate::networking::peers::PeerStore;
+use crate::persistence::database::Database;
+use crate::supervisor::{RestartPolicy, TaskSupervisor};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, RwLock};
+
+const FLUSH_INTERVAL_SECS: u64 = 10;
+
+/// Traffic with each peer and the operator's ban and trust decisions. Both live in
+/// peer_record, where the REST API and the `ocm-core peers` command see them; decisions
+/// and disconnections they record there reach the node at its next flush
+pub struct PeerReputation {
+    database: Arc<Database>,
+    peers: Arc<PeerStore>,
+    traffic: Mutex<HashMap<String, PeerTraffic>>, // Not yet added to peer_record
+    standings: RwLock<HashMap<String, PeerStanding>>, // Trusted and banned peers only
+}
+
+impl PeerReputation {
+    pub fn new(database: Arc<Database>, peers: Arc<PeerStore>) -> Self {
+        Self {
+            database,
+            peers,
+            traffic: Mutex::new(HashMap::new()),
+            standings: RwLock::new(HashMap::new()),
+        }
+    }
+
+    pub fn record_sent(&self, peer_id: &str, payload_bytes: usize) {
+        self.count(peer_id, |traffic| {
+            traffic.messages_sent += 1;
+            traffic.bytes_sent += payload_bytes as u64;
+        });
+    }
+
+    pub fn record_received(&self, peer_id: &str, payload_bytes: usize) {
+        self.count(peer_id, |traffic| {
+            traffic.messages_received += 1;
+            traffic.bytes_received += payload_bytes as u64;
+        });
+    }
+
+    pub fn record_send_failure(&self, peer_id: &str) {
+        self.count(peer_id, |traffic| traffic.send_failures += 1);
+    }
+
+    /// A message or memory from the peer failed validation, authentication or verification
+    pub fn record_rejected(&self, peer_id: &str) {
+        self.count(peer_id, |traffic| traffic.rejected += 1);
+    }
+
+    fn count(&self, peer_id: &str, update: impl FnOnce(&mut PeerTraffic)) {
+        let mut traffic = self.traffic.lock().unwrap_or_else(|e| e.into_inner());
+        update(traffic.entry(peer_id.to_string()).or_default());
+    }
+
+    pub fn standing(&self, peer_id: &str) -> PeerStanding {
+        self.standings
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .get(peer_id)
+            .copied()
+            .unwrap_or_default()
+    }
+
+    pub fn is_banned(&self, peer_id: &str) -> bool {
+        self.standing(peer_id) == PeerStanding::Banned
+    }
+
+    pub fn is_trusted(&self, peer_id: &str) -> bool {
+        self.standing(peer_id) == PeerStanding::Trusted
+    }
+
+    /// Ban, trust or clear a peer. Banning removes it from the peer set
+    pub async fn set_standing(
+        &self,
+        peer_id: &PeerId,
+        standing: PeerStanding,
+        reason: Option<&str>,
+    ) -> Result<()> {
+        self.database.set_peer_standing(peer_id, standing, reason)?;
+        self.apply(peer_id.as_str(), standing).await;
+        Ok(())
+    }
+
+    async fn apply(&self, peer_id: &str, standing: PeerStanding) {
+        {
+            let mut standings = self.standings.write().unwrap_or_else(|e| e.into_inner());
+            match standing {
+                PeerStanding::Neutral => standings.remove(peer_id),
+                _ => standings.insert(peer_id.to_string(), standing),
+            };
+        }
+        self.peers
+            .set_banned(peer_id, standing == PeerStanding::Banned)
+            .await;
+    }
+
+    /// Write traffic and the peer set to peer_record, then take up standings and
+    /// disconnections recorded there
+    pub async fn flush(&self) -> Result<()> {
+        let traffic = {
+            let mut traffic = self.traffic.lock().unwrap_or_else(|e| e.into_inner());
+            std::mem::take(&mut *traffic)
+        };
+        for (peer_id, traffic) in &traffic {
+            // Counted before the peer ID was checked; one that fails it has no record
+            let Ok(peer_id) = PeerId::parse(peer_id) else {
+                continue;
+            };
+            self.database.add_peer_traffic(&peer_id, traffic)?;
+        }
+
+        let peers = self.peers.list().await;
+        for peer in &peers {
+            self.database.record_peer(peer, true)?;
+        }
+        let connected: Vec<String> = peers.into_iter().map(|peer| peer.peer_id).collect();
+        self.database.mark_peers_disconnected(&connected)?;
+
+        for record in self.database.list_peer_records()? {
+            if record.standing != self.standing(&record.peer_id) {
+                println!(
+                    "🛂 Peer {} is now {}",
+                    record.peer_id,
+                    record.standing.as_str()
+                );
+                self.apply(&record.peer_id, record.standing).await;
+            }
+        }
+
+        for peer_id in self.database.take_peer_disconnect_requests()? {
+            if self.peers.remove(&peer_id).await.is_some() {
+                println!("Disconnected from peer: {}", peer_id);
+            }
+        }
+        Ok(())
+    }
+
+    /// Flush every FLUSH_INTERVAL_SECS, starting with the standings recorded so far
+    pub fn start(self: &Arc<Self>, supervisor: &TaskSupervisor) {
+        let reputation = self.clone();
+        supervisor.spawn("peer_records", RestartPolicy::Always, move || {
+            let reputation = reputation.clone();
+            async move {
+                let mut interval =
+                    tokio::time::interval(tokio::time::Duration::from_secs(FLUSH_INTERVAL_SECS));
+                loop {
+                    interval.tick().await;
+                    if let Err(e) = reputation.flush().await {
+                        eprintln!("⚠️  Failed to update peer records: {}", e);
+                    }
+                }
+            }
+        });
+    }
+}
@@ -0,0 +1,100 @@
+use crate::config::InboundReviewConfig;
+use crate::core::error::{OcmError, Result};
+use crate::core::ids::Did;
+use crate::core::models::{PendingMemory, PendingStatus, SignedMemory};
+use crate::networking::groups::PeerGroupRegistry;
+use crate::persistence::database::Database;
+use std::sync::Arc;
+use tokio::sync::Mutex;
+
+/// Decides whether a verified memory from a peer is stored right away or held in
+/// pending_memory until an operator approves it
+pub struct InboundReview {
+    config: Mutex<InboundReviewConfig>,
+    database: Arc<Database>,
+    peer_groups: Arc<PeerGroupRegistry>,
+}
+
+impl InboundReview {
+    pub fn new(database: Arc<Database>, peer_groups: Arc<PeerGroupRegistry>) -> Self {
+        Self {
+            config: Mutex::new(InboundReviewConfig::default()),
+            database,
+            peer_groups,
+        }
+    }
+
+    pub async fn configure(&self, config: &InboundReviewConfig) {
+        *self.config.lock().await = config.clone();
+    }
+
+    /// Whether a memory received from a peer may be stored now. If not, it has been
+    /// held for review, or was rejected before and is dropped
+    pub async fn admit(
+        &self,
+        memory: &SignedMemory,
+        peer_id: &str,
+        peer_did: Option<&str>,
+        offline_public_key: Option<&str>,
+    ) -> Result<bool> {
+        let config = self.config.lock().await.clone();
+        if !config.enabled {
+            return Ok(true);
+        }
+
+        let groups = self.peer_groups.groups_for(peer_id, peer_did).await;
+        let approved = config.auto_approve.iter().any(|rule| {
+            groups.iter().any(|g| g.name == rule.group)
+                && match &rule.memory_types {
+                    Some(types) => types.iter().any(|t| t == &memory.memory_type),
+                    None => true,
+                }
+        });
+        if approved {
+            return Ok(true);
+        }
+
+        let held = self.database.hold_pending_memory(&PendingMemory {
+            id: uuid::Uuid::new_v4().to_string(),
+            memory: memory.clone(),
+            source_peer: peer_id.to_string(),
+            source_did: peer_did.map(Did::parse).transpose()?,
+            offline_public_key: offline_public_key.map(str::to_string),
+            status: PendingStatus::Pending,
+            received_at: chrono::Utc::now().to_rfc3339(),
+            decided_at: None,
+        })?;
+        if held {
+            println!(
+                "📥 Held {} memory {} from peer {} for review",
+                memory.memory_type, memory.id, peer_id
+            );
+        }
+        Ok(false)
+    }
+
+    pub fn list(&self, status: PendingStatus) -> Result<Vec<PendingMemory>> {
+        self.database.list_pending_memories(status)
+    }
+
+    /// Store a held memory
+    pub fn approve(&self, id: &str) -> Result<PendingMemory> {
+        let approved = self
+            .database
+            .approve_pending_memory(id)?
+            .ok_or_else(|| OcmError::NotFound(format!("Pending memory {}", id)))?;
+        println!(
+            "✅ Approved memory {} from peer {}",
+            approved.memory.id, approved.source_peer
+        );
+        Ok(approved)
+    }
+
+    /// Drop a held memory; the same version is not held again if a peer resends it
+    pub fn reject(&self, id: &str) -> Result<()> {
+        if !self.database.reject_pending_memory(id)? {
+            return Err(OcmError::NotFound(format!("Pending memory {}", id)));
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,92 @@
+use chrono::{DateTime, Duration, Utc};
+use std::collections::{HashMap, VecDeque};
+use std::sync::Mutex;
+
+const MAX_SAMPLES: usize = 8; // Per peer; the median of these is the offset estimate
+const MAX_ROUND_TRIP_MS: i64 = 10_000; // Slower exchanges say too little about the offset
+const SKEW_WARNING_SECS: i64 = 60;
+
+/// Per-peer clock offsets, estimated from request/acknowledgment round trips.
+/// An offset is the peer's clock minus ours: positive means the peer runs fast.
+#[derive(Debug, Default)]
+pub struct ClockSkewTracker {
+    samples: Mutex<HashMap<String, VecDeque<i64>>>, // peer_id -> offsets in ms, newest last
+}
+
+impl ClockSkewTracker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record one exchange: we sent at `sent`, the peer stamped its reply `remote`,
+    /// and it arrived at `received`. Assumes the reply was stamped halfway through,
+    /// as NTP does. Returns the sample, or None if the round trip was too slow to trust
+    pub fn record_round_trip(
+        &self,
+        peer_id: &str,
+        sent: DateTime<Utc>,
+        remote: DateTime<Utc>,
+        received: DateTime<Utc>,
+    ) -> Option<Duration> {
+        let round_trip = received.signed_duration_since(sent);
+        if round_trip < Duration::zero() || round_trip.num_milliseconds() > MAX_ROUND_TRIP_MS {
+            return None;
+        }
+        let offset = remote.signed_duration_since(sent + round_trip / 2);
+
+        let mut samples = self.samples.lock().unwrap_or_else(|e| e.into_inner());
+        let peer_samples = samples.entry(peer_id.to_string()).or_default();
+        peer_samples.push_back(offset.num_milliseconds());
+        if peer_samples.len() > MAX_SAMPLES {
+            peer_samples.pop_front();
+        }
+
+        if offset.num_seconds().abs() >= SKEW_WARNING_SECS {
+            eprintln!(
+                "⚠️  Peer {} clock is {}s {} ours",
+                peer_id,
+                offset.num_seconds().abs(),
+                if offset > Duration::zero() {
+                    "ahead of"
+                } else {
+                    "behind"
+                }
+            );
+        }
+        Some(offset)
+    }
+
+    /// Median offset, robust to the odd delayed reply; None before the first exchange
+    pub fn offset(&self, peer_id: &str) -> Option<Duration> {
+        let samples = self.samples.lock().unwrap_or_else(|e| e.into_inner());
+        let mut offsets: Vec<i64> = samples.get(peer_id)?.iter().copied().collect();
+        offsets.sort_unstable();
+        offsets
+            .get(offsets.len() / 2)
+            .map(|ms| Duration::milliseconds(*ms))
+    }
+
+    /// Every peer's current estimate, for diagnostics
+    pub fn offsets(&self) -> Vec<(String, Duration)> {
+        let peer_ids: Vec<String> = {
+            let samples = self.samples.lock().unwrap_or_else(|e| e.into_inner());
+            samples.keys().cloned().collect()
+        };
+        peer_ids
+            .into_iter()
+            .filter_map(|peer_id| self.offset(&peer_id).map(|offset| (peer_id, offset)))
+            .collect()
+    }
+
+    /// Translate a timestamp from a peer's clock to ours, if its offset is known
+    pub fn to_local(&self, peer_id: &str, remote: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        self.offset(peer_id).map(|offset| remote - offset)
+    }
+
+    pub fn forget(&self, peer_id: &str) {
+        self.samples
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .remove(peer_id);
+    }
+}
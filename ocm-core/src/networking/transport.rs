@@ -0,0 +1,86 @@
+use super::bandwidth::BandwidthController;
+use super::framing::Framing;
+use super::protocol::{MessageType, NetworkMessage, PeerInfo};
+use futures_util::future::BoxFuture;
+use std::sync::Arc;
+use tokio::net::TcpStream;
+
+pub type TransportError = Box<dyn std::error::Error + Send + Sync>;
+
+/// Most responses a peer may send on one connection ahead of its acknowledgment
+const MAX_INLINE_REPLIES: usize = 64;
+
+/// What came back on the connection a message went out on
+#[derive(Debug, Default)]
+pub struct Delivery {
+    pub ack: Option<NetworkMessage>,
+    pub replies: Vec<NetworkMessage>, // Responses sent ahead of the ack, e.g. memories for a MemoryRequest
+}
+
+/// How outgoing messages reach a peer. Nodes use TCP; the simulation harness
+/// swaps in an in-memory network so multi-node runs are reproducible.
+pub trait Transport: Send + Sync {
+    /// Deliver one message, resolving once the peer has accepted it, to the peer's
+    /// acknowledgment and any responses when the transport carries them
+    fn send<'a>(
+        &'a self,
+        peer: &'a PeerInfo,
+        message: &'a NetworkMessage,
+    ) -> BoxFuture<'a, Result<Delivery, TransportError>>;
+}
+
+/// One connection per message: length-prefixed JSON, fragmented when larger than a
+/// frame, then read the peer's responses until its ack
+pub struct TcpTransport {
+    bandwidth: Arc<BandwidthController>,
+    framing: Arc<Framing>,
+}
+
+impl TcpTransport {
+    pub fn new(bandwidth: Arc<BandwidthController>, framing: Arc<Framing>) -> Self {
+        Self { bandwidth, framing }
+    }
+}
+
+impl Transport for TcpTransport {
+    fn send<'a>(
+        &'a self,
+        peer: &'a PeerInfo,
+        message: &'a NetworkMessage,
+    ) -> BoxFuture<'a, Result<Delivery, TransportError>> {
+        Box::pin(async move {
+            let addr = format!("{}:{}", peer.address, peer.port);
+            let mut stream = TcpStream::connect(&addr).await?;
+
+            // Same framing as handle_connection
+            self.framing
+                .write_message(&mut stream, &self.bandwidth, message, Some(peer))
+                .await?;
+
+            // Responses come back on this connection ahead of the acknowledgment
+            let delivery = tokio::time::timeout(std::time::Duration::from_secs(30), async {
+                let mut delivery = Delivery::default();
+                let mut reassembler = self.framing.reassembler();
+                loop {
+                    let reply = self
+                        .framing
+                        .read_message(&mut stream, &self.bandwidth, &mut reassembler)
+                        .await?
+                        .ok_or("Connection closed before the acknowledgment")?;
+
+                    if matches!(reply.message_type, MessageType::Pong) {
+                        delivery.ack = Some(reply);
+                        return Ok::<Delivery, TransportError>(delivery);
+                    }
+                    if delivery.replies.len() >= MAX_INLINE_REPLIES {
+                        return Err("Too many responses before the acknowledgment".into());
+                    }
+                    delivery.replies.push(reply);
+                }
+            })
+            .await??;
+
+            Ok(delivery)
+        })
+    }
+}
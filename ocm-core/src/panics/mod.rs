@@ -0,0 +1,170 @@
+//! Panic telemetry. A process-wide hook records every panic, including those in tasks
+//! nobody awaits, in the activity feed and forwards it to a telemetry sink when one is
+//! configured. `catch_panic` and `catch_panic_async` turn panics at module boundaries
+//! into OcmError::OperationFailed.
+
+use crate::config::TelemetryConfig;
+use crate::core::error::OcmError;
+use crate::core::models::PANIC_EVENT;
+use crate::persistence::database::Database;
+use crate::supervisor::{RestartPolicy, TaskSupervisor};
+use futures_util::FutureExt;
+use serde::Serialize;
+use std::any::Any;
+use std::future::Future;
+use std::panic::AssertUnwindSafe;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, OnceLock};
+use std::time::Duration;
+use tokio::sync::{mpsc, Mutex};
+
+static PANICS: AtomicU64 = AtomicU64::new(0);
+static REPORTS: OnceLock<mpsc::UnboundedSender<PanicReport>> = OnceLock::new();
+
+/// A panic as the hook saw it
+#[derive(Debug, Clone, Serialize)]
+pub struct PanicReport {
+    pub process: String,
+    pub thread: String,
+    pub location: Option<String>, // file:line:column
+    pub message: String,
+    pub occurred_at: String,
+}
+
+/// Reports queued by the hook until `start` records them
+pub struct PanicReports {
+    receiver: Arc<Mutex<mpsc::UnboundedReceiver<PanicReport>>>,
+}
+
+/// Install the panic hook for this process; the default hook still prints each panic.
+/// Only the first call installs it, later ones return None
+pub fn install_hook(process: &str) -> Option<PanicReports> {
+    let (sender, receiver) = mpsc::unbounded_channel();
+    REPORTS.set(sender).ok()?;
+
+    let process = process.to_string();
+    let default_hook = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        default_hook(info);
+        PANICS.fetch_add(1, Ordering::Relaxed);
+
+        let report = PanicReport {
+            process: process.clone(),
+            thread: std::thread::current()
+                .name()
+                .unwrap_or("unnamed")
+                .to_string(),
+            location: info.location().map(|location| {
+                format!(
+                    "{}:{}:{}",
+                    location.file(),
+                    location.line(),
+                    location.column()
+                )
+            }),
+            message: panic_message(info.payload()),
+            occurred_at: chrono::Utc::now().to_rfc3339(),
+        };
+        // Queued rather than stored here, as the panicking thread may hold the database
+        if let Some(reports) = REPORTS.get() {
+            let _ = reports.send(report);
+        }
+    }));
+
+    Some(PanicReports {
+        receiver: Arc::new(Mutex::new(receiver)),
+    })
+}
+
+impl PanicReports {
+    /// Record queued and future panics as activity events, and send them to the
+    /// configured telemetry sink
+    pub fn start(
+        self,
+        database: Arc<Database>,
+        config: &TelemetryConfig,
+        supervisor: &TaskSupervisor,
+    ) -> crate::core::error::Result<()> {
+        let http = reqwest::Client::builder()
+            .timeout(Duration::from_secs(config.timeout_seconds))
+            .build()?;
+        let sink = config.panic_sink_url.clone();
+        let receiver = self.receiver;
+
+        supervisor.spawn("panic_reports", RestartPolicy::Always, move || {
+            let receiver = receiver.clone();
+            let database = database.clone();
+            let http = http.clone();
+            let sink = sink.clone();
+            async move {
+                let mut receiver = receiver.lock().await;
+                while let Some(report) = receiver.recv().await {
+                    let payload = serde_json::to_value(&report).unwrap_or_default();
+                    if let Err(e) = database.record_activity_event(PANIC_EVENT, &payload) {
+                        eprintln!("Failed to record panic: {}", e);
+                    }
+
+                    let Some(url) = &sink else {
+                        continue;
+                    };
+                    let sent = http
+                        .post(url)
+                        .json(&report)
+                        .send()
+                        .await
+                        .and_then(|response| response.error_for_status());
+                    if let Err(e) = sent {
+                        eprintln!("Failed to send panic report to telemetry sink: {}", e);
+                    }
+                }
+            }
+        });
+        Ok(())
+    }
+}
+
+/// Panics in this process since it started, caught or not
+pub fn panic_count() -> u64 {
+    PANICS.load(Ordering::Relaxed)
+}
+
+/// Run `operation`, turning a panic inside it into OcmError::OperationFailed, so a
+/// caller in another module gets an error instead of losing its thread
+pub fn catch_panic<T, E: From<OcmError>>(
+    operation: &str,
+    f: impl FnOnce() -> Result<T, E>,
+) -> Result<T, E> {
+    std::panic::catch_unwind(AssertUnwindSafe(f))
+        .unwrap_or_else(|payload| Err(panicked(operation, payload.as_ref()).into()))
+}
+
+/// `catch_panic` for futures
+pub async fn catch_panic_async<T, E, F>(operation: &str, future: F) -> Result<T, E>
+where
+    F: Future<Output = Result<T, E>>,
+    E: From<OcmError>,
+{
+    AssertUnwindSafe(future)
+        .catch_unwind()
+        .await
+        .unwrap_or_else(|payload| Err(panicked(operation, payload.as_ref()).into()))
+}
+
+fn panicked(operation: &str, payload: &(dyn Any + Send)) -> OcmError {
+    OcmError::OperationFailed(format!(
+        "{} panicked: {}",
+        operation,
+        panic_message(payload)
+    ))
+}
+
+/// The message a panic was raised with, if it was a string
+pub fn panic_message(payload: &(dyn Any + Send)) -> String {
+    if let Some(message) = payload.downcast_ref::<&str>() {
+        message.to_string()
+    } else if let Some(message) = payload.downcast_ref::<String>() {
+        message.clone()
+    } else {
+        "non-string panic payload".to_string()
+    }
+}
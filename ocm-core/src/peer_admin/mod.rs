@@ -0,0 +1,127 @@
+//! `ocm-core peers`: lists the peers a node has recorded and bans, trusts or
+//! disconnects them. Changes are written to peer_record, where a running node picks
+//! them up within a few seconds.
+
+use crate::config::OcmConfig;
+use crate::core::error::{OcmError, Result};
+use crate::core::ids::PeerId;
+use crate::core::models::{PeerRecord, PeerStanding};
+use crate::core::redact::Redacted;
+use crate::persistence::Database;
+
+const USAGE: &str = "Usage: ocm-core peers [list | show ID | ban ID [--reason R] | trust ID [--reason R] | reset ID | disconnect ID] [--json]
+
+ban drops the peer and ignores it from now on; trust stores its memories without
+inbound review; reset clears either. A running node applies changes within 10 seconds.";
+
+#[derive(Debug, Clone)]
+pub enum PeerCommand {
+    List,
+    Show(PeerId),
+    Standing(PeerId, PeerStanding),
+    Disconnect(PeerId),
+}
+
+#[derive(Debug, Clone)]
+pub struct PeerOptions {
+    pub command: PeerCommand,
+    pub reason: Option<String>,
+    pub json: bool,
+}
+
+impl PeerOptions {
+    /// Parse the arguments following `peers`
+    pub fn from_args(args: &[String]) -> std::result::Result<Self, &'static str> {
+        let reason = args
+            .iter()
+            .position(|arg| arg == "--reason")
+            .and_then(|i| args.get(i + 1))
+            .cloned();
+        let peer_id = || {
+            args.get(1)
+                .filter(|id| !id.starts_with("--"))
+                .and_then(|id| PeerId::parse(id).ok())
+        };
+        let command = match args.first().map(String::as_str) {
+            None | Some("list") => PeerCommand::List,
+            Some("show") => PeerCommand::Show(peer_id().ok_or(USAGE)?),
+            Some("ban") => PeerCommand::Standing(peer_id().ok_or(USAGE)?, PeerStanding::Banned),
+            Some("trust") => PeerCommand::Standing(peer_id().ok_or(USAGE)?, PeerStanding::Trusted),
+            Some("reset") => PeerCommand::Standing(peer_id().ok_or(USAGE)?, PeerStanding::Neutral),
+            Some("disconnect") => PeerCommand::Disconnect(peer_id().ok_or(USAGE)?),
+            Some("--json") if args.len() == 1 => PeerCommand::List,
+            Some(_) => return Err(USAGE),
+        };
+        Ok(Self {
+            command,
+            reason,
+            json: args.iter().any(|arg| arg == "--json"),
+        })
+    }
+}
+
+/// Carry out the command, returning the peers it listed or changed
+pub fn run(config: &OcmConfig, options: &PeerOptions) -> Result<Vec<PeerRecord>> {
+    let path = config.database.path.to_string_lossy();
+    match &options.command {
+        PeerCommand::List => Database::open_read_only(&path)?.list_peer_records(),
+        PeerCommand::Show(peer_id) => {
+            let database = Database::open_read_only(&path)?;
+            Ok(vec![known_peer(&database, peer_id)?])
+        }
+        PeerCommand::Standing(peer_id, standing) => {
+            let database = Database::new(&path)?;
+            database.set_peer_standing(peer_id, *standing, options.reason.as_deref())?;
+            Ok(vec![known_peer(&database, peer_id)?])
+        }
+        PeerCommand::Disconnect(peer_id) => {
+            let database = Database::new(&path)?;
+            if !database.request_peer_disconnect(peer_id)? {
+                return Err(OcmError::NotFound(format!("Peer {}", peer_id)));
+            }
+            Ok(vec![known_peer(&database, peer_id)?])
+        }
+    }
+}
+
+fn known_peer(database: &Database, peer_id: &PeerId) -> Result<PeerRecord> {
+    database
+        .get_peer_record(peer_id)?
+        .ok_or_else(|| OcmError::NotFound(format!("Peer {}", peer_id)))
+}
+
+pub fn print(peers: &[PeerRecord]) {
+    if peers.is_empty() {
+        println!("No peers recorded yet");
+        return;
+    }
+    println!(
+        "{:<38} {:<18} {:<9} {:<8} {:>4} {:>8} {:>8} {:>6} {:>6}  LAST SEEN",
+        "PEER", "DID", "STATE", "STANDING", "REP", "SENT", "RECEIVED", "FAILED", "REJECT",
+    );
+    for peer in peers {
+        println!(
+            "{:<38} {:<18} {:<9} {:<8} {:>4} {:>8} {:>8} {:>6} {:>6}  {}",
+            peer.peer_id,
+            peer.did
+                .as_deref()
+                .map(|did| Redacted::did(did).to_string())
+                .unwrap_or_else(|| "-".to_string()),
+            if peer.connected {
+                "connected"
+            } else {
+                "offline"
+            },
+            peer.standing.as_str(),
+            peer.reputation,
+            peer.traffic.messages_sent,
+            peer.traffic.messages_received,
+            peer.traffic.send_failures,
+            peer.traffic.rejected,
+            peer.last_seen.as_deref().unwrap_or("never"),
+        );
+        if let Some(reason) = &peer.standing_reason {
+            println!("    {}: {}", peer.standing.as_str(), reason);
+        }
+    }
+}
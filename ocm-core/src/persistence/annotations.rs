@@ -0,0 +1,110 @@
+use crate::core::error::{OcmError, Result};
+use crate::core::ids::MemoryId;
+use crate::core::memory_types::MemoryTypeRegistry;
+use crate::core::models::{
+    AnnotationData, AnnotationKind, AnnotationTargetStatus, AnnotationThread, SignedMemory,
+    ANNOTATION_MEMORY_TYPE,
+};
+use crate::identity::plc::PlcIdentity;
+use crate::persistence::database::Database;
+use std::sync::Arc;
+
+/// Replies nested deeper than this are not expanded
+const MAX_THREAD_DEPTH: usize = 16;
+
+/// Notes and corrections on memories. The annotated memory is never modified; an
+/// annotation pins the content hash it was written against so readers can tell
+/// whether it still applies
+pub struct AnnotationService {
+    db: Arc<Database>,
+    memory_types: Arc<MemoryTypeRegistry>,
+    signer: PlcIdentity,
+}
+
+impl AnnotationService {
+    pub fn new(
+        db: Arc<Database>,
+        memory_types: Arc<MemoryTypeRegistry>,
+        signer: PlcIdentity,
+    ) -> Self {
+        Self {
+            db,
+            memory_types,
+            signer,
+        }
+    }
+
+    /// Annotate the memory as currently held; annotate an annotation to reply to it
+    pub fn annotate(
+        &self,
+        target_memory_id: &MemoryId,
+        kind: AnnotationKind,
+        body: &str,
+    ) -> Result<SignedMemory> {
+        let target = self
+            .db
+            .get_signed_memory(target_memory_id)?
+            .ok_or_else(|| OcmError::NotFound(format!("Memory {}", target_memory_id)))?;
+        if target.is_tombstone() {
+            return Err(OcmError::Validation(format!(
+                "Memory {} was erased",
+                target_memory_id
+            )));
+        }
+
+        let data = AnnotationData {
+            target_memory_id: target.id,
+            target_content_hash: target.content_hash,
+            kind,
+            body: body.to_string(),
+        };
+        let mut memory = self.memory_types.create_memory(
+            &self.signer.did,
+            ANNOTATION_MEMORY_TYPE,
+            &serde_json::to_string(&data)?,
+        )?;
+        self.signer
+            .sign_memory(&mut memory)
+            .map_err(|e| OcmError::Cryptography(e.to_string()))?;
+        self.db.create_outbound_signed_memory(&memory)?;
+        Ok(memory)
+    }
+
+    /// Check an annotation's pinned hash against the target as held now
+    pub fn verify_target(&self, data: &AnnotationData) -> Result<AnnotationTargetStatus> {
+        Ok(match self.db.get_signed_memory(&data.target_memory_id)? {
+            Some(target) if target.content_hash == data.target_content_hash => {
+                AnnotationTargetStatus::Matches
+            }
+            Some(_) => AnnotationTargetStatus::Changed,
+            None => AnnotationTargetStatus::Missing,
+        })
+    }
+
+    /// Annotations on a memory with their replies, oldest first at every level
+    pub fn list_annotations(&self, memory_id: &MemoryId) -> Result<Vec<AnnotationThread>> {
+        self.threads(memory_id, 0)
+    }
+
+    fn threads(&self, memory_id: &MemoryId, depth: usize) -> Result<Vec<AnnotationThread>> {
+        if depth >= MAX_THREAD_DEPTH {
+            return Ok(Vec::new());
+        }
+
+        let mut threads = Vec::new();
+        for annotation in self.db.list_annotations_for(memory_id)? {
+            let Ok(data) = serde_json::from_str::<AnnotationData>(&annotation.memory_data) else {
+                continue;
+            };
+            let target_status = self.verify_target(&data)?;
+            let replies = self.threads(&annotation.id, depth + 1)?;
+            threads.push(AnnotationThread {
+                annotation,
+                data,
+                target_status,
+                replies,
+            });
+        }
+        Ok(threads)
+    }
+}
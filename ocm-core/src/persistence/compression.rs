@@ -0,0 +1,148 @@
+use crate::config::CompressionConfig;
+use crate::core::error::{OcmError, Result};
+use rusqlite::functions::FunctionFlags;
+use rusqlite::Connection;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::io::Read;
+use std::sync::{Arc, RwLock};
+
+/// SQL function returning a memory_content body as text:
+/// `ocm_content(memory_data, dictionary_id)`
+pub const CONTENT_FUNCTION: &str = "ocm_content";
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct CompressionReport {
+    pub dictionaries_trained: usize,
+    pub bodies_compressed: usize,
+    pub bytes_before: usize,
+    pub bytes_after: usize,
+}
+
+/// zstd dictionaries of one database. Every connection can read compressed bodies
+/// through [`CONTENT_FUNCTION`]; new bodies are only compressed once enabled
+#[derive(Default)]
+pub struct ContentCompression {
+    dictionaries: RwLock<HashMap<i64, Arc<Vec<u8>>>>,
+    newest: RwLock<HashMap<String, i64>>, // memory_type -> dictionary new bodies use
+    config: RwLock<Option<CompressionConfig>>,
+}
+
+impl ContentCompression {
+    /// Register [`CONTENT_FUNCTION`] on a connection and load the stored dictionaries.
+    /// Databases not yet migrated have none
+    pub fn attach(self: &Arc<Self>, conn: &Connection) -> Result<()> {
+        let compression = self.clone();
+        conn.create_scalar_function(
+            CONTENT_FUNCTION,
+            2,
+            FunctionFlags::SQLITE_UTF8 | FunctionFlags::SQLITE_DETERMINISTIC,
+            move |ctx| {
+                let Some(dictionary_id) = ctx.get::<Option<i64>>(1)? else {
+                    return ctx.get::<String>(0);
+                };
+                let compressed: Vec<u8> = ctx.get(0)?;
+                compression
+                    .decompress(dictionary_id, &compressed)
+                    .map_err(|e| rusqlite::Error::UserFunctionError(e.to_string().into()))
+            },
+        )?;
+
+        let migrated: bool = conn.query_row(
+            "SELECT COUNT(*) > 0 FROM sqlite_master WHERE type = 'table' AND name = 'compression_dictionary'",
+            [],
+            |row| row.get(0),
+        )?;
+        if migrated {
+            let mut stmt = conn.prepare(
+                "SELECT id, memory_type, dictionary FROM compression_dictionary ORDER BY id",
+            )?;
+            let rows = stmt.query_map([], |row| {
+                Ok((row.get::<_, i64>(0)?, row.get::<_, String>(1)?, row.get(2)?))
+            })?;
+            for row in rows {
+                let (id, memory_type, dictionary) = row?;
+                self.add(id, &memory_type, dictionary);
+            }
+        }
+        Ok(())
+    }
+
+    pub fn enable(&self, config: CompressionConfig) {
+        *self.config.write().unwrap_or_else(|e| e.into_inner()) = Some(config);
+    }
+
+    /// The settings in force, if compression is enabled
+    pub fn config(&self) -> Option<CompressionConfig> {
+        self.config
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .clone()
+            .filter(|config| config.enabled)
+    }
+
+    /// Make a dictionary available for reading, and for compressing its type's bodies
+    pub fn add(&self, id: i64, memory_type: &str, dictionary: Vec<u8>) {
+        self.dictionaries
+            .write()
+            .unwrap_or_else(|e| e.into_inner())
+            .insert(id, Arc::new(dictionary));
+        self.newest
+            .write()
+            .unwrap_or_else(|e| e.into_inner())
+            .insert(memory_type.to_string(), id);
+    }
+
+    pub fn has_dictionary(&self, memory_type: &str) -> bool {
+        self.newest
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .contains_key(memory_type)
+    }
+
+    /// `data` compressed with its type's dictionary, and that dictionary's id. None when
+    /// compression is off, the type has no dictionary yet or compressing saves nothing
+    pub fn compress(&self, memory_type: &str, data: &str) -> Result<Option<(i64, Vec<u8>)>> {
+        let Some(config) = self.config() else {
+            return Ok(None);
+        };
+        let Some(id) = self
+            .newest
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .get(memory_type)
+            .copied()
+        else {
+            return Ok(None);
+        };
+        let dictionary = self.dictionary(id)?;
+        let compressed = zstd::bulk::Compressor::with_dictionary(config.level, &dictionary)?
+            .compress(data.as_bytes())?;
+        if compressed.len() >= data.len() {
+            return Ok(None);
+        }
+        Ok(Some((id, compressed)))
+    }
+
+    pub fn decompress(&self, dictionary_id: i64, compressed: &[u8]) -> Result<String> {
+        let dictionary = self.dictionary(dictionary_id)?;
+        let mut data = String::new();
+        zstd::stream::read::Decoder::with_dictionary(compressed, &dictionary)?
+            .read_to_string(&mut data)?;
+        Ok(data)
+    }
+
+    fn dictionary(&self, id: i64) -> Result<Arc<Vec<u8>>> {
+        self.dictionaries
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .get(&id)
+            .cloned()
+            .ok_or_else(|| OcmError::NotFound(format!("Compression dictionary {}", id)))
+    }
+}
+
+/// Train a dictionary of at most `max_bytes` from sample bodies
+pub fn train_dictionary(samples: &[String], max_bytes: usize) -> Result<Vec<u8>> {
+    Ok(zstd::dict::from_samples(samples, max_bytes)?)
+}
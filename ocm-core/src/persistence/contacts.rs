@@ -0,0 +1,351 @@
+use crate::core::error::{OcmError, Result};
+use crate::core::ids::Did;
+use crate::core::models::{
+    normalize_tag, Contact, ContactData, ContactVerification, SafetyNumber, SignedMemory,
+    CONTACT_MEMORY_TYPE, MEMORY_STORED_EVENT,
+};
+use crate::core::redact::Redacted;
+use crate::identity::plc::{key_fingerprint, PlcDirectory, PlcIdentity};
+use crate::persistence::database::Database;
+use crate::supervisor::{RestartPolicy, TaskSupervisor};
+use base64::{engine::general_purpose, Engine as _};
+use ocm_protocol::safety::{safety_number, safety_numbers_match};
+use std::sync::Arc;
+use tokio::sync::Mutex;
+
+const EVENT_BATCH: usize = 100;
+const POLL_INTERVAL_MS: u64 = 1000;
+
+/// Changes to a contact; None leaves a field as it is
+#[derive(Debug, Clone, Default, serde::Deserialize)]
+pub struct ContactUpdate {
+    pub display_name: Option<String>,
+    pub notes: Option<String>,
+    pub groups: Option<Vec<String>>,
+}
+
+/// The local identity's contact book. Every change is a signed contact memory carrying
+/// the contact's full state; contact memories are private, so they reach the
+/// identity's other devices and no one else
+pub struct ContactService {
+    db: Arc<Database>,
+    signer: PlcIdentity,
+    directory: Mutex<PlcDirectory>,
+}
+
+impl ContactService {
+    pub fn new(db: Arc<Database>, signer: PlcIdentity) -> Self {
+        Self {
+            db,
+            signer,
+            directory: Mutex::new(PlcDirectory::new()),
+        }
+    }
+
+    /// Add a contact, pinning the key its DID currently publishes (trust on first use)
+    pub async fn add(&self, did: &Did, update: ContactUpdate) -> Result<Contact> {
+        if !did.starts_with("did:") {
+            return Err(OcmError::Validation(format!("{} is not a DID", did)));
+        }
+        if *did == self.signer.did {
+            return Err(OcmError::Validation(
+                "Your own DID cannot be a contact".to_string(),
+            ));
+        }
+        if self.db.get_contact(did)?.is_some() {
+            return Err(OcmError::AlreadyExists(format!(
+                "Contact {}",
+                Redacted::did(did)
+            )));
+        }
+
+        let key_fingerprint = self.resolve_fingerprint(did).await;
+        let data = ContactData {
+            contact_did: did.clone(),
+            display_name: update.display_name.unwrap_or_default().trim().to_string(),
+            verification: ContactVerification::Tofu,
+            key_fingerprint,
+            notes: update.notes.unwrap_or_default(),
+            groups: normalize_groups(update.groups.unwrap_or_default())?,
+            removed: false,
+        };
+        self.record_existing(data)
+    }
+
+    pub fn update(&self, did: &Did, update: ContactUpdate) -> Result<Contact> {
+        let contact = self.get(did)?;
+        let data = ContactData {
+            contact_did: contact.did,
+            display_name: update
+                .display_name
+                .map(|name| name.trim().to_string())
+                .unwrap_or(contact.display_name),
+            verification: contact.verification,
+            key_fingerprint: contact.key_fingerprint,
+            notes: update.notes.unwrap_or(contact.notes),
+            groups: match update.groups {
+                Some(groups) => normalize_groups(groups)?,
+                None => contact.groups,
+            },
+            removed: false,
+        };
+        self.record_existing(data)
+    }
+
+    /// Mark a contact verified after the user compared `fingerprint` out of band.
+    /// The fingerprint must match the key the DID publishes now
+    pub async fn verify(&self, did: &Did, fingerprint: &str) -> Result<Contact> {
+        let contact = self.get(did)?;
+        let current = self.resolve_fingerprint(did).await.ok_or_else(|| {
+            OcmError::Plc(format!("Cannot resolve the key of {}", Redacted::did(did)))
+        })?;
+        if !current.eq_ignore_ascii_case(fingerprint.trim()) {
+            return Err(OcmError::Validation(
+                "Fingerprint does not match the contact's current key".to_string(),
+            ));
+        }
+
+        self.record_existing(ContactData {
+            verification: ContactVerification::Verified,
+            key_fingerprint: Some(current),
+            ..contact_data(contact)
+        })
+    }
+
+    /// The safety number to compare with a contact. If the contact's key changed since
+    /// it was pinned, the new key is pinned and a verified contact drops back to TOFU
+    pub async fn safety_number(&self, did: &Did) -> Result<SafetyNumber> {
+        let contact = self.get(did)?;
+        let their_key = self.resolve_key(did).await.ok_or_else(|| {
+            OcmError::Plc(format!("Cannot resolve the key of {}", Redacted::did(did)))
+        })?;
+        let fingerprint = key_fingerprint(&their_key);
+        let key_changed = contact
+            .key_fingerprint
+            .as_deref()
+            .is_some_and(|pinned| pinned != fingerprint);
+
+        let contact = if contact.key_fingerprint.as_deref() == Some(fingerprint.as_str()) {
+            contact
+        } else {
+            if key_changed {
+                println!(
+                    "⚠️  The key of contact {} changed; compare safety numbers again",
+                    Redacted::did(did)
+                );
+            }
+            let verification = if key_changed {
+                ContactVerification::Tofu
+            } else {
+                contact.verification
+            };
+            self.record_existing(ContactData {
+                verification,
+                key_fingerprint: Some(fingerprint),
+                ..contact_data(contact)
+            })?
+        };
+
+        Ok(SafetyNumber {
+            did: contact.did,
+            safety_number: safety_number(&self.signer.did, &self.own_key()?, did, &their_key),
+            verification: contact.verification,
+            key_changed,
+        })
+    }
+
+    /// Mark a contact verified once the user confirmed both sides show the same safety number
+    pub async fn confirm_safety_number(&self, did: &Did, entered: &str) -> Result<Contact> {
+        let expected = self.safety_number(did).await?;
+        if !safety_numbers_match(&expected.safety_number, entered) {
+            return Err(OcmError::Validation(
+                "Safety number does not match; the contact's key may not be theirs".to_string(),
+            ));
+        }
+
+        let contact = self.get(did)?;
+        let contact = self.record_existing(ContactData {
+            verification: ContactVerification::Verified,
+            ..contact_data(contact)
+        })?;
+        println!("✅ Verified contact {}", Redacted::did(did));
+        Ok(contact)
+    }
+
+    pub fn remove(&self, did: &Did) -> Result<()> {
+        let contact = self.get(did)?;
+        self.record(ContactData {
+            notes: String::new(),
+            groups: Vec::new(),
+            removed: true,
+            ..contact_data(contact)
+        })?;
+        println!("📇 Removed contact {}", Redacted::did(did));
+        Ok(())
+    }
+
+    pub fn get(&self, did: &Did) -> Result<Contact> {
+        self.db
+            .get_contact(did)?
+            .ok_or_else(|| OcmError::NotFound(format!("Contact {}", Redacted::did(did))))
+    }
+
+    pub fn list(&self, group: Option<&str>) -> Result<Vec<Contact>> {
+        match group {
+            Some(group) => {
+                let group = normalize_tag(group).map_err(OcmError::Validation)?;
+                self.db.list_contacts(Some(&group))
+            }
+            None => self.db.list_contacts(None),
+        }
+    }
+
+    /// Sign and store the contact's new state, returning the contact as it now stands
+    fn record(&self, data: ContactData) -> Result<Option<Contact>> {
+        if !data.removed && data.display_name.is_empty() {
+            return Err(OcmError::Validation(
+                "Contact display name is required".to_string(),
+            ));
+        }
+        let mut memory = SignedMemory::new(
+            &self.signer.did,
+            CONTACT_MEMORY_TYPE,
+            &serde_json::to_string(&data)?,
+        );
+        self.signer
+            .sign_memory(&mut memory)
+            .map_err(|e| OcmError::Cryptography(e.to_string()))?;
+        self.db.create_outbound_signed_memory(&memory)?;
+        self.apply(&memory)?;
+        self.db.get_contact(&data.contact_did)
+    }
+
+    fn record_existing(&self, data: ContactData) -> Result<Contact> {
+        let did = data.contact_did.clone();
+        self.record(data)?
+            .ok_or_else(|| OcmError::NotFound(format!("Contact {}", Redacted::did(&did))))
+    }
+
+    /// Apply a contact memory written on any of the identity's devices; later changes win.
+    /// Contact memories by other DIDs are not ours and are ignored
+    pub fn apply(&self, memory: &SignedMemory) -> Result<bool> {
+        if memory.memory_type != CONTACT_MEMORY_TYPE || memory.did != self.signer.did {
+            return Ok(false);
+        }
+        let data: ContactData = serde_json::from_str(&memory.memory_data)
+            .map_err(|e| OcmError::Validation(format!("Not a contact: {}", e)))?;
+        self.db.apply_contact(&data, &memory.timestamp, &memory.id)
+    }
+
+    async fn resolve_fingerprint(&self, did: &str) -> Option<String> {
+        self.resolve_key(did).await.map(|key| key_fingerprint(&key))
+    }
+
+    async fn resolve_key(&self, did: &str) -> Option<[u8; 32]> {
+        let key = {
+            let mut directory = self.directory.lock().await;
+            directory
+                .resolve_public_key(did)
+                .await
+                .map_err(|e| e.to_string())
+        };
+        match key {
+            Ok(key) => key,
+            Err(e) => {
+                eprintln!(
+                    "⚠️  Failed to resolve the key of {}: {}",
+                    Redacted::did(did),
+                    e
+                );
+                None
+            }
+        }
+    }
+
+    fn own_key(&self) -> Result<[u8; 32]> {
+        general_purpose::STANDARD
+            .decode(&self.signer.keypair.public_key)?
+            .try_into()
+            .map_err(|_| OcmError::Cryptography("Public key is not 32 bytes".to_string()))
+    }
+
+    /// Apply contact changes made on the identity's other devices as they sync in
+    pub fn start(self: Arc<Self>, supervisor: &TaskSupervisor) {
+        supervisor.spawn("contacts", RestartPolicy::Always, move || {
+            let service = self.clone();
+            async move {
+                let mut cursor = match service.db.latest_activity_event_id() {
+                    Ok(event_id) => event_id,
+                    Err(e) => {
+                        eprintln!("❌ Contacts cannot read the activity feed: {}", e);
+                        return;
+                    }
+                };
+                let mut interval =
+                    tokio::time::interval(tokio::time::Duration::from_millis(POLL_INTERVAL_MS));
+
+                loop {
+                    interval.tick().await;
+
+                    let events = match service.db.list_activity_events_after(
+                        cursor,
+                        Some(&service.signer.did),
+                        Some(CONTACT_MEMORY_TYPE),
+                        EVENT_BATCH,
+                    ) {
+                        Ok(events) => events,
+                        Err(e) => {
+                            eprintln!("⚠️  Contacts failed to read events: {}", e);
+                            continue;
+                        }
+                    };
+                    let Some(last) = events.last() else {
+                        continue;
+                    };
+                    cursor = last.event_id;
+
+                    for event in events
+                        .iter()
+                        .filter(|event| event.event_type == MEMORY_STORED_EVENT)
+                    {
+                        let Some(memory_id) = &event.memory_id else {
+                            continue;
+                        };
+                        let applied =
+                            service.db.get_signed_memory(memory_id).and_then(
+                                |memory| match memory {
+                                    Some(memory) => service.apply(&memory),
+                                    None => Ok(false),
+                                },
+                            );
+                        if let Err(e) = applied {
+                            eprintln!("⚠️  Failed to apply contact change {}: {}", memory_id, e);
+                        }
+                    }
+                }
+            }
+        });
+    }
+}
+
+fn normalize_groups(groups: Vec<String>) -> Result<Vec<String>> {
+    let mut normalized = groups
+        .iter()
+        .map(|group| normalize_tag(group).map_err(OcmError::Validation))
+        .collect::<Result<Vec<_>>>()?;
+    normalized.sort();
+    normalized.dedup();
+    Ok(normalized)
+}
+
+fn contact_data(contact: Contact) -> ContactData {
+    ContactData {
+        contact_did: contact.did,
+        display_name: contact.display_name,
+        verification: contact.verification,
+        key_fingerprint: contact.key_fingerprint,
+        notes: contact.notes,
+        groups: contact.groups,
+        removed: false,
+    }
+}
@@ -0,0 +1,154 @@
+//! Kills a process writing signed memories at random points and checks what it left.
+//! The writer is this test binary run again with `CHILD_DATABASE` set
+
+use crate::core::ids::{Did, MemoryId};
+use crate::core::models::SignedMemory;
+use crate::persistence::database::Database;
+use crate::persistence::migrations::run_migrations;
+use std::collections::{HashMap, HashSet};
+use std::process::{Command, Stdio};
+use std::time::Duration;
+
+const CHILD_DATABASE: &str = "OCM_CRASH_TEST_DATABASE";
+const BATCH_SIZE: usize = 10;
+const KILLS: u64 = 6;
+
+fn temp_database_path() -> String {
+    std::env::temp_dir()
+        .join(format!("ocm-crash-{}.db", uuid::Uuid::new_v4()))
+        .to_string_lossy()
+        .to_string()
+}
+
+fn remove_database(path: &str) {
+    for suffix in ["", "-wal", "-shm", ".pepper"] {
+        let _ = std::fs::remove_file(format!("{}{}", path, suffix));
+    }
+}
+
+/// The child side: writes single memories and batches until killed. Does nothing
+/// when run as an ordinary test
+#[test]
+fn crash_writer() {
+    let Ok(path) = std::env::var(CHILD_DATABASE) else {
+        return;
+    };
+    let database = Database::new(&path).expect("Failed to open database");
+    let padding = "x".repeat(8 * 1024); // Large enough that writes span several pages
+
+    for batch in 0..100_000u64 {
+        let writer = std::process::id();
+        let memories: Vec<SignedMemory> = (0..BATCH_SIZE)
+            .map(|index| {
+                let data = serde_json::json!({
+                    "writer": writer,
+                    "batch": batch,
+                    "index": index,
+                    "padding": padding,
+                });
+                SignedMemory::new(
+                    &Did::parse("did:plc:crashtest").unwrap(),
+                    "crash_test",
+                    &data.to_string(),
+                )
+            })
+            .collect();
+        if batch % 2 == 0 {
+            database
+                .create_outbound_signed_memories(&memories)
+                .expect("Failed to write batch");
+        } else {
+            for memory in &memories {
+                database
+                    .create_outbound_signed_memory(memory)
+                    .expect("Failed to write memory");
+            }
+        }
+    }
+}
+
+/// Every stored memory must be whole and queued for broadcast, and every batch must
+/// be stored entirely or not at all
+fn check_database(path: &str) -> usize {
+    let database = Database::new(path).expect("Failed to reopen database");
+    assert!(database.integrity_check().unwrap().is_empty());
+
+    let memories = database.list_memories_by_type("crash_test").unwrap();
+    let queued: HashSet<MemoryId> = database
+        .list_pending_outbox(1_000_000)
+        .unwrap()
+        .into_iter()
+        .map(|entry| entry.memory_id)
+        .collect();
+
+    let mut batches: HashMap<(u64, u64), usize> = HashMap::new();
+    for memory in &memories {
+        assert!(memory.verify_hash(), "Memory {} is torn", memory.id);
+        assert!(
+            queued.contains(&memory.id),
+            "Memory {} was never queued",
+            memory.id
+        );
+        let data: serde_json::Value = serde_json::from_str(&memory.memory_data).unwrap();
+        let key = (
+            data["writer"].as_u64().unwrap(),
+            data["batch"].as_u64().unwrap(),
+        );
+        *batches.entry(key).or_default() += 1;
+    }
+    for ((_, batch), count) in batches {
+        if batch % 2 == 0 {
+            assert_eq!(count, BATCH_SIZE, "Batch {} was partly stored", batch);
+        }
+    }
+    memories.len()
+}
+
+#[test]
+fn test_killed_writer_never_leaves_half_persisted_memories() {
+    let path = temp_database_path();
+    run_migrations(&path).expect("Failed to migrate database");
+    let exe = std::env::current_exe().unwrap();
+
+    let mut stored = 0;
+    for kill in 0..KILLS {
+        let mut child = Command::new(&exe)
+            .args(["crash_recovery::crash_writer", "--test-threads=1"])
+            .env(CHILD_DATABASE, &path)
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn()
+            .expect("Failed to start writer");
+        std::thread::sleep(Duration::from_millis(150 + 70 * kill));
+        child.kill().expect("Failed to kill writer");
+        child.wait().unwrap();
+
+        let now_stored = check_database(&path);
+        assert!(now_stored >= stored, "Committed memories were lost");
+        stored = now_stored;
+    }
+    assert!(stored > 0, "The writer never committed anything");
+    remove_database(&path);
+}
+
+#[test]
+fn test_durability_settings_are_applied() {
+    let path = temp_database_path();
+    run_migrations(&path).expect("Failed to migrate database");
+    let database = Database::new(&path).unwrap();
+    let memory = SignedMemory::new(
+        &Did::parse("did:plc:crashtest").unwrap(),
+        "crash_test",
+        "{}",
+    );
+    database.create_outbound_signed_memory(&memory).unwrap();
+    drop(database);
+
+    let conn = rusqlite::Connection::open(&path).unwrap();
+    let mode: String = conn
+        .pragma_query_value(None, "journal_mode", |row| row.get(0))
+        .unwrap();
+    assert_eq!(mode.to_lowercase(), "wal");
+    drop(conn);
+    remove_database(&path);
+}
@@ -1,19 +1,144 @@
+use crate::config::{CompressionConfig, DurabilityConfig};
 use crate::core::error::{OcmError, Result};
+use crate::core::ids::{Did, MemoryId, PeerId, TokenId};
 use crate::core::models::*;
-use rusqlite::Connection;
-use std::sync::{Arc, Mutex};
+use crate::core::tenancy::TenantScope;
+use crate::identity::group::{GroupMembership, GROUP_MEMBERSHIP_MEMORY_TYPE};
+use crate::persistence::compression::{train_dictionary, CompressionReport, ContentCompression};
+use ocm_protocol::feed::{FeedCursor, FeedPage, FeedQuery, FeedSource};
+use ocm_protocol::filter::{DataAccess, Filter, SqlValue};
+use ocm_protocol::message::PeerInfo;
+use rusqlite::{Connection, OpenFlags, OptionalExtension};
+use std::collections::HashSet;
+use std::sync::{Arc, Mutex, RwLock};
+
+// A memory's data, which is held in memory_content when its hash checked out, compressed
+// there once its type has a dictionary
+const MEMORY_DATA_SQL: &str = "COALESCE((SELECT ocm_content(memory_data, dictionary_id) FROM memory_content WHERE memory_content.content_hash = signed_memory.body_hash), memory_data)";
 
 #[derive(Clone)]
 pub struct Database {
     conn: Arc<Mutex<Connection>>,
+    indexed_fields: Arc<RwLock<HashSet<String>>>, // memory_data paths with indices, e.g. "$.last_name"
+    compression: Arc<ContentCompression>,
+    durability: Arc<RwLock<DurabilityConfig>>,
+    claim_token_pepper: Arc<[u8; 32]>,
 }
 
 impl Database {
     pub fn new(db_path: &str) -> Result<Self> {
         let conn = Connection::open(db_path).map_err(OcmError::Database)?;
-        Ok(Database {
+        Self::from_connection(conn, load_claim_token_pepper(db_path, true)?)
+    }
+
+    /// Open an existing database without write access, for analysis and export tools
+    pub fn open_read_only(db_path: &str) -> Result<Self> {
+        let conn = Connection::open_with_flags(db_path, OpenFlags::SQLITE_OPEN_READ_ONLY)
+            .map_err(OcmError::Database)?;
+        Self::from_connection(conn, load_claim_token_pepper(db_path, false)?)
+    }
+
+    fn from_connection(conn: Connection, claim_token_pepper: [u8; 32]) -> Result<Self> {
+        let compression = Arc::new(ContentCompression::default());
+        compression.attach(&conn)?;
+        let database = Database {
             conn: Arc::new(Mutex::new(conn)),
-        })
+            indexed_fields: Arc::default(),
+            compression,
+            durability: Arc::default(),
+            claim_token_pepper: Arc::new(claim_token_pepper),
+        };
+        database.set_durability(DurabilityConfig::default())?;
+        Ok(database)
+    }
+
+    /// Apply journal and synchronous settings. Read-only databases keep their journal
+    pub fn set_durability(&self, config: DurabilityConfig) -> Result<()> {
+        let conn = self.get_connection()?;
+        if config.wal && !conn.is_readonly(rusqlite::DatabaseName::Main)? {
+            let mode: String =
+                conn.pragma_update_and_check(None, "journal_mode", "WAL", |row| row.get(0))?;
+            // In-memory databases have no write-ahead log
+            if !mode.eq_ignore_ascii_case("wal") && !mode.eq_ignore_ascii_case("memory") {
+                eprintln!("⚠️  Database stayed in {} journal mode", mode);
+            }
+        }
+        conn.pragma_update(None, "synchronous", config.synchronous.as_sql())?;
+        *self.durability.write().unwrap_or_else(|e| e.into_inner()) = config;
+        Ok(())
+    }
+
+    /// Run a write of memories this node signed at the signed synchronous level, so it
+    /// has reached the disk when this returns
+    fn write_signed<T>(&self, write: impl FnOnce(&mut Connection) -> Result<T>) -> Result<T> {
+        let durability = self
+            .durability
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .clone();
+        let mut conn = self.get_connection()?;
+        let raise = durability.signed_synchronous != durability.synchronous;
+        if raise {
+            conn.pragma_update(None, "synchronous", durability.signed_synchronous.as_sql())?;
+        }
+        let result = write(&mut conn);
+        if raise {
+            conn.pragma_update(None, "synchronous", durability.synchronous.as_sql())?;
+        }
+        result
+    }
+
+    /// Create expression indices on memory_data fields, for both content stored by hash
+    /// and inline data, so filters on those fields use them. Returns the number of
+    /// fields indexed; read-only databases only use indices that already exist
+    pub fn index_memory_fields(&self, fields: &[String]) -> Result<usize> {
+        let read_only = self
+            .get_connection()?
+            .is_readonly(rusqlite::DatabaseName::Main)?;
+        let mut indexed = 0;
+        for field in fields {
+            let valid = field.split('.').all(|segment| {
+                !segment.is_empty()
+                    && segment
+                        .chars()
+                        .all(|c| c.is_ascii_alphanumeric() || c == '_')
+            });
+            if !valid {
+                return Err(OcmError::Validation(format!(
+                    "Invalid indexed field '{}'",
+                    field
+                )));
+            }
+            let path = format!("$.{}", field);
+            let name = field.replace('.', "_");
+            if read_only {
+                let conn = self.get_connection()?;
+                let exists: bool = conn.query_row(
+                    "SELECT COUNT(*) > 0 FROM sqlite_master WHERE type = 'index' AND name = ?1",
+                    [format!("idx_content_data_{}", name)],
+                    |row| row.get(0),
+                )?;
+                if !exists {
+                    continue;
+                }
+            } else {
+                let conn = self.get_connection()?;
+                conn.execute_batch(&format!(
+                    // idx_content_field_* indexed memory_data directly, which compressed bodies break
+                    "DROP INDEX IF EXISTS idx_content_field_{name};
+                     CREATE INDEX IF NOT EXISTS idx_content_data_{name} ON memory_content (json_extract(ocm_content(memory_data, dictionary_id), '{path}'));
+                     CREATE INDEX IF NOT EXISTS idx_inline_field_{name} ON signed_memory (json_extract(memory_data, '{path}')) WHERE body_hash IS NULL;",
+                    name = name,
+                    path = path
+                ))?;
+            }
+            self.indexed_fields
+                .write()
+                .unwrap_or_else(|e| e.into_inner())
+                .insert(path);
+            indexed += 1;
+        }
+        Ok(indexed)
     }
 
     fn get_connection(&self) -> Result<std::sync::MutexGuard<'_, Connection>> {
@@ -115,6 +240,43 @@ impl Database {
         }
     }
 
+    /// Create or update individuals in one transaction, returning for each whether an
+    /// existing record was updated
+    pub fn save_individuals(&self, individuals: &[Individual]) -> Result<Vec<bool>> {
+        let mut conn = self.get_connection()?;
+        let tx = conn.transaction()?;
+        let mut updated = Vec::with_capacity(individuals.len());
+        for individual in individuals {
+            let exists: bool = tx.query_row(
+                "SELECT EXISTS(SELECT 1 FROM individual WHERE id = ?1)",
+                [&individual.id],
+                |row| row.get(0),
+            )?;
+            let sql = if exists {
+                Individual::update_sql()
+            } else {
+                Individual::insert_sql()
+            };
+            tx.execute(
+                sql,
+                (
+                    &individual.id,
+                    &individual.first_name,
+                    &individual.middle_name,
+                    &individual.last_name,
+                    &individual.dob,
+                    &individual.phone,
+                    &individual.email,
+                    &individual.employer,
+                    &individual.updated_on,
+                ),
+            )?;
+            updated.push(exists);
+        }
+        tx.commit()?;
+        Ok(updated)
+    }
+
     pub fn delete_individual(&self, id: &str) -> Result<()> {
         self.delete::<Individual>(id)
     }
@@ -123,26 +285,95 @@ impl Database {
         self.list()
     }
 
+    /// How many times a table has changed, and when it last did (RFC 3339)
+    pub fn collection_version(&self, table: &str) -> Result<(i64, String)> {
+        let conn = self.get_connection()?;
+        conn.query_row(
+            "SELECT version, updated_at FROM collection_version WHERE name = ?1",
+            [table],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )
+        .optional()?
+        .ok_or_else(|| OcmError::NotFound(format!("Collection {}", table)))
+    }
+
     // SignedMemory CRUD operations
     pub fn create_signed_memory(&self, memory: &SignedMemory) -> Result<()> {
-        let conn = self.get_connection()?;
-        conn.execute(
-            SignedMemory::insert_sql(),
-            (
-                &memory.id,
-                &memory.did,
-                &memory.memory_type,
-                &memory.memory_data,
-                &memory.content_hash,
-                &memory.signature,
-                &memory.timestamp,
-                &memory.updated_on,
-            ),
-        )?;
+        let mut conn = self.get_connection()?;
+        let tx = conn.transaction()?;
+        insert_signed_memory(&tx, &self.compression, memory)?;
+        tx.commit()?;
+        Ok(())
+    }
+
+    /// Store a locally authored memory and queue it for broadcast in one transaction,
+    /// so a crash can never leave a stored memory that peers will not hear about
+    pub fn create_outbound_signed_memory(&self, memory: &SignedMemory) -> Result<()> {
+        self.create_outbound_signed_memories(std::slice::from_ref(memory))
+    }
+
+    /// Store and queue several locally authored memories in one transaction; if any
+    /// insert fails none are kept
+    pub fn create_outbound_signed_memories(&self, memories: &[SignedMemory]) -> Result<()> {
+        self.write_signed(|conn| {
+            let tx = conn.transaction()?;
+            let created_at = chrono::Utc::now().to_rfc3339();
+            for memory in memories {
+                insert_signed_memory(&tx, &self.compression, memory)?;
+                tx.execute(
+                    "INSERT INTO memory_outbox (memory_id, created_at) VALUES (?1, ?2)",
+                    (&memory.id, &created_at),
+                )?;
+            }
+            tx.commit()?;
+            Ok(())
+        })
+    }
+
+    /// Store memories from a snapshot in one transaction, skipping any already held
+    pub fn import_signed_memories(&self, memories: &[SignedMemory]) -> Result<usize> {
+        let mut conn = self.get_connection()?;
+        let tx = conn.transaction()?;
+        let mut imported = 0;
+        for memory in memories {
+            let exists: bool = tx.query_row(
+                "SELECT EXISTS(SELECT 1 FROM signed_memory WHERE id = ?1)",
+                [&memory.id],
+                |row| row.get(0),
+            )?;
+            if !exists {
+                insert_signed_memory(&tx, &self.compression, memory)?;
+                imported += 1;
+            }
+        }
+        tx.commit()?;
+        Ok(imported)
+    }
+
+    pub fn update_signed_memory(&self, memory: &SignedMemory) -> Result<()> {
+        let mut conn = self.get_connection()?;
+        let tx = conn.transaction()?;
+        update_signed_memory_row(&tx, &self.compression, memory)?;
+        tx.commit()?;
         Ok(())
     }
 
-    pub fn get_signed_memory(&self, id: &str) -> Result<Option<SignedMemory>> {
+    /// Replace a locally authored memory and queue the new version for broadcast in
+    /// one transaction
+    pub fn update_outbound_signed_memory(&self, memory: &SignedMemory) -> Result<()> {
+        self.write_signed(|conn| {
+            let tx = conn.transaction()?;
+            update_signed_memory_row(&tx, &self.compression, memory)?;
+            tx.execute(
+                "INSERT INTO memory_outbox (memory_id, created_at) VALUES (?1, ?2)",
+                (&memory.id, chrono::Utc::now().to_rfc3339()),
+            )?;
+            tx.commit()?;
+            Ok(())
+        })
+    }
+
+    pub fn get_signed_memory(&self, id: &MemoryId) -> Result<Option<SignedMemory>> {
         self.get(id)
     }
 
@@ -150,7 +381,7 @@ impl Database {
         self.list()
     }
 
-    pub fn list_memories_by_did(&self, did: &str) -> Result<Vec<SignedMemory>> {
+    pub fn list_memories_by_did(&self, did: &Did) -> Result<Vec<SignedMemory>> {
         let sql = format!(
             "SELECT {} FROM {} WHERE did = ?1 ORDER BY timestamp DESC",
             SignedMemory::select_fields(),
@@ -167,355 +398,556 @@ impl Database {
         Ok(memories)
     }
 
-    // Location CRUD operations
-    pub fn create_location(&self, location: &Location) -> Result<()> {
+    /// The latest stored memory with this content hash
+    pub fn get_signed_memory_by_content_hash(
+        &self,
+        content_hash: &str,
+    ) -> Result<Option<SignedMemory>> {
+        let sql = format!(
+            "SELECT {} FROM {} WHERE content_hash = ?1 ORDER BY updated_on DESC LIMIT 1",
+            SignedMemory::select_fields(),
+            SignedMemory::table_name()
+        );
+        let conn = self.get_connection()?;
+        Ok(conn
+            .query_row(&sql, [content_hash], SignedMemory::from_row)
+            .optional()?)
+    }
+
+    // Witness receipt operations
+    pub fn create_witness_receipt(&self, receipt: &WitnessReceipt) -> Result<()> {
         let conn = self.get_connection()?;
         conn.execute(
-            "INSERT INTO location (id, email, phone, address, city, state, zip, country, coordinates_lat, coordinates_lon, updated_on)
-             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11)",
+            WitnessReceipt::insert_sql(),
             (
-                &location.id,
-                &location.email,
-                &location.phone,
-                &location.address,
-                &location.city,
-                &location.state,
-                &location.zip,
-                &location.country,
-                &location.coordinates_lat,
-                &location.coordinates_lon,
-                &location.updated_on,
+                &receipt.id,
+                &receipt.memory_id,
+                &receipt.content_hash,
+                &receipt.witness_did,
+                &receipt.witnessed_at,
+                &receipt.signature,
             ),
         )?;
         Ok(())
     }
 
-    pub fn get_location(&self, id: &str) -> Result<Option<Location>> {
+    pub fn list_witness_receipts(&self, memory_id: &MemoryId) -> Result<Vec<WitnessReceipt>> {
+        let sql = format!(
+            "SELECT {} FROM {} WHERE memory_id = ?1 ORDER BY witnessed_at ASC",
+            WitnessReceipt::select_fields(),
+            WitnessReceipt::table_name()
+        );
+        let conn = self.get_connection()?;
+        let mut stmt = conn.prepare(&sql)?;
+        let rows = stmt.query_map([memory_id], WitnessReceipt::from_row)?;
+
+        let mut receipts = Vec::new();
+        for row in rows {
+            receipts.push(row?);
+        }
+        Ok(receipts)
+    }
+
+    // Transparency log operations
+    pub fn list_transparency_leaves(&self) -> Result<Vec<String>> {
+        let conn = self.get_connection()?;
+        let mut stmt =
+            conn.prepare("SELECT leaf_hash FROM transparency_log ORDER BY leaf_index ASC")?;
+        let rows = stmt.query_map([], |row| row.get::<_, String>(0))?;
+
+        let mut leaves = Vec::new();
+        for row in rows {
+            leaves.push(row?);
+        }
+        Ok(leaves)
+    }
+
+    pub fn find_transparency_leaf(&self, memory_id: &MemoryId) -> Result<Option<u64>> {
         let conn = self.get_connection()?;
         let mut stmt = conn.prepare(
-            "SELECT id, email, phone, address, city, state, zip, country, coordinates_lat, coordinates_lon, updated_on 
-             FROM location WHERE id = ?1"
+            "SELECT leaf_index FROM transparency_log WHERE memory_id = ?1 ORDER BY leaf_index ASC LIMIT 1",
         )?;
-
-        let mut rows = stmt.query_map([id], |row| {
-            Ok(Location {
-                id: row.get(0)?,
-                email: row.get(1)?,
-                phone: row.get(2)?,
-                address: row.get(3)?,
-                city: row.get(4)?,
-                state: row.get(5)?,
-                zip: row.get(6)?,
-                country: row.get(7)?,
-                coordinates_lat: row.get(8)?,
-                coordinates_lon: row.get(9)?,
-                updated_on: row.get(10)?,
-            })
-        })?;
+        let mut rows = stmt.query_map([memory_id], |row| row.get::<_, i64>(0))?;
 
         match rows.next() {
-            Some(row) => Ok(Some(row?)),
+            Some(row) => Ok(Some(row? as u64)),
             None => Ok(None),
         }
     }
 
-    pub fn update_location(&self, location: &Location) -> Result<()> {
+    // Activity feed operations
+    pub fn record_activity_event(
+        &self,
+        event_type: &str,
+        payload: &serde_json::Value,
+    ) -> Result<()> {
         let conn = self.get_connection()?;
-        conn.execute(
-            "UPDATE location SET email = ?2, phone = ?3, address = ?4, city = ?5, state = ?6, 
-             zip = ?7, country = ?8, coordinates_lat = ?9, coordinates_lon = ?10, updated_on = ?11 
-             WHERE id = ?1",
-            (
-                &location.id,
-                &location.email,
-                &location.phone,
-                &location.address,
-                &location.city,
-                &location.state,
-                &location.zip,
-                &location.country,
-                &location.coordinates_lat,
-                &location.coordinates_lon,
-                &location.updated_on,
-            ),
+        insert_activity_event(&conn, event_type, None, None, None, payload)
+    }
+
+    /// An event concerning one DID and, optionally, one of its memories
+    pub fn record_did_activity_event(
+        &self,
+        event_type: &str,
+        did: &Did,
+        memory_id: Option<&MemoryId>,
+        payload: &serde_json::Value,
+    ) -> Result<()> {
+        let conn = self.get_connection()?;
+        insert_activity_event(&conn, event_type, Some(did), None, memory_id, payload)
+    }
+
+    /// How many events of `event_type` were recorded at or after `since` (RFC 3339)
+    pub fn count_activity_events_since(&self, event_type: &str, since: &str) -> Result<u64> {
+        let conn = self.get_connection()?;
+        let count: i64 = conn.query_row(
+            "SELECT COUNT(*) FROM activity_event WHERE event_type = ?1 AND created_at >= ?2",
+            (event_type, since),
+            |row| row.get(0),
         )?;
-        Ok(())
+        Ok(count as u64)
     }
 
-    pub fn delete_location(&self, id: &str) -> Result<()> {
+    /// The newest event's ID, or 0 before any event; where a live follower starts
+    pub fn latest_activity_event_id(&self) -> Result<i64> {
         let conn = self.get_connection()?;
-        conn.execute("DELETE FROM location WHERE id = ?1", [id])?;
-        Ok(())
+        Ok(conn.query_row(
+            "SELECT COALESCE(MAX(event_id), 0) FROM activity_event",
+            [],
+            |row| row.get(0),
+        )?)
     }
 
-    pub fn list_locations(&self) -> Result<Vec<Location>> {
+    /// Events after `after_event_id`, oldest first, optionally limited to one DID or memory type
+    pub fn list_activity_events_after(
+        &self,
+        after_event_id: i64,
+        did: Option<&Did>,
+        memory_type: Option<&str>,
+        limit: usize,
+    ) -> Result<Vec<ActivityEvent>> {
         let conn = self.get_connection()?;
         let mut stmt = conn.prepare(
-            "SELECT id, email, phone, address, city, state, zip, country, coordinates_lat, coordinates_lon, updated_on 
-             FROM location"
+            "SELECT event_id, event_type, did, memory_type, memory_id, payload, created_at
+             FROM activity_event
+             WHERE event_id > ?1
+               AND (?2 IS NULL OR did = ?2)
+               AND (?3 IS NULL OR memory_type = ?3)
+             ORDER BY event_id ASC
+             LIMIT ?4",
+        )?;
+        let rows = stmt.query_map(
+            rusqlite::params![after_event_id, did, memory_type, limit as i64],
+            |row| {
+                let payload: String = row.get(5)?;
+                Ok(ActivityEvent {
+                    event_id: row.get(0)?,
+                    event_type: row.get(1)?,
+                    did: row.get(2)?,
+                    memory_type: row.get(3)?,
+                    memory_id: row.get(4)?,
+                    payload: serde_json::from_str(&payload).unwrap_or(serde_json::Value::Null),
+                    created_at: row.get(6)?,
+                })
+            },
         )?;
 
-        let rows = stmt.query_map([], |row| {
-            Ok(Location {
-                id: row.get(0)?,
-                email: row.get(1)?,
-                phone: row.get(2)?,
-                address: row.get(3)?,
-                city: row.get(4)?,
-                state: row.get(5)?,
-                zip: row.get(6)?,
-                country: row.get(7)?,
-                coordinates_lat: row.get(8)?,
-                coordinates_lon: row.get(9)?,
-                updated_on: row.get(10)?,
-            })
-        })?;
-
-        let mut locations = Vec::new();
+        let mut events = Vec::new();
         for row in rows {
-            locations.push(row?);
+            events.push(row?);
         }
-        Ok(locations)
+        Ok(events)
     }
 
-    // Experience CRUD operations
-    pub fn create_experience(&self, experience: &Experience) -> Result<()> {
+    // Node state operations
+    /// Stable ID this node announces to peers, generated once and reused across restarts
+    pub fn get_or_create_node_id(&self) -> Result<String> {
         let conn = self.get_connection()?;
         conn.execute(
-            "INSERT INTO experience (id, name, updated_on) VALUES (?1, ?2, ?3)",
-            (&experience.id, &experience.name, &experience.updated_on),
+            "INSERT OR IGNORE INTO node_metadata (key, value) VALUES ('node_id', ?1)",
+            [uuid::Uuid::new_v4().to_string()],
         )?;
-        Ok(())
+        let node_id = conn.query_row(
+            "SELECT value FROM node_metadata WHERE key = 'node_id'",
+            [],
+            |row| row.get(0),
+        )?;
+        Ok(node_id)
     }
 
-    pub fn get_experience(&self, id: &str) -> Result<Option<Experience>> {
+    /// The stored node ID, without creating one
+    pub fn get_node_id(&self) -> Result<Option<String>> {
         let conn = self.get_connection()?;
-        let mut stmt = conn.prepare("SELECT id, name, updated_on FROM experience WHERE id = ?1")?;
-        let mut rows = stmt.query_map([id], |row| {
-            Ok(Experience {
-                id: row.get(0)?,
-                name: row.get(1)?,
-                updated_on: row.get(2)?,
-            })
-        })?;
-        match rows.next() {
-            Some(row) => Ok(Some(row?)),
-            None => Ok(None),
-        }
+        let mut stmt = conn.prepare("SELECT value FROM node_metadata WHERE key = 'node_id'")?;
+        let mut rows = stmt.query_map([], |row| row.get(0))?;
+        Ok(rows.next().transpose()?)
     }
 
-    pub fn update_experience(&self, experience: &Experience) -> Result<()> {
+    pub fn record_peer_sync(&self, watermark: &SyncWatermark) -> Result<()> {
         let conn = self.get_connection()?;
         conn.execute(
-            "UPDATE experience SET name = ?2, updated_on = ?3 WHERE id = ?1",
-            (&experience.id, &experience.name, &experience.updated_on),
+            "INSERT INTO peer_sync_state (peer_node_id, last_synced_at, memory_count, memory_digest)
+             VALUES (?1, ?2, ?3, ?4)
+             ON CONFLICT(peer_node_id) DO UPDATE SET last_synced_at = excluded.last_synced_at,
+                 memory_count = excluded.memory_count, memory_digest = excluded.memory_digest",
+            (
+                &watermark.peer_node_id,
+                &watermark.synced_at,
+                watermark.memory_count.map(|count| count as i64),
+                &watermark.memory_digest,
+            ),
         )?;
         Ok(())
     }
 
-    pub fn delete_experience(&self, id: &str) -> Result<()> {
-        let conn = self.get_connection()?;
-        conn.execute("DELETE FROM experience WHERE id = ?1", [id])?;
-        Ok(())
-    }
-
-    pub fn list_experiences(&self) -> Result<Vec<Experience>> {
+    /// The sync watermark of every peer this node has synced with
+    pub fn list_peer_sync_state(&self) -> Result<Vec<SyncWatermark>> {
         let conn = self.get_connection()?;
-        let mut stmt = conn.prepare("SELECT id, name, updated_on FROM experience")?;
+        let mut stmt = conn.prepare(
+            "SELECT peer_node_id, last_synced_at, memory_count, memory_digest FROM peer_sync_state",
+        )?;
         let rows = stmt.query_map([], |row| {
-            Ok(Experience {
-                id: row.get(0)?,
-                name: row.get(1)?,
-                updated_on: row.get(2)?,
+            Ok(SyncWatermark {
+                peer_node_id: row.get(0)?,
+                synced_at: row.get(1)?,
+                memory_count: row.get::<_, Option<i64>>(2)?.map(|count| count as u64),
+                memory_digest: row.get(3)?,
             })
         })?;
-        let mut experiences = Vec::new();
+
+        let mut states = Vec::new();
         for row in rows {
-            experiences.push(row?);
+            states.push(row?);
         }
-        Ok(experiences)
+        Ok(states)
     }
 
-    // Cohort CRUD operations
-    pub fn create_cohort(&self, cohort: &Cohort) -> Result<()> {
+    /// (count, digest) of the stored memory set; the digest is the SHA-256 of the sorted
+    /// content hashes, so it changes whenever a memory is added or removed
+    pub fn memory_set_digest(&self) -> Result<(u64, String)> {
         let conn = self.get_connection()?;
-        conn.execute(
-            "INSERT INTO cohort (id, name, capacity, updated_on) VALUES (?1, ?2, ?3, ?4)",
-            (
-                &cohort.id,
-                &cohort.name,
-                &cohort.capacity,
-                &cohort.updated_on,
-            ),
-        )?;
-        Ok(())
+        let mut stmt =
+            conn.prepare("SELECT content_hash FROM signed_memory ORDER BY content_hash")?;
+        let rows = stmt.query_map([], |row| row.get::<_, String>(0))?;
+
+        let mut hashes = Vec::new();
+        for row in rows {
+            hashes.push(row?);
+        }
+        Ok((
+            hashes.len() as u64,
+            SignedMemory::compute_hash(&hashes.join("\n")),
+        ))
     }
 
-    pub fn get_cohort(&self, id: &str) -> Result<Option<Cohort>> {
+    // Maintenance operations
+    /// Problems found by SQLite's integrity check; empty when the file is sound
+    pub fn integrity_check(&self) -> Result<Vec<String>> {
         let conn = self.get_connection()?;
-        let mut stmt =
-            conn.prepare("SELECT id, name, capacity, updated_on FROM cohort WHERE id = ?1")?;
-        let mut rows = stmt.query_map([id], |row| {
-            Ok(Cohort {
-                id: row.get(0)?,
-                name: row.get(1)?,
-                capacity: row.get(2)?,
-                updated_on: row.get(3)?,
-            })
-        })?;
-        match rows.next() {
-            Some(row) => Ok(Some(row?)),
-            None => Ok(None),
+        let mut stmt = conn.prepare("PRAGMA integrity_check")?;
+        let rows = stmt.query_map([], |row| row.get::<_, String>(0))?;
+
+        let mut problems = Vec::new();
+        for row in rows {
+            let row = row?;
+            if row != "ok" {
+                problems.push(row);
+            }
         }
+        Ok(problems)
     }
 
-    pub fn update_cohort(&self, cohort: &Cohort) -> Result<()> {
+    /// Newest migration applied by refinery, or None if migrations have never run
+    pub fn schema_version(&self) -> Result<Option<i64>> {
         let conn = self.get_connection()?;
-        conn.execute(
-            "UPDATE cohort SET name = ?2, capacity = ?3, updated_on = ?4 WHERE id = ?1",
-            (
-                &cohort.id,
-                &cohort.name,
-                &cohort.capacity,
-                &cohort.updated_on,
-            ),
+        let has_history: bool = conn.query_row(
+            "SELECT EXISTS(SELECT 1 FROM sqlite_master
+             WHERE type = 'table' AND name = 'refinery_schema_history')",
+            [],
+            |row| row.get(0),
         )?;
-        Ok(())
+        if !has_history {
+            return Ok(None);
+        }
+        let version = conn.query_row(
+            "SELECT MAX(version) FROM refinery_schema_history",
+            [],
+            |row| row.get(0),
+        )?;
+        Ok(version)
     }
 
-    pub fn delete_cohort(&self, id: &str) -> Result<()> {
+    // Peer group operations
+    pub fn upsert_peer_group(
+        &self,
+        name: &str,
+        memory_types: Option<&[String]>,
+        tags: Option<&[String]>,
+    ) -> Result<()> {
+        let memory_types = memory_types.map(serde_json::to_string).transpose()?;
+        let tags = tags.map(serde_json::to_string).transpose()?;
         let conn = self.get_connection()?;
-        conn.execute("DELETE FROM cohort WHERE id = ?1", [id])?;
+        conn.execute(
+            "INSERT INTO peer_group (name, memory_types, tags, created_at) VALUES (?1, ?2, ?3, ?4)
+             ON CONFLICT(name) DO UPDATE SET
+                 memory_types = excluded.memory_types,
+                 tags = excluded.tags",
+            (name, memory_types, tags, chrono::Utc::now().to_rfc3339()),
+        )?;
         Ok(())
     }
 
-    pub fn list_cohorts(&self) -> Result<Vec<Cohort>> {
+    pub fn add_peer_group_member(&self, group_name: &str, member: &str) -> Result<()> {
         let conn = self.get_connection()?;
-        let mut stmt = conn.prepare("SELECT id, name, capacity, updated_on FROM cohort")?;
-        let rows = stmt.query_map([], |row| {
-            Ok(Cohort {
-                id: row.get(0)?,
-                name: row.get(1)?,
-                capacity: row.get(2)?,
-                updated_on: row.get(3)?,
-            })
-        })?;
-        let mut cohorts = Vec::new();
-        for row in rows {
-            cohorts.push(row?);
-        }
-        Ok(cohorts)
+        conn.execute(
+            "INSERT OR IGNORE INTO peer_group_member (group_name, member, assigned_at)
+             VALUES (?1, ?2, ?3)",
+            (group_name, member, chrono::Utc::now().to_rfc3339()),
+        )?;
+        Ok(())
     }
 
-    // Schedule CRUD operations
-    pub fn create_schedule(&self, schedule: &Schedule) -> Result<()> {
+    pub fn remove_peer_group_member(&self, group_name: &str, member: &str) -> Result<()> {
         let conn = self.get_connection()?;
         conn.execute(
-            "INSERT INTO schedule (id, \"from\", \"to\", days_of_week_min, days_of_week_max) VALUES (?1, ?2, ?3, ?4, ?5)",
-            (&schedule.id, &schedule.from, &schedule.to, &schedule.days_of_week_min, &schedule.days_of_week_max),
+            "DELETE FROM peer_group_member WHERE group_name = ?1 AND member = ?2",
+            (group_name, member),
         )?;
         Ok(())
     }
 
-    pub fn get_schedule(&self, id: &str) -> Result<Option<Schedule>> {
+    pub fn list_peer_groups(&self) -> Result<Vec<PeerGroup>> {
         let conn = self.get_connection()?;
-        let mut stmt = conn.prepare("SELECT id, \"from\", \"to\", days_of_week_min, days_of_week_max FROM schedule WHERE id = ?1")?;
-        let mut rows = stmt.query_map([id], |row| {
-            Ok(Schedule {
-                id: row.get(0)?,
-                from: row.get(1)?,
-                to: row.get(2)?,
-                days_of_week_min: row.get(3)?,
-                days_of_week_max: row.get(4)?,
+        let mut stmt =
+            conn.prepare("SELECT name, memory_types, tags FROM peer_group ORDER BY name")?;
+        let rows = stmt.query_map([], |row| {
+            let memory_types: Option<String> = row.get(1)?;
+            let tags: Option<String> = row.get(2)?;
+            Ok(PeerGroup {
+                name: row.get(0)?,
+                memory_types: memory_types.and_then(|t| serde_json::from_str(&t).ok()),
+                tags: tags.and_then(|t| serde_json::from_str(&t).ok()),
+                members: Vec::new(),
             })
         })?;
-        match rows.next() {
-            Some(row) => Ok(Some(row?)),
-            None => Ok(None),
+
+        let mut groups = Vec::new();
+        for row in rows {
+            groups.push(row?);
+        }
+
+        let mut stmt = conn.prepare(
+            "SELECT member FROM peer_group_member WHERE group_name = ?1 ORDER BY assigned_at",
+        )?;
+        for group in &mut groups {
+            let rows = stmt.query_map([&group.name], |row| row.get::<_, String>(0))?;
+            for row in rows {
+                group.members.push(row?);
+            }
         }
+        Ok(groups)
     }
 
-    pub fn update_schedule(&self, schedule: &Schedule) -> Result<()> {
+    // Tag operations
+    /// Apply a tag operation unless a later one on the same memory and tag is already held.
+    /// Returns whether it took effect
+    pub fn apply_memory_tag(
+        &self,
+        data: &MemoryTagData,
+        tagged_by: &str,
+        tagged_at: &str,
+        tag_memory_id: &MemoryId,
+    ) -> Result<bool> {
         let conn = self.get_connection()?;
-        conn.execute(
-            "UPDATE schedule SET \"from\" = ?2, \"to\" = ?3, days_of_week_min = ?4, days_of_week_max = ?5 WHERE id = ?1",
-            (&schedule.id, &schedule.from, &schedule.to, &schedule.days_of_week_min, &schedule.days_of_week_max),
+        let changed = conn.execute(
+            "INSERT INTO memory_tag (memory_id, tag, removed, tagged_by, tagged_at, tag_memory_id)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6)
+             ON CONFLICT(memory_id, tag) DO UPDATE SET
+                 removed = excluded.removed,
+                 tagged_by = excluded.tagged_by,
+                 tagged_at = excluded.tagged_at,
+                 tag_memory_id = excluded.tag_memory_id
+             WHERE excluded.tagged_at > memory_tag.tagged_at",
+            (
+                &data.memory_id,
+                &data.tag,
+                data.removed,
+                tagged_by,
+                tagged_at,
+                tag_memory_id,
+            ),
         )?;
-        Ok(())
+        Ok(changed > 0)
     }
 
-    pub fn delete_schedule(&self, id: &str) -> Result<()> {
+    pub fn list_memory_tags(&self, memory_id: &MemoryId) -> Result<Vec<String>> {
         let conn = self.get_connection()?;
-        conn.execute("DELETE FROM schedule WHERE id = ?1", [id])?;
-        Ok(())
+        let mut stmt = conn.prepare(
+            "SELECT tag FROM memory_tag WHERE memory_id = ?1 AND removed = 0 ORDER BY tag",
+        )?;
+        let rows = stmt.query_map([memory_id], |row| row.get(0))?;
+
+        let mut tags = Vec::new();
+        for row in rows {
+            tags.push(row?);
+        }
+        Ok(tags)
     }
 
-    pub fn list_schedules(&self) -> Result<Vec<Schedule>> {
+    pub fn list_memories_by_tag(&self, tag: &str) -> Result<Vec<SignedMemory>> {
+        let sql = format!(
+            "SELECT {} FROM {} WHERE id IN
+                 (SELECT memory_id FROM memory_tag WHERE tag = ?1 AND removed = 0)
+             ORDER BY timestamp DESC",
+            SignedMemory::select_fields(),
+            SignedMemory::table_name()
+        );
+        let conn = self.get_connection()?;
+        let mut stmt = conn.prepare(&sql)?;
+        let rows = stmt.query_map([tag], SignedMemory::from_row)?;
+
+        let mut memories = Vec::new();
+        for row in rows {
+            memories.push(row?);
+        }
+        Ok(memories)
+    }
+
+    pub fn list_tags(&self) -> Result<Vec<TagCount>> {
         let conn = self.get_connection()?;
         let mut stmt = conn.prepare(
-            "SELECT id, \"from\", \"to\", days_of_week_min, days_of_week_max FROM schedule",
+            "SELECT tag, COUNT(*) FROM memory_tag WHERE removed = 0 GROUP BY tag ORDER BY tag",
         )?;
         let rows = stmt.query_map([], |row| {
-            Ok(Schedule {
-                id: row.get(0)?,
-                from: row.get(1)?,
-                to: row.get(2)?,
-                days_of_week_min: row.get(3)?,
-                days_of_week_max: row.get(4)?,
+            Ok(TagCount {
+                tag: row.get(0)?,
+                memory_count: row.get(1)?,
             })
         })?;
-        let mut schedules = Vec::new();
+
+        let mut tags = Vec::new();
         for row in rows {
-            schedules.push(row?);
+            tags.push(row?);
         }
-        Ok(schedules)
+        Ok(tags)
     }
 
-    // Affiliation CRUD operations
-    pub fn create_affiliation(&self, affiliation: &Affiliation) -> Result<()> {
+    // Feed operations
+    /// A page of memories by a DID, a group or everyone, newest first, limited to
+    /// what `scope` may see and matching `filter`. `cursor` is the `next_cursor` of
+    /// the previous page
+    pub fn get_feed(
+        &self,
+        scope: &TenantScope,
+        source: &FeedSource,
+        cursor: Option<&str>,
+        limit: usize,
+        memory_types: &[String],
+        filter: Option<&Filter>,
+    ) -> Result<FeedPage> {
+        let authors = match source {
+            FeedSource::All => None,
+            FeedSource::Did(did) => Some(vec![did.clone()]),
+            FeedSource::Group(group_did) => Some(self.group_feed_authors(group_did)?),
+        };
+        let query = FeedQuery {
+            authors: scope.restrict_authors(authors),
+            memory_types: memory_types.to_vec(),
+            cursor: cursor
+                .map(FeedCursor::decode)
+                .transpose()
+                .map_err(OcmError::Validation)?,
+            limit,
+            filter: filter.cloned(),
+        };
+
+        let (clause, params) = query.where_clause(self);
+        let params = params.into_iter().map(|param| match param {
+            SqlValue::Null => rusqlite::types::Value::Null,
+            SqlValue::Integer(integer) => rusqlite::types::Value::Integer(integer),
+            SqlValue::Real(real) => rusqlite::types::Value::Real(real),
+            SqlValue::Text(text) => rusqlite::types::Value::Text(text),
+        });
+        let sql = format!(
+            "SELECT {} FROM {} WHERE {} ORDER BY timestamp DESC, id DESC LIMIT {}",
+            SignedMemory::select_fields(),
+            SignedMemory::table_name(),
+            clause,
+            query.fetch_limit()
+        );
         let conn = self.get_connection()?;
-        conn.execute(
-            "INSERT INTO affiliation (id, name, affiliation_type, value, range_min, range_max, cohort, updated_on)
-             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
-            (
-                &affiliation.id,
-                &affiliation.name,
-                &affiliation.affiliation_type.to_string(),
-                &affiliation.value,
-                &affiliation.range_min,
-                &affiliation.range_max,
-                &affiliation.cohort,
-                &affiliation.updated_on,
-            ),
-        )?;
-        Ok(())
+        let mut stmt = conn.prepare(&sql)?;
+        let rows = stmt.query_map(rusqlite::params_from_iter(params), SignedMemory::from_row)?;
+
+        let mut memories = Vec::new();
+        for row in rows {
+            memories.push(row?);
+        }
+        Ok(query.page(memories))
     }
 
-    pub fn get_affiliation(&self, id: &str) -> Result<Option<Affiliation>> {
+    /// The group DID and the members of its latest known membership version
+    fn group_feed_authors(&self, group_did: &str) -> Result<Vec<String>> {
+        let latest = self
+            .list_memories_by_type(GROUP_MEMBERSHIP_MEMORY_TYPE)?
+            .iter()
+            .filter_map(|memory| serde_json::from_str::<GroupMembership>(&memory.memory_data).ok())
+            .filter(|membership| membership.group_did == group_did)
+            .max_by_key(|membership| membership.version)
+            .ok_or_else(|| OcmError::NotFound(format!("Group {}", group_did)))?;
+
+        let mut authors = latest.members;
+        authors.push(group_did.to_string());
+        Ok(authors)
+    }
+
+    // Annotation operations
+    /// Annotations whose target is `memory_id`, oldest first
+    pub fn list_annotations_for(&self, memory_id: &MemoryId) -> Result<Vec<SignedMemory>> {
+        let sql = format!(
+            "SELECT {} FROM {} WHERE id IN
+                 (SELECT annotation_id FROM memory_annotation WHERE target_memory_id = ?1)
+             ORDER BY timestamp ASC",
+            SignedMemory::select_fields(),
+            SignedMemory::table_name()
+        );
         let conn = self.get_connection()?;
-        let mut stmt = conn.prepare(
-            "SELECT id, name, affiliation_type, value, range_min, range_max, cohort, updated_on 
-             FROM affiliation WHERE id = ?1",
-        )?;
+        let mut stmt = conn.prepare(&sql)?;
+        let rows = stmt.query_map([memory_id], SignedMemory::from_row)?;
 
-        let mut rows = stmt.query_map([id], |row| {
-            let affiliation_type_str: String = row.get(2)?;
-            let affiliation_type =
-                AffiliationType::from_string(&affiliation_type_str).map_err(|e| {
-                    rusqlite::Error::InvalidColumnType(2, e.into(), rusqlite::types::Type::Text)
-                })?;
+        let mut annotations = Vec::new();
+        for row in rows {
+            annotations.push(row?);
+        }
+        Ok(annotations)
+    }
 
-            Ok(Affiliation {
-                id: row.get(0)?,
-                name: row.get(1)?,
-                affiliation_type,
-                value: row.get(3)?,
-                range_min: row.get(4)?,
-                range_max: row.get(5)?,
-                cohort: row.get(6)?,
-                updated_on: row.get(7)?,
-            })
-        })?;
+    // Derived memory operations
+    pub fn list_memories_by_type(&self, memory_type: &str) -> Result<Vec<SignedMemory>> {
+        let sql = format!(
+            "SELECT {} FROM {} WHERE memory_type = ?1 ORDER BY timestamp ASC",
+            SignedMemory::select_fields(),
+            SignedMemory::table_name()
+        );
+        let conn = self.get_connection()?;
+        let mut stmt = conn.prepare(&sql)?;
+        let rows = stmt.query_map([memory_type], SignedMemory::from_row)?;
+
+        let mut memories = Vec::new();
+        for row in rows {
+            memories.push(row?);
+        }
+        Ok(memories)
+    }
+
+    /// ID of the memory currently holding a derivation's value for `key`
+    pub fn get_memory_derivation(&self, derivation: &str, key: &str) -> Result<Option<MemoryId>> {
+        let conn = self.get_connection()?;
+        let mut stmt = conn.prepare(
+            "SELECT memory_id FROM memory_derivation
+             WHERE derivation = ?1 AND derivation_key = ?2",
+        )?;
+        let mut rows = stmt.query_map([derivation, key], |row| row.get(0))?;
 
         match rows.next() {
             Some(row) => Ok(Some(row?)),
@@ -523,352 +955,3783 @@ impl Database {
         }
     }
 
-    pub fn update_affiliation(&self, affiliation: &Affiliation) -> Result<()> {
+    /// Record which source versions a derived memory was computed from
+    pub fn record_memory_derivation(
+        &self,
+        derivation: &str,
+        key: &str,
+        memory_id: &MemoryId,
+        sources: &[DerivedSource],
+    ) -> Result<()> {
+        let mut conn = self.get_connection()?;
+        let tx = conn.transaction()?;
+        tx.execute(
+            "INSERT INTO memory_derivation (derivation, derivation_key, memory_id, computed_at)
+             VALUES (?1, ?2, ?3, ?4)
+             ON CONFLICT(derivation, derivation_key) DO UPDATE SET
+                 memory_id = excluded.memory_id,
+                 computed_at = excluded.computed_at",
+            (derivation, key, memory_id, chrono::Utc::now().to_rfc3339()),
+        )?;
+        tx.execute(
+            "DELETE FROM memory_derivation_source WHERE memory_id = ?1",
+            [memory_id],
+        )?;
+        for source in sources {
+            tx.execute(
+                "INSERT INTO memory_derivation_source
+                     (memory_id, source_memory_id, source_content_hash)
+                 VALUES (?1, ?2, ?3)",
+                (memory_id, &source.memory_id, &source.content_hash),
+            )?;
+        }
+        tx.commit()?;
+        Ok(())
+    }
+
+    /// (derivation, key) pairs whose current value was computed from `source_memory_id`
+    pub fn list_derivations_by_source(
+        &self,
+        source_memory_id: &MemoryId,
+    ) -> Result<Vec<(String, String)>> {
+        let conn = self.get_connection()?;
+        let mut stmt = conn.prepare(
+            "SELECT d.derivation, d.derivation_key
+             FROM memory_derivation_source s
+             JOIN memory_derivation d ON d.memory_id = s.memory_id
+             WHERE s.source_memory_id = ?1",
+        )?;
+        let rows = stmt.query_map([source_memory_id], |row| Ok((row.get(0)?, row.get(1)?)))?;
+
+        let mut derivations = Vec::new();
+        for row in rows {
+            derivations.push(row?);
+        }
+        Ok(derivations)
+    }
+
+    // Automation rule operations
+    /// Create or replace a rule, clearing the outcome of its previous version
+    pub fn upsert_automation_rule(&self, rule: &AutomationRule) -> Result<()> {
         let conn = self.get_connection()?;
         conn.execute(
-            "UPDATE affiliation SET name = ?2, affiliation_type = ?3, value = ?4, range_min = ?5, 
-             range_max = ?6, cohort = ?7, updated_on = ?8 WHERE id = ?1",
+            "INSERT INTO automation_rule
+                 (name, description, event_type, memory_type, script, enabled, updated_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)
+             ON CONFLICT(name) DO UPDATE SET
+                 description = excluded.description,
+                 event_type = excluded.event_type,
+                 memory_type = excluded.memory_type,
+                 script = excluded.script,
+                 enabled = excluded.enabled,
+                 updated_at = excluded.updated_at,
+                 last_run_at = NULL,
+                 last_error = NULL",
             (
-                &affiliation.id,
-                &affiliation.name,
-                &affiliation.affiliation_type.to_string(),
-                &affiliation.value,
-                &affiliation.range_min,
-                &affiliation.range_max,
-                &affiliation.cohort,
-                &affiliation.updated_on,
+                &rule.name,
+                &rule.description,
+                &rule.event_type,
+                &rule.memory_type,
+                &rule.script,
+                rule.enabled,
+                &rule.updated_at,
             ),
         )?;
         Ok(())
     }
 
-    pub fn delete_affiliation(&self, id: &str) -> Result<()> {
+    pub fn delete_automation_rule(&self, name: &str) -> Result<bool> {
         let conn = self.get_connection()?;
-        conn.execute("DELETE FROM affiliation WHERE id = ?1", [id])?;
-        Ok(())
+        let deleted = conn.execute("DELETE FROM automation_rule WHERE name = ?1", [name])?;
+        Ok(deleted > 0)
     }
 
-    pub fn list_affiliations(&self) -> Result<Vec<Affiliation>> {
+    pub fn list_automation_rules(&self) -> Result<Vec<AutomationRule>> {
         let conn = self.get_connection()?;
         let mut stmt = conn.prepare(
-            "SELECT id, name, affiliation_type, value, range_min, range_max, cohort, updated_on 
-             FROM affiliation",
+            "SELECT name, description, event_type, memory_type, script, enabled, updated_at,
+                    last_run_at, last_error
+             FROM automation_rule ORDER BY name",
         )?;
-
         let rows = stmt.query_map([], |row| {
-            let affiliation_type_str: String = row.get(2)?;
-            let affiliation_type =
-                AffiliationType::from_string(&affiliation_type_str).map_err(|e| {
-                    rusqlite::Error::InvalidColumnType(2, e.into(), rusqlite::types::Type::Text)
-                })?;
-
-            Ok(Affiliation {
-                id: row.get(0)?,
-                name: row.get(1)?,
-                affiliation_type,
-                value: row.get(3)?,
-                range_min: row.get(4)?,
-                range_max: row.get(5)?,
-                cohort: row.get(6)?,
-                updated_on: row.get(7)?,
+            Ok(AutomationRule {
+                name: row.get(0)?,
+                description: row.get(1)?,
+                event_type: row.get(2)?,
+                memory_type: row.get(3)?,
+                script: row.get(4)?,
+                enabled: row.get(5)?,
+                updated_at: row.get(6)?,
+                last_run_at: row.get(7)?,
+                last_error: row.get(8)?,
             })
         })?;
 
-        let mut affiliations = Vec::new();
+        let mut rules = Vec::new();
         for row in rows {
-            affiliations.push(row?);
+            rules.push(row?);
         }
-        Ok(affiliations)
+        Ok(rules)
     }
 
-    // Condition CRUD operations
-    pub fn create_condition(&self, condition: &Condition) -> Result<()> {
+    /// Record the outcome of running a rule; `error` is None on success
+    pub fn record_automation_rule_run(&self, name: &str, error: Option<&str>) -> Result<()> {
         let conn = self.get_connection()?;
         conn.execute(
-            "INSERT INTO condition (id, name, condition_type, age_min, age_max, calculated_age_from, calculated_age_to, coordinates_lat, coordinates_lon, distance, updated_on)
-             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11)",
+            "UPDATE automation_rule SET last_run_at = ?2, last_error = ?3 WHERE name = ?1",
+            (name, chrono::Utc::now().to_rfc3339(), error),
+        )?;
+        Ok(())
+    }
+
+    // Contact operations
+    /// Apply a contact memory unless a later one for the same contact was applied already
+    pub fn apply_contact(
+        &self,
+        data: &ContactData,
+        updated_at: &str,
+        contact_memory_id: &MemoryId,
+    ) -> Result<bool> {
+        let mut conn = self.get_connection()?;
+        let tx = conn.transaction()?;
+        let changed = tx.execute(
+            "INSERT INTO contact (did, display_name, verification, key_fingerprint, notes, removed,
+                                  updated_at, contact_memory_id)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)
+             ON CONFLICT(did) DO UPDATE SET
+                 display_name = excluded.display_name,
+                 verification = excluded.verification,
+                 key_fingerprint = excluded.key_fingerprint,
+                 notes = excluded.notes,
+                 removed = excluded.removed,
+                 updated_at = excluded.updated_at,
+                 contact_memory_id = excluded.contact_memory_id
+             WHERE excluded.updated_at > contact.updated_at",
             (
-                &condition.id,
-                &condition.name,
-                &condition.condition_type.to_string(),
-                &condition.age_min,
-                &condition.age_max,
-                &condition.calculated_age_from,
-                &condition.calculated_age_to,
-                &condition.coordinates_lat,
-                &condition.coordinates_lon,
-                &condition.distance,
-                &condition.updated_on,
+                &data.contact_did,
+                &data.display_name,
+                data.verification.as_str(),
+                &data.key_fingerprint,
+                &data.notes,
+                data.removed,
+                updated_at,
+                contact_memory_id,
             ),
         )?;
-        Ok(())
+        if changed > 0 {
+            tx.execute(
+                "DELETE FROM contact_group WHERE contact_did = ?1",
+                [&data.contact_did],
+            )?;
+            if !data.removed {
+                for group in &data.groups {
+                    tx.execute(
+                        "INSERT OR IGNORE INTO contact_group (contact_did, group_name)
+                         VALUES (?1, ?2)",
+                        (&data.contact_did, group),
+                    )?;
+                }
+            }
+        }
+        tx.commit()?;
+        Ok(changed > 0)
     }
 
-    pub fn get_condition(&self, id: &str) -> Result<Option<Condition>> {
+    pub fn get_contact(&self, did: &Did) -> Result<Option<Contact>> {
+        Ok(self.query_contacts("c.did = ?1", [did])?.into_iter().next())
+    }
+
+    /// Contacts by display name, optionally only those in `group`
+    pub fn list_contacts(&self, group: Option<&str>) -> Result<Vec<Contact>> {
+        match group {
+            Some(group) => self.query_contacts(
+                "c.did IN (SELECT contact_did FROM contact_group WHERE group_name = ?1)",
+                [group],
+            ),
+            None => self.query_contacts("1 = 1", []),
+        }
+    }
+
+    fn query_contacts<P: rusqlite::Params>(&self, filter: &str, params: P) -> Result<Vec<Contact>> {
         let conn = self.get_connection()?;
-        let mut stmt = conn.prepare(
-            "SELECT id, name, condition_type, age_min, age_max, calculated_age_from, calculated_age_to, coordinates_lat, coordinates_lon, distance, updated_on 
-             FROM condition WHERE id = ?1"
-        )?;
+        let mut stmt = conn.prepare(&format!(
+            "SELECT c.did, c.display_name, c.verification, c.key_fingerprint, c.notes, c.updated_at,
+                    (SELECT group_concat(group_name, ',') FROM contact_group
+                     WHERE contact_did = c.did)
+             FROM contact c
+             WHERE c.removed = 0 AND {}
+             ORDER BY c.display_name COLLATE NOCASE, c.did",
+            filter
+        ))?;
+        let rows = stmt.query_map(params, |row| {
+            let verification: String = row.get(2)?;
+            let groups: Option<String> = row.get(6)?;
+            let mut groups: Vec<String> = groups
+                .unwrap_or_default()
+                .split(',')
+                .filter(|group| !group.is_empty())
+                .map(str::to_string)
+                .collect();
+            groups.sort();
+            Ok(Contact {
+                did: row.get(0)?,
+                display_name: row.get(1)?,
+                verification: ContactVerification::parse(&verification).unwrap_or_default(),
+                key_fingerprint: row.get(3)?,
+                notes: row.get(4)?,
+                groups,
+                updated_at: row.get(5)?,
+            })
+        })?;
 
-        let mut rows = stmt.query_map([id], |row| {
-            let condition_type_str: String = row.get(2)?;
-            let condition_type = ConditionType::from_string(&condition_type_str).map_err(|e| {
-                rusqlite::Error::InvalidColumnType(2, e.into(), rusqlite::types::Type::Text)
-            })?;
+        let mut contacts = Vec::new();
+        for row in rows {
+            contacts.push(row?);
+        }
+        Ok(contacts)
+    }
 
-            Ok(Condition {
+    // Direct message operations
+    pub fn insert_direct_message(&self, message: &DirectMessage) -> Result<bool> {
+        let conn = self.get_connection()?;
+        let inserted = conn.execute(
+            "INSERT OR IGNORE INTO direct_message (id, sender_did, recipient_did, body, sent_at, read_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+            (
+                &message.id,
+                &message.sender_did,
+                &message.recipient_did,
+                &message.body,
+                &message.sent_at,
+                &message.read_at,
+            ),
+        )?;
+        Ok(inserted > 0)
+    }
+
+    pub fn get_direct_message(&self, id: &str) -> Result<Option<DirectMessage>> {
+        Ok(self
+            .query_direct_messages("id = ?1", [id])?
+            .into_iter()
+            .next())
+    }
+
+    /// Messages exchanged between two DIDs in either direction, oldest first
+    pub fn list_conversation(&self, did_a: &str, did_b: &str) -> Result<Vec<DirectMessage>> {
+        self.query_direct_messages(
+            "(sender_did = ?1 AND recipient_did = ?2) OR (sender_did = ?2 AND recipient_did = ?1)",
+            [did_a, did_b],
+        )
+    }
+
+    /// Messages received by `recipient_did`, oldest first
+    pub fn list_inbox(&self, recipient_did: &Did, unread_only: bool) -> Result<Vec<DirectMessage>> {
+        if unread_only {
+            self.query_direct_messages("recipient_did = ?1 AND read_at IS NULL", [recipient_did])
+        } else {
+            self.query_direct_messages("recipient_did = ?1", [recipient_did])
+        }
+    }
+
+    /// Mark a received message read; false if it was read already or does not exist
+    pub fn mark_direct_message_read(&self, id: &str, recipient_did: &Did) -> Result<bool> {
+        let conn = self.get_connection()?;
+        let updated = conn.execute(
+            "UPDATE direct_message SET read_at = ?3
+             WHERE id = ?1 AND recipient_did = ?2 AND read_at IS NULL",
+            (id, recipient_did, chrono::Utc::now().to_rfc3339()),
+        )?;
+        Ok(updated > 0)
+    }
+
+    fn query_direct_messages<P: rusqlite::Params>(
+        &self,
+        filter: &str,
+        params: P,
+    ) -> Result<Vec<DirectMessage>> {
+        let conn = self.get_connection()?;
+        let mut stmt = conn.prepare(&format!(
+            "SELECT id, sender_did, recipient_did, body, sent_at, read_at
+             FROM direct_message
+             WHERE {}
+             ORDER BY sent_at, id",
+            filter
+        ))?;
+        let rows = stmt.query_map(params, |row| {
+            Ok(DirectMessage {
                 id: row.get(0)?,
-                name: row.get(1)?,
-                condition_type,
-                age_min: row.get(3)?,
-                age_max: row.get(4)?,
-                calculated_age_from: row.get(5)?,
-                calculated_age_to: row.get(6)?,
-                coordinates_lat: row.get(7)?,
-                coordinates_lon: row.get(8)?,
-                distance: row.get(9)?,
-                updated_on: row.get(10)?,
+                sender_did: row.get(1)?,
+                recipient_did: row.get(2)?,
+                body: row.get(3)?,
+                sent_at: row.get(4)?,
+                read_at: row.get(5)?,
             })
         })?;
 
-        match rows.next() {
-            Some(row) => Ok(Some(row?)),
-            None => Ok(None),
+        let mut messages = Vec::new();
+        for row in rows {
+            messages.push(row?);
         }
+        Ok(messages)
     }
 
-    pub fn update_condition(&self, condition: &Condition) -> Result<()> {
+    // Receipt operations
+    /// Record a receipt signed by `did`; false if that acknowledgment was recorded already
+    pub fn record_receipt(
+        &self,
+        data: &ReceiptData,
+        did: &Did,
+        signed_at: &str,
+        receipt_memory_id: &MemoryId,
+    ) -> Result<bool> {
         let conn = self.get_connection()?;
-        conn.execute(
-            "UPDATE condition SET name = ?2, condition_type = ?3, age_min = ?4, age_max = ?5, 
-             calculated_age_from = ?6, calculated_age_to = ?7, coordinates_lat = ?8, coordinates_lon = ?9, 
-             distance = ?10, updated_on = ?11 WHERE id = ?1",
+        let inserted = conn.execute(
+            "INSERT OR IGNORE INTO memory_receipt (memory_id, did, kind, signed_at, receipt_memory_id)
+             VALUES (?1, ?2, ?3, ?4, ?5)",
             (
-                &condition.id,
-                &condition.name,
-                &condition.condition_type.to_string(),
-                &condition.age_min,
-                &condition.age_max,
-                &condition.calculated_age_from,
-                &condition.calculated_age_to,
-                &condition.coordinates_lat,
-                &condition.coordinates_lon,
-                &condition.distance,
-                &condition.updated_on,
+                &data.memory_id,
+                did,
+                data.kind.as_str(),
+                signed_at,
+                receipt_memory_id,
             ),
         )?;
-        Ok(())
+        Ok(inserted > 0)
     }
 
-    pub fn delete_condition(&self, id: &str) -> Result<()> {
+    pub fn has_receipt(&self, memory_id: &MemoryId, did: &Did, kind: ReceiptKind) -> Result<bool> {
         let conn = self.get_connection()?;
-        conn.execute("DELETE FROM condition WHERE id = ?1", [id])?;
-        Ok(())
+        let found: Option<i64> = conn
+            .query_row(
+                "SELECT 1 FROM memory_receipt WHERE memory_id = ?1 AND did = ?2 AND kind = ?3",
+                (memory_id, did, kind.as_str()),
+                |row| row.get(0),
+            )
+            .optional()?;
+        Ok(found.is_some())
     }
 
-    pub fn list_conditions(&self) -> Result<Vec<Condition>> {
+    /// Receipts for a memory, one entry per acknowledging DID
+    pub fn get_delivery_status(&self, memory_id: &MemoryId) -> Result<DeliveryStatus> {
         let conn = self.get_connection()?;
         let mut stmt = conn.prepare(
-            "SELECT id, name, condition_type, age_min, age_max, calculated_age_from, calculated_age_to, coordinates_lat, coordinates_lon, distance, updated_on 
-             FROM condition"
+            "SELECT did,
+                    MIN(CASE WHEN kind = 'delivered' THEN signed_at END),
+                    MIN(CASE WHEN kind = 'read' THEN signed_at END)
+             FROM memory_receipt
+             WHERE memory_id = ?1
+             GROUP BY did
+             ORDER BY did",
         )?;
-
-        let rows = stmt.query_map([], |row| {
-            let condition_type_str: String = row.get(2)?;
-            let condition_type = ConditionType::from_string(&condition_type_str).map_err(|e| {
-                rusqlite::Error::InvalidColumnType(2, e.into(), rusqlite::types::Type::Text)
-            })?;
-
-            Ok(Condition {
-                id: row.get(0)?,
-                name: row.get(1)?,
-                condition_type,
-                age_min: row.get(3)?,
-                age_max: row.get(4)?,
-                calculated_age_from: row.get(5)?,
-                calculated_age_to: row.get(6)?,
-                coordinates_lat: row.get(7)?,
-                coordinates_lon: row.get(8)?,
-                distance: row.get(9)?,
-                updated_on: row.get(10)?,
+        let rows = stmt.query_map([memory_id], |row| {
+            let did: Did = row.get(0)?;
+            let delivered_at: Option<String> = row.get(1)?;
+            let read_at: Option<String> = row.get(2)?;
+            Ok(MemoryReceipt {
+                did,
+                // Reading implies delivery, even if the delivery receipt is still in transit
+                delivered_at: delivered_at.or_else(|| read_at.clone()),
+                read_at,
             })
         })?;
 
-        let mut conditions = Vec::new();
+        let mut receipts = Vec::new();
         for row in rows {
-            conditions.push(row?);
+            receipts.push(row?);
         }
-        Ok(conditions)
+        Ok(DeliveryStatus {
+            memory_id: memory_id.clone(),
+            receipts,
+        })
     }
 
-    // Claim Token CRUD operations
-    pub fn create_claim_token(&self, token: &ClaimToken) -> Result<()> {
+    // Presence operations
+    pub fn record_peer_presence(
+        &self,
+        peer_id: &PeerId,
+        did: Option<&Did>,
+        update: &PresenceUpdate,
+        last_seen: &str,
+    ) -> Result<()> {
         let conn = self.get_connection()?;
         conn.execute(
-            ClaimToken::insert_sql(),
+            "INSERT INTO peer_presence (peer_id, did, status, last_sync_at, last_seen)
+             VALUES (?1, ?2, ?3, ?4, ?5)
+             ON CONFLICT(peer_id) DO UPDATE SET
+                 did = excluded.did,
+                 status = excluded.status,
+                 last_sync_at = excluded.last_sync_at,
+                 last_seen = excluded.last_seen",
             (
-                &token.id,
-                &token.token,
-                &token.memory_id,
-                &token.organization_did,
-                &token.expiry_timestamp,
-                &token.claimed_by_did,
-                &token.claimed_timestamp,
-                &token.created_timestamp,
-                &token.updated_on,
+                peer_id,
+                did,
+                update.status.map(|status| status.as_str()),
+                &update.last_sync_at,
+                last_seen,
             ),
         )?;
         Ok(())
     }
 
-    pub fn get_claim_token(&self, id: &str) -> Result<Option<ClaimToken>> {
-        let sql = format!(
-            "SELECT {} FROM {} WHERE id = ?1",
-            ClaimToken::select_fields(),
-            ClaimToken::table_name()
-        );
+    /// Presence of every peer heard from, most recently seen first. Peers seen at or
+    /// after `online_since` count as online
+    pub fn list_peer_presence(&self, online_since: &str) -> Result<Vec<PeerPresence>> {
         let conn = self.get_connection()?;
-        let mut stmt = conn.prepare(&sql)?;
-        let mut rows = stmt.query_map([id], ClaimToken::from_row)?;
+        let mut stmt = conn.prepare(
+            "SELECT peer_id, did, status, last_sync_at, last_seen, last_seen >= ?1
+             FROM peer_presence
+             ORDER BY last_seen DESC, peer_id",
+        )?;
+        let rows = stmt.query_map([online_since], |row| {
+            let status: Option<String> = row.get(2)?;
+            Ok(PeerPresence {
+                peer_id: row.get(0)?,
+                did: row.get(1)?,
+                online: row.get(5)?,
+                status: status.as_deref().and_then(PresenceStatus::parse),
+                last_sync_at: row.get(3)?,
+                last_seen: row.get(4)?,
+            })
+        })?;
 
-        match rows.next() {
-            Some(row) => Ok(Some(row?)),
-            None => Ok(None),
+        let mut presence = Vec::new();
+        for row in rows {
+            presence.push(row?);
         }
+        Ok(presence)
     }
 
-    pub fn get_claim_token_by_token(&self, token: &str) -> Result<Option<ClaimToken>> {
-        let sql = format!(
-            "SELECT {} FROM {} WHERE token = ?1",
-            ClaimToken::select_fields(),
-            ClaimToken::table_name()
-        );
+    // Peer record operations
+    /// Record what a peer last said about itself, and whether it is in the peer set
+    pub fn record_peer(&self, peer: &PeerInfo, connected: bool) -> Result<()> {
         let conn = self.get_connection()?;
-        let mut stmt = conn.prepare(&sql)?;
-        let mut rows = stmt.query_map([token], ClaimToken::from_row)?;
+        conn.execute(
+            "INSERT INTO peer_record
+                 (peer_id, did, address, port, capabilities, protocol_version, last_seen, connected)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)
+             ON CONFLICT(peer_id) DO UPDATE SET
+                 did = COALESCE(excluded.did, peer_record.did),
+                 address = excluded.address,
+                 port = excluded.port,
+                 capabilities = excluded.capabilities,
+                 protocol_version = excluded.protocol_version,
+                 last_seen = excluded.last_seen,
+                 connected = excluded.connected",
+            rusqlite::params![
+                &peer.peer_id,
+                &peer.did,
+                &peer.address,
+                peer.port,
+                serde_json::to_string(&peer.capabilities)?,
+                peer.protocol_version,
+                peer.last_seen.to_rfc3339(),
+                connected,
+            ],
+        )?;
+        Ok(())
+    }
 
-        match rows.next() {
-            Some(row) => Ok(Some(row?)),
-            None => Ok(None),
+    /// Mark every recorded peer not in `peer_ids` as disconnected
+    pub fn mark_peers_disconnected(&self, peer_ids: &[String]) -> Result<()> {
+        let mut conn = self.get_connection()?;
+        let tx = conn.transaction()?;
+        tx.execute("UPDATE peer_record SET connected = 0", [])?;
+        for peer_id in peer_ids {
+            tx.execute(
+                "UPDATE peer_record SET connected = 1 WHERE peer_id = ?1",
+                [peer_id],
+            )?;
         }
+        tx.commit()?;
+        Ok(())
     }
 
-    pub fn update_claim_token(&self, token: &ClaimToken) -> Result<()> {
+    /// Add traffic to a peer's running totals
+    pub fn add_peer_traffic(&self, peer_id: &PeerId, traffic: &PeerTraffic) -> Result<()> {
         let conn = self.get_connection()?;
         conn.execute(
-            ClaimToken::update_sql(),
+            "INSERT INTO peer_record
+                 (peer_id, messages_sent, messages_received, bytes_sent, bytes_received,
+                  send_failures, rejected)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)
+             ON CONFLICT(peer_id) DO UPDATE SET
+                 messages_sent = messages_sent + excluded.messages_sent,
+                 messages_received = messages_received + excluded.messages_received,
+                 bytes_sent = bytes_sent + excluded.bytes_sent,
+                 bytes_received = bytes_received + excluded.bytes_received,
+                 send_failures = send_failures + excluded.send_failures,
+                 rejected = rejected + excluded.rejected",
+            rusqlite::params![
+                peer_id,
+                traffic.messages_sent as i64,
+                traffic.messages_received as i64,
+                traffic.bytes_sent as i64,
+                traffic.bytes_received as i64,
+                traffic.send_failures as i64,
+                traffic.rejected as i64,
+            ],
+        )?;
+        Ok(())
+    }
+
+    /// Ban, trust or clear a peer, including one not seen yet
+    pub fn set_peer_standing(
+        &self,
+        peer_id: &PeerId,
+        standing: PeerStanding,
+        reason: Option<&str>,
+    ) -> Result<()> {
+        let conn = self.get_connection()?;
+        conn.execute(
+            "INSERT INTO peer_record (peer_id, standing, standing_reason, standing_changed_at)
+             VALUES (?1, ?2, ?3, ?4)
+             ON CONFLICT(peer_id) DO UPDATE SET
+                 standing = excluded.standing,
+                 standing_reason = excluded.standing_reason,
+                 standing_changed_at = excluded.standing_changed_at",
             (
-                &token.id,
-                &token.token,
-                &token.memory_id,
-                &token.organization_did,
-                &token.expiry_timestamp,
-                &token.claimed_by_did,
-                &token.claimed_timestamp,
-                &token.created_timestamp,
-                &token.updated_on,
+                peer_id,
+                standing.as_str(),
+                reason,
+                chrono::Utc::now().to_rfc3339(),
             ),
         )?;
         Ok(())
     }
 
-    pub fn list_claim_tokens_by_organization(
+    /// Ask the node to drop a peer from its peer set; false if the peer is unknown
+    pub fn request_peer_disconnect(&self, peer_id: &PeerId) -> Result<bool> {
+        let conn = self.get_connection()?;
+        let requested = conn.execute(
+            "UPDATE peer_record SET disconnect_requested = 1 WHERE peer_id = ?1",
+            [peer_id],
+        )?;
+        Ok(requested > 0)
+    }
+
+    /// Peers whose disconnection was requested since the last call
+    pub fn take_peer_disconnect_requests(&self) -> Result<Vec<String>> {
+        let mut conn = self.get_connection()?;
+        let tx = conn.transaction()?;
+        let peer_ids = {
+            let mut stmt =
+                tx.prepare("SELECT peer_id FROM peer_record WHERE disconnect_requested = 1")?;
+            let rows = stmt.query_map([], |row| row.get::<_, String>(0))?;
+            let mut peer_ids = Vec::new();
+            for row in rows {
+                peer_ids.push(row?);
+            }
+            peer_ids
+        };
+        tx.execute(
+            "UPDATE peer_record SET disconnect_requested = 0 WHERE disconnect_requested = 1",
+            [],
+        )?;
+        tx.commit()?;
+        Ok(peer_ids)
+    }
+
+    pub fn get_peer_record(&self, peer_id: &PeerId) -> Result<Option<PeerRecord>> {
+        Ok(self
+            .query_peer_records("WHERE peer_id = ?1", &[&peer_id])?
+            .into_iter()
+            .next())
+    }
+
+    /// Every recorded peer, connected ones first, then most recently seen
+    pub fn list_peer_records(&self) -> Result<Vec<PeerRecord>> {
+        self.query_peer_records("", &[])
+    }
+
+    fn query_peer_records(
         &self,
-        organization_did: &str,
-    ) -> Result<Vec<ClaimToken>> {
-        let sql = format!(
-            "SELECT {} FROM {} WHERE organization_did = ?1 ORDER BY created_timestamp DESC",
-            ClaimToken::select_fields(),
-            ClaimToken::table_name()
-        );
+        filter: &str,
+        params: &[&dyn rusqlite::ToSql],
+    ) -> Result<Vec<PeerRecord>> {
         let conn = self.get_connection()?;
-        let mut stmt = conn.prepare(&sql)?;
-        let rows = stmt.query_map([organization_did], ClaimToken::from_row)?;
+        let mut stmt = conn.prepare(&format!(
+            "SELECT peer_id, did, address, port, capabilities, protocol_version, last_seen,
+                    connected, messages_sent, messages_received, bytes_sent, bytes_received,
+                    send_failures, rejected, standing, standing_reason, standing_changed_at
+             FROM peer_record {}
+             ORDER BY connected DESC, last_seen DESC, peer_id",
+            filter
+        ))?;
+        let rows = stmt.query_map(params, |row| {
+            let capabilities: String = row.get(4)?;
+            let standing: String = row.get(14)?;
+            let traffic = PeerTraffic {
+                messages_sent: row.get::<_, i64>(8)? as u64,
+                messages_received: row.get::<_, i64>(9)? as u64,
+                bytes_sent: row.get::<_, i64>(10)? as u64,
+                bytes_received: row.get::<_, i64>(11)? as u64,
+                send_failures: row.get::<_, i64>(12)? as u64,
+                rejected: row.get::<_, i64>(13)? as u64,
+            };
+            let standing = PeerStanding::parse(&standing).unwrap_or_default();
+            Ok(PeerRecord {
+                peer_id: row.get(0)?,
+                did: row.get(1)?,
+                address: row.get(2)?,
+                port: row.get(3)?,
+                capabilities: serde_json::from_str(&capabilities).unwrap_or_default(),
+                protocol_version: row.get(5)?,
+                last_seen: row.get(6)?,
+                connected: row.get(7)?,
+                reputation: PeerRecord::reputation_of(standing, &traffic),
+                traffic,
+                standing,
+                standing_reason: row.get(15)?,
+                standing_changed_at: row.get(16)?,
+            })
+        })?;
 
-        let mut tokens = Vec::new();
+        let mut records = Vec::new();
         for row in rows {
-            tokens.push(row?);
+            records.push(row?);
         }
-        Ok(tokens)
+        Ok(records)
     }
 
-    // Proxy Memory CRUD operations
-    pub fn create_proxy_memory(&self, proxy: &ProxyMemory) -> Result<()> {
+    // Supervised task operations
+    /// Replace what is recorded about `process`'s tasks with `tasks`
+    pub fn replace_task_health(&self, process: &str, tasks: &[TaskHealth]) -> Result<()> {
+        let mut conn = self.get_connection()?;
+        let tx = conn.transaction()?;
+        tx.execute("DELETE FROM supervised_task WHERE process = ?1", [process])?;
+        for task in tasks {
+            tx.execute(
+                "INSERT INTO supervised_task
+                     (process, name, state, restarts, last_panic, started_at, reported_at)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+                rusqlite::params![
+                    process,
+                    &task.name,
+                    task.state.as_str(),
+                    task.restarts,
+                    &task.last_panic,
+                    &task.started_at,
+                    &task.reported_at,
+                ],
+            )?;
+        }
+        tx.commit()?;
+        Ok(())
+    }
+
+    /// Every recorded task, grouped by process
+    pub fn list_task_health(&self) -> Result<Vec<TaskHealth>> {
+        let conn = self.get_connection()?;
+        let mut stmt = conn.prepare(
+            "SELECT process, name, state, restarts, last_panic, started_at, reported_at
+             FROM supervised_task ORDER BY process, name",
+        )?;
+        let rows = stmt.query_map([], |row| {
+            let state: String = row.get(2)?;
+            Ok(TaskHealth {
+                process: row.get(0)?,
+                name: row.get(1)?,
+                state: TaskState::parse(&state).unwrap_or(TaskState::Failed),
+                restarts: row.get(3)?,
+                last_panic: row.get(4)?,
+                started_at: row.get(5)?,
+                reported_at: row.get(6)?,
+            })
+        })?;
+
+        let mut tasks = Vec::new();
+        for row in rows {
+            tasks.push(row?);
+        }
+        Ok(tasks)
+    }
+
+    // Push subscription operations
+    /// Store a subscription, replacing any other registration of the same endpoint
+    pub fn upsert_push_subscription(&self, subscription: &PushSubscription) -> Result<()> {
         let conn = self.get_connection()?;
         conn.execute(
-            ProxyMemory::insert_sql(),
+            "INSERT INTO push_subscription
+                 (id, did, kind, endpoint, p256dh, auth, events, created_at, last_pushed_at, last_error)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, NULL, NULL)
+             ON CONFLICT(endpoint) DO UPDATE SET
+                 id = excluded.id,
+                 did = excluded.did,
+                 kind = excluded.kind,
+                 p256dh = excluded.p256dh,
+                 auth = excluded.auth,
+                 events = excluded.events,
+                 created_at = excluded.created_at,
+                 last_pushed_at = NULL,
+                 last_error = NULL",
             (
-                &proxy.id,
-                &proxy.proxy_for_name,
-                &proxy.proxy_for_info,
-                &proxy.organization_did,
-                &proxy.memory_data,
-                &proxy.created_timestamp,
-                &proxy.claim_token_id,
+                &subscription.id,
+                &subscription.did,
+                subscription.kind.as_str(),
+                &subscription.endpoint,
+                &subscription.p256dh,
+                &subscription.auth,
+                serde_json::to_string(&subscription.events)?,
+                &subscription.created_at,
             ),
         )?;
         Ok(())
     }
 
-    pub fn get_proxy_memory(&self, id: &str) -> Result<Option<ProxyMemory>> {
-        let sql = format!(
-            "SELECT {} FROM {} WHERE id = ?1",
-            ProxyMemory::select_fields(),
-            ProxyMemory::table_name()
-        );
-        let conn = self.get_connection()?;
-        let mut stmt = conn.prepare(&sql)?;
-        let mut rows = stmt.query_map([id], ProxyMemory::from_row)?;
+    pub fn get_push_subscription(&self, id: &str) -> Result<Option<PushSubscription>> {
+        Ok(self
+            .query_push_subscriptions("WHERE id = ?1", [id])?
+            .into_iter()
+            .next())
+    }
 
-        match rows.next() {
-            Some(row) => Ok(Some(row?)),
-            None => Ok(None),
-        }
+    /// A DID's subscriptions, or every subscription
+    pub fn list_push_subscriptions(&self, did: Option<&Did>) -> Result<Vec<PushSubscription>> {
+        self.query_push_subscriptions("WHERE ?1 IS NULL OR did = ?1", [did])
     }
 
-    pub fn list_proxy_memories_by_organization(
+    fn query_push_subscriptions<P: rusqlite::Params>(
         &self,
-        organization_did: &str,
-    ) -> Result<Vec<ProxyMemory>> {
-        let sql = format!(
-            "SELECT {} FROM {} WHERE organization_did = ?1 ORDER BY created_timestamp DESC",
-            ProxyMemory::select_fields(),
-            ProxyMemory::table_name()
-        );
+        filter: &str,
+        params: P,
+    ) -> Result<Vec<PushSubscription>> {
         let conn = self.get_connection()?;
-        let mut stmt = conn.prepare(&sql)?;
-        let rows = stmt.query_map([organization_did], ProxyMemory::from_row)?;
+        let mut stmt = conn.prepare(&format!(
+            "SELECT id, did, kind, endpoint, p256dh, auth, events, created_at, last_pushed_at, last_error
+             FROM push_subscription {} ORDER BY created_at, id",
+            filter
+        ))?;
+        let rows = stmt.query_map(params, |row| {
+            let kind: String = row.get(2)?;
+            let events: String = row.get(6)?;
+            Ok(PushSubscription {
+                id: row.get(0)?,
+                did: row.get(1)?,
+                kind: PushKind::parse(&kind).unwrap_or(PushKind::WebPush),
+                endpoint: row.get(3)?,
+                p256dh: row.get(4)?,
+                auth: row.get(5)?,
+                events: serde_json::from_str(&events).unwrap_or_default(),
+                created_at: row.get(7)?,
+                last_pushed_at: row.get(8)?,
+                last_error: row.get(9)?,
+            })
+        })?;
 
-        let mut proxies = Vec::new();
+        let mut subscriptions = Vec::new();
         for row in rows {
-            proxies.push(row?);
+            subscriptions.push(row?);
         }
-        Ok(proxies)
+        Ok(subscriptions)
     }
 
-    pub fn search_proxy_memories_by_name(&self, name_pattern: &str) -> Result<Vec<ProxyMemory>> {
-        let sql = format!(
-            "SELECT {} FROM {} WHERE proxy_for_name LIKE ?1 ORDER BY created_timestamp DESC",
-            ProxyMemory::select_fields(),
-            ProxyMemory::table_name()
-        );
+    pub fn update_push_subscription_events(&self, id: &str, events: &[PushEvent]) -> Result<bool> {
         let conn = self.get_connection()?;
-        let mut stmt = conn.prepare(&sql)?;
+        let updated = conn.execute(
+            "UPDATE push_subscription SET events = ?2 WHERE id = ?1",
+            (id, serde_json::to_string(events)?),
+        )?;
+        Ok(updated > 0)
+    }
 
-        // Escape SQL wildcards to prevent injection
-        let escaped_pattern = name_pattern
-            .replace('\\', "\\\\") // Escape backslashes first
-            .replace('%', "\\%") // Escape percent signs
-            .replace('_', "\\_"); // Escape underscores
-        let search_pattern = format!("%{}%", escaped_pattern);
+    /// Note the outcome of the latest push; `error` is None when it was accepted
+    pub fn record_push_outcome(&self, id: &str, error: Option<&str>) -> Result<()> {
+        let conn = self.get_connection()?;
+        conn.execute(
+            "UPDATE push_subscription
+             SET last_pushed_at = CASE WHEN ?2 IS NULL THEN ?3 ELSE last_pushed_at END,
+                 last_error = ?2
+             WHERE id = ?1",
+            (id, error, chrono::Utc::now().to_rfc3339()),
+        )?;
+        Ok(())
+    }
 
-        let rows = stmt.query_map([search_pattern], ProxyMemory::from_row)?;
+    pub fn delete_push_subscription(&self, id: &str) -> Result<bool> {
+        let conn = self.get_connection()?;
+        let deleted = conn.execute("DELETE FROM push_subscription WHERE id = ?1", [id])?;
+        Ok(deleted > 0)
+    }
 
-        let mut proxies = Vec::new();
-        for row in rows {
-            proxies.push(row?);
-        }
-        Ok(proxies)
+    // Job operations
+    pub fn create_job(&self, job: &Job) -> Result<()> {
+        let conn = self.get_connection()?;
+        conn.execute(
+            "INSERT INTO job (id, kind, state, params, progress, total, created_by, created_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+            (
+                &job.id,
+                &job.kind,
+                job.state.as_str(),
+                serde_json::to_string(&job.params)?,
+                job.progress as i64,
+                job.total.map(|total| total as i64),
+                &job.created_by,
+                &job.created_at,
+            ),
+        )?;
+        Ok(())
+    }
+
+    pub fn get_job(&self, id: &str) -> Result<Option<Job>> {
+        Ok(self
+            .query_jobs("WHERE id = ?1 LIMIT 1", [id])?
+            .into_iter()
+            .next())
+    }
+
+    /// The most recently created jobs, newest first
+    pub fn list_jobs(&self, limit: usize) -> Result<Vec<Job>> {
+        self.query_jobs("ORDER BY created_at DESC, id DESC LIMIT ?1", [limit as i64])
+    }
+
+    pub fn count_unfinished_jobs(&self) -> Result<usize> {
+        let conn = self.get_connection()?;
+        let count: i64 = conn.query_row(
+            "SELECT COUNT(*) FROM job WHERE state IN ('queued', 'running')",
+            [],
+            |row| row.get(0),
+        )?;
+        Ok(count as usize)
+    }
+
+    fn query_jobs<P: rusqlite::Params>(&self, filter: &str, params: P) -> Result<Vec<Job>> {
+        let conn = self.get_connection()?;
+        let mut stmt = conn.prepare(&format!(
+            "SELECT id, kind, state, params, progress, total, result, error, cancel_requested,
+                    created_by, created_at, started_at, finished_at
+             FROM job {}",
+            filter
+        ))?;
+        let rows = stmt.query_map(params, |row| {
+            let state: String = row.get(2)?;
+            let params: String = row.get(3)?;
+            let progress: i64 = row.get(4)?;
+            let total: Option<i64> = row.get(5)?;
+            let result: Option<String> = row.get(6)?;
+            Ok(Job {
+                id: row.get(0)?,
+                kind: row.get(1)?,
+                state: JobState::parse(&state).unwrap_or(JobState::Failed),
+                params: serde_json::from_str(&params).unwrap_or_default(),
+                progress: progress as u64,
+                total: total.map(|total| total as u64),
+                result: result.and_then(|result| serde_json::from_str(&result).ok()),
+                error: row.get(7)?,
+                cancel_requested: row.get(8)?,
+                created_by: row.get(9)?,
+                created_at: row.get(10)?,
+                started_at: row.get(11)?,
+                finished_at: row.get(12)?,
+            })
+        })?;
+
+        let mut jobs = Vec::new();
+        for row in rows {
+            jobs.push(row?);
+        }
+        Ok(jobs)
+    }
+
+    /// Mark the oldest queued job as running and return it
+    pub fn claim_next_job(&self) -> Result<Option<Job>> {
+        let id: Option<String> = {
+            let mut conn = self.get_connection()?;
+            let tx = conn.transaction()?;
+            let id: Option<String> = tx
+                .query_row(
+                    "SELECT id FROM job WHERE state = 'queued' ORDER BY created_at, id LIMIT 1",
+                    [],
+                    |row| row.get(0),
+                )
+                .optional()?;
+            if let Some(id) = &id {
+                tx.execute(
+                    "UPDATE job SET state = 'running', started_at = ?2 WHERE id = ?1",
+                    (id, chrono::Utc::now().to_rfc3339()),
+                )?;
+            }
+            tx.commit()?;
+            id
+        };
+        match id {
+            Some(id) => self.get_job(&id),
+            None => Ok(None),
+        }
+    }
+
+    /// Record a running job's progress, returning whether it has been asked to stop
+    pub fn update_job_progress(&self, id: &str, progress: u64, total: Option<u64>) -> Result<bool> {
+        let conn = self.get_connection()?;
+        conn.execute(
+            "UPDATE job SET progress = ?2, total = ?3 WHERE id = ?1",
+            (id, progress as i64, total.map(|total| total as i64)),
+        )?;
+        let cancel_requested: Option<bool> = conn
+            .query_row(
+                "SELECT cancel_requested FROM job WHERE id = ?1",
+                [id],
+                |row| row.get(0),
+            )
+            .optional()?;
+        Ok(cancel_requested.unwrap_or(true))
+    }
+
+    /// Record how a job ended; `result` is kept only for succeeded jobs
+    pub fn finish_job(
+        &self,
+        id: &str,
+        state: JobState,
+        result: Option<&serde_json::Value>,
+        error: Option<&str>,
+    ) -> Result<()> {
+        let result = result.map(serde_json::to_string).transpose()?;
+        let conn = self.get_connection()?;
+        conn.execute(
+            "UPDATE job SET state = ?2, result = ?3, error = ?4, finished_at = ?5 WHERE id = ?1",
+            (
+                id,
+                state.as_str(),
+                result,
+                error,
+                chrono::Utc::now().to_rfc3339(),
+            ),
+        )?;
+        Ok(())
+    }
+
+    /// Ask a job to stop. A queued job is cancelled at once; a running one stops at
+    /// its next progress update. Finished jobs are left alone
+    pub fn request_job_cancel(&self, id: &str) -> Result<Option<Job>> {
+        {
+            let conn = self.get_connection()?;
+            conn.execute(
+                "UPDATE job SET
+                     cancel_requested = 1,
+                     state = CASE WHEN state = 'queued' THEN 'cancelled' ELSE state END,
+                     finished_at = CASE WHEN state = 'queued' THEN ?2 ELSE finished_at END
+                 WHERE id = ?1 AND state IN ('queued', 'running')",
+                (id, chrono::Utc::now().to_rfc3339()),
+            )?;
+        }
+        self.get_job(id)
+    }
+
+    /// Jobs left running by a previous process start again from the beginning, unless
+    /// they had been asked to stop. Returns how many were requeued
+    pub fn requeue_interrupted_jobs(&self) -> Result<usize> {
+        let mut conn = self.get_connection()?;
+        let tx = conn.transaction()?;
+        tx.execute(
+            "UPDATE job SET state = 'cancelled', finished_at = ?1
+             WHERE state = 'running' AND cancel_requested = 1",
+            [chrono::Utc::now().to_rfc3339()],
+        )?;
+        let requeued = tx.execute(
+            "UPDATE job SET state = 'queued', progress = 0, started_at = NULL
+             WHERE state = 'running'",
+            [],
+        )?;
+        tx.commit()?;
+        Ok(requeued)
+    }
+
+    // Handle operations
+    /// Give a DID `record.handle`, replacing any handle it had and logging the change.
+    /// Fails if another DID holds the handle
+    pub fn set_handle(&self, record: &HandleRecord) -> Result<Option<String>> {
+        let mut conn = self.get_connection()?;
+        let tx = conn.transaction()?;
+        let holder: Option<String> = tx
+            .query_row(
+                "SELECT did FROM handle WHERE handle = ?1",
+                [&record.handle],
+                |row| row.get(0),
+            )
+            .optional()?;
+        if holder.as_deref().is_some_and(|did| did != record.did) {
+            return Err(OcmError::AlreadyExists(format!(
+                "Handle {} belongs to another DID",
+                record.handle
+            )));
+        }
+        let previous: Option<String> = tx
+            .query_row(
+                "SELECT handle FROM handle WHERE did = ?1",
+                [&record.did],
+                |row| row.get(0),
+            )
+            .optional()?;
+
+        tx.execute("DELETE FROM handle WHERE did = ?1", [&record.did])?;
+        tx.execute(
+            "INSERT INTO handle (handle, did, verification, verified_at, registered_at)
+             VALUES (?1, ?2, ?3, ?4, ?5)",
+            (
+                &record.handle,
+                &record.did,
+                record.verification.as_str(),
+                &record.verified_at,
+                &record.registered_at,
+            ),
+        )?;
+        if previous.as_deref() != Some(record.handle.as_str()) {
+            tx.execute(
+                "INSERT INTO handle_change (did, old_handle, new_handle, changed_at)
+                 VALUES (?1, ?2, ?3, ?4)",
+                (
+                    &record.did,
+                    &previous,
+                    &record.handle,
+                    chrono::Utc::now().to_rfc3339(),
+                ),
+            )?;
+        }
+        tx.commit()?;
+        Ok(previous)
+    }
+
+    /// Drop a DID's handle; false if it had none
+    pub fn release_handle(&self, did: &Did) -> Result<bool> {
+        let mut conn = self.get_connection()?;
+        let tx = conn.transaction()?;
+        let previous: Option<String> = tx
+            .query_row("SELECT handle FROM handle WHERE did = ?1", [did], |row| {
+                row.get(0)
+            })
+            .optional()?;
+        let Some(previous) = previous else {
+            return Ok(false);
+        };
+
+        tx.execute("DELETE FROM handle WHERE did = ?1", [did])?;
+        tx.execute(
+            "INSERT INTO handle_change (did, old_handle, new_handle, changed_at)
+             VALUES (?1, ?2, NULL, ?3)",
+            (did, &previous, chrono::Utc::now().to_rfc3339()),
+        )?;
+        tx.commit()?;
+        Ok(true)
+    }
+
+    pub fn get_handle(&self, handle: &str) -> Result<Option<HandleRecord>> {
+        Ok(self
+            .query_handles("handle = ?1", handle)?
+            .into_iter()
+            .next())
+    }
+
+    pub fn get_handle_for_did(&self, did: &Did) -> Result<Option<HandleRecord>> {
+        Ok(self.query_handles("did = ?1", did)?.into_iter().next())
+    }
+
+    fn query_handles(&self, filter: &str, value: &str) -> Result<Vec<HandleRecord>> {
+        let conn = self.get_connection()?;
+        let mut stmt = conn.prepare(&format!(
+            "SELECT handle, did, verification, verified_at, registered_at
+             FROM handle WHERE {}",
+            filter
+        ))?;
+        let rows = stmt.query_map([value], |row| {
+            let verification: String = row.get(2)?;
+            Ok(HandleRecord {
+                handle: row.get(0)?,
+                did: row.get(1)?,
+                verification: HandleVerification::parse(&verification)
+                    .unwrap_or(HandleVerification::Local),
+                verified_at: row.get(3)?,
+                registered_at: row.get(4)?,
+            })
+        })?;
+
+        let mut records = Vec::new();
+        for row in rows {
+            records.push(row?);
+        }
+        Ok(records)
+    }
+
+    /// Handle changes of a DID, oldest first
+    pub fn list_handle_changes(&self, did: &Did) -> Result<Vec<HandleChange>> {
+        self.query_handle_changes("did = ?1", did)
+    }
+
+    fn query_handle_changes(&self, filter: &str, value: &str) -> Result<Vec<HandleChange>> {
+        let conn = self.get_connection()?;
+        let mut stmt = conn.prepare(&format!(
+            "SELECT did, old_handle, new_handle, changed_at
+             FROM handle_change WHERE {} ORDER BY changed_at, id",
+            filter
+        ))?;
+        let rows = stmt.query_map([value], |row| {
+            Ok(HandleChange {
+                did: row.get(0)?,
+                old_handle: row.get(1)?,
+                new_handle: row.get(2)?,
+                changed_at: row.get(3)?,
+            })
+        })?;
+
+        let mut changes = Vec::new();
+        for row in rows {
+            changes.push(row?);
+        }
+        Ok(changes)
+    }
+
+    // Organization verification operations
+    /// Record how an organization was verified, replacing any earlier verification
+    pub fn set_organization_verification(
+        &self,
+        verification: &OrganizationVerification,
+    ) -> Result<()> {
+        let conn = self.get_connection()?;
+        conn.execute(
+            "INSERT OR REPLACE INTO organization_verification
+             (organization_did, method, domain, attested_by, attestation_memory_id, verified_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+            (
+                &verification.organization_did,
+                verification.method.as_str(),
+                &verification.domain,
+                &verification.attested_by,
+                &verification.attestation_memory_id,
+                &verification.verified_at,
+            ),
+        )?;
+        Ok(())
+    }
+
+    /// The stored verification of an organization, whether or not it still holds
+    pub fn get_organization_verification(
+        &self,
+        organization_did: &Did,
+    ) -> Result<Option<OrganizationVerification>> {
+        let conn = self.get_connection()?;
+        Ok(conn
+            .query_row(
+                "SELECT organization_did, method, domain, attested_by, attestation_memory_id,
+                        verified_at
+                 FROM organization_verification WHERE organization_did = ?1",
+                [organization_did],
+                |row| {
+                    let method: String = row.get(1)?;
+                    Ok(OrganizationVerification {
+                        organization_did: row.get(0)?,
+                        method: OrganizationVerificationMethod::parse(&method)
+                            .unwrap_or(OrganizationVerificationMethod::Attestation),
+                        domain: row.get(2)?,
+                        attested_by: row.get(3)?,
+                        attestation_memory_id: row.get(4)?,
+                        verified_at: row.get(5)?,
+                    })
+                },
+            )
+            .optional()?)
+    }
+
+    pub fn delete_organization_verification(&self, organization_did: &Did) -> Result<bool> {
+        let conn = self.get_connection()?;
+        let deleted = conn.execute(
+            "DELETE FROM organization_verification WHERE organization_did = ?1",
+            [organization_did],
+        )?;
+        Ok(deleted > 0)
+    }
+
+    // Invitation operations
+    /// Record an invitation unless one with the same ID is already known
+    pub fn record_invitation(&self, invitation: &Invitation) -> Result<()> {
+        let conn = self.get_connection()?;
+        conn.execute(
+            "INSERT OR IGNORE INTO invitation (id, issuer_did, group_name, expires_at, created_at)
+             VALUES (?1, ?2, ?3, ?4, ?5)",
+            (
+                &invitation.id,
+                &invitation.issuer_did,
+                &invitation.group_name,
+                &invitation.expires_at,
+                &invitation.created_at,
+            ),
+        )?;
+        Ok(())
+    }
+
+    /// Mark an invitation used by `did`; false if it was already used or revoked
+    pub fn redeem_invitation(&self, id: &str, did: &Did) -> Result<bool> {
+        let conn = self.get_connection()?;
+        let updated = conn.execute(
+            "UPDATE invitation SET redeemed_by_did = ?2, redeemed_at = ?3
+             WHERE id = ?1 AND redeemed_by_did IS NULL AND revoked = 0",
+            (id, did, chrono::Utc::now().to_rfc3339()),
+        )?;
+        Ok(updated > 0)
+    }
+
+    /// Revoke an unused invitation; false if there is none
+    pub fn revoke_invitation(&self, id: &str) -> Result<bool> {
+        let conn = self.get_connection()?;
+        let updated = conn.execute(
+            "UPDATE invitation SET revoked = 1 WHERE id = ?1 AND redeemed_by_did IS NULL",
+            [id],
+        )?;
+        Ok(updated > 0)
+    }
+
+    pub fn get_invitation(&self, id: &str) -> Result<Option<Invitation>> {
+        Ok(self
+            .query_invitations("WHERE id = ?1", [id])?
+            .into_iter()
+            .next())
+    }
+
+    /// Every invitation this node issued or redeemed, newest first
+    pub fn list_invitations(&self) -> Result<Vec<Invitation>> {
+        self.query_invitations("", [])
+    }
+
+    fn query_invitations<P: rusqlite::Params>(
+        &self,
+        filter: &str,
+        params: P,
+    ) -> Result<Vec<Invitation>> {
+        let conn = self.get_connection()?;
+        let mut stmt = conn.prepare(&format!(
+            "SELECT id, issuer_did, group_name, expires_at, created_at, redeemed_by_did,
+                    redeemed_at, revoked
+             FROM invitation {} ORDER BY created_at DESC",
+            filter
+        ))?;
+        let rows = stmt.query_map(params, |row| {
+            Ok(Invitation {
+                id: row.get(0)?,
+                issuer_did: row.get(1)?,
+                group_name: row.get(2)?,
+                expires_at: row.get(3)?,
+                created_at: row.get(4)?,
+                redeemed_by_did: row.get(5)?,
+                redeemed_at: row.get(6)?,
+                revoked: row.get(7)?,
+            })
+        })?;
+
+        let mut invitations = Vec::new();
+        for row in rows {
+            invitations.push(row?);
+        }
+        Ok(invitations)
+    }
+
+    pub fn add_federation_member(&self, member: &FederationMember) -> Result<()> {
+        let conn = self.get_connection()?;
+        conn.execute(
+            "INSERT OR IGNORE INTO federation_member (did, invited_by_did, invitation_id, joined_at)
+             VALUES (?1, ?2, ?3, ?4)",
+            (
+                &member.did,
+                &member.invited_by_did,
+                &member.invitation_id,
+                &member.joined_at,
+            ),
+        )?;
+        Ok(())
+    }
+
+    pub fn get_federation_member(&self, did: &Did) -> Result<Option<FederationMember>> {
+        let conn = self.get_connection()?;
+        let mut stmt = conn.prepare(
+            "SELECT did, invited_by_did, invitation_id, joined_at
+             FROM federation_member WHERE did = ?1",
+        )?;
+        let mut rows = stmt.query_map([did], |row| {
+            Ok(FederationMember {
+                did: row.get(0)?,
+                invited_by_did: row.get(1)?,
+                invitation_id: row.get(2)?,
+                joined_at: row.get(3)?,
+            })
+        })?;
+
+        match rows.next() {
+            Some(row) => Ok(Some(row?)),
+            None => Ok(None),
+        }
+    }
+
+    // Tenant operations, on the host database of a multi-tenant node
+    pub fn create_tenant(&self, tenant: &Tenant) -> Result<()> {
+        let conn = self.get_connection()?;
+        conn.execute(
+            "INSERT INTO tenant
+                 (id, name, status, max_memories, requests_per_minute, created_at, updated_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+            (
+                &tenant.id,
+                &tenant.name,
+                tenant.status.as_str(),
+                tenant.max_memories,
+                tenant.requests_per_minute,
+                &tenant.created_at,
+                &tenant.updated_at,
+            ),
+        )?;
+        Ok(())
+    }
+
+    /// Update name, status and quotas; false if there is no such tenant
+    pub fn update_tenant(&self, tenant: &Tenant) -> Result<bool> {
+        let conn = self.get_connection()?;
+        let updated = conn.execute(
+            "UPDATE tenant SET name = ?2, status = ?3, max_memories = ?4,
+                 requests_per_minute = ?5, updated_at = ?6
+             WHERE id = ?1",
+            (
+                &tenant.id,
+                &tenant.name,
+                tenant.status.as_str(),
+                tenant.max_memories,
+                tenant.requests_per_minute,
+                &tenant.updated_at,
+            ),
+        )?;
+        Ok(updated > 0)
+    }
+
+    pub fn delete_tenant(&self, id: &str) -> Result<bool> {
+        let conn = self.get_connection()?;
+        let deleted = conn.execute("DELETE FROM tenant WHERE id = ?1", [id])?;
+        Ok(deleted > 0)
+    }
+
+    pub fn get_tenant(&self, id: &str) -> Result<Option<Tenant>> {
+        Ok(self
+            .query_tenants("WHERE id = ?1", [id])?
+            .into_iter()
+            .next())
+    }
+
+    pub fn list_tenants(&self) -> Result<Vec<Tenant>> {
+        self.query_tenants("", [])
+    }
+
+    fn query_tenants<P: rusqlite::Params>(&self, filter: &str, params: P) -> Result<Vec<Tenant>> {
+        let conn = self.get_connection()?;
+        let mut stmt = conn.prepare(&format!(
+            "SELECT id, name, status, max_memories, requests_per_minute, created_at, updated_at
+             FROM tenant {} ORDER BY id",
+            filter
+        ))?;
+        let rows = stmt.query_map(params, |row| {
+            let status: String = row.get(2)?;
+            Ok(Tenant {
+                id: row.get(0)?,
+                name: row.get(1)?,
+                // Unknown statuses fail closed
+                status: TenantStatus::parse(&status).unwrap_or(TenantStatus::Suspended),
+                max_memories: row.get(3)?,
+                requests_per_minute: row.get(4)?,
+                created_at: row.get(5)?,
+                updated_at: row.get(6)?,
+            })
+        })?;
+
+        let mut tenants = Vec::new();
+        for row in rows {
+            tenants.push(row?);
+        }
+        Ok(tenants)
+    }
+
+    pub fn count_signed_memories(&self) -> Result<u64> {
+        let conn = self.get_connection()?;
+        let count = conn.query_row("SELECT COUNT(*) FROM signed_memory", [], |row| row.get(0))?;
+        Ok(count)
+    }
+
+    // Content store operations
+    /// Body stored under a content hash, if any memory still references it
+    pub fn get_memory_content(&self, content_hash: &str) -> Result<Option<String>> {
+        let conn = self.get_connection()?;
+        let mut stmt =
+            conn.prepare("SELECT ocm_content(memory_data, dictionary_id) FROM memory_content WHERE content_hash = ?1")?;
+        let mut rows = stmt.query_map([content_hash], |row| row.get(0))?;
+
+        match rows.next() {
+            Some(row) => Ok(Some(row?)),
+            None => Ok(None),
+        }
+    }
+
+    /// (unique bodies stored, memories referencing them)
+    pub fn content_store_stats(&self) -> Result<(u64, u64)> {
+        let conn = self.get_connection()?;
+        let stats = conn.query_row(
+            "SELECT COUNT(*), COALESCE(SUM(ref_count), 0) FROM memory_content",
+            [],
+            |row| Ok((row.get::<_, i64>(0)? as u64, row.get::<_, i64>(1)? as u64)),
+        )?;
+        Ok(stats)
+    }
+
+    /// Compress new bodies of memory types that have a dictionary from now on
+    pub fn enable_compression(&self, config: CompressionConfig) {
+        self.compression.enable(config);
+    }
+
+    /// Train a dictionary for each memory type with enough stored bodies and none yet,
+    /// then compress the stored bodies of every type that has one. Does nothing unless
+    /// compression is enabled; bodies that would not shrink stay as they are
+    pub fn compress_memory_content(&self) -> Result<CompressionReport> {
+        let mut report = CompressionReport::default();
+        let Some(config) = self.compression.config() else {
+            return Ok(report);
+        };
+
+        let memory_types: Vec<String> = {
+            let conn = self.get_connection()?;
+            let mut stmt = conn.prepare(
+                "SELECT DISTINCT signed_memory.memory_type FROM memory_content
+                 JOIN signed_memory ON signed_memory.body_hash = memory_content.content_hash
+                 WHERE memory_content.dictionary_id IS NULL",
+            )?;
+            let rows = stmt.query_map([], |row| row.get(0))?;
+            let mut memory_types = Vec::new();
+            for row in rows {
+                memory_types.push(row?);
+            }
+            memory_types
+        };
+
+        for memory_type in memory_types {
+            if !self.compression.has_dictionary(&memory_type) {
+                let samples: Vec<String> = self
+                    .uncompressed_bodies(&memory_type, Some(config.max_samples))?
+                    .into_iter()
+                    .map(|(_, data)| data)
+                    .collect();
+                if samples.len() < config.min_samples {
+                    continue;
+                }
+                let dictionary = train_dictionary(&samples, config.dictionary_bytes)?;
+                let conn = self.get_connection()?;
+                conn.execute(
+                    "INSERT INTO compression_dictionary (memory_type, dictionary, sample_count, created_at)
+                     VALUES (?1, ?2, ?3, ?4)",
+                    (
+                        &memory_type,
+                        &dictionary,
+                        samples.len() as i64,
+                        chrono::Utc::now().to_rfc3339(),
+                    ),
+                )?;
+                self.compression
+                    .add(conn.last_insert_rowid(), &memory_type, dictionary);
+                report.dictionaries_trained += 1;
+            }
+
+            let bodies = self.uncompressed_bodies(&memory_type, None)?;
+            let mut conn = self.get_connection()?;
+            let tx = conn.transaction()?;
+            for (content_hash, data) in bodies {
+                if let Some((id, compressed)) = self.compression.compress(&memory_type, &data)? {
+                    tx.execute(
+                        "UPDATE memory_content SET memory_data = ?2, dictionary_id = ?3 WHERE content_hash = ?1",
+                        (&content_hash, &compressed, id),
+                    )?;
+                    report.bodies_compressed += 1;
+                    report.bytes_before += data.len();
+                    report.bytes_after += compressed.len();
+                }
+            }
+            tx.commit()?;
+        }
+        Ok(report)
+    }
+
+    /// (content hash, body) of a memory type's uncompressed bodies
+    fn uncompressed_bodies(
+        &self,
+        memory_type: &str,
+        limit: Option<usize>,
+    ) -> Result<Vec<(String, String)>> {
+        let conn = self.get_connection()?;
+        let mut stmt = conn.prepare(
+            "SELECT content_hash, memory_data FROM memory_content
+             WHERE dictionary_id IS NULL
+             AND content_hash IN (SELECT body_hash FROM signed_memory WHERE memory_type = ?1)
+             LIMIT ?2",
+        )?;
+        let limit = limit.map(|limit| limit as i64).unwrap_or(-1);
+        let rows = stmt.query_map((memory_type, limit), |row| Ok((row.get(0)?, row.get(1)?)))?;
+
+        let mut bodies = Vec::new();
+        for row in rows {
+            bodies.push(row?);
+        }
+        Ok(bodies)
+    }
+
+    // DID key pin operations
+    /// DIDs of memory authors and pinned keys whose documents were last checked before
+    /// `checked_before` (RFC 3339), never-checked ones first. DIDs with memories awaiting
+    /// an offline verification recheck are always due
+    pub fn list_dids_due_for_refresh(
+        &self,
+        checked_before: &str,
+        limit: usize,
+    ) -> Result<Vec<Did>> {
+        let conn = self.get_connection()?;
+        let mut stmt = conn.prepare(
+            "SELECT tracked.did FROM (
+                 SELECT DISTINCT did FROM signed_memory WHERE did LIKE 'did:plc:%'
+                 UNION SELECT did FROM did_key_pin
+             ) AS tracked
+             LEFT JOIN did_refresh ON did_refresh.did = tracked.did
+             WHERE did_refresh.checked_at IS NULL OR did_refresh.checked_at < ?1
+                OR tracked.did IN (SELECT did FROM offline_verification WHERE rechecked_at IS NULL)
+             ORDER BY did_refresh.checked_at IS NOT NULL, did_refresh.checked_at
+             LIMIT ?2",
+        )?;
+        let rows = stmt.query_map((checked_before, limit as i64), |row| row.get(0))?;
+
+        let mut dids = Vec::new();
+        for row in rows {
+            dids.push(row?);
+        }
+        Ok(dids)
+    }
+
+    /// Every key seen for a DID, current ones first
+    pub fn list_did_key_pins(&self, did: &Did) -> Result<Vec<DidKeyPin>> {
+        let conn = self.get_connection()?;
+        let mut stmt = conn.prepare(
+            "SELECT did, public_key, first_seen_at, last_seen_at, removed_at FROM did_key_pin
+             WHERE did = ?1 ORDER BY removed_at IS NOT NULL, first_seen_at",
+        )?;
+        let rows = stmt.query_map([did], |row| {
+            Ok(DidKeyPin {
+                did: row.get(0)?,
+                public_key: row.get(1)?,
+                first_seen_at: row.get(2)?,
+                last_seen_at: row.get(3)?,
+                removed_at: row.get(4)?,
+            })
+        })?;
+
+        let mut pins = Vec::new();
+        for row in rows {
+            pins.push(row?);
+        }
+        Ok(pins)
+    }
+
+    /// Record the keys a DID's document publishes now, in one transaction, and mark the
+    /// DID checked. Keys no longer published are kept with `removed_at` set
+    pub fn record_did_keys(&self, did: &Did, keys: &[String]) -> Result<DidKeyChange> {
+        let now = chrono::Utc::now().to_rfc3339();
+        let mut conn = self.get_connection()?;
+        let tx = conn.transaction()?;
+
+        let (current, pinned_before): (Vec<String>, bool) = {
+            let mut stmt = tx
+                .prepare("SELECT public_key, removed_at IS NULL FROM did_key_pin WHERE did = ?1")?;
+            let rows = stmt.query_map([did], |row| {
+                Ok((row.get::<_, String>(0)?, row.get::<_, bool>(1)?))
+            })?;
+            let mut current = Vec::new();
+            let mut pinned_before = false;
+            for row in rows {
+                let (key, is_current) = row?;
+                pinned_before = true;
+                if is_current {
+                    current.push(key);
+                }
+            }
+            (current, pinned_before)
+        };
+
+        let change = DidKeyChange {
+            first_pinned: !pinned_before,
+            added: keys
+                .iter()
+                .filter(|key| !current.contains(key))
+                .cloned()
+                .collect(),
+            removed: current
+                .iter()
+                .filter(|key| !keys.contains(key))
+                .cloned()
+                .collect(),
+        };
+        for key in keys {
+            tx.execute(
+                "INSERT INTO did_key_pin (did, public_key, first_seen_at, last_seen_at)
+                 VALUES (?1, ?2, ?3, ?3)
+                 ON CONFLICT(did, public_key) DO UPDATE SET last_seen_at = ?3, removed_at = NULL",
+                (did, key, &now),
+            )?;
+        }
+        for key in &change.removed {
+            tx.execute(
+                "UPDATE did_key_pin SET removed_at = ?3 WHERE did = ?1 AND public_key = ?2",
+                (did, key, &now),
+            )?;
+        }
+        tx.execute(
+            "INSERT INTO did_refresh (did, checked_at, error) VALUES (?1, ?2, NULL)
+             ON CONFLICT(did) DO UPDATE SET checked_at = ?2, error = NULL",
+            (did, &now),
+        )?;
+        tx.commit()?;
+        Ok(change)
+    }
+
+    /// Mark a DID checked without an answer from the directory
+    pub fn record_did_refresh_error(&self, did: &Did, error: &str) -> Result<()> {
+        let conn = self.get_connection()?;
+        conn.execute(
+            "INSERT INTO did_refresh (did, checked_at, error) VALUES (?1, ?2, ?3)
+             ON CONFLICT(did) DO UPDATE SET checked_at = ?2, error = ?3",
+            (did, chrono::Utc::now().to_rfc3339(), error),
+        )?;
+        Ok(())
+    }
+
+    // Offline verification operations
+    /// Note that a memory was accepted on an operation chain ending on `public_key`
+    pub fn record_offline_verification(
+        &self,
+        memory_id: &MemoryId,
+        did: &Did,
+        public_key: &str,
+    ) -> Result<()> {
+        let conn = self.get_connection()?;
+        conn.execute(
+            "INSERT OR REPLACE INTO offline_verification (memory_id, did, public_key, verified_at)
+             VALUES (?1, ?2, ?3, ?4)",
+            (memory_id, did, public_key, chrono::Utc::now().to_rfc3339()),
+        )?;
+        Ok(())
+    }
+
+    /// Offline verifications of a DID's memories not yet checked against the directory
+    pub fn list_pending_offline_verifications(
+        &self,
+        did: &Did,
+    ) -> Result<Vec<OfflineVerification>> {
+        let conn = self.get_connection()?;
+        let mut stmt = conn.prepare(
+            "SELECT memory_id, did, public_key, verified_at, rechecked_at, confirmed
+             FROM offline_verification WHERE did = ?1 AND rechecked_at IS NULL
+             ORDER BY verified_at",
+        )?;
+        let rows = stmt.query_map([did], |row| {
+            Ok(OfflineVerification {
+                memory_id: row.get(0)?,
+                did: row.get(1)?,
+                public_key: row.get(2)?,
+                verified_at: row.get(3)?,
+                rechecked_at: row.get(4)?,
+                confirmed: row.get(5)?,
+            })
+        })?;
+
+        let mut verifications = Vec::new();
+        for row in rows {
+            verifications.push(row?);
+        }
+        Ok(verifications)
+    }
+
+    /// Record whether the directory still publishes the key a memory was accepted on
+    pub fn record_offline_recheck(&self, memory_id: &MemoryId, confirmed: bool) -> Result<()> {
+        let conn = self.get_connection()?;
+        conn.execute(
+            "UPDATE offline_verification SET rechecked_at = ?2, confirmed = ?3 WHERE memory_id = ?1",
+            (memory_id, chrono::Utc::now().to_rfc3339(), confirmed),
+        )?;
+        Ok(())
+    }
+
+    // Memory header operations
+    /// Record a memory whose body was left with `source_peer`; ignored if the body is already held
+    pub fn store_memory_header(&self, header: &MemoryHeader, source_peer: &str) -> Result<()> {
+        let conn = self.get_connection()?;
+        conn.execute(
+            "INSERT INTO memory_header (id, did, memory_type, content_hash, signature, timestamp, updated_on, source_peer, received_at)
+             SELECT ?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9
+             WHERE NOT EXISTS (SELECT 1 FROM signed_memory WHERE id = ?1)
+             ON CONFLICT(id) DO UPDATE SET source_peer = excluded.source_peer, updated_on = excluded.updated_on",
+            rusqlite::params![
+                header.id,
+                header.did,
+                header.memory_type,
+                header.content_hash,
+                header.signature,
+                header.timestamp,
+                header.updated_on,
+                source_peer,
+                chrono::Utc::now().to_rfc3339()
+            ],
+        )?;
+        Ok(())
+    }
+
+    /// A held header and the peer that can serve its body
+    pub fn get_memory_header(&self, id: &MemoryId) -> Result<Option<(MemoryHeader, String)>> {
+        let conn = self.get_connection()?;
+        let mut stmt = conn.prepare(
+            "SELECT id, did, memory_type, content_hash, signature, timestamp, updated_on, source_peer
+             FROM memory_header WHERE id = ?1",
+        )?;
+        let mut rows =
+            stmt.query_map([id], |row| Ok((memory_header_from_row(row)?, row.get(7)?)))?;
+
+        match rows.next() {
+            Some(row) => Ok(Some(row?)),
+            None => Ok(None),
+        }
+    }
+
+    /// Drop the bodies of all but the `keep` most recent memories, keeping their headers
+    /// so the bodies can be fetched again on demand. Memories signed by `protected_did`
+    /// always keep their bodies. Returns how many bodies were dropped
+    pub fn demote_memories_to_headers(&self, keep: usize, protected_did: &Did) -> Result<usize> {
+        const DEMOTED: &str = "SELECT id FROM signed_memory WHERE did != ?2
+             ORDER BY timestamp DESC LIMIT -1 OFFSET ?1";
+        let mut conn = self.get_connection()?;
+        let tx = conn.transaction()?;
+        // No peer is known to hold the body, so any peer serving bodies is asked
+        tx.execute(
+            &format!(
+                "INSERT INTO memory_header (id, did, memory_type, content_hash, signature, timestamp, updated_on, source_peer, received_at)
+                 SELECT id, did, memory_type, content_hash, signature, timestamp, updated_on, '', ?3
+                 FROM signed_memory WHERE id IN ({})
+                 ON CONFLICT(id) DO NOTHING",
+                DEMOTED
+            ),
+            (keep as i64, protected_did, chrono::Utc::now().to_rfc3339()),
+        )?;
+        let demoted = tx.execute(
+            &format!("DELETE FROM signed_memory WHERE id IN ({})", DEMOTED),
+            (keep as i64, protected_did),
+        )?;
+        tx.commit()?;
+        Ok(demoted)
+    }
+
+    pub fn list_memory_headers(&self) -> Result<Vec<MemoryHeader>> {
+        let conn = self.get_connection()?;
+        let mut stmt = conn.prepare(
+            "SELECT id, did, memory_type, content_hash, signature, timestamp, updated_on
+             FROM memory_header ORDER BY timestamp DESC",
+        )?;
+        let rows = stmt.query_map([], memory_header_from_row)?;
+
+        let mut headers = Vec::new();
+        for row in rows {
+            headers.push(row?);
+        }
+        Ok(headers)
+    }
+
+    // Outbox operations
+    pub fn list_pending_outbox(&self, limit: usize) -> Result<Vec<OutboxEntry>> {
+        let conn = self.get_connection()?;
+        let mut stmt = conn.prepare(
+            "SELECT outbox_id, memory_id, created_at, attempts, last_error
+             FROM memory_outbox WHERE delivered_at IS NULL
+             ORDER BY outbox_id ASC LIMIT ?1",
+        )?;
+        let rows = stmt.query_map([limit as i64], |row| {
+            Ok(OutboxEntry {
+                outbox_id: row.get(0)?,
+                memory_id: row.get(1)?,
+                created_at: row.get(2)?,
+                attempts: row.get(3)?,
+                last_error: row.get(4)?,
+            })
+        })?;
+
+        let mut entries = Vec::new();
+        for row in rows {
+            entries.push(row?);
+        }
+        Ok(entries)
+    }
+
+    pub fn mark_outbox_delivered(&self, outbox_id: i64) -> Result<()> {
+        let conn = self.get_connection()?;
+        let now = chrono::Utc::now().to_rfc3339();
+        conn.execute(
+            "UPDATE memory_outbox SET delivered_at = ?2, last_attempt_at = ?2, attempts = attempts + 1
+             WHERE outbox_id = ?1",
+            (outbox_id, &now),
+        )?;
+        Ok(())
+    }
+
+    pub fn record_outbox_failure(&self, outbox_id: i64, error: &str) -> Result<()> {
+        let conn = self.get_connection()?;
+        conn.execute(
+            "UPDATE memory_outbox SET last_attempt_at = ?2, last_error = ?3, attempts = attempts + 1
+             WHERE outbox_id = ?1",
+            (outbox_id, chrono::Utc::now().to_rfc3339(), error),
+        )?;
+        Ok(())
+    }
+
+    // Location CRUD operations
+    pub fn create_location(&self, location: &Location) -> Result<()> {
+        let conn = self.get_connection()?;
+        conn.execute(
+            "INSERT INTO location (id, email, phone, address, city, state, zip, country, coordinates_lat, coordinates_lon, updated_on)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11)",
+            (
+                &location.id,
+                &location.email,
+                &location.phone,
+                &location.address,
+                &location.city,
+                &location.state,
+                &location.zip,
+                &location.country,
+                &location.coordinates_lat,
+                &location.coordinates_lon,
+                &location.updated_on,
+            ),
+        )?;
+        Ok(())
+    }
+
+    pub fn get_location(&self, id: &str) -> Result<Option<Location>> {
+        let conn = self.get_connection()?;
+        let mut stmt = conn.prepare(
+            "SELECT id, email, phone, address, city, state, zip, country, coordinates_lat, coordinates_lon, updated_on 
+             FROM location WHERE id = ?1"
+        )?;
+
+        let mut rows = stmt.query_map([id], |row| {
+            Ok(Location {
+                id: row.get(0)?,
+                email: row.get(1)?,
+                phone: row.get(2)?,
+                address: row.get(3)?,
+                city: row.get(4)?,
+                state: row.get(5)?,
+                zip: row.get(6)?,
+                country: row.get(7)?,
+                coordinates_lat: row.get(8)?,
+                coordinates_lon: row.get(9)?,
+                updated_on: row.get(10)?,
+            })
+        })?;
+
+        match rows.next() {
+            Some(row) => Ok(Some(row?)),
+            None => Ok(None),
+        }
+    }
+
+    pub fn update_location(&self, location: &Location) -> Result<()> {
+        let conn = self.get_connection()?;
+        conn.execute(
+            "UPDATE location SET email = ?2, phone = ?3, address = ?4, city = ?5, state = ?6, 
+             zip = ?7, country = ?8, coordinates_lat = ?9, coordinates_lon = ?10, updated_on = ?11 
+             WHERE id = ?1",
+            (
+                &location.id,
+                &location.email,
+                &location.phone,
+                &location.address,
+                &location.city,
+                &location.state,
+                &location.zip,
+                &location.country,
+                &location.coordinates_lat,
+                &location.coordinates_lon,
+                &location.updated_on,
+            ),
+        )?;
+        Ok(())
+    }
+
+    pub fn delete_location(&self, id: &str) -> Result<()> {
+        let conn = self.get_connection()?;
+        conn.execute("DELETE FROM location WHERE id = ?1", [id])?;
+        Ok(())
+    }
+
+    pub fn list_locations(&self) -> Result<Vec<Location>> {
+        let conn = self.get_connection()?;
+        let mut stmt = conn.prepare(
+            "SELECT id, email, phone, address, city, state, zip, country, coordinates_lat, coordinates_lon, updated_on 
+             FROM location"
+        )?;
+
+        let rows = stmt.query_map([], |row| {
+            Ok(Location {
+                id: row.get(0)?,
+                email: row.get(1)?,
+                phone: row.get(2)?,
+                address: row.get(3)?,
+                city: row.get(4)?,
+                state: row.get(5)?,
+                zip: row.get(6)?,
+                country: row.get(7)?,
+                coordinates_lat: row.get(8)?,
+                coordinates_lon: row.get(9)?,
+                updated_on: row.get(10)?,
+            })
+        })?;
+
+        let mut locations = Vec::new();
+        for row in rows {
+            locations.push(row?);
+        }
+        Ok(locations)
+    }
+
+    // Experience CRUD operations
+    pub fn create_experience(&self, experience: &Experience) -> Result<()> {
+        let conn = self.get_connection()?;
+        conn.execute(
+            "INSERT INTO experience (id, name, updated_on) VALUES (?1, ?2, ?3)",
+            (&experience.id, &experience.name, &experience.updated_on),
+        )?;
+        Ok(())
+    }
+
+    pub fn get_experience(&self, id: &str) -> Result<Option<Experience>> {
+        let conn = self.get_connection()?;
+        let mut stmt = conn.prepare("SELECT id, name, updated_on FROM experience WHERE id = ?1")?;
+        let mut rows = stmt.query_map([id], |row| {
+            Ok(Experience {
+                id: row.get(0)?,
+                name: row.get(1)?,
+                updated_on: row.get(2)?,
+            })
+        })?;
+        match rows.next() {
+            Some(row) => Ok(Some(row?)),
+            None => Ok(None),
+        }
+    }
+
+    pub fn update_experience(&self, experience: &Experience) -> Result<()> {
+        let conn = self.get_connection()?;
+        conn.execute(
+            "UPDATE experience SET name = ?2, updated_on = ?3 WHERE id = ?1",
+            (&experience.id, &experience.name, &experience.updated_on),
+        )?;
+        Ok(())
+    }
+
+    pub fn delete_experience(&self, id: &str) -> Result<()> {
+        let conn = self.get_connection()?;
+        conn.execute("DELETE FROM experience WHERE id = ?1", [id])?;
+        Ok(())
+    }
+
+    pub fn list_experiences(&self) -> Result<Vec<Experience>> {
+        let conn = self.get_connection()?;
+        let mut stmt = conn.prepare("SELECT id, name, updated_on FROM experience")?;
+        let rows = stmt.query_map([], |row| {
+            Ok(Experience {
+                id: row.get(0)?,
+                name: row.get(1)?,
+                updated_on: row.get(2)?,
+            })
+        })?;
+        let mut experiences = Vec::new();
+        for row in rows {
+            experiences.push(row?);
+        }
+        Ok(experiences)
+    }
+
+    // Cohort CRUD operations
+    pub fn create_cohort(&self, cohort: &Cohort) -> Result<()> {
+        let conn = self.get_connection()?;
+        conn.execute(
+            "INSERT INTO cohort (id, name, capacity, updated_on) VALUES (?1, ?2, ?3, ?4)",
+            (
+                &cohort.id,
+                &cohort.name,
+                &cohort.capacity,
+                &cohort.updated_on,
+            ),
+        )?;
+        Ok(())
+    }
+
+    pub fn get_cohort(&self, id: &str) -> Result<Option<Cohort>> {
+        let conn = self.get_connection()?;
+        let mut stmt =
+            conn.prepare("SELECT id, name, capacity, updated_on FROM cohort WHERE id = ?1")?;
+        let mut rows = stmt.query_map([id], |row| {
+            Ok(Cohort {
+                id: row.get(0)?,
+                name: row.get(1)?,
+                capacity: row.get(2)?,
+                updated_on: row.get(3)?,
+            })
+        })?;
+        match rows.next() {
+            Some(row) => Ok(Some(row?)),
+            None => Ok(None),
+        }
+    }
+
+    pub fn update_cohort(&self, cohort: &Cohort) -> Result<()> {
+        let conn = self.get_connection()?;
+        conn.execute(
+            "UPDATE cohort SET name = ?2, capacity = ?3, updated_on = ?4 WHERE id = ?1",
+            (
+                &cohort.id,
+                &cohort.name,
+                &cohort.capacity,
+                &cohort.updated_on,
+            ),
+        )?;
+        Ok(())
+    }
+
+    pub fn delete_cohort(&self, id: &str) -> Result<()> {
+        let conn = self.get_connection()?;
+        conn.execute("DELETE FROM cohort WHERE id = ?1", [id])?;
+        Ok(())
+    }
+
+    pub fn list_cohorts(&self) -> Result<Vec<Cohort>> {
+        let conn = self.get_connection()?;
+        let mut stmt = conn.prepare("SELECT id, name, capacity, updated_on FROM cohort")?;
+        let rows = stmt.query_map([], |row| {
+            Ok(Cohort {
+                id: row.get(0)?,
+                name: row.get(1)?,
+                capacity: row.get(2)?,
+                updated_on: row.get(3)?,
+            })
+        })?;
+        let mut cohorts = Vec::new();
+        for row in rows {
+            cohorts.push(row?);
+        }
+        Ok(cohorts)
+    }
+
+    // Schedule CRUD operations
+    pub fn create_schedule(&self, schedule: &Schedule) -> Result<()> {
+        let conn = self.get_connection()?;
+        conn.execute(
+            "INSERT INTO schedule (id, \"from\", \"to\", days_of_week_min, days_of_week_max) VALUES (?1, ?2, ?3, ?4, ?5)",
+            (&schedule.id, &schedule.from, &schedule.to, &schedule.days_of_week_min, &schedule.days_of_week_max),
+        )?;
+        Ok(())
+    }
+
+    pub fn get_schedule(&self, id: &str) -> Result<Option<Schedule>> {
+        let conn = self.get_connection()?;
+        let mut stmt = conn.prepare("SELECT id, \"from\", \"to\", days_of_week_min, days_of_week_max FROM schedule WHERE id = ?1")?;
+        let mut rows = stmt.query_map([id], |row| {
+            Ok(Schedule {
+                id: row.get(0)?,
+                from: row.get(1)?,
+                to: row.get(2)?,
+                days_of_week_min: row.get(3)?,
+                days_of_week_max: row.get(4)?,
+            })
+        })?;
+        match rows.next() {
+            Some(row) => Ok(Some(row?)),
+            None => Ok(None),
+        }
+    }
+
+    pub fn update_schedule(&self, schedule: &Schedule) -> Result<()> {
+        let conn = self.get_connection()?;
+        conn.execute(
+            "UPDATE schedule SET \"from\" = ?2, \"to\" = ?3, days_of_week_min = ?4, days_of_week_max = ?5 WHERE id = ?1",
+            (&schedule.id, &schedule.from, &schedule.to, &schedule.days_of_week_min, &schedule.days_of_week_max),
+        )?;
+        Ok(())
+    }
+
+    pub fn delete_schedule(&self, id: &str) -> Result<()> {
+        let conn = self.get_connection()?;
+        conn.execute("DELETE FROM schedule WHERE id = ?1", [id])?;
+        Ok(())
+    }
+
+    pub fn list_schedules(&self) -> Result<Vec<Schedule>> {
+        let conn = self.get_connection()?;
+        let mut stmt = conn.prepare(
+            "SELECT id, \"from\", \"to\", days_of_week_min, days_of_week_max FROM schedule",
+        )?;
+        let rows = stmt.query_map([], |row| {
+            Ok(Schedule {
+                id: row.get(0)?,
+                from: row.get(1)?,
+                to: row.get(2)?,
+                days_of_week_min: row.get(3)?,
+                days_of_week_max: row.get(4)?,
+            })
+        })?;
+        let mut schedules = Vec::new();
+        for row in rows {
+            schedules.push(row?);
+        }
+        Ok(schedules)
+    }
+
+    // Affiliation CRUD operations
+    pub fn create_affiliation(&self, affiliation: &Affiliation) -> Result<()> {
+        let conn = self.get_connection()?;
+        conn.execute(
+            "INSERT INTO affiliation (id, name, affiliation_type, value, range_min, range_max, cohort, updated_on)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+            (
+                &affiliation.id,
+                &affiliation.name,
+                &affiliation.affiliation_type.to_string(),
+                &affiliation.value,
+                &affiliation.range_min,
+                &affiliation.range_max,
+                &affiliation.cohort,
+                &affiliation.updated_on,
+            ),
+        )?;
+        Ok(())
+    }
+
+    pub fn get_affiliation(&self, id: &str) -> Result<Option<Affiliation>> {
+        let conn = self.get_connection()?;
+        let mut stmt = conn.prepare(
+            "SELECT id, name, affiliation_type, value, range_min, range_max, cohort, updated_on 
+             FROM affiliation WHERE id = ?1",
+        )?;
+
+        let mut rows = stmt.query_map([id], |row| {
+            let affiliation_type_str: String = row.get(2)?;
+            let affiliation_type =
+                AffiliationType::from_string(&affiliation_type_str).map_err(|e| {
+                    rusqlite::Error::InvalidColumnType(2, e.into(), rusqlite::types::Type::Text)
+                })?;
+
+            Ok(Affiliation {
+                id: row.get(0)?,
+                name: row.get(1)?,
+                affiliation_type,
+                value: row.get(3)?,
+                range_min: row.get(4)?,
+                range_max: row.get(5)?,
+                cohort: row.get(6)?,
+                updated_on: row.get(7)?,
+            })
+        })?;
+
+        match rows.next() {
+            Some(row) => Ok(Some(row?)),
+            None => Ok(None),
+        }
+    }
+
+    pub fn update_affiliation(&self, affiliation: &Affiliation) -> Result<()> {
+        let conn = self.get_connection()?;
+        conn.execute(
+            "UPDATE affiliation SET name = ?2, affiliation_type = ?3, value = ?4, range_min = ?5, 
+             range_max = ?6, cohort = ?7, updated_on = ?8 WHERE id = ?1",
+            (
+                &affiliation.id,
+                &affiliation.name,
+                &affiliation.affiliation_type.to_string(),
+                &affiliation.value,
+                &affiliation.range_min,
+                &affiliation.range_max,
+                &affiliation.cohort,
+                &affiliation.updated_on,
+            ),
+        )?;
+        Ok(())
+    }
+
+    pub fn delete_affiliation(&self, id: &str) -> Result<()> {
+        let conn = self.get_connection()?;
+        conn.execute("DELETE FROM affiliation WHERE id = ?1", [id])?;
+        Ok(())
+    }
+
+    pub fn list_affiliations(&self) -> Result<Vec<Affiliation>> {
+        let conn = self.get_connection()?;
+        let mut stmt = conn.prepare(
+            "SELECT id, name, affiliation_type, value, range_min, range_max, cohort, updated_on 
+             FROM affiliation",
+        )?;
+
+        let rows = stmt.query_map([], |row| {
+            let affiliation_type_str: String = row.get(2)?;
+            let affiliation_type =
+                AffiliationType::from_string(&affiliation_type_str).map_err(|e| {
+                    rusqlite::Error::InvalidColumnType(2, e.into(), rusqlite::types::Type::Text)
+                })?;
+
+            Ok(Affiliation {
+                id: row.get(0)?,
+                name: row.get(1)?,
+                affiliation_type,
+                value: row.get(3)?,
+                range_min: row.get(4)?,
+                range_max: row.get(5)?,
+                cohort: row.get(6)?,
+                updated_on: row.get(7)?,
+            })
+        })?;
+
+        let mut affiliations = Vec::new();
+        for row in rows {
+            affiliations.push(row?);
+        }
+        Ok(affiliations)
+    }
+
+    // Condition CRUD operations
+    pub fn create_condition(&self, condition: &Condition) -> Result<()> {
+        let conn = self.get_connection()?;
+        conn.execute(
+            "INSERT INTO condition (id, name, condition_type, age_min, age_max, calculated_age_from, calculated_age_to, coordinates_lat, coordinates_lon, distance, updated_on)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11)",
+            (
+                &condition.id,
+                &condition.name,
+                &condition.condition_type.to_string(),
+                &condition.age_min,
+                &condition.age_max,
+                &condition.calculated_age_from,
+                &condition.calculated_age_to,
+                &condition.coordinates_lat,
+                &condition.coordinates_lon,
+                &condition.distance,
+                &condition.updated_on,
+            ),
+        )?;
+        Ok(())
+    }
+
+    pub fn get_condition(&self, id: &str) -> Result<Option<Condition>> {
+        let conn = self.get_connection()?;
+        let mut stmt = conn.prepare(
+            "SELECT id, name, condition_type, age_min, age_max, calculated_age_from, calculated_age_to, coordinates_lat, coordinates_lon, distance, updated_on 
+             FROM condition WHERE id = ?1"
+        )?;
+
+        let mut rows = stmt.query_map([id], |row| {
+            let condition_type_str: String = row.get(2)?;
+            let condition_type = ConditionType::from_string(&condition_type_str).map_err(|e| {
+                rusqlite::Error::InvalidColumnType(2, e.into(), rusqlite::types::Type::Text)
+            })?;
+
+            Ok(Condition {
+                id: row.get(0)?,
+                name: row.get(1)?,
+                condition_type,
+                age_min: row.get(3)?,
+                age_max: row.get(4)?,
+                calculated_age_from: row.get(5)?,
+                calculated_age_to: row.get(6)?,
+                coordinates_lat: row.get(7)?,
+                coordinates_lon: row.get(8)?,
+                distance: row.get(9)?,
+                updated_on: row.get(10)?,
+            })
+        })?;
+
+        match rows.next() {
+            Some(row) => Ok(Some(row?)),
+            None => Ok(None),
+        }
+    }
+
+    pub fn update_condition(&self, condition: &Condition) -> Result<()> {
+        let conn = self.get_connection()?;
+        conn.execute(
+            "UPDATE condition SET name = ?2, condition_type = ?3, age_min = ?4, age_max = ?5, 
+             calculated_age_from = ?6, calculated_age_to = ?7, coordinates_lat = ?8, coordinates_lon = ?9, 
+             distance = ?10, updated_on = ?11 WHERE id = ?1",
+            (
+                &condition.id,
+                &condition.name,
+                &condition.condition_type.to_string(),
+                &condition.age_min,
+                &condition.age_max,
+                &condition.calculated_age_from,
+                &condition.calculated_age_to,
+                &condition.coordinates_lat,
+                &condition.coordinates_lon,
+                &condition.distance,
+                &condition.updated_on,
+            ),
+        )?;
+        Ok(())
+    }
+
+    pub fn delete_condition(&self, id: &str) -> Result<()> {
+        let conn = self.get_connection()?;
+        conn.execute("DELETE FROM condition WHERE id = ?1", [id])?;
+        Ok(())
+    }
+
+    pub fn list_conditions(&self) -> Result<Vec<Condition>> {
+        let conn = self.get_connection()?;
+        let mut stmt = conn.prepare(
+            "SELECT id, name, condition_type, age_min, age_max, calculated_age_from, calculated_age_to, coordinates_lat, coordinates_lon, distance, updated_on 
+             FROM condition"
+        )?;
+
+        let rows = stmt.query_map([], |row| {
+            let condition_type_str: String = row.get(2)?;
+            let condition_type = ConditionType::from_string(&condition_type_str).map_err(|e| {
+                rusqlite::Error::InvalidColumnType(2, e.into(), rusqlite::types::Type::Text)
+            })?;
+
+            Ok(Condition {
+                id: row.get(0)?,
+                name: row.get(1)?,
+                condition_type,
+                age_min: row.get(3)?,
+                age_max: row.get(4)?,
+                calculated_age_from: row.get(5)?,
+                calculated_age_to: row.get(6)?,
+                coordinates_lat: row.get(7)?,
+                coordinates_lon: row.get(8)?,
+                distance: row.get(9)?,
+                updated_on: row.get(10)?,
+            })
+        })?;
+
+        let mut conditions = Vec::new();
+        for row in rows {
+            conditions.push(row?);
+        }
+        Ok(conditions)
+    }
+
+    // Claim Token CRUD operations
+    pub fn create_claim_token(&self, token: &ClaimToken) -> Result<()> {
+        let conn = self.get_connection()?;
+        conn.execute(
+            ClaimToken::insert_sql(),
+            (
+                &token.id,
+                &token.token_selector,
+                &token.token_salt,
+                &token.token_hash,
+                &token.memory_id,
+                &token.organization_did,
+                &token.expiry_timestamp,
+                &token.claimed_by_did,
+                &token.claimed_timestamp,
+                &token.created_timestamp,
+                &token.updated_on,
+                token.scope.as_str(),
+                &token.claimed_memory_id,
+                &token.bound_to_did,
+                &token.handover_memory_id,
+            ),
+        )?;
+        Ok(())
+    }
+
+    pub fn get_claim_token(&self, id: &TokenId) -> Result<Option<ClaimToken>> {
+        let sql = format!(
+            "SELECT {} FROM {} WHERE id = ?1",
+            ClaimToken::select_fields(),
+            ClaimToken::table_name()
+        );
+        let conn = self.get_connection()?;
+        let mut stmt = conn.prepare(&sql)?;
+        let mut rows = stmt.query_map([id], ClaimToken::from_row)?;
+
+        match rows.next() {
+            Some(row) => Ok(Some(row?)),
+            None => Ok(None),
+        }
+    }
+
+    /// The token whose code is `token`: rows sharing its selector, each checked against
+    /// its salted hash
+    pub fn get_claim_token_by_token(&self, token: &str) -> Result<Option<ClaimToken>> {
+        let sql = format!(
+            "SELECT {} FROM {} WHERE token_selector = ?1",
+            ClaimToken::select_fields(),
+            ClaimToken::table_name()
+        );
+        let conn = self.get_connection()?;
+        let mut stmt = conn.prepare(&sql)?;
+        let rows = stmt.query_map([ClaimToken::selector(token)], ClaimToken::from_row)?;
+
+        let mut found = None;
+        for row in rows {
+            let candidate = row?;
+            // Every candidate is hashed, so timing does not say which one matched
+            if candidate.matches(token, self.claim_token_pepper()) && found.is_none() {
+                found = Some(candidate);
+            }
+        }
+        Ok(found)
+    }
+
+    /// Key claim code hashes with; see `load_claim_token_pepper`
+    pub fn claim_token_pepper(&self) -> &[u8] {
+        self.claim_token_pepper.as_slice()
+    }
+
+    /// Replace the plaintext of tokens stored before they were hashed with a peppered
+    /// hash, and key hashes stored before the pepper with it. Returns how many tokens
+    /// were changed
+    pub fn hash_claim_tokens(&self) -> Result<usize> {
+        let pepper = self.claim_token_pepper();
+        let mut conn = self.get_connection()?;
+        let tx = conn.transaction()?;
+        let plaintext: Vec<(String, String)> = {
+            let mut stmt =
+                tx.prepare("SELECT id, token FROM claim_token WHERE token IS NOT NULL")?;
+            let rows = stmt.query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?;
+            rows.collect::<std::result::Result<_, _>>()?
+        };
+        for (id, code) in &plaintext {
+            let salt: [u8; 16] = rand::random();
+            tx.execute(
+                "UPDATE claim_token SET token = NULL, token_selector = ?2, token_salt = ?3, token_hash = ?4, token_peppered = 1 WHERE id = ?1",
+                (
+                    id,
+                    ClaimToken::selector(code),
+                    hex::encode(salt),
+                    ClaimToken::hash_code(pepper, &salt, code),
+                ),
+            )?;
+        }
+        let unpeppered: Vec<(String, String)> = {
+            let mut stmt = tx.prepare(
+                "SELECT id, token_hash FROM claim_token WHERE token_peppered = 0 AND token_hash IS NOT NULL",
+            )?;
+            let rows = stmt.query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?;
+            rows.collect::<std::result::Result<_, _>>()?
+        };
+        for (id, salted_hash) in &unpeppered {
+            tx.execute(
+                "UPDATE claim_token SET token_hash = ?2, token_peppered = 1 WHERE id = ?1",
+                (id, ClaimToken::pepper_hash(pepper, salted_hash)),
+            )?;
+        }
+        tx.commit()?;
+        Ok(plaintext.len() + unpeppered.len())
+    }
+
+    pub fn update_claim_token(&self, token: &ClaimToken) -> Result<()> {
+        let conn = self.get_connection()?;
+        conn.execute(
+            ClaimToken::update_sql(),
+            (
+                &token.id,
+                &token.memory_id,
+                &token.organization_did,
+                &token.expiry_timestamp,
+                &token.claimed_by_did,
+                &token.claimed_timestamp,
+                &token.created_timestamp,
+                &token.updated_on,
+                token.scope.as_str(),
+                &token.claimed_memory_id,
+                &token.bound_to_did,
+                &token.handover_memory_id,
+            ),
+        )?;
+        Ok(())
+    }
+
+    pub fn list_claim_tokens_by_organization(
+        &self,
+        organization_did: &Did,
+    ) -> Result<Vec<ClaimToken>> {
+        let sql = format!(
+            "SELECT {} FROM {} WHERE organization_did = ?1 ORDER BY created_timestamp DESC",
+            ClaimToken::select_fields(),
+            ClaimToken::table_name()
+        );
+        let conn = self.get_connection()?;
+        let mut stmt = conn.prepare(&sql)?;
+        let rows = stmt.query_map([organization_did], ClaimToken::from_row)?;
+
+        let mut tokens = Vec::new();
+        for row in rows {
+            tokens.push(row?);
+        }
+        Ok(tokens)
+    }
+
+    // Claim statistics, aggregated in SQL so dashboards never load every token
+
+    /// Total, claimed and expired-unclaimed tokens of an organization
+    pub fn count_claim_tokens(&self, organization_did: &Did) -> Result<(u64, u64, u64)> {
+        let conn = self.get_connection()?;
+        let counts = conn.query_row(
+            "SELECT COUNT(*),
+                    COUNT(claimed_by_did),
+                    COALESCE(SUM(claimed_by_did IS NULL
+                        AND COALESCE(julianday(expiry_timestamp) <= julianday('now'), 1)), 0)
+             FROM claim_token WHERE organization_did = ?1",
+            [organization_did],
+            |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+        )?;
+        Ok(counts)
+    }
+
+    pub fn count_proxy_memories(&self, organization_did: &Did) -> Result<u64> {
+        let conn = self.get_connection()?;
+        let count = conn.query_row(
+            "SELECT COUNT(*) FROM proxy_memory WHERE organization_did = ?1",
+            [organization_did],
+            |row| row.get(0),
+        )?;
+        Ok(count)
+    }
+
+    fn check_stats_range(query: &ClaimStatsQuery) -> Result<()> {
+        for bound in [&query.since, &query.until].into_iter().flatten() {
+            chrono::DateTime::parse_from_rfc3339(bound).map_err(|e| {
+                OcmError::Validation(format!("Invalid time range bound {}: {}", bound, e))
+            })?;
+        }
+        Ok(())
+    }
+
+    /// Claim rate of the tokens created in each period
+    pub fn claim_rate_over_time(
+        &self,
+        organization_did: &Did,
+        query: &ClaimStatsQuery,
+    ) -> Result<Vec<ClaimRatePoint>> {
+        Self::check_stats_range(query)?;
+        let sql = format!(
+            "SELECT {} AS period, COUNT(*), COUNT(claimed_by_did)
+             FROM claim_token
+             WHERE organization_did = ?1
+               AND (?2 IS NULL OR julianday(created_timestamp) >= julianday(?2))
+               AND (?3 IS NULL OR julianday(created_timestamp) < julianday(?3))
+             GROUP BY period ORDER BY period",
+            query.bucket.period_sql("created_timestamp")
+        );
+        let conn = self.get_connection()?;
+        let mut stmt = conn.prepare(&sql)?;
+        let rows = stmt.query_map(
+            rusqlite::params![organization_did, query.since, query.until],
+            |row| {
+                let tokens_created: u64 = row.get(1)?;
+                let tokens_claimed: u64 = row.get(2)?;
+                Ok(ClaimRatePoint {
+                    period: row.get(0)?,
+                    tokens_created,
+                    tokens_claimed,
+                    claim_rate: tokens_claimed as f64 / tokens_created.max(1) as f64 * 100.0,
+                })
+            },
+        )?;
+
+        let mut points = Vec::new();
+        for row in rows {
+            points.push(row?);
+        }
+        Ok(points)
+    }
+
+    /// Active, claimed and expired counts of the tokens created in each period
+    pub fn token_status_over_time(
+        &self,
+        organization_did: &Did,
+        query: &ClaimStatsQuery,
+    ) -> Result<Vec<TokenStatusPoint>> {
+        Self::check_stats_range(query)?;
+        let sql = format!(
+            "SELECT period,
+                    SUM(claimed_by_did IS NULL AND NOT expired),
+                    COUNT(claimed_by_did),
+                    SUM(claimed_by_did IS NULL AND expired)
+             FROM (
+                 SELECT {} AS period, claimed_by_did,
+                        COALESCE(julianday(expiry_timestamp) <= julianday('now'), 1) AS expired
+                 FROM claim_token
+                 WHERE organization_did = ?1
+                   AND (?2 IS NULL OR julianday(created_timestamp) >= julianday(?2))
+                   AND (?3 IS NULL OR julianday(created_timestamp) < julianday(?3))
+             )
+             GROUP BY period ORDER BY period",
+            query.bucket.period_sql("created_timestamp")
+        );
+        let conn = self.get_connection()?;
+        let mut stmt = conn.prepare(&sql)?;
+        let rows = stmt.query_map(
+            rusqlite::params![organization_did, query.since, query.until],
+            |row| {
+                Ok(TokenStatusPoint {
+                    period: row.get(0)?,
+                    active: row.get(1)?,
+                    claimed: row.get(2)?,
+                    expired: row.get(3)?,
+                })
+            },
+        )?;
+
+        let mut points = Vec::new();
+        for row in rows {
+            points.push(row?);
+        }
+        Ok(points)
+    }
+
+    /// Median creation-to-claim time of the tokens claimed in each period
+    pub fn time_to_claim_over_time(
+        &self,
+        organization_did: &Did,
+        query: &ClaimStatsQuery,
+    ) -> Result<Vec<TimeToClaimPoint>> {
+        Self::check_stats_range(query)?;
+        // The middle row, or the mean of the middle two, within each period
+        let sql = format!(
+            "WITH durations AS (
+                 SELECT {} AS period,
+                        (julianday(claimed_timestamp) - julianday(created_timestamp)) * 86400.0
+                            AS seconds
+                 FROM claim_token
+                 WHERE organization_did = ?1 AND claimed_timestamp IS NOT NULL
+                   AND (?2 IS NULL OR julianday(claimed_timestamp) >= julianday(?2))
+                   AND (?3 IS NULL OR julianday(claimed_timestamp) < julianday(?3))
+             ), ranked AS (
+                 SELECT period, seconds,
+                        ROW_NUMBER() OVER (PARTITION BY period ORDER BY seconds) AS position,
+                        COUNT(*) OVER (PARTITION BY period) AS claimed
+                 FROM durations WHERE seconds IS NOT NULL
+             )
+             SELECT period, claimed, AVG(seconds)
+             FROM ranked
+             WHERE position IN ((claimed + 1) / 2, (claimed + 2) / 2)
+             GROUP BY period, claimed ORDER BY period",
+            query.bucket.period_sql("claimed_timestamp")
+        );
+        let conn = self.get_connection()?;
+        let mut stmt = conn.prepare(&sql)?;
+        let rows = stmt.query_map(
+            rusqlite::params![organization_did, query.since, query.until],
+            |row| {
+                Ok(TimeToClaimPoint {
+                    period: row.get(0)?,
+                    tokens_claimed: row.get(1)?,
+                    median_seconds: row.get(2)?,
+                })
+            },
+        )?;
+
+        let mut points = Vec::new();
+        for row in rows {
+            points.push(row?);
+        }
+        Ok(points)
+    }
+
+    // Proxy Memory CRUD operations
+    pub fn create_proxy_memory(&self, proxy: &ProxyMemory) -> Result<()> {
+        let conn = self.get_connection()?;
+        conn.execute(
+            ProxyMemory::insert_sql(),
+            (
+                &proxy.id,
+                &proxy.proxy_for_name,
+                &proxy.proxy_for_info,
+                &proxy.organization_did,
+                &proxy.memory_data,
+                &proxy.created_timestamp,
+                &proxy.claim_token_id,
+            ),
+        )?;
+        Ok(())
+    }
+
+    pub fn get_proxy_memory(&self, id: &str) -> Result<Option<ProxyMemory>> {
+        let sql = format!(
+            "SELECT {} FROM {} WHERE id = ?1",
+            ProxyMemory::select_fields(),
+            ProxyMemory::table_name()
+        );
+        let conn = self.get_connection()?;
+        let mut stmt = conn.prepare(&sql)?;
+        let mut rows = stmt.query_map([id], ProxyMemory::from_row)?;
+
+        match rows.next() {
+            Some(row) => Ok(Some(row?)),
+            None => Ok(None),
+        }
+    }
+
+    pub fn list_proxy_memories_by_organization(
+        &self,
+        organization_did: &Did,
+    ) -> Result<Vec<ProxyMemory>> {
+        let sql = format!(
+            "SELECT {} FROM {} WHERE organization_did = ?1 ORDER BY created_timestamp DESC",
+            ProxyMemory::select_fields(),
+            ProxyMemory::table_name()
+        );
+        let conn = self.get_connection()?;
+        let mut stmt = conn.prepare(&sql)?;
+        let rows = stmt.query_map([organization_did], ProxyMemory::from_row)?;
+
+        let mut proxies = Vec::new();
+        for row in rows {
+            proxies.push(row?);
+        }
+        Ok(proxies)
+    }
+
+    pub fn search_proxy_memories_by_name(&self, name_pattern: &str) -> Result<Vec<ProxyMemory>> {
+        let sql = format!(
+            "SELECT {} FROM {} WHERE proxy_for_name LIKE ?1 ORDER BY created_timestamp DESC",
+            ProxyMemory::select_fields(),
+            ProxyMemory::table_name()
+        );
+        let conn = self.get_connection()?;
+        let mut stmt = conn.prepare(&sql)?;
+
+        // Escape SQL wildcards to prevent injection
+        let escaped_pattern = name_pattern
+            .replace('\\', "\\\\") // Escape backslashes first
+            .replace('%', "\\%") // Escape percent signs
+            .replace('_', "\\_"); // Escape underscores
+        let search_pattern = format!("%{}%", escaped_pattern);
+
+        let rows = stmt.query_map([search_pattern], ProxyMemory::from_row)?;
+
+        let mut proxies = Vec::new();
+        for row in rows {
+            proxies.push(row?);
+        }
+        Ok(proxies)
+    }
+
+    pub fn get_proxy_memory_by_claim_token(
+        &self,
+        claim_token_id: &TokenId,
+    ) -> Result<Option<ProxyMemory>> {
+        let sql = format!(
+            "SELECT {} FROM {} WHERE claim_token_id = ?1",
+            ProxyMemory::select_fields(),
+            ProxyMemory::table_name()
+        );
+        let conn = self.get_connection()?;
+        let mut stmt = conn.prepare(&sql)?;
+        let mut rows = stmt.query_map([claim_token_id], ProxyMemory::from_row)?;
+
+        match rows.next() {
+            Some(row) => Ok(Some(row?)),
+            None => Ok(None),
+        }
+    }
+
+    // Data subject request operations
+    pub fn list_memories_referencing_did(&self, did: &Did) -> Result<Vec<SignedMemory>> {
+        let sql = format!(
+            "SELECT {} FROM {} WHERE did = ?1 OR co_signatures LIKE ?2 ESCAPE '\\' ORDER BY timestamp ASC",
+            SignedMemory::select_fields(),
+            SignedMemory::table_name()
+        );
+        let conn = self.get_connection()?;
+        let mut stmt = conn.prepare(&sql)?;
+
+        let escaped_did = did
+            .replace('\\', "\\\\")
+            .replace('%', "\\%")
+            .replace('_', "\\_");
+        let co_signer_pattern = format!("%\"signer_did\":\"{}\"%", escaped_did);
+
+        let rows = stmt.query_map([did, co_signer_pattern.as_str()], SignedMemory::from_row)?;
+
+        let mut memories = Vec::new();
+        for row in rows {
+            memories.push(row?);
+        }
+        Ok(memories)
+    }
+
+    pub fn list_claim_tokens_claimed_by(&self, claimer_did: &Did) -> Result<Vec<ClaimToken>> {
+        let sql = format!(
+            "SELECT {} FROM {} WHERE claimed_by_did = ?1 ORDER BY created_timestamp DESC",
+            ClaimToken::select_fields(),
+            ClaimToken::table_name()
+        );
+        let conn = self.get_connection()?;
+        let mut stmt = conn.prepare(&sql)?;
+        let rows = stmt.query_map([claimer_did], ClaimToken::from_row)?;
+
+        let mut tokens = Vec::new();
+        for row in rows {
+            tokens.push(row?);
+        }
+        Ok(tokens)
+    }
+
+    /// Replace a memory's content with an empty tombstone, keeping its hash and signatures
+    pub fn scrub_signed_memory(&self, id: &MemoryId) -> Result<()> {
+        let conn = self.get_connection()?;
+        conn.execute(
+            "UPDATE signed_memory SET memory_type = ?2, memory_data = '', body_hash = NULL, updated_on = ?3 WHERE id = ?1",
+            (id, TOMBSTONE_MEMORY_TYPE, chrono::Utc::now().to_rfc3339()),
+        )?;
+        Ok(())
+    }
+
+    pub fn scrub_proxy_memory(&self, id: &str) -> Result<()> {
+        let conn = self.get_connection()?;
+        conn.execute(
+            "UPDATE proxy_memory SET proxy_for_name = '[erased]', proxy_for_info = NULL, memory_data = '' WHERE id = ?1",
+            [id],
+        )?;
+        // The fingerprint is derived from the erased name and date of birth
+        conn.execute("DELETE FROM proxy_linkage WHERE proxy_id = ?1", [id])?;
+        Ok(())
+    }
+
+    // Record linkage operations
+    pub fn create_proxy_linkage(
+        &self,
+        proxy_id: &str,
+        organization_did: &Did,
+        dob_key: &str,
+        name_bloom: &[u8],
+    ) -> Result<()> {
+        let conn = self.get_connection()?;
+        conn.execute(
+            "INSERT OR REPLACE INTO proxy_linkage
+             (proxy_id, organization_did, dob_key, name_bloom, created_at)
+             VALUES (?1, ?2, ?3, ?4, ?5)",
+            (
+                proxy_id,
+                organization_did,
+                dob_key,
+                hex::encode(name_bloom),
+                chrono::Utc::now().to_rfc3339(),
+            ),
+        )?;
+        Ok(())
+    }
+
+    /// Proxy records of organizations other than `organization_did` sharing a date of
+    /// birth key, as (proxy ID, name Bloom filter)
+    pub fn list_proxy_linkages(
+        &self,
+        dob_key: &str,
+        organization_did: &Did,
+    ) -> Result<Vec<(String, Vec<u8>)>> {
+        let conn = self.get_connection()?;
+        let mut stmt = conn.prepare(
+            "SELECT proxy_id, name_bloom FROM proxy_linkage
+             WHERE dob_key = ?1 AND organization_did != ?2",
+        )?;
+        let rows = stmt.query_map([dob_key, organization_did], |row| {
+            Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?))
+        })?;
+
+        let mut linkages = Vec::new();
+        for row in rows {
+            let (proxy_id, bloom) = row?;
+            match hex::decode(&bloom) {
+                Ok(bloom) => linkages.push((proxy_id, bloom)),
+                Err(e) => eprintln!("⚠️  Skipping bad linkage of proxy {}: {}", proxy_id, e),
+            }
+        }
+        Ok(linkages)
+    }
+
+    pub fn create_duplicate_candidate(&self, candidate: &DuplicateCandidate) -> Result<()> {
+        let conn = self.get_connection()?;
+        conn.execute(
+            "INSERT OR IGNORE INTO duplicate_candidate
+             (id, proxy_id, matched_proxy_id, score, status, merged_memory_id, detected_at,
+              resolved_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+            (
+                &candidate.id,
+                &candidate.proxy_id,
+                &candidate.matched_proxy_id,
+                candidate.score,
+                candidate.status.as_str(),
+                &candidate.merged_memory_id,
+                &candidate.detected_at,
+                &candidate.resolved_at,
+            ),
+        )?;
+        Ok(())
+    }
+
+    pub fn update_duplicate_candidate(&self, candidate: &DuplicateCandidate) -> Result<()> {
+        let conn = self.get_connection()?;
+        conn.execute(
+            "UPDATE duplicate_candidate SET status = ?2, merged_memory_id = ?3, resolved_at = ?4
+             WHERE id = ?1",
+            (
+                &candidate.id,
+                candidate.status.as_str(),
+                &candidate.merged_memory_id,
+                &candidate.resolved_at,
+            ),
+        )?;
+        Ok(())
+    }
+
+    pub fn get_duplicate_candidate(&self, id: &str) -> Result<Option<DuplicateCandidate>> {
+        Ok(self
+            .query_duplicate_candidates("id = ?1", id)?
+            .into_iter()
+            .next())
+    }
+
+    /// Candidates either side of which is the proxy record
+    pub fn list_duplicate_candidates_for_proxy(
+        &self,
+        proxy_id: &str,
+    ) -> Result<Vec<DuplicateCandidate>> {
+        self.query_duplicate_candidates("proxy_id = ?1 OR matched_proxy_id = ?1", proxy_id)
+    }
+
+    fn query_duplicate_candidates(
+        &self,
+        filter: &str,
+        value: &str,
+    ) -> Result<Vec<DuplicateCandidate>> {
+        let conn = self.get_connection()?;
+        let mut stmt = conn.prepare(&format!(
+            "SELECT id, proxy_id, matched_proxy_id, score, status, merged_memory_id,
+                    detected_at, resolved_at
+             FROM duplicate_candidate WHERE {} ORDER BY detected_at",
+            filter
+        ))?;
+        let rows = stmt.query_map([value], |row| {
+            let status: String = row.get(4)?;
+            Ok(DuplicateCandidate {
+                id: row.get(0)?,
+                proxy_id: row.get(1)?,
+                matched_proxy_id: row.get(2)?,
+                score: row.get(3)?,
+                status: DuplicateStatus::parse(&status).unwrap_or(DuplicateStatus::Pending),
+                merged_memory_id: row.get(5)?,
+                detected_at: row.get(6)?,
+                resolved_at: row.get(7)?,
+            })
+        })?;
+
+        let mut candidates = Vec::new();
+        for row in rows {
+            candidates.push(row?);
+        }
+        Ok(candidates)
+    }
+
+    /// Hold a peer's memory for review; false if this version was held before, whether
+    /// it is still pending or was already decided
+    pub fn hold_pending_memory(&self, pending: &PendingMemory) -> Result<bool> {
+        let memory = &pending.memory;
+        let conn = self.get_connection()?;
+        let held = conn.execute(
+            "INSERT OR IGNORE INTO pending_memory
+             (id, memory_id, did, memory_type, memory_data, content_hash, signature, timestamp,
+              updated_on, co_signatures, source_peer, source_did, offline_public_key, status,
+              received_at, decided_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16)",
+            rusqlite::params![
+                &pending.id,
+                &memory.id,
+                &memory.did,
+                &memory.memory_type,
+                &memory.memory_data,
+                &memory.content_hash,
+                &memory.signature,
+                &memory.timestamp,
+                &memory.updated_on,
+                serde_json::to_string(&memory.co_signatures)?,
+                &pending.source_peer,
+                &pending.source_did,
+                &pending.offline_public_key,
+                pending.status.as_str(),
+                &pending.received_at,
+                &pending.decided_at,
+            ],
+        )?;
+        Ok(held > 0)
+    }
+
+    pub fn get_pending_memory(&self, id: &str) -> Result<Option<PendingMemory>> {
+        Ok(self
+            .query_pending_memories("id = ?1", id)?
+            .into_iter()
+            .next())
+    }
+
+    /// Held memories with the given status, oldest first
+    pub fn list_pending_memories(&self, status: PendingStatus) -> Result<Vec<PendingMemory>> {
+        self.query_pending_memories("status = ?1", status.as_str())
+    }
+
+    fn query_pending_memories(&self, filter: &str, value: &str) -> Result<Vec<PendingMemory>> {
+        let conn = self.get_connection()?;
+        let mut stmt = conn.prepare(&format!(
+            "SELECT memory_id, did, memory_type, memory_data, content_hash, signature, timestamp,
+                    updated_on, co_signatures, id, source_peer, source_did, offline_public_key,
+                    status, received_at, decided_at
+             FROM pending_memory WHERE {} ORDER BY received_at",
+            filter
+        ))?;
+        let rows = stmt.query_map([value], |row| {
+            let status: String = row.get(13)?;
+            Ok(PendingMemory {
+                memory: SignedMemory::from_row(row)?,
+                id: row.get(9)?,
+                source_peer: row.get(10)?,
+                source_did: row.get(11)?,
+                offline_public_key: row.get(12)?,
+                status: PendingStatus::parse(&status).unwrap_or(PendingStatus::Pending),
+                received_at: row.get(14)?,
+                decided_at: row.get(15)?,
+            })
+        })?;
+
+        let mut pending = Vec::new();
+        for row in rows {
+            pending.push(row?);
+        }
+        Ok(pending)
+    }
+
+    /// Store a held memory and mark it approved in one transaction. A local copy is only
+    /// replaced by a newer version. None if the entry is unknown or already decided
+    pub fn approve_pending_memory(&self, id: &str) -> Result<Option<PendingMemory>> {
+        let Some(mut pending) = self.get_pending_memory(id)? else {
+            return Ok(None);
+        };
+        if pending.status != PendingStatus::Pending {
+            return Ok(None);
+        }
+        let decided_at = chrono::Utc::now().to_rfc3339();
+
+        let approved = self.write_signed(|conn| {
+            let tx = conn.transaction()?;
+            let decided = tx.execute(
+                "UPDATE pending_memory SET status = 'approved', decided_at = ?2
+                 WHERE id = ?1 AND status = 'pending'",
+                (id, &decided_at),
+            )?;
+            if decided == 0 {
+                return Ok(false);
+            }
+            let memory = &pending.memory;
+            let local_updated_on: Option<String> = tx
+                .query_row(
+                    "SELECT updated_on FROM signed_memory WHERE id = ?1",
+                    [&memory.id],
+                    |row| row.get(0),
+                )
+                .optional()?;
+            match local_updated_on {
+                None => insert_signed_memory(&tx, &self.compression, memory)?,
+                Some(local) if memory.updated_on > local => {
+                    update_signed_memory_row(&tx, &self.compression, memory)?
+                }
+                Some(_) => {}
+            }
+            if let Some(public_key) = &pending.offline_public_key {
+                tx.execute(
+                    "INSERT OR REPLACE INTO offline_verification (memory_id, did, public_key, verified_at)
+                     VALUES (?1, ?2, ?3, ?4)",
+                    (&memory.id, &memory.did, public_key, &decided_at),
+                )?;
+            }
+            tx.commit()?;
+            Ok(true)
+        })?;
+        if !approved {
+            return Ok(None);
+        }
+
+        pending.status = PendingStatus::Approved;
+        pending.decided_at = Some(decided_at);
+        Ok(Some(pending))
+    }
+
+    /// Reject a held memory; false if the entry is unknown or already decided
+    pub fn reject_pending_memory(&self, id: &str) -> Result<bool> {
+        let conn = self.get_connection()?;
+        let rejected = conn.execute(
+            "UPDATE pending_memory SET status = 'rejected', decided_at = ?2
+             WHERE id = ?1 AND status = 'pending'",
+            (id, chrono::Utc::now().to_rfc3339()),
+        )?;
+        Ok(rejected > 0)
+    }
+
+    pub fn create_erasure_record(&self, record: &ErasureRecord) -> Result<()> {
+        let conn = self.get_connection()?;
+        conn.execute(
+            ErasureRecord::insert_sql(),
+            (
+                &record.id,
+                &record.subject_did,
+                &record.memory_id,
+                &record.content_hash,
+                &record.erased_by_did,
+                &record.erased_at,
+                &record.signature,
+            ),
+        )?;
+        Ok(())
+    }
+
+    pub fn list_erasure_records(&self, subject_did: &Did) -> Result<Vec<ErasureRecord>> {
+        let sql = format!(
+            "SELECT {} FROM {} WHERE subject_did = ?1 ORDER BY erased_at ASC",
+            ErasureRecord::select_fields(),
+            ErasureRecord::table_name()
+        );
+        let conn = self.get_connection()?;
+        let mut stmt = conn.prepare(&sql)?;
+        let rows = stmt.query_map([subject_did], ErasureRecord::from_row)?;
+
+        let mut records = Vec::new();
+        for row in rows {
+            records.push(row?);
+        }
+        Ok(records)
+    }
+
+    // Retention operations
+    pub fn list_memories_by_type_before(
+        &self,
+        memory_type: &str,
+        cutoff: &str,
+    ) -> Result<Vec<SignedMemory>> {
+        let sql = format!(
+            "SELECT {} FROM {} WHERE memory_type = ?1 AND timestamp < ?2 ORDER BY timestamp ASC",
+            SignedMemory::select_fields(),
+            SignedMemory::table_name()
+        );
+        let conn = self.get_connection()?;
+        let mut stmt = conn.prepare(&sql)?;
+        let rows = stmt.query_map([memory_type, cutoff], SignedMemory::from_row)?;
+
+        let mut memories = Vec::new();
+        for row in rows {
+            memories.push(row?);
+        }
+        Ok(memories)
+    }
+
+    pub fn get_claim_token_by_memory(&self, memory_id: &MemoryId) -> Result<Option<ClaimToken>> {
+        let sql = format!(
+            "SELECT {} FROM {} WHERE memory_id = ?1",
+            ClaimToken::select_fields(),
+            ClaimToken::table_name()
+        );
+        let conn = self.get_connection()?;
+        let mut stmt = conn.prepare(&sql)?;
+        let mut rows = stmt.query_map([memory_id], ClaimToken::from_row)?;
+
+        match rows.next() {
+            Some(row) => Ok(Some(row?)),
+            None => Ok(None),
+        }
+    }
+
+    /// Move a memory into the archive table in a single transaction
+    pub fn archive_signed_memory(&self, id: &MemoryId) -> Result<()> {
+        let mut conn = self.get_connection()?;
+        let tx = conn.transaction()?;
+        archive_signed_memory_row(&tx, id, &chrono::Utc::now().to_rfc3339())?;
+        tx.commit()?;
+        Ok(())
+    }
+
+    /// Replace stub-signed memories with re-signed copies in one transaction. The copies
+    /// and the lineage memory are stored and queued for broadcast; each legacy memory is
+    /// archived and recorded in legacy_memory with the copy that replaced it
+    pub fn migrate_legacy_memories(
+        &self,
+        replacements: &[(MemoryId, SignedMemory)], // Legacy memory ID and its copy
+        lineage: &SignedMemory,
+    ) -> Result<()> {
+        self.write_signed(|conn| {
+            let tx = conn.transaction()?;
+            let migrated_at = chrono::Utc::now().to_rfc3339();
+            let copies = replacements.iter().map(|(_, copy)| copy);
+            for memory in copies.chain(std::iter::once(lineage)) {
+                insert_signed_memory(&tx, &self.compression, memory)?;
+                tx.execute(
+                    "INSERT INTO memory_outbox (memory_id, created_at) VALUES (?1, ?2)",
+                    (&memory.id, &migrated_at),
+                )?;
+            }
+            for (legacy_id, copy) in replacements {
+                tx.execute(
+                    "INSERT INTO legacy_memory (memory_id, legacy_did, replaced_by, did, migrated_at)
+                     SELECT id, did, ?2, ?3, ?4 FROM signed_memory WHERE id = ?1",
+                    (legacy_id, &copy.id, &copy.did, &migrated_at),
+                )?;
+                archive_signed_memory_row(&tx, legacy_id, &migrated_at)?;
+            }
+            tx.commit()?;
+            Ok(())
+        })
+    }
+
+    /// Complete a custody handover in one transaction: store and queue the new owner's
+    /// copy and the co-signed authorization, archive the handed-over memory and record
+    /// it in custody_handover with the copy that replaced it
+    pub fn hand_over_memory(
+        &self,
+        memory_id: &MemoryId,
+        copy: &SignedMemory,
+        authorization: &SignedMemory,
+        claim_token_id: &TokenId,
+    ) -> Result<()> {
+        self.write_signed(|conn| {
+            let tx = conn.transaction()?;
+            let handed_over_at = chrono::Utc::now().to_rfc3339();
+            insert_signed_memory(&tx, &self.compression, copy)?;
+            update_signed_memory_row(&tx, &self.compression, authorization)?;
+            for queued in [copy, authorization] {
+                tx.execute(
+                    "INSERT INTO memory_outbox (memory_id, created_at) VALUES (?1, ?2)",
+                    (&queued.id, &handed_over_at),
+                )?;
+            }
+            tx.execute(
+                "INSERT INTO custody_handover (memory_id, from_did, replaced_by, did, handover_memory_id, claim_token_id, handed_over_at)
+                 SELECT id, did, ?2, ?3, ?4, ?5, ?6 FROM signed_memory WHERE id = ?1",
+                (
+                    memory_id,
+                    &copy.id,
+                    &copy.did,
+                    &authorization.id,
+                    claim_token_id,
+                    &handed_over_at,
+                ),
+            )?;
+            archive_signed_memory_row(&tx, memory_id, &handed_over_at)?;
+            tx.commit()?;
+            Ok(())
+        })
+    }
+
+    /// Complete a local merge in one transaction: store and queue the consolidated
+    /// memory and the merge record, then archive each source and record what
+    /// superseded it
+    pub fn merge_memories(
+        &self,
+        merged: &SignedMemory,
+        merge_record: &SignedMemory,
+        source_ids: &[MemoryId],
+    ) -> Result<()> {
+        self.write_signed(|conn| {
+            let tx = conn.transaction()?;
+            let merged_at = chrono::Utc::now().to_rfc3339();
+            for queued in [merged, merge_record] {
+                insert_signed_memory(&tx, &self.compression, queued)?;
+                tx.execute(
+                    "INSERT INTO memory_outbox (memory_id, created_at) VALUES (?1, ?2)",
+                    (&queued.id, &merged_at),
+                )?;
+            }
+            for source_id in source_ids {
+                supersede_signed_memory_row(
+                    &tx,
+                    source_id,
+                    &merged.id,
+                    &merge_record.id,
+                    &merged_at,
+                )?;
+            }
+            tx.commit()?;
+            Ok(())
+        })
+    }
+
+    /// Apply a merge record received from a peer, archiving whichever of its sources
+    /// this node holds. Returns how many were archived
+    pub fn apply_memory_merge(
+        &self,
+        merged_memory_id: &MemoryId,
+        merge_memory_id: &MemoryId,
+        source_ids: &[MemoryId],
+    ) -> Result<usize> {
+        self.write_signed(|conn| {
+            let tx = conn.transaction()?;
+            let merged_at = chrono::Utc::now().to_rfc3339();
+            let mut superseded = 0;
+            for source_id in source_ids {
+                superseded += supersede_signed_memory_row(
+                    &tx,
+                    source_id,
+                    merged_memory_id,
+                    merge_memory_id,
+                    &merged_at,
+                )?;
+            }
+            tx.commit()?;
+            Ok(superseded)
+        })
+    }
+
+    /// The memory that a merge consolidated this one into, if any
+    pub fn get_superseding_memory_id(&self, memory_id: &MemoryId) -> Result<Option<MemoryId>> {
+        let conn = self.get_connection()?;
+        Ok(conn
+            .query_row(
+                "SELECT superseded_by FROM memory_supersedence WHERE memory_id = ?1",
+                [memory_id],
+                |row| row.get(0),
+            )
+            .optional()?)
+    }
+
+    /// A memory moved to the archive by retention, a merge or a handover
+    pub fn get_archived_signed_memory(&self, id: &MemoryId) -> Result<Option<SignedMemory>> {
+        let conn = self.get_connection()?;
+        Ok(conn
+            .query_row(
+                "SELECT id, did, memory_type, memory_data, content_hash, signature, timestamp, updated_on, co_signatures
+                 FROM signed_memory_archive WHERE id = ?1",
+                [id],
+                SignedMemory::from_row,
+            )
+            .optional()?)
+    }
+
+    /// Move a memory that failed verification into the quarantine table, so it is no
+    /// longer served or synced, in a single transaction
+    pub fn quarantine_signed_memory(&self, id: &MemoryId, reason: &str) -> Result<()> {
+        let mut conn = self.get_connection()?;
+        let tx = conn.transaction()?;
+        tx.execute(
+            "INSERT INTO signed_memory_quarantine (id, did, memory_type, memory_data, content_hash, signature, timestamp, updated_on, co_signatures, reason, quarantined_at)
+             SELECT id, did, memory_type, COALESCE((SELECT ocm_content(memory_data, dictionary_id) FROM memory_content WHERE memory_content.content_hash = signed_memory.body_hash), memory_data), content_hash, signature, timestamp, updated_on, co_signatures, ?2, ?3
+             FROM signed_memory WHERE id = ?1",
+            (id, reason, chrono::Utc::now().to_rfc3339()),
+        )?;
+        tx.execute("DELETE FROM signed_memory WHERE id = ?1", [id])?;
+        tx.commit()?;
+        Ok(())
+    }
+
+    /// Quarantine a memory a peer sent that failed verification, without storing it.
+    /// A later copy with the same ID replaces the earlier one
+    pub fn quarantine_received_memory(
+        &self,
+        memory: &SignedMemory,
+        reason: &str,
+        source: &str,
+    ) -> Result<()> {
+        let conn = self.get_connection()?;
+        conn.execute(
+            "INSERT OR REPLACE INTO signed_memory_quarantine (id, did, memory_type, memory_data, content_hash, signature, timestamp, updated_on, co_signatures, reason, quarantined_at, source)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12)",
+            rusqlite::params![
+                &memory.id,
+                &memory.did,
+                &memory.memory_type,
+                &memory.memory_data,
+                &memory.content_hash,
+                &memory.signature,
+                &memory.timestamp,
+                &memory.updated_on,
+                serde_json::to_string(&memory.co_signatures)?,
+                reason,
+                chrono::Utc::now().to_rfc3339(),
+                source,
+            ],
+        )?;
+        Ok(())
+    }
+
+    /// Quarantined memories, most recent first
+    pub fn list_quarantined_memories(&self, limit: usize) -> Result<Vec<QuarantineEntry>> {
+        let conn = self.get_connection()?;
+        let mut stmt = conn.prepare(
+            "SELECT id, did, memory_type, memory_data, content_hash, signature, timestamp,
+                    updated_on, co_signatures, reason, source, quarantined_at
+             FROM signed_memory_quarantine ORDER BY quarantined_at DESC LIMIT ?1",
+        )?;
+        let rows = stmt.query_map([limit as i64], |row| {
+            Ok(QuarantineEntry {
+                memory: SignedMemory::from_row(row)?,
+                reason: row.get(9)?,
+                source: row.get(10)?,
+                quarantined_at: row.get(11)?,
+            })
+        })?;
+
+        let mut entries = Vec::new();
+        for row in rows {
+            entries.push(row?);
+        }
+        Ok(entries)
+    }
+}
+
+/// Move one memory from signed_memory into signed_memory_archive
+fn archive_signed_memory_row(conn: &Connection, id: &str, archived_at: &str) -> Result<()> {
+    conn.execute(
+        "INSERT INTO signed_memory_archive (id, did, memory_type, memory_data, content_hash, signature, timestamp, updated_on, co_signatures, archived_at)
+         SELECT id, did, memory_type, COALESCE((SELECT ocm_content(memory_data, dictionary_id) FROM memory_content WHERE memory_content.content_hash = signed_memory.body_hash), memory_data), content_hash, signature, timestamp, updated_on, co_signatures, ?2
+         FROM signed_memory WHERE id = ?1",
+        (id, archived_at),
+    )?;
+    conn.execute("DELETE FROM signed_memory WHERE id = ?1", [id])?;
+    Ok(())
+}
+
+/// Record that a merge superseded a memory and archive it; 0 if the memory is not held
+fn supersede_signed_memory_row(
+    conn: &Connection,
+    id: &str,
+    superseded_by: &str,
+    merge_memory_id: &str,
+    superseded_at: &str,
+) -> Result<usize> {
+    let recorded = conn.execute(
+        "INSERT OR IGNORE INTO memory_supersedence (memory_id, superseded_by, merge_memory_id, did, superseded_at)
+         SELECT id, ?2, ?3, did, ?4 FROM signed_memory WHERE id = ?1",
+        (id, superseded_by, merge_memory_id, superseded_at),
+    )?;
+    if recorded > 0 {
+        archive_signed_memory_row(conn, id, superseded_at)?;
+    }
+    Ok(recorded)
+}
+
+fn memory_header_from_row(row: &rusqlite::Row) -> rusqlite::Result<MemoryHeader> {
+    Ok(MemoryHeader {
+        id: row.get(0)?,
+        did: row.get(1)?,
+        memory_type: row.get(2)?,
+        content_hash: row.get(3)?,
+        signature: row.get(4)?,
+        timestamp: row.get(5)?,
+        updated_on: row.get(6)?,
+        co_signatures: Vec::new(), // Not kept for headers; they arrive with the body
+    })
+}
+
+/// Put a memory's body in the content store, returning the inline data and body hash to
+/// write on its row. Only bodies matching their content hash are shared, so a forged
+/// hash can never substitute another memory's content. The hash is of the uncompressed
+/// body whether or not the store compresses it
+fn store_memory_body<'a>(
+    conn: &Connection,
+    compression: &ContentCompression,
+    memory: &'a SignedMemory,
+) -> Result<(&'a str, Option<&'a str>)> {
+    if memory.memory_data.is_empty() || !memory.verify_hash() {
+        return Ok((&memory.memory_data, None));
+    }
+
+    let (stored, dictionary_id) =
+        match compression.compress(&memory.memory_type, &memory.memory_data)? {
+            Some((id, compressed)) => (rusqlite::types::Value::Blob(compressed), Some(id)),
+            None => (
+                rusqlite::types::Value::Text(memory.memory_data.clone()),
+                None,
+            ),
+        };
+    conn.execute(
+        "INSERT INTO memory_content (content_hash, memory_data, dictionary_id, ref_count, created_at)
+         VALUES (?1, ?2, ?3, 1, ?4)
+         ON CONFLICT(content_hash) DO UPDATE SET ref_count = ref_count + 1",
+        (
+            &memory.content_hash,
+            stored,
+            dictionary_id,
+            chrono::Utc::now().to_rfc3339(),
+        ),
+    )?;
+    Ok(("", Some(&memory.content_hash)))
+}
+
+fn update_signed_memory_row(
+    conn: &Connection,
+    compression: &ContentCompression,
+    memory: &SignedMemory,
+) -> Result<()> {
+    let co_signatures = serde_json::to_string(&memory.co_signatures)?;
+    let (memory_data, body_hash) = store_memory_body(conn, compression, memory)?;
+    conn.execute(
+        SignedMemory::update_sql(),
+        (
+            &memory.id,
+            &memory.did,
+            &memory.memory_type,
+            memory_data,
+            &memory.content_hash,
+            &memory.signature,
+            &memory.timestamp,
+            &memory.updated_on,
+            &co_signatures,
+            body_hash,
+        ),
+    )?;
+    Ok(())
+}
+
+/// The key claim code hashes are made with, from `<database>.pepper` beside the
+/// database rather than in it, so a copy of the database alone does not allow guessing
+/// codes offline. Created, readable by the owner only, when `create` and missing.
+/// Losing it makes every outstanding code unusable. In-memory databases get a new one
+fn load_claim_token_pepper(db_path: &str, create: bool) -> Result<[u8; 32]> {
+    if db_path.is_empty() || db_path == ":memory:" {
+        return Ok(rand::random());
+    }
+    let path = format!("{}.pepper", db_path);
+    match std::fs::read(&path) {
+        Ok(bytes) => bytes
+            .try_into()
+            .map_err(|_| OcmError::Validation(format!("{} does not hold a 32-byte pepper", path))),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound && create => {
+            let pepper: [u8; 32] = rand::random();
+            let mut options = std::fs::OpenOptions::new();
+            options.write(true).create_new(true);
+            #[cfg(unix)]
+            {
+                use std::os::unix::fs::OpenOptionsExt;
+                options.mode(0o600);
+            }
+            let mut file = options.open(&path)?;
+            std::io::Write::write_all(&mut file, &pepper)?;
+            file.sync_all()?;
+            Ok(pepper)
+        }
+        // Read-only tools never issue or check codes
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(rand::random()),
+        Err(e) => Err(e.into()),
+    }
+}
+
+fn insert_activity_event(
+    conn: &Connection,
+    event_type: &str,
+    did: Option<&Did>,
+    memory_type: Option<&str>,
+    memory_id: Option<&MemoryId>,
+    payload: &serde_json::Value,
+) -> Result<()> {
+    conn.execute(
+        "INSERT INTO activity_event (event_type, did, memory_type, memory_id, payload, created_at)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+        rusqlite::params![
+            event_type,
+            did,
+            memory_type,
+            memory_id,
+            payload.to_string(),
+            chrono::Utc::now().to_rfc3339()
+        ],
+    )?;
+    Ok(())
+}
+
+/// Insert a memory with its transparency log leaf and activity event
+fn insert_signed_memory(
+    conn: &Connection,
+    compression: &ContentCompression,
+    memory: &SignedMemory,
+) -> Result<()> {
+    let co_signatures = serde_json::to_string(&memory.co_signatures)?;
+    let (memory_data, body_hash) = store_memory_body(conn, compression, memory)?;
+    conn.execute(
+        SignedMemory::insert_sql(),
+        (
+            &memory.id,
+            &memory.did,
+            &memory.memory_type,
+            memory_data,
+            &memory.content_hash,
+            &memory.signature,
+            &memory.timestamp,
+            &memory.updated_on,
+            &co_signatures,
+            body_hash,
+        ),
+    )?;
+
+    // The body has arrived, so any header held for it is superseded
+    conn.execute("DELETE FROM memory_header WHERE id = ?1", [&memory.id])?;
+
+    // Annotations are indexed by their target for threaded retrieval
+    if memory.memory_type == ANNOTATION_MEMORY_TYPE {
+        if let Ok(annotation) = serde_json::from_str::<AnnotationData>(&memory.memory_data) {
+            conn.execute(
+                "INSERT OR IGNORE INTO memory_annotation
+                     (annotation_id, target_memory_id, target_content_hash, annotated_at)
+                 VALUES (?1, ?2, ?3, ?4)",
+                (
+                    &memory.id,
+                    &annotation.target_memory_id,
+                    &annotation.target_content_hash,
+                    &memory.timestamp,
+                ),
+            )?;
+        }
+    }
+
+    // Every stored memory hash is appended to the transparency log in the same transaction
+    conn.execute(
+        "INSERT INTO transparency_log (leaf_index, memory_id, content_hash, leaf_hash, appended_at)
+         VALUES ((SELECT COUNT(*) FROM transparency_log), ?1, ?2, ?3, ?4)",
+        (
+            &memory.id,
+            &memory.content_hash,
+            hex::encode(crate::persistence::transparency::leaf_hash(
+                &memory.content_hash,
+            )),
+            chrono::Utc::now().to_rfc3339(),
+        ),
+    )?;
+
+    let payload = serde_json::json!({
+        "memory_id": memory.id,
+        "did": memory.did,
+        "memory_type": memory.memory_type,
+        "content_hash": memory.content_hash,
+        "timestamp": memory.timestamp,
+    });
+    insert_activity_event(
+        conn,
+        MEMORY_STORED_EVENT,
+        Some(&memory.did),
+        Some(&memory.memory_type),
+        Some(&memory.id),
+        &payload,
+    )
+}
+
+/// Indexed fields are matched through memory_content and the inline column separately,
+/// since an index cannot cover the COALESCE of the two
+impl DataAccess for Database {
+    fn data_condition(&self, path: &str, condition: &mut dyn FnMut(&str) -> String) -> String {
+        let indexed = self
+            .indexed_fields
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .contains(path);
+        if !indexed {
+            return condition(&format!("json_extract({}, '{}')", MEMORY_DATA_SQL, path));
+        }
+        format!(
+            "(body_hash IN (SELECT content_hash FROM memory_content WHERE {}) OR (body_hash IS NULL AND {}))",
+            condition(&format!(
+                "json_extract(ocm_content(memory_data, dictionary_id), '{}')",
+                path
+            )),
+            condition(&format!("json_extract(memory_data, '{}')", path))
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::persistence::migrations::run_migrations;
+
+    fn migrated_database() -> (String, Database) {
+        let path = std::env::temp_dir()
+            .join(format!("ocm-claims-{}.db", uuid::Uuid::new_v4()))
+            .to_string_lossy()
+            .to_string();
+        run_migrations(&path).expect("Failed to migrate database");
+        let database = Database::new(&path).unwrap();
+        (path, database)
+    }
+
+    fn remove_database(path: &str) {
+        for suffix in ["", "-wal", "-shm", ".pepper"] {
+            let _ = std::fs::remove_file(format!("{}{}", path, suffix));
+        }
+    }
+
+    #[test]
+    fn test_claim_codes_keep_the_selector_apart_from_the_secret() {
+        let (path, database) = migrated_database();
+        let memory = SignedMemory::new(
+            &Did::parse("did:plc:org").unwrap(),
+            "proxy_individual",
+            "{}",
+        );
+        database.create_signed_memory(&memory).unwrap();
+        let token = ClaimToken::new(
+            &memory.id,
+            &Did::parse("did:plc:org").unwrap(),
+            1,
+            database.claim_token_pepper(),
+        );
+        database.create_claim_token(&token).unwrap();
+
+        let (selector, secret) = token.token["OCM-".len()..].split_once('-').unwrap();
+        assert_eq!(token.token_selector, selector);
+        assert_eq!(secret.len(), 16); // 80 bits of base32
+
+        let found = database.get_claim_token_by_token(&token.token).unwrap();
+        assert_eq!(found.map(|found| found.id), Some(token.id.clone()));
+        let wrong = format!("OCM-{}-{}", selector, "A".repeat(16));
+        assert!(database.get_claim_token_by_token(&wrong).unwrap().is_none());
+
+        // The same database with a different pepper cannot check codes
+        drop(database);
+        std::fs::write(format!("{}.pepper", path), [7u8; 32]).unwrap();
+        let database = Database::new(&path).unwrap();
+        assert!(database
+            .get_claim_token_by_token(&token.token)
+            .unwrap()
+            .is_none());
+        remove_database(&path);
+    }
+
+    #[test]
+    fn test_hashes_stored_before_the_pepper_are_keyed_in_place() {
+        let (path, database) = migrated_database();
+        let memory = SignedMemory::new(
+            &Did::parse("did:plc:org").unwrap(),
+            "proxy_individual",
+            "{}",
+        );
+        database.create_signed_memory(&memory).unwrap();
+
+        // A row as migration V35 left it: an unkeyed salted hash of an older code
+        let code = "OCM-ABCDEFGHIJKLMNOP";
+        let salt = [3u8; 16];
+        let salted_hash = {
+            use sha2::{Digest, Sha256};
+            hex::encode(Sha256::digest([&salt[..], code.as_bytes()].concat()))
+        };
+        database
+            .get_connection()
+            .unwrap()
+            .execute(
+                "INSERT INTO claim_token (id, token_selector, token_salt, token_hash, memory_id, organization_did, expiry_timestamp, created_timestamp, updated_on, token_peppered)
+                 VALUES ('legacy', ?1, ?2, ?3, ?4, 'did:plc:org', '2999-01-01T00:00:00Z', '', '', 0)",
+                (ClaimToken::selector(code), hex::encode(salt), &salted_hash, &memory.id),
+            )
+            .unwrap();
+
+        assert_eq!(database.hash_claim_tokens().unwrap(), 1);
+        assert_eq!(database.hash_claim_tokens().unwrap(), 0);
+        let legacy = TokenId::parse("legacy").unwrap();
+        let stored = database.get_claim_token(&legacy).unwrap().unwrap();
+        assert_ne!(stored.token_hash.as_deref(), Some(salted_hash.as_str()));
+        let found = database.get_claim_token_by_token(code).unwrap();
+        assert_eq!(found.map(|found| found.id), Some(legacy));
+        remove_database(&path);
     }
 }
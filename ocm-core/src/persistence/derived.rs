@@ -0,0 +1,349 @@
+use crate::config::DerivedMemoriesConfig;
+use crate::core::error::{OcmError, Result};
+use crate::core::ids::MemoryId;
+use crate::core::memory_types::{
+    MemoryTypeHandler, MemoryTypeRegistry, MergeStrategy, RenderMetadata,
+};
+use crate::core::models::{
+    DerivedMemoryData, DerivedSource, SignedMemory, DERIVED_MEMORY_TYPE_PREFIX, MEMORY_STORED_EVENT,
+};
+use crate::identity::plc::PlcIdentity;
+use crate::persistence::database::Database;
+use crate::supervisor::{RestartPolicy, TaskSupervisor};
+use std::collections::{BTreeSet, HashMap};
+use std::sync::Arc;
+
+const EVENT_BATCH: usize = 100;
+
+/// A memory type computed from other memories, one value per key
+pub trait Derivation: Send + Sync {
+    fn name(&self) -> &str;
+
+    /// The derived memory type; must start with `derived:`
+    fn memory_type(&self) -> &str;
+
+    fn source_types(&self) -> Vec<String>;
+
+    /// Which value a source memory contributes to, or None if it contributes to none
+    fn key(&self, source: &SignedMemory) -> Option<String>;
+
+    fn compute(&self, key: &str, sources: &[SignedMemory]) -> Result<serde_json::Value>;
+}
+
+/// Days attended per individual, from attendance memories with `individual_id` and `date`
+pub struct AttendanceTotal;
+
+fn memory_field(memory: &SignedMemory, field: &str) -> Option<String> {
+    serde_json::from_str::<serde_json::Value>(&memory.memory_data)
+        .ok()?
+        .get(field)?
+        .as_str()
+        .map(str::to_string)
+}
+
+impl Derivation for AttendanceTotal {
+    fn name(&self) -> &str {
+        "attendance_total"
+    }
+
+    fn memory_type(&self) -> &str {
+        "derived:attendance_total"
+    }
+
+    fn source_types(&self) -> Vec<String> {
+        vec!["attendance".to_string()]
+    }
+
+    fn key(&self, source: &SignedMemory) -> Option<String> {
+        memory_field(source, "individual_id")
+    }
+
+    fn compute(&self, key: &str, sources: &[SignedMemory]) -> Result<serde_json::Value> {
+        // Several memories for the same day count once
+        let days: BTreeSet<String> = sources
+            .iter()
+            .map(|memory| {
+                memory_field(memory, "date")
+                    .unwrap_or_else(|| memory.timestamp.chars().take(10).collect())
+            })
+            .collect();
+        Ok(serde_json::json!({
+            "individual_id": key,
+            "total_days": days.len(),
+        }))
+    }
+}
+
+/// Registry entry for a derived type: replaced wholesale on recomputation
+struct DerivedType {
+    memory_type: String,
+    derivation: String,
+}
+
+impl MemoryTypeHandler for DerivedType {
+    fn memory_type(&self) -> &str {
+        &self.memory_type
+    }
+
+    fn validate(&self, memory_data: &str) -> Result<()> {
+        serde_json::from_str::<DerivedMemoryData>(memory_data)
+            .map(|_| ())
+            .map_err(|e| OcmError::Validation(format!("Not a derived memory: {}", e)))
+    }
+
+    fn render(&self) -> RenderMetadata {
+        RenderMetadata {
+            display_name: format!("{} (derived)", self.derivation),
+            summary_fields: vec!["key".to_string()],
+            contains_pii: false,
+        }
+    }
+
+    fn indexed_fields(&self) -> Vec<String> {
+        vec!["key".to_string()]
+    }
+
+    fn merge_strategy(&self) -> MergeStrategy {
+        MergeStrategy::LastWriterWins
+    }
+}
+
+/// Keeps derived memories current: each is recomputed, re-signed by this node and
+/// re-broadcast whenever the set or content of its source memories changes
+pub struct DerivedMemoryEngine {
+    db: Arc<Database>,
+    memory_types: Arc<MemoryTypeRegistry>,
+    signer: PlcIdentity,
+    config: DerivedMemoriesConfig,
+    derivations: HashMap<String, Arc<dyn Derivation>>,
+}
+
+impl DerivedMemoryEngine {
+    pub fn new(
+        db: Arc<Database>,
+        memory_types: Arc<MemoryTypeRegistry>,
+        signer: PlcIdentity,
+        config: DerivedMemoriesConfig,
+    ) -> Self {
+        Self {
+            db,
+            memory_types,
+            signer,
+            config,
+            derivations: HashMap::new(),
+        }
+    }
+
+    /// Add a derivation and register its memory type
+    pub fn with_derivation(mut self, derivation: Arc<dyn Derivation>) -> Result<Self> {
+        if !derivation
+            .memory_type()
+            .starts_with(DERIVED_MEMORY_TYPE_PREFIX)
+        {
+            return Err(OcmError::Validation(format!(
+                "Derived memory type {} must start with {}",
+                derivation.memory_type(),
+                DERIVED_MEMORY_TYPE_PREFIX
+            )));
+        }
+
+        let handler = DerivedType {
+            memory_type: derivation.memory_type().to_string(),
+            derivation: derivation.name().to_string(),
+        };
+        self.db.index_memory_fields(&handler.indexed_fields())?;
+        self.memory_types.register(Arc::new(handler));
+        self.derivations
+            .insert(derivation.name().to_string(), derivation);
+        Ok(self)
+    }
+
+    fn sources(&self, derivation: &dyn Derivation, key: &str) -> Result<Vec<SignedMemory>> {
+        let mut sources = Vec::new();
+        for source_type in derivation.source_types() {
+            for memory in self.db.list_memories_by_type(&source_type)? {
+                // Derived memories never feed other derivations as if they were facts
+                if !memory.is_derived() && derivation.key(&memory).as_deref() == Some(key) {
+                    sources.push(memory);
+                }
+            }
+        }
+        sources.sort_by(|a, b| a.id.cmp(&b.id));
+        Ok(sources)
+    }
+
+    /// Recompute one value; returns the derived memory's ID if it had to change
+    pub fn recompute(&self, derivation: &dyn Derivation, key: &str) -> Result<Option<MemoryId>> {
+        let sources = self.sources(derivation, key)?;
+        let source_refs: Vec<DerivedSource> = sources
+            .iter()
+            .map(|memory| DerivedSource {
+                memory_id: memory.id.clone(),
+                content_hash: memory.content_hash.clone(),
+            })
+            .collect();
+
+        let existing = match self.db.get_memory_derivation(derivation.name(), key)? {
+            Some(memory_id) => self.db.get_signed_memory(&memory_id)?,
+            None => None,
+        };
+        if let Some(existing) = &existing {
+            let current = serde_json::from_str::<DerivedMemoryData>(&existing.memory_data);
+            if current.is_ok_and(|data| data.sources == source_refs) {
+                return Ok(None);
+            }
+        }
+
+        let memory_data = serde_json::to_string(&DerivedMemoryData {
+            derivation: derivation.name().to_string(),
+            key: key.to_string(),
+            sources: source_refs.clone(),
+            value: derivation.compute(key, &sources)?,
+        })?;
+        let mut memory = self.memory_types.create_memory(
+            &self.signer.did,
+            derivation.memory_type(),
+            &memory_data,
+        )?;
+        // A recomputation is a new version of the same memory, so peers replace it
+        if let Some(existing) = &existing {
+            memory.id = existing.id.clone();
+            memory.timestamp = existing.timestamp.clone();
+        }
+        self.signer
+            .sign_memory(&mut memory)
+            .map_err(|e| OcmError::Cryptography(e.to_string()))?;
+
+        match existing {
+            Some(_) => self.db.update_outbound_signed_memory(&memory)?,
+            None => self.db.create_outbound_signed_memory(&memory)?,
+        }
+        self.db
+            .record_memory_derivation(derivation.name(), key, &memory.id, &source_refs)?;
+        Ok(Some(memory.id))
+    }
+
+    /// Recompute everything a changed, added or removed source memory affects:
+    /// the value it now contributes to and any value it was previously part of
+    pub fn source_changed(&self, memory_id: &MemoryId) -> Result<usize> {
+        let mut affected: BTreeSet<(String, String)> = self
+            .db
+            .list_derivations_by_source(memory_id)?
+            .into_iter()
+            .collect();
+
+        if let Some(memory) = self.db.get_signed_memory(memory_id)? {
+            if memory.is_derived() {
+                return Ok(0);
+            }
+            for derivation in self.derivations.values() {
+                if !derivation.source_types().contains(&memory.memory_type) {
+                    continue;
+                }
+                if let Some(key) = derivation.key(&memory) {
+                    affected.insert((derivation.name().to_string(), key));
+                }
+            }
+        }
+
+        let mut recomputed = 0;
+        for (name, key) in affected {
+            let Some(derivation) = self.derivations.get(&name) else {
+                continue;
+            };
+            if self.recompute(derivation.as_ref(), &key)?.is_some() {
+                recomputed += 1;
+            }
+        }
+        Ok(recomputed)
+    }
+
+    /// Recompute every value from the memories currently held
+    pub fn recompute_all(&self) -> Result<usize> {
+        let mut recomputed = 0;
+        for derivation in self.derivations.values() {
+            let mut keys = BTreeSet::new();
+            for source_type in derivation.source_types() {
+                for memory in self.db.list_memories_by_type(&source_type)? {
+                    if let Some(key) = derivation.key(&memory) {
+                        keys.insert(key);
+                    }
+                }
+            }
+            for key in keys {
+                if self.recompute(derivation.as_ref(), &key)?.is_some() {
+                    recomputed += 1;
+                }
+            }
+        }
+        Ok(recomputed)
+    }
+
+    /// Catch up once, then follow the activity feed and recompute as sources are stored
+    pub fn start(self: Arc<Self>, supervisor: &TaskSupervisor) {
+        if !self.config.enabled {
+            return;
+        }
+        let interval_ms = self.config.poll_interval_ms;
+
+        supervisor.spawn("derived_memories", RestartPolicy::Always, move || {
+            let service = self.clone();
+            async move {
+                let mut cursor = match service.db.latest_activity_event_id() {
+                    Ok(event_id) => event_id,
+                    Err(e) => {
+                        eprintln!("❌ Derived memories cannot read the activity feed: {}", e);
+                        return;
+                    }
+                };
+                match service.recompute_all() {
+                    Ok(0) => {}
+                    Ok(count) => println!("🧮 Recomputed {} derived memories", count),
+                    Err(e) => eprintln!("⚠️  Derived memory recomputation failed: {}", e),
+                }
+
+                let mut interval =
+                    tokio::time::interval(tokio::time::Duration::from_millis(interval_ms));
+                loop {
+                    interval.tick().await;
+
+                    let events =
+                        match service
+                            .db
+                            .list_activity_events_after(cursor, None, None, EVENT_BATCH)
+                        {
+                            Ok(events) => events,
+                            Err(e) => {
+                                eprintln!("⚠️  Derived memories failed to read events: {}", e);
+                                continue;
+                            }
+                        };
+                    let Some(last) = events.last() else {
+                        continue;
+                    };
+                    cursor = last.event_id;
+
+                    for event in &events {
+                        if event.event_type != MEMORY_STORED_EVENT {
+                            continue;
+                        }
+                        let Some(memory_id) = &event.memory_id else {
+                            continue;
+                        };
+                        match service.source_changed(memory_id) {
+                            Ok(0) => {}
+                            Ok(count) => println!(
+                                "🧮 Recomputed {} derived memories after {} changed",
+                                count, memory_id
+                            ),
+                            Err(e) => eprintln!(
+                                "⚠️  Failed to recompute memories derived from {}: {}",
+                                memory_id, e
+                            ),
+                        }
+                    }
+                }
+            }
+        });
+    }
+}
@@ -0,0 +1,241 @@
+use crate::core::error::{OcmError, Result};
+use crate::core::models::{Individual, SignedMemory};
+use crate::core::redact::SENSITIVE_FIELDS;
+use crate::persistence::database::Database;
+use arrow_array::{ArrayRef, RecordBatch, StringArray, TimestampMicrosecondArray, UInt32Array};
+use arrow_schema::{DataType, Field, Schema, SchemaRef, TimeUnit};
+use parquet::arrow::ArrowWriter;
+use std::collections::HashSet;
+use std::path::Path;
+use std::sync::Arc;
+
+/// Rows per Arrow record batch and Parquet row group
+const EXPORT_BATCH_ROWS: usize = 8192;
+
+/// Memory type under which attendance is recorded
+pub const ATTENDANCE_MEMORY_TYPE: &str = "attendance";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExportTable {
+    SignedMemory,
+    Individual,
+    Attendance, // Signed memories of type "attendance"
+}
+
+/// Row filter and redaction applied to an export
+#[derive(Debug, Clone, Default)]
+pub struct ExportFilter {
+    pub memory_type: Option<String>, // Ignored for Individual and Attendance
+    pub did: Option<String>,
+    pub since: Option<String>, // RFC 3339; memories older than this are skipped
+    pub redacted_fields: HashSet<String>, // Columns written as nulls
+}
+
+impl ExportFilter {
+    /// Redact every column named in the logging layer's sensitive field list
+    pub fn redact_sensitive(mut self) -> Self {
+        self.redacted_fields
+            .extend(SENSITIVE_FIELDS.iter().map(|field| field.to_string()));
+        self
+    }
+
+    fn is_redacted(&self, field: &str) -> bool {
+        self.redacted_fields.contains(field)
+    }
+}
+
+impl Database {
+    /// Arrow record batches for a table, at most EXPORT_BATCH_ROWS rows each
+    pub fn export_record_batches(
+        &self,
+        table: ExportTable,
+        filter: &ExportFilter,
+    ) -> Result<Vec<RecordBatch>> {
+        match table {
+            ExportTable::Individual => {
+                let individuals = self.list_individuals()?;
+                individuals
+                    .chunks(EXPORT_BATCH_ROWS)
+                    .map(|chunk| individual_batch(chunk, filter))
+                    .collect()
+            }
+            ExportTable::SignedMemory | ExportTable::Attendance => {
+                let memory_type = match table {
+                    ExportTable::Attendance => Some(ATTENDANCE_MEMORY_TYPE),
+                    _ => filter.memory_type.as_deref(),
+                };
+                let memories = filter_memories(self.list_signed_memories()?, memory_type, filter)?;
+                memories
+                    .chunks(EXPORT_BATCH_ROWS)
+                    .map(|chunk| memory_batch(chunk, filter))
+                    .collect()
+            }
+        }
+    }
+
+    /// Write a table to a Snappy-compressed Parquet file, returning the number of rows
+    pub fn export_parquet(
+        &self,
+        table: ExportTable,
+        path: &Path,
+        filter: &ExportFilter,
+    ) -> Result<usize> {
+        let batches = self.export_record_batches(table, filter)?;
+        let schema = match table {
+            ExportTable::Individual => individual_schema(),
+            ExportTable::SignedMemory | ExportTable::Attendance => memory_schema(),
+        };
+
+        let file = std::fs::File::create(path)?;
+        let properties = parquet::file::properties::WriterProperties::builder()
+            .set_compression(parquet::basic::Compression::SNAPPY)
+            .set_max_row_group_size(EXPORT_BATCH_ROWS)
+            .build();
+        let mut writer =
+            ArrowWriter::try_new(file, schema, Some(properties)).map_err(export_error)?;
+
+        let mut rows = 0;
+        for batch in &batches {
+            writer.write(batch).map_err(export_error)?;
+            rows += batch.num_rows();
+        }
+        writer.close().map_err(export_error)?;
+
+        println!("📦 Exported {} rows to {}", rows, path.display());
+        Ok(rows)
+    }
+}
+
+fn filter_memories(
+    memories: Vec<SignedMemory>,
+    memory_type: Option<&str>,
+    filter: &ExportFilter,
+) -> Result<Vec<SignedMemory>> {
+    let since = filter
+        .since
+        .as_deref()
+        .map(chrono::DateTime::parse_from_rfc3339)
+        .transpose()
+        .map_err(|e| OcmError::Validation(format!("Invalid export start time: {}", e)))?;
+
+    Ok(memories
+        .into_iter()
+        .filter(|m| memory_type.map_or(true, |t| m.memory_type == t))
+        .filter(|m| filter.did.as_deref().map_or(true, |d| m.did == d))
+        .filter(|m| match since {
+            Some(since) => chrono::DateTime::parse_from_rfc3339(&m.timestamp)
+                .map(|timestamp| timestamp >= since)
+                .unwrap_or(false),
+            None => true,
+        })
+        .collect())
+}
+
+fn memory_schema() -> SchemaRef {
+    Arc::new(Schema::new(vec![
+        Field::new("id", DataType::Utf8, false),
+        Field::new("did", DataType::Utf8, true),
+        Field::new("memory_type", DataType::Utf8, false),
+        Field::new("memory_data", DataType::Utf8, true),
+        Field::new("content_hash", DataType::Utf8, false),
+        Field::new("signature", DataType::Utf8, true),
+        Field::new("timestamp", utc_timestamp(), true),
+        Field::new("updated_on", utc_timestamp(), true),
+        Field::new("co_signer_count", DataType::UInt32, false),
+    ]))
+}
+
+fn individual_schema() -> SchemaRef {
+    Arc::new(Schema::new(vec![
+        Field::new("id", DataType::Utf8, false),
+        Field::new("first_name", DataType::Utf8, true),
+        Field::new("middle_name", DataType::Utf8, true),
+        Field::new("last_name", DataType::Utf8, true),
+        Field::new("dob", DataType::Utf8, true),
+        Field::new("phone", DataType::Utf8, true),
+        Field::new("email", DataType::Utf8, true),
+        Field::new("employer", DataType::Utf8, true),
+        Field::new("updated_on", DataType::Utf8, true), // SQLite datetime(), not RFC 3339
+    ]))
+}
+
+fn utc_timestamp() -> DataType {
+    DataType::Timestamp(TimeUnit::Microsecond, Some("UTC".into()))
+}
+
+fn memory_batch(memories: &[SignedMemory], filter: &ExportFilter) -> Result<RecordBatch> {
+    let text = |name: &str, value: fn(&SignedMemory) -> &str| -> ArrayRef {
+        let redacted = filter.is_redacted(name);
+        Arc::new(StringArray::from_iter(
+            memories.iter().map(|m| (!redacted).then(|| value(m))),
+        ))
+    };
+    let timestamp = |name: &str, value: fn(&SignedMemory) -> &str| -> ArrayRef {
+        let redacted = filter.is_redacted(name);
+        let micros = memories.iter().map(|m| {
+            if redacted {
+                return None;
+            }
+            chrono::DateTime::parse_from_rfc3339(value(m))
+                .ok()
+                .map(|t| t.timestamp_micros())
+        });
+        Arc::new(TimestampMicrosecondArray::from_iter(micros).with_timezone("UTC"))
+    };
+
+    // Columns the schema declares non-nullable are never redacted
+    let columns: Vec<ArrayRef> = vec![
+        Arc::new(StringArray::from_iter_values(
+            memories.iter().map(|m| m.id.as_str()),
+        )),
+        text("did", |m| m.did.as_str()),
+        Arc::new(StringArray::from_iter_values(
+            memories.iter().map(|m| m.memory_type.as_str()),
+        )),
+        text("memory_data", |m| m.memory_data.as_str()),
+        Arc::new(StringArray::from_iter_values(
+            memories.iter().map(|m| m.content_hash.as_str()),
+        )),
+        text("signature", |m| m.signature.as_str()),
+        timestamp("timestamp", |m| m.timestamp.as_str()),
+        timestamp("updated_on", |m| m.updated_on.as_str()),
+        Arc::new(UInt32Array::from_iter_values(
+            memories.iter().map(|m| m.co_signatures.len() as u32),
+        )),
+    ];
+
+    RecordBatch::try_new(memory_schema(), columns).map_err(export_error)
+}
+
+fn individual_batch(individuals: &[Individual], filter: &ExportFilter) -> Result<RecordBatch> {
+    let text = |name: &str, value: fn(&Individual) -> Option<&str>| -> ArrayRef {
+        let redacted = filter.is_redacted(name);
+        Arc::new(StringArray::from_iter(individuals.iter().map(|i| {
+            if redacted {
+                None
+            } else {
+                value(i)
+            }
+        })))
+    };
+
+    let columns: Vec<ArrayRef> = vec![
+        Arc::new(StringArray::from_iter_values(
+            individuals.iter().map(|i| &i.id),
+        )),
+        text("first_name", |i| Some(i.first_name.as_str())),
+        text("middle_name", |i| i.middle_name.as_deref()),
+        text("last_name", |i| Some(i.last_name.as_str())),
+        text("dob", |i| i.dob.as_deref()),
+        text("phone", |i| i.phone.as_deref()),
+        text("email", |i| i.email.as_deref()),
+        text("employer", |i| i.employer.as_deref()),
+        text("updated_on", |i| Some(i.updated_on.as_str())),
+    ];
+
+    RecordBatch::try_new(individual_schema(), columns).map_err(export_error)
+}
+
+fn export_error(err: impl std::fmt::Display) -> OcmError {
+    OcmError::OperationFailed(format!("Export failed: {}", err))
+}
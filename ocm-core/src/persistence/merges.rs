@@ -0,0 +1,261 @@
+use crate::core::error::{OcmError, Result};
+use crate::core::ids::MemoryId;
+use crate::core::models::{
+    MemoryMergeData, MergeSource, SignedMemory, MEMORY_MERGE_MEMORY_TYPE, MEMORY_STORED_EVENT,
+};
+use crate::identity::plc::PlcIdentity;
+use crate::persistence::database::Database;
+use crate::supervisor::{RestartPolicy, TaskSupervisor};
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Arc;
+
+const EVENT_BATCH: usize = 100;
+const POLL_INTERVAL_MS: u64 = 1000;
+
+/// The consolidated memory of a merge and the signed record of where each field came from
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct MemoryMerge {
+    pub merged: SignedMemory,
+    pub record: SignedMemory,
+}
+
+/// Consolidates several memories of one DID, such as the individual records a parent
+/// claimed from different organizations, into one. The originals are archived as
+/// superseded here and, through the broadcast memory_merge record, on every peer
+pub struct MergeService {
+    db: Arc<Database>,
+    signer: PlcIdentity,
+}
+
+impl MergeService {
+    pub fn new(db: Arc<Database>, signer: PlcIdentity) -> Self {
+        Self { db, signer }
+    }
+
+    /// Merge the signer's memories. Each field takes the first non-empty value in
+    /// `memory_ids` order unless `choices` names the memory to take it from
+    pub fn merge(
+        &self,
+        memory_ids: &[MemoryId],
+        choices: &HashMap<String, MemoryId>,
+    ) -> Result<MemoryMerge> {
+        merge_memories(&self.db, &self.signer, memory_ids, choices)
+    }
+
+    /// Archive the sources of a merge record received from anywhere. Only memories by
+    /// the record's author whose content still matches the record are superseded
+    pub fn apply(&self, memory: &SignedMemory) -> Result<bool> {
+        if memory.memory_type != MEMORY_MERGE_MEMORY_TYPE {
+            return Ok(false);
+        }
+        let data: MemoryMergeData = serde_json::from_str(&memory.memory_data)
+            .map_err(|e| OcmError::Validation(format!("Not a memory merge: {}", e)))?;
+
+        let mut source_ids = Vec::new();
+        for source in &data.sources {
+            match self.db.get_signed_memory(&source.memory_id)? {
+                Some(held)
+                    if held.did == memory.did && held.content_hash == source.content_hash =>
+                {
+                    source_ids.push(source.memory_id.clone())
+                }
+                Some(_) => eprintln!(
+                    "⚠️  Merge {} names memory {} it cannot supersede",
+                    memory.id, source.memory_id
+                ),
+                None => {}
+            }
+        }
+        let superseded =
+            self.db
+                .apply_memory_merge(&data.merged_memory_id, &memory.id, &source_ids)?;
+        Ok(superseded > 0)
+    }
+
+    /// Apply merges as they sync in
+    pub fn start(self: Arc<Self>, supervisor: &TaskSupervisor) {
+        supervisor.spawn("merges", RestartPolicy::Always, move || {
+            let service = self.clone();
+            async move {
+                let mut cursor = match service.db.latest_activity_event_id() {
+                    Ok(event_id) => event_id,
+                    Err(e) => {
+                        eprintln!("❌ Merges cannot read the activity feed: {}", e);
+                        return;
+                    }
+                };
+                let mut interval =
+                    tokio::time::interval(tokio::time::Duration::from_millis(POLL_INTERVAL_MS));
+
+                loop {
+                    interval.tick().await;
+
+                    let events = match service.db.list_activity_events_after(
+                        cursor,
+                        None,
+                        Some(MEMORY_MERGE_MEMORY_TYPE),
+                        EVENT_BATCH,
+                    ) {
+                        Ok(events) => events,
+                        Err(e) => {
+                            eprintln!("⚠️  Merges failed to read events: {}", e);
+                            continue;
+                        }
+                    };
+                    let Some(last) = events.last() else {
+                        continue;
+                    };
+                    cursor = last.event_id;
+
+                    for event in events
+                        .iter()
+                        .filter(|event| event.event_type == MEMORY_STORED_EVENT)
+                    {
+                        let Some(memory_id) = &event.memory_id else {
+                            continue;
+                        };
+                        let applied =
+                            service.db.get_signed_memory(memory_id).and_then(
+                                |memory| match memory {
+                                    Some(memory) => service.apply(&memory),
+                                    None => Ok(false),
+                                },
+                            );
+                        if let Err(e) = applied {
+                            eprintln!("⚠️  Failed to apply memory merge {}: {}", memory_id, e);
+                        }
+                    }
+                }
+            }
+        });
+    }
+}
+
+/// Merge `memory_ids`, all of one type and all authored by `signer`, into one memory
+/// signed by `signer`. The sources are archived and a memory_merge record with each
+/// field's source memory is stored and queued for peers alongside the merged memory
+pub fn merge_memories(
+    db: &Database,
+    signer: &PlcIdentity,
+    memory_ids: &[MemoryId],
+    choices: &HashMap<String, MemoryId>,
+) -> Result<MemoryMerge> {
+    if memory_ids.len() < 2 {
+        return Err(OcmError::Validation(
+            "A merge needs at least two memories".to_string(),
+        ));
+    }
+
+    let mut sources: Vec<(SignedMemory, serde_json::Map<String, serde_json::Value>)> = Vec::new();
+    for (i, id) in memory_ids.iter().enumerate() {
+        if memory_ids[..i].contains(id) {
+            return Err(OcmError::Validation(format!(
+                "Memory {} is listed twice",
+                id
+            )));
+        }
+        let memory = db
+            .get_signed_memory(id)?
+            .ok_or_else(|| OcmError::NotFound(format!("Memory {}", id)))?;
+        if memory.did != signer.did {
+            return Err(OcmError::Validation(format!(
+                "Memory {} belongs to another DID",
+                id
+            )));
+        }
+        if memory.is_tombstone() || memory.is_derived() {
+            return Err(OcmError::Validation(format!(
+                "Memory {} is erased or derived and cannot be merged",
+                id
+            )));
+        }
+        if sources
+            .first()
+            .is_some_and(|(first, _)| first.memory_type != memory.memory_type)
+        {
+            return Err(OcmError::Validation(
+                "Only memories of one type can be merged".to_string(),
+            ));
+        }
+        let fields: serde_json::Map<String, serde_json::Value> =
+            serde_json::from_str(&memory.memory_data)
+                .map_err(|_| OcmError::Validation(format!("Memory {} is not a JSON object", id)))?;
+        sources.push((memory, fields));
+    }
+    for (field, memory_id) in choices {
+        if !sources
+            .iter()
+            .any(|(m, f)| &m.id == memory_id && f.contains_key(field))
+        {
+            return Err(OcmError::Validation(format!(
+                "Memory {} has no field {} to take",
+                memory_id, field
+            )));
+        }
+    }
+
+    let mut consolidated = serde_json::Map::new();
+    let mut provenance = BTreeMap::new();
+    for (_, fields) in &sources {
+        for field in fields.keys() {
+            if consolidated.contains_key(field) {
+                continue;
+            }
+            let chosen = match choices.get(field) {
+                Some(memory_id) => sources.iter().find(|(m, _)| &m.id == memory_id),
+                None => sources
+                    .iter()
+                    .find(|(_, f)| f.get(field).is_some_and(|v| !is_empty_value(v)))
+                    .or_else(|| sources.iter().find(|(_, f)| f.contains_key(field))),
+            };
+            if let Some((memory, fields)) = chosen {
+                consolidated.insert(field.clone(), fields[field].clone());
+                provenance.insert(field.clone(), memory.id.clone());
+            }
+        }
+    }
+
+    let memory_type = sources[0].0.memory_type.clone();
+    let mut merged = SignedMemory::new(
+        &signer.did,
+        &memory_type,
+        &serde_json::Value::Object(consolidated).to_string(),
+    );
+    signer
+        .sign_memory(&mut merged)
+        .map_err(|e| OcmError::Cryptography(e.to_string()))?;
+
+    let data = MemoryMergeData {
+        merged_memory_id: merged.id.clone(),
+        memory_type,
+        sources: sources
+            .iter()
+            .map(|(m, _)| MergeSource {
+                memory_id: m.id.clone(),
+                content_hash: m.content_hash.clone(),
+            })
+            .collect(),
+        provenance,
+        merged_at: chrono::Utc::now().to_rfc3339(),
+    };
+    let mut record = SignedMemory::new(
+        &signer.did,
+        MEMORY_MERGE_MEMORY_TYPE,
+        &serde_json::to_string(&data)?,
+    );
+    signer
+        .sign_memory(&mut record)
+        .map_err(|e| OcmError::Cryptography(e.to_string()))?;
+
+    db.merge_memories(&merged, &record, memory_ids)?;
+    println!("🔗 Merged {} memories into {}", memory_ids.len(), merged.id);
+    Ok(MemoryMerge { merged, record })
+}
+
+fn is_empty_value(value: &serde_json::Value) -> bool {
+    match value {
+        serde_json::Value::Null => true,
+        serde_json::Value::String(s) => s.is_empty(),
+        _ => false,
+    }
+}
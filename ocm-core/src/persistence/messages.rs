@@ -0,0 +1,227 @@
+use crate::core::error::{OcmError, Result};
+use crate::core::ids::{Did, MemoryId};
+use crate::core::memory_types::MAX_DIRECT_MESSAGE_BODY;
+use crate::core::models::{
+    DirectMessage, DirectMessageData, SignedMemory, DIRECT_MESSAGE_MEMORY_TYPE, MEMORY_STORED_EVENT,
+};
+use crate::core::redact::Redacted;
+use crate::identity::envelope;
+use crate::identity::plc::{PlcDirectory, PlcIdentity};
+use crate::persistence::database::Database;
+use crate::supervisor::{RestartPolicy, TaskSupervisor};
+use base64::{engine::general_purpose, Engine as _};
+use std::sync::Arc;
+use tokio::sync::Mutex;
+
+const EVENT_BATCH: usize = 100;
+const POLL_INTERVAL_MS: u64 = 1000;
+
+/// End-to-end encrypted messages between DIDs. Each message is a signed
+/// direct_message memory whose body is sealed to the recipient's and the sender's
+/// keys, so it can travel over any transport and through the relay unread
+pub struct DirectMessageService {
+    db: Arc<Database>,
+    signer: PlcIdentity,
+    directory: Mutex<PlcDirectory>,
+}
+
+impl DirectMessageService {
+    pub fn new(db: Arc<Database>, signer: PlcIdentity) -> Self {
+        Self {
+            db,
+            signer,
+            directory: Mutex::new(PlcDirectory::new()),
+        }
+    }
+
+    pub async fn send(&self, recipient_did: &Did, body: &str) -> Result<DirectMessage> {
+        if !recipient_did.starts_with("did:") {
+            return Err(OcmError::Validation(format!(
+                "{} is not a DID",
+                recipient_did
+            )));
+        }
+        if *recipient_did == self.signer.did {
+            return Err(OcmError::Validation(
+                "Messages cannot be sent to your own DID".to_string(),
+            ));
+        }
+        if body.trim().is_empty() || body.len() > MAX_DIRECT_MESSAGE_BODY {
+            return Err(OcmError::Validation(format!(
+                "Message body must be between 1 and {} bytes",
+                MAX_DIRECT_MESSAGE_BODY
+            )));
+        }
+
+        let recipient_key = self.resolve_key(recipient_did).await?;
+        // Sealed to ourselves too, so the identity's other devices can show the conversation
+        let envelope = envelope::seal(
+            body.as_bytes(),
+            &[
+                (recipient_did.clone(), recipient_key),
+                (self.signer.did.clone(), self.own_key()?),
+            ],
+        )?;
+        let data = DirectMessageData {
+            recipient_did: recipient_did.clone(),
+            envelope,
+        };
+
+        let mut memory = SignedMemory::new(
+            &self.signer.did,
+            DIRECT_MESSAGE_MEMORY_TYPE,
+            &serde_json::to_string(&data)?,
+        );
+        self.signer
+            .sign_memory(&mut memory)
+            .map_err(|e| OcmError::Cryptography(e.to_string()))?;
+        self.db.create_outbound_signed_memory(&memory)?;
+        self.apply(&memory)?;
+
+        println!("💬 Sent a message to {}", Redacted::did(recipient_did));
+        self.db
+            .get_direct_message(&memory.id)?
+            .ok_or_else(|| OcmError::NotFound(format!("Direct message {}", memory.id)))
+    }
+
+    /// Messages exchanged with `did`, oldest first
+    pub fn conversation(&self, did: &Did) -> Result<Vec<DirectMessage>> {
+        self.db.list_conversation(&self.signer.did, did)
+    }
+
+    pub fn inbox(&self, unread_only: bool) -> Result<Vec<DirectMessage>> {
+        self.db.list_inbox(&self.signer.did, unread_only)
+    }
+
+    pub fn mark_read(&self, id: &MemoryId) -> Result<DirectMessage> {
+        let message = self
+            .db
+            .get_direct_message(id)?
+            .filter(|message| message.recipient_did == self.signer.did)
+            .ok_or_else(|| OcmError::NotFound(format!("Received message {}", id)))?;
+        if message.read_at.is_some() {
+            return Ok(message);
+        }
+        self.db.mark_direct_message_read(id, &self.signer.did)?;
+        self.db
+            .get_direct_message(id)?
+            .ok_or_else(|| OcmError::NotFound(format!("Received message {}", id)))
+    }
+
+    /// Decrypt and store a direct message we sent or received; messages between
+    /// other DIDs cannot be opened and are ignored
+    pub fn apply(&self, memory: &SignedMemory) -> Result<bool> {
+        if memory.memory_type != DIRECT_MESSAGE_MEMORY_TYPE {
+            return Ok(false);
+        }
+        let data: DirectMessageData = serde_json::from_str(&memory.memory_data)
+            .map_err(|e| OcmError::Validation(format!("Not a direct message: {}", e)))?;
+        if memory.did != self.signer.did && data.recipient_did != self.signer.did {
+            return Ok(false);
+        }
+
+        let body = String::from_utf8(envelope::open(&data.envelope, &self.signer)?)
+            .map_err(|_| OcmError::Validation("Message body is not UTF-8".to_string()))?;
+        self.db.insert_direct_message(&DirectMessage {
+            id: memory.id.clone(),
+            sender_did: memory.did.clone(),
+            recipient_did: data.recipient_did,
+            body,
+            sent_at: memory.timestamp.clone(),
+            read_at: None,
+        })
+    }
+
+    async fn resolve_key(&self, did: &str) -> Result<[u8; 32]> {
+        let key = {
+            let mut directory = self.directory.lock().await;
+            directory
+                .resolve_public_key(did)
+                .await
+                .map_err(|e| e.to_string())
+        };
+        match key {
+            Ok(Some(key)) => Ok(key),
+            Ok(None) => Err(OcmError::Plc(format!(
+                "{} publishes no key to send to",
+                Redacted::did(did)
+            ))),
+            Err(e) => Err(OcmError::Plc(format!(
+                "Cannot resolve the key of {}: {}",
+                Redacted::did(did),
+                e
+            ))),
+        }
+    }
+
+    fn own_key(&self) -> Result<[u8; 32]> {
+        general_purpose::STANDARD
+            .decode(&self.signer.keypair.public_key)?
+            .try_into()
+            .map_err(|_| OcmError::Cryptography("Public key is not 32 bytes".to_string()))
+    }
+
+    /// Open direct messages as they sync in from peers and the identity's other devices
+    pub fn start(self: Arc<Self>, supervisor: &TaskSupervisor) {
+        supervisor.spawn("messages", RestartPolicy::Always, move || {
+            let service = self.clone();
+            async move {
+                let mut cursor = match service.db.latest_activity_event_id() {
+                    Ok(event_id) => event_id,
+                    Err(e) => {
+                        eprintln!("❌ Direct messages cannot read the activity feed: {}", e);
+                        return;
+                    }
+                };
+                let mut interval =
+                    tokio::time::interval(tokio::time::Duration::from_millis(POLL_INTERVAL_MS));
+
+                loop {
+                    interval.tick().await;
+
+                    let events = match service.db.list_activity_events_after(
+                        cursor,
+                        None,
+                        Some(DIRECT_MESSAGE_MEMORY_TYPE),
+                        EVENT_BATCH,
+                    ) {
+                        Ok(events) => events,
+                        Err(e) => {
+                            eprintln!("⚠️  Direct messages failed to read events: {}", e);
+                            continue;
+                        }
+                    };
+                    let Some(last) = events.last() else {
+                        continue;
+                    };
+                    cursor = last.event_id;
+
+                    for event in events
+                        .iter()
+                        .filter(|event| event.event_type == MEMORY_STORED_EVENT)
+                    {
+                        let Some(memory_id) = &event.memory_id else {
+                            continue;
+                        };
+                        let applied =
+                            service.db.get_signed_memory(memory_id).and_then(
+                                |memory| match memory {
+                                    Some(memory) => service.apply(&memory),
+                                    None => Ok(false),
+                                },
+                            );
+                        match (applied, event.did.as_deref()) {
+                            (Ok(true), Some(sender)) if sender != service.signer.did => {
+                                println!("💬 New message from {}", Redacted::did(sender))
+                            }
+                            (Ok(_), _) => {}
+                            (Err(e), _) => {
+                                eprintln!("⚠️  Failed to open direct message {}: {}", memory_id, e)
+                            }
+                        }
+                    }
+                }
+            }
+        });
+    }
+}
@@ -5,6 +5,30 @@ mod embedded {
     embed_migrations!("migrations");
 }
 
+/// Version of the newest migration built into this binary
+pub fn latest_schema_version() -> i64 {
+    embedded::migrations::runner()
+        .get_migrations()
+        .iter()
+        .map(|migration| i64::from(migration.version()))
+        .max()
+        .unwrap_or(0)
+}
+
+/// Bring the database at `db_path` up to the latest schema, creating it if needed, and
+/// hash any claim tokens still stored in the clear
+pub fn run_migrations(db_path: &str) -> Result<(), Box<dyn std::error::Error>> {
+    // refinery opens a configured path read-write only, so open it here to create it
+    let mut conn = rusqlite::Connection::open(db_path)?;
+    embedded::migrations::runner().run(&mut conn)?;
+    drop(conn);
+    let hashed = crate::persistence::database::Database::new(db_path)?.hash_claim_tokens()?;
+    if hashed > 0 {
+        println!("🔒 Hashed {} claim tokens stored in plaintext", hashed);
+    }
+    Ok(())
+}
+
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
     std::fs::create_dir_all("data")?;
@@ -1,4 +1,21 @@
+pub mod annotations;
+pub mod compression;
+pub mod contacts;
+#[cfg(test)]
+mod crash_recovery;
 pub mod database;
+pub mod derived;
+#[cfg(feature = "export")]
+pub mod export;
+pub mod merges;
+pub mod messages;
 pub mod migrations;
+pub mod quota;
+pub mod receipts;
+pub mod retention;
+pub mod snapshot;
+pub mod tags;
+pub mod tenants;
+pub mod transparency;
 
 pub use database::*;
@@ -0,0 +1,57 @@
+use crate::core::error::Result;
+use crate::core::ids::Did;
+use crate::persistence::database::Database;
+use crate::supervisor::{RestartPolicy, TaskSupervisor};
+use std::sync::Arc;
+
+const ENFORCEMENT_INTERVAL_SECS: u64 = 600;
+
+/// Keeps a light node's store within its configured number of full memories by
+/// dropping the bodies of older ones; their headers stay, and bodies are fetched
+/// from archive or full nodes when needed
+pub struct StorageQuota {
+    db: Arc<Database>,
+    max_full_memories: usize,
+    protected_did: Did, // The node's own memories always keep their bodies
+}
+
+impl StorageQuota {
+    pub fn new(db: Arc<Database>, max_full_memories: usize, protected_did: Did) -> Self {
+        Self {
+            db,
+            max_full_memories,
+            protected_did,
+        }
+    }
+
+    /// Demote memories past the quota to headers, returning how many were demoted
+    pub fn enforce(&self) -> Result<usize> {
+        self.db
+            .demote_memories_to_headers(self.max_full_memories, &self.protected_did)
+    }
+
+    /// Enforce the quota on a fixed interval in the background
+    pub fn start(self: Arc<Self>, supervisor: &TaskSupervisor) {
+        supervisor.spawn("storage_quota", RestartPolicy::Always, move || {
+            let service = self.clone();
+            async move {
+                let mut interval = tokio::time::interval(tokio::time::Duration::from_secs(
+                    ENFORCEMENT_INTERVAL_SECS,
+                ));
+
+                loop {
+                    interval.tick().await;
+
+                    match service.enforce() {
+                        Ok(0) => {}
+                        Ok(demoted) => println!(
+                            "🪶 Storage quota: kept {} memories as headers only",
+                            demoted
+                        ),
+                        Err(e) => eprintln!("Storage quota enforcement failed: {}", e),
+                    }
+                }
+            }
+        });
+    }
+}
@@ -0,0 +1,164 @@
+use crate::config::ReceiptsConfig;
+use crate::core::error::{OcmError, Result};
+use crate::core::ids::MemoryId;
+use crate::core::models::{
+    DeliveryStatus, DirectMessageData, ReceiptData, ReceiptKind, SignedMemory,
+    DIRECT_MESSAGE_MEMORY_TYPE, MEMORY_STORED_EVENT, RECEIPT_MEMORY_TYPE,
+};
+use crate::identity::plc::PlcIdentity;
+use crate::persistence::database::Database;
+use crate::supervisor::{RestartPolicy, TaskSupervisor};
+use std::sync::Arc;
+
+const EVENT_BATCH: usize = 100;
+const POLL_INTERVAL_MS: u64 = 1000;
+
+/// Signed delivery and read receipts. When a memory addressed to this identity
+/// arrives, a receipt memory goes back to its author alone; receipts from others
+/// are stored next to the memories they acknowledge
+pub struct ReceiptService {
+    db: Arc<Database>,
+    signer: PlcIdentity,
+    config: ReceiptsConfig,
+}
+
+impl ReceiptService {
+    pub fn new(db: Arc<Database>, signer: PlcIdentity, config: ReceiptsConfig) -> Self {
+        Self { db, signer, config }
+    }
+
+    /// Which DIDs have acknowledged a memory, and whether they read it
+    pub fn get_delivery_status(&self, memory_id: &MemoryId) -> Result<DeliveryStatus> {
+        if self.db.get_signed_memory(memory_id)?.is_none() {
+            return Err(OcmError::NotFound(format!("Memory {}", memory_id)));
+        }
+        self.db.get_delivery_status(memory_id)
+    }
+
+    /// Tell the author of a received memory that it was read, if read receipts are on.
+    /// Returns whether a receipt was sent
+    pub fn mark_read(&self, memory_id: &MemoryId) -> Result<bool> {
+        let memory = self
+            .db
+            .get_signed_memory(memory_id)?
+            .ok_or_else(|| OcmError::NotFound(format!("Memory {}", memory_id)))?;
+        if !self.config.read_receipts || !self.wants_receipt(&memory) {
+            return Ok(false);
+        }
+        self.acknowledge(&memory, ReceiptKind::Read)
+    }
+
+    /// Memories by other DIDs that were addressed to us, or whose type is configured
+    fn wants_receipt(&self, memory: &SignedMemory) -> bool {
+        if memory.did == self.signer.did {
+            return false;
+        }
+        if memory.memory_type == DIRECT_MESSAGE_MEMORY_TYPE {
+            return serde_json::from_str::<DirectMessageData>(&memory.memory_data)
+                .is_ok_and(|data| data.recipient_did == self.signer.did);
+        }
+        self.config
+            .memory_types
+            .iter()
+            .any(|memory_type| memory_type == &memory.memory_type)
+    }
+
+    fn acknowledge(&self, memory: &SignedMemory, kind: ReceiptKind) -> Result<bool> {
+        if self.db.has_receipt(&memory.id, &self.signer.did, kind)? {
+            return Ok(false);
+        }
+        let data = ReceiptData {
+            memory_id: memory.id.clone(),
+            memory_did: memory.did.clone(),
+            kind,
+        };
+        let mut receipt = SignedMemory::new(
+            &self.signer.did,
+            RECEIPT_MEMORY_TYPE,
+            &serde_json::to_string(&data)?,
+        );
+        self.signer
+            .sign_memory(&mut receipt)
+            .map_err(|e| OcmError::Cryptography(e.to_string()))?;
+        self.db.create_outbound_signed_memory(&receipt)?;
+        self.apply(&receipt)
+    }
+
+    /// Store a receipt for a memory we wrote, or one we sent from another device
+    pub fn apply(&self, memory: &SignedMemory) -> Result<bool> {
+        if memory.memory_type != RECEIPT_MEMORY_TYPE {
+            return Ok(false);
+        }
+        let data: ReceiptData = serde_json::from_str(&memory.memory_data)
+            .map_err(|e| OcmError::Validation(format!("Not a receipt: {}", e)))?;
+        if memory.did != self.signer.did && data.memory_did != self.signer.did {
+            return Ok(false);
+        }
+        self.db
+            .record_receipt(&data, &memory.did, &memory.timestamp, &memory.id)
+    }
+
+    /// Acknowledge memories and store receipts as they sync in
+    pub fn start(self: Arc<Self>, supervisor: &TaskSupervisor) {
+        supervisor.spawn("receipts", RestartPolicy::Always, move || {
+            let service = self.clone();
+            async move {
+                let mut cursor = match service.db.latest_activity_event_id() {
+                    Ok(event_id) => event_id,
+                    Err(e) => {
+                        eprintln!("❌ Receipts cannot read the activity feed: {}", e);
+                        return;
+                    }
+                };
+                let mut interval =
+                    tokio::time::interval(tokio::time::Duration::from_millis(POLL_INTERVAL_MS));
+
+                loop {
+                    interval.tick().await;
+
+                    let events =
+                        match service
+                            .db
+                            .list_activity_events_after(cursor, None, None, EVENT_BATCH)
+                        {
+                            Ok(events) => events,
+                            Err(e) => {
+                                eprintln!("⚠️  Receipts failed to read events: {}", e);
+                                continue;
+                            }
+                        };
+                    let Some(last) = events.last() else {
+                        continue;
+                    };
+                    cursor = last.event_id;
+
+                    for event in events
+                        .iter()
+                        .filter(|event| event.event_type == MEMORY_STORED_EVENT)
+                    {
+                        let Some(memory_id) = &event.memory_id else {
+                            continue;
+                        };
+                        let handled = service.db.get_signed_memory(memory_id).and_then(|memory| {
+                            let Some(memory) = memory else {
+                                return Ok(false);
+                            };
+                            if memory.memory_type == RECEIPT_MEMORY_TYPE {
+                                service.apply(&memory)
+                            } else if service.config.delivery_receipts
+                                && service.wants_receipt(&memory)
+                            {
+                                service.acknowledge(&memory, ReceiptKind::Delivered)
+                            } else {
+                                Ok(false)
+                            }
+                        });
+                        if let Err(e) = handled {
+                            eprintln!("⚠️  Failed to handle receipts for {}: {}", memory_id, e);
+                        }
+                    }
+                }
+            }
+        });
+    }
+}
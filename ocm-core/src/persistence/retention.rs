@@ -0,0 +1,184 @@
+use crate::config::{RetentionAction, RetentionConfig};
+use crate::core::error::{OcmError, Result};
+use crate::core::ids::MemoryId;
+use crate::core::models::{ClaimToken, ProxyMemory, SignedMemory};
+use crate::identity::plc::PlcIdentity;
+use crate::persistence::database::Database;
+use crate::supervisor::{RestartPolicy, TaskSupervisor};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+
+/// Memory type of the signed record left behind whenever retention removes a memory
+pub const RETENTION_DELETION_MEMORY_TYPE: &str = "retention_deletion";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RetentionCandidate {
+    pub memory_id: MemoryId,
+    pub memory_type: String,
+    pub content_hash: String,
+    pub age_days: i64,
+    pub action: RetentionAction,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RetentionReport {
+    pub evaluated_at: String,
+    pub dry_run: bool,
+    pub candidates: Vec<RetentionCandidate>,
+    pub deletion_memory_ids: Vec<MemoryId>, // Signed deletion memories written for applied actions
+}
+
+pub struct RetentionEngine {
+    db: Arc<Database>,
+    config: RetentionConfig,
+}
+
+impl RetentionEngine {
+    pub fn new(db: Arc<Database>, config: RetentionConfig) -> Self {
+        Self { db, config }
+    }
+
+    /// Find every memory a retention rule currently applies to, without changing anything
+    pub fn evaluate(&self) -> Result<Vec<RetentionCandidate>> {
+        let now = chrono::Utc::now();
+        let mut candidates = Vec::new();
+
+        for rule in &self.config.rules {
+            let cutoff = now - chrono::Duration::days(rule.max_age_days as i64);
+            let memories = self
+                .db
+                .list_memories_by_type_before(&rule.memory_type, &cutoff.to_rfc3339())?;
+
+            for memory in memories {
+                if rule.unclaimed_only && self.is_claimed(&memory)? {
+                    continue;
+                }
+
+                let age_days = chrono::DateTime::parse_from_rfc3339(&memory.timestamp)
+                    .map(|t| (now - t.with_timezone(&chrono::Utc)).num_days())
+                    .unwrap_or(rule.max_age_days as i64);
+
+                candidates.push(RetentionCandidate {
+                    memory_id: memory.id,
+                    memory_type: memory.memory_type,
+                    content_hash: memory.content_hash,
+                    age_days,
+                    action: rule.action.clone(),
+                });
+            }
+        }
+
+        Ok(candidates)
+    }
+
+    /// Evaluate the rules and, unless configured as a dry run, apply them.
+    /// Each applied action is recorded as a deletion memory signed by `operator`
+    pub fn run(&self, operator: &PlcIdentity) -> Result<RetentionReport> {
+        let candidates = self.evaluate()?;
+        let mut deletion_memory_ids = Vec::new();
+
+        if !self.config.dry_run {
+            for candidate in &candidates {
+                self.apply(candidate)?;
+                deletion_memory_ids.push(self.record_deletion(operator, candidate)?);
+            }
+        }
+
+        Ok(RetentionReport {
+            evaluated_at: chrono::Utc::now().to_rfc3339(),
+            dry_run: self.config.dry_run,
+            candidates,
+            deletion_memory_ids,
+        })
+    }
+
+    /// Run retention on a fixed interval in the background
+    pub fn start_scheduler(self: Arc<Self>, operator: PlcIdentity, supervisor: &TaskSupervisor) {
+        if !self.config.enabled {
+            return;
+        }
+        let interval_secs = self.config.evaluation_interval_hours * 60 * 60;
+
+        supervisor.spawn("retention", RestartPolicy::Always, move || {
+            let service = self.clone();
+            let operator = operator.clone();
+            async move {
+                let mut interval =
+                    tokio::time::interval(tokio::time::Duration::from_secs(interval_secs));
+
+                loop {
+                    interval.tick().await;
+
+                    match service.run(&operator) {
+                        Ok(report) if report.dry_run => {
+                            for candidate in &report.candidates {
+                                println!(
+                                    "🗓️  [dry run] Would {:?} {} memory {} ({} days old)",
+                                    candidate.action,
+                                    candidate.memory_type,
+                                    candidate.memory_id,
+                                    candidate.age_days
+                                );
+                            }
+                        }
+                        Ok(report) => {
+                            println!(
+                                "🗓️  Retention applied to {} memories",
+                                report.deletion_memory_ids.len()
+                            );
+                        }
+                        Err(e) => eprintln!("Retention run failed: {}", e),
+                    }
+                }
+            }
+        });
+    }
+
+    fn is_claimed(&self, memory: &SignedMemory) -> Result<bool> {
+        Ok(self
+            .db
+            .get_claim_token_by_memory(&memory.id)?
+            .map(|token| token.is_claimed())
+            .unwrap_or(false))
+    }
+
+    fn apply(&self, candidate: &RetentionCandidate) -> Result<()> {
+        match candidate.action {
+            RetentionAction::Archive => self.db.archive_signed_memory(&candidate.memory_id),
+            RetentionAction::Purge => {
+                // Unclaimed proxy records take their claim token and proxy entry with them
+                if let Some(token) = self.db.get_claim_token_by_memory(&candidate.memory_id)? {
+                    if let Some(proxy) = self.db.get_proxy_memory_by_claim_token(&token.id)? {
+                        self.db.delete::<ProxyMemory>(&proxy.id)?;
+                    }
+                    self.db.delete::<ClaimToken>(&token.id)?;
+                }
+                self.db.delete::<SignedMemory>(&candidate.memory_id)
+            }
+        }
+    }
+
+    fn record_deletion(
+        &self,
+        operator: &PlcIdentity,
+        candidate: &RetentionCandidate,
+    ) -> Result<MemoryId> {
+        let memory_data = serde_json::json!({
+            "memory_id": candidate.memory_id,
+            "memory_type": candidate.memory_type,
+            "content_hash": candidate.content_hash,
+            "action": candidate.action,
+            "age_days": candidate.age_days,
+        })
+        .to_string();
+
+        let mut deletion =
+            SignedMemory::new(&operator.did, RETENTION_DELETION_MEMORY_TYPE, &memory_data);
+        operator
+            .sign_memory(&mut deletion)
+            .map_err(|e| OcmError::Cryptography(e.to_string()))?;
+        self.db.create_signed_memory(&deletion)?;
+
+        Ok(deletion.id)
+    }
+}
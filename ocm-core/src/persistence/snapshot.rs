@@ -0,0 +1,108 @@
+use crate::core::error::{OcmError, Result};
+use crate::core::models::SignedMemory;
+use crate::identity::plc::PlcIdentity;
+use crate::persistence::database::Database;
+use crate::persistence::transparency::{leaf_hash, merkle_root};
+use serde::{Deserialize, Serialize};
+
+/// Signed description of a snapshot: which node produced it, when, and the Merkle
+/// root over the content hashes of its memories in order
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct SnapshotManifest {
+    pub source_node_id: String,
+    pub signer_did: String,
+    pub created_at: String,
+    pub memory_count: u64,
+    pub merkle_root: String, // Hex encoded, same tree layout as the transparency log
+    pub signature: String,
+}
+
+impl SnapshotManifest {
+    pub fn get_signing_payload(&self) -> String {
+        // The "snapshot" tag keeps a manifest signature from being replayed as a tree head
+        serde_json::json!({
+            "type": "snapshot",
+            "source_node_id": self.source_node_id,
+            "created_at": self.created_at,
+            "memory_count": self.memory_count,
+            "merkle_root": self.merkle_root
+        })
+        .to_string()
+    }
+}
+
+/// Checkpoint of every memory a node holds, for bootstrapping a new node without replay
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Snapshot {
+    pub manifest: SnapshotManifest,
+    pub memories: Vec<SignedMemory>,
+}
+
+impl Snapshot {
+    /// Capture the current memories and sign the manifest with the node's identity
+    pub fn create(db: &Database, identity: &PlcIdentity, source_node_id: &str) -> Result<Self> {
+        Self::from_memories(db.list_signed_memories()?, identity, source_node_id)
+    }
+
+    /// Sign a snapshot over an already selected set of memories, e.g. filtered by peer group
+    pub fn from_memories(
+        mut memories: Vec<SignedMemory>,
+        identity: &PlcIdentity,
+        source_node_id: &str,
+    ) -> Result<Self> {
+        memories.sort_by(|a, b| a.timestamp.cmp(&b.timestamp).then(a.id.cmp(&b.id)));
+
+        let mut manifest = SnapshotManifest {
+            source_node_id: source_node_id.to_string(),
+            signer_did: identity.did.to_string(),
+            created_at: chrono::Utc::now().to_rfc3339(),
+            memory_count: memories.len() as u64,
+            merkle_root: snapshot_root(&memories),
+            signature: String::new(),
+        };
+        manifest.signature = identity.sign_payload(&manifest.get_signing_payload())?;
+
+        Ok(Self { manifest, memories })
+    }
+
+    /// Check the memories against the manifest. The manifest signature is checked
+    /// separately, since that needs the signer's DID document
+    pub fn verify_contents(&self) -> Result<()> {
+        if self.memories.len() as u64 != self.manifest.memory_count {
+            return Err(OcmError::Validation(format!(
+                "Snapshot holds {} memories but its manifest declares {}",
+                self.memories.len(),
+                self.manifest.memory_count
+            )));
+        }
+
+        if let Some(memory) = self.memories.iter().find(|m| !m.verify_hash()) {
+            return Err(OcmError::Validation(format!(
+                "Snapshot memory {} does not match its content hash",
+                memory.id
+            )));
+        }
+
+        if snapshot_root(&self.memories) != self.manifest.merkle_root {
+            return Err(OcmError::Validation(
+                "Snapshot Merkle root does not match its manifest".to_string(),
+            ));
+        }
+
+        Ok(())
+    }
+
+    /// Store every memory not already held, returning how many were imported
+    pub fn import(&self, db: &Database) -> Result<usize> {
+        self.verify_contents()?;
+        db.import_signed_memories(&self.memories)
+    }
+}
+
+fn snapshot_root(memories: &[SignedMemory]) -> String {
+    let leaves: Vec<_> = memories
+        .iter()
+        .map(|memory| leaf_hash(&memory.content_hash))
+        .collect();
+    hex::encode(merkle_root(&leaves))
+}
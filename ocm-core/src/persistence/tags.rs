@@ -0,0 +1,146 @@
+use crate::core::error::{OcmError, Result};
+use crate::core::ids::MemoryId;
+use crate::core::models::{
+    normalize_tag, MemoryTagData, SignedMemory, TagCount, MEMORY_STORED_EVENT,
+    MEMORY_TAG_MEMORY_TYPE,
+};
+use crate::identity::plc::PlcIdentity;
+use crate::persistence::database::Database;
+use crate::supervisor::{RestartPolicy, TaskSupervisor};
+use std::sync::Arc;
+
+const EVENT_BATCH: usize = 100;
+const POLL_INTERVAL_MS: u64 = 1000;
+
+/// Tags on memories. Each tag or untag is a signed memory_tag memory, broadcast like any
+/// other, so tags reach peers without touching the signed payload of the tagged memory
+pub struct TagService {
+    db: Arc<Database>,
+    signer: PlcIdentity,
+}
+
+impl TagService {
+    pub fn new(db: Arc<Database>, signer: PlcIdentity) -> Self {
+        Self { db, signer }
+    }
+
+    pub fn tag(&self, memory_id: &MemoryId, tag: &str) -> Result<SignedMemory> {
+        self.record(memory_id, tag, false)
+    }
+
+    pub fn untag(&self, memory_id: &MemoryId, tag: &str) -> Result<SignedMemory> {
+        self.record(memory_id, tag, true)
+    }
+
+    fn record(&self, memory_id: &MemoryId, tag: &str, removed: bool) -> Result<SignedMemory> {
+        let tag = normalize_tag(tag).map_err(OcmError::Validation)?;
+        if self.db.get_signed_memory(memory_id)?.is_none() {
+            return Err(OcmError::NotFound(format!("Memory {}", memory_id)));
+        }
+
+        let data = MemoryTagData {
+            memory_id: memory_id.clone(),
+            tag,
+            removed,
+        };
+        let mut memory = SignedMemory::new(
+            &self.signer.did,
+            MEMORY_TAG_MEMORY_TYPE,
+            &serde_json::to_string(&data)?,
+        );
+        self.signer
+            .sign_memory(&mut memory)
+            .map_err(|e| OcmError::Cryptography(e.to_string()))?;
+        self.db.create_outbound_signed_memory(&memory)?;
+        self.apply(&memory)?;
+        Ok(memory)
+    }
+
+    /// Apply a tag operation received from anywhere; later operations win per memory and tag
+    pub fn apply(&self, memory: &SignedMemory) -> Result<bool> {
+        if memory.memory_type != MEMORY_TAG_MEMORY_TYPE {
+            return Ok(false);
+        }
+        let data: MemoryTagData = serde_json::from_str(&memory.memory_data)
+            .map_err(|e| OcmError::Validation(format!("Not a tag operation: {}", e)))?;
+        let tag = normalize_tag(&data.tag).map_err(OcmError::Validation)?;
+
+        self.db.apply_memory_tag(
+            &MemoryTagData { tag, ..data },
+            &memory.did,
+            &memory.timestamp,
+            &memory.id,
+        )
+    }
+
+    pub fn tags_of(&self, memory_id: &MemoryId) -> Result<Vec<String>> {
+        self.db.list_memory_tags(memory_id)
+    }
+
+    pub fn memories_tagged(&self, tag: &str) -> Result<Vec<SignedMemory>> {
+        let tag = normalize_tag(tag).map_err(OcmError::Validation)?;
+        self.db.list_memories_by_tag(&tag)
+    }
+
+    pub fn tags(&self) -> Result<Vec<TagCount>> {
+        self.db.list_tags()
+    }
+
+    /// Apply tag operations as they arrive from peers
+    pub fn start(self: Arc<Self>, supervisor: &TaskSupervisor) {
+        supervisor.spawn("tags", RestartPolicy::Always, move || {
+            let service = self.clone();
+            async move {
+                let mut cursor = match service.db.latest_activity_event_id() {
+                    Ok(event_id) => event_id,
+                    Err(e) => {
+                        eprintln!("❌ Tags cannot read the activity feed: {}", e);
+                        return;
+                    }
+                };
+                let mut interval =
+                    tokio::time::interval(tokio::time::Duration::from_millis(POLL_INTERVAL_MS));
+
+                loop {
+                    interval.tick().await;
+
+                    let events = match service.db.list_activity_events_after(
+                        cursor,
+                        None,
+                        Some(MEMORY_TAG_MEMORY_TYPE),
+                        EVENT_BATCH,
+                    ) {
+                        Ok(events) => events,
+                        Err(e) => {
+                            eprintln!("⚠️  Tags failed to read events: {}", e);
+                            continue;
+                        }
+                    };
+                    let Some(last) = events.last() else {
+                        continue;
+                    };
+                    cursor = last.event_id;
+
+                    for event in events
+                        .iter()
+                        .filter(|event| event.event_type == MEMORY_STORED_EVENT)
+                    {
+                        let Some(memory_id) = &event.memory_id else {
+                            continue;
+                        };
+                        let applied =
+                            service.db.get_signed_memory(memory_id).and_then(
+                                |memory| match memory {
+                                    Some(memory) => service.apply(&memory),
+                                    None => Ok(false),
+                                },
+                            );
+                        if let Err(e) = applied {
+                            eprintln!("⚠️  Failed to apply tag operation {}: {}", memory_id, e);
+                        }
+                    }
+                }
+            }
+        });
+    }
+}
@@ -0,0 +1,219 @@
+use crate::config::TenancyConfig;
+use crate::core::error::{OcmError, Result};
+use crate::core::models::{Tenant, TenantStatus, TenantUsage};
+use crate::persistence::database::Database;
+use crate::persistence::migrations::run_migrations;
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+
+const MAX_TENANT_ID_LEN: usize = 63;
+
+/// Tenant IDs become file names and URL segments: lowercase letters, digits and
+/// inner hyphens only
+pub fn validate_tenant_id(id: &str) -> Result<()> {
+    let valid = !id.is_empty()
+        && id.len() <= MAX_TENANT_ID_LEN
+        && id
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-')
+        && !id.starts_with('-')
+        && !id.ends_with('-');
+    if valid {
+        Ok(())
+    } else {
+        Err(OcmError::Validation(format!(
+            "Tenant ID must be 1-{} lowercase letters, digits or inner hyphens: {}",
+            MAX_TENANT_ID_LEN, id
+        )))
+    }
+}
+
+/// The organizations hosted on a multi-tenant node. Tenants are recorded in the host
+/// database and each keeps its data in `{directory}/{id}.db`, so no query needs a
+/// tenant filter and deleting a tenant removes its data entirely
+pub struct TenantRegistry {
+    host: Arc<Database>,
+    config: TenancyConfig,
+    open: Mutex<HashMap<String, Arc<Database>>>,
+}
+
+impl TenantRegistry {
+    pub fn new(host: Arc<Database>, config: TenancyConfig) -> Self {
+        Self {
+            host,
+            config,
+            open: Mutex::new(HashMap::new()),
+        }
+    }
+
+    fn database_path(&self, id: &str) -> PathBuf {
+        self.config.directory.join(format!("{}.db", id))
+    }
+
+    pub fn create(&self, id: &str, name: &str) -> Result<Tenant> {
+        validate_tenant_id(id)?;
+        if name.trim().is_empty() {
+            return Err(OcmError::Validation("Tenant name is required".to_string()));
+        }
+        if self.host.get_tenant(id)?.is_some() {
+            return Err(OcmError::Validation(format!(
+                "Tenant {} already exists",
+                id
+            )));
+        }
+        if self.host.list_tenants()?.len() >= self.config.max_tenants {
+            return Err(OcmError::Validation(format!(
+                "This node hosts at most {} tenants",
+                self.config.max_tenants
+            )));
+        }
+
+        std::fs::create_dir_all(&self.config.directory).map_err(|e| {
+            OcmError::OperationFailed(format!("Cannot create tenant directory: {}", e))
+        })?;
+        let path = self.database_path(id);
+        run_migrations(&path.to_string_lossy()).map_err(|e| {
+            OcmError::OperationFailed(format!("Cannot create database for tenant {}: {}", id, e))
+        })?;
+
+        let now = chrono::Utc::now().to_rfc3339();
+        let tenant = Tenant {
+            id: id.to_string(),
+            name: name.trim().to_string(),
+            status: TenantStatus::Active,
+            max_memories: None,
+            requests_per_minute: None,
+            created_at: now.clone(),
+            updated_at: now,
+        };
+        self.host.create_tenant(&tenant)?;
+        println!("🏢 Created tenant {}", id);
+        Ok(tenant)
+    }
+
+    pub fn get(&self, id: &str) -> Result<Tenant> {
+        self.host
+            .get_tenant(id)?
+            .ok_or_else(|| OcmError::NotFound(format!("Tenant {}", id)))
+    }
+
+    pub fn list(&self) -> Result<Vec<Tenant>> {
+        self.host.list_tenants()
+    }
+
+    /// Change a tenant's name or quotas; None quotas fall back to the node defaults
+    pub fn update(
+        &self,
+        id: &str,
+        name: Option<&str>,
+        max_memories: Option<u64>,
+        requests_per_minute: Option<u32>,
+    ) -> Result<Tenant> {
+        if max_memories == Some(0) || requests_per_minute == Some(0) {
+            return Err(OcmError::Validation(
+                "Tenant quotas must be positive".to_string(),
+            ));
+        }
+        let mut tenant = self.get(id)?;
+        if let Some(name) = name.filter(|name| !name.trim().is_empty()) {
+            tenant.name = name.trim().to_string();
+        }
+        tenant.max_memories = max_memories;
+        tenant.requests_per_minute = requests_per_minute;
+        self.save(tenant)
+    }
+
+    pub fn set_status(&self, id: &str, status: TenantStatus) -> Result<Tenant> {
+        let mut tenant = self.get(id)?;
+        tenant.status = status;
+        let tenant = self.save(tenant)?;
+        println!("🏢 Tenant {} is now {}", id, status.as_str());
+        Ok(tenant)
+    }
+
+    fn save(&self, mut tenant: Tenant) -> Result<Tenant> {
+        tenant.updated_at = chrono::Utc::now().to_rfc3339();
+        if !self.host.update_tenant(&tenant)? {
+            return Err(OcmError::NotFound(format!("Tenant {}", tenant.id)));
+        }
+        Ok(tenant)
+    }
+
+    /// Remove a tenant and delete its database file. Suspend first to keep the data
+    pub fn delete(&self, id: &str) -> Result<()> {
+        validate_tenant_id(id)?;
+        if !self.host.delete_tenant(id)? {
+            return Err(OcmError::NotFound(format!("Tenant {}", id)));
+        }
+        self.open
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .remove(id);
+
+        let path = self.database_path(id);
+        for file in [
+            path.clone(),
+            path.with_extension("db-wal"),
+            path.with_extension("db-shm"),
+        ] {
+            if file.exists() {
+                std::fs::remove_file(&file).map_err(|e| {
+                    OcmError::OperationFailed(format!("Cannot delete {:?}: {}", file, e))
+                })?;
+            }
+        }
+        println!("🗑️  Deleted tenant {}", id);
+        Ok(())
+    }
+
+    /// The database of an active tenant, opened on first use
+    pub fn database(&self, id: &str) -> Result<Arc<Database>> {
+        let tenant = self.get(id)?;
+        if tenant.status != TenantStatus::Active {
+            return Err(OcmError::Validation(format!("Tenant {} is suspended", id)));
+        }
+        self.open_database(id)
+    }
+
+    fn open_database(&self, id: &str) -> Result<Arc<Database>> {
+        let mut open = self.open.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some(database) = open.get(id) {
+            return Ok(database.clone());
+        }
+        let database = Arc::new(Database::new(&self.database_path(id).to_string_lossy())?);
+        open.insert(id.to_string(), database.clone());
+        Ok(database)
+    }
+
+    pub fn requests_per_minute(&self, tenant: &Tenant) -> u32 {
+        tenant
+            .requests_per_minute
+            .unwrap_or(self.config.default_requests_per_minute)
+    }
+
+    pub fn usage(&self, id: &str) -> Result<TenantUsage> {
+        let tenant = self.get(id)?;
+        let memories = self.open_database(id)?.count_signed_memories()?;
+        Ok(TenantUsage {
+            tenant_id: tenant.id.clone(),
+            memories,
+            max_memories: tenant
+                .max_memories
+                .unwrap_or(self.config.default_max_memories),
+            requests_per_minute: self.requests_per_minute(&tenant),
+        })
+    }
+
+    /// Refuse writes once a tenant holds as many memories as its quota allows
+    pub fn check_write_quota(&self, id: &str) -> Result<()> {
+        let usage = self.usage(id)?;
+        if usage.memories >= usage.max_memories {
+            return Err(OcmError::Validation(format!(
+                "Tenant {} has reached its quota of {} memories",
+                id, usage.max_memories
+            )));
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,616 @@
+use crate::core::error::{OcmError, Result};
+use crate::core::ids::MemoryId;
+use crate::identity::plc::PlcIdentity;
+use crate::persistence::database::Database;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::sync::Arc;
+
+/// Append-only Merkle log of every stored memory hash (RFC 6962 tree layout)
+pub type MerkleHash = [u8; 32];
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct SignedTreeHead {
+    pub tree_size: u64,
+    pub root_hash: String, // Hex encoded Merkle root
+    pub timestamp: String,
+    pub signer_did: String,
+    pub signature: String,
+}
+
+impl SignedTreeHead {
+    pub fn get_signing_payload(&self) -> String {
+        serde_json::json!({
+            "tree_size": self.tree_size,
+            "root_hash": self.root_hash,
+            "timestamp": self.timestamp
+        })
+        .to_string()
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct InclusionProof {
+    pub leaf_index: u64,
+    pub tree_size: u64,
+    pub leaf_hash: String,
+    pub audit_path: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ConsistencyProof {
+    pub first_size: u64,
+    pub second_size: u64,
+    pub proof: Vec<String>,
+}
+
+pub struct TransparencyLog {
+    db: Arc<Database>,
+}
+
+impl TransparencyLog {
+    pub fn new(db: Arc<Database>) -> Self {
+        Self { db }
+    }
+
+    fn leaves(&self, tree_size: Option<u64>) -> Result<Vec<MerkleHash>> {
+        let leaves = self
+            .db
+            .list_transparency_leaves()?
+            .iter()
+            .map(|hex_hash| decode_hash(hex_hash))
+            .collect::<Result<Vec<_>>>()?;
+
+        match tree_size {
+            Some(size) if size as usize > leaves.len() => Err(OcmError::Validation(format!(
+                "Tree size {} exceeds log size {}",
+                size,
+                leaves.len()
+            ))),
+            Some(size) => Ok(leaves[..size as usize].to_vec()),
+            None => Ok(leaves),
+        }
+    }
+
+    pub fn tree_size(&self) -> Result<u64> {
+        Ok(self.db.list_transparency_leaves()?.len() as u64)
+    }
+
+    pub fn root_hash(&self) -> Result<String> {
+        Ok(hex::encode(merkle_root(&self.leaves(None)?)))
+    }
+
+    /// Sign the current tree head with the node's identity
+    pub fn signed_tree_head(&self, identity: &PlcIdentity) -> Result<SignedTreeHead> {
+        let leaves = self.leaves(None)?;
+        let mut sth = SignedTreeHead {
+            tree_size: leaves.len() as u64,
+            root_hash: hex::encode(merkle_root(&leaves)),
+            timestamp: chrono::Utc::now().to_rfc3339(),
+            signer_did: identity.did.to_string(),
+            signature: String::new(),
+        };
+        sth.signature = identity.sign_payload(&sth.get_signing_payload())?;
+        Ok(sth)
+    }
+
+    pub fn inclusion_proof(
+        &self,
+        memory_id: &MemoryId,
+        tree_size: Option<u64>,
+    ) -> Result<InclusionProof> {
+        let leaf_index = self.db.find_transparency_leaf(memory_id)?.ok_or_else(|| {
+            OcmError::NotFound(format!(
+                "Memory {} is not in the transparency log",
+                memory_id
+            ))
+        })?;
+        let leaves = self.leaves(tree_size)?;
+
+        if leaf_index as usize >= leaves.len() {
+            return Err(OcmError::Validation(format!(
+                "Leaf {} is outside tree size {}",
+                leaf_index,
+                leaves.len()
+            )));
+        }
+
+        Ok(InclusionProof {
+            leaf_index,
+            tree_size: leaves.len() as u64,
+            leaf_hash: hex::encode(leaves[leaf_index as usize]),
+            audit_path: inclusion_path(leaf_index as usize, &leaves)
+                .iter()
+                .map(hex::encode)
+                .collect(),
+        })
+    }
+
+    pub fn consistency_proof(&self, first_size: u64, second_size: u64) -> Result<ConsistencyProof> {
+        if first_size > second_size {
+            return Err(OcmError::Validation(
+                "First tree size must not exceed second tree size".to_string(),
+            ));
+        }
+        let leaves = self.leaves(Some(second_size))?;
+
+        Ok(ConsistencyProof {
+            first_size,
+            second_size,
+            proof: consistency_path(first_size as usize, &leaves)
+                .iter()
+                .map(hex::encode)
+                .collect(),
+        })
+    }
+}
+
+/// Tracks the latest tree head gossiped by each peer DID to detect history rewrites.
+/// A larger head is only accepted once a consistency proof shows it extends the last one
+#[derive(Debug, Default)]
+pub struct TreeHeadMonitor {
+    latest: HashMap<String, SignedTreeHead>,
+    pending: HashMap<String, PendingTreeHead>, // DID -> larger head awaiting a consistency proof
+}
+
+#[derive(Debug)]
+struct PendingTreeHead {
+    head: SignedTreeHead,
+    peer_id: String, // The peer asked for the proof
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum TreeHeadStatus {
+    First,
+    Unchanged,
+    NeedsProof { first_size: u64, second_size: u64 },
+    Grew,
+    Rewritten(String),
+}
+
+impl TreeHeadMonitor {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record a head gossiped by `peer_id`. A larger head is held back until
+    /// `prove_growth` is called with the proof that peer sends
+    pub fn observe(&mut self, sth: SignedTreeHead, peer_id: &str) -> TreeHeadStatus {
+        let Some(previous) = self.latest.get(&sth.signer_did) else {
+            self.latest.insert(sth.signer_did.clone(), sth);
+            return TreeHeadStatus::First;
+        };
+
+        if sth.tree_size < previous.tree_size {
+            return TreeHeadStatus::Rewritten(format!(
+                "tree shrank from {} to {}",
+                previous.tree_size, sth.tree_size
+            ));
+        }
+        if sth.tree_size == previous.tree_size {
+            return if sth.root_hash == previous.root_hash {
+                TreeHeadStatus::Unchanged
+            } else {
+                TreeHeadStatus::Rewritten(format!("root changed at tree size {}", sth.tree_size))
+            };
+        }
+
+        let first_size = previous.tree_size;
+        let second_size = sth.tree_size;
+        if let Some(pending) = self.pending.remove(&sth.signer_did) {
+            // The same head again only asks for the proof again, in case it was lost
+            if pending.head.tree_size != sth.tree_size || pending.head.root_hash != sth.root_hash {
+                return TreeHeadStatus::Rewritten(format!(
+                    "no consistency proof for tree size {}",
+                    pending.head.tree_size
+                ));
+            }
+        }
+        self.pending.insert(
+            sth.signer_did.clone(),
+            PendingTreeHead {
+                head: sth,
+                peer_id: peer_id.to_string(),
+            },
+        );
+        TreeHeadStatus::NeedsProof {
+            first_size,
+            second_size,
+        }
+    }
+
+    /// Accept the head held back for the DID `peer_id` was asked about if `proof` shows
+    /// it extends the last accepted head. Returns the DID and its status, or None if
+    /// no proof was asked of `peer_id` for these sizes
+    pub fn prove_growth(
+        &mut self,
+        peer_id: &str,
+        proof: &ConsistencyProof,
+    ) -> Option<(String, TreeHeadStatus)> {
+        let did = self
+            .pending
+            .iter()
+            .find(|(did, pending)| {
+                pending.peer_id == peer_id
+                    && pending.head.tree_size == proof.second_size
+                    && self.latest.get(*did).map(|head| head.tree_size) == Some(proof.first_size)
+            })
+            .map(|(did, _)| did.clone())?;
+        let pending = self.pending.remove(&did)?;
+        let previous = self.latest.get(&did)?;
+
+        if !proves_extension(previous, &pending.head, proof) {
+            return Some((
+                did,
+                TreeHeadStatus::Rewritten(format!(
+                    "consistency proof from tree size {} to {} does not verify",
+                    proof.first_size, proof.second_size
+                )),
+            ));
+        }
+        self.latest.insert(did.clone(), pending.head);
+        Some((did, TreeHeadStatus::Grew))
+    }
+
+    pub fn latest(&self, did: &str) -> Option<&SignedTreeHead> {
+        self.latest.get(did)
+    }
+}
+
+fn proves_extension(
+    first: &SignedTreeHead,
+    second: &SignedTreeHead,
+    proof: &ConsistencyProof,
+) -> bool {
+    let (Ok(first_root), Ok(second_root)) = (
+        decode_hash(&first.root_hash),
+        decode_hash(&second.root_hash),
+    ) else {
+        return false;
+    };
+    let Ok(path) = proof
+        .proof
+        .iter()
+        .map(|hex_hash| decode_hash(hex_hash))
+        .collect::<Result<Vec<_>>>()
+    else {
+        return false;
+    };
+    verify_consistency(
+        first.tree_size,
+        second.tree_size,
+        &first_root,
+        &second_root,
+        &path,
+    )
+}
+
+pub fn leaf_hash(content_hash: &str) -> MerkleHash {
+    let mut hasher = Sha256::new();
+    hasher.update([0x00]);
+    hasher.update(content_hash.as_bytes());
+    hasher.finalize().into()
+}
+
+fn node_hash(left: &MerkleHash, right: &MerkleHash) -> MerkleHash {
+    let mut hasher = Sha256::new();
+    hasher.update([0x01]);
+    hasher.update(left);
+    hasher.update(right);
+    hasher.finalize().into()
+}
+
+fn decode_hash(hex_hash: &str) -> Result<MerkleHash> {
+    hex::decode(hex_hash)
+        .ok()
+        .and_then(|bytes| bytes.try_into().ok())
+        .ok_or_else(|| OcmError::Validation(format!("Invalid Merkle hash: {}", hex_hash)))
+}
+
+// Largest power of two strictly less than n (n > 1)
+fn split_point(n: usize) -> usize {
+    let mut k = 1;
+    while k * 2 < n {
+        k *= 2;
+    }
+    k
+}
+
+pub fn merkle_root(leaves: &[MerkleHash]) -> MerkleHash {
+    match leaves.len() {
+        0 => Sha256::digest(b"").into(),
+        1 => leaves[0],
+        n => {
+            let k = split_point(n);
+            node_hash(&merkle_root(&leaves[..k]), &merkle_root(&leaves[k..]))
+        }
+    }
+}
+
+pub fn inclusion_path(index: usize, leaves: &[MerkleHash]) -> Vec<MerkleHash> {
+    let n = leaves.len();
+    if n <= 1 {
+        return Vec::new();
+    }
+    let k = split_point(n);
+    if index < k {
+        let mut path = inclusion_path(index, &leaves[..k]);
+        path.push(merkle_root(&leaves[k..]));
+        path
+    } else {
+        let mut path = inclusion_path(index - k, &leaves[k..]);
+        path.push(merkle_root(&leaves[..k]));
+        path
+    }
+}
+
+pub fn consistency_path(first_size: usize, leaves: &[MerkleHash]) -> Vec<MerkleHash> {
+    if first_size == 0 || first_size >= leaves.len() {
+        return Vec::new();
+    }
+    subproof(first_size, leaves, true)
+}
+
+fn subproof(m: usize, leaves: &[MerkleHash], complete: bool) -> Vec<MerkleHash> {
+    let n = leaves.len();
+    if m == n {
+        return if complete {
+            Vec::new()
+        } else {
+            vec![merkle_root(leaves)]
+        };
+    }
+    let k = split_point(n);
+    if m <= k {
+        let mut proof = subproof(m, &leaves[..k], complete);
+        proof.push(merkle_root(&leaves[k..]));
+        proof
+    } else {
+        let mut proof = subproof(m - k, &leaves[k..], false);
+        proof.push(merkle_root(&leaves[..k]));
+        proof
+    }
+}
+
+pub fn verify_inclusion(
+    leaf_index: u64,
+    tree_size: u64,
+    leaf: &MerkleHash,
+    path: &[MerkleHash],
+    root: &MerkleHash,
+) -> bool {
+    if leaf_index >= tree_size {
+        return false;
+    }
+
+    let mut fnode = leaf_index;
+    let mut snode = tree_size - 1;
+    let mut hash = *leaf;
+
+    for sibling in path {
+        if snode == 0 {
+            return false;
+        }
+        if fnode & 1 == 1 || fnode == snode {
+            hash = node_hash(sibling, &hash);
+            while fnode & 1 == 0 && fnode != 0 {
+                fnode >>= 1;
+                snode >>= 1;
+            }
+        } else {
+            hash = node_hash(&hash, sibling);
+        }
+        fnode >>= 1;
+        snode >>= 1;
+    }
+
+    snode == 0 && &hash == root
+}
+
+pub fn verify_consistency(
+    first_size: u64,
+    second_size: u64,
+    first_root: &MerkleHash,
+    second_root: &MerkleHash,
+    proof: &[MerkleHash],
+) -> bool {
+    if first_size > second_size {
+        return false;
+    }
+    if first_size == second_size {
+        return proof.is_empty() && first_root == second_root;
+    }
+    if first_size == 0 {
+        return proof.is_empty();
+    }
+    if proof.is_empty() {
+        return false;
+    }
+
+    let mut path = proof.to_vec();
+    if first_size.is_power_of_two() {
+        path.insert(0, *first_root);
+    }
+
+    let mut fnode = first_size - 1;
+    let mut snode = second_size - 1;
+    while fnode & 1 == 1 {
+        fnode >>= 1;
+        snode >>= 1;
+    }
+
+    let mut first_hash = path[0];
+    let mut second_hash = path[0];
+
+    for sibling in &path[1..] {
+        if snode == 0 {
+            return false;
+        }
+        if fnode & 1 == 1 || fnode == snode {
+            first_hash = node_hash(sibling, &first_hash);
+            second_hash = node_hash(sibling, &second_hash);
+            while fnode & 1 == 0 && fnode != 0 {
+                fnode >>= 1;
+                snode >>= 1;
+            }
+        } else {
+            second_hash = node_hash(&second_hash, sibling);
+        }
+        fnode >>= 1;
+        snode >>= 1;
+    }
+
+    &first_hash == first_root && &second_hash == second_root && snode == 0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample_leaves(n: usize) -> Vec<MerkleHash> {
+        (0..n).map(|i| leaf_hash(&format!("hash-{}", i))).collect()
+    }
+
+    #[test]
+    fn test_inclusion_proofs_verify() {
+        for size in 1..=9 {
+            let leaves = sample_leaves(size);
+            let root = merkle_root(&leaves);
+            for index in 0..size {
+                let path = inclusion_path(index, &leaves);
+                assert!(verify_inclusion(
+                    index as u64,
+                    size as u64,
+                    &leaves[index],
+                    &path,
+                    &root
+                ));
+            }
+        }
+    }
+
+    #[test]
+    fn test_consistency_proofs_verify() {
+        let leaves = sample_leaves(9);
+        for second in 1..=leaves.len() {
+            let second_root = merkle_root(&leaves[..second]);
+            for first in 1..=second {
+                let first_root = merkle_root(&leaves[..first]);
+                let proof = consistency_path(first, &leaves[..second]);
+                assert!(verify_consistency(
+                    first as u64,
+                    second as u64,
+                    &first_root,
+                    &second_root,
+                    &proof
+                ));
+            }
+        }
+    }
+
+    fn signed_head(leaves: &[MerkleHash]) -> SignedTreeHead {
+        SignedTreeHead {
+            tree_size: leaves.len() as u64,
+            root_hash: hex::encode(merkle_root(leaves)),
+            timestamp: chrono::Utc::now().to_rfc3339(),
+            signer_did: "did:plc:peer".to_string(),
+            signature: String::new(),
+        }
+    }
+
+    fn proof_between(first_size: usize, leaves: &[MerkleHash]) -> ConsistencyProof {
+        ConsistencyProof {
+            first_size: first_size as u64,
+            second_size: leaves.len() as u64,
+            proof: consistency_path(first_size, leaves)
+                .iter()
+                .map(hex::encode)
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn test_tree_head_monitor_detects_rewrite() {
+        let leaves = sample_leaves(3);
+        let mut monitor = TreeHeadMonitor::new();
+        assert_eq!(
+            monitor.observe(signed_head(&leaves[..2]), "peer"),
+            TreeHeadStatus::First
+        );
+        assert_eq!(
+            monitor.observe(signed_head(&leaves), "peer"),
+            TreeHeadStatus::NeedsProof {
+                first_size: 2,
+                second_size: 3
+            }
+        );
+        assert_eq!(
+            monitor.prove_growth("peer", &proof_between(2, &leaves)),
+            Some(("did:plc:peer".to_string(), TreeHeadStatus::Grew))
+        );
+
+        let mut changed = signed_head(&leaves);
+        changed.root_hash = "cc".repeat(32);
+        assert!(matches!(
+            monitor.observe(changed, "peer"),
+            TreeHeadStatus::Rewritten(_)
+        ));
+        assert!(matches!(
+            monitor.observe(signed_head(&leaves[..1]), "peer"),
+            TreeHeadStatus::Rewritten(_)
+        ));
+    }
+
+    #[test]
+    fn test_tree_head_monitor_rejects_rewritten_then_extended_log() {
+        let original = sample_leaves(4);
+        let mut monitor = TreeHeadMonitor::new();
+        monitor.observe(signed_head(&original), "peer");
+
+        // Replace a leaf, then append enough to make the tree larger than before
+        let mut rewritten = sample_leaves(6);
+        rewritten[1] = leaf_hash("forged");
+        assert!(matches!(
+            monitor.observe(signed_head(&rewritten), "peer"),
+            TreeHeadStatus::NeedsProof { .. }
+        ));
+        assert!(matches!(
+            monitor.prove_growth("peer", &proof_between(4, &rewritten)),
+            Some((_, TreeHeadStatus::Rewritten(_)))
+        ));
+        assert_eq!(
+            monitor.latest("did:plc:peer").map(|head| head.tree_size),
+            Some(4)
+        );
+    }
+
+    #[test]
+    fn test_tree_head_monitor_requires_proof_before_the_next_head() {
+        let leaves = sample_leaves(6);
+        let mut monitor = TreeHeadMonitor::new();
+        monitor.observe(signed_head(&leaves[..2]), "peer");
+        monitor.observe(signed_head(&leaves[..4]), "peer");
+
+        // Gossiping the held head again only asks for the proof again
+        assert!(matches!(
+            monitor.observe(signed_head(&leaves[..4]), "peer"),
+            TreeHeadStatus::NeedsProof { .. }
+        ));
+        assert!(matches!(
+            monitor.observe(signed_head(&leaves), "peer"),
+            TreeHeadStatus::Rewritten(_)
+        ));
+
+        // Proofs nobody asked for, or from another peer, are ignored
+        monitor.observe(signed_head(&leaves[..4]), "peer");
+        assert_eq!(
+            monitor.prove_growth("other", &proof_between(2, &leaves[..4])),
+            None
+        );
+        assert_eq!(
+            monitor.prove_growth("peer", &proof_between(3, &leaves[..4])),
+            None
+        );
+    }
+}
@@ -0,0 +1,136 @@
+use crate::core::memory_types::MemoryTypeRegistry;
+use crate::core::models::SignedMemory;
+use crate::identity::plc::PlcIdentity;
+use crate::persistence::database::Database;
+use crate::plugins::manifest::PluginPermissions;
+use std::sync::Arc;
+use wasmtime::StoreLimits;
+
+wasmtime::component::bindgen!({
+    path: "wit/plugin.wit",
+    world: "plugin",
+    trappable_imports: true,
+});
+
+pub use ocm::plugin::types::{DerivedMemory, Event, Memory};
+
+const MAX_LISTED_MEMORIES: u32 = 500;
+
+/// Per-plugin store data: the host API's view of the node, narrowed by the manifest
+pub struct PluginState {
+    pub(crate) plugin: String,
+    pub(crate) permissions: PluginPermissions,
+    pub(crate) database: Arc<Database>,
+    pub(crate) memory_types: Arc<MemoryTypeRegistry>,
+    pub(crate) signer: PlcIdentity,
+    pub(crate) limits: StoreLimits,
+    pub(crate) emitted: Vec<String>, // IDs emitted during the current call
+}
+
+impl From<&SignedMemory> for Memory {
+    fn from(memory: &SignedMemory) -> Self {
+        Memory {
+            id: memory.id.clone(),
+            did: memory.did.clone(),
+            memory_type: memory.memory_type.clone(),
+            memory_data: memory.memory_data.clone(),
+            content_hash: memory.content_hash.clone(),
+            timestamp: memory.timestamp.clone(),
+        }
+    }
+}
+
+fn denied(what: &str) -> String {
+    format!("Permission denied: {}", what)
+}
+
+impl ocm::plugin::types::Host for PluginState {}
+
+impl ocm::plugin::host::Host for PluginState {
+    fn get_memory(&mut self, id: String) -> wasmtime::Result<Result<Option<Memory>, String>> {
+        if !self.permissions.read_memories {
+            return Ok(Err(denied("read memories")));
+        }
+        Ok(self
+            .database
+            .get_signed_memory(&id)
+            .map(|memory| {
+                memory
+                    .filter(|m| self.permissions.can_read(&m.memory_type))
+                    .map(|m| Memory::from(&m))
+            })
+            .map_err(|e| e.to_string()))
+    }
+
+    fn list_memories(
+        &mut self,
+        memory_type: Option<String>,
+        limit: u32,
+    ) -> wasmtime::Result<Result<Vec<Memory>, String>> {
+        if !self.permissions.read_memories {
+            return Ok(Err(denied("read memories")));
+        }
+        if let Some(memory_type) = &memory_type {
+            if !self.permissions.can_read(memory_type) {
+                return Ok(Err(denied(&format!("read {} memories", memory_type))));
+            }
+        }
+
+        Ok(self
+            .database
+            .list_signed_memories()
+            .map(|memories| {
+                memories
+                    .iter()
+                    .filter(|m| memory_type.as_deref().map_or(true, |t| m.memory_type == t))
+                    .filter(|m| self.permissions.can_read(&m.memory_type))
+                    .take(limit.min(MAX_LISTED_MEMORIES) as usize)
+                    .map(Memory::from)
+                    .collect()
+            })
+            .map_err(|e| e.to_string()))
+    }
+
+    fn emit_memory(&mut self, memory: DerivedMemory) -> wasmtime::Result<Result<String, String>> {
+        if !self.permissions.can_emit(&memory.memory_type) {
+            return Ok(Err(denied(&format!(
+                "emit {} memories",
+                memory.memory_type
+            ))));
+        }
+        if self.emitted.len() >= self.permissions.max_emits_per_event {
+            return Ok(Err(format!(
+                "At most {} memories may be emitted per call",
+                self.permissions.max_emits_per_event
+            )));
+        }
+
+        let mut signed = match self.memory_types.create_memory(
+            &self.signer.did,
+            &memory.memory_type,
+            &memory.memory_data,
+        ) {
+            Ok(signed) => signed,
+            Err(e) => return Ok(Err(e.to_string())),
+        };
+        if let Err(e) = self.signer.sign_memory(&mut signed) {
+            return Ok(Err(e.to_string()));
+        }
+        // Queued for broadcast like any locally authored memory
+        if let Err(e) = self.database.create_outbound_signed_memory(&signed) {
+            return Ok(Err(e.to_string()));
+        }
+
+        println!(
+            "🧩 Plugin {} emitted {} memory {}",
+            self.plugin, signed.memory_type, signed.id
+        );
+        self.emitted.push(signed.id.clone());
+        Ok(Ok(signed.id))
+    }
+
+    fn log(&mut self, message: String) -> wasmtime::Result<()> {
+        println!("🧩 [{}] {}", self.plugin, message);
+        Ok(())
+    }
+}
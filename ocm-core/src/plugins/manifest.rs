@@ -0,0 +1,101 @@
+use crate::core::error::{OcmError, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+/// `plugin.toml`, next to the component it describes
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PluginManifest {
+    pub name: String,
+    pub version: String,
+    pub component: PathBuf, // Relative to the manifest
+    #[serde(default)]
+    pub subscriptions: Vec<String>, // Activity event types delivered to on-event
+    #[serde(default)]
+    pub permissions: PluginPermissions,
+    #[serde(default)]
+    pub settings: HashMap<String, String>, // Passed to init as a JSON object
+}
+
+/// What a plugin may touch; everything is denied unless granted
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PluginPermissions {
+    pub read_memories: bool,
+    pub read_types: Vec<String>, // Empty = every type, when reading is granted
+    pub emit_memories: bool,
+    pub emit_types: Vec<String>, // Types the plugin may emit; required to emit anything
+    pub max_emits_per_event: usize,
+}
+
+impl Default for PluginPermissions {
+    fn default() -> Self {
+        Self {
+            read_memories: false,
+            read_types: vec![],
+            emit_memories: false,
+            emit_types: vec![],
+            max_emits_per_event: 10,
+        }
+    }
+}
+
+impl PluginPermissions {
+    pub fn can_read(&self, memory_type: &str) -> bool {
+        self.read_memories
+            && (self.read_types.is_empty() || self.read_types.iter().any(|t| t == memory_type))
+    }
+
+    pub fn can_emit(&self, memory_type: &str) -> bool {
+        self.emit_memories && self.emit_types.iter().any(|t| t == memory_type)
+    }
+}
+
+impl PluginManifest {
+    pub fn load(path: &Path) -> Result<Self> {
+        let manifest: Self = config::Config::builder()
+            .add_source(config::File::from(path).format(config::FileFormat::Toml))
+            .build()
+            .and_then(|c| c.try_deserialize())
+            .map_err(|e| {
+                OcmError::Config(format!("Invalid plugin manifest {}: {}", path.display(), e))
+            })?;
+        manifest.validate()?;
+        Ok(manifest)
+    }
+
+    fn validate(&self) -> Result<()> {
+        let valid_name = !self.name.is_empty()
+            && self
+                .name
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+        if !valid_name {
+            return Err(OcmError::Config(format!(
+                "Plugin name '{}' must be letters, digits, '-' or '_'",
+                self.name
+            )));
+        }
+        if self.component.is_absolute() {
+            return Err(OcmError::Config(format!(
+                "Plugin {} component path must be relative to its manifest",
+                self.name
+            )));
+        }
+        if self.permissions.emit_memories && self.permissions.emit_types.is_empty() {
+            return Err(OcmError::Config(format!(
+                "Plugin {} may emit memories but lists no emit_types",
+                self.name
+            )));
+        }
+        Ok(())
+    }
+
+    /// The component file, resolved against the directory holding the manifest
+    pub fn component_path(&self, manifest_path: &Path) -> PathBuf {
+        manifest_path
+            .parent()
+            .unwrap_or_else(|| Path::new("."))
+            .join(&self.component)
+    }
+}
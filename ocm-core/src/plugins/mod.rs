@@ -0,0 +1,363 @@
+pub mod host;
+pub mod manifest;
+
+use crate::config::PluginsConfig;
+use crate::core::error::{OcmError, Result};
+use crate::core::memory_types::MemoryTypeRegistry;
+use crate::core::models::ActivityEvent;
+use crate::identity::plc::PlcIdentity;
+use crate::persistence::database::Database;
+use crate::supervisor::{RestartPolicy, TaskSupervisor};
+use host::{Event, Memory, Plugin, PluginState};
+use manifest::PluginManifest;
+use serde::Serialize;
+use std::collections::{BTreeMap, VecDeque};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use wasmtime::component::{Component, Linker};
+use wasmtime::{Engine, Store, StoreLimitsBuilder};
+
+const EVENT_BATCH: usize = 100;
+const EMITTED_HISTORY: usize = 1000; // Own memories remembered so their events are not fed back
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "state", content = "reason", rename_all = "snake_case")]
+pub enum PluginStatus {
+    Running,
+    Stopped,
+    Failed(String), // Trapped, ran out of fuel, or kept returning errors; reload to retry
+}
+
+/// A loaded plugin as reported to operators
+#[derive(Debug, Clone, Serialize)]
+pub struct PluginInfo {
+    pub name: String,
+    pub version: String,
+    pub status: PluginStatus,
+    pub subscriptions: Vec<String>,
+    pub failures: u32,
+}
+
+struct LoadedPlugin {
+    manifest: PluginManifest,
+    manifest_path: PathBuf,
+    store: Store<PluginState>,
+    bindings: Plugin,
+    status: PluginStatus,
+    failures: u32,
+    emitted: VecDeque<String>,
+}
+
+impl LoadedPlugin {
+    fn info(&self) -> PluginInfo {
+        PluginInfo {
+            name: self.manifest.name.clone(),
+            version: self.manifest.version.clone(),
+            status: self.status.clone(),
+            subscriptions: self.manifest.subscriptions.clone(),
+            failures: self.failures,
+        }
+    }
+
+    fn wants(&self, event: &ActivityEvent) -> bool {
+        self.status == PluginStatus::Running
+            && self.manifest.subscriptions.contains(&event.event_type)
+            && !event
+                .memory_id
+                .as_ref()
+                .is_some_and(|id| self.emitted.contains(id))
+    }
+}
+
+/// Runs WebAssembly component plugins against the host API in `wit/plugin.wit`.
+/// Plugins get no WASI: no files, network or clock beyond what the host API offers.
+/// Every call is metered with fuel and every instance's memory is capped
+pub struct PluginHost {
+    engine: Engine,
+    linker: Linker<PluginState>,
+    config: PluginsConfig,
+    database: Arc<Database>,
+    memory_types: Arc<MemoryTypeRegistry>,
+    signer: PlcIdentity,
+    plugins: Mutex<BTreeMap<String, LoadedPlugin>>,
+}
+
+fn plugin_error(plugin: &str, e: impl std::fmt::Display) -> OcmError {
+    OcmError::OperationFailed(format!("Plugin {}: {}", plugin, e))
+}
+
+impl PluginHost {
+    pub fn new(
+        config: PluginsConfig,
+        database: Arc<Database>,
+        memory_types: Arc<MemoryTypeRegistry>,
+        signer: PlcIdentity,
+    ) -> Result<Self> {
+        let mut engine_config = wasmtime::Config::new();
+        engine_config.wasm_component_model(true);
+        engine_config.consume_fuel(true);
+        let engine = Engine::new(&engine_config).map_err(|e| plugin_error("host", e))?;
+
+        let mut linker = Linker::new(&engine);
+        Plugin::add_to_linker(&mut linker, |state: &mut PluginState| state)
+            .map_err(|e| plugin_error("host", e))?;
+
+        Ok(Self {
+            engine,
+            linker,
+            config,
+            database,
+            memory_types,
+            signer,
+            plugins: Mutex::new(BTreeMap::new()),
+        })
+    }
+
+    /// Load every `*/plugin.toml` under the plugin directory, logging the ones that fail
+    pub fn load_all(&self) -> usize {
+        let entries = match std::fs::read_dir(&self.config.directory) {
+            Ok(entries) => entries,
+            Err(e) => {
+                eprintln!(
+                    "⚠️  Cannot read plugin directory {}: {}",
+                    self.config.directory.display(),
+                    e
+                );
+                return 0;
+            }
+        };
+
+        let mut loaded = 0;
+        for entry in entries.flatten() {
+            let manifest_path = entry.path().join("plugin.toml");
+            if !manifest_path.exists() {
+                continue;
+            }
+            match self.load(&manifest_path) {
+                Ok(name) => {
+                    println!("🧩 Loaded plugin {}", name);
+                    loaded += 1;
+                }
+                Err(e) => eprintln!("❌ {}", e),
+            }
+        }
+        loaded
+    }
+
+    /// Compile, instantiate and initialise a plugin, replacing any loaded one of the same name
+    pub fn load(&self, manifest_path: &Path) -> Result<String> {
+        let manifest = PluginManifest::load(manifest_path)?;
+        let name = manifest.name.clone();
+        let component = Component::from_file(&self.engine, manifest.component_path(manifest_path))
+            .map_err(|e| plugin_error(&name, e))?;
+
+        let state = PluginState {
+            plugin: name.clone(),
+            permissions: manifest.permissions.clone(),
+            database: self.database.clone(),
+            memory_types: self.memory_types.clone(),
+            signer: self.signer.clone(),
+            limits: StoreLimitsBuilder::new()
+                .memory_size(self.config.max_memory_mb as usize * 1024 * 1024)
+                .instances(1)
+                .build(),
+            emitted: Vec::new(),
+        };
+        let mut store = Store::new(&self.engine, state);
+        store.limiter(|state| &mut state.limits);
+        store
+            .set_fuel(self.config.fuel_per_call)
+            .map_err(|e| plugin_error(&name, e))?;
+
+        let instance = self
+            .linker
+            .instantiate(&mut store, &component)
+            .map_err(|e| plugin_error(&name, e))?;
+        let bindings = Plugin::new(&mut store, &instance).map_err(|e| plugin_error(&name, e))?;
+
+        let settings = serde_json::to_string(&manifest.settings)?;
+        bindings
+            .call_init(&mut store, &settings)
+            .map_err(|e| plugin_error(&name, e))?
+            .map_err(|e| plugin_error(&name, format!("init failed: {}", e)))?;
+
+        let plugin = LoadedPlugin {
+            manifest,
+            manifest_path: manifest_path.to_path_buf(),
+            store,
+            bindings,
+            status: PluginStatus::Running,
+            failures: 0,
+            emitted: VecDeque::new(),
+        };
+        self.plugins
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .insert(name.clone(), plugin);
+        Ok(name)
+    }
+
+    /// Drop a plugin's instance entirely
+    pub fn unload(&self, name: &str) -> bool {
+        self.plugins
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .remove(name)
+            .is_some()
+    }
+
+    /// Load a plugin afresh from its manifest, e.g. after replacing its component or a failure
+    pub fn reload(&self, name: &str) -> Result<String> {
+        let manifest_path = self
+            .plugins
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .get(name)
+            .map(|plugin| plugin.manifest_path.clone())
+            .ok_or_else(|| OcmError::NotFound(format!("Plugin {}", name)))?;
+        self.unload(name);
+        self.load(&manifest_path)
+    }
+
+    /// Stop or resume event delivery without unloading
+    pub fn set_running(&self, name: &str, running: bool) -> Result<()> {
+        let mut plugins = self.plugins.lock().unwrap_or_else(|e| e.into_inner());
+        let plugin = plugins
+            .get_mut(name)
+            .ok_or_else(|| OcmError::NotFound(format!("Plugin {}", name)))?;
+        match (&plugin.status, running) {
+            (PluginStatus::Failed(_), true) => Err(OcmError::Validation(format!(
+                "Plugin {} failed; reload it instead",
+                name
+            ))),
+            (_, true) => {
+                plugin.status = PluginStatus::Running;
+                Ok(())
+            }
+            (_, false) => {
+                plugin.status = PluginStatus::Stopped;
+                Ok(())
+            }
+        }
+    }
+
+    pub fn list(&self) -> Vec<PluginInfo> {
+        self.plugins
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .values()
+            .map(LoadedPlugin::info)
+            .collect()
+    }
+
+    /// Hand each event to the plugins subscribed to it. Blocking: plugin code runs here
+    pub fn deliver(&self, events: &[ActivityEvent]) {
+        let mut plugins = self.plugins.lock().unwrap_or_else(|e| e.into_inner());
+        for event in events {
+            for plugin in plugins.values_mut().filter(|plugin| plugin.wants(event)) {
+                self.deliver_one(plugin, event);
+            }
+        }
+    }
+
+    fn deliver_one(&self, plugin: &mut LoadedPlugin, event: &ActivityEvent) {
+        let name = plugin.manifest.name.clone();
+        let memory = event
+            .memory_id
+            .as_ref()
+            .and_then(|id| self.database.get_signed_memory(id).ok().flatten())
+            .filter(|memory| plugin.manifest.permissions.can_read(&memory.memory_type))
+            .map(|memory| Memory::from(&memory));
+        let wit_event = Event {
+            event_id: event.event_id,
+            event_type: event.event_type.clone(),
+            memory_type: event.memory_type.clone(),
+            memory,
+            payload: event.payload.to_string(),
+        };
+
+        plugin.store.data_mut().emitted.clear();
+        if let Err(e) = plugin.store.set_fuel(self.config.fuel_per_call) {
+            plugin.status = PluginStatus::Failed(e.to_string());
+            return;
+        }
+        let outcome = plugin.bindings.call_on_event(&mut plugin.store, &wit_event);
+
+        for id in plugin.store.data_mut().emitted.drain(..) {
+            plugin.emitted.push_back(id);
+            if plugin.emitted.len() > EMITTED_HISTORY {
+                plugin.emitted.pop_front();
+            }
+        }
+
+        match outcome {
+            Ok(Ok(())) => plugin.failures = 0,
+            Ok(Err(e)) => {
+                plugin.failures += 1;
+                eprintln!(
+                    "⚠️  Plugin {} failed on event {}: {}",
+                    name, event.event_id, e
+                );
+                if plugin.failures >= self.config.max_failures {
+                    plugin.status = PluginStatus::Failed(format!(
+                        "{} consecutive errors: {}",
+                        plugin.failures, e
+                    ));
+                }
+            }
+            Err(trap) => {
+                // A trapped instance cannot be entered again
+                eprintln!("❌ Plugin {} trapped: {}", name, trap);
+                plugin.failures += 1;
+                plugin.status = PluginStatus::Failed(trap.to_string());
+            }
+        }
+    }
+
+    /// Follow the activity feed from now on, delivering new events to subscribed plugins
+    pub fn start(self: Arc<Self>, supervisor: &TaskSupervisor) {
+        let interval_ms = self.config.poll_interval_ms;
+
+        supervisor.spawn("plugin_host", RestartPolicy::Always, move || {
+            let plugin_host = self.clone();
+            async move {
+                let mut cursor = match plugin_host.database.latest_activity_event_id() {
+                    Ok(event_id) => event_id,
+                    Err(e) => {
+                        eprintln!("❌ Plugin host cannot read the activity feed: {}", e);
+                        return;
+                    }
+                };
+                let mut interval =
+                    tokio::time::interval(tokio::time::Duration::from_millis(interval_ms));
+
+                loop {
+                    interval.tick().await;
+
+                    let events = match plugin_host.database.list_activity_events_after(
+                        cursor,
+                        None,
+                        None,
+                        EVENT_BATCH,
+                    ) {
+                        Ok(events) => events,
+                        Err(e) => {
+                            eprintln!("⚠️  Plugin host failed to read events: {}", e);
+                            continue;
+                        }
+                    };
+                    let Some(last) = events.last() else {
+                        continue;
+                    };
+                    cursor = last.event_id;
+
+                    let host = plugin_host.clone();
+                    if let Err(e) = tokio::task::spawn_blocking(move || host.deliver(&events)).await
+                    {
+                        eprintln!("❌ Plugin delivery panicked: {}", e);
+                    }
+                }
+            }
+        });
+    }
+}
@@ -0,0 +1,404 @@
+use crate::config::PushConfig;
+use crate::core::error::{OcmError, Result};
+use crate::core::ids::Did;
+use crate::core::models::{
+    private_audience, ActivityEvent, PushEvent, PushKind, PushSubscription, CLAIM_REDEEMED_EVENT,
+    CONFLICT_DETECTED_EVENT, KEY_ROTATED_EVENT, MEMORY_STORED_EVENT, RECEIPT_MEMORY_TYPE,
+};
+use crate::core::redact::Redacted;
+use crate::persistence::database::Database;
+use crate::supervisor::{RestartPolicy, TaskSupervisor};
+use base64::{engine::general_purpose, Engine as _};
+use p256::ecdsa::{signature::Signer, Signature, SigningKey};
+use p256::pkcs8::DecodePrivateKey;
+use std::sync::Arc;
+use std::time::Duration;
+
+const EVENT_BATCH: usize = 100;
+const VAPID_TOKEN_LIFETIME_HOURS: i64 = 12; // Push services refuse tokens valid for over 24h
+const FCM_SEND_URL: &str = "https://fcm.googleapis.com/v1/projects";
+
+/// What became of one push
+enum Delivery {
+    Accepted,
+    Gone, // The push service no longer knows the subscription
+    Failed(String),
+}
+
+/// Sends push notifications about activity events to the devices subscribed for them.
+/// Pushes carry no memory content: a Web Push is empty and an FCM message names only
+/// the event, so the device fetches what changed over the authenticated API
+pub struct PushGateway {
+    database: Arc<Database>,
+    config: PushConfig,
+    http: reqwest::Client,
+    vapid_key: Option<SigningKey>,
+}
+
+impl PushGateway {
+    pub fn new(config: PushConfig, database: Arc<Database>) -> Result<Self> {
+        let vapid_key = match &config.vapid_private_key_path {
+            Some(path) => {
+                let pem = std::fs::read_to_string(path)?;
+                Some(SigningKey::from_pkcs8_pem(&pem).map_err(|e| {
+                    OcmError::Config(format!("Invalid VAPID key {}: {}", path.display(), e))
+                })?)
+            }
+            None => None,
+        };
+        let http = reqwest::Client::builder()
+            .timeout(Duration::from_millis(config.timeout_ms))
+            .build()?;
+
+        Ok(Self {
+            database,
+            config,
+            http,
+            vapid_key,
+        })
+    }
+
+    /// The VAPID public key browsers pass to `PushManager.subscribe` as the
+    /// `applicationServerKey`: an uncompressed P-256 point, base64url without padding
+    pub fn vapid_public_key(&self) -> Option<String> {
+        self.vapid_key.as_ref().map(|key| {
+            general_purpose::URL_SAFE_NO_PAD
+                .encode(key.verifying_key().to_encoded_point(false).as_bytes())
+        })
+    }
+
+    /// Register a device for pushes about `did`. Registering an endpoint again replaces
+    /// the earlier registration, whoever made it
+    pub fn subscribe(
+        &self,
+        did: &Did,
+        kind: PushKind,
+        endpoint: &str,
+        keys: Option<(String, String)>,
+        events: Option<Vec<PushEvent>>,
+    ) -> Result<PushSubscription> {
+        match kind {
+            PushKind::WebPush => {
+                if self.vapid_key.is_none() {
+                    return Err(OcmError::Validation(
+                        "Web Push is not configured on this server".to_string(),
+                    ));
+                }
+                let url = reqwest::Url::parse(endpoint)
+                    .map_err(|e| OcmError::Validation(format!("Invalid push endpoint: {}", e)))?;
+                if url.scheme() != "https" || url.host_str().is_none() {
+                    return Err(OcmError::Validation(
+                        "Push endpoints must be https URLs".to_string(),
+                    ));
+                }
+            }
+            PushKind::Fcm => {
+                if self.config.fcm_project_id.is_none() {
+                    return Err(OcmError::Validation(
+                        "FCM is not configured on this server".to_string(),
+                    ));
+                }
+                if endpoint.is_empty() || endpoint.len() > 4096 {
+                    return Err(OcmError::Validation(
+                        "Invalid FCM registration token".to_string(),
+                    ));
+                }
+            }
+        }
+
+        let existing = self.database.list_push_subscriptions(Some(did))?;
+        let replaces = existing
+            .iter()
+            .any(|subscription| subscription.endpoint == endpoint);
+        if !replaces && existing.len() >= self.config.max_subscriptions_per_did {
+            return Err(OcmError::Validation(format!(
+                "At most {} push subscriptions per DID",
+                self.config.max_subscriptions_per_did
+            )));
+        }
+
+        let (p256dh, auth) = keys.unzip();
+        let subscription = PushSubscription {
+            id: uuid::Uuid::new_v4().to_string(),
+            did: did.clone(),
+            kind,
+            endpoint: endpoint.to_string(),
+            p256dh,
+            auth,
+            events: dedup_events(events.unwrap_or_else(|| self.config.default_events.clone())),
+            created_at: chrono::Utc::now().to_rfc3339(),
+            last_pushed_at: None,
+            last_error: None,
+        };
+        self.database.upsert_push_subscription(&subscription)?;
+        Ok(subscription)
+    }
+
+    pub fn list(&self, did: &Did) -> Result<Vec<PushSubscription>> {
+        self.database.list_push_subscriptions(Some(did))
+    }
+
+    /// A DID's subscription; other DIDs' subscriptions are treated as absent
+    fn owned(&self, did: &Did, id: &str) -> Result<PushSubscription> {
+        self.database
+            .get_push_subscription(id)?
+            .filter(|subscription| subscription.did == *did)
+            .ok_or_else(|| OcmError::NotFound(format!("Push subscription {}", id)))
+    }
+
+    /// Change which events a subscription is pushed about
+    pub fn set_events(
+        &self,
+        did: &Did,
+        id: &str,
+        events: Vec<PushEvent>,
+    ) -> Result<PushSubscription> {
+        let mut subscription = self.owned(did, id)?;
+        subscription.events = dedup_events(events);
+        self.database
+            .update_push_subscription_events(id, &subscription.events)?;
+        Ok(subscription)
+    }
+
+    pub fn unsubscribe(&self, did: &Did, id: &str) -> Result<()> {
+        self.owned(did, id)?;
+        self.database.delete_push_subscription(id)?;
+        Ok(())
+    }
+
+    /// The DIDs an event is pushed to, and as which kind of push
+    fn recipients(&self, event: &ActivityEvent) -> Vec<(Did, PushEvent)> {
+        match event.event_type.as_str() {
+            MEMORY_STORED_EVENT => {
+                let memory = event
+                    .memory_id
+                    .as_ref()
+                    .and_then(|id| self.database.get_signed_memory(id).ok().flatten());
+                let Some(memory) = memory else {
+                    return Vec::new();
+                };
+                if memory.memory_type == RECEIPT_MEMORY_TYPE {
+                    return Vec::new();
+                }
+                private_audience(&memory)
+                    .unwrap_or_default()
+                    .into_iter()
+                    .filter(|did| did != &memory.did)
+                    .map(|did| (did, PushEvent::NewMemory))
+                    .collect()
+            }
+            CLAIM_REDEEMED_EVENT => event
+                .did
+                .iter()
+                .map(|did| (did.clone(), PushEvent::ClaimRedeemed))
+                .collect(),
+            CONFLICT_DETECTED_EVENT => event
+                .did
+                .iter()
+                .map(|did| (did.clone(), PushEvent::ConflictNeedsResolution))
+                .collect(),
+            KEY_ROTATED_EVENT => event
+                .did
+                .iter()
+                .map(|did| (did.clone(), PushEvent::KeyRotated))
+                .collect(),
+            _ => Vec::new(),
+        }
+    }
+
+    async fn dispatch(&self, event: &ActivityEvent) {
+        for (did, push_event) in self.recipients(event) {
+            let subscriptions = match self.database.list_push_subscriptions(Some(&did)) {
+                Ok(subscriptions) => subscriptions,
+                Err(e) => {
+                    eprintln!("⚠️  Failed to read push subscriptions: {}", e);
+                    continue;
+                }
+            };
+            for subscription in subscriptions
+                .iter()
+                .filter(|subscription| subscription.events.contains(&push_event))
+            {
+                let delivery = match subscription.kind {
+                    PushKind::WebPush => self.send_web_push(subscription, push_event).await,
+                    PushKind::Fcm => self.send_fcm(subscription, push_event).await,
+                };
+                let recorded = match delivery {
+                    Delivery::Accepted => self.database.record_push_outcome(&subscription.id, None),
+                    Delivery::Gone => {
+                        println!(
+                            "🔕 Push subscription {} of {} expired",
+                            subscription.id,
+                            Redacted::did(&did)
+                        );
+                        self.database
+                            .delete_push_subscription(&subscription.id)
+                            .map(|_| ())
+                    }
+                    Delivery::Failed(error) => {
+                        eprintln!(
+                            "⚠️  Push to subscription {} failed: {}",
+                            subscription.id, error
+                        );
+                        self.database
+                            .record_push_outcome(&subscription.id, Some(&error))
+                    }
+                };
+                if let Err(e) = recorded {
+                    eprintln!("⚠️  Failed to record push outcome: {}", e);
+                }
+            }
+        }
+    }
+
+    /// VAPID authorization (RFC 8292) for one push service: an ES256 JWT whose audience
+    /// is the endpoint's origin
+    fn vapid_authorization(&self, key: &SigningKey, endpoint: &str) -> Result<String> {
+        let url = reqwest::Url::parse(endpoint)
+            .map_err(|e| OcmError::Validation(format!("Invalid push endpoint: {}", e)))?;
+        let expires = chrono::Utc::now() + chrono::Duration::hours(VAPID_TOKEN_LIFETIME_HOURS);
+        let header = serde_json::json!({ "typ": "JWT", "alg": "ES256" });
+        let claims = serde_json::json!({
+            "aud": url.origin().ascii_serialization(),
+            "exp": expires.timestamp(),
+            "sub": self.config.vapid_subject,
+        });
+
+        let signing_input = format!(
+            "{}.{}",
+            general_purpose::URL_SAFE_NO_PAD.encode(header.to_string()),
+            general_purpose::URL_SAFE_NO_PAD.encode(claims.to_string())
+        );
+        let signature: Signature = key.sign(signing_input.as_bytes());
+        Ok(format!(
+            "vapid t={}.{}, k={}",
+            signing_input,
+            general_purpose::URL_SAFE_NO_PAD.encode(signature.to_bytes()),
+            self.vapid_public_key().unwrap_or_default()
+        ))
+    }
+
+    /// An empty push; the service worker wakes up and asks the API what is new.
+    /// The Topic header lets the push service replace an undelivered push of the same event
+    async fn send_web_push(&self, subscription: &PushSubscription, event: PushEvent) -> Delivery {
+        let Some(key) = &self.vapid_key else {
+            return Delivery::Failed("Web Push is not configured".to_string());
+        };
+        let authorization = match self.vapid_authorization(key, &subscription.endpoint) {
+            Ok(authorization) => authorization,
+            Err(e) => return Delivery::Failed(e.to_string()),
+        };
+
+        let response = self
+            .http
+            .post(&subscription.endpoint)
+            .header("Authorization", authorization)
+            .header("TTL", self.config.ttl_seconds.to_string())
+            .header("Urgency", "normal")
+            .header("Topic", event.as_str().replace('_', "-"))
+            .header("Content-Length", "0")
+            .send()
+            .await;
+        match response {
+            Ok(response) if response.status().is_success() => Delivery::Accepted,
+            Ok(response) if matches!(response.status().as_u16(), 404 | 410) => Delivery::Gone,
+            Ok(response) => {
+                Delivery::Failed(format!("Push service answered {}", response.status()))
+            }
+            Err(e) => Delivery::Failed(e.to_string()),
+        }
+    }
+
+    /// A data message through the FCM HTTP v1 API, naming only the event
+    async fn send_fcm(&self, subscription: &PushSubscription, event: PushEvent) -> Delivery {
+        let (Some(project_id), Some(token_path)) = (
+            &self.config.fcm_project_id,
+            &self.config.fcm_access_token_path,
+        ) else {
+            return Delivery::Failed("FCM is not configured".to_string());
+        };
+        let access_token = match std::fs::read_to_string(token_path) {
+            Ok(token) => token.trim().to_string(),
+            Err(e) => return Delivery::Failed(format!("Cannot read FCM access token: {}", e)),
+        };
+
+        let message = serde_json::json!({
+            "message": {
+                "token": subscription.endpoint,
+                "data": { "event": event.as_str() },
+                "android": { "ttl": format!("{}s", self.config.ttl_seconds) },
+            }
+        });
+        let response = self
+            .http
+            .post(format!("{}/{}/messages:send", FCM_SEND_URL, project_id))
+            .bearer_auth(access_token)
+            .json(&message)
+            .send()
+            .await;
+        match response {
+            Ok(response) if response.status().is_success() => Delivery::Accepted,
+            // FCM answers 404 UNREGISTERED for tokens of uninstalled apps
+            Ok(response) if response.status().as_u16() == 404 => Delivery::Gone,
+            Ok(response) => Delivery::Failed(format!("FCM answered {}", response.status())),
+            Err(e) => Delivery::Failed(e.to_string()),
+        }
+    }
+
+    /// Follow the activity feed from now on, pushing each event to its subscribers.
+    /// Subscriptions are re-read for every event, so changes apply at once
+    pub fn start(self: Arc<Self>, supervisor: &TaskSupervisor) {
+        let interval_ms = self.config.poll_interval_ms;
+
+        supervisor.spawn("push_gateway", RestartPolicy::Always, move || {
+            let gateway = self.clone();
+            async move {
+                let mut cursor = match gateway.database.latest_activity_event_id() {
+                    Ok(event_id) => event_id,
+                    Err(e) => {
+                        eprintln!("❌ Push gateway cannot read the activity feed: {}", e);
+                        return;
+                    }
+                };
+                let mut interval =
+                    tokio::time::interval(tokio::time::Duration::from_millis(interval_ms));
+
+                loop {
+                    interval.tick().await;
+
+                    let events = match gateway.database.list_activity_events_after(
+                        cursor,
+                        None,
+                        None,
+                        EVENT_BATCH,
+                    ) {
+                        Ok(events) => events,
+                        Err(e) => {
+                            eprintln!("⚠️  Push gateway failed to read events: {}", e);
+                            continue;
+                        }
+                    };
+                    let Some(last) = events.last() else {
+                        continue;
+                    };
+                    cursor = last.event_id;
+
+                    for event in &events {
+                        gateway.dispatch(event).await;
+                    }
+                }
+            }
+        });
+    }
+}
+
+/// Events in their first-chosen order, each once
+fn dedup_events(events: Vec<PushEvent>) -> Vec<PushEvent> {
+    let mut unique = Vec::with_capacity(events.len());
+    for event in events {
+        if !unique.contains(&event) {
+            unique.push(event);
+        }
+    }
+    unique
+}
@@ -0,0 +1,258 @@
+use crate::config::RulesConfig;
+use crate::core::error::{OcmError, Result};
+use crate::core::models::{ActivityEvent, AutomationRule};
+use crate::persistence::database::Database;
+use crate::supervisor::{RestartPolicy, TaskSupervisor};
+use rhai::{Dynamic, Engine, EvalAltResult, Scope, AST};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+const EVENT_BATCH: usize = 100;
+const MAX_WEBHOOKS_PER_RUN: usize = 10;
+
+/// A webhook call queued by a script, sent once the script has finished
+#[derive(Debug, Clone)]
+pub struct WebhookCall {
+    pub url: String,
+    pub body: serde_json::Value,
+}
+
+/// A Rhai engine with no file, module or network access; only `webhook` reaches outside.
+/// `max_operations` bounds the work a single run may do
+fn sandboxed_engine(max_operations: u64) -> Engine {
+    let mut engine = Engine::new();
+    engine
+        .set_max_operations(max_operations)
+        .set_max_modules(0)
+        .set_max_call_levels(32)
+        .set_max_expr_depths(64, 32)
+        .set_max_string_size(64 * 1024)
+        .set_max_array_size(10_000)
+        .set_max_map_size(10_000)
+        .disable_symbol("eval")
+        .disable_symbol("import");
+    engine.on_print(|message| println!("📜 {}", message));
+    engine
+}
+
+/// Reject scripts that do not parse, before they are stored
+pub fn check_script(script: &str) -> Result<()> {
+    sandboxed_engine(1)
+        .compile(script)
+        .map(|_| ())
+        .map_err(|e| OcmError::Validation(format!("Invalid rule script: {}", e)))
+}
+
+/// Runs the automation rules stored in the database against the activity feed.
+/// Scripts see the triggering `event` and, for memory events, the `memory` it concerns
+/// (with `memory.data` parsed from JSON), and may call `webhook(url, #{...})`
+pub struct RuleEngine {
+    engine: Engine,
+    database: Arc<Database>,
+    config: RulesConfig,
+    http: reqwest::Client,
+    pending: Arc<Mutex<Vec<WebhookCall>>>, // Filled by `webhook` during a run
+    run_lock: Mutex<()>,
+    compiled: Mutex<HashMap<String, (String, AST)>>, // name -> (updated_at, script)
+}
+
+impl RuleEngine {
+    pub fn new(config: RulesConfig, database: Arc<Database>) -> Result<Self> {
+        let http = reqwest::Client::builder()
+            .timeout(Duration::from_millis(config.webhook_timeout_ms))
+            .build()?;
+
+        let pending = Arc::new(Mutex::new(Vec::new()));
+        let mut engine = sandboxed_engine(config.max_operations);
+        let calls = pending.clone();
+        let allowed_hosts = config.allowed_webhook_hosts.clone();
+        engine.register_fn(
+            "webhook",
+            move |url: &str, body: rhai::Map| -> std::result::Result<(), Box<EvalAltResult>> {
+                let parsed = reqwest::Url::parse(url)
+                    .map_err(|e| format!("Invalid webhook URL {}: {}", url, e))?;
+                let host_allowed = parsed
+                    .host_str()
+                    .is_some_and(|host| allowed_hosts.iter().any(|allowed| allowed == host));
+                if !matches!(parsed.scheme(), "http" | "https") || !host_allowed {
+                    return Err(format!("Webhook host not allowed: {}", url).into());
+                }
+                let body: serde_json::Value = rhai::serde::from_dynamic(&Dynamic::from_map(body))?;
+
+                let mut calls = calls.lock().unwrap_or_else(|e| e.into_inner());
+                if calls.len() >= MAX_WEBHOOKS_PER_RUN {
+                    return Err(format!(
+                        "At most {} webhooks may be called per run",
+                        MAX_WEBHOOKS_PER_RUN
+                    )
+                    .into());
+                }
+                calls.push(WebhookCall {
+                    url: parsed.to_string(),
+                    body,
+                });
+                Ok(())
+            },
+        );
+
+        Ok(Self {
+            engine,
+            database,
+            config,
+            http,
+            pending,
+            run_lock: Mutex::new(()),
+            compiled: Mutex::new(HashMap::new()),
+        })
+    }
+
+    /// The rule's compiled script, recompiled whenever the stored rule changes
+    fn compiled(&self, rule: &AutomationRule) -> std::result::Result<AST, String> {
+        let mut compiled = self.compiled.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some((updated_at, ast)) = compiled.get(&rule.name) {
+            if *updated_at == rule.updated_at {
+                return Ok(ast.clone());
+            }
+        }
+        let ast = self
+            .engine
+            .compile(&rule.script)
+            .map_err(|e| format!("Script does not compile: {}", e))?;
+        compiled.insert(rule.name.clone(), (rule.updated_at.clone(), ast.clone()));
+        Ok(ast)
+    }
+
+    fn memory_for(&self, event: &ActivityEvent) -> Dynamic {
+        let memory = event
+            .memory_id
+            .as_ref()
+            .and_then(|id| self.database.get_signed_memory(id).ok().flatten());
+        let Some(memory) = memory else {
+            return Dynamic::UNIT;
+        };
+
+        let data = serde_json::from_str::<serde_json::Value>(&memory.memory_data)
+            .unwrap_or(serde_json::Value::String(memory.memory_data.clone()));
+        let value = serde_json::json!({
+            "id": memory.id,
+            "did": memory.did,
+            "memory_type": memory.memory_type,
+            "timestamp": memory.timestamp,
+            "data": data,
+        });
+        rhai::serde::to_dynamic(value).unwrap_or(Dynamic::UNIT)
+    }
+
+    /// Run a script for one event, returning the webhooks it queued without sending them
+    pub fn evaluate(
+        &self,
+        rule: &AutomationRule,
+        event: &ActivityEvent,
+    ) -> std::result::Result<Vec<WebhookCall>, String> {
+        let ast = self.compiled(rule)?;
+        let event_value = rhai::serde::to_dynamic(event).map_err(|e| e.to_string())?;
+
+        let mut scope = Scope::new();
+        scope.push_constant("event", event_value);
+        scope.push_constant("memory", self.memory_for(event));
+
+        // One run at a time, so the queued webhooks belong to this run
+        let _run = self.run_lock.lock().unwrap_or_else(|e| e.into_inner());
+        self.pending
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .clear();
+        let outcome = self.engine.eval_ast_with_scope::<Dynamic>(&mut scope, &ast);
+        let calls = std::mem::take(&mut *self.pending.lock().unwrap_or_else(|e| e.into_inner()));
+
+        outcome.map(|_| calls).map_err(|e| e.to_string())
+    }
+
+    async fn send_webhooks(&self, calls: Vec<WebhookCall>) -> std::result::Result<(), String> {
+        for call in calls {
+            self.http
+                .post(&call.url)
+                .json(&call.body)
+                .send()
+                .await
+                .and_then(|response| response.error_for_status())
+                .map_err(|e| format!("Webhook {} failed: {}", call.url, e))?;
+        }
+        Ok(())
+    }
+
+    async fn run(&self, rule: &AutomationRule, event: &ActivityEvent) {
+        let outcome = match self.evaluate(rule, event) {
+            Ok(calls) => self.send_webhooks(calls).await,
+            Err(e) => Err(e),
+        };
+        if let Err(e) = &outcome {
+            eprintln!(
+                "⚠️  Rule {} failed on event {}: {}",
+                rule.name, event.event_id, e
+            );
+        }
+        if let Err(e) = self
+            .database
+            .record_automation_rule_run(&rule.name, outcome.err().as_deref())
+        {
+            eprintln!("⚠️  Failed to record run of rule {}: {}", rule.name, e);
+        }
+    }
+
+    /// Follow the activity feed from now on, running the rules each new event matches.
+    /// Rules are re-read every poll, so changes made through the admin API apply at once
+    pub fn start(self: Arc<Self>, supervisor: &TaskSupervisor) {
+        let interval_ms = self.config.poll_interval_ms;
+
+        supervisor.spawn("rule_engine", RestartPolicy::Always, move || {
+            let engine = self.clone();
+            async move {
+                let mut cursor = match engine.database.latest_activity_event_id() {
+                    Ok(event_id) => event_id,
+                    Err(e) => {
+                        eprintln!("❌ Rule engine cannot read the activity feed: {}", e);
+                        return;
+                    }
+                };
+                let mut interval =
+                    tokio::time::interval(tokio::time::Duration::from_millis(interval_ms));
+
+                loop {
+                    interval.tick().await;
+
+                    let events = match engine.database.list_activity_events_after(
+                        cursor,
+                        None,
+                        None,
+                        EVENT_BATCH,
+                    ) {
+                        Ok(events) => events,
+                        Err(e) => {
+                            eprintln!("⚠️  Rule engine failed to read events: {}", e);
+                            continue;
+                        }
+                    };
+                    let Some(last) = events.last() else {
+                        continue;
+                    };
+                    cursor = last.event_id;
+
+                    let rules = match engine.database.list_automation_rules() {
+                        Ok(rules) => rules,
+                        Err(e) => {
+                            eprintln!("⚠️  Rule engine failed to read rules: {}", e);
+                            continue;
+                        }
+                    };
+                    for event in &events {
+                        for rule in rules.iter().filter(|rule| rule.matches(event)) {
+                            engine.run(rule, event).await;
+                        }
+                    }
+                }
+            }
+        });
+    }
+}
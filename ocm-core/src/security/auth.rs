@@ -11,6 +11,9 @@ use serde::{Deserialize, Serialize};
 use sha2::{Digest, Sha256};
 use std::collections::HashMap;
 
+use crate::config::SessionConfig;
+use crate::core::tenancy::TenantScope;
+
 // API Key structure
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ApiKey {
@@ -22,6 +25,10 @@ pub struct ApiKey {
     pub last_used: Option<DateTime<Utc>>,
     pub is_active: bool,
     pub rate_limit_tier: RateLimitTier,
+    #[serde(default)]
+    pub organization_did: Option<String>, // Restricts the key to this organization's data
+    #[serde(default)]
+    pub tenant_id: Option<String>, // On a multi-tenant node, the only tenant the key works for
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -41,6 +48,65 @@ pub struct Session {
     pub expires_at: DateTime<Utc>,
     pub last_activity: DateTime<Utc>,
     pub is_active: bool,
+    #[serde(default)]
+    pub organization_did: Option<String>, // Restricts the session to this organization's data
+    #[serde(default)]
+    pub tenant_id: Option<String>, // On a multi-tenant node, the only tenant the session works for
+    #[serde(default)]
+    pub absolute_expires_at: Option<DateTime<Utc>>, // Activity never slides expires_at past this
+    #[serde(default)]
+    pub family_id: Option<String>, // Shared by every session refreshed from the same login
+}
+
+// Refresh token, stored by hash. Each one is single use: refreshing marks it used and
+// issues a successor in the same family
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RefreshToken {
+    pub token_hash: String,
+    pub family_id: String,
+    pub session_id: String, // The session it was issued with
+    pub expires_at: DateTime<Utc>,
+    pub used_at: Option<DateTime<Utc>>,
+}
+
+/// What a client keeps after logging in or refreshing
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SessionTokens {
+    pub session_id: String,
+    pub refresh_token: String,
+    pub expires_at: DateTime<Utc>,
+    pub refresh_expires_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum RefreshError {
+    Invalid, // Unknown or expired
+    Reused,  // Already used, so it may be stolen: its whole family is now revoked
+    Store(String),
+}
+
+/// How long sessions and refresh tokens last
+#[derive(Debug, Clone)]
+pub struct SessionPolicy {
+    pub idle_timeout: Duration,
+    pub absolute_lifetime: Duration,
+    pub refresh_lifetime: Duration,
+}
+
+impl Default for SessionPolicy {
+    fn default() -> Self {
+        Self::from_config(&SessionConfig::default())
+    }
+}
+
+impl SessionPolicy {
+    pub fn from_config(config: &SessionConfig) -> Self {
+        Self {
+            idle_timeout: Duration::minutes(config.idle_timeout_minutes),
+            absolute_lifetime: Duration::hours(config.absolute_lifetime_hours),
+            refresh_lifetime: Duration::days(config.refresh_token_days),
+        }
+    }
 }
 
 // Authentication context passed to handlers
@@ -51,6 +117,8 @@ pub struct AuthContext {
     pub rate_limit_tier: RateLimitTier,
     pub session_id: Option<String>,
     pub api_key_id: Option<String>,
+    pub organization_did: Option<String>,
+    pub tenant_id: Option<String>,
 }
 
 impl Default for AuthContext {
@@ -61,6 +129,8 @@ impl Default for AuthContext {
             rate_limit_tier: RateLimitTier::Basic,
             session_id: None,
             api_key_id: None,
+            organization_did: None,
+            tenant_id: None,
         }
     }
 }
@@ -68,17 +138,32 @@ impl Default for AuthContext {
 // In-memory storage for demonstration (replace with database in production)
 use std::sync::{Arc, RwLock};
 
-#[derive(Debug)]
+// Clones share the same keys and sessions
+#[derive(Debug, Clone)]
 pub struct AuthStore {
     api_keys: Arc<RwLock<HashMap<String, ApiKey>>>,
     sessions: Arc<RwLock<HashMap<String, Session>>>,
+    refresh_tokens: Arc<RwLock<HashMap<String, RefreshToken>>>,
+    policy: SessionPolicy,
+}
+
+impl Default for AuthStore {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl AuthStore {
     pub fn new() -> Self {
+        Self::with_policy(SessionPolicy::default())
+    }
+
+    pub fn with_policy(policy: SessionPolicy) -> Self {
         Self {
             api_keys: Arc::new(RwLock::new(HashMap::new())),
             sessions: Arc::new(RwLock::new(HashMap::new())),
+            refresh_tokens: Arc::new(RwLock::new(HashMap::new())),
+            policy,
         }
     }
 
@@ -88,6 +173,17 @@ impl AuthStore {
         permissions: Vec<String>,
         expires_in_days: Option<i64>,
         rate_limit_tier: RateLimitTier,
+    ) -> Result<(String, String), String> {
+        self.create_scoped_api_key(None, permissions, expires_in_days, rate_limit_tier)
+    }
+
+    /// An API key that only sees the given organization's data, or everything if None
+    pub fn create_scoped_api_key(
+        &self,
+        organization_did: Option<String>,
+        permissions: Vec<String>,
+        expires_in_days: Option<i64>,
+        rate_limit_tier: RateLimitTier,
     ) -> Result<(String, String), String> {
         // Generate secure API key
         let key_bytes: [u8; 32] = rand::random();
@@ -110,6 +206,8 @@ impl AuthStore {
             last_used: None,
             is_active: true,
             rate_limit_tier,
+            organization_did,
+            tenant_id: None,
         };
 
         self.api_keys
@@ -144,6 +242,21 @@ impl AuthStore {
         None
     }
 
+    /// Bind a key to one tenant of a multi-tenant node, or None for the host
+    pub fn set_api_key_tenant(
+        &self,
+        key_id: &str,
+        tenant_id: Option<String>,
+    ) -> Result<(), String> {
+        let mut api_keys = self
+            .api_keys
+            .write()
+            .map_err(|_| "Failed to acquire write lock")?;
+        let key_record = api_keys.get_mut(key_id).ok_or("Unknown API key")?;
+        key_record.tenant_id = tenant_id;
+        Ok(())
+    }
+
     pub fn update_api_key_usage(&self, key_id: &str) {
         if let Ok(mut api_keys) = self.api_keys.write() {
             if let Some(key_record) = api_keys.get_mut(key_id) {
@@ -159,25 +272,160 @@ impl AuthStore {
         permissions: Vec<String>,
         expires_in_hours: i64,
     ) -> Result<String, String> {
-        let session_id = uuid::Uuid::new_v4().to_string();
-        let now = Utc::now();
+        self.create_scoped_session(user_did, None, permissions, expires_in_hours)
+    }
+
+    /// A session that only sees the given organization's data, or everything if None.
+    /// It lasts at most `expires_in_hours`, and less if left idle
+    pub fn create_scoped_session(
+        &self,
+        user_did: String,
+        organization_did: Option<String>,
+        permissions: Vec<String>,
+        expires_in_hours: i64,
+    ) -> Result<String, String> {
+        let session = self.new_session(
+            user_did,
+            organization_did,
+            permissions,
+            Duration::hours(expires_in_hours),
+            None,
+        );
+        let session_id = session.session_id.clone();
+
+        self.sessions
+            .write()
+            .map_err(|_| "Failed to acquire write lock")?
+            .insert(session_id.clone(), session);
 
-        let session = Session {
-            session_id: session_id.clone(),
+        Ok(session_id)
+    }
+
+    /// A session with a refresh token, lasting as long as the session policy allows
+    pub fn start_session(
+        &self,
+        user_did: String,
+        organization_did: Option<String>,
+        permissions: Vec<String>,
+    ) -> Result<SessionTokens, String> {
+        let family_id = uuid::Uuid::new_v4().to_string();
+        let session = self.new_session(
+            user_did,
+            organization_did,
+            permissions,
+            self.policy.absolute_lifetime,
+            Some(family_id),
+        );
+        self.issue_tokens(session)
+    }
+
+    fn new_session(
+        &self,
+        user_did: String,
+        organization_did: Option<String>,
+        permissions: Vec<String>,
+        lifetime: Duration,
+        family_id: Option<String>,
+    ) -> Session {
+        let now = Utc::now();
+        let absolute_expires_at = now + lifetime;
+        Session {
+            session_id: uuid::Uuid::new_v4().to_string(),
             user_did,
             permissions,
             created_at: now,
-            expires_at: now + Duration::hours(expires_in_hours),
+            expires_at: (now + self.policy.idle_timeout).min(absolute_expires_at),
             last_activity: now,
             is_active: true,
+            organization_did,
+            tenant_id: None,
+            absolute_expires_at: Some(absolute_expires_at),
+            family_id,
+        }
+    }
+
+    // Store the session along with a new refresh token for it
+    fn issue_tokens(&self, session: Session) -> Result<SessionTokens, String> {
+        let family_id = session
+            .family_id
+            .clone()
+            .ok_or("Session has no refresh token family")?;
+        let refresh_bytes: [u8; 32] = rand::random();
+        let refresh_token = hex::encode(refresh_bytes);
+        let refresh_expires_at = Utc::now() + self.policy.refresh_lifetime;
+        let tokens = SessionTokens {
+            session_id: session.session_id.clone(),
+            refresh_token: refresh_token.clone(),
+            expires_at: session.expires_at,
+            refresh_expires_at,
         };
 
-        self.sessions
+        // Lock order: sessions, then refresh tokens
+        let mut sessions = self
+            .sessions
             .write()
-            .map_err(|_| "Failed to acquire write lock")?
-            .insert(session_id.clone(), session);
+            .map_err(|_| "Failed to acquire write lock")?;
+        let mut refresh_tokens = self
+            .refresh_tokens
+            .write()
+            .map_err(|_| "Failed to acquire write lock")?;
+        refresh_tokens.insert(
+            hash_token(&refresh_token),
+            RefreshToken {
+                token_hash: hash_token(&refresh_token),
+                family_id,
+                session_id: session.session_id.clone(),
+                expires_at: refresh_expires_at,
+                used_at: None,
+            },
+        );
+        sessions.insert(session.session_id.clone(), session);
 
-        Ok(session_id)
+        Ok(tokens)
+    }
+
+    /// Trade a refresh token for a new session and refresh token, ending the session it
+    /// was issued with. A token presented twice means it leaked, so every session and
+    /// token descended from the same login is revoked
+    pub fn refresh_session(&self, refresh_token: &str) -> Result<SessionTokens, RefreshError> {
+        let now = Utc::now();
+        let token_hash = hash_token(refresh_token);
+        let record = {
+            let mut refresh_tokens = self
+                .refresh_tokens
+                .write()
+                .map_err(|_| RefreshError::Store("Failed to acquire write lock".to_string()))?;
+            let record = refresh_tokens
+                .get_mut(&token_hash)
+                .ok_or(RefreshError::Invalid)?;
+            if record.used_at.is_some() {
+                let family_id = record.family_id.clone();
+                drop(refresh_tokens);
+                self.revoke_family(&family_id);
+                return Err(RefreshError::Reused);
+            }
+            if now >= record.expires_at {
+                return Err(RefreshError::Invalid);
+            }
+            record.used_at = Some(now);
+            record.clone()
+        };
+
+        let previous = self
+            .sessions
+            .write()
+            .map_err(|_| RefreshError::Store("Failed to acquire write lock".to_string()))?
+            .remove(&record.session_id)
+            .ok_or(RefreshError::Invalid)?; // Logged out since
+        let mut session = self.new_session(
+            previous.user_did,
+            previous.organization_did,
+            previous.permissions,
+            self.policy.absolute_lifetime,
+            Some(record.family_id),
+        );
+        session.tenant_id = previous.tenant_id;
+        self.issue_tokens(session).map_err(RefreshError::Store)
     }
 
     pub fn validate_session(&self, session_id: &str) -> Option<Session> {
@@ -192,23 +440,93 @@ impl AuthStore {
         None
     }
 
+    /// Bind a session to one tenant of a multi-tenant node, or None for the host
+    pub fn set_session_tenant(
+        &self,
+        session_id: &str,
+        tenant_id: Option<String>,
+    ) -> Result<(), String> {
+        let mut sessions = self
+            .sessions
+            .write()
+            .map_err(|_| "Failed to acquire write lock")?;
+        let session = sessions.get_mut(session_id).ok_or("Unknown session")?;
+        session.tenant_id = tenant_id;
+        Ok(())
+    }
+
+    /// Record activity, sliding the session's expiry forward by the idle timeout
     pub fn update_session_activity(&self, session_id: &str) {
         if let Ok(mut sessions) = self.sessions.write() {
             if let Some(session) = sessions.get_mut(session_id) {
-                session.last_activity = Utc::now();
+                let now = Utc::now();
+                session.last_activity = now;
+                let slid = now + self.policy.idle_timeout;
+                session.expires_at = match session.absolute_expires_at {
+                    Some(absolute) => slid.min(absolute),
+                    None => slid,
+                };
             }
         }
     }
 
+    /// Log out: end the session and the refresh tokens that could renew it
     pub fn invalidate_session(&self, session_id: &str) {
+        let removed = match self.sessions.write() {
+            Ok(mut sessions) => sessions.remove(session_id),
+            Err(_) => return,
+        };
+        if let Some(family_id) = removed.and_then(|s| s.family_id) {
+            self.revoke_family(&family_id);
+        }
+    }
+
+    /// Log a user out everywhere, returning how many sessions ended
+    pub fn invalidate_user_sessions(&self, user_did: &str) -> usize {
+        let Ok(mut sessions) = self.sessions.write() else {
+            return 0;
+        };
+        let Ok(mut refresh_tokens) = self.refresh_tokens.write() else {
+            return 0;
+        };
+        let families: std::collections::HashSet<String> = sessions
+            .values()
+            .filter(|s| s.user_did == user_did)
+            .filter_map(|s| s.family_id.clone())
+            .collect();
+        let before = sessions.len();
+        sessions.retain(|_, s| s.user_did != user_did);
+        refresh_tokens.retain(|_, t| !families.contains(&t.family_id));
+        before - sessions.len()
+    }
+
+    fn revoke_family(&self, family_id: &str) {
         if let Ok(mut sessions) = self.sessions.write() {
-            sessions.remove(session_id);
+            sessions.retain(|_, s| s.family_id.as_deref() != Some(family_id));
+        }
+        if let Ok(mut refresh_tokens) = self.refresh_tokens.write() {
+            refresh_tokens.retain(|_, t| t.family_id != family_id);
         }
     }
 }
 
+fn hash_token(token: &str) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(token.as_bytes());
+    hex::encode(hasher.finalize())
+}
+
 // Simplified API key handling without custom headers crate
 
+// The server's shared store, added to requests as an extension; an empty one otherwise
+fn request_auth_store(request: &Request) -> AuthStore {
+    request
+        .extensions()
+        .get::<AuthStore>()
+        .cloned()
+        .unwrap_or_default()
+}
+
 // Authentication middleware
 pub async fn auth_middleware(
     headers: HeaderMap,
@@ -216,17 +534,17 @@ pub async fn auth_middleware(
     next: Next,
 ) -> Result<Response, (StatusCode, Json<serde_json::Value>)> {
     let mut auth_context = AuthContext::default();
+    let auth_store = request_auth_store(&request);
 
     // Try API key authentication first
     if let Some(api_key_header) = headers.get("x-api-key") {
         if let Ok(api_key) = api_key_header.to_str() {
-            // In a real application, this would be dependency injected
-            let auth_store = AuthStore::new();
-
             if let Some(key_record) = auth_store.validate_api_key(api_key) {
                 auth_context.api_key_id = Some(key_record.key_id.clone());
                 auth_context.permissions = key_record.permissions.clone();
                 auth_context.rate_limit_tier = key_record.rate_limit_tier.clone();
+                auth_context.organization_did = key_record.organization_did.clone();
+                auth_context.tenant_id = key_record.tenant_id.clone();
 
                 // Update usage
                 auth_store.update_api_key_usage(&key_record.key_id);
@@ -244,12 +562,12 @@ pub async fn auth_middleware(
     // Try session authentication
     else if let Some(session_header) = headers.get("x-session-id") {
         if let Ok(session_id) = session_header.to_str() {
-            let auth_store = AuthStore::new();
-
             if let Some(session) = auth_store.validate_session(session_id) {
                 auth_context.session_id = Some(session.session_id.clone());
                 auth_context.user_did = Some(session.user_did.clone());
                 auth_context.permissions = session.permissions.clone();
+                auth_context.organization_did = session.organization_did.clone();
+                auth_context.tenant_id = session.tenant_id.clone();
 
                 // Update activity
                 auth_store.update_session_activity(&session.session_id);
@@ -278,18 +596,31 @@ pub async fn optional_auth_middleware(
     next: Next,
 ) -> Result<Response, std::convert::Infallible> {
     let mut auth_context = AuthContext::default();
+    let auth_store = request_auth_store(&request);
 
     // Try authentication but don't fail if not present
     if let Some(api_key_header) = headers.get("x-api-key") {
         if let Ok(api_key) = api_key_header.to_str() {
-            let auth_store = AuthStore::new();
             if let Some(key_record) = auth_store.validate_api_key(api_key) {
                 auth_context.api_key_id = Some(key_record.key_id.clone());
                 auth_context.permissions = key_record.permissions.clone();
                 auth_context.rate_limit_tier = key_record.rate_limit_tier.clone();
+                auth_context.organization_did = key_record.organization_did.clone();
+                auth_context.tenant_id = key_record.tenant_id.clone();
                 auth_store.update_api_key_usage(&key_record.key_id);
             }
         }
+    } else if let Some(session_header) = headers.get("x-session-id") {
+        if let Ok(session_id) = session_header.to_str() {
+            if let Some(session) = auth_store.validate_session(session_id) {
+                auth_context.session_id = Some(session.session_id.clone());
+                auth_context.user_did = Some(session.user_did.clone());
+                auth_context.permissions = session.permissions.clone();
+                auth_context.organization_did = session.organization_did.clone();
+                auth_context.tenant_id = session.tenant_id.clone();
+                auth_store.update_session_activity(&session.session_id);
+            }
+        }
     }
 
     request.extensions_mut().insert(auth_context);
@@ -319,6 +650,64 @@ impl AuthContext {
         }
         Ok(())
     }
+
+    pub fn tenant_scope(&self) -> TenantScope {
+        TenantScope::new(self.organization_did.clone())
+    }
+
+    /// Refuse callers scoped to an organization other than `organization_did`
+    pub fn require_organization(
+        &self,
+        organization_did: &str,
+    ) -> Result<(), (StatusCode, Json<serde_json::Value>)> {
+        if !self.tenant_scope().allows_did(organization_did) {
+            return Err((
+                StatusCode::FORBIDDEN,
+                Json(serde_json::json!({
+                    "error": "organization_scope",
+                    "message": "This credential is limited to another organization's data"
+                })),
+            ));
+        }
+        Ok(())
+    }
+
+    /// Refuse credentials bound to a different tenant than the API being called
+    /// (`None` for the host API). Unbound admins may manage any tenant's API
+    pub fn require_tenant(
+        &self,
+        tenant_id: Option<&str>,
+    ) -> Result<(), (StatusCode, Json<serde_json::Value>)> {
+        let allowed = match (self.tenant_id.as_deref(), tenant_id) {
+            (bound, called) if bound == called => true,
+            (None, Some(_)) => self.organization_did.is_none() && self.has_permission("admin"),
+            _ => false,
+        };
+        if !allowed {
+            return Err((
+                StatusCode::FORBIDDEN,
+                Json(serde_json::json!({
+                    "error": "tenant_scope",
+                    "message": "This credential is not valid for this tenant"
+                })),
+            ));
+        }
+        Ok(())
+    }
+
+    /// Refuse organization-scoped callers, for operations that span every organization
+    pub fn require_unscoped(&self) -> Result<(), (StatusCode, Json<serde_json::Value>)> {
+        if self.organization_did.is_some() {
+            return Err((
+                StatusCode::FORBIDDEN,
+                Json(serde_json::json!({
+                    "error": "organization_scope",
+                    "message": "Not available to organization-scoped credentials"
+                })),
+            ));
+        }
+        Ok(())
+    }
 }
 
 #[cfg(test)]
@@ -376,4 +765,176 @@ mod tests {
         context.permissions.push("admin".to_string());
         assert!(context.has_permission("delete")); // Admin has all permissions
     }
+
+    #[test]
+    fn test_scoped_credentials_carry_organization() {
+        let store = AuthStore::new();
+        let (_, api_key) = store
+            .create_scoped_api_key(
+                Some("did:plc:camp".to_string()),
+                vec!["read".to_string()],
+                None,
+                RateLimitTier::Basic,
+            )
+            .unwrap();
+        let key = store.validate_api_key(&api_key).unwrap();
+        assert_eq!(key.organization_did.as_deref(), Some("did:plc:camp"));
+
+        let session_id = store
+            .create_scoped_session(
+                "did:plc:counselor".to_string(),
+                Some("did:plc:camp".to_string()),
+                vec!["read".to_string()],
+                1,
+            )
+            .unwrap();
+        let session = store.validate_session(&session_id).unwrap();
+        assert_eq!(session.organization_did.as_deref(), Some("did:plc:camp"));
+    }
+
+    #[test]
+    fn test_cross_organization_isolation() {
+        let camp = AuthContext {
+            permissions: vec!["admin".to_string()],
+            organization_did: Some("did:plc:camp".to_string()),
+            ..AuthContext::default()
+        };
+        assert!(camp.require_organization("did:plc:camp").is_ok());
+        assert!(camp.require_organization("did:plc:school").is_err());
+        // Even an admin key scoped to one camp cannot run cross-organization operations
+        assert!(camp.require_unscoped().is_err());
+
+        let operator = AuthContext::default();
+        assert!(operator.require_organization("did:plc:school").is_ok());
+        assert!(operator.require_unscoped().is_ok());
+    }
+
+    #[test]
+    fn test_cross_tenant_isolation() {
+        let store = AuthStore::new();
+        let (key_id, api_key) = store
+            .create_api_key(vec!["admin".to_string()], None, RateLimitTier::Admin)
+            .unwrap();
+        store
+            .set_api_key_tenant(&key_id, Some("camp".to_string()))
+            .unwrap();
+        assert_eq!(
+            store
+                .validate_api_key(&api_key)
+                .unwrap()
+                .tenant_id
+                .as_deref(),
+            Some("camp")
+        );
+
+        let camp = AuthContext {
+            permissions: vec!["admin".to_string()],
+            tenant_id: Some("camp".to_string()),
+            ..AuthContext::default()
+        };
+        assert!(camp.require_tenant(Some("camp")).is_ok());
+        assert!(camp.require_tenant(Some("school")).is_err());
+        assert!(camp.require_tenant(None).is_err()); // Nor the host API
+
+        let host_admin = AuthContext {
+            permissions: vec!["admin".to_string()],
+            ..AuthContext::default()
+        };
+        assert!(host_admin.require_tenant(None).is_ok());
+        assert!(host_admin.require_tenant(Some("camp")).is_ok());
+
+        let host_reader = AuthContext {
+            permissions: vec!["read".to_string()],
+            ..AuthContext::default()
+        };
+        assert!(host_reader.require_tenant(Some("camp")).is_err());
+    }
+
+    #[test]
+    fn test_refresh_rotates_tokens() {
+        let store = AuthStore::new();
+        let first = store
+            .start_session(
+                "did:plc:test123".to_string(),
+                None,
+                vec!["read".to_string()],
+            )
+            .unwrap();
+        let second = store.refresh_session(&first.refresh_token).unwrap();
+
+        assert_ne!(second.session_id, first.session_id);
+        assert_ne!(second.refresh_token, first.refresh_token);
+        assert!(store.validate_session(&first.session_id).is_none());
+        let session = store.validate_session(&second.session_id).unwrap();
+        assert_eq!(session.permissions, vec!["read".to_string()]);
+    }
+
+    #[test]
+    fn test_refresh_token_reuse_revokes_family() {
+        let store = AuthStore::new();
+        let first = store
+            .start_session(
+                "did:plc:test123".to_string(),
+                None,
+                vec!["read".to_string()],
+            )
+            .unwrap();
+        let second = store.refresh_session(&first.refresh_token).unwrap();
+
+        // Replaying the rotated token ends the session and token issued in its place
+        assert_eq!(
+            store.refresh_session(&first.refresh_token).unwrap_err(),
+            RefreshError::Reused
+        );
+        assert!(store.validate_session(&second.session_id).is_none());
+        assert_eq!(
+            store.refresh_session(&second.refresh_token).unwrap_err(),
+            RefreshError::Invalid
+        );
+        assert_eq!(
+            store.refresh_session("not-a-token").unwrap_err(),
+            RefreshError::Invalid
+        );
+    }
+
+    #[test]
+    fn test_sliding_expiration_and_logout_all() {
+        let store = AuthStore::with_policy(SessionPolicy {
+            idle_timeout: Duration::minutes(30),
+            absolute_lifetime: Duration::hours(8),
+            refresh_lifetime: Duration::days(30),
+        });
+        let tokens = store
+            .start_session(
+                "did:plc:test123".to_string(),
+                None,
+                vec!["read".to_string()],
+            )
+            .unwrap();
+        let session = store.validate_session(&tokens.session_id).unwrap();
+        assert!(session.expires_at <= session.created_at + Duration::minutes(30));
+
+        store.update_session_activity(&tokens.session_id);
+        let slid = store.validate_session(&tokens.session_id).unwrap();
+        assert!(slid.expires_at >= session.expires_at);
+        assert!(slid.expires_at <= slid.absolute_expires_at.unwrap());
+
+        let other = store
+            .start_session(
+                "did:plc:test123".to_string(),
+                None,
+                vec!["read".to_string()],
+            )
+            .unwrap();
+        let bystander = store
+            .create_session("did:plc:other".to_string(), vec!["read".to_string()], 1)
+            .unwrap();
+        assert_eq!(store.invalidate_user_sessions("did:plc:test123"), 2);
+        assert!(store.validate_session(&other.session_id).is_none());
+        assert!(store.validate_session(&bystander).is_some());
+        assert_eq!(
+            store.refresh_session(&other.refresh_token).unwrap_err(),
+            RefreshError::Invalid
+        );
+    }
 }
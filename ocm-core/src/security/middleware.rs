@@ -1,12 +1,109 @@
+use crate::config::CorsConfig;
+use crate::core::correlation::{is_valid_request_id, new_request_id, REQUEST_ID_HEADER};
+use crate::core::error::OcmError;
+use crate::identity::plc::PlcIdentity;
+use crate::panics::catch_panic_async;
 use axum::{
-    extract::Request,
-    http::{header, HeaderMap, HeaderValue, StatusCode},
+    extract::{Request, State},
+    http::{header, HeaderMap, HeaderValue, Method, StatusCode},
     middleware::Next,
-    response::Response,
+    response::{IntoResponse, Response},
     Json,
 };
+use base64::{engine::general_purpose, Engine as _};
+use futures_util::FutureExt;
 use serde_json::json;
+use sha2::{Digest, Sha256};
 use std::convert::Infallible;
+use std::sync::Arc;
+use tracing::Instrument;
+
+// Largest error body rewritten to carry the request ID
+const MAX_ERROR_BODY_BYTES: usize = 64 * 1024;
+// Largest response body buffered to be signed
+const MAX_SIGNED_BODY_BYTES: usize = 256 * 1024 * 1024;
+// Label of the response signature in Signature-Input and Signature
+const SIGNATURE_LABEL: &str = "ocm";
+
+/// Request extension holding the correlation ID of the current HTTP request
+#[derive(Debug, Clone)]
+pub struct RequestId(pub String);
+
+// Request ID middleware: adopts a well-formed X-Request-ID or generates one, runs the
+// request inside a span carrying it, and returns it in the response headers and JSON errors
+pub async fn request_id_middleware(mut request: Request, next: Next) -> Response {
+    let request_id = request
+        .headers()
+        .get(REQUEST_ID_HEADER)
+        .and_then(|v| v.to_str().ok())
+        .filter(|id| is_valid_request_id(id))
+        .map(str::to_string)
+        .unwrap_or_else(new_request_id);
+    request
+        .extensions_mut()
+        .insert(RequestId(request_id.clone()));
+
+    let span = tracing::info_span!(
+        "http_request",
+        request_id = %request_id,
+        method = %request.method(),
+        path = %request.uri().path(),
+    );
+    let response = next.run(request).instrument(span).await;
+
+    let mut response = attach_request_id_to_error(response, &request_id).await;
+    if let Ok(value) = HeaderValue::from_str(&request_id) {
+        response.headers_mut().insert(REQUEST_ID_HEADER, value);
+    }
+    response
+}
+
+// Panic middleware: a handler that panics answers 500 like any other internal error,
+// instead of the connection being dropped. The panic hook has already recorded it
+pub async fn panic_middleware(request: Request, next: Next) -> Response {
+    let path = request.uri().path().to_string();
+    match catch_panic_async(&path, next.run(request).map(Ok::<_, OcmError>)).await {
+        Ok(response) => response,
+        Err(e) => {
+            tracing::error!("Request handler failed: {}", e);
+            create_error_response(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "INTERNAL_ERROR",
+                "Request could not be completed",
+            )
+            .into_response()
+        }
+    }
+}
+
+async fn attach_request_id_to_error(response: Response, request_id: &str) -> Response {
+    let status = response.status();
+    let is_json = response
+        .headers()
+        .get(header::CONTENT_TYPE)
+        .and_then(|ct| ct.to_str().ok())
+        .map(|ct| ct.starts_with("application/json"))
+        .unwrap_or(false);
+    if !(status.is_client_error() || status.is_server_error()) || !is_json {
+        return response;
+    }
+
+    let (mut parts, body) = response.into_parts();
+    let bytes = match axum::body::to_bytes(body, MAX_ERROR_BODY_BYTES).await {
+        Ok(bytes) => bytes,
+        Err(_) => return Response::from_parts(parts, axum::body::Body::empty()),
+    };
+
+    let body = match serde_json::from_slice::<serde_json::Value>(&bytes) {
+        Ok(serde_json::Value::Object(mut error)) => {
+            error.insert("request_id".to_string(), json!(request_id));
+            parts.headers.remove(header::CONTENT_LENGTH);
+            serde_json::Value::Object(error).to_string().into()
+        }
+        _ => bytes.into(),
+    };
+    Response::from_parts(parts, body)
+}
 // Removed unused imports
 
 // Security headers middleware
@@ -36,6 +133,33 @@ pub async fn security_headers_middleware(
         HeaderValue::from_static("max-age=31536000; includeSubDomains; preload"),
     );
 
+    insert_common_security_headers(headers);
+    Ok(response)
+}
+
+// Development variant: plain HTTP must not be upgraded or pinned by HSTS
+pub async fn development_security_headers_middleware(
+    request: Request,
+    next: Next,
+) -> Result<Response, Infallible> {
+    let mut response = next.run(request).await;
+
+    let headers = response.headers_mut();
+    headers.insert(
+        "Content-Security-Policy",
+        HeaderValue::from_static(
+            "default-src 'self'; script-src 'self' 'wasm-unsafe-eval'; \
+             connect-src 'self' ws: wss: http: https:; img-src 'self' data:; \
+             style-src 'self' 'unsafe-inline'; font-src 'self' data:; \
+             object-src 'none'; base-uri 'self'; frame-ancestors 'none';",
+        ),
+    );
+
+    insert_common_security_headers(headers);
+    Ok(response)
+}
+
+fn insert_common_security_headers(headers: &mut HeaderMap) {
     // X-Frame-Options
     headers.insert("X-Frame-Options", HeaderValue::from_static("DENY"));
 
@@ -69,10 +193,139 @@ pub async fn security_headers_middleware(
 
     // Server header (minimal information disclosure)
     headers.insert("Server", HeaderValue::from_static("OCM-Server"));
+}
+
+// Cross-origin isolation headers, required for SharedArrayBuffer, wasm threads and OPFS sync access
+pub async fn cross_origin_isolation_middleware(
+    request: Request,
+    next: Next,
+) -> Result<Response, Infallible> {
+    let mut response = next.run(request).await;
+    let headers = response.headers_mut();
+
+    headers.insert(
+        "Cross-Origin-Opener-Policy",
+        HeaderValue::from_static("same-origin"),
+    );
+    headers.insert(
+        "Cross-Origin-Embedder-Policy",
+        HeaderValue::from_static("require-corp"),
+    );
+    headers.insert(
+        "Cross-Origin-Resource-Policy",
+        HeaderValue::from_static("same-origin"),
+    );
 
     Ok(response)
 }
 
+/// RFC 9421 signature base for a response: the request path, status, content type (when
+/// set) and RFC 9530 Content-Digest, ending with the signature parameters line.
+/// Returns the base and the parameters for Signature-Input
+pub fn response_signature_base(
+    path: &str,
+    status: u16,
+    content_type: Option<&str>,
+    content_digest: &str,
+    created: i64,
+    key_id: &str,
+) -> (String, String) {
+    let mut components = vec![("\"@path\";req", path.to_string())];
+    components.push(("\"@status\"", status.to_string()));
+    if let Some(content_type) = content_type {
+        components.push(("\"content-type\"", content_type.to_string()));
+    }
+    components.push(("\"content-digest\"", content_digest.to_string()));
+
+    let covered: Vec<&str> = components.iter().map(|(name, _)| *name).collect();
+    let params = format!(
+        "({});created={};keyid=\"{}\";alg=\"ed25519\"",
+        covered.join(" "),
+        created,
+        key_id
+    );
+    let mut base = String::new();
+    for (name, value) in &components {
+        base.push_str(&format!("{}: {}\n", name, value));
+    }
+    base.push_str(&format!("\"@signature-params\": {}", params));
+    (base, params)
+}
+
+/// Sign API responses with the node identity (RFC 9421 HTTP Message Signatures), so
+/// consumers can check them through caches and proxies. Event streams and NDJSON
+/// exports are left unsigned, since signing would mean buffering bodies that are
+/// unbounded or never end
+pub async fn response_signature_middleware(
+    State(identity): State<Arc<PlcIdentity>>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let path = request.uri().path().to_string();
+    let response = next.run(request).await;
+    let content_type = response
+        .headers()
+        .get(header::CONTENT_TYPE)
+        .and_then(|ct| ct.to_str().ok())
+        .map(str::to_string);
+    let is_api = path.starts_with("/api/") || path.starts_with("/t/");
+    if !is_api
+        || content_type.as_deref().is_some_and(|ct| {
+            ct.starts_with("text/event-stream") || ct.starts_with("application/x-ndjson")
+        })
+    {
+        return response;
+    }
+
+    let (mut parts, body) = response.into_parts();
+    let bytes = match axum::body::to_bytes(body, MAX_SIGNED_BODY_BYTES).await {
+        Ok(bytes) => bytes,
+        Err(_) => {
+            return create_error_response(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "RESPONSE_TOO_LARGE",
+                "Response is too large to sign",
+            )
+            .into_response()
+        }
+    };
+
+    let content_digest = format!(
+        "sha-256=:{}:",
+        general_purpose::STANDARD.encode(Sha256::digest(&bytes))
+    );
+    let (base, params) = response_signature_base(
+        &path,
+        parts.status.as_u16(),
+        content_type.as_deref(),
+        &content_digest,
+        chrono::Utc::now().timestamp(),
+        &identity.did,
+    );
+    let signature = match identity.sign_payload(&base) {
+        Ok(signature) => signature,
+        Err(_) => {
+            return create_error_response(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "SIGNING_FAILED",
+                "Response could not be signed",
+            )
+            .into_response()
+        }
+    };
+
+    for (name, value) in [
+        ("content-digest", content_digest),
+        ("signature-input", format!("{}={}", SIGNATURE_LABEL, params)),
+        ("signature", format!("{}=:{}:", SIGNATURE_LABEL, signature)),
+    ] {
+        if let Ok(value) = HeaderValue::from_str(&value) {
+            parts.headers.insert(name, value);
+        }
+    }
+    Response::from_parts(parts, bytes.into())
+}
+
 // Request validation middleware
 pub async fn request_validation_middleware(
     mut request: Request,
@@ -183,37 +436,82 @@ pub async fn json_validation_middleware(
 }
 
 // CORS security middleware (more restrictive than basic CORS)
-pub async fn secure_cors_middleware(request: Request, next: Next) -> Result<Response, StatusCode> {
-    let mut response = next.run(request).await;
-    let headers = response.headers_mut();
-
-    // Only allow specific origins in production
-    headers.insert(
-        "Access-Control-Allow-Origin",
-        HeaderValue::from_static("https://localhost:8443"), // Update for production
-    );
-
-    headers.insert(
-        "Access-Control-Allow-Methods",
-        HeaderValue::from_static("GET, POST, PUT, DELETE, OPTIONS"),
-    );
+// Answers preflights directly and reflects only origins on the configured allowlist
+pub async fn secure_cors_middleware(
+    State(cors): State<Arc<CorsConfig>>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let origin = request
+        .headers()
+        .get(header::ORIGIN)
+        .and_then(|o| o.to_str().ok())
+        .map(str::to_string);
+    let allowed_origin = origin.filter(|o| is_origin_allowed(&cors, o));
+
+    let is_preflight = request.method() == Method::OPTIONS
+        && request
+            .headers()
+            .contains_key(header::ACCESS_CONTROL_REQUEST_METHOD);
+
+    if is_preflight {
+        let Some(origin) = allowed_origin else {
+            return StatusCode::FORBIDDEN.into_response();
+        };
+        let requested_method = request
+            .headers()
+            .get(header::ACCESS_CONTROL_REQUEST_METHOD)
+            .and_then(|m| m.to_str().ok())
+            .unwrap_or("");
+        if !cors
+            .allowed_methods
+            .iter()
+            .any(|m| m.eq_ignore_ascii_case(requested_method))
+        {
+            return StatusCode::FORBIDDEN.into_response();
+        }
 
-    headers.insert(
-        "Access-Control-Allow-Headers",
-        HeaderValue::from_static("Content-Type, Authorization, X-Requested-With"),
-    );
+        let mut response = StatusCode::NO_CONTENT.into_response();
+        let headers = response.headers_mut();
+        insert_cors_origin_headers(headers, &cors, &origin);
+        if let Ok(value) = HeaderValue::from_str(&cors.allowed_methods.join(", ")) {
+            headers.insert(header::ACCESS_CONTROL_ALLOW_METHODS, value);
+        }
+        if let Ok(value) = HeaderValue::from_str(&cors.allowed_headers.join(", ")) {
+            headers.insert(header::ACCESS_CONTROL_ALLOW_HEADERS, value);
+        }
+        headers.insert(
+            header::ACCESS_CONTROL_MAX_AGE,
+            HeaderValue::from(cors.max_age_seconds),
+        );
+        return response;
+    }
 
-    headers.insert(
-        "Access-Control-Max-Age",
-        HeaderValue::from_static("86400"), // 24 hours
-    );
+    let mut response = next.run(request).await;
+    if let Some(origin) = allowed_origin {
+        insert_cors_origin_headers(response.headers_mut(), &cors, &origin);
+    }
+    response
+}
 
-    headers.insert(
-        "Access-Control-Allow-Credentials",
-        HeaderValue::from_static("true"),
-    );
+pub fn is_origin_allowed(cors: &CorsConfig, origin: &str) -> bool {
+    cors.allowed_origins
+        .iter()
+        .any(|allowed| allowed == "*" || allowed.trim_end_matches('/') == origin)
+}
 
-    Ok(response)
+fn insert_cors_origin_headers(headers: &mut HeaderMap, cors: &CorsConfig, origin: &str) {
+    if let Ok(value) = HeaderValue::from_str(origin) {
+        headers.insert(header::ACCESS_CONTROL_ALLOW_ORIGIN, value);
+    }
+    if cors.allow_credentials {
+        headers.insert(
+            header::ACCESS_CONTROL_ALLOW_CREDENTIALS,
+            HeaderValue::from_static("true"),
+        );
+    }
+    // Responses differ per origin, so caches must key on it
+    headers.append(header::VARY, HeaderValue::from_static("Origin"));
 }
 
 // Request logging middleware for security monitoring
@@ -326,4 +624,63 @@ mod tests {
         assert_eq!(status, StatusCode::BAD_REQUEST);
         // Additional assertions would test the JSON structure
     }
+
+    #[test]
+    fn test_cors_origin_allowlist() {
+        let cors = CorsConfig {
+            allowed_origins: vec!["https://ocm.example/".to_string()],
+            ..CorsConfig::default()
+        };
+
+        assert!(is_origin_allowed(&cors, "https://ocm.example"));
+        assert!(!is_origin_allowed(&cors, "https://evil.example"));
+        assert!(!is_origin_allowed(&cors, "https://ocm.example.evil"));
+
+        let any = CorsConfig {
+            allowed_origins: vec!["*".to_string()],
+            allow_credentials: false,
+            ..CorsConfig::default()
+        };
+        assert!(is_origin_allowed(&any, "https://anything.example"));
+    }
+
+    #[test]
+    fn test_response_signature_base() {
+        let identity = PlcIdentity::generate(None).unwrap();
+        let (base, params) = response_signature_base(
+            "/api/v1/memories",
+            200,
+            Some("application/json"),
+            "sha-256=:X48E9qOokqqrvdts8nOJRJN3OWDUoyWxBf7kbu9DBPE=:",
+            1_700_000_000,
+            &identity.did,
+        );
+
+        assert_eq!(
+            base,
+            format!(
+                "\"@path\";req: /api/v1/memories\n\
+                 \"@status\": 200\n\
+                 \"content-type\": application/json\n\
+                 \"content-digest\": sha-256=:X48E9qOokqqrvdts8nOJRJN3OWDUoyWxBf7kbu9DBPE=:\n\
+                 \"@signature-params\": {}",
+                params
+            )
+        );
+        assert!(params.starts_with(
+            "(\"@path\";req \"@status\" \"content-type\" \"content-digest\");created=1700000000;"
+        ));
+
+        let public_key: [u8; 32] = general_purpose::STANDARD
+            .decode(&identity.keypair.public_key)
+            .unwrap()
+            .try_into()
+            .unwrap();
+        let signature = identity.sign_payload(&base).unwrap();
+        assert!(crate::identity::plc::verify_payload_signature(
+            &public_key,
+            &base,
+            &signature
+        ));
+    }
 }
@@ -1,9 +1,14 @@
 pub mod auth;
 pub mod middleware;
 pub mod rate_limiting;
+pub mod static_files;
 pub mod validation;
 
+pub use crate::core::tenancy;
+
 pub use auth::*;
 pub use middleware::*;
 pub use rate_limiting::*;
+pub use static_files::*;
+pub use tenancy::*;
 pub use validation::*;
@@ -7,7 +7,7 @@ use axum::{
 };
 use dashmap::DashMap;
 use serde_json::json;
-use std::sync::Arc;
+use std::sync::{Arc, Mutex};
 use std::time::{Duration, Instant};
 
 // Rate limiting configurations for different endpoint types
@@ -105,6 +105,123 @@ impl RateLimitState {
     }
 }
 
+// Failed-attempt lockout, for endpoints where each attempt guesses a secret
+#[derive(Debug, Clone)]
+pub struct LockoutConfig {
+    pub free_attempts: u32,     // Failures allowed before the first lockout
+    pub base_lockout: Duration, // Doubled with each failure past free_attempts
+    pub max_lockout: Duration,
+}
+
+impl Default for LockoutConfig {
+    fn default() -> Self {
+        Self {
+            free_attempts: 5,
+            base_lockout: Duration::from_secs(60),
+            max_lockout: Duration::from_secs(24 * 60 * 60),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+struct LockoutState {
+    failures: u32,
+    last_failure: Instant,
+    locked_until: Option<Instant>,
+}
+
+impl LockoutState {
+    // Not locked out and quiet for `window`, so its failures no longer count
+    fn is_stale(&self, now: Instant, window: Duration) -> bool {
+        self.locked_until.is_none_or(|until| until <= now)
+            && now.duration_since(self.last_failure) >= window
+    }
+}
+
+/// Consecutive failures per key (a client IP, a DID), with an exponentially growing
+/// lockout once they pass the free attempts. Keys quiet for the longest lockout are
+/// forgotten
+#[derive(Debug, Clone)]
+pub struct LockoutTracker {
+    config: LockoutConfig,
+    attempts: Arc<DashMap<String, LockoutState>>,
+    last_pruned: Arc<Mutex<Instant>>,
+}
+
+impl Default for LockoutTracker {
+    fn default() -> Self {
+        Self::new(LockoutConfig::default())
+    }
+}
+
+impl LockoutTracker {
+    pub fn new(config: LockoutConfig) -> Self {
+        Self {
+            config,
+            attempts: Arc::new(DashMap::new()),
+            last_pruned: Arc::new(Mutex::new(Instant::now())),
+        }
+    }
+
+    /// How much longer `key` is locked out, or None if it may try
+    pub fn locked_for(&self, key: &str) -> Option<Duration> {
+        let state = self.attempts.get(key)?;
+        let remaining = state.locked_until?.checked_duration_since(Instant::now())?;
+        (!remaining.is_zero()).then_some(remaining)
+    }
+
+    /// Count a failure, returning the consecutive failures so far and the lockout it
+    /// started, if any
+    pub fn record_failure(&self, key: &str) -> (u32, Option<Duration>) {
+        let now = Instant::now();
+        self.prune(now);
+        let mut state = self
+            .attempts
+            .entry(key.to_string())
+            .or_insert(LockoutState {
+                failures: 0,
+                last_failure: now,
+                locked_until: None,
+            });
+        if state.is_stale(now, self.config.max_lockout) {
+            state.failures = 0;
+        }
+        state.failures += 1;
+        state.last_failure = now;
+        if state.failures <= self.config.free_attempts {
+            return (state.failures, None);
+        }
+        let doublings = (state.failures - self.config.free_attempts - 1).min(31);
+        let lockout = self
+            .config
+            .base_lockout
+            .saturating_mul(1 << doublings)
+            .min(self.config.max_lockout);
+        state.locked_until = Some(now + lockout);
+        (state.failures, Some(lockout))
+    }
+
+    /// Forget `key`'s failures after it succeeds
+    pub fn record_success(&self, key: &str) {
+        self.attempts.remove(key);
+    }
+
+    // Drops stale keys, at most once per base lockout, so the map only holds keys
+    // that failed recently
+    fn prune(&self, now: Instant) {
+        {
+            let mut last_pruned = self.last_pruned.lock().unwrap_or_else(|e| e.into_inner());
+            if now.duration_since(*last_pruned) < self.config.base_lockout {
+                return;
+            }
+            *last_pruned = now;
+        }
+        let window = self.config.max_lockout;
+        self.attempts
+            .retain(|_, state| !state.is_stale(now, window));
+    }
+}
+
 // Global rate limiter store
 pub type RateLimiterStore = Arc<DashMap<String, RateLimitState>>;
 
@@ -241,4 +358,49 @@ mod tests {
         assert_eq!(limits::API_WRITE.requests_per_minute, 30);
         assert_eq!(limits::API_SENSITIVE.requests_per_minute, 10);
     }
+
+    #[test]
+    fn test_lockout_grows_exponentially() {
+        let tracker = LockoutTracker::new(LockoutConfig {
+            free_attempts: 2,
+            base_lockout: Duration::from_secs(60),
+            max_lockout: Duration::from_secs(300),
+        });
+        assert_eq!(tracker.record_failure("ip:10.0.0.1"), (1, None));
+        assert_eq!(tracker.record_failure("ip:10.0.0.1"), (2, None));
+        assert!(tracker.locked_for("ip:10.0.0.1").is_none());
+
+        let lockouts: Vec<_> = (0..4)
+            .map(|_| tracker.record_failure("ip:10.0.0.1").1.unwrap())
+            .collect();
+        assert_eq!(
+            lockouts,
+            [60, 120, 240, 300].map(Duration::from_secs).to_vec()
+        );
+        assert!(tracker.locked_for("ip:10.0.0.1").is_some());
+        assert!(tracker.locked_for("ip:10.0.0.2").is_none());
+
+        tracker.record_success("ip:10.0.0.1");
+        assert!(tracker.locked_for("ip:10.0.0.1").is_none());
+    }
+
+    #[test]
+    fn test_lockout_forgets_quiet_keys() {
+        let tracker = LockoutTracker::new(LockoutConfig {
+            free_attempts: 1,
+            base_lockout: Duration::from_millis(10),
+            max_lockout: Duration::from_millis(20),
+        });
+        tracker.record_failure("ip:10.0.0.1");
+        assert_eq!(
+            tracker.record_failure("ip:10.0.0.1").1,
+            Some(Duration::from_millis(10))
+        );
+        std::thread::sleep(Duration::from_millis(30));
+
+        // The next failure anywhere prunes the quiet key, and it starts over
+        tracker.record_failure("ip:10.0.0.2");
+        assert_eq!(tracker.attempts.len(), 1);
+        assert_eq!(tracker.record_failure("ip:10.0.0.1"), (1, None));
+    }
 }
@@ -0,0 +1,188 @@
+use crate::config::WebConfig;
+use axum::{
+    extract::{Request, State},
+    http::{header, HeaderValue, StatusCode, Uri},
+    middleware::{self, Next},
+    response::{IntoResponse, Response},
+    routing::get,
+    Router,
+};
+use std::path::PathBuf;
+use tower_http::services::ServeDir;
+
+// Only these file types are served from the web root, so source files and build
+// metadata that happen to live next to index.html are never exposed
+const ALLOWED_EXTENSIONS: &[&str] = &[
+    "html",
+    "js",
+    "mjs",
+    "wasm",
+    "css",
+    "json",
+    "map",
+    "webmanifest",
+    "png",
+    "jpg",
+    "jpeg",
+    "gif",
+    "svg",
+    "ico",
+    "webp",
+    "woff",
+    "woff2",
+    "txt",
+];
+
+// Shortest hex run treated as a content hash, e.g. `ocm_wasm-3f2a9b1c.js`
+const MIN_HASH_LEN: usize = 8;
+
+#[derive(Clone)]
+struct StaticFileState {
+    index_path: PathBuf,
+    spa_fallback: bool,
+    immutable_max_age_seconds: u64,
+}
+
+/// Router serving the web root with cache, MIME and SPA fallback handling
+pub fn static_file_router(config: &WebConfig) -> Router {
+    let state = StaticFileState {
+        index_path: config.root.join("index.html"),
+        spa_fallback: config.spa_fallback,
+        immutable_max_age_seconds: config.immutable_max_age_seconds,
+    };
+
+    let serve_dir =
+        ServeDir::new(&config.root).fallback(get(spa_fallback).with_state(state.clone()));
+
+    Router::new()
+        .fallback_service(serve_dir)
+        .layer(middleware::from_fn_with_state(
+            state,
+            static_asset_middleware,
+        ))
+}
+
+/// Whether a file name carries a content hash and can therefore be cached forever
+pub fn is_hashed_asset(path: &str) -> bool {
+    let file_name = path.rsplit('/').next().unwrap_or(path);
+    let stem = match file_name.rsplit_once('.') {
+        Some((stem, _)) => stem,
+        None => return false,
+    };
+
+    stem.split(['-', '.', '_'])
+        .skip(1)
+        .any(|part| part.len() >= MIN_HASH_LEN && part.chars().all(|c| c.is_ascii_hexdigit()))
+}
+
+/// Whether a request path may be served from the web root at all
+pub fn is_servable_path(path: &str) -> bool {
+    if path
+        .split('/')
+        .any(|segment| segment.starts_with('.') && !segment.is_empty())
+    {
+        return false; // Dotfiles and traversal attempts
+    }
+
+    let file_name = path.rsplit('/').next().unwrap_or(path);
+    match file_name.rsplit_once('.') {
+        Some((_, extension)) => {
+            ALLOWED_EXTENSIONS.contains(&extension.to_ascii_lowercase().as_str())
+        }
+        None => true, // Directories and client-side routes
+    }
+}
+
+fn content_type_override(path: &str) -> Option<&'static str> {
+    if path.ends_with(".wasm") {
+        Some("application/wasm")
+    } else if path.ends_with(".js") || path.ends_with(".mjs") {
+        Some("text/javascript; charset=utf-8")
+    } else if path.ends_with(".webmanifest") {
+        Some("application/manifest+json")
+    } else {
+        None
+    }
+}
+
+async fn static_asset_middleware(
+    State(state): State<StaticFileState>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let path = request.uri().path().to_string();
+    if !is_servable_path(&path) {
+        return StatusCode::NOT_FOUND.into_response();
+    }
+
+    let mut response = next.run(request).await;
+    if !response.status().is_success() {
+        return response;
+    }
+
+    let headers = response.headers_mut();
+    if let Some(content_type) = content_type_override(&path) {
+        headers.insert(header::CONTENT_TYPE, HeaderValue::from_static(content_type));
+    }
+
+    let cache_control = if is_hashed_asset(&path) {
+        format!(
+            "public, max-age={}, immutable",
+            state.immutable_max_age_seconds
+        )
+    } else {
+        // index.html and unhashed files must be revalidated so new builds are picked up
+        "no-cache".to_string()
+    };
+    if let Ok(value) = HeaderValue::from_str(&cache_control) {
+        headers.insert(header::CACHE_CONTROL, value);
+    }
+
+    response
+}
+
+/// Serve index.html for client-side routes; missing files keep their 404
+async fn spa_fallback(State(state): State<StaticFileState>, uri: Uri) -> Response {
+    let path = uri.path();
+    let file_name = path.rsplit('/').next().unwrap_or(path);
+    if !state.spa_fallback || file_name.contains('.') {
+        return StatusCode::NOT_FOUND.into_response();
+    }
+
+    match tokio::fs::read(&state.index_path).await {
+        Ok(contents) => (
+            [(
+                header::CONTENT_TYPE,
+                HeaderValue::from_static("text/html; charset=utf-8"),
+            )],
+            contents,
+        )
+            .into_response(),
+        Err(_) => StatusCode::NOT_FOUND.into_response(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_hashed_asset_detection() {
+        assert!(is_hashed_asset("/pkg/ocm_wasm-3f2a9b1c.js"));
+        assert!(is_hashed_asset("/assets/app.3f2a9b1c4d5e.css"));
+        assert!(!is_hashed_asset("/index.html"));
+        assert!(!is_hashed_asset("/pkg/ocm_wasm_bg.wasm"));
+        assert!(!is_hashed_asset("/pkg/deadbeef"));
+    }
+
+    #[test]
+    fn test_servable_paths() {
+        assert!(is_servable_path("/"));
+        assert!(is_servable_path("/pkg/ocm_wasm_bg.wasm"));
+        assert!(is_servable_path("/memories/123"));
+        assert!(!is_servable_path("/src/lib.rs"));
+        assert!(!is_servable_path("/Cargo.toml"));
+        assert!(!is_servable_path("/.git/config"));
+        assert!(!is_servable_path("/../secret.html"));
+    }
+}
@@ -0,0 +1,37 @@
+use chrono::TimeZone;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+
+/// Simulated time in milliseconds. It only moves when the driver delivers a
+/// message or is told to advance, so a replay sees exactly the same times.
+#[derive(Debug, Clone, Default)]
+pub struct VirtualClock {
+    now_ms: Arc<AtomicU64>,
+}
+
+impl VirtualClock {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn now_ms(&self) -> u64 {
+        self.now_ms.load(Ordering::SeqCst)
+    }
+
+    /// Move forward to `ms`; never moves backwards
+    pub fn advance_to(&self, ms: u64) {
+        self.now_ms.fetch_max(ms, Ordering::SeqCst);
+    }
+
+    pub fn advance(&self, ms: u64) {
+        self.now_ms.fetch_add(ms, Ordering::SeqCst);
+    }
+
+    /// RFC 3339 timestamp counted from the Unix epoch, for operation and message timestamps
+    pub fn timestamp(&self) -> String {
+        chrono::Utc
+            .timestamp_millis_opt(self.now_ms() as i64)
+            .unwrap()
+            .to_rfc3339()
+    }
+}
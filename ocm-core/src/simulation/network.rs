@@ -0,0 +1,223 @@
+use super::clock::VirtualClock;
+use crate::networking::protocol::{NetworkMessage, PeerInfo};
+use crate::networking::transport::{Delivery, Transport, TransportError};
+use futures_util::future::BoxFuture;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use std::collections::{BTreeMap, BTreeSet};
+use std::sync::{Arc, Mutex};
+
+/// Faults injected into every message sent after they are set
+#[derive(Debug, Clone)]
+pub struct FaultConfig {
+    pub drop_rate: f64, // Probability in [0, 1] that a message is silently lost
+    pub min_delay_ms: u64,
+    pub max_delay_ms: u64,
+}
+
+impl Default for FaultConfig {
+    fn default() -> Self {
+        Self {
+            drop_rate: 0.0,
+            min_delay_ms: 5,
+            max_delay_ms: 50,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TraceKind {
+    Sent,
+    Delivered,
+    Dropped,     // Lost to the configured drop rate
+    Partitioned, // Sender and receiver were on opposite sides of a partition
+}
+
+/// One step of a run; two runs with the same seed and script produce identical traces
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TraceEvent {
+    pub at_ms: u64,
+    pub seq: u64,
+    pub from: String,
+    pub to: String,
+    pub message_type: String,
+    pub kind: TraceKind,
+}
+
+#[derive(Debug, Clone)]
+pub struct Envelope {
+    pub seq: u64,
+    pub from: String,
+    pub to: String,
+    pub message: NetworkMessage,
+}
+
+struct NetworkState {
+    rng: StdRng,
+    faults: FaultConfig,
+    next_seq: u64,
+    in_flight: BTreeMap<(u64, u64), Envelope>, // (deliver_at_ms, seq) -> envelope
+    partitions: BTreeSet<(String, String)>,
+    trace: Vec<TraceEvent>,
+}
+
+impl NetworkState {
+    fn record(&mut self, at_ms: u64, envelope: &Envelope, kind: TraceKind) {
+        self.trace.push(TraceEvent {
+            at_ms,
+            seq: envelope.seq,
+            from: envelope.from.clone(),
+            to: envelope.to.clone(),
+            message_type: format!("{:?}", envelope.message.message_type),
+            kind,
+        });
+    }
+
+    fn is_partitioned(&self, from: &str, to: &str) -> bool {
+        self.partitions
+            .contains(&(from.to_string(), to.to_string()))
+    }
+}
+
+/// In-memory network shared by every simulated node. Delays, drops and
+/// partitions all draw from one seeded RNG, so a seed replays a run exactly.
+#[derive(Clone)]
+pub struct SimNetwork {
+    state: Arc<Mutex<NetworkState>>,
+    clock: VirtualClock,
+}
+
+impl SimNetwork {
+    pub fn new(seed: u64, faults: FaultConfig, clock: VirtualClock) -> Self {
+        Self {
+            state: Arc::new(Mutex::new(NetworkState {
+                rng: StdRng::seed_from_u64(seed),
+                faults,
+                next_seq: 0,
+                in_flight: BTreeMap::new(),
+                partitions: BTreeSet::new(),
+                trace: Vec::new(),
+            })),
+            clock,
+        }
+    }
+
+    fn state(&self) -> std::sync::MutexGuard<'_, NetworkState> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// A node's handle for sending onto this network
+    pub fn transport(&self, node_id: &str) -> SimTransport {
+        SimTransport {
+            node_id: node_id.to_string(),
+            network: self.clone(),
+        }
+    }
+
+    pub fn set_faults(&self, faults: FaultConfig) {
+        self.state().faults = faults;
+    }
+
+    /// Cut every link between the two sides, in both directions
+    pub fn partition(&self, side_a: &[&str], side_b: &[&str]) {
+        let mut state = self.state();
+        for a in side_a {
+            for b in side_b {
+                state.partitions.insert((a.to_string(), b.to_string()));
+                state.partitions.insert((b.to_string(), a.to_string()));
+            }
+        }
+    }
+
+    pub fn heal(&self) {
+        self.state().partitions.clear();
+    }
+
+    /// Queue a message with a random delay. A partitioned send fails like a
+    /// refused connection; a dropped one succeeds and is lost silently.
+    pub fn send(
+        &self,
+        from: &str,
+        to: &str,
+        message: NetworkMessage,
+    ) -> Result<(), TransportError> {
+        let now = self.clock.now_ms();
+        let mut state = self.state();
+        let envelope = Envelope {
+            seq: state.next_seq,
+            from: from.to_string(),
+            to: to.to_string(),
+            message,
+        };
+        state.next_seq += 1;
+
+        if state.is_partitioned(from, to) {
+            state.record(now, &envelope, TraceKind::Partitioned);
+            return Err(format!("{} is partitioned from {}", from, to).into());
+        }
+        state.record(now, &envelope, TraceKind::Sent);
+
+        let faults = state.faults.clone();
+        if state.rng.gen_bool(faults.drop_rate.clamp(0.0, 1.0)) {
+            state.record(now, &envelope, TraceKind::Dropped);
+            return Ok(());
+        }
+        let delay = state
+            .rng
+            .gen_range(faults.min_delay_ms..=faults.max_delay_ms.max(faults.min_delay_ms));
+        state
+            .in_flight
+            .insert((now + delay, envelope.seq), envelope);
+        Ok(())
+    }
+
+    /// Deliver the next message due at or before `until_ms`, advancing the clock to its
+    /// arrival. Messages whose link was partitioned while in flight are lost.
+    pub fn deliver_next(&self, until_ms: u64) -> Option<Envelope> {
+        let mut state = self.state();
+        loop {
+            let (&(deliver_at, seq), _) = state.in_flight.iter().next()?;
+            if deliver_at > until_ms {
+                return None;
+            }
+            let envelope = state.in_flight.remove(&(deliver_at, seq))?;
+            self.clock.advance_to(deliver_at);
+
+            if state.is_partitioned(&envelope.from, &envelope.to) {
+                state.record(deliver_at, &envelope, TraceKind::Partitioned);
+                continue;
+            }
+            state.record(deliver_at, &envelope, TraceKind::Delivered);
+            return Some(envelope);
+        }
+    }
+
+    pub fn in_flight(&self) -> usize {
+        self.state().in_flight.len()
+    }
+
+    pub fn trace(&self) -> Vec<TraceEvent> {
+        self.state().trace.clone()
+    }
+}
+
+/// Lets a real `OcmNetworking` send onto the simulated network
+pub struct SimTransport {
+    node_id: String,
+    network: SimNetwork,
+}
+
+impl Transport for SimTransport {
+    fn send<'a>(
+        &'a self,
+        peer: &'a PeerInfo,
+        message: &'a NetworkMessage,
+    ) -> BoxFuture<'a, Result<Delivery, TransportError>> {
+        // Delivery happens later, when the driver steps, so there is no acknowledgment yet
+        let result = self
+            .network
+            .send(&self.node_id, &peer.peer_id, message.clone())
+            .map(|()| Delivery::default());
+        Box::pin(async move { result })
+    }
+}
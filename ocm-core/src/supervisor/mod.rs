@@ -0,0 +1,263 @@
+//! Owns a process's long-running background tasks. Each runs in its own tokio task;
+//! one that panics is logged and restarted with backoff, and the state of every task
+//! is reported to the database for the health endpoint.
+
+use crate::config::SupervisorConfig;
+use crate::core::models::{TaskHealth, TaskState};
+use crate::panics::panic_message;
+use crate::persistence::database::Database;
+use std::collections::BTreeMap;
+use std::future::Future;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+const REPORT_INTERVAL_SECS: u64 = 10;
+
+/// What to do when a supervised task ends
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RestartPolicy {
+    Always,  // Background loops, which should never end
+    OnPanic, // Tasks that may finish, e.g. when the channel they watch closes
+    Never,
+}
+
+#[derive(Clone)]
+pub struct TaskSupervisor {
+    process: String,
+    config: SupervisorConfig,
+    tasks: Arc<Mutex<BTreeMap<String, TaskHealth>>>,
+}
+
+impl TaskSupervisor {
+    /// `process` names the reporting process in the health endpoint, e.g. "node"
+    pub fn new(process: &str, config: SupervisorConfig) -> Self {
+        Self {
+            process: process.to_string(),
+            config,
+            tasks: Arc::new(Mutex::new(BTreeMap::new())),
+        }
+    }
+
+    /// Run the future `task` makes under supervision, making a fresh one for each restart
+    pub fn spawn<F, Fut>(&self, name: &str, policy: RestartPolicy, task: F)
+    where
+        F: Fn() -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = ()> + Send + 'static,
+    {
+        let supervisor = self.clone();
+        let name = name.to_string();
+        let now = chrono::Utc::now().to_rfc3339();
+        self.tasks_lock().insert(
+            name.clone(),
+            TaskHealth {
+                process: self.process.clone(),
+                name: name.clone(),
+                state: TaskState::Running,
+                restarts: 0,
+                last_panic: None,
+                started_at: now.clone(),
+                reported_at: now,
+            },
+        );
+
+        tokio::spawn(async move {
+            let mut crashes: u32 = 0;
+            loop {
+                let started = Instant::now();
+                let panic = match tokio::spawn(task()).await {
+                    Ok(()) => None,
+                    Err(e) if e.is_panic() => Some(panic_message(&*e.into_panic())),
+                    Err(_) => None, // Cancelled at runtime shutdown
+                };
+
+                let restart = match policy {
+                    RestartPolicy::Always => true,
+                    RestartPolicy::OnPanic => panic.is_some(),
+                    RestartPolicy::Never => false,
+                };
+                if let Some(message) = &panic {
+                    eprintln!("💥 Task {} panicked: {}", name, message);
+                }
+                if !restart {
+                    let state = if panic.is_some() {
+                        TaskState::Failed
+                    } else {
+                        TaskState::Stopped
+                    };
+                    supervisor.update(&name, state, panic);
+                    return;
+                }
+
+                if started.elapsed() >= Duration::from_secs(supervisor.config.stable_after_seconds)
+                {
+                    crashes = 0;
+                }
+                crashes += 1;
+                if supervisor.config.max_restarts > 0 && crashes > supervisor.config.max_restarts {
+                    eprintln!(
+                        "❌ Task {} ended {} times in a row; no longer restarting it",
+                        name, crashes
+                    );
+                    supervisor.update(&name, TaskState::Failed, panic);
+                    return;
+                }
+
+                let backoff = supervisor.backoff(crashes);
+                eprintln!("🔁 Restarting task {} in {:?}", name, backoff);
+                supervisor.update(&name, TaskState::Backoff, panic);
+                tokio::time::sleep(backoff).await;
+                supervisor.update(&name, TaskState::Running, None);
+            }
+        });
+    }
+
+    /// Every supervised task as it stands now
+    pub fn health(&self) -> Vec<TaskHealth> {
+        self.tasks_lock().values().cloned().collect()
+    }
+
+    /// Write task health to the database on a fixed interval, replacing what this
+    /// process reported before it last started
+    pub fn report_to(&self, database: Arc<Database>) {
+        let supervisor = self.clone();
+        tokio::spawn(async move {
+            let mut interval =
+                tokio::time::interval(tokio::time::Duration::from_secs(REPORT_INTERVAL_SECS));
+
+            loop {
+                interval.tick().await;
+
+                let now = chrono::Utc::now().to_rfc3339();
+                let mut tasks = supervisor.health();
+                for task in &mut tasks {
+                    task.reported_at = now.clone();
+                }
+                if let Err(e) = database.replace_task_health(&supervisor.process, &tasks) {
+                    eprintln!("Failed to report task health: {}", e);
+                }
+            }
+        });
+    }
+
+    /// Delay before restart number `crashes` in a row: doubling from the initial backoff
+    fn backoff(&self, crashes: u32) -> Duration {
+        let initial = Duration::from_millis(self.config.initial_backoff_ms);
+        let max = Duration::from_secs(self.config.max_backoff_seconds);
+        initial
+            .checked_mul(2u32.saturating_pow(crashes.saturating_sub(1)))
+            .map_or(max, |backoff| backoff.min(max))
+    }
+
+    fn update(&self, name: &str, state: TaskState, panic: Option<String>) {
+        if let Some(task) = self.tasks_lock().get_mut(name) {
+            if state == TaskState::Backoff {
+                task.restarts += 1;
+            }
+            if panic.is_some() {
+                task.last_panic = panic;
+            }
+            task.state = state;
+        }
+    }
+
+    fn tasks_lock(&self) -> std::sync::MutexGuard<'_, BTreeMap<String, TaskHealth>> {
+        self.tasks.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicU32, Ordering};
+
+    fn supervisor(max_restarts: u32, stable_after_seconds: u64) -> TaskSupervisor {
+        TaskSupervisor::new(
+            "test",
+            SupervisorConfig {
+                initial_backoff_ms: 1,
+                max_backoff_seconds: 1,
+                max_restarts,
+                stable_after_seconds,
+            },
+        )
+    }
+
+    /// Wait for the task named "task" to reach `state`
+    async fn wait_for(supervisor: &TaskSupervisor, state: TaskState) -> TaskHealth {
+        for _ in 0..500 {
+            let task = supervisor.health().into_iter().next().unwrap();
+            if task.state == state {
+                return task;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        panic!("Task never reached {:?}", state);
+    }
+
+    #[test]
+    fn test_backoff_doubles_up_to_the_maximum() {
+        let supervisor = TaskSupervisor::new(
+            "test",
+            SupervisorConfig {
+                initial_backoff_ms: 100,
+                max_backoff_seconds: 1,
+                max_restarts: 0,
+                stable_after_seconds: 60,
+            },
+        );
+        assert_eq!(supervisor.backoff(1), Duration::from_millis(100));
+        assert_eq!(supervisor.backoff(2), Duration::from_millis(200));
+        assert_eq!(supervisor.backoff(4), Duration::from_millis(800));
+        assert_eq!(supervisor.backoff(5), Duration::from_secs(1));
+        assert_eq!(supervisor.backoff(u32::MAX), Duration::from_secs(1));
+    }
+
+    #[tokio::test]
+    async fn test_panicking_task_fails_after_max_restarts() {
+        let supervisor = supervisor(2, 60);
+        let runs = Arc::new(AtomicU32::new(0));
+        let counter = runs.clone();
+        supervisor.spawn("task", RestartPolicy::OnPanic, move || {
+            let counter = counter.clone();
+            async move {
+                counter.fetch_add(1, Ordering::SeqCst);
+                panic!("boom");
+            }
+        });
+
+        let task = wait_for(&supervisor, TaskState::Failed).await;
+        assert_eq!(task.restarts, 2);
+        assert_eq!(runs.load(Ordering::SeqCst), 3);
+        assert_eq!(task.last_panic.as_deref(), Some("boom"));
+    }
+
+    #[tokio::test]
+    async fn test_stable_runs_reset_the_crash_count() {
+        // Every run counts as stable, so crashes never add up to max_restarts
+        let supervisor = supervisor(1, 0);
+        let runs = Arc::new(AtomicU32::new(0));
+        let counter = runs.clone();
+        supervisor.spawn("task", RestartPolicy::OnPanic, move || {
+            let counter = counter.clone();
+            async move {
+                if counter.fetch_add(1, Ordering::SeqCst) < 4 {
+                    panic!("boom");
+                }
+            }
+        });
+
+        let task = wait_for(&supervisor, TaskState::Stopped).await;
+        assert_eq!(task.restarts, 4);
+        assert_eq!(runs.load(Ordering::SeqCst), 5);
+    }
+
+    #[tokio::test]
+    async fn test_finished_task_is_not_restarted_under_on_panic() {
+        let supervisor = supervisor(0, 60);
+        supervisor.spawn("task", RestartPolicy::OnPanic, || async {});
+
+        let task = wait_for(&supervisor, TaskState::Stopped).await;
+        assert_eq!(task.restarts, 0);
+        assert_eq!(task.last_panic, None);
+    }
+}
@@ -1,33 +1,31 @@
-use crate::core::models::SignedMemory;
-use crate::networking::protocol::{MessageType, OcmNetworking};
+use crate::config::SyncPolicyConfig;
+use crate::core::ids::MemoryId;
+use crate::core::memory_types::{MemoryTypeRegistry, MergeStrategy};
+use crate::core::models::{
+    MemoryHeader, PeerPresence, SignedMemory, SyncWatermark, CONFLICT_DETECTED_EVENT,
+    SYNC_COMPLETED_EVENT,
+};
+use crate::networking::groups::PeerGroupStats;
+use crate::networking::ingest::IngestDecision;
+use crate::networking::peers::PeerEvent;
+use crate::networking::protocol::{
+    MessageType, NodeRole, OcmNetworking, MEMORY_HEADERS, MEMORY_SYNC,
+};
 use crate::persistence::database::Database;
-use crate::sync::crdt::{CrdtManager, CrdtMemory};
-use serde::{Deserialize, Serialize};
+use crate::persistence::snapshot::Snapshot;
+use crate::supervisor::{RestartPolicy, TaskSupervisor};
+use crate::sync::crdt::{ConflictType, CrdtManager, CrdtMemory};
+use crate::sync::patch::{self, PatchOperation, PatchedMemory};
+use ocm_protocol::ingest::IngestStats;
+use ocm_protocol::patch::MemoryDiff;
 use std::collections::{HashMap, HashSet};
 use std::sync::Arc;
+use tokio::sync::broadcast::error::RecvError;
 use tokio::sync::Mutex;
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct SyncRequest {
-    pub requesting_peer: String,
-    pub last_sync_timestamp: Option<String>,
-    pub known_memory_hashes: Vec<String>,
-}
-
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct SyncResponse {
-    pub responding_peer: String,
-    pub memories: Vec<SignedMemory>,
-    pub missing_hashes: Vec<String>,
-}
-
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct MemoryVector {
-    pub peer_id: String,
-    pub memory_hash: String,
-    pub timestamp: String,
-    pub version: u64,
-}
+pub use ocm_protocol::sync::{
+    MemoryVector, PartialSyncPolicy, SyncPriority, SyncRequest, SyncResponse,
+};
 
 pub struct SyncManager {
     pub local_peer_id: String,
@@ -35,11 +33,14 @@ pub struct SyncManager {
     pub networking: Arc<OcmNetworking>,
     pub sync_state: Arc<Mutex<SyncState>>,
     pub crdt_manager: Arc<Mutex<CrdtManager>>,
+    pub sync_policy: Arc<Mutex<SyncPolicyConfig>>,
+    pub memory_types: Arc<MemoryTypeRegistry>,
 }
 
 #[derive(Debug)]
 pub struct SyncState {
     pub last_sync_per_peer: HashMap<String, chrono::DateTime<chrono::Utc>>,
+    pub synced_digests: HashMap<String, String>, // peer_id -> our memory set digest at its last sync
     pub sync_in_progress: HashSet<String>,
     pub memory_versions: HashMap<String, u64>, // memory_hash -> version
 }
@@ -89,51 +90,204 @@ impl SyncManager {
     ) -> Self {
         let crdt_manager = CrdtManager::new(local_peer_id.clone());
 
+        // Peers keep their node ID across restarts, so earlier sync progress still applies
+        let mut last_sync_per_peer = HashMap::new();
+        let mut synced_digests = HashMap::new();
+        let local_count = database
+            .memory_set_digest()
+            .map(|(count, _)| count)
+            .unwrap_or_default();
+        match database.list_peer_sync_state() {
+            Ok(watermarks) => {
+                for watermark in watermarks {
+                    // Fewer memories than right after the sync means the database was restored
+                    // from an older copy; resuming would skip what it lost, so start over
+                    if watermark
+                        .memory_count
+                        .is_some_and(|count| count > local_count)
+                    {
+                        eprintln!(
+                            "⚠️  Sync state for {} is ahead of the database, re-syncing from scratch",
+                            watermark.peer_node_id
+                        );
+                        continue;
+                    }
+                    if let Ok(synced_at) =
+                        chrono::DateTime::parse_from_rfc3339(&watermark.synced_at)
+                    {
+                        last_sync_per_peer.insert(
+                            watermark.peer_node_id.clone(),
+                            synced_at.with_timezone(&chrono::Utc),
+                        );
+                    }
+                    if let Some(digest) = watermark.memory_digest {
+                        synced_digests.insert(watermark.peer_node_id, digest);
+                    }
+                }
+            }
+            Err(e) => eprintln!("⚠️  Failed to load peer sync state: {}", e),
+        }
+
         SyncManager {
             local_peer_id,
             database,
             networking,
             sync_state: Arc::new(Mutex::new(SyncState {
-                last_sync_per_peer: HashMap::new(),
+                last_sync_per_peer,
+                synced_digests,
                 sync_in_progress: HashSet::new(),
                 memory_versions: HashMap::new(),
             })),
             crdt_manager: Arc::new(Mutex::new(crdt_manager)),
+            sync_policy: Arc::new(Mutex::new(SyncPolicyConfig::default())),
+            memory_types: Arc::new(MemoryTypeRegistry::with_builtin_types()),
         }
     }
 
-    pub async fn start_sync_service(&self) -> Result<(), Box<dyn std::error::Error>> {
+    /// Share a registry the application or its plugins register memory types with
+    pub fn with_memory_types(mut self, memory_types: Arc<MemoryTypeRegistry>) -> Self {
+        self.memory_types = memory_types;
+        self
+    }
+
+    /// Replace the partial sync policy used for subsequent sync requests
+    pub async fn configure_policy(&self, config: &SyncPolicyConfig) {
+        *self.sync_policy.lock().await = config.clone();
+    }
+
+    pub async fn start_sync_service(
+        &self,
+        supervisor: &TaskSupervisor,
+    ) -> Result<(), Box<dyn std::error::Error>> {
         let sync_state = self.sync_state.clone();
         let _database = self.database.clone();
         let _local_peer_id = self.local_peer_id.clone();
 
         // Start periodic sync with all known peers
-        tokio::spawn(async move {
-            let mut interval = tokio::time::interval(tokio::time::Duration::from_secs(30));
-
-            loop {
-                interval.tick().await;
+        supervisor.spawn("sync_check", RestartPolicy::Always, move || {
+            let sync_state = sync_state.clone();
+            async move {
+                let mut interval = tokio::time::interval(tokio::time::Duration::from_secs(30));
+
+                loop {
+                    interval.tick().await;
+
+                    // Get list of known peers and sync with them
+                    // This would be integrated with the networking layer
+                    println!("🔄 Periodic sync check initiated");
+
+                    // Clean up stale sync operations (older than 5 minutes)
+                    {
+                        let state = sync_state.lock().await;
+                        // In a real implementation, we'd track sync start times and clean up stale ones
+                        // For now, just log the active syncs
+                        if !state.sync_in_progress.is_empty() {
+                            println!("🔄 Active syncs: {:?}", state.sync_in_progress);
+                        }
+                    }
+                }
+            }
+        });
 
-                // Get list of known peers and sync with them
-                // This would be integrated with the networking layer
-                println!("🔄 Periodic sync check initiated");
+        Ok(())
+    }
 
-                // Clean up stale sync operations (older than 5 minutes)
-                {
-                    let state = sync_state.lock().await;
-                    // In a real implementation, we'd track sync start times and clean up stale ones
-                    // For now, just log the active syncs
-                    if !state.sync_in_progress.is_empty() {
-                        println!("🔄 Active syncs: {:?}", state.sync_in_progress);
+    /// Sync with peers as soon as they join the shared peer store, and stop tracking
+    /// syncs with peers that leave it
+    pub fn start_peer_watch(self: &Arc<Self>, supervisor: &TaskSupervisor) {
+        let manager = self.clone();
+
+        supervisor.spawn("sync_peer_watch", RestartPolicy::OnPanic, move || {
+            let mut events = manager.networking.peers.subscribe();
+            let manager = manager.clone();
+            async move {
+                loop {
+                    match events.recv().await {
+                        Ok(PeerEvent::Added(peer)) => {
+                            if let Err(e) = manager.sync_with_peer(&peer.peer_id).await {
+                                eprintln!("Initial sync with {} failed: {}", peer.peer_id, e);
+                            }
+                        }
+                        Ok(PeerEvent::Removed(peer_id)) => {
+                            manager
+                                .sync_state
+                                .lock()
+                                .await
+                                .sync_in_progress
+                                .remove(&peer_id);
+                        }
+                        Ok(PeerEvent::Updated(_)) => {}
+                        Err(RecvError::Lagged(_)) => continue,
+                        Err(RecvError::Closed) => break,
                     }
                 }
             }
         });
+    }
 
-        Ok(())
+    /// Periodically tell trusted peers this node is online, and whether it is syncing
+    pub fn start_presence(self: &Arc<Self>, supervisor: &TaskSupervisor) {
+        let manager = self.clone();
+
+        supervisor.spawn("presence", RestartPolicy::Always, move || {
+            let manager = manager.clone();
+            async move {
+                let mut interval =
+                    tokio::time::interval(manager.networking.presence.interval().await);
+
+                loop {
+                    interval.tick().await;
+
+                    let (syncing, last_sync_at) = {
+                        let state = manager.sync_state.lock().await;
+                        (
+                            !state.sync_in_progress.is_empty(),
+                            state.last_sync_per_peer.values().max().copied(),
+                        )
+                    };
+                    let did = {
+                        let ocm = manager.networking.ocm_protocol.lock().await;
+                        ocm.current_identity().map(|identity| identity.did.clone())
+                    };
+                    let Some(update) = manager
+                        .networking
+                        .presence
+                        .local_update(
+                            &manager.local_peer_id,
+                            did.as_deref(),
+                            syncing,
+                            last_sync_at,
+                        )
+                        .await
+                    else {
+                        continue;
+                    };
+                    if let Err(e) = manager.networking.broadcast_presence(&update).await {
+                        eprintln!("Failed to announce presence: {}", e);
+                    }
+                }
+            }
+        });
     }
 
     pub async fn sync_with_peer(&self, peer_id: &str) -> Result<(), Box<dyn std::error::Error>> {
+        // Respect the configured sync windows and idle requirement on metered links
+        if !self.networking.bandwidth.sync_allowed_now().await {
+            println!(
+                "⏸️  Sync with {} deferred until the next sync window",
+                peer_id
+            );
+            return Ok(());
+        }
+
+        // Peers that advertise capabilities without memory sync are left alone
+        if let Some(peer) = self.networking.peers.get(peer_id).await {
+            if !peer.supports(MEMORY_SYNC) {
+                println!("⏭️  Peer {} does not sync memories", peer_id);
+                return Ok(());
+            }
+        }
+
         // Check if sync is already in progress with this peer
         {
             let mut state = self.sync_state.lock().await;
@@ -146,13 +300,21 @@ impl SyncManager {
         // Ensure cleanup happens even if sync fails
         let mut cleanup_guard = SyncCleanupGuard::new(self.sync_state.clone(), peer_id.to_string());
 
-        let last_sync = {
+        let (last_sync, synced_digest) = {
             let state = self.sync_state.lock().await;
-            state.last_sync_per_peer.get(peer_id).cloned()
+            (
+                state.last_sync_per_peer.get(peer_id).cloned(),
+                state.synced_digests.get(peer_id).cloned(),
+            )
         };
 
-        // Get our known memory hashes since last sync
-        let known_memories = self.database.list_signed_memories()?;
+        // Get our known memory hashes since last sync; none if nothing was stored since
+        let (_, digest) = self.database.memory_set_digest()?;
+        let known_memories = if synced_digest.as_deref() == Some(digest.as_str()) {
+            Vec::new()
+        } else {
+            self.database.list_signed_memories()?
+        };
         let known_hashes: Vec<String> = known_memories
             .iter()
             .filter(|memory| {
@@ -167,22 +329,52 @@ impl SyncManager {
             .map(|memory| memory.content_hash.clone())
             .collect();
 
+        // Full nodes ask for everything unless they send a hot set or priorities, with
+        // registered memory type defaults wherever the config sets none. Archive nodes
+        // always ask for everything; light nodes take headers of all but critical types
+        let role = self.networking.capabilities.role();
+        let policy = {
+            let config = self.sync_policy.lock().await;
+            let partial = config.hot_set_days.is_some() || !config.priorities.is_empty();
+            if role == NodeRole::Archive || (role == NodeRole::Full && !partial) {
+                None
+            } else {
+                let mut policy = config.partial_sync_policy();
+                if role == NodeRole::Light {
+                    policy.default_priority = SyncPriority::Background;
+                    policy
+                        .priorities
+                        .retain(|_, priority| *priority == SyncPriority::Critical);
+                }
+                for (memory_type, priority) in self.memory_types.sync_priorities() {
+                    if role == NodeRole::Full || priority == SyncPriority::Critical {
+                        policy.priorities.entry(memory_type).or_insert(priority);
+                    }
+                }
+                Some(policy)
+            }
+        };
+
         // Create sync request
         let sync_request = SyncRequest {
             requesting_peer: self.local_peer_id.clone(),
             last_sync_timestamp: last_sync.map(|dt| dt.to_rfc3339()),
             known_memory_hashes: known_hashes,
+            policy,
         };
 
         // Send sync request via networking layer
-        let _message = OcmNetworking::create_authenticated_message(
+        let message = OcmNetworking::create_authenticated_message(
             MessageType::MemoryRequest,
             serde_json::to_string(&sync_request)?,
             self.local_peer_id.clone(),
         );
 
         // This would be sent through the networking layer
-        println!("📡 Requesting sync from peer: {}", peer_id);
+        println!(
+            "📡 Requesting sync from peer: {} (request {})",
+            peer_id, message.request_id
+        );
 
         // Mark sync as complete (cleanup_guard will handle removal from sync_in_progress)
         cleanup_guard.complete().await;
@@ -218,12 +410,49 @@ impl SyncManager {
                     true
                 }
             })
-            .filter(|memory| {
-                // Only send memories they don't already have
-                !request.known_memory_hashes.contains(&memory.content_hash)
-            })
             .collect();
 
+        // Only share what the requesting peer's groups allow
+        let requester = self.networking.peers.get(from_peer).await;
+        let peer_did = requester.as_ref().and_then(|p| p.did.clone());
+        // Peers that cannot fetch bodies on demand get every memory in full
+        let sends_headers = requester.is_none_or(|p| p.supports(MEMORY_HEADERS));
+        let peer_groups = &self.networking.peer_groups;
+        let mut allowed_memories = Vec::new();
+        for memory in memories_to_send {
+            if peer_groups
+                .allows(from_peer, peer_did.as_deref(), &memory)
+                .await
+            {
+                allowed_memories.push(memory);
+            }
+        }
+
+        // They already hold this content, possibly under another memory ID, so send
+        // only the header and let them resolve the body from their content store
+        let known_hashes: HashSet<&String> = request.known_memory_hashes.iter().collect();
+        let (known_content, mut memories_to_send): (Vec<SignedMemory>, Vec<SignedMemory>) =
+            allowed_memories
+                .into_iter()
+                .partition(|memory| sends_headers && known_hashes.contains(&memory.content_hash));
+        let mut headers: Vec<MemoryHeader> = known_content.iter().map(MemoryHeader::from).collect();
+
+        // Partial sync: highest priority first, newest first within a class, and
+        // anything outside the requester's hot set goes as a header only
+        if let Some(policy) = &request.policy {
+            memories_to_send.sort_by(|a, b| {
+                policy
+                    .priority_for(&a.memory_type)
+                    .cmp(&policy.priority_for(&b.memory_type))
+                    .then_with(|| b.timestamp.cmp(&a.timestamp))
+            });
+            let (full, header_only): (Vec<SignedMemory>, Vec<SignedMemory>) = memories_to_send
+                .into_iter()
+                .partition(|memory| !sends_headers || policy.wants_full_content(memory));
+            headers.extend(header_only.iter().map(MemoryHeader::from));
+            memories_to_send = full;
+        }
+
         // Find memories they have that we don't
         let our_hashes: HashSet<String> = self
             .database
@@ -240,9 +469,10 @@ impl SyncManager {
             .collect();
 
         println!(
-            "🔍 Sync request from {}: sending {} memories, requesting {} missing",
+            "🔍 Sync request from {}: sending {} memories and {} headers, requesting {} missing",
             from_peer,
             memories_to_send.len(),
+            headers.len(),
             missing_hashes.len()
         );
 
@@ -250,6 +480,7 @@ impl SyncManager {
             responding_peer: self.local_peer_id.clone(),
             memories: memories_to_send,
             missing_hashes,
+            headers,
         })
     }
 
@@ -259,11 +490,65 @@ impl SyncManager {
     ) -> Result<(), Box<dyn std::error::Error>> {
         let mut stored_count = 0;
         let mut conflict_count = 0;
+        let peer_did = self
+            .networking
+            .peers
+            .get(&response.responding_peer)
+            .await
+            .and_then(|peer| peer.did);
 
         // Store received memories using CRDT conflict resolution
         for memory in response.memories {
             // Verify memory integrity and signature
-            if memory.verify_hash() {
+            let decision = self
+                .networking
+                .ingest
+                .check_memory(
+                    &self.networking.ocm_protocol,
+                    &memory,
+                    &response.responding_peer,
+                )
+                .await;
+            if matches!(decision, IngestDecision::Store { .. }) {
+                if let Err(e) = self
+                    .memory_types
+                    .validate(&memory.memory_type, &memory.memory_data)
+                {
+                    eprintln!(
+                        "❌ Rejected {} memory {} from peer {}: {}",
+                        memory.memory_type, memory.id, response.responding_peer, e
+                    );
+                    continue;
+                }
+                match self
+                    .networking
+                    .review
+                    .admit(
+                        &memory,
+                        &response.responding_peer,
+                        peer_did.as_deref(),
+                        None,
+                    )
+                    .await
+                {
+                    Ok(true) => {}
+                    Ok(false) => continue,
+                    Err(e) => {
+                        eprintln!("⚠️  Failed to hold memory {} for review: {}", memory.id, e);
+                        continue;
+                    }
+                }
+
+                let strategy = self.memory_types.merge_strategy(&memory.memory_type);
+                if strategy != MergeStrategy::Crdt {
+                    match self.store_whole_memory(&memory, strategy) {
+                        Ok(true) => stored_count += 1,
+                        Ok(false) => {}
+                        Err(e) => eprintln!("❌ Failed to store memory {}: {}", memory.id, e),
+                    }
+                    continue;
+                }
+
                 // Try to merge using CRDT
                 let crdt_memory = CrdtMemory::new(memory.clone(), &response.responding_peer);
                 let mut crdt_manager = self.crdt_manager.lock().await;
@@ -295,12 +580,28 @@ impl SyncManager {
                             );
 
                             // Log conflict details
-                            for conflict in conflicts {
+                            for conflict in &conflicts {
                                 println!(
                                     "   🔀 Conflict in field '{}': local vs remote operation",
                                     conflict.field_path
                                 );
                             }
+
+                            let event = serde_json::json!({
+                                "peer_id": response.responding_peer,
+                                "fields": conflicts
+                                    .iter()
+                                    .map(|conflict| conflict.field_path.clone())
+                                    .collect::<Vec<_>>(),
+                            });
+                            if let Err(e) = self.database.record_did_activity_event(
+                                CONFLICT_DETECTED_EVENT,
+                                &memory.did,
+                                Some(&memory.id),
+                                &event,
+                            ) {
+                                eprintln!("⚠️  Failed to record conflict event: {}", e);
+                            }
                         }
                     }
                     Err(e) => {
@@ -311,11 +612,18 @@ impl SyncManager {
                         }
                     }
                 }
-            } else {
-                eprintln!(
-                    "❌ Invalid memory hash from peer: {}",
-                    response.responding_peer
-                );
+            }
+        }
+
+        // Headers whose content we already hold become full memories without a transfer;
+        // the rest stay with the peer until fetched on demand
+        for header in &response.headers {
+            if let Err(e) = self
+                .store_header_or_resolve(header, &response.responding_peer, peer_did.as_deref())
+                .await
+                .map_err(|e| e.to_string())
+            {
+                eprintln!("❌ Failed to store memory header {}: {}", header.id, e);
             }
         }
 
@@ -325,23 +633,62 @@ impl SyncManager {
                 .await?;
         }
 
-        // Update sync state - use single lock acquisition for atomicity
+        if let Err(e) = self
+            .record_sync(&response.responding_peer, chrono::Utc::now())
+            .await
         {
-            let mut state = self.sync_state.lock().await;
-            state
-                .last_sync_per_peer
-                .insert(response.responding_peer.clone(), chrono::Utc::now());
-            state.sync_in_progress.remove(&response.responding_peer);
+            eprintln!("⚠️  Failed to persist sync state: {}", e);
         }
+        self.sync_state
+            .lock()
+            .await
+            .sync_in_progress
+            .remove(&response.responding_peer);
 
         println!(
             "🎉 CRDT sync completed with {}: stored {} memories, {} conflicts resolved",
             response.responding_peer, stored_count, conflict_count
         );
 
+        let event = serde_json::json!({
+            "peer_id": response.responding_peer,
+            "stored_count": stored_count,
+            "conflict_count": conflict_count,
+        });
+        if let Err(e) = self
+            .database
+            .record_activity_event(SYNC_COMPLETED_EVENT, &event)
+        {
+            eprintln!("⚠️  Failed to record sync event: {}", e);
+        }
+
         Ok(())
     }
 
+    /// Move a peer's sync cursor forward and persist it with a watermark of what we now hold
+    async fn record_sync(
+        &self,
+        peer_id: &str,
+        synced_at: chrono::DateTime<chrono::Utc>,
+    ) -> crate::core::error::Result<()> {
+        let (memory_count, memory_digest) = self.database.memory_set_digest()?;
+        {
+            let mut state = self.sync_state.lock().await;
+            state
+                .last_sync_per_peer
+                .insert(peer_id.to_string(), synced_at);
+            state
+                .synced_digests
+                .insert(peer_id.to_string(), memory_digest.clone());
+        }
+        self.database.record_peer_sync(&SyncWatermark {
+            peer_node_id: peer_id.to_string(),
+            synced_at: synced_at.to_rfc3339(),
+            memory_count: Some(memory_count),
+            memory_digest: Some(memory_digest),
+        })
+    }
+
     async fn send_missing_memories(
         &self,
         peer_id: &str,
@@ -365,6 +712,107 @@ impl SyncManager {
         Ok(())
     }
 
+    /// Import a snapshot from a trusted signer, then catch up incrementally from the
+    /// point it was taken. Returns how many memories were imported
+    pub async fn bootstrap_from_snapshot(
+        &self,
+        snapshot: Snapshot,
+        trusted_signers: &[String],
+    ) -> Result<usize, Box<dyn std::error::Error>> {
+        let manifest = &snapshot.manifest;
+        if !trusted_signers.contains(&manifest.signer_did) {
+            return Err(format!("Snapshot signer {} is not trusted", manifest.signer_did).into());
+        }
+
+        let signature_valid = {
+            let mut ocm = self.networking.ocm_protocol.lock().await;
+            ocm.verify_did_signature(
+                &manifest.signer_did,
+                &manifest.get_signing_payload(),
+                &manifest.signature,
+            )
+            .await
+            .map_err(|e| e.to_string())?
+        };
+        if !signature_valid {
+            return Err("Snapshot manifest signature is invalid".into());
+        }
+
+        let imported = snapshot.import(&self.database)?;
+
+        // Everything up to the snapshot is held now, so later syncs only ask for newer memories
+        let source_peer = manifest.source_node_id.clone();
+        let checkpoint =
+            chrono::DateTime::parse_from_rfc3339(&manifest.created_at)?.with_timezone(&chrono::Utc);
+        self.record_sync(&source_peer, checkpoint).await?;
+
+        println!(
+            "📸 Imported {} of {} snapshot memories from {}",
+            imported, manifest.memory_count, source_peer
+        );
+
+        self.initialize_crdt_from_database().await?;
+        self.sync_with_peer(&source_peer).await?;
+        Ok(imported)
+    }
+
+    async fn store_header_or_resolve(
+        &self,
+        header: &MemoryHeader,
+        source_peer: &str,
+        source_did: Option<&str>,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        if self.database.get_signed_memory(&header.id)?.is_some() {
+            return Ok(());
+        }
+
+        if let Some(memory_data) = self.database.get_memory_content(&header.content_hash)? {
+            let memory = SignedMemory {
+                id: header.id.clone(),
+                did: header.did.clone(),
+                memory_type: header.memory_type.clone(),
+                memory_data,
+                content_hash: header.content_hash.clone(),
+                signature: header.signature.clone(),
+                timestamp: header.timestamp.clone(),
+                updated_on: header.updated_on.clone(),
+                co_signatures: header.co_signatures.clone(),
+            };
+            let decision = self
+                .networking
+                .ingest
+                .check_memory(&self.networking.ocm_protocol, &memory, source_peer)
+                .await;
+            // A local copy that fails verification is not kept even as a header
+            if decision == IngestDecision::Drop
+                || !self
+                    .networking
+                    .review
+                    .admit(&memory, source_peer, source_did, None)
+                    .await?
+            {
+                return Ok(());
+            }
+            self.database.create_signed_memory(&memory)?;
+            println!(
+                "♻️  Resolved memory {} from local content {}",
+                memory.id, memory.content_hash
+            );
+            return Ok(());
+        }
+
+        self.database.store_memory_header(header, source_peer)?;
+        Ok(())
+    }
+
+    /// Fetch the body of a memory held only as a header; it is stored when the peer replies
+    pub async fn fetch_memory_body(
+        &self,
+        memory_id: &MemoryId,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        self.networking.request_memory_body(memory_id).await
+    }
+
     pub async fn detect_conflicts(&self) -> Result<Vec<ConflictInfo>, Box<dyn std::error::Error>> {
         let memories = self.database.list_signed_memories()?;
         let mut conflicts = Vec::new();
@@ -417,13 +865,59 @@ impl SyncManager {
         Ok(())
     }
 
+    /// Store a memory whose type is not merged field by field, returning whether it was written
+    fn store_whole_memory(
+        &self,
+        memory: &SignedMemory,
+        strategy: MergeStrategy,
+    ) -> Result<bool, Box<dyn std::error::Error>> {
+        let Some(local) = self.database.get_signed_memory(&memory.id)? else {
+            self.database.create_signed_memory(memory)?;
+            return Ok(true);
+        };
+        if local.content_hash == memory.content_hash {
+            return Ok(false);
+        }
+
+        match strategy {
+            MergeStrategy::Immutable => {
+                eprintln!(
+                    "❌ Rejected a change to immutable {} memory {}",
+                    memory.memory_type, memory.id
+                );
+                Ok(false)
+            }
+            _ => {
+                let newer = match (
+                    chrono::DateTime::parse_from_rfc3339(&memory.updated_on),
+                    chrono::DateTime::parse_from_rfc3339(&local.updated_on),
+                ) {
+                    (Ok(remote), Ok(local)) => remote > local,
+                    _ => memory.updated_on > local.updated_on,
+                };
+                if newer {
+                    self.database.update_signed_memory(memory)?;
+                }
+                Ok(newer)
+            }
+        }
+    }
+
     pub async fn update_memory_field(
         &self,
-        memory_id: &str,
+        memory_id: &MemoryId,
         field_path: &str,
         value: serde_json::Value,
     ) -> Result<(), Box<dyn std::error::Error>> {
         let mut crdt_manager = self.crdt_manager.lock().await;
+        if let Some(crdt_memory) = crdt_manager.get_memory(memory_id) {
+            let memory_type = &crdt_memory.base_memory.memory_type;
+            if self.memory_types.merge_strategy(memory_type) != MergeStrategy::Crdt {
+                return Err(
+                    format!("{} memories cannot be edited field by field", memory_type).into(),
+                );
+            }
+        }
         crdt_manager.update_memory(memory_id, field_path, value)?;
 
         // Update the database with the modified memory
@@ -435,6 +929,42 @@ impl SyncManager {
         Ok(())
     }
 
+    /// What changed from one memory to another, field by field
+    pub fn diff_memories(
+        &self,
+        id_a: &MemoryId,
+        id_b: &MemoryId,
+    ) -> Result<MemoryDiff, Box<dyn std::error::Error>> {
+        Ok(patch::diff_memories(&self.database, id_a, id_b)?)
+    }
+
+    /// Apply a JSON Patch to one of this node's memories as signed CRDT operations
+    pub async fn patch_memory(
+        &self,
+        memory_id: &MemoryId,
+        patch: &[PatchOperation],
+    ) -> Result<PatchedMemory, Box<dyn std::error::Error>> {
+        let signer = {
+            let ocm = self.networking.ocm_protocol.lock().await;
+            ocm.current_identity()
+                .cloned()
+                .ok_or("No identity to sign the patch with")?
+        };
+        let patched = patch::patch_memory(
+            &self.database,
+            &self.memory_types,
+            &signer,
+            memory_id,
+            patch,
+        )?;
+
+        self.crdt_manager
+            .lock()
+            .await
+            .add_memory(patched.memory.clone());
+        Ok(patched)
+    }
+
     pub async fn get_conflict_summary(&self) -> ConflictSummary {
         let crdt_manager = self.crdt_manager.lock().await;
         let conflicted_memories = crdt_manager.list_conflicts();
@@ -447,7 +977,7 @@ impl SyncManager {
 
     pub async fn force_resolve_conflicts(
         &self,
-        memory_id: &str,
+        memory_id: &MemoryId,
         resolution_strategy: crate::sync::crdt::ConflictStrategy,
     ) -> Result<(), Box<dyn std::error::Error>> {
         let mut crdt_manager = self.crdt_manager.lock().await;
@@ -477,6 +1007,14 @@ impl SyncManager {
         let crdt_memories = crdt_manager.memories.len();
         let conflicts = crdt_manager.list_conflicts().len();
 
+        let peers = self.networking.peers.snapshot().await;
+        let peer_groups = self.networking.peer_groups.statistics(&peers).await;
+        let peer_presence = self.networking.presence.list().await;
+        let ingest = self.networking.ingest.stats().await;
+
+        let (stored_bodies, body_references) =
+            self.database.content_store_stats().unwrap_or_default();
+
         SyncStatistics {
             total_peers_synced: total_peers,
             active_sync_operations: active_syncs,
@@ -484,6 +1022,16 @@ impl SyncManager {
             crdt_memories: crdt_memories,
             unresolved_conflicts: conflicts,
             last_sync_times: state.last_sync_per_peer.clone(),
+            peer_groups,
+            header_only_memories: self
+                .database
+                .list_memory_headers()
+                .map(|headers| headers.len())
+                .unwrap_or_default(),
+            stored_bodies,
+            body_references,
+            peer_presence,
+            ingest,
         }
     }
 }
@@ -496,17 +1044,10 @@ pub struct ConflictInfo {
     pub conflict_type: ConflictType,
 }
 
-#[derive(Debug, Clone)]
-pub enum ConflictType {
-    ContentMismatch,
-    TimestampConflict,
-    SignatureConflict,
-}
-
 #[derive(Debug, Clone)]
 pub struct ConflictSummary {
     pub total_conflicts: usize,
-    pub conflicted_memory_ids: Vec<String>,
+    pub conflicted_memory_ids: Vec<MemoryId>,
 }
 
 #[derive(Debug, Clone)]
@@ -517,4 +1058,10 @@ pub struct SyncStatistics {
     pub crdt_memories: usize,
     pub unresolved_conflicts: usize,
     pub last_sync_times: HashMap<String, chrono::DateTime<chrono::Utc>>,
+    pub peer_groups: Vec<PeerGroupStats>,
+    pub header_only_memories: usize,
+    pub stored_bodies: u64,   // Unique bodies in the content store
+    pub body_references: u64, // Memories sharing those bodies
+    pub peer_presence: Vec<PeerPresence>,
+    pub ingest: IngestStats, // Received memories verified or rejected
 }
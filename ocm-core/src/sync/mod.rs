@@ -1,5 +1,7 @@
-pub mod crdt;
 pub mod manager;
+pub mod patch;
+
+pub use ocm_protocol::crdt;
 
 pub use crdt::*;
 pub use manager::*;
@@ -0,0 +1,121 @@
+use crate::core::error::{OcmError, Result};
+use crate::core::ids::MemoryId;
+use crate::core::memory_types::{MemoryTypeRegistry, MergeStrategy};
+use crate::core::models::SignedMemory;
+use crate::identity::plc::PlcIdentity;
+use crate::persistence::database::Database;
+use crate::sync::crdt::{CrdtMemory, MemoryOperation};
+use ocm_protocol::patch::{diff_values, memory_data_value, patch_to_operations, MemoryDiff};
+
+pub use ocm_protocol::patch::{ChangeKind, FieldChange, PatchOperation};
+
+/// A memory after a patch and the signed operations that made the change
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct PatchedMemory {
+    pub memory: SignedMemory,
+    pub operations: Vec<MemoryOperation>,
+}
+
+/// What changed from memory `id_a` to memory `id_b`. Either may be archived, so a
+/// memory can be compared with the versions a merge or handover replaced
+pub fn diff_memories(database: &Database, id_a: &MemoryId, id_b: &MemoryId) -> Result<MemoryDiff> {
+    let from = find_memory(database, id_a)?;
+    let to = find_memory(database, id_b)?;
+    Ok(MemoryDiff {
+        from_id: from.id,
+        to_id: to.id,
+        memory_type: from.memory_type,
+        changes: diff_values(
+            &memory_data_value(&from.memory_data),
+            &memory_data_value(&to.memory_data),
+        ),
+    })
+}
+
+fn find_memory(database: &Database, id: &MemoryId) -> Result<SignedMemory> {
+    match database.get_signed_memory(id)? {
+        Some(memory) => Ok(memory),
+        None => database
+            .get_archived_signed_memory(id)?
+            .ok_or_else(|| OcmError::NotFound(format!("Memory {}", id))),
+    }
+}
+
+/// Apply a JSON Patch to one of the signer's memories as CRDT operations, each signed
+/// by the signer. The patched memory must still pass its type's validation; it is
+/// re-signed, stored and queued for peers
+pub fn patch_memory(
+    database: &Database,
+    memory_types: &MemoryTypeRegistry,
+    signer: &PlcIdentity,
+    memory_id: &MemoryId,
+    patch: &[PatchOperation],
+) -> Result<PatchedMemory> {
+    let memory = database
+        .get_signed_memory(memory_id)?
+        .ok_or_else(|| OcmError::NotFound(format!("Memory {}", memory_id)))?;
+    if memory.did != signer.did {
+        return Err(OcmError::Validation(format!(
+            "Memory {} belongs to another DID",
+            memory_id
+        )));
+    }
+    if memory.is_tombstone() || memory.is_derived() {
+        return Err(OcmError::Validation(format!(
+            "Memory {} is erased or derived and cannot be patched",
+            memory_id
+        )));
+    }
+    if memory_types.merge_strategy(&memory.memory_type) != MergeStrategy::Crdt {
+        return Err(OcmError::Validation(format!(
+            "{} memories cannot be edited field by field",
+            memory.memory_type
+        )));
+    }
+    let data: serde_json::Value = serde_json::from_str(&memory.memory_data)
+        .map_err(|_| OcmError::Validation(format!("Memory {} is not JSON", memory_id)))?;
+
+    let mut crdt_memory = CrdtMemory::new(memory, &signer.did);
+    let mut vector_clock = crdt_memory.vector_clock.clone();
+    vector_clock.increment(&signer.did);
+    let operations = patch_to_operations(
+        &data,
+        patch,
+        &vector_clock,
+        &chrono::Utc::now().to_rfc3339(),
+    )
+    .map_err(OcmError::Validation)?;
+    if operations.is_empty() {
+        return Err(OcmError::Validation(
+            "The patch changes nothing".to_string(),
+        ));
+    }
+
+    let mut signed = Vec::with_capacity(operations.len());
+    for mut operation in operations {
+        let signature = signer
+            .sign_payload(&operation.signing_payload(memory_id))
+            .map_err(|e| OcmError::Cryptography(e.to_string()))?;
+        operation.signature = Some(signature);
+        crdt_memory
+            .apply_operation(operation.clone(), &signer.did)
+            .map_err(|e| OcmError::OperationFailed(e.to_string()))?;
+        signed.push(operation);
+    }
+
+    let mut patched = crdt_memory.base_memory;
+    memory_types.validate(&patched.memory_type, &patched.memory_data)?;
+    signer
+        .sign_memory(&mut patched)
+        .map_err(|e| OcmError::Cryptography(e.to_string()))?;
+    database.update_outbound_signed_memory(&patched)?;
+    println!(
+        "🩹 Patched memory {} with {} operations",
+        patched.id,
+        signed.len()
+    );
+    Ok(PatchedMemory {
+        memory: patched,
+        operations: signed,
+    })
+}
@@ -0,0 +1,94 @@
+use crate::core::error::Result;
+use std::collections::VecDeque;
+use std::fs::{File, OpenOptions};
+use std::io::{self, BufRead, BufReader};
+use std::os::fd::FromRawFd;
+use std::sync::{Arc, Mutex};
+
+/// Lines kept for the Logs pane
+const LOG_CAPACITY: usize = 500;
+
+/// Most recent lines written to stdout and stderr while the dashboard owns the terminal
+#[derive(Clone, Default)]
+pub struct LogBuffer {
+    lines: Arc<Mutex<VecDeque<String>>>,
+}
+
+impl LogBuffer {
+    fn push(&self, line: String) {
+        let mut lines = self.lines.lock().unwrap_or_else(|e| e.into_inner());
+        if lines.len() == LOG_CAPACITY {
+            lines.pop_front();
+        }
+        lines.push_back(line);
+    }
+
+    /// The last `count` lines, oldest first
+    pub fn tail(&self, count: usize) -> Vec<String> {
+        let lines = self.lines.lock().unwrap_or_else(|e| e.into_inner());
+        lines
+            .iter()
+            .skip(lines.len().saturating_sub(count))
+            .cloned()
+            .collect()
+    }
+}
+
+pub struct CapturedOutput {
+    pub logs: LogBuffer,
+    pub terminal: File, // The controlling terminal, for drawing
+}
+
+/// Redirect stdout and stderr into a log buffer so println! and tracing output
+/// shows up in the Logs pane instead of tearing through the dashboard
+pub fn capture_output() -> Result<CapturedOutput> {
+    let terminal = OpenOptions::new().read(true).write(true).open("/dev/tty")?;
+
+    let mut fds = [0; 2];
+    // SAFETY: pipe fills both descriptors on success; dup2 only replaces stdout and
+    // stderr, and the write end is closed once both point at it
+    let reader = unsafe {
+        if libc::pipe(fds.as_mut_ptr()) != 0 {
+            return Err(io::Error::last_os_error().into());
+        }
+        if libc::dup2(fds[1], libc::STDOUT_FILENO) < 0
+            || libc::dup2(fds[1], libc::STDERR_FILENO) < 0
+        {
+            return Err(io::Error::last_os_error().into());
+        }
+        libc::close(fds[1]);
+        File::from_raw_fd(fds[0])
+    };
+
+    let logs = LogBuffer::default();
+    let sink = logs.clone();
+    std::thread::spawn(move || {
+        for line in BufReader::new(reader).lines() {
+            match line {
+                Ok(line) => sink.push(strip_ansi(&line)),
+                Err(_) => break,
+            }
+        }
+    });
+
+    Ok(CapturedOutput { logs, terminal })
+}
+
+// tracing colours its output; escape sequences would garble the pane
+fn strip_ansi(line: &str) -> String {
+    let mut plain = String::with_capacity(line.len());
+    let mut chars = line.chars();
+    while let Some(c) = chars.next() {
+        if c == '\x1b' {
+            // Skip the CSI sequence up to and including its final byte
+            for c in chars.by_ref() {
+                if c.is_ascii_alphabetic() {
+                    break;
+                }
+            }
+        } else {
+            plain.push(c);
+        }
+    }
+    plain
+}
@@ -0,0 +1,265 @@
+//! `ocm-core tui`: a live dashboard for a running node showing peers, sync progress,
+//! recent memories, CRDT conflicts and the node's log output.
+
+mod capture;
+mod view;
+
+pub use capture::capture_output;
+
+use crate::core::error::Result;
+use crate::core::ids::MemoryId;
+use crate::core::SignedMemory;
+use crate::networking::PeerInfo;
+use crate::sync::crdt::ConflictStrategy;
+use crate::sync::manager::SyncStatistics;
+use crate::RunningNode;
+use capture::{CapturedOutput, LogBuffer};
+use crossterm::event::{self, Event, KeyCode, KeyEventKind, KeyModifiers};
+use crossterm::terminal::{
+    disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen,
+};
+use ratatui::backend::CrosstermBackend;
+use ratatui::widgets::{ListState, TableState};
+use ratatui::Terminal;
+use std::fs::File;
+use std::io::Write;
+use tokio::sync::mpsc;
+
+const REFRESH_INTERVAL: std::time::Duration = std::time::Duration::from_secs(1);
+const RECENT_MEMORIES: usize = 20;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Pane {
+    Peers,
+    Conflicts,
+}
+
+/// Node state as of the last refresh
+#[derive(Default)]
+struct Snapshot {
+    peers: Vec<PeerInfo>, // Sorted by peer ID so selection is stable
+    stats: Option<SyncStatistics>,
+    conflicts: Vec<MemoryId>, // Awaiting manual resolution
+    recent_memories: Vec<SignedMemory>,
+}
+
+struct Dashboard {
+    node: RunningNode,
+    logs: LogBuffer,
+    snapshot: Snapshot,
+    focus: Pane,
+    peer_state: TableState,
+    conflict_state: ListState,
+}
+
+/// Take over the terminal until the user quits
+pub async fn run(node: RunningNode, output: CapturedOutput) -> Result<()> {
+    let mut tty = output.terminal.try_clone()?;
+    enable_raw_mode()?;
+    crossterm::execute!(tty, EnterAlternateScreen)?;
+    install_panic_hook(output.terminal.try_clone()?);
+
+    let mut terminal = Terminal::new(CrosstermBackend::new(output.terminal))?;
+    let mut dashboard = Dashboard {
+        node,
+        logs: output.logs,
+        snapshot: Snapshot::default(),
+        focus: Pane::Peers,
+        peer_state: TableState::default(),
+        conflict_state: ListState::default(),
+    };
+
+    let result = dashboard.event_loop(&mut terminal).await;
+
+    disable_raw_mode()?;
+    crossterm::execute!(tty, LeaveAlternateScreen)?;
+    writeln!(tty, "👋 OCM node shutting down gracefully")?;
+    result
+}
+
+// Restore the terminal before the default hook prints, or the message is lost
+fn install_panic_hook(mut tty: File) {
+    let default_hook = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        let _ = disable_raw_mode();
+        let _ = crossterm::execute!(tty, LeaveAlternateScreen);
+        let _ = writeln!(tty, "{}", info);
+        default_hook(info);
+    }));
+}
+
+// crossterm's event reader blocks, so it gets its own thread
+fn spawn_key_reader() -> mpsc::UnboundedReceiver<Event> {
+    let (sender, receiver) = mpsc::unbounded_channel();
+    std::thread::spawn(move || {
+        while let Ok(event) = event::read() {
+            if sender.send(event).is_err() {
+                break;
+            }
+        }
+    });
+    receiver
+}
+
+impl Dashboard {
+    async fn event_loop(&mut self, terminal: &mut Terminal<CrosstermBackend<File>>) -> Result<()> {
+        let mut events = spawn_key_reader();
+        let mut refresh = tokio::time::interval(REFRESH_INTERVAL);
+
+        loop {
+            tokio::select! {
+                _ = refresh.tick() => self.refresh().await,
+                event = events.recv() => {
+                    let Some(Event::Key(key)) = event else {
+                        continue;
+                    };
+                    if key.kind != KeyEventKind::Press {
+                        continue;
+                    }
+                    // Raw mode swallows SIGINT, so Ctrl+C arrives as a key
+                    let ctrl_c = key.modifiers.contains(KeyModifiers::CONTROL)
+                        && key.code == KeyCode::Char('c');
+                    if ctrl_c || matches!(key.code, KeyCode::Char('q') | KeyCode::Esc) {
+                        return Ok(());
+                    }
+                    self.handle_key(key.code).await;
+                    self.refresh().await;
+                }
+                _ = tokio::signal::ctrl_c() => return Ok(()),
+            }
+
+            terminal.draw(|frame| view::draw(frame, self))?;
+        }
+    }
+
+    async fn handle_key(&mut self, code: KeyCode) {
+        match code {
+            KeyCode::Tab => {
+                self.focus = match self.focus {
+                    Pane::Peers => Pane::Conflicts,
+                    Pane::Conflicts => Pane::Peers,
+                };
+            }
+            KeyCode::Up | KeyCode::Char('k') => self.move_selection(-1),
+            KeyCode::Down | KeyCode::Char('j') => self.move_selection(1),
+            KeyCode::Char('s') => {
+                if let Some(peer) = self.selected_peer() {
+                    self.sync_with(vec![peer.peer_id.clone()]).await;
+                }
+            }
+            KeyCode::Char('a') => {
+                let peer_ids = self.snapshot.peers.iter().map(|p| p.peer_id.clone());
+                self.sync_with(peer_ids.collect()).await;
+            }
+            KeyCode::Char('d') => {
+                if let Some(peer_id) = self.selected_peer().map(|p| p.peer_id.clone()) {
+                    self.node.networking.disconnect_peer(&peer_id).await;
+                }
+            }
+            KeyCode::Char('r') => {
+                if let Some(memory_id) = self.selected_conflict().cloned() {
+                    let result = self
+                        .node
+                        .sync_manager
+                        .force_resolve_conflicts(&memory_id, ConflictStrategy::LastWriterWins)
+                        .await
+                        .map_err(|e| e.to_string());
+                    if let Err(e) = result {
+                        eprintln!("❌ Failed to resolve conflict on {}: {}", memory_id, e);
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    async fn sync_with(&self, peer_ids: Vec<String>) {
+        for peer_id in peer_ids {
+            let result = self
+                .node
+                .sync_manager
+                .sync_with_peer(&peer_id)
+                .await
+                .map_err(|e| e.to_string());
+            if let Err(e) = result {
+                eprintln!("❌ Sync with {} failed: {}", peer_id, e);
+            }
+        }
+    }
+
+    async fn refresh(&mut self) {
+        let mut peers: Vec<PeerInfo> = self.node.networking.peers.list().await;
+        peers.sort_by(|a, b| a.peer_id.cmp(&b.peer_id));
+
+        let mut recent_memories = self
+            .node
+            .database
+            .list_signed_memories()
+            .unwrap_or_default();
+        recent_memories.sort_by(|a, b| b.timestamp.cmp(&a.timestamp));
+        recent_memories.truncate(RECENT_MEMORIES);
+
+        self.snapshot = Snapshot {
+            peers,
+            stats: Some(self.node.sync_manager.get_sync_statistics().await),
+            conflicts: self
+                .node
+                .sync_manager
+                .get_conflict_summary()
+                .await
+                .conflicted_memory_ids,
+            recent_memories,
+        };
+
+        self.peer_state.select(clamp_index(
+            self.peer_state.selected(),
+            self.snapshot.peers.len(),
+        ));
+        self.conflict_state.select(clamp_index(
+            self.conflict_state.selected(),
+            self.snapshot.conflicts.len(),
+        ));
+    }
+
+    fn move_selection(&mut self, delta: isize) {
+        let (selected, len) = match self.focus {
+            Pane::Peers => (self.peer_state.selected(), self.snapshot.peers.len()),
+            Pane::Conflicts => (
+                self.conflict_state.selected(),
+                self.snapshot.conflicts.len(),
+            ),
+        };
+        if len == 0 {
+            return;
+        }
+        let next = match selected {
+            Some(index) => (index as isize + delta).clamp(0, len as isize - 1) as usize,
+            None => 0,
+        };
+        match self.focus {
+            Pane::Peers => self.peer_state.select(Some(next)),
+            Pane::Conflicts => self.conflict_state.select(Some(next)),
+        }
+    }
+
+    fn selected_peer(&self) -> Option<&PeerInfo> {
+        self.peer_state
+            .selected()
+            .and_then(|index| self.snapshot.peers.get(index))
+    }
+
+    fn selected_conflict(&self) -> Option<&MemoryId> {
+        self.conflict_state
+            .selected()
+            .and_then(|index| self.snapshot.conflicts.get(index))
+    }
+}
+
+// Keep a selection on a row that still exists, selecting the first row once there is one
+fn clamp_index(selected: Option<usize>, len: usize) -> Option<usize> {
+    match (selected, len) {
+        (_, 0) => None,
+        (Some(index), len) => Some(index.min(len - 1)),
+        (None, _) => Some(0),
+    }
+}
@@ -0,0 +1,232 @@
+use super::{Dashboard, Pane};
+use crate::core::redact::Redacted;
+use ratatui::layout::{Constraint, Direction, Layout, Rect};
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::text::Line;
+use ratatui::widgets::{Block, Borders, Gauge, List, ListItem, Paragraph, Row, Table};
+use ratatui::Frame;
+
+/// Peers synced more recently than this count towards the sync progress gauge
+const RECENT_SYNC_SECS: i64 = 300;
+
+const KEY_HELP: &str =
+    " q quit · Tab switch pane · ↑/↓ select · s sync peer · a sync all · d disconnect peer · r resolve conflict";
+
+pub(super) fn draw(frame: &mut Frame, dashboard: &mut Dashboard) {
+    let rows = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Length(4),
+            Constraint::Min(8),
+            Constraint::Length(8),
+            Constraint::Min(6),
+            Constraint::Length(1),
+        ])
+        .split(frame.size());
+    let middle = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([Constraint::Percentage(65), Constraint::Percentage(35)])
+        .split(rows[1]);
+
+    draw_sync_progress(frame, dashboard, rows[0]);
+    draw_peers(frame, dashboard, middle[0]);
+    draw_conflicts(frame, dashboard, middle[1]);
+    draw_recent_memories(frame, dashboard, rows[2]);
+    draw_logs(frame, dashboard, rows[3]);
+    frame.render_widget(
+        Paragraph::new(KEY_HELP).style(Style::default().fg(Color::DarkGray)),
+        rows[4],
+    );
+}
+
+fn pane_block(title: &str, focused: bool) -> Block<'_> {
+    let border = if focused {
+        Style::default().fg(Color::Yellow)
+    } else {
+        Style::default()
+    };
+    Block::default()
+        .borders(Borders::ALL)
+        .border_style(border)
+        .title(title)
+}
+
+fn draw_sync_progress(frame: &mut Frame, dashboard: &Dashboard, area: Rect) {
+    let block = Block::default().borders(Borders::ALL).title(format!(
+        " OCM node {} ",
+        dashboard.node.networking.local_peer_id
+    ));
+    let inner = block.inner(area);
+    frame.render_widget(block, area);
+
+    let lines = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Length(1), Constraint::Length(1)])
+        .split(inner);
+
+    let Some(stats) = &dashboard.snapshot.stats else {
+        frame.render_widget(Paragraph::new("Loading…"), lines[0]);
+        return;
+    };
+
+    frame.render_widget(
+        Paragraph::new(format!(
+            "{} memories · {} CRDT-managed · {} header-only · {} active syncs · {} unresolved conflicts",
+            stats.total_memories,
+            stats.crdt_memories,
+            stats.header_only_memories,
+            stats.active_sync_operations,
+            stats.unresolved_conflicts
+        )),
+        lines[0],
+    );
+
+    let now = chrono::Utc::now();
+    let peers = &dashboard.snapshot.peers;
+    let synced = peers
+        .iter()
+        .filter(|peer| {
+            stats
+                .last_sync_times
+                .get(&peer.peer_id)
+                .map_or(false, |at| (now - *at).num_seconds() < RECENT_SYNC_SECS)
+        })
+        .count();
+    let ratio = if peers.is_empty() {
+        0.0
+    } else {
+        synced as f64 / peers.len() as f64
+    };
+    frame.render_widget(
+        Gauge::default()
+            .gauge_style(Style::default().fg(Color::Green))
+            .ratio(ratio)
+            .label(format!(
+                "{}/{} peers synced in the last {} minutes",
+                synced,
+                peers.len(),
+                RECENT_SYNC_SECS / 60
+            )),
+        lines[1],
+    );
+}
+
+fn draw_peers(frame: &mut Frame, dashboard: &mut Dashboard, area: Rect) {
+    let now = chrono::Utc::now();
+    let last_sync_times = dashboard
+        .snapshot
+        .stats
+        .as_ref()
+        .map(|s| &s.last_sync_times);
+
+    let rows: Vec<Row> = dashboard
+        .snapshot
+        .peers
+        .iter()
+        .map(|peer| {
+            let last_sync = last_sync_times
+                .and_then(|times| times.get(&peer.peer_id))
+                .map_or_else(|| "never".to_string(), |at| ago(now - *at));
+            Row::new(vec![
+                peer.peer_id.clone(),
+                format!("{}:{}", peer.address, peer.port),
+                ago(now - peer.last_seen),
+                last_sync,
+                if peer.capabilities.is_empty() {
+                    "-".to_string()
+                } else {
+                    peer.capabilities.join(", ")
+                },
+            ])
+        })
+        .collect();
+
+    let table = Table::new(
+        rows,
+        [
+            Constraint::Percentage(25),
+            Constraint::Percentage(15),
+            Constraint::Percentage(10),
+            Constraint::Percentage(10),
+            Constraint::Percentage(40),
+        ],
+    )
+    .header(
+        Row::new(vec![
+            "Peer",
+            "Address",
+            "Last seen",
+            "Last sync",
+            "Capabilities",
+        ])
+        .style(Style::default().add_modifier(Modifier::BOLD)),
+    )
+    .block(pane_block(" Peers ", dashboard.focus == Pane::Peers))
+    .highlight_style(Style::default().add_modifier(Modifier::REVERSED));
+
+    frame.render_stateful_widget(table, area, &mut dashboard.peer_state);
+}
+
+fn draw_conflicts(frame: &mut Frame, dashboard: &mut Dashboard, area: Rect) {
+    let items: Vec<ListItem> = dashboard
+        .snapshot
+        .conflicts
+        .iter()
+        .map(|memory_id| ListItem::new(memory_id.as_str()))
+        .collect();
+
+    let list = List::new(items)
+        .block(pane_block(
+            " Conflicts ",
+            dashboard.focus == Pane::Conflicts,
+        ))
+        .highlight_style(Style::default().add_modifier(Modifier::REVERSED));
+
+    frame.render_stateful_widget(list, area, &mut dashboard.conflict_state);
+}
+
+fn draw_recent_memories(frame: &mut Frame, dashboard: &Dashboard, area: Rect) {
+    let items: Vec<ListItem> = dashboard
+        .snapshot
+        .recent_memories
+        .iter()
+        .map(|memory| {
+            ListItem::new(format!(
+                "{}  {:<12}  {}  {}",
+                memory.timestamp,
+                memory.memory_type,
+                memory.id,
+                Redacted::did(&memory.did)
+            ))
+        })
+        .collect();
+
+    frame.render_widget(
+        List::new(items).block(pane_block(" Recent memories ", false)),
+        area,
+    );
+}
+
+fn draw_logs(frame: &mut Frame, dashboard: &Dashboard, area: Rect) {
+    let visible = area.height.saturating_sub(2) as usize;
+    let lines: Vec<Line> = dashboard
+        .logs
+        .tail(visible)
+        .into_iter()
+        .map(Line::from)
+        .collect();
+
+    frame.render_widget(
+        Paragraph::new(lines).block(pane_block(" Logs ", false)),
+        area,
+    );
+}
+
+fn ago(elapsed: chrono::Duration) -> String {
+    let secs = elapsed.num_seconds().max(0);
+    match secs {
+        0..=59 => format!("{}s ago", secs),
+        60..=3599 => format!("{}m ago", secs / 60),
+        _ => format!("{}h ago", secs / 3600),
+    }
+}
@@ -0,0 +1,242 @@
+//! `ocm-core verify`: re-hashes stored memories and re-checks their author and
+//! co-signatures, reporting (and optionally quarantining) any that fail.
+
+use crate::config::OcmConfig;
+use crate::core::error::{OcmError, Result};
+use crate::core::ids::{Did, MemoryId};
+use crate::core::redact::Redacted;
+use crate::core::SignedMemory;
+use crate::identity::plc::{verify_payload_signature, PlcDirectory};
+use crate::persistence::Database;
+use serde::Serialize;
+use std::collections::HashMap;
+
+const USAGE: &str = "Usage: ocm-core verify (--all | --did DID | --id ID) [--quarantine] [--json]
+
+Exits 1 if any memory is tampered. Only tampered memories are quarantined; unverifiable
+ones (signer key could not be resolved) are reported but left in place.";
+
+/// Which stored memories to check
+#[derive(Debug, Clone)]
+pub enum VerifyScope {
+    All,
+    Did(Did),
+    Id(MemoryId),
+}
+
+#[derive(Debug, Clone)]
+pub struct VerifyOptions {
+    pub scope: VerifyScope,
+    pub quarantine: bool,
+    pub json: bool,
+}
+
+impl VerifyOptions {
+    /// Parse the arguments following `verify`
+    pub fn from_args(args: &[String]) -> std::result::Result<Self, &'static str> {
+        let value = |flag: &str| {
+            args.iter()
+                .position(|arg| arg == flag)
+                .and_then(|i| args.get(i + 1))
+                .cloned()
+        };
+        let all = args.iter().any(|arg| arg == "--all");
+        let scope = match (all, value("--did"), value("--id")) {
+            (true, None, None) => VerifyScope::All,
+            (false, Some(did), None) => VerifyScope::Did(Did::parse(&did).map_err(|_| USAGE)?),
+            (false, None, Some(id)) => VerifyScope::Id(MemoryId::parse(&id).map_err(|_| USAGE)?),
+            _ => return Err(USAGE),
+        };
+        Ok(Self {
+            scope,
+            quarantine: args.iter().any(|arg| arg == "--quarantine"),
+            json: args.iter().any(|arg| arg == "--json"),
+        })
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct MemoryProblem {
+    pub id: MemoryId,
+    pub did: Did,
+    pub memory_type: String,
+    pub reason: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct VerifyReport {
+    pub checked: usize,
+    pub valid: usize,
+    pub tampered: Vec<MemoryProblem>, // Hash mismatch or a signature that does not verify
+    pub unverifiable: Vec<MemoryProblem>, // A signer's key could not be resolved
+    pub quarantined: usize,
+}
+
+impl VerifyReport {
+    pub fn print(&self) {
+        for (label, problems) in [
+            ("❌ Tampered", &self.tampered),
+            ("❓ Unverifiable", &self.unverifiable),
+        ] {
+            for problem in problems {
+                println!(
+                    "{} {} ({}, {}): {}",
+                    label,
+                    problem.id,
+                    problem.memory_type,
+                    Redacted::did(&problem.did),
+                    problem.reason
+                );
+            }
+        }
+        println!(
+            "🔏 Checked {} memories: {} valid, {} tampered, {} unverifiable, {} quarantined",
+            self.checked,
+            self.valid,
+            self.tampered.len(),
+            self.unverifiable.len(),
+            self.quarantined
+        );
+    }
+}
+
+enum Verdict {
+    Valid,
+    Tampered(String),
+    Unverifiable(String),
+}
+
+/// Signer keys, resolved once per DID
+struct KeyResolver {
+    directory: PlcDirectory,
+    network_enabled: bool,
+    keys: HashMap<String, Option<[u8; 32]>>,
+}
+
+impl KeyResolver {
+    async fn resolve(&mut self, did: &str) -> Option<[u8; 32]> {
+        if let Some(key) = self.keys.get(did) {
+            return *key;
+        }
+        // did:key identifiers carry their own key; anything else needs the directory
+        let key = if did.starts_with("did:key:") || self.network_enabled {
+            self.directory
+                .resolve_public_key(did)
+                .await
+                .unwrap_or_default()
+        } else {
+            None
+        };
+        self.keys.insert(did.to_string(), key);
+        key
+    }
+
+    async fn verdict(&mut self, memory: &SignedMemory) -> Verdict {
+        if !memory.verify_hash() {
+            return Verdict::Tampered("content hash does not match memory data".to_string());
+        }
+
+        let payload = memory.get_signing_payload();
+        let signatures = std::iter::once((memory.did.as_str(), memory.signature.as_str())).chain(
+            memory
+                .co_signatures
+                .iter()
+                .map(|s| (s.signer_did.as_str(), s.signature.as_str())),
+        );
+
+        let mut unresolved = Vec::new();
+        for (signer_did, signature) in signatures {
+            match self.resolve(signer_did).await {
+                Some(key) if verify_payload_signature(&key, &payload, signature) => {}
+                Some(_) if signer_did == memory.did => {
+                    return Verdict::Tampered("author signature does not verify".to_string())
+                }
+                Some(_) => {
+                    return Verdict::Tampered(format!(
+                        "co-signature by {} does not verify",
+                        Redacted::did(signer_did)
+                    ))
+                }
+                None => unresolved.push(Redacted::did(signer_did).to_string()),
+            }
+        }
+
+        if unresolved.is_empty() {
+            Verdict::Valid
+        } else {
+            Verdict::Unverifiable(format!("no public key for {}", unresolved.join(", ")))
+        }
+    }
+}
+
+pub async fn run(config: &OcmConfig, options: &VerifyOptions) -> Result<VerifyReport> {
+    let path = config.database.path.to_string_lossy();
+    let database = if options.quarantine {
+        Database::new(&path)?
+    } else {
+        Database::open_read_only(&path)?
+    };
+
+    verify_memories(
+        &database,
+        config,
+        &options.scope,
+        options.quarantine,
+        |_, _| Ok(()),
+    )
+    .await
+}
+
+/// Check the memories in `scope`, calling `progress` with the number checked so far
+/// and the total after each one. An error from `progress` stops the run
+pub async fn verify_memories(
+    database: &Database,
+    config: &OcmConfig,
+    scope: &VerifyScope,
+    quarantine: bool,
+    mut progress: impl FnMut(usize, usize) -> Result<()>,
+) -> Result<VerifyReport> {
+    let memories = match scope {
+        VerifyScope::All => database.list_signed_memories()?,
+        VerifyScope::Did(did) => database.list_memories_by_did(did)?,
+        VerifyScope::Id(id) => vec![database
+            .get_signed_memory(id)?
+            .ok_or_else(|| OcmError::NotFound(format!("No memory with id {}", id)))?],
+    };
+
+    let mut resolver = KeyResolver {
+        directory: PlcDirectory::with_config(&config.plc),
+        network_enabled: config.plc.enable_network_calls,
+        keys: HashMap::new(),
+    };
+
+    let mut report = VerifyReport {
+        checked: memories.len(),
+        valid: 0,
+        tampered: Vec::new(),
+        unverifiable: Vec::new(),
+        quarantined: 0,
+    };
+    for (index, memory) in memories.iter().enumerate() {
+        let problem = |reason: String| MemoryProblem {
+            id: memory.id.clone(),
+            did: memory.did.clone(),
+            memory_type: memory.memory_type.clone(),
+            reason,
+        };
+        match resolver.verdict(memory).await {
+            Verdict::Valid => report.valid += 1,
+            Verdict::Unverifiable(reason) => report.unverifiable.push(problem(reason)),
+            Verdict::Tampered(reason) => {
+                if quarantine {
+                    database.quarantine_signed_memory(&memory.id, &reason)?;
+                    report.quarantined += 1;
+                }
+                report.tampered.push(problem(reason));
+            }
+        }
+        progress(index + 1, memories.len())?;
+    }
+
+    Ok(report)
+}
@@ -0,0 +1,128 @@
+use crate::{MemoryRecord, OcmFfiError};
+use base64::{engine::general_purpose, Engine as _};
+use ed25519_dalek::SigningKey;
+use ocm_core::core::ids::Did;
+use ocm_core::core::models::SignedMemory;
+use ocm_core::identity::plc::{verify_memory_with_key, PlcIdentity, PlcKeypair};
+use std::sync::Arc;
+
+/// A DID:PLC identity whose private key the app keeps in the Keychain or Keystore
+#[derive(uniffi::Object)]
+pub struct OcmIdentity {
+    inner: PlcIdentity,
+}
+
+#[uniffi::export]
+impl OcmIdentity {
+    /// Generate a new identity without network access
+    #[uniffi::constructor]
+    pub fn generate(handle: Option<String>) -> Result<Arc<Self>, OcmFfiError> {
+        let inner = PlcIdentity::generate(handle).map_err(|e| OcmFfiError::Identity {
+            message: e.to_string(),
+        })?;
+        Ok(Arc::new(Self { inner }))
+    }
+
+    /// Rebuild an identity from its DID and the key returned by `export_private_key`
+    #[uniffi::constructor]
+    pub fn restore(did: String, private_key: String) -> Result<Arc<Self>, OcmFfiError> {
+        let key_bytes: [u8; 32] = general_purpose::STANDARD
+            .decode(&private_key)
+            .ok()
+            .and_then(|bytes| bytes.try_into().ok())
+            .ok_or_else(|| OcmFfiError::Identity {
+                message: "Private key must be 32 base64 encoded bytes".to_string(),
+            })?;
+
+        let signing_key = SigningKey::from_bytes(&key_bytes);
+        let public_key = general_purpose::STANDARD.encode(signing_key.verifying_key().to_bytes());
+
+        let did = Did::parse(&did).map_err(|e| OcmFfiError::Identity {
+            message: e.to_string(),
+        })?;
+        let inner = PlcIdentity {
+            did,
+            keypair: PlcKeypair::new(public_key.clone(), key_bytes),
+            plc_operations: Vec::new(), // Held by the PLC directory, not needed for signing
+            created_at: chrono::Utc::now().to_rfc3339(),
+            rotation_keys: vec![public_key],
+        };
+        Ok(Arc::new(Self { inner }))
+    }
+
+    pub fn did(&self) -> String {
+        self.inner.did.to_string()
+    }
+
+    /// Base64 Ed25519 public key
+    pub fn public_key(&self) -> String {
+        self.inner.keypair.public_key.clone()
+    }
+
+    /// Base64 private key, for the app's secure storage only
+    pub fn export_private_key(&self) -> String {
+        // Identities made through the FFI always hold their key in memory
+        let private_key = self
+            .inner
+            .keypair
+            .private_key_bytes()
+            .expect("in-memory key");
+        general_purpose::STANDARD.encode(private_key)
+    }
+
+    /// Create and sign a memory authored by this identity
+    pub fn create_memory(
+        &self,
+        memory_type: String,
+        memory_data: String,
+    ) -> Result<MemoryRecord, OcmFfiError> {
+        let mut memory = SignedMemory::new(&self.inner.did, &memory_type, &memory_data);
+        self.sign(&mut memory)?;
+        Ok(memory.into())
+    }
+
+    /// Re-sign a memory after its content changed
+    pub fn sign_memory(&self, memory: MemoryRecord) -> Result<MemoryRecord, OcmFfiError> {
+        let mut memory = SignedMemory::try_from(memory)?;
+        memory.content_hash = SignedMemory::compute_hash(&memory.memory_data);
+        memory.updated_on = chrono::Utc::now().to_rfc3339();
+        self.sign(&mut memory)?;
+        Ok(memory.into())
+    }
+
+    /// Add this identity's co-signature to a memory authored by someone else
+    pub fn co_sign_memory(&self, memory: MemoryRecord) -> Result<MemoryRecord, OcmFfiError> {
+        let mut memory = SignedMemory::try_from(memory)?;
+        self.inner
+            .co_sign_memory(&mut memory)
+            .map_err(|e| OcmFfiError::Identity {
+                message: e.to_string(),
+            })?;
+        Ok(memory.into())
+    }
+
+    /// Whether a memory was authored and signed by this identity
+    pub fn verify_memory(&self, memory: MemoryRecord) -> bool {
+        let Ok(memory) = SignedMemory::try_from(memory) else {
+            return false;
+        };
+        memory.did == self.inner.did && self.inner.verify_memory(&memory).unwrap_or(false)
+    }
+}
+
+impl OcmIdentity {
+    fn sign(&self, memory: &mut SignedMemory) -> Result<(), OcmFfiError> {
+        self.inner
+            .sign_memory(memory)
+            .map_err(|e| OcmFfiError::Identity {
+                message: e.to_string(),
+            })
+    }
+}
+
+/// Check a memory's content hash and its author signature against a base64 public key,
+/// e.g. one resolved from the author's DID document
+#[uniffi::export]
+pub fn verify_memory_signature(memory: MemoryRecord, public_key: String) -> bool {
+    SignedMemory::try_from(memory).is_ok_and(|memory| verify_memory_with_key(&memory, &public_key))
+}
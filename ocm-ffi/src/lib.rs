@@ -0,0 +1,124 @@
+//! UniFFI bindings so iOS and Android apps can embed ocm-core directly instead of
+//! going through the WASM build.
+//!
+//! Generate Swift or Kotlin sources from a built library with
+//! `cargo run -p ocm-ffi --features bindgen --bin uniffi-bindgen -- generate --library <libocm_ffi> --language swift --out-dir out`.
+//! Android builds should enable `bundled-sqlite`.
+//!
+//! The app owns its WebSocket (URLSessionWebSocketTask, OkHttp, ...); this layer
+//! builds and parses relay frames and stores what arrives.
+
+mod identity;
+mod relay;
+mod store;
+
+pub use identity::*;
+pub use relay::*;
+pub use store::*;
+
+use ocm_core::core::ids::{Did, InvalidId, MemoryId};
+use ocm_core::core::models::{CoSignature, SignedMemory};
+
+uniffi::setup_scaffolding!();
+
+#[derive(Debug, thiserror::Error, uniffi::Error)]
+pub enum OcmFfiError {
+    #[error("Identity error: {message}")]
+    Identity { message: String },
+    #[error("Storage error: {message}")]
+    Storage { message: String },
+    #[error("Invalid memory: {message}")]
+    InvalidMemory { message: String },
+    #[error("Relay error: {message}")]
+    Relay { message: String },
+}
+
+impl From<InvalidId> for OcmFfiError {
+    fn from(err: InvalidId) -> Self {
+        OcmFfiError::InvalidMemory {
+            message: err.to_string(),
+        }
+    }
+}
+
+impl From<ocm_core::OcmError> for OcmFfiError {
+    fn from(err: ocm_core::OcmError) -> Self {
+        OcmFfiError::Storage {
+            message: err.to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, uniffi::Record)]
+pub struct CoSignatureRecord {
+    pub signer_did: String,
+    pub signature: String,
+    pub signed_at: String,
+}
+
+/// A signed memory as seen from Swift/Kotlin
+#[derive(Debug, Clone, uniffi::Record)]
+pub struct MemoryRecord {
+    pub id: String,
+    pub did: String,
+    pub memory_type: String,
+    pub memory_data: String,
+    pub content_hash: String,
+    pub signature: String,
+    pub timestamp: String,
+    pub updated_on: String,
+    pub co_signatures: Vec<CoSignatureRecord>,
+}
+
+impl From<SignedMemory> for MemoryRecord {
+    fn from(memory: SignedMemory) -> Self {
+        MemoryRecord {
+            id: memory.id.into_string(),
+            did: memory.did.into_string(),
+            memory_type: memory.memory_type,
+            memory_data: memory.memory_data,
+            content_hash: memory.content_hash,
+            signature: memory.signature,
+            timestamp: memory.timestamp,
+            updated_on: memory.updated_on,
+            co_signatures: memory
+                .co_signatures
+                .into_iter()
+                .map(|s| CoSignatureRecord {
+                    signer_did: s.signer_did.into_string(),
+                    signature: s.signature,
+                    signed_at: s.signed_at,
+                })
+                .collect(),
+        }
+    }
+}
+
+/// Records come from app code, so their IDs are checked on the way in
+impl TryFrom<MemoryRecord> for SignedMemory {
+    type Error = OcmFfiError;
+
+    fn try_from(record: MemoryRecord) -> Result<Self, OcmFfiError> {
+        Ok(SignedMemory {
+            id: MemoryId::parse(&record.id)?,
+            did: Did::parse(&record.did)?,
+            memory_type: record.memory_type,
+            memory_data: record.memory_data,
+            content_hash: record.content_hash,
+            signature: record.signature,
+            timestamp: record.timestamp,
+            updated_on: record.updated_on,
+            co_signatures: record
+                .co_signatures
+                .into_iter()
+                .map(|s| {
+                    Ok(CoSignature {
+                        signer_did: Did::parse(&s.signer_did)?,
+                        signature: s.signature,
+                        signed_at: s.signed_at,
+                    })
+                })
+                .collect::<Result<_, OcmFfiError>>()?,
+        })
+    }
+}
@@ -0,0 +1,99 @@
+use crate::{MemoryRecord, OcmFfiError, OcmStore};
+use ocm_protocol::relay::{RelayMessage, RELAY_PROTOCOL_VERSION};
+
+/// A relay frame decoded for the app
+#[derive(Debug, Clone, uniffi::Enum)]
+pub enum RelayEvent {
+    Welcome {
+        client_id: String,
+        protocol_version: u32,
+    },
+    Memory {
+        memory: MemoryRecord,
+    },
+    MessagesDropped {
+        count: u64,
+    },
+    /// The relay would not pass on a memory this client sent
+    MemoryRejected {
+        memory_id: String,
+        reason: String,
+    },
+    Pong,
+    /// Metrics replies and message types this version does not know
+    Other,
+}
+
+#[uniffi::export]
+pub fn relay_protocol_version() -> u32 {
+    RELAY_PROTOCOL_VERSION
+}
+
+/// Text frame that shares a memory with every other client on the relay
+#[uniffi::export]
+pub fn encode_relay_memory(memory: MemoryRecord) -> Result<String, OcmFfiError> {
+    Ok(RelayMessage::MemorySync {
+        data: memory.try_into()?,
+    }
+    .to_json())
+}
+
+/// Text frame asking the relay for a pong
+#[uniffi::export]
+pub fn encode_relay_ping() -> String {
+    RelayMessage::Ping.to_json()
+}
+
+#[uniffi::export]
+pub fn decode_relay_message(text: String) -> Result<RelayEvent, OcmFfiError> {
+    let message: RelayMessage = serde_json::from_str(&text).map_err(|e| OcmFfiError::Relay {
+        message: e.to_string(),
+    })?;
+
+    Ok(match message {
+        RelayMessage::Welcome {
+            client_id,
+            protocol_version,
+            ..
+        } => RelayEvent::Welcome {
+            client_id,
+            protocol_version,
+        },
+        RelayMessage::MemorySync { data } => RelayEvent::Memory {
+            memory: data.into(),
+        },
+        RelayMessage::MessagesDropped { count, .. } => RelayEvent::MessagesDropped { count },
+        RelayMessage::MemoryRejected { memory_id, reason } => RelayEvent::MemoryRejected {
+            memory_id: memory_id.into_string(),
+            reason: reason.as_str().to_string(),
+        },
+        RelayMessage::Pong { .. } => RelayEvent::Pong,
+        RelayMessage::Ping
+        | RelayMessage::Metrics(_)
+        | RelayMessage::Quarantine { .. }
+        | RelayMessage::DirectoryRegister { .. }
+        | RelayMessage::DirectoryQuery { .. }
+        | RelayMessage::DirectoryEntries { .. }
+        | RelayMessage::DirectoryRejected { .. }
+        | RelayMessage::Presence(_)
+        | RelayMessage::Unknown => RelayEvent::Other,
+    })
+}
+
+#[uniffi::export]
+impl OcmStore {
+    /// Decode a relay frame and store the memory it carries, if any.
+    /// Returns the memory when it was new to this device
+    pub fn ingest_relay_message(&self, text: String) -> Result<Option<MemoryRecord>, OcmFfiError> {
+        match decode_relay_message(text)? {
+            RelayEvent::Memory { memory } => {
+                if self.import_memory(memory.clone())? {
+                    Ok(Some(memory))
+                } else {
+                    Ok(None)
+                }
+            }
+            _ => Ok(None),
+        }
+    }
+}
@@ -0,0 +1,77 @@
+use crate::{MemoryRecord, OcmFfiError};
+use ocm_core::core::ids::MemoryId;
+use ocm_core::core::models::SignedMemory;
+use ocm_core::persistence::database::Database;
+use std::sync::Arc;
+
+mod embedded {
+    use refinery::embed_migrations;
+    embed_migrations!("../ocm-core/migrations");
+}
+
+/// On-device SQLite memory store, using the same schema as a full node
+#[derive(uniffi::Object)]
+pub struct OcmStore {
+    database: Database,
+}
+
+#[uniffi::export]
+impl OcmStore {
+    /// Open (or create) the store at `path` and bring its schema up to date
+    #[uniffi::constructor]
+    pub fn open(path: String) -> Result<Arc<Self>, OcmFfiError> {
+        let mut conn = rusqlite::Connection::open(&path).map_err(|e| OcmFfiError::Storage {
+            message: e.to_string(),
+        })?;
+        embedded::migrations::runner()
+            .run(&mut conn)
+            .map_err(|e| OcmFfiError::Storage {
+                message: format!("Migration failed: {}", e),
+            })?;
+        drop(conn);
+
+        Ok(Arc::new(Self {
+            database: Database::new(&path)?,
+        }))
+    }
+
+    /// Store a memory authored on this device
+    pub fn save_memory(&self, memory: MemoryRecord) -> Result<(), OcmFfiError> {
+        let memory = SignedMemory::try_from(memory)?;
+        if !memory.verify_hash() {
+            return Err(OcmFfiError::InvalidMemory {
+                message: format!("Memory {} does not match its content hash", memory.id),
+            });
+        }
+        self.database.create_signed_memory(&memory)?;
+        Ok(())
+    }
+
+    /// Store a memory received from elsewhere unless it is already held.
+    /// Returns whether it was new
+    pub fn import_memory(&self, memory: MemoryRecord) -> Result<bool, OcmFfiError> {
+        let memory = SignedMemory::try_from(memory)?;
+        if !memory.verify_hash() {
+            return Err(OcmFfiError::InvalidMemory {
+                message: format!("Memory {} does not match its content hash", memory.id),
+            });
+        }
+        Ok(self.database.import_signed_memories(&[memory])? > 0)
+    }
+
+    pub fn get_memory(&self, id: String) -> Result<Option<MemoryRecord>, OcmFfiError> {
+        Ok(self
+            .database
+            .get_signed_memory(&MemoryId::parse(&id)?)?
+            .map(MemoryRecord::from))
+    }
+
+    pub fn list_memories(&self) -> Result<Vec<MemoryRecord>, OcmFfiError> {
+        Ok(self
+            .database
+            .list_signed_memories()?
+            .into_iter()
+            .map(MemoryRecord::from)
+            .collect())
+    }
+}
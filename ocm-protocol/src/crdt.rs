@@ -1,8 +1,9 @@
-use crate::core::models::SignedMemory;
+use crate::ids::MemoryId;
+use crate::memory::SignedMemory;
 use serde::{Deserialize, Serialize};
 use std::collections::{BTreeMap, HashMap, HashSet};
 
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
 pub struct VectorClock {
     pub clock: BTreeMap<String, u64>, // peer_id -> logical_clock
 }
@@ -117,6 +118,25 @@ pub struct MemoryOperation {
     pub value: serde_json::Value,
     pub vector_clock: VectorClock,
     pub timestamp: String,
+    // Base64 signature of signing_payload by the memory's author, for operations made
+    // through a patch; operations from older peers have none
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub signature: Option<String>,
+}
+
+impl MemoryOperation {
+    /// What the author signs: everything but the vector clock, which peers rewrite
+    pub fn signing_payload(&self, memory_id: &str) -> String {
+        format!(
+            "{}|{}|{:?}|{}|{}|{}",
+            memory_id,
+            self.operation_id,
+            self.operation_type,
+            self.field_path,
+            self.value,
+            self.timestamp
+        )
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -412,7 +432,7 @@ impl CrdtMemory {
 
         // Find operations that conflict (same field path, different values)
         for other_op in &other.operations {
-            if self.has_operation(&other_op.operation_id.as_str()) {
+            if self.has_operation(other_op.operation_id.as_str()) {
                 continue; // Already applied this operation
             }
 
@@ -458,7 +478,7 @@ impl CrdtMemory {
                             field_path: other_op.field_path.clone(),
                             local_operation: conflicting_ops[0].clone(),
                             remote_operation: other_op.clone(),
-                            conflict_type: super::manager::ConflictType::ContentMismatch,
+                            conflict_type: ConflictType::ContentMismatch,
                         });
                     }
                 }
@@ -511,7 +531,14 @@ pub struct ConflictInfo {
     pub field_path: String,
     pub local_operation: MemoryOperation,
     pub remote_operation: MemoryOperation,
-    pub conflict_type: super::manager::ConflictType,
+    pub conflict_type: ConflictType,
+}
+
+#[derive(Debug, Clone)]
+pub enum ConflictType {
+    ContentMismatch,
+    TimestampConflict,
+    SignatureConflict,
 }
 
 #[derive(Debug, Clone)]
@@ -536,7 +563,7 @@ impl std::error::Error for CrdtError {}
 #[derive(Debug)]
 pub struct CrdtManager {
     pub peer_id: String,
-    pub memories: HashMap<String, CrdtMemory>, // memory_id -> CrdtMemory
+    pub memories: HashMap<MemoryId, CrdtMemory>,
 }
 
 impl CrdtManager {
@@ -547,7 +574,7 @@ impl CrdtManager {
         }
     }
 
-    pub fn add_memory(&mut self, memory: SignedMemory) -> String {
+    pub fn add_memory(&mut self, memory: SignedMemory) -> MemoryId {
         let memory_id = memory.id.clone();
         let crdt_memory = CrdtMemory::new(memory, &self.peer_id);
         self.memories.insert(memory_id.clone(), crdt_memory);
@@ -556,7 +583,7 @@ impl CrdtManager {
 
     pub fn update_memory(
         &mut self,
-        memory_id: &str,
+        memory_id: &MemoryId,
         field_path: &str,
         value: serde_json::Value,
     ) -> Result<(), CrdtError> {
@@ -572,6 +599,7 @@ impl CrdtManager {
                     clock
                 },
                 timestamp: chrono::Utc::now().to_rfc3339(),
+                signature: None,
             };
 
             crdt_memory.apply_operation(operation, &self.peer_id)?;
@@ -582,23 +610,23 @@ impl CrdtManager {
 
     pub fn merge_memory(
         &mut self,
-        memory_id: &str,
+        memory_id: &MemoryId,
         remote_memory: CrdtMemory,
     ) -> Result<Vec<ConflictInfo>, CrdtError> {
         if let Some(local_memory) = self.memories.get_mut(memory_id) {
-            local_memory.merge_with(&remote_memory, &self.peer_id.as_str())
+            local_memory.merge_with(&remote_memory, self.peer_id.as_str())
         } else {
             // New memory from remote peer
-            self.memories.insert(memory_id.to_string(), remote_memory);
+            self.memories.insert(memory_id.clone(), remote_memory);
             Ok(Vec::new())
         }
     }
 
-    pub fn get_memory(&self, memory_id: &str) -> Option<&CrdtMemory> {
+    pub fn get_memory(&self, memory_id: &MemoryId) -> Option<&CrdtMemory> {
         self.memories.get(memory_id)
     }
 
-    pub fn list_conflicts(&self) -> Vec<String> {
+    pub fn list_conflicts(&self) -> Vec<MemoryId> {
         // Return list of memory IDs that have unresolved conflicts
         self.memories
             .iter()
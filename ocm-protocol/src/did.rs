@@ -0,0 +1,125 @@
+//! DID documents and the signature checks that need nothing but a memory and its
+//! author's document, shared by the node, the relay and the browser client.
+
+use crate::ingest::RejectionReason;
+use crate::memory::SignedMemory;
+use base64::{engine::general_purpose, Engine as _};
+use ed25519_dalek::{Signature, Verifier, VerifyingKey};
+use serde::{Deserialize, Serialize};
+
+// Multicodec prefix for Ed25519 public keys in did:key identifiers
+pub const ED25519_PUB_MULTICODEC: [u8; 2] = [0xed, 0x01];
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PlcDocument {
+    pub id: String,
+    #[serde(rename = "@context")]
+    pub context: Vec<String>,
+    #[serde(rename = "alsoKnownAs")]
+    pub also_known_as: Option<Vec<String>>,
+    #[serde(rename = "verificationMethod")]
+    pub verification_method: Option<Vec<VerificationMethod>>,
+    pub service: Option<Vec<Service>>,
+}
+
+impl PlcDocument {
+    /// Multibase keys of the document's Multikey verification methods
+    pub fn multibase_keys(&self) -> Vec<String> {
+        self.verification_method
+            .iter()
+            .flatten()
+            .filter(|vm| vm.method_type == "Multikey")
+            .filter_map(|vm| vm.public_key_multibase.clone())
+            .collect()
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VerificationMethod {
+    pub id: String,
+    #[serde(rename = "type")]
+    pub method_type: String,
+    pub controller: String,
+    #[serde(rename = "publicKeyMultibase")]
+    pub public_key_multibase: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Service {
+    pub id: String,
+    #[serde(rename = "type")]
+    pub service_type: String,
+    #[serde(rename = "serviceEndpoint")]
+    pub service_endpoint: String,
+}
+
+/// Decode a 'z' (base58btc) multibase string into a 32-byte Ed25519 public key
+pub fn decode_multibase_ed25519(multibase: &str) -> Option<[u8; 32]> {
+    let encoded = multibase.strip_prefix('z')?;
+    let bytes = bs58::decode(encoded).into_vec().ok()?;
+    bytes.try_into().ok()
+}
+
+/// Decode a did:key identifier into the raw Ed25519 public key it carries
+pub fn decode_did_key(did: &str) -> Option<[u8; 32]> {
+    let encoded = did.strip_prefix("did:key:z")?;
+    let bytes = bs58::decode(encoded).into_vec().ok()?;
+    let key = bytes.strip_prefix(&ED25519_PUB_MULTICODEC[..])?;
+    key.try_into().ok()
+}
+
+/// Whether checking a memory by `did` needs its PLC document; did:key authors carry their key
+pub fn needs_plc_document(did: &str) -> bool {
+    !did.starts_with("did:key:")
+}
+
+/// Check a received memory against its author's PLC document, fetched by the caller, or
+/// against its did:key. Without a document a did:plc memory is unverifiable
+pub fn verify_memory_with_document(
+    memory: &SignedMemory,
+    document: Option<&PlcDocument>,
+) -> Result<(), RejectionReason> {
+    if !memory.verify_hash() {
+        return Err(RejectionReason::HashMismatch);
+    }
+    let keys: Vec<[u8; 32]> = match decode_did_key(&memory.did) {
+        Some(key) => vec![key],
+        None if !needs_plc_document(&memory.did) => return Err(RejectionReason::BadSignature),
+        None => match document.filter(|document| memory.did == document.id) {
+            Some(document) => document
+                .multibase_keys()
+                .iter()
+                .filter_map(|key| decode_multibase_ed25519(key))
+                .collect(),
+            None => return Err(RejectionReason::Unverifiable),
+        },
+    };
+    let payload = memory.get_signing_payload();
+    if keys
+        .iter()
+        .any(|key| verify_payload_signature(key, &payload, &memory.signature))
+    {
+        Ok(())
+    } else {
+        Err(RejectionReason::BadSignature)
+    }
+}
+
+/// Check a base64 Ed25519 signature over `payload` against a raw public key
+pub fn verify_payload_signature(public_key: &[u8; 32], payload: &str, signature_b64: &str) -> bool {
+    let verifying_key = match VerifyingKey::from_bytes(public_key) {
+        Ok(key) => key,
+        Err(_) => return false,
+    };
+    let signature_bytes = match general_purpose::STANDARD.decode(signature_b64) {
+        Ok(bytes) => bytes,
+        Err(_) => return false,
+    };
+    let signature_array: [u8; 64] = match signature_bytes.try_into() {
+        Ok(array) => array,
+        Err(_) => return false,
+    };
+    let signature = Signature::from_bytes(&signature_array);
+
+    verifying_key.verify(payload.as_bytes(), &signature).is_ok()
+}
@@ -0,0 +1,134 @@
+use crate::filter::{DataAccess, Filter, SqlValue};
+use crate::ids::MemoryId;
+use crate::memory::SignedMemory;
+use serde::{Deserialize, Serialize};
+
+pub const DEFAULT_FEED_LIMIT: usize = 20;
+pub const MAX_FEED_LIMIT: usize = 100;
+
+/// Whose memories a feed shows
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind", content = "did", rename_all = "snake_case")]
+pub enum FeedSource {
+    All,
+    Did(String),
+    Group(String), // The group DID and its current members
+}
+
+/// Position in a feed: the last memory of the previous page.
+/// Memories are ordered newest first by timestamp, then by ID to break ties
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FeedCursor {
+    pub timestamp: String,
+    pub id: MemoryId,
+}
+
+impl FeedCursor {
+    pub fn after(memory: &SignedMemory) -> Self {
+        Self {
+            timestamp: memory.timestamp.clone(),
+            id: memory.id.clone(),
+        }
+    }
+
+    /// Opaque form handed to clients
+    pub fn encode(&self) -> String {
+        format!("{}/{}", self.timestamp, self.id)
+    }
+
+    pub fn decode(cursor: &str) -> Result<Self, String> {
+        match cursor.split_once('/') {
+            Some((timestamp, id)) if !timestamp.is_empty() => match MemoryId::parse(id) {
+                Ok(id) => Ok(Self {
+                    timestamp: timestamp.to_string(),
+                    id,
+                }),
+                Err(_) => Err(format!("Invalid feed cursor: {}", cursor)),
+            },
+            _ => Err(format!("Invalid feed cursor: {}", cursor)),
+        }
+    }
+}
+
+/// One page of memories with filters already resolved to author DIDs
+#[derive(Debug, Clone, Default)]
+pub struct FeedQuery {
+    pub authors: Option<Vec<String>>, // None means every author
+    pub memory_types: Vec<String>,    // Empty means every type
+    pub cursor: Option<FeedCursor>,
+    pub limit: usize,
+    pub filter: Option<Filter>,
+}
+
+impl FeedQuery {
+    /// WHERE clause with `?` placeholders, and its parameters in order, for the
+    /// signed_memory table in either the native or the browser database.
+    /// `data` says where a memory's data lives
+    pub fn where_clause(&self, data: &dyn DataAccess) -> (String, Vec<SqlValue>) {
+        let mut conditions = Vec::new();
+        let mut params = Vec::new();
+
+        match &self.authors {
+            Some(authors) if authors.is_empty() => conditions.push("0 = 1".to_string()),
+            Some(authors) => {
+                conditions.push(format!("did IN ({})", placeholders(authors.len())));
+                params.extend(authors.iter().cloned().map(SqlValue::Text));
+            }
+            None => {}
+        }
+        if !self.memory_types.is_empty() {
+            conditions.push(format!(
+                "memory_type IN ({})",
+                placeholders(self.memory_types.len())
+            ));
+            params.extend(self.memory_types.iter().cloned().map(SqlValue::Text));
+        }
+        if let Some(cursor) = &self.cursor {
+            conditions.push("(timestamp < ? OR (timestamp = ? AND id < ?))".to_string());
+            params.push(SqlValue::Text(cursor.timestamp.clone()));
+            params.push(SqlValue::Text(cursor.timestamp.clone()));
+            params.push(SqlValue::Text(cursor.id.to_string()));
+        }
+        if let Some(filter) = &self.filter {
+            conditions.push(filter.to_sql(data, &mut params));
+        }
+
+        let clause = if conditions.is_empty() {
+            "1 = 1".to_string()
+        } else {
+            conditions.join(" AND ")
+        };
+        (clause, params)
+    }
+
+    /// Rows to fetch: one more than the page, to learn whether another page follows
+    pub fn fetch_limit(&self) -> usize {
+        self.limit.clamp(1, MAX_FEED_LIMIT) + 1
+    }
+
+    /// Trim fetched rows to the page and work out the cursor for the next one
+    pub fn page(&self, mut memories: Vec<SignedMemory>) -> FeedPage {
+        let limit = self.limit.clamp(1, MAX_FEED_LIMIT);
+        let has_more = memories.len() > limit;
+        memories.truncate(limit);
+        let next_cursor = match (has_more, memories.last()) {
+            (true, Some(last)) => Some(FeedCursor::after(last).encode()),
+            _ => None,
+        };
+        FeedPage {
+            memories,
+            next_cursor,
+        }
+    }
+}
+
+/// Newest-first memories; pass `next_cursor` back to continue, None means the end
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FeedPage {
+    pub memories: Vec<SignedMemory>,
+    pub next_cursor: Option<String>,
+}
+
+fn placeholders(count: usize) -> String {
+    vec!["?"; count].join(", ")
+}
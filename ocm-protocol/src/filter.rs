@@ -0,0 +1,507 @@
+//! A small filter language for memory queries, such as
+//! `type=attendance AND data.cohort='Red' AND timestamp>2024-06-01`.
+//! Filters compile to a parameterized WHERE clause for the signed_memory table, with
+//! JSON1 `json_extract` for fields inside the memory data. Values are always bound as
+//! parameters; data paths are inlined (so expression indices can match them) and
+//! restricted to letters, digits and underscores.
+
+pub const MAX_FILTER_LENGTH: usize = 1024;
+const MAX_DEPTH: usize = 16; // Nested parentheses and NOTs
+
+/// A parameter bound to a compiled filter's placeholder
+#[derive(Debug, Clone, PartialEq)]
+pub enum SqlValue {
+    Null,
+    Integer(i64),
+    Real(f64),
+    Text(String),
+}
+
+/// How compiled filters reach values inside memory data. Storage that indexes some
+/// paths can answer those comparisons with SQL its indices match
+pub trait DataAccess {
+    /// SQL testing the value at `path` (e.g. `$.cohort`, safe to inline). `condition`
+    /// renders the test against a JSON text expression and appends its parameters, so
+    /// call it once per place the test appears, in order
+    fn data_condition(&self, path: &str, condition: &mut dyn FnMut(&str) -> String) -> String;
+}
+
+/// Memory data held as JSON text in one column or expression
+pub struct JsonColumn<'a>(pub &'a str);
+
+impl DataAccess for JsonColumn<'_> {
+    fn data_condition(&self, path: &str, condition: &mut dyn FnMut(&str) -> String) -> String {
+        condition(&format!("json_extract({}, '{}')", self.0, path))
+    }
+}
+
+/// What a comparison looks at
+#[derive(Debug, Clone, PartialEq)]
+pub enum FilterField {
+    Id,
+    Did,
+    MemoryType,
+    Timestamp,
+    UpdatedOn,
+    Data(String), // JSON path into the memory data, e.g. "$.cohort"
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FilterOp {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    Contains, // Substring match, written `~`
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Filter {
+    Compare {
+        field: FilterField,
+        op: FilterOp,
+        value: SqlValue,
+    },
+    And(Box<Filter>, Box<Filter>),
+    Or(Box<Filter>, Box<Filter>),
+    Not(Box<Filter>),
+}
+
+impl Filter {
+    /// Parse a filter expression. Comparisons are `field op value` with fields `id`,
+    /// `did`, `type`, `timestamp`, `updated_on` or `data.<path>`, operators
+    /// `= != < <= > >= ~`, and values either quoted ('Red', "Red") or bare (Red,
+    /// 2024-06-01, 42, true, null). Combine them with AND, OR, NOT and parentheses
+    pub fn parse(input: &str) -> Result<Self, String> {
+        if input.len() > MAX_FILTER_LENGTH {
+            return Err(format!(
+                "Filter is longer than {} characters",
+                MAX_FILTER_LENGTH
+            ));
+        }
+        let mut parser = Parser {
+            tokens: tokenize(input)?,
+            position: 0,
+        };
+        let filter = parser.or(0)?;
+        match parser.next() {
+            None => Ok(filter),
+            Some(token) => Err(format!("Unexpected {} in filter", token.describe())),
+        }
+    }
+
+    /// WHERE clause with `?` placeholders, appending its parameters to `params`.
+    /// `data` says where the memory data lives
+    pub fn to_sql(&self, data: &dyn DataAccess, params: &mut Vec<SqlValue>) -> String {
+        match self {
+            Filter::Compare { field, op, value } => {
+                let mut condition = |expression: &str| match (op, value) {
+                    (FilterOp::Eq, SqlValue::Null) => format!("{} IS NULL", expression),
+                    (FilterOp::Ne, SqlValue::Null) => format!("{} IS NOT NULL", expression),
+                    (FilterOp::Contains, value) => {
+                        params.push(value.clone());
+                        format!("instr({}, ?) > 0", expression)
+                    }
+                    (op, value) => {
+                        params.push(value.clone());
+                        format!("{} {} ?", expression, op.as_sql())
+                    }
+                };
+                match field {
+                    FilterField::Id => condition("id"),
+                    FilterField::Did => condition("did"),
+                    FilterField::MemoryType => condition("memory_type"),
+                    FilterField::Timestamp => condition("timestamp"),
+                    FilterField::UpdatedOn => condition("updated_on"),
+                    FilterField::Data(path) => data.data_condition(path, &mut condition),
+                }
+            }
+            Filter::And(left, right) => format!(
+                "({} AND {})",
+                left.to_sql(data, params),
+                right.to_sql(data, params)
+            ),
+            Filter::Or(left, right) => format!(
+                "({} OR {})",
+                left.to_sql(data, params),
+                right.to_sql(data, params)
+            ),
+            Filter::Not(inner) => format!("NOT ({})", inner.to_sql(data, params)),
+        }
+    }
+}
+
+impl FilterOp {
+    fn as_sql(&self) -> &'static str {
+        match self {
+            FilterOp::Eq => "=",
+            FilterOp::Ne => "!=",
+            FilterOp::Lt => "<",
+            FilterOp::Le => "<=",
+            FilterOp::Gt => ">",
+            FilterOp::Ge => ">=",
+            FilterOp::Contains => "~",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Open,
+    Close,
+    Op(FilterOp),
+    Word(String),   // Bare field names, keywords and values
+    Quoted(String), // Always a text value
+}
+
+impl Token {
+    fn describe(&self) -> String {
+        match self {
+            Token::Open => "'('".to_string(),
+            Token::Close => "')'".to_string(),
+            Token::Op(op) => format!("'{}'", op.as_sql()),
+            Token::Word(word) => format!("'{}'", word),
+            Token::Quoted(text) => format!("'{}'", text),
+        }
+    }
+
+    fn is_keyword(&self, keyword: &str) -> bool {
+        matches!(self, Token::Word(word) if word.eq_ignore_ascii_case(keyword))
+    }
+}
+
+fn tokenize(input: &str) -> Result<Vec<Token>, String> {
+    let mut tokens = Vec::new();
+    let mut chars = input.chars().peekable();
+    while let Some(&c) = chars.peek() {
+        match c {
+            c if c.is_whitespace() => {
+                chars.next();
+            }
+            '(' => {
+                chars.next();
+                tokens.push(Token::Open);
+            }
+            ')' => {
+                chars.next();
+                tokens.push(Token::Close);
+            }
+            '=' | '~' => {
+                chars.next();
+                tokens.push(Token::Op(if c == '=' {
+                    FilterOp::Eq
+                } else {
+                    FilterOp::Contains
+                }));
+            }
+            '!' | '<' | '>' => {
+                chars.next();
+                let or_equal = chars.next_if_eq(&'=').is_some();
+                let op = match (c, or_equal) {
+                    ('!', true) => FilterOp::Ne,
+                    ('!', false) => return Err("Expected '=' after '!'".to_string()),
+                    ('<', true) => FilterOp::Le,
+                    ('<', false) => FilterOp::Lt,
+                    ('>', true) => FilterOp::Ge,
+                    _ => FilterOp::Gt,
+                };
+                tokens.push(Token::Op(op));
+            }
+            '\'' | '"' => {
+                chars.next();
+                let mut text = String::new();
+                loop {
+                    match chars.next() {
+                        // A doubled quote stands for one quote character
+                        Some(q) if q == c && chars.next_if_eq(&c).is_some() => text.push(c),
+                        Some(q) if q == c => break,
+                        Some(other) => text.push(other),
+                        None => return Err("Unterminated string in filter".to_string()),
+                    }
+                }
+                tokens.push(Token::Quoted(text));
+            }
+            _ => {
+                let mut word = String::new();
+                while let Some(&c) = chars.peek() {
+                    if c.is_whitespace() || "()=~!<>'\"".contains(c) {
+                        break;
+                    }
+                    word.push(c);
+                    chars.next();
+                }
+                tokens.push(Token::Word(word));
+            }
+        }
+    }
+    Ok(tokens)
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    position: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.position)
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.position).cloned();
+        self.position += 1;
+        token
+    }
+
+    fn next_is_keyword(&self, keyword: &str) -> bool {
+        self.peek().is_some_and(|token| token.is_keyword(keyword))
+    }
+
+    fn or(&mut self, depth: usize) -> Result<Filter, String> {
+        let mut filter = self.and(depth)?;
+        while self.next_is_keyword("OR") {
+            self.next();
+            filter = Filter::Or(Box::new(filter), Box::new(self.and(depth)?));
+        }
+        Ok(filter)
+    }
+
+    fn and(&mut self, depth: usize) -> Result<Filter, String> {
+        let mut filter = self.unary(depth)?;
+        while self.next_is_keyword("AND") {
+            self.next();
+            filter = Filter::And(Box::new(filter), Box::new(self.unary(depth)?));
+        }
+        Ok(filter)
+    }
+
+    fn unary(&mut self, depth: usize) -> Result<Filter, String> {
+        if depth >= MAX_DEPTH {
+            return Err(format!("Filter is nested more than {} deep", MAX_DEPTH));
+        }
+        if self.next_is_keyword("NOT") {
+            self.next();
+            return Ok(Filter::Not(Box::new(self.unary(depth + 1)?)));
+        }
+        if self.peek() == Some(&Token::Open) {
+            self.next();
+            let filter = self.or(depth + 1)?;
+            return match self.next() {
+                Some(Token::Close) => Ok(filter),
+                _ => Err("Expected ')' in filter".to_string()),
+            };
+        }
+        self.comparison()
+    }
+
+    fn comparison(&mut self) -> Result<Filter, String> {
+        let field = match self.next() {
+            Some(Token::Word(name)) => parse_field(&name)?,
+            Some(token) => return Err(format!("Expected a field, found {}", token.describe())),
+            None => return Err("Expected a field at the end of the filter".to_string()),
+        };
+        let op = match self.next() {
+            Some(Token::Op(op)) => op,
+            _ => return Err("Expected an operator after the field".to_string()),
+        };
+        let value = match self.next() {
+            Some(Token::Quoted(text)) => SqlValue::Text(text),
+            Some(Token::Word(word)) => parse_bare_value(&field, op, word),
+            _ => return Err("Expected a value after the operator".to_string()),
+        };
+        if value == SqlValue::Null && !matches!(op, FilterOp::Eq | FilterOp::Ne) {
+            return Err("null can only be compared with = or !=".to_string());
+        }
+        Ok(Filter::Compare { field, op, value })
+    }
+}
+
+fn parse_field(name: &str) -> Result<FilterField, String> {
+    match name {
+        "id" => Ok(FilterField::Id),
+        "did" => Ok(FilterField::Did),
+        "type" | "memory_type" => Ok(FilterField::MemoryType),
+        "timestamp" => Ok(FilterField::Timestamp),
+        "updated_on" => Ok(FilterField::UpdatedOn),
+        _ => {
+            let path = name
+                .strip_prefix("data.")
+                .ok_or_else(|| format!("Unknown filter field '{}'", name))?;
+            let valid = path.split('.').all(|segment| {
+                !segment.is_empty()
+                    && segment
+                        .chars()
+                        .all(|c| c.is_ascii_alphanumeric() || c == '_')
+            });
+            if !valid {
+                return Err(format!("Invalid data path '{}'", name));
+            }
+            Ok(FilterField::Data(format!("$.{}", path)))
+        }
+    }
+}
+
+/// Columns hold text, so bare values compare as text there. Inside the memory data
+/// numbers, booleans and null keep the types json_extract returns for them
+fn parse_bare_value(field: &FilterField, op: FilterOp, word: String) -> SqlValue {
+    if !matches!(field, FilterField::Data(_)) || op == FilterOp::Contains {
+        return SqlValue::Text(word);
+    }
+    match word.as_str() {
+        "null" => SqlValue::Null,
+        "true" => SqlValue::Integer(1),
+        "false" => SqlValue::Integer(0),
+        _ => {
+            if let Ok(integer) = word.parse::<i64>() {
+                SqlValue::Integer(integer)
+            } else if let Some(real) = word.parse::<f64>().ok().filter(|real| real.is_finite()) {
+                SqlValue::Real(real)
+            } else {
+                SqlValue::Text(word)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn compile(input: &str) -> (String, Vec<SqlValue>) {
+        let mut params = Vec::new();
+        let sql = Filter::parse(input)
+            .unwrap()
+            .to_sql(&JsonColumn("memory_data"), &mut params);
+        (sql, params)
+    }
+
+    fn text(value: &str) -> SqlValue {
+        SqlValue::Text(value.to_string())
+    }
+
+    #[test]
+    fn test_and_binds_tighter_than_or() {
+        let (sql, params) = compile("type=a OR type=b AND type=c");
+        assert_eq!(
+            sql,
+            "(memory_type = ? OR (memory_type = ? AND memory_type = ?))"
+        );
+        assert_eq!(params, vec![text("a"), text("b"), text("c")]);
+
+        let (sql, _) = compile("(type=a OR type=b) AND type=c");
+        assert_eq!(
+            sql,
+            "((memory_type = ? OR memory_type = ?) AND memory_type = ?)"
+        );
+    }
+
+    #[test]
+    fn test_not_applies_to_the_next_operand_only() {
+        let (sql, _) = compile("NOT type=a AND did=b");
+        assert_eq!(sql, "(NOT (memory_type = ?) AND did = ?)");
+
+        let (sql, _) = compile("not (type=a or did=b)");
+        assert_eq!(sql, "NOT ((memory_type = ? OR did = ?))");
+    }
+
+    #[test]
+    fn test_quoted_and_bare_values() {
+        let (_, params) =
+            compile(r#"data.cohort='Red' AND data.name="O""Brien" AND data.note='it''s'"#);
+        assert_eq!(params, vec![text("Red"), text("O\"Brien"), text("it's")]);
+
+        let (_, params) =
+            compile("data.age=42 AND data.score>=1.5 AND data.active=true AND data.label=blue");
+        assert_eq!(
+            params,
+            vec![
+                SqlValue::Integer(42),
+                SqlValue::Real(1.5),
+                SqlValue::Integer(1),
+                text("blue")
+            ]
+        );
+
+        // Quoted numbers and bare values compared with a column stay text
+        let (_, params) = compile("data.age='42' AND timestamp>2024-06-01 AND id=7");
+        assert_eq!(params, vec![text("42"), text("2024-06-01"), text("7")]);
+    }
+
+    #[test]
+    fn test_null_comparisons() {
+        let (sql, params) = compile("data.email=null OR data.phone!=null");
+        assert_eq!(
+            sql,
+            "(json_extract(memory_data, '$.email') IS NULL OR json_extract(memory_data, '$.phone') IS NOT NULL)"
+        );
+        assert!(params.is_empty());
+        assert!(Filter::parse("data.email>null").is_err());
+    }
+
+    #[test]
+    fn test_generated_sql_and_parameter_order() {
+        let (sql, params) =
+            compile("type=attendance AND data.cohort.name~'Re' AND NOT updated_on<=2024-01-01");
+        assert_eq!(
+            sql,
+            "((memory_type = ? AND instr(json_extract(memory_data, '$.cohort.name'), ?) > 0) AND NOT (updated_on <= ?))"
+        );
+        assert_eq!(
+            params,
+            vec![text("attendance"), text("Re"), text("2024-01-01")]
+        );
+    }
+
+    #[test]
+    fn test_invalid_data_paths_are_rejected() {
+        for input in [
+            "data.a'b = 1",
+            "data.a-b = 1",
+            "data.a b = 1",
+            "data..a = 1",
+            "data. = 1",
+            "data.a.$ = 1",
+            "data = 1",
+            "unknown = 1",
+        ] {
+            assert!(Filter::parse(input).is_err(), "{} was accepted", input);
+        }
+    }
+
+    #[test]
+    fn test_malformed_filters_are_rejected() {
+        for input in [
+            "",
+            "type",
+            "type=",
+            "type!a",
+            "type='open",
+            "(type=a",
+            "type=a)",
+            "type=a AND",
+            "type=a did=b",
+        ] {
+            assert!(Filter::parse(input).is_err(), "{:?} was accepted", input);
+        }
+    }
+
+    #[test]
+    fn test_nesting_limit() {
+        let nested = |depth: usize| format!("{}type=a{}", "(".repeat(depth), ")".repeat(depth));
+        assert!(Filter::parse(&nested(MAX_DEPTH - 1)).is_ok());
+        assert!(Filter::parse(&nested(MAX_DEPTH)).is_err());
+
+        let negated = |depth: usize| format!("{}type=a", "NOT ".repeat(depth));
+        assert!(Filter::parse(&negated(MAX_DEPTH - 1)).is_ok());
+        assert!(Filter::parse(&negated(MAX_DEPTH)).is_err());
+    }
+
+    #[test]
+    fn test_length_limit() {
+        let filter = |length: usize| format!("type={}", "a".repeat(length - "type=".len()));
+        assert!(Filter::parse(&filter(MAX_FILTER_LENGTH)).is_ok());
+        assert!(Filter::parse(&filter(MAX_FILTER_LENGTH + 1)).is_err());
+    }
+}
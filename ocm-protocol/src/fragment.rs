@@ -0,0 +1,258 @@
+//! Splitting peer messages larger than one frame into fragments and putting them back
+//! together. Fragments of a message travel in order on one connection; the receiver
+//! checks the reassembled bytes against the digest every fragment carries
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+/// Frames every node has always accepted; peers that advertise no limits get no more
+pub const LEGACY_FRAME_BYTES: usize = 1024 * 1024;
+
+// Room for the envelope around a fragment's data: IDs, nonce, HMAC and field names
+const FRAGMENT_OVERHEAD_BYTES: usize = 2048;
+
+/// How large a frame and a whole (possibly fragmented) message a node accepts
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct FrameLimits {
+    pub max_frame_bytes: usize,
+    pub max_message_bytes: usize,
+}
+
+impl Default for FrameLimits {
+    fn default() -> Self {
+        Self {
+            max_frame_bytes: LEGACY_FRAME_BYTES,
+            max_message_bytes: 32 * 1024 * 1024,
+        }
+    }
+}
+
+impl FrameLimits {
+    /// Limits both sides accept. A peer that advertised none takes legacy frames and
+    /// cannot reassemble fragments
+    pub fn negotiate(&self, peer: Option<&FrameLimits>) -> FrameLimits {
+        match peer {
+            Some(peer) => FrameLimits {
+                max_frame_bytes: self.max_frame_bytes.min(peer.max_frame_bytes),
+                max_message_bytes: self.max_message_bytes.min(peer.max_message_bytes),
+            },
+            None => {
+                let max_frame_bytes = self.max_frame_bytes.min(LEGACY_FRAME_BYTES);
+                FrameLimits {
+                    max_frame_bytes,
+                    max_message_bytes: max_frame_bytes,
+                }
+            }
+        }
+    }
+
+    /// Bytes of message carried per fragment. Escaping inside the envelope can at most
+    /// double them, so this stays within a frame
+    pub fn fragment_bytes(&self) -> usize {
+        (self.max_frame_bytes.saturating_sub(FRAGMENT_OVERHEAD_BYTES) / 2).max(1)
+    }
+}
+
+/// One piece of a serialized message too large for a single frame
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FragmentPayload {
+    pub message_id: String,
+    pub index: u32,
+    pub total: u32,
+    pub total_bytes: usize,
+    pub digest: String, // Hex SHA-256 of the whole serialized message
+    pub data: String,
+}
+
+/// Split a serialized message into fragments of at most `fragment_bytes` each,
+/// never inside a UTF-8 character
+pub fn split(message: &str, fragment_bytes: usize) -> Vec<FragmentPayload> {
+    let message_id = uuid::Uuid::new_v4().to_string();
+    let digest = hex::encode(Sha256::digest(message.as_bytes()));
+
+    let mut chunks = Vec::new();
+    let mut rest = message;
+    while !rest.is_empty() {
+        let mut end = fragment_bytes.max(4).min(rest.len());
+        while !rest.is_char_boundary(end) {
+            end -= 1;
+        }
+        let (chunk, tail) = rest.split_at(end);
+        chunks.push(chunk);
+        rest = tail;
+    }
+
+    let total = chunks.len() as u32;
+    chunks
+        .into_iter()
+        .enumerate()
+        .map(|(index, data)| FragmentPayload {
+            message_id: message_id.clone(),
+            index: index as u32,
+            total,
+            total_bytes: message.len(),
+            digest: digest.clone(),
+            data: data.to_string(),
+        })
+        .collect()
+}
+
+/// Puts the fragments arriving on one connection back together, one message at a time
+#[derive(Debug)]
+pub struct Reassembler {
+    max_message_bytes: usize,
+    partial: Option<Partial>,
+}
+
+#[derive(Debug)]
+struct Partial {
+    first: FragmentPayload, // Its ID, count, size and digest hold for every fragment
+    next: u32,
+    data: String,
+}
+
+impl Reassembler {
+    pub fn new(max_message_bytes: usize) -> Self {
+        Self {
+            max_message_bytes,
+            partial: None,
+        }
+    }
+
+    /// Add the next fragment, returning the whole message once its last fragment is in.
+    /// Out-of-order fragments, oversized messages and digest mismatches are errors and
+    /// discard what was collected
+    pub fn push(&mut self, fragment: FragmentPayload) -> Result<Option<String>, String> {
+        let result = self.accept(fragment);
+        if !matches!(result, Ok(None)) {
+            self.partial = None;
+        }
+        result
+    }
+
+    fn accept(&mut self, fragment: FragmentPayload) -> Result<Option<String>, String> {
+        if fragment.total_bytes > self.max_message_bytes {
+            return Err(format!(
+                "Fragmented message of {} bytes exceeds the {} byte limit",
+                fragment.total_bytes, self.max_message_bytes
+            ));
+        }
+
+        let mut partial = match self.partial.take() {
+            // Grown as fragments arrive; total_bytes is only what the sender claims
+            None if fragment.index == 0 => Partial {
+                data: String::new(),
+                first: fragment.clone(),
+                next: 0,
+            },
+            None => return Err(format!("Fragment {} arrived first", fragment.index)),
+            Some(partial) => partial,
+        };
+        if fragment.message_id != partial.first.message_id || fragment.index != partial.next {
+            return Err(format!(
+                "Expected fragment {} of {}, got {} of {}",
+                partial.next, partial.first.message_id, fragment.index, fragment.message_id
+            ));
+        }
+
+        partial.data.push_str(&fragment.data);
+        partial.next += 1;
+        if partial.data.len() > partial.first.total_bytes {
+            return Err("Fragments carry more data than announced".to_string());
+        }
+        if partial.next < partial.first.total {
+            self.partial = Some(partial);
+            return Ok(None);
+        }
+
+        if partial.data.len() != partial.first.total_bytes
+            || hex::encode(Sha256::digest(partial.data.as_bytes())) != partial.first.digest
+        {
+            return Err(format!(
+                "Reassembled message {} does not match its digest",
+                partial.first.message_id
+            ));
+        }
+        Ok(Some(partial.data))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LIMIT: usize = 1024;
+
+    fn reassemble(fragments: Vec<FragmentPayload>) -> Result<Option<String>, String> {
+        let mut reassembler = Reassembler::new(LIMIT);
+        let mut result = Ok(None);
+        for fragment in fragments {
+            result = reassembler.push(fragment);
+        }
+        result
+    }
+
+    #[test]
+    fn test_split_and_reassemble_round_trip() {
+        let message = "héllo wörld ".repeat(20);
+        let fragments = split(&message, 16);
+        assert!(fragments.len() > 1);
+        assert!(fragments.iter().all(|f| f.data.len() <= 16));
+        assert_eq!(reassemble(fragments), Ok(Some(message)));
+
+        // The reassembler takes the next message once one is complete
+        let mut reassembler = Reassembler::new(LIMIT);
+        for message in ["first message", "second message"] {
+            let mut fragments = split(message, 8);
+            let last = fragments.pop().unwrap();
+            for fragment in fragments {
+                assert_eq!(reassembler.push(fragment), Ok(None));
+            }
+            assert_eq!(reassembler.push(last), Ok(Some(message.to_string())));
+        }
+    }
+
+    #[test]
+    fn test_out_of_order_fragments_are_rejected() {
+        let mut fragments = split("abcdefghijklmnop", 4);
+        fragments.swap(1, 2);
+        assert!(reassemble(fragments).is_err());
+
+        let fragments = split("abcdefghijklmnop", 4);
+        assert!(reassemble(fragments[1..].to_vec()).is_err());
+    }
+
+    #[test]
+    fn test_duplicate_fragment_is_rejected_and_discards_the_message() {
+        let fragments = split("abcdefghijklmnop", 4);
+        let mut reassembler = Reassembler::new(LIMIT);
+        assert_eq!(reassembler.push(fragments[0].clone()), Ok(None));
+        assert!(reassembler.push(fragments[0].clone()).is_err());
+        // What was collected is gone, so the rest cannot complete it
+        assert!(reassembler.push(fragments[1].clone()).is_err());
+    }
+
+    #[test]
+    fn test_digest_mismatch_is_rejected() {
+        let mut fragments = split("abcdefghijklmnop", 4);
+        fragments[2].data = "XXXX".to_string();
+        let result = reassemble(fragments);
+        assert!(result.is_err_and(|e| e.contains("does not match its digest")));
+    }
+
+    #[test]
+    fn test_oversize_messages_are_rejected() {
+        let message = "x".repeat(LIMIT + 1);
+        let result = reassemble(split(&message, 256));
+        assert!(result.is_err_and(|e| e.contains("exceeds")));
+
+        // Fragments carrying more than the size they announce
+        let mut fragments = split("abcdefgh", 4);
+        for fragment in &mut fragments {
+            fragment.total_bytes = 4;
+        }
+        let result = reassemble(fragments);
+        assert!(result.is_err_and(|e| e.contains("more data than announced")));
+    }
+}
@@ -0,0 +1,101 @@
+use serde::{Deserialize, Serialize};
+
+/// DNS name prefix of the TXT record proving a domain handle: `_ocm.alice.example.org`
+pub const DNS_TXT_PREFIX: &str = "_ocm";
+/// Value prefix inside that TXT record: `did=did:plc:...`
+pub const DNS_TXT_VALUE_PREFIX: &str = "did=";
+/// Path served by a handle's domain with the bare DID as its body
+pub const WELL_KNOWN_PATH: &str = "/.well-known/ocm-did";
+
+const MAX_HANDLE_LEN: usize = 253;
+const MAX_LABEL_LEN: usize = 63;
+
+/// How a handle was tied to its DID
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HandleVerification {
+    Local,     // Under the node's own suffix, nothing outside to check
+    Dns,       // TXT record at _ocm.{handle}
+    WellKnown, // https://{handle}/.well-known/ocm-did
+}
+
+impl HandleVerification {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            HandleVerification::Local => "local",
+            HandleVerification::Dns => "dns",
+            HandleVerification::WellKnown => "well_known",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "local" => Some(HandleVerification::Local),
+            "dns" => Some(HandleVerification::Dns),
+            "well_known" => Some(HandleVerification::WellKnown),
+            _ => None,
+        }
+    }
+}
+
+/// Canonical form of a handle as typed by a user: `@Alice.Family-OCM` becomes
+/// `alice.family-ocm`. Handles are domain names of at least two labels
+pub fn normalize_handle(input: &str) -> Result<String, String> {
+    let handle = input.trim().trim_start_matches('@').to_ascii_lowercase();
+    if handle.is_empty() || handle.len() > MAX_HANDLE_LEN {
+        return Err(format!(
+            "Handle must be 1-{} characters: {}",
+            MAX_HANDLE_LEN, input
+        ));
+    }
+
+    let labels: Vec<&str> = handle.split('.').collect();
+    if labels.len() < 2 {
+        return Err(format!(
+            "Handle needs at least two dot-separated parts: {}",
+            input
+        ));
+    }
+    for label in &labels {
+        let valid = !label.is_empty()
+            && label.len() <= MAX_LABEL_LEN
+            && label
+                .chars()
+                .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-')
+            && !label.starts_with('-')
+            && !label.ends_with('-');
+        if !valid {
+            return Err(format!(
+                "Handle parts must be letters, digits or inner hyphens: {}",
+                input
+            ));
+        }
+    }
+    if labels[labels.len() - 1].chars().all(|c| c.is_ascii_digit()) {
+        return Err(format!("Handle cannot end in a number: {}", input));
+    }
+    Ok(handle)
+}
+
+/// Whether a normalized handle sits under `suffix`, e.g. `alice.family-ocm` under `family-ocm`
+pub fn is_under_suffix(handle: &str, suffix: &str) -> bool {
+    let suffix = suffix.trim_start_matches('.');
+    handle
+        .strip_suffix(suffix)
+        .is_some_and(|rest| rest.len() > 1 && rest.ends_with('.'))
+}
+
+/// DNS name holding the TXT record for a handle
+pub fn dns_txt_name(handle: &str) -> String {
+    format!("{}.{}", DNS_TXT_PREFIX, handle)
+}
+
+/// The DID named by a TXT record value, if it is a handle record
+pub fn parse_dns_txt(value: &str) -> Option<&str> {
+    value
+        .trim()
+        .trim_matches('"')
+        .strip_prefix(DNS_TXT_VALUE_PREFIX)
+        .map(str::trim)
+        .filter(|did| did.starts_with("did:"))
+}
@@ -0,0 +1,259 @@
+//! Typed identifiers. Memory IDs, DIDs, peer IDs and claim token IDs are all strings on
+//! the wire and in the database; these wrappers keep one from being passed where
+//! another is expected, and are checked when parsed.
+
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::ops::Deref;
+use std::str::FromStr;
+
+const MAX_ID_LEN: usize = 128;
+const MAX_DID_LEN: usize = 256;
+
+/// A string that is not a valid identifier of the expected kind
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InvalidId {
+    pub kind: &'static str,
+    pub value: String,
+}
+
+impl fmt::Display for InvalidId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Invalid {} '{}'", self.kind, self.value)
+    }
+}
+
+impl std::error::Error for InvalidId {}
+
+macro_rules! id_type {
+    ($(#[$doc:meta])* $name:ident, $label:literal, $check:ident) => {
+        $(#[$doc])*
+        #[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+        #[serde(try_from = "String", into = "String")]
+        pub struct $name(String);
+
+        impl $name {
+            pub fn parse(value: &str) -> Result<Self, InvalidId> {
+                if $check(value) {
+                    Ok(Self(value.to_string()))
+                } else {
+                    Err(InvalidId { kind: $label, value: value.to_string() })
+                }
+            }
+
+            pub fn as_str(&self) -> &str {
+                &self.0
+            }
+
+            pub fn into_string(self) -> String {
+                self.0
+            }
+        }
+
+        impl FromStr for $name {
+            type Err = InvalidId;
+
+            fn from_str(value: &str) -> Result<Self, InvalidId> {
+                Self::parse(value)
+            }
+        }
+
+        impl TryFrom<String> for $name {
+            type Error = InvalidId;
+
+            fn try_from(value: String) -> Result<Self, InvalidId> {
+                if $check(&value) {
+                    Ok(Self(value))
+                } else {
+                    Err(InvalidId { kind: $label, value })
+                }
+            }
+        }
+
+        impl From<$name> for String {
+            fn from(id: $name) -> String {
+                id.0
+            }
+        }
+
+        impl fmt::Display for $name {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                f.write_str(&self.0)
+            }
+        }
+
+        impl Deref for $name {
+            type Target = str;
+
+            fn deref(&self) -> &str {
+                &self.0
+            }
+        }
+
+        impl PartialEq<str> for $name {
+            fn eq(&self, other: &str) -> bool {
+                self.0 == other
+            }
+        }
+
+        impl PartialEq<&str> for $name {
+            fn eq(&self, other: &&str) -> bool {
+                self.0 == *other
+            }
+        }
+
+        impl PartialEq<String> for $name {
+            fn eq(&self, other: &String) -> bool {
+                &self.0 == other
+            }
+        }
+
+        impl PartialEq<$name> for str {
+            fn eq(&self, other: &$name) -> bool {
+                self == other.0
+            }
+        }
+
+        impl PartialEq<$name> for &str {
+            fn eq(&self, other: &$name) -> bool {
+                *self == other.0
+            }
+        }
+
+        impl PartialEq<$name> for String {
+            fn eq(&self, other: &$name) -> bool {
+                *self == other.0
+            }
+        }
+
+        impl AsRef<str> for $name {
+            fn as_ref(&self) -> &str {
+                &self.0
+            }
+        }
+
+        impl std::borrow::Borrow<str> for $name {
+            fn borrow(&self) -> &str {
+                &self.0
+            }
+        }
+
+        #[cfg(feature = "sqlite")]
+        impl rusqlite::types::ToSql for $name {
+            fn to_sql(&self) -> rusqlite::Result<rusqlite::types::ToSqlOutput<'_>> {
+                Ok(rusqlite::types::ToSqlOutput::from(self.0.as_str()))
+            }
+        }
+
+        #[cfg(feature = "sqlite")]
+        impl rusqlite::types::FromSql for $name {
+            fn column_result(
+                value: rusqlite::types::ValueRef<'_>,
+            ) -> rusqlite::types::FromSqlResult<Self> {
+                Self::parse(value.as_str()?)
+                    .map_err(|e| rusqlite::types::FromSqlError::Other(Box::new(e)))
+            }
+        }
+    };
+}
+
+id_type!(
+    /// A signed memory's ID
+    MemoryId,
+    "memory ID",
+    is_plain_id
+);
+id_type!(
+    /// A decentralized identifier: `did:method:identifier`
+    Did,
+    "DID",
+    is_did
+);
+id_type!(
+    /// A node's ID on the P2P network
+    PeerId,
+    "peer ID",
+    is_plain_id
+);
+id_type!(
+    /// A claim token's row ID; never the claim code itself
+    TokenId,
+    "token ID",
+    is_plain_id
+);
+
+impl MemoryId {
+    /// A fresh random ID for a new memory
+    pub fn generate() -> Self {
+        Self(uuid::Uuid::new_v4().to_string())
+    }
+}
+
+impl TokenId {
+    /// A fresh random ID for a new claim token
+    pub fn generate() -> Self {
+        Self(uuid::Uuid::new_v4().to_string())
+    }
+}
+
+/// Up to MAX_ID_LEN printable ASCII characters, and not a DID
+fn is_plain_id(value: &str) -> bool {
+    !value.is_empty()
+        && value.len() <= MAX_ID_LEN
+        && value.chars().all(|c| c.is_ascii_graphic())
+        && !value.starts_with("did:")
+}
+
+fn is_did(value: &str) -> bool {
+    if value.len() > MAX_DID_LEN {
+        return false;
+    }
+    match value
+        .strip_prefix("did:")
+        .and_then(|rest| rest.split_once(':'))
+    {
+        Some((method, identifier)) => {
+            !method.is_empty()
+                && method
+                    .chars()
+                    .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit())
+                && !identifier.is_empty()
+                && identifier.chars().all(|c| c.is_ascii_graphic())
+        }
+        None => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_did_parsing() {
+        assert!(Did::parse("did:plc:ewvi7nxzyoun6zhxrhs64oiz").is_ok());
+        assert!(Did::parse("did:web:example.org%3A8443").is_ok());
+        assert!(Did::parse("did:plc:").is_err());
+        assert!(Did::parse("did:PLC:abc").is_err());
+        assert!(Did::parse("plc:abc").is_err());
+        assert!(Did::parse("did:plc:has space").is_err());
+    }
+
+    #[test]
+    fn test_plain_ids_reject_dids() {
+        let memory_id = "0b5bb8b8-3a0a-4d5e-9d7c-5f0c7c1f7d2e";
+        assert_eq!(MemoryId::parse(memory_id).unwrap().as_str(), memory_id);
+        assert!(MemoryId::parse("did:plc:ewvi7nxzyoun6zhxrhs64oiz").is_err());
+        assert!(PeerId::parse("").is_err());
+        assert!(TokenId::parse(&"a".repeat(MAX_ID_LEN + 1)).is_err());
+    }
+
+    #[test]
+    fn test_serde_validates() {
+        let did: Did = serde_json::from_str("\"did:key:z6MkhaXgBZD\"").unwrap();
+        assert_eq!(
+            serde_json::to_string(&did).unwrap(),
+            "\"did:key:z6MkhaXgBZD\""
+        );
+        assert!(serde_json::from_str::<PeerId>("\"did:key:z6MkhaXgBZD\"").is_err());
+    }
+}
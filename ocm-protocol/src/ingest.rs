@@ -0,0 +1,154 @@
+//! Verify-before-store policy shared by every path memories enter through: P2P sync on
+//! nodes, the relay and the browser client. Rejections are counted, and tampered
+//! memories are kept in a bounded quarantine for inspection
+
+use crate::memory::SignedMemory;
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+
+/// Whether received memories must pass verification before they are stored or forwarded
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct IngestPolicy {
+    pub verify_before_store: bool, // Off only for development against unsigned fixtures
+    pub quarantine_capacity: usize, // Rejected memories kept in memory; older ones are dropped
+}
+
+impl Default for IngestPolicy {
+    fn default() -> Self {
+        Self {
+            verify_before_store: true,
+            quarantine_capacity: 500,
+        }
+    }
+}
+
+/// Why a received memory was not stored
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RejectionReason {
+    HashMismatch, // Content does not match its hash
+    BadSignature, // Not signed by its author's key
+    Unverifiable, // The author's key could not be resolved
+}
+
+impl RejectionReason {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            RejectionReason::HashMismatch => "hash_mismatch",
+            RejectionReason::BadSignature => "bad_signature",
+            RejectionReason::Unverifiable => "unverifiable",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "hash_mismatch" => Some(RejectionReason::HashMismatch),
+            "bad_signature" => Some(RejectionReason::BadSignature),
+            "unverifiable" => Some(RejectionReason::Unverifiable),
+            _ => None,
+        }
+    }
+
+    /// Tampered memories are quarantined; unverifiable ones may be fine and are only counted
+    pub fn is_tampered(&self) -> bool {
+        !matches!(self, RejectionReason::Unverifiable)
+    }
+}
+
+/// A rejected memory kept for inspection
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct QuarantinedMemory {
+    pub memory: SignedMemory,
+    pub reason: RejectionReason,
+    pub source: String, // Peer, client or connection it came from
+    pub rejected_at: String,
+}
+
+/// Counts of received memories by outcome
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct IngestStats {
+    pub verified: u64,
+    pub unchecked: u64, // Let through with verification turned off
+    pub hash_mismatch: u64,
+    pub bad_signature: u64,
+    pub unverifiable: u64,
+}
+
+impl IngestStats {
+    pub fn rejected(&self) -> u64 {
+        self.hash_mismatch + self.bad_signature + self.unverifiable
+    }
+
+    pub fn record_rejection(&mut self, reason: RejectionReason) {
+        match reason {
+            RejectionReason::HashMismatch => self.hash_mismatch += 1,
+            RejectionReason::BadSignature => self.bad_signature += 1,
+            RejectionReason::Unverifiable => self.unverifiable += 1,
+        }
+    }
+}
+
+/// Counters and quarantine for one ingestion path, for callers without a database
+#[derive(Debug, Clone, Default)]
+pub struct IngestLedger {
+    policy: IngestPolicy,
+    stats: IngestStats,
+    quarantine: VecDeque<QuarantinedMemory>,
+}
+
+impl IngestLedger {
+    pub fn new(policy: IngestPolicy) -> Self {
+        Self {
+            policy,
+            ..Default::default()
+        }
+    }
+
+    pub fn policy(&self) -> &IngestPolicy {
+        &self.policy
+    }
+
+    pub fn set_policy(&mut self, policy: IngestPolicy) {
+        self.policy = policy;
+        self.trim();
+    }
+
+    pub fn record_verified(&mut self) {
+        self.stats.verified += 1;
+    }
+
+    pub fn record_unchecked(&mut self) {
+        self.stats.unchecked += 1;
+    }
+
+    /// Count a rejection, quarantining the memory if it was tampered with
+    pub fn reject(&mut self, memory: &SignedMemory, reason: RejectionReason, source: &str) {
+        self.stats.record_rejection(reason);
+        if reason.is_tampered() {
+            self.quarantine.push_back(QuarantinedMemory {
+                memory: memory.clone(),
+                reason,
+                source: source.to_string(),
+                rejected_at: chrono::Utc::now().to_rfc3339(),
+            });
+            self.trim();
+        }
+    }
+
+    pub fn stats(&self) -> &IngestStats {
+        &self.stats
+    }
+
+    /// Quarantined memories, oldest first
+    pub fn quarantined(&self) -> Vec<QuarantinedMemory> {
+        self.quarantine.iter().cloned().collect()
+    }
+
+    fn trim(&mut self) {
+        while self.quarantine.len() > self.policy.quarantine_capacity {
+            self.quarantine.pop_front();
+        }
+    }
+}
@@ -0,0 +1,24 @@
+//! Wire and domain types shared by ocm-core, ocm-wasm and the relay server.
+//! Nothing here touches the network, storage or an async runtime, so the crate
+//! builds unchanged for native, WASM and embedded consumers.
+
+pub mod crdt;
+pub mod did;
+pub mod feed;
+pub mod filter;
+pub mod fragment;
+pub mod handle;
+pub mod ids;
+pub mod ingest;
+pub mod memory;
+pub mod message;
+pub mod patch;
+pub mod presence;
+pub mod redact;
+pub mod relay;
+pub mod safety;
+pub mod seeds;
+pub mod sync;
+pub mod verification;
+
+pub use memory::*;
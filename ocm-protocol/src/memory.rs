@@ -0,0 +1,157 @@
+use crate::ids::{Did, MemoryId};
+use crate::redact::Redacted;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::fmt;
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct SignedMemory {
+    pub id: MemoryId,
+    pub did: Did,             // DID:PLC identifier of the author
+    pub memory_type: String,  // Type of memory (individual, location, etc.)
+    pub memory_data: String,  // JSON serialized memory content
+    pub content_hash: String, // SHA256 hash of memory_data
+    pub signature: String,    // Cryptographic signature
+    pub timestamp: String,    // ISO 8601 timestamp
+    pub updated_on: String,
+    #[serde(default)]
+    pub co_signatures: Vec<CoSignature>, // Additional signers, in signing order
+}
+
+impl fmt::Debug for SignedMemory {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SignedMemory")
+            .field("id", &self.id)
+            .field("did", &Redacted::did(&self.did))
+            .field("memory_type", &self.memory_type)
+            .field("memory_data", &Redacted::pii(&self.memory_data))
+            .field("content_hash", &self.content_hash)
+            .field("signature", &Redacted::secret(&self.signature))
+            .field("timestamp", &self.timestamp)
+            .field("updated_on", &self.updated_on)
+            .field("co_signatures", &self.co_signatures.len())
+            .finish()
+    }
+}
+
+/// A signature over a memory's signing payload by a DID other than the author
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct CoSignature {
+    pub signer_did: Did,
+    pub signature: String, // Base64 encoded Ed25519 signature
+    pub signed_at: String, // ISO 8601 timestamp
+}
+
+/// Memory type given to memories whose content was scrubbed by an erasure request
+pub const TOMBSTONE_MEMORY_TYPE: &str = "tombstone";
+
+/// Prefix of memory types computed from other memories. A derived memory is signed by the
+/// node that computed it, which vouches for the computation, not for the underlying facts
+pub const DERIVED_MEMORY_TYPE_PREFIX: &str = "derived:";
+
+/// memory_data of a derived memory: the computed value and the source versions behind it
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct DerivedMemoryData {
+    pub derivation: String,
+    pub key: String, // What the value is about, e.g. an individual ID
+    pub sources: Vec<DerivedSource>,
+    pub value: serde_json::Value,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct DerivedSource {
+    pub memory_id: MemoryId,
+    pub content_hash: String,
+}
+
+impl SignedMemory {
+    pub fn new(did: &Did, memory_type: &str, memory_data: &str) -> Self {
+        let content_hash = Self::compute_hash(memory_data);
+        let timestamp = chrono::Utc::now().to_rfc3339();
+        let updated_on = timestamp.clone();
+
+        SignedMemory {
+            id: MemoryId::generate(),
+            did: did.clone(),
+            memory_type: memory_type.to_string(),
+            memory_data: memory_data.to_string(),
+            content_hash,
+            signature: String::new(), // Will be set during signing
+            timestamp,
+            updated_on,
+            co_signatures: Vec::new(),
+        }
+    }
+
+    pub fn compute_hash(data: &str) -> String {
+        let mut hasher = Sha256::new();
+        hasher.update(data.as_bytes());
+        hex::encode(hasher.finalize())
+    }
+
+    pub fn get_signing_payload(&self) -> String {
+        // Create deterministic payload for signing
+        serde_json::json!({
+            "did": self.did,
+            "memory_type": self.memory_type,
+            "content_hash": self.content_hash,
+            "timestamp": self.timestamp
+        })
+        .to_string()
+    }
+
+    pub fn verify_hash(&self) -> bool {
+        let computed_hash = Self::compute_hash(&self.memory_data);
+        computed_hash == self.content_hash
+    }
+
+    pub fn is_co_signed_by(&self, did: &str) -> bool {
+        self.co_signatures.iter().any(|s| s.signer_did == did)
+    }
+
+    pub fn is_tombstone(&self) -> bool {
+        self.memory_type == TOMBSTONE_MEMORY_TYPE
+    }
+
+    /// Computed from other memories rather than attested by its author
+    pub fn is_derived(&self) -> bool {
+        self.memory_type.starts_with(DERIVED_MEMORY_TYPE_PREFIX)
+    }
+
+    /// DIDs that have signed this memory: the author first, then co-signers in order
+    pub fn signer_dids(&self) -> Vec<Did> {
+        let mut dids = vec![self.did.clone()];
+        dids.extend(self.co_signatures.iter().map(|s| s.signer_did.clone()));
+        dids
+    }
+}
+
+/// A memory held without its body, e.g. outside a constrained device's hot set.
+/// The body can be fetched from a peer on demand and checked against content_hash
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MemoryHeader {
+    pub id: MemoryId,
+    pub did: Did,
+    pub memory_type: String,
+    pub content_hash: String,
+    pub signature: String,
+    pub timestamp: String,
+    pub updated_on: String,
+    #[serde(default)]
+    pub co_signatures: Vec<CoSignature>,
+}
+
+impl From<&SignedMemory> for MemoryHeader {
+    fn from(memory: &SignedMemory) -> Self {
+        MemoryHeader {
+            id: memory.id.clone(),
+            did: memory.did.clone(),
+            memory_type: memory.memory_type.clone(),
+            content_hash: memory.content_hash.clone(),
+            signature: memory.signature.clone(),
+            timestamp: memory.timestamp.clone(),
+            updated_on: memory.updated_on.clone(),
+            co_signatures: memory.co_signatures.clone(),
+        }
+    }
+}
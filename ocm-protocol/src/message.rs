@@ -0,0 +1,191 @@
+use crate::fragment::FrameLimits;
+use crate::ids::MemoryId;
+use serde::{Deserialize, Serialize};
+
+/// Version of the peer-to-peer TCP protocol, raised when its messages change incompatibly
+pub const PEER_PROTOCOL_VERSION: u32 = 1;
+
+pub const MEMORY_SYNC: &str = "memory-sync";
+pub const PEER_DISCOVERY: &str = "peer-discovery";
+pub const IDENTITY_VERIFICATION: &str = "identity-verification"; // Includes tree head gossip and consistency proofs
+/// Memories may be sent as headers, with bodies fetched on demand
+pub const MEMORY_HEADERS: &str = "memory-headers";
+pub const NOTARIZATION: &str = "notarization";
+pub const PRESENCE: &str = "presence";
+
+/// The node reads responses on the connection its request went out on, ahead of the
+/// acknowledgment. Peers without it are answered on a new connection to their listener
+pub const INLINE_REPLIES: &str = "inline-replies";
+
+/// The node reads zstd-compressed messages
+pub const COMPRESSION: &str = "zstd-compression";
+
+/// The node passes memories from peers it is connected to on to its other peers.
+/// Opt-in, so not part of PEER_CAPABILITIES
+pub const RELAY: &str = "relay";
+
+/// Features of every node from before capabilities were advertised. Peers that
+/// advertise nothing are assumed to have these and no others
+pub const BASELINE_CAPABILITIES: &[&str] = &[
+    MEMORY_SYNC,
+    PEER_DISCOVERY,
+    IDENTITY_VERIFICATION,
+    MEMORY_HEADERS,
+    NOTARIZATION,
+    PRESENCE,
+];
+
+/// Features this version of the peer protocol offers, advertised in handshakes and beacons
+pub const PEER_CAPABILITIES: &[&str] = &[
+    MEMORY_SYNC,
+    PEER_DISCOVERY,
+    IDENTITY_VERIFICATION,
+    MEMORY_HEADERS,
+    NOTARIZATION,
+    PRESENCE,
+    INLINE_REPLIES,
+    COMPRESSION,
+];
+
+/// What kind of node a peer is, announced in its handshake
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum NodeRole {
+    #[default]
+    Full, // Keeps what its sync policy asks for in full
+    Light,   // Phones and browsers: headers, with bodies fetched on demand
+    Archive, // Keeps every memory in full and never prunes; preferred for fetching bodies
+}
+
+impl NodeRole {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            NodeRole::Full => "full",
+            NodeRole::Light => "light",
+            NodeRole::Archive => "archive",
+        }
+    }
+}
+
+/// Envelope for every message on the peer-to-peer TCP protocol
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NetworkMessage {
+    pub message_type: MessageType,
+    pub payload: String,
+    pub from_peer: String,
+    pub timestamp: String,
+    pub nonce: String, // Unique nonce for replay protection
+    pub hmac: String,  // HMAC for message authentication
+    #[serde(default)]
+    pub request_id: String, // Correlation ID shared by a request and its replies; not authenticated
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum MessageType {
+    Handshake,
+    MemorySync,
+    MemoryRequest,
+    PeerDiscovery,
+    Ping,
+    Pong,
+    NotarizationRequest,
+    NotarizationReceipt,
+    TreeHead,
+    ConsistencyProofRequest,
+    ConsistencyProof,
+    MemoryBodyRequest,
+    Presence,
+    Fragment,   // A piece of a message larger than one frame; see ocm_protocol::fragment
+    Compressed, // Base64 of a zstd-compressed NetworkMessage
+}
+
+/// What a node says about itself when it opens a connection. The DID is taken only
+/// when `signature`, by that DID's key, covers the rest of the payload
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HandshakePayload {
+    pub peer_id: String,
+    #[serde(alias = "port")]
+    pub listen_port: u16, // Where the node accepts connections; 0 if it does not
+    #[serde(default)]
+    pub did: Option<String>,
+    #[serde(default)]
+    pub capabilities: Vec<String>,
+    #[serde(default)]
+    pub protocol_version: u32, // 0 for nodes from before the payload was typed
+    #[serde(default)]
+    pub frame_limits: Option<FrameLimits>, // None for nodes that cannot reassemble fragments
+    #[serde(default)]
+    pub role: NodeRole, // Full for nodes from before roles
+    #[serde(default)]
+    pub signature: Option<String>,
+}
+
+impl HandshakePayload {
+    pub fn get_signing_payload(&self) -> String {
+        let mut payload = serde_json::json!({
+            "peer_id": self.peer_id,
+            "listen_port": self.listen_port,
+            "did": self.did,
+            "capabilities": self.capabilities,
+            "protocol_version": self.protocol_version,
+        });
+        // Only when present, so handshakes signed by older nodes still verify
+        if let Some(limits) = &self.frame_limits {
+            payload["frame_limits"] = serde_json::json!(limits);
+        }
+        if self.role != NodeRole::Full {
+            payload["role"] = serde_json::json!(self.role);
+        }
+        payload.to_string()
+    }
+}
+
+/// Ask a peer to witness that a memory's content hash existed at this point in time
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NotarizationRequest {
+    pub memory_id: MemoryId,
+    pub content_hash: String,
+}
+
+/// Ask the peer that gossiped a larger tree head to prove its log at `second_size`
+/// extends the one it signed at `first_size`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ConsistencyProofRequest {
+    pub first_size: u64,
+    pub second_size: u64,
+}
+
+/// Ask a peer for the full body of a memory held locally only as a header
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MemoryBodyRequest {
+    pub memory_id: MemoryId,
+    pub content_hash: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PeerInfo {
+    pub peer_id: String,
+    pub address: String,
+    pub port: u16,
+    pub last_seen: chrono::DateTime<chrono::Utc>,
+    pub did: Option<String>,
+    #[serde(default)]
+    pub capabilities: Vec<String>,
+    #[serde(default)]
+    pub protocol_version: u32, // From the peer's handshake; 0 if not yet known
+    #[serde(default)]
+    pub frame_limits: Option<FrameLimits>,
+    #[serde(default)]
+    pub role: NodeRole, // From the peer's handshake; Full if not yet known
+}
+
+impl PeerInfo {
+    /// Whether the peer advertised `capability`, or has it from before advertising
+    pub fn supports(&self, capability: &str) -> bool {
+        if self.capabilities.is_empty() {
+            BASELINE_CAPABILITIES.contains(&capability)
+        } else {
+            self.capabilities.iter().any(|c| c == capability)
+        }
+    }
+}
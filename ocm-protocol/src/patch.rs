@@ -0,0 +1,314 @@
+//! Field-level diffs between memories and JSON Patch (RFC 6902) documents turned into
+//! CRDT operations. Paths in diffs and operations use the CRDT's dot notation
+//! (`address.city`); arrays are compared and replaced as whole values
+
+use crate::crdt::{MemoryOperation, OperationType, VectorClock};
+use crate::ids::MemoryId;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ChangeKind {
+    Added,
+    Removed,
+    Changed,
+}
+
+/// One field that differs between two versions
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FieldChange {
+    pub path: String,
+    pub kind: ChangeKind,
+    pub old: Option<Value>, // None when added
+    pub new: Option<Value>, // None when removed
+}
+
+/// What changed from one memory to another, field by field
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MemoryDiff {
+    pub from_id: MemoryId,
+    pub to_id: MemoryId,
+    pub memory_type: String,
+    pub changes: Vec<FieldChange>,
+}
+
+/// Changes turning `old` into `new`, in path order. Objects are compared member by
+/// member; anything else differs as a whole
+pub fn diff_values(old: &Value, new: &Value) -> Vec<FieldChange> {
+    let mut changes = Vec::new();
+    diff_into(&mut changes, "", old, new);
+    changes
+}
+
+fn diff_into(changes: &mut Vec<FieldChange>, path: &str, old: &Value, new: &Value) {
+    match (old, new) {
+        (Value::Object(old), Value::Object(new)) => {
+            let mut keys: Vec<&String> = old.keys().chain(new.keys()).collect();
+            keys.sort();
+            keys.dedup();
+            for key in keys {
+                let child = if path.is_empty() {
+                    key.clone()
+                } else {
+                    format!("{}.{}", path, key)
+                };
+                match (old.get(key), new.get(key)) {
+                    (Some(o), Some(n)) => diff_into(changes, &child, o, n),
+                    (Some(o), None) => changes.push(FieldChange {
+                        path: child,
+                        kind: ChangeKind::Removed,
+                        old: Some(o.clone()),
+                        new: None,
+                    }),
+                    (None, Some(n)) => changes.push(FieldChange {
+                        path: child,
+                        kind: ChangeKind::Added,
+                        old: None,
+                        new: Some(n.clone()),
+                    }),
+                    (None, None) => {}
+                }
+            }
+        }
+        (old, new) if old != new => changes.push(FieldChange {
+            path: path.to_string(),
+            kind: ChangeKind::Changed,
+            old: Some(old.clone()),
+            new: Some(new.clone()),
+        }),
+        _ => {}
+    }
+}
+
+/// One operation of a JSON Patch document
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "op", rename_all = "lowercase")]
+pub enum PatchOperation {
+    Add { path: String, value: Value },
+    Remove { path: String },
+    Replace { path: String, value: Value },
+    Move { from: String, path: String },
+    Copy { from: String, path: String },
+    Test { path: String, value: Value },
+}
+
+/// CRDT operations with the effect of `patch` on `data`, or why the patch does not
+/// apply. The patch is applied in full or not at all: a failed `test` rejects it.
+/// Members of objects become Set and Delete operations; appending with `/-` becomes
+/// Append; any other change inside an array sets the whole array
+pub fn patch_to_operations(
+    data: &Value,
+    patch: &[PatchOperation],
+    vector_clock: &VectorClock,
+    timestamp: &str,
+) -> Result<Vec<MemoryOperation>, String> {
+    let mut working = data.clone();
+    let mut operations = Vec::new();
+    let mut push = |operation_type: OperationType, field_path: String, value: Value| {
+        operations.push(MemoryOperation {
+            operation_id: uuid::Uuid::new_v4().to_string(),
+            operation_type,
+            field_path,
+            value,
+            vector_clock: vector_clock.clone(),
+            timestamp: timestamp.to_string(),
+            signature: None,
+        });
+    };
+
+    for operation in patch {
+        match operation {
+            PatchOperation::Test { path, value } => {
+                let tokens = parse_pointer(path)?;
+                if lookup(&working, &tokens) != Some(value) {
+                    return Err(format!("Test failed at {}", path));
+                }
+            }
+            PatchOperation::Remove { path } => {
+                let tokens = parse_pointer(path)?;
+                remove(&mut working, &tokens)?;
+                emit(&mut push, &working, &tokens, None)?;
+            }
+            PatchOperation::Add { path, value } | PatchOperation::Replace { path, value } => {
+                let tokens = parse_pointer(path)?;
+                let replace = matches!(operation, PatchOperation::Replace { .. });
+                if replace && lookup(&working, &tokens).is_none() {
+                    return Err(format!("Nothing to replace at {}", path));
+                }
+                let appended = insert(&mut working, &tokens, value.clone(), replace)?;
+                emit(&mut push, &working, &tokens, appended.then_some(value))?;
+            }
+            PatchOperation::Move { from, path } | PatchOperation::Copy { from, path } => {
+                let from_tokens = parse_pointer(from)?;
+                let tokens = parse_pointer(path)?;
+                let value = lookup(&working, &from_tokens)
+                    .cloned()
+                    .ok_or_else(|| format!("Nothing at {}", from))?;
+                if matches!(operation, PatchOperation::Move { .. }) {
+                    if tokens.starts_with(&from_tokens) && tokens != from_tokens {
+                        return Err(format!("Cannot move {} into itself", from));
+                    }
+                    remove(&mut working, &from_tokens)?;
+                    emit(&mut push, &working, &from_tokens, None)?;
+                }
+                let appended = insert(&mut working, &tokens, value.clone(), false)?;
+                emit(&mut push, &working, &tokens, appended.then_some(&value))?;
+            }
+        }
+    }
+    Ok(operations)
+}
+
+/// The operation recording a change at `tokens`, after it was made to `working`
+fn emit(
+    push: &mut impl FnMut(OperationType, String, Value),
+    working: &Value,
+    tokens: &[String],
+    appended: Option<&Value>,
+) -> Result<(), String> {
+    // Changes inside an array replace the array, which the CRDT only addresses whole
+    let mut depth = 0;
+    let mut current = working;
+    while depth < tokens.len() {
+        match current {
+            Value::Object(map) => match map.get(&tokens[depth]) {
+                Some(next) => current = next,
+                None => break,
+            },
+            _ => break,
+        }
+        depth += 1;
+    }
+    let in_array = depth < tokens.len() && matches!(current, Value::Array(_));
+    if in_array {
+        let array_path = field_path(&tokens[..depth])?;
+        match appended {
+            Some(value) if depth == tokens.len() - 1 && tokens[depth] == "-" => {
+                push(OperationType::Append, array_path, value.clone())
+            }
+            _ => push(OperationType::Set, array_path, current.clone()),
+        }
+        return Ok(());
+    }
+
+    let path = field_path(tokens)?;
+    match lookup(working, tokens) {
+        Some(value) => push(OperationType::Set, path, value.clone()),
+        None => push(OperationType::Delete, path, Value::Null),
+    }
+    Ok(())
+}
+
+/// The CRDT field path of a pointer below the root; its keys cannot contain dots
+fn field_path(tokens: &[String]) -> Result<String, String> {
+    if tokens.is_empty() {
+        return Err("Patches cannot replace the whole memory".to_string());
+    }
+    if let Some(key) = tokens.iter().find(|t| t.contains('.') || t.is_empty()) {
+        return Err(format!("Field name '{}' cannot be patched", key));
+    }
+    Ok(tokens.join("."))
+}
+
+/// The reference tokens of a JSON Pointer (RFC 6901)
+fn parse_pointer(pointer: &str) -> Result<Vec<String>, String> {
+    if pointer.is_empty() {
+        return Ok(Vec::new());
+    }
+    let Some(rest) = pointer.strip_prefix('/') else {
+        return Err(format!("Not a JSON Pointer: {}", pointer));
+    };
+    Ok(rest
+        .split('/')
+        .map(|token| token.replace("~1", "/").replace("~0", "~"))
+        .collect())
+}
+
+fn lookup<'a>(value: &'a Value, tokens: &[String]) -> Option<&'a Value> {
+    tokens
+        .iter()
+        .try_fold(value, |current, token| match current {
+            Value::Object(map) => map.get(token),
+            Value::Array(items) => token.parse::<usize>().ok().and_then(|i| items.get(i)),
+            _ => None,
+        })
+}
+
+fn parent_mut<'a>(value: &'a mut Value, tokens: &[String]) -> Result<&'a mut Value, String> {
+    let mut current = value;
+    for token in tokens {
+        current = match current {
+            Value::Object(map) => map.get_mut(token),
+            Value::Array(items) => token.parse::<usize>().ok().and_then(|i| items.get_mut(i)),
+            _ => None,
+        }
+        .ok_or_else(|| format!("Nothing at /{}", tokens.join("/")))?;
+    }
+    Ok(current)
+}
+
+/// Insert at `tokens`, returning whether it appended to an array
+fn insert(value: &mut Value, tokens: &[String], new: Value, replace: bool) -> Result<bool, String> {
+    let Some((last, parent)) = tokens.split_last() else {
+        return Err("Patches cannot replace the whole memory".to_string());
+    };
+    match parent_mut(value, parent)? {
+        Value::Object(map) => {
+            map.insert(last.clone(), new);
+            Ok(false)
+        }
+        Value::Array(items) if last == "-" && !replace => {
+            items.push(new);
+            Ok(true)
+        }
+        Value::Array(items) => {
+            let index = last
+                .parse::<usize>()
+                .ok()
+                .filter(|i| {
+                    if replace {
+                        *i < items.len()
+                    } else {
+                        *i <= items.len()
+                    }
+                })
+                .ok_or_else(|| format!("No array index {} at /{}", last, parent.join("/")))?;
+            if replace {
+                items[index] = new;
+            } else {
+                items.insert(index, new);
+            }
+            Ok(false)
+        }
+        _ => Err(format!("/{} is not an object or array", parent.join("/"))),
+    }
+}
+
+fn remove(value: &mut Value, tokens: &[String]) -> Result<(), String> {
+    let Some((last, parent)) = tokens.split_last() else {
+        return Err("Patches cannot remove the whole memory".to_string());
+    };
+    let removed = match parent_mut(value, parent)? {
+        Value::Object(map) => map.remove(last).is_some(),
+        Value::Array(items) => match last.parse::<usize>() {
+            Ok(index) if index < items.len() => {
+                items.remove(index);
+                true
+            }
+            _ => false,
+        },
+        _ => false,
+    };
+    if removed {
+        Ok(())
+    } else {
+        Err(format!("Nothing to remove at /{}", tokens.join("/")))
+    }
+}
+
+/// A memory's data as JSON; data that is not JSON is one string, which diffs as a
+/// change at the empty path
+pub fn memory_data_value(memory_data: &str) -> Value {
+    serde_json::from_str(memory_data).unwrap_or_else(|_| Value::String(memory_data.to_string()))
+}
@@ -0,0 +1,40 @@
+use serde::{Deserialize, Serialize};
+
+/// What a device is doing, when it chooses to say
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PresenceStatus {
+    Syncing,
+    Idle,
+}
+
+impl PresenceStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            PresenceStatus::Syncing => "syncing",
+            PresenceStatus::Idle => "idle",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "syncing" => Some(PresenceStatus::Syncing),
+            "idle" => Some(PresenceStatus::Idle),
+            _ => None,
+        }
+    }
+}
+
+/// Lightweight presence announcement. Receiving one means the sender is online;
+/// the optional fields are left out when the sender does not share them
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PresenceUpdate {
+    pub device_id: String, // Node ID, or a browser client's own ID
+    #[serde(default)]
+    pub did: Option<String>,
+    #[serde(default)]
+    pub status: Option<PresenceStatus>,
+    #[serde(default)]
+    pub last_sync_at: Option<String>,
+    pub sent_at: String,
+}
@@ -0,0 +1,90 @@
+use std::fmt;
+
+/// Structured log fields that are always masked by the logging layer
+pub const SENSITIVE_FIELDS: &[&str] = &[
+    "did",
+    "token",
+    "claim_token",
+    "email",
+    "phone",
+    "dob",
+    "address",
+    "memory_data",
+    "signature",
+    "private_key",
+    "api_key",
+    "session_id",
+];
+
+pub fn is_sensitive_field(name: &str) -> bool {
+    SENSITIVE_FIELDS.contains(&name)
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum RedactionKind {
+    Did,
+    Secret,
+    Pii,
+}
+
+/// Display/Debug wrapper that masks a sensitive value wherever it is formatted
+#[derive(Clone, Copy)]
+pub struct Redacted<'a> {
+    value: &'a str,
+    kind: RedactionKind,
+}
+
+impl<'a> Redacted<'a> {
+    /// Keep the DID method and a short prefix so log lines stay correlatable
+    pub fn did(value: &'a str) -> Self {
+        Self {
+            value,
+            kind: RedactionKind::Did,
+        }
+    }
+
+    /// Tokens, signatures and keys: nothing of the value is shown
+    pub fn secret(value: &'a str) -> Self {
+        Self {
+            value,
+            kind: RedactionKind::Secret,
+        }
+    }
+
+    /// Personal data such as names, contact details and memory content
+    pub fn pii(value: &'a str) -> Self {
+        Self {
+            value,
+            kind: RedactionKind::Pii,
+        }
+    }
+}
+
+impl fmt::Display for Redacted<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.kind {
+            RedactionKind::Did => {
+                // "did:plc:" plus the first 6 characters of the identifier
+                let prefix_len = self
+                    .value
+                    .rfind(':')
+                    .map(|i| i + 1 + 6)
+                    .unwrap_or(6)
+                    .min(self.value.len());
+                match self.value.get(..prefix_len) {
+                    Some(prefix) if prefix_len < self.value.len() => write!(f, "{}…", prefix),
+                    Some(prefix) => write!(f, "{}", prefix),
+                    None => write!(f, "[REDACTED]"),
+                }
+            }
+            RedactionKind::Secret => write!(f, "[REDACTED]"),
+            RedactionKind::Pii => write!(f, "[REDACTED {} bytes]", self.value.len()),
+        }
+    }
+}
+
+impl fmt::Debug for Redacted<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(self, f)
+    }
+}
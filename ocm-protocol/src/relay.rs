@@ -0,0 +1,122 @@
+use crate::ids::MemoryId;
+use crate::ingest::{IngestStats, QuarantinedMemory, RejectionReason};
+use crate::memory::SignedMemory;
+use crate::presence::PresenceUpdate;
+use serde::{Deserialize, Serialize};
+
+/// Bumped on incompatible changes to the relay message schema
+pub const RELAY_PROTOCOL_VERSION: u32 = 1;
+
+/// WebSocket relay protocol shared by the relay server, the WASM client and native clients.
+/// Each message is a JSON object tagged by its `type` field
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum RelayMessage {
+    /// Sent by the relay when a client connects
+    Welcome {
+        client_id: String,
+        message: String,
+        #[serde(default = "legacy_protocol_version")]
+        protocol_version: u32,
+    },
+    /// A memory to hand to every other connected client
+    MemorySync {
+        data: SignedMemory,
+    },
+    /// Sent back to a client whose memory failed verification and was not relayed
+    MemoryRejected {
+        memory_id: MemoryId,
+        reason: RejectionReason,
+    },
+    /// Sent empty by a client to ask for memories the relay rejected as tampered with
+    Quarantine {
+        #[serde(default)]
+        memories: Vec<QuarantinedMemory>,
+    },
+    Ping,
+    Pong {
+        timestamp: String,
+    },
+    /// The relay discarded messages for this client because it fell behind
+    MessagesDropped {
+        count: u64,
+        timestamp: String,
+    },
+    /// Sent empty by a client to ask for relay counters; the relay answers with them filled in
+    Metrics(RelayMetricsReport),
+    /// Advertise where this node can be reached, so peers behind other NATs can find it.
+    /// The relay answers with `DirectoryEntries` holding the stored entry
+    DirectoryRegister {
+        did: String,
+        node_id: String,
+        endpoints: Vec<String>, // host:port of the node's P2P listener
+        #[serde(default)]
+        ttl_secs: Option<u64>, // None takes the relay's default; re-register before it lapses
+    },
+    /// Look up registered nodes by DID; an empty list asks for any registered nodes
+    DirectoryQuery {
+        #[serde(default)]
+        dids: Vec<String>,
+    },
+    DirectoryEntries {
+        entries: Vec<DirectoryEntry>,
+    },
+    /// A registration or query the relay would not serve
+    DirectoryRejected {
+        reason: String,
+    },
+    /// A device announcing it is online, forwarded to the other clients like a memory
+    Presence(PresenceUpdate),
+    /// Any message type this version does not know; relays forward it unchanged
+    #[serde(other)]
+    Unknown,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RelayMetricsReport {
+    pub connected_clients: usize,
+    pub messages_relayed: u64,
+    pub messages_dropped: u64,
+    pub slow_client_disconnects: u64,
+    pub client_dropped: u64, // Messages dropped for the requesting client
+    pub ingest: IngestStats, // Memories verified or rejected before relaying
+}
+
+/// A node registered in the relay's rendezvous directory. Entries are self-reported
+/// hints: the P2P handshake, not the relay, establishes who a peer is
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DirectoryEntry {
+    pub did: String,
+    pub node_id: String,
+    pub endpoints: Vec<String>,
+    pub observed_addr: Option<String>, // Address the relay saw the registration come from
+    pub registered_at: String,
+    pub expires_at: String,
+}
+
+// Relays from before versioning sent no protocol_version
+fn legacy_protocol_version() -> u32 {
+    0
+}
+
+impl RelayMessage {
+    pub fn welcome(client_id: &str) -> Self {
+        RelayMessage::Welcome {
+            client_id: client_id.to_string(),
+            message: "Connected to OCM relay server".to_string(),
+            protocol_version: RELAY_PROTOCOL_VERSION,
+        }
+    }
+
+    pub fn pong() -> Self {
+        RelayMessage::Pong {
+            timestamp: chrono::Utc::now().to_rfc3339(),
+        }
+    }
+
+    pub fn to_json(&self) -> String {
+        // Every variant is plain data, so serialization cannot fail
+        serde_json::to_string(self).unwrap_or_default()
+    }
+}
@@ -0,0 +1,53 @@
+use sha2::{Digest, Sha512};
+
+/// Hash rounds per party, making a matching prefix expensive to search for
+const ITERATIONS: usize = 5200;
+const VERSION: &[u8] = b"ocm-safety-number-v1";
+const CHUNKS_PER_PARTY: usize = 6;
+
+/// Thirty digits identifying one party's key, in six groups of five
+fn party_digits(did: &str, public_key: &[u8; 32]) -> Vec<String> {
+    let mut hash = Sha512::new()
+        .chain_update(VERSION)
+        .chain_update(public_key)
+        .chain_update(did.as_bytes())
+        .finalize();
+    for _ in 0..ITERATIONS {
+        hash = Sha512::new()
+            .chain_update(hash)
+            .chain_update(public_key)
+            .finalize();
+    }
+
+    hash.chunks(5)
+        .take(CHUNKS_PER_PARTY)
+        .map(|chunk| {
+            let value = chunk
+                .iter()
+                .fold(0u64, |acc, byte| (acc << 8) | *byte as u64);
+            format!("{:05}", value % 100_000)
+        })
+        .collect()
+}
+
+/// Signal-style safety number for two DIDs and their Ed25519 keys: sixty digits in
+/// groups of five. Both parties compute the same number, so reading it aloud or
+/// comparing it in person shows neither key was swapped in transit
+pub fn safety_number(did_a: &str, key_a: &[u8; 32], did_b: &str, key_b: &[u8; 32]) -> String {
+    let a = party_digits(did_a, key_a);
+    let b = party_digits(did_b, key_b);
+    let (first, second) = if did_a <= did_b { (a, b) } else { (b, a) };
+    first
+        .into_iter()
+        .chain(second)
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Compare a safety number as typed or scanned, ignoring spacing and separators
+pub fn safety_numbers_match(expected: &str, entered: &str) -> bool {
+    let digits =
+        |number: &str| -> String { number.chars().filter(|c| c.is_ascii_digit()).collect() };
+    let entered = digits(entered);
+    !entered.is_empty() && digits(expected) == entered
+}
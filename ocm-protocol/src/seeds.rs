@@ -0,0 +1,83 @@
+use serde::{Deserialize, Serialize};
+
+/// DNS name prefix of the TXT records listing a federation's seed nodes:
+/// `_ocm-seeds.camps.example.org`
+pub const SEED_TXT_PREFIX: &str = "_ocm-seeds";
+/// DNS name prefix of the SRV records for the same seeds: `_ocm._tcp.camps.example.org`
+pub const SEED_SRV_PREFIX: &str = "_ocm._tcp";
+
+const ADDR_KEY: &str = "addr=";
+const DID_KEY: &str = "did=";
+
+/// A node to bootstrap from, as published in DNS
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct SeedRecord {
+    pub host: String,
+    pub port: u16,           // The node's P2P port
+    pub did: Option<String>, // When set, the seed must prove this DID in its handshake
+}
+
+/// DNS name holding the seed TXT records for a federation domain
+pub fn seed_txt_name(domain: &str) -> String {
+    format!("{}.{}", SEED_TXT_PREFIX, domain.trim_end_matches('.'))
+}
+
+/// DNS name holding the seed SRV records for a federation domain
+pub fn seed_srv_name(domain: &str) -> String {
+    format!("{}.{}", SEED_SRV_PREFIX, domain.trim_end_matches('.'))
+}
+
+/// The seed named by a TXT record value: `addr=seed1.example.org:8080 did=did:plc:...`.
+/// The DID is optional. Values split into several quoted strings are joined first
+pub fn parse_seed_txt(value: &str) -> Option<SeedRecord> {
+    let value = value.trim();
+    let value = if value.contains('"') {
+        value.split('"').skip(1).step_by(2).collect::<String>()
+    } else {
+        value.to_string()
+    };
+
+    let mut endpoint = None;
+    let mut did = None;
+    for field in value.split_whitespace() {
+        if let Some(addr) = field.strip_prefix(ADDR_KEY) {
+            endpoint = parse_endpoint(addr);
+        } else if let Some(value) = field.strip_prefix(DID_KEY) {
+            did = Some(value.to_string()).filter(|did| did.starts_with("did:"));
+        }
+    }
+    let (host, port) = endpoint?;
+    Some(SeedRecord { host, port, did })
+}
+
+/// The seed named by an SRV record as DNS JSON APIs give it: `10 5 8080 seed1.example.org.`,
+/// with its priority. A target of `.` means the domain offers no seeds
+pub fn parse_seed_srv(data: &str) -> Option<(u16, SeedRecord)> {
+    let mut fields = data.split_whitespace();
+    let priority = fields.next()?.parse::<u16>().ok()?;
+    let _weight = fields.next()?.parse::<u16>().ok()?;
+    let port = fields.next()?.parse::<u16>().ok()?;
+    let host = fields.next()?.trim_end_matches('.');
+    if host.is_empty() || port == 0 {
+        return None;
+    }
+    Some((
+        priority,
+        SeedRecord {
+            host: host.to_string(),
+            port,
+            did: None,
+        },
+    ))
+}
+
+/// `host:port`, with IPv6 hosts in brackets
+fn parse_endpoint(endpoint: &str) -> Option<(String, u16)> {
+    let (host, port) = endpoint.rsplit_once(':')?;
+    let host = host.trim_start_matches('[').trim_end_matches(']');
+    let port = port.parse::<u16>().ok().filter(|port| *port != 0)?;
+    if host.is_empty() {
+        return None;
+    }
+    Some((host.to_string(), port))
+}
@@ -0,0 +1,74 @@
+use crate::memory::{MemoryHeader, SignedMemory};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SyncRequest {
+    pub requesting_peer: String,
+    pub last_sync_timestamp: Option<String>,
+    pub known_memory_hashes: Vec<String>,
+    #[serde(default)]
+    pub policy: Option<PartialSyncPolicy>, // None asks for full content of everything
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SyncResponse {
+    pub responding_peer: String,
+    pub memories: Vec<SignedMemory>,
+    pub missing_hashes: Vec<String>,
+    #[serde(default)]
+    pub headers: Vec<MemoryHeader>, // Memories outside the requester's hot set, without bodies
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "lowercase")]
+pub enum SyncPriority {
+    Critical,   // Always synced first with full content
+    Normal,     // Full content inside the hot set, header only outside it
+    Background, // Header only; bodies are fetched on demand
+}
+
+/// What a partially syncing peer wants in full, sent with its sync request
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PartialSyncPolicy {
+    pub hot_set_since: Option<String>, // Normal priority memories older than this arrive as headers
+    pub default_priority: SyncPriority,
+    pub priorities: HashMap<String, SyncPriority>, // memory_type -> priority
+}
+
+impl PartialSyncPolicy {
+    pub fn priority_for(&self, memory_type: &str) -> SyncPriority {
+        self.priorities
+            .get(memory_type)
+            .copied()
+            .unwrap_or(self.default_priority)
+    }
+
+    /// Whether the memory should be sent with its body rather than as a header
+    pub fn wants_full_content(&self, memory: &SignedMemory) -> bool {
+        match self.priority_for(&memory.memory_type) {
+            SyncPriority::Critical => true,
+            SyncPriority::Background => false,
+            SyncPriority::Normal => match &self.hot_set_since {
+                Some(since) => {
+                    match (
+                        chrono::DateTime::parse_from_rfc3339(&memory.timestamp),
+                        chrono::DateTime::parse_from_rfc3339(since),
+                    ) {
+                        (Ok(timestamp), Ok(since)) => timestamp >= since,
+                        _ => true, // Unparseable timestamps err on the side of full content
+                    }
+                }
+                None => true,
+            },
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MemoryVector {
+    pub peer_id: String,
+    pub memory_hash: String,
+    pub timestamp: String,
+    pub version: u64,
+}
@@ -0,0 +1,69 @@
+use crate::ids::{Did, MemoryId};
+use serde::{Deserialize, Serialize};
+
+/// How an organization proved who it is
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OrganizationVerificationMethod {
+    Domain,      // Holds a domain handle proven by DNS or /.well-known/ocm-did
+    Attestation, // Vouched for by an organization that is itself verified
+}
+
+impl OrganizationVerificationMethod {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            OrganizationVerificationMethod::Domain => "domain",
+            OrganizationVerificationMethod::Attestation => "attestation",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "domain" => Some(OrganizationVerificationMethod::Domain),
+            "attestation" => Some(OrganizationVerificationMethod::Attestation),
+            _ => None,
+        }
+    }
+}
+
+/// Why an organization's DID can be trusted to be the organization it claims to be
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct OrganizationVerification {
+    pub organization_did: Did,
+    pub method: OrganizationVerificationMethod,
+    pub domain: Option<String>,                  // Domain methods only
+    pub attested_by: Option<Did>,                // DID of the vouching organization
+    pub attestation_memory_id: Option<MemoryId>, // Its signed attestation
+    pub verified_at: String,
+}
+
+impl OrganizationVerification {
+    /// Short text for a verified badge: `camp.example.org`, or `Vouched for by did:plc:...`
+    pub fn badge_label(&self) -> String {
+        match (&self.domain, &self.attested_by) {
+            (Some(domain), _) => domain.clone(),
+            (None, Some(attested_by)) => format!("Vouched for by {}", attested_by),
+            (None, None) => "Verified".to_string(),
+        }
+    }
+}
+
+/// What a client shows next to an organization: the verification, if there is one
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OrganizationBadge {
+    pub organization_did: Did,
+    pub verified: bool,
+    pub label: Option<String>,
+    pub verification: Option<OrganizationVerification>,
+}
+
+impl OrganizationBadge {
+    pub fn new(organization_did: &Did, verification: Option<OrganizationVerification>) -> Self {
+        Self {
+            organization_did: organization_did.clone(),
+            verified: verification.is_some(),
+            label: verification.as_ref().map(|v| v.badge_label()),
+            verification,
+        }
+    }
+}
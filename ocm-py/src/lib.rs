@@ -0,0 +1,253 @@
+//! Python bindings for analysing a node's memory store.
+//!
+//! Build with `maturin develop` from this directory. Batches are dicts of equal length
+//! column lists, so `pandas.DataFrame(batch)` needs no further conversion:
+//!
+//! ```python
+//! import ocm_py, pandas as pd
+//! store = ocm_py.MemoryStore("data/ocm-impl.db")
+//! df = pd.concat(pd.DataFrame(b) for b in store.batches(memory_type="experience"))
+//! ```
+
+use ocm_core::core::ids::{Did, InvalidId, MemoryId};
+use ocm_core::core::models::{CoSignature, SignedMemory};
+use ocm_core::identity::plc::verify_memory_with_key;
+use ocm_core::persistence::database::Database;
+use ocm_core::OcmError;
+use pyo3::exceptions::{PyIOError, PyRuntimeError, PyValueError};
+use pyo3::prelude::*;
+use pyo3::types::PyDict;
+use std::collections::BTreeMap;
+use std::io::Write;
+
+const DEFAULT_BATCH_SIZE: usize = 10_000;
+
+fn to_py_err(err: OcmError) -> PyErr {
+    PyRuntimeError::new_err(err.to_string())
+}
+
+fn invalid_id(err: InvalidId) -> PyErr {
+    PyValueError::new_err(err.to_string())
+}
+
+/// Read-only handle on an OCM SQLite database; nothing here can modify it
+#[pyclass(module = "ocm_py")]
+struct MemoryStore {
+    database: Database,
+}
+
+#[pymethods]
+impl MemoryStore {
+    #[new]
+    fn new(path: &str) -> PyResult<Self> {
+        Ok(Self {
+            database: Database::open_read_only(path).map_err(to_py_err)?,
+        })
+    }
+
+    /// One memory as a dict, or None
+    fn get(&self, py: Python<'_>, id: &str) -> PyResult<Option<PyObject>> {
+        match self
+            .database
+            .get_signed_memory(&MemoryId::parse(id).map_err(invalid_id)?)
+            .map_err(to_py_err)?
+        {
+            Some(memory) => Ok(Some(memory_to_dict(py, &memory)?.into())),
+            None => Ok(None),
+        }
+    }
+
+    /// Memories matching the filters as a single column batch
+    #[pyo3(signature = (memory_type=None, did=None, since=None))]
+    fn memories(
+        &self,
+        py: Python<'_>,
+        memory_type: Option<&str>,
+        did: Option<&str>,
+        since: Option<&str>,
+    ) -> PyResult<PyObject> {
+        let memories = self.filtered(memory_type, did, since)?;
+        Ok(record_batch(py, &memories)?.into())
+    }
+
+    /// Memories matching the filters as a list of column batches of at most `batch_size` rows
+    #[pyo3(signature = (batch_size=DEFAULT_BATCH_SIZE, memory_type=None, did=None, since=None))]
+    fn batches(
+        &self,
+        py: Python<'_>,
+        batch_size: usize,
+        memory_type: Option<&str>,
+        did: Option<&str>,
+        since: Option<&str>,
+    ) -> PyResult<Vec<PyObject>> {
+        if batch_size == 0 {
+            return Err(PyValueError::new_err("batch_size must be at least 1"));
+        }
+        let memories = self.filtered(memory_type, did, since)?;
+        memories
+            .chunks(batch_size)
+            .map(|chunk| Ok(record_batch(py, chunk)?.into()))
+            .collect()
+    }
+
+    /// Number of memories per memory type
+    fn count_by_type(&self) -> PyResult<BTreeMap<String, usize>> {
+        let mut counts = BTreeMap::new();
+        for memory in self.database.list_signed_memories().map_err(to_py_err)? {
+            *counts.entry(memory.memory_type).or_insert(0) += 1;
+        }
+        Ok(counts)
+    }
+
+    /// Write matching memories to `path` as JSON Lines, returning how many were written
+    #[pyo3(signature = (path, memory_type=None, did=None, since=None))]
+    fn export_jsonl(
+        &self,
+        path: &str,
+        memory_type: Option<&str>,
+        did: Option<&str>,
+        since: Option<&str>,
+    ) -> PyResult<usize> {
+        let memories = self.filtered(memory_type, did, since)?;
+        let file = std::fs::File::create(path).map_err(|e| PyIOError::new_err(e.to_string()))?;
+        let mut writer = std::io::BufWriter::new(file);
+        for memory in &memories {
+            let line = serde_json::to_string(memory)
+                .map_err(|e| PyRuntimeError::new_err(e.to_string()))?;
+            writeln!(writer, "{}", line).map_err(|e| PyIOError::new_err(e.to_string()))?;
+        }
+        writer
+            .flush()
+            .map_err(|e| PyIOError::new_err(e.to_string()))?;
+        Ok(memories.len())
+    }
+
+    /// Check a stored memory's hash and author signature against a base64 public key
+    fn verify(&self, id: &str, public_key: &str) -> PyResult<bool> {
+        match self
+            .database
+            .get_signed_memory(&MemoryId::parse(id).map_err(invalid_id)?)
+            .map_err(to_py_err)?
+        {
+            Some(memory) => Ok(verify_memory_with_key(&memory, public_key)),
+            None => Err(PyValueError::new_err(format!("No memory with id {}", id))),
+        }
+    }
+}
+
+impl MemoryStore {
+    fn filtered(
+        &self,
+        memory_type: Option<&str>,
+        did: Option<&str>,
+        since: Option<&str>,
+    ) -> PyResult<Vec<SignedMemory>> {
+        let since = since
+            .map(chrono::DateTime::parse_from_rfc3339)
+            .transpose()
+            .map_err(|e| PyValueError::new_err(format!("since must be RFC 3339: {}", e)))?;
+
+        Ok(self
+            .database
+            .list_signed_memories()
+            .map_err(to_py_err)?
+            .into_iter()
+            .filter(|m| memory_type.is_none_or(|t| m.memory_type == t))
+            .filter(|m| did.is_none_or(|d| m.did == d))
+            .filter(|m| match since {
+                Some(since) => chrono::DateTime::parse_from_rfc3339(&m.timestamp)
+                    .map(|timestamp| timestamp >= since)
+                    .unwrap_or(false),
+                None => true,
+            })
+            .collect())
+    }
+}
+
+/// Check a memory dict (as returned by `MemoryStore.get`) against a base64 public key
+#[pyfunction]
+fn verify_memory(memory: &Bound<'_, PyDict>, public_key: &str) -> PyResult<bool> {
+    Ok(verify_memory_with_key(
+        &memory_from_dict(memory)?,
+        public_key,
+    ))
+}
+
+fn memory_to_dict<'py>(py: Python<'py>, memory: &SignedMemory) -> PyResult<Bound<'py, PyDict>> {
+    let dict = PyDict::new_bound(py);
+    dict.set_item("id", memory.id.as_str())?;
+    dict.set_item("did", memory.did.as_str())?;
+    dict.set_item("memory_type", &memory.memory_type)?;
+    dict.set_item("memory_data", &memory.memory_data)?;
+    dict.set_item("content_hash", &memory.content_hash)?;
+    dict.set_item("signature", &memory.signature)?;
+    dict.set_item("timestamp", &memory.timestamp)?;
+    dict.set_item("updated_on", &memory.updated_on)?;
+    dict.set_item("co_signatures", co_signatures_json(memory))?;
+    Ok(dict)
+}
+
+fn memory_from_dict(dict: &Bound<'_, PyDict>) -> PyResult<SignedMemory> {
+    let field = |name: &str| -> PyResult<String> {
+        dict.get_item(name)?
+            .ok_or_else(|| PyValueError::new_err(format!("memory is missing '{}'", name)))?
+            .extract()
+    };
+    let co_signatures: Vec<CoSignature> = match dict.get_item("co_signatures")? {
+        Some(value) => serde_json::from_str(&value.extract::<String>()?)
+            .map_err(|e| PyValueError::new_err(format!("Invalid co_signatures: {}", e)))?,
+        None => Vec::new(),
+    };
+
+    Ok(SignedMemory {
+        id: MemoryId::parse(&field("id")?).map_err(invalid_id)?,
+        did: Did::parse(&field("did")?).map_err(invalid_id)?,
+        memory_type: field("memory_type")?,
+        memory_data: field("memory_data")?,
+        content_hash: field("content_hash")?,
+        signature: field("signature")?,
+        timestamp: field("timestamp")?,
+        updated_on: field("updated_on")?,
+        co_signatures,
+    })
+}
+
+/// Column-oriented batch: every column is a list with one entry per memory
+fn record_batch<'py>(py: Python<'py>, memories: &[SignedMemory]) -> PyResult<Bound<'py, PyDict>> {
+    let column = |f: fn(&SignedMemory) -> String| memories.iter().map(f).collect::<Vec<_>>();
+
+    let batch = PyDict::new_bound(py);
+    batch.set_item("id", column(|m| m.id.to_string()))?;
+    batch.set_item("did", column(|m| m.did.to_string()))?;
+    batch.set_item("memory_type", column(|m| m.memory_type.clone()))?;
+    batch.set_item("memory_data", column(|m| m.memory_data.clone()))?;
+    batch.set_item("content_hash", column(|m| m.content_hash.clone()))?;
+    batch.set_item("signature", column(|m| m.signature.clone()))?;
+    batch.set_item("timestamp", column(|m| m.timestamp.clone()))?;
+    batch.set_item("updated_on", column(|m| m.updated_on.clone()))?;
+    batch.set_item("co_signatures", column(co_signatures_json))?;
+    batch.set_item(
+        "co_signer_count",
+        memories
+            .iter()
+            .map(|m| m.co_signatures.len())
+            .collect::<Vec<_>>(),
+    )?;
+    batch.set_item(
+        "hash_valid",
+        memories.iter().map(|m| m.verify_hash()).collect::<Vec<_>>(),
+    )?;
+    Ok(batch)
+}
+
+// Nested co-signatures are flattened to JSON so every column stays scalar
+fn co_signatures_json(memory: &SignedMemory) -> String {
+    serde_json::to_string(&memory.co_signatures).unwrap_or_else(|_| "[]".to_string())
+}
+
+#[pymodule]
+fn ocm_py(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add_class::<MemoryStore>()?;
+    m.add_function(wrap_pyfunction!(verify_memory, m)?)?;
+    Ok(())
+}
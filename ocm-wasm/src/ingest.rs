@@ -0,0 +1,107 @@
+use ocm_core::{needs_plc_document, verify_memory_with_document, PlcDocument, SignedMemory};
+use ocm_protocol::ingest::{IngestLedger, IngestPolicy};
+use std::cell::RefCell;
+use std::collections::HashMap;
+use wasm_bindgen::JsCast;
+use wasm_bindgen_futures::JsFuture;
+
+/// Verifies memories from the relay before the app sees or stores them. did:plc
+/// authors' documents are fetched from the directory once per page load
+pub struct BrowserIngest {
+    ledger: RefCell<IngestLedger>,
+    documents: RefCell<HashMap<String, PlcDocument>>,
+    directory_url: RefCell<String>,
+}
+
+impl BrowserIngest {
+    pub fn new() -> Self {
+        Self {
+            ledger: RefCell::new(IngestLedger::new(IngestPolicy::default())),
+            documents: RefCell::new(HashMap::new()),
+            directory_url: RefCell::new("https://plc.directory".to_string()),
+        }
+    }
+
+    pub fn set_policy(&self, policy: IngestPolicy) {
+        self.ledger.borrow_mut().set_policy(policy);
+    }
+
+    pub fn policy(&self) -> IngestPolicy {
+        self.ledger.borrow().policy().clone()
+    }
+
+    pub fn set_directory_url(&self, url: &str) {
+        *self.directory_url.borrow_mut() = url.trim_end_matches('/').to_string();
+    }
+
+    /// Use `document` for its DID instead of fetching it
+    pub fn add_document(&self, document: PlcDocument) {
+        self.documents
+            .borrow_mut()
+            .insert(document.id.clone(), document);
+    }
+
+    pub fn ledger(&self) -> std::cell::Ref<'_, IngestLedger> {
+        self.ledger.borrow()
+    }
+
+    /// Whether `memory` from `source` may be passed on or stored; rejections are
+    /// counted and tampered memories quarantined
+    pub async fn check(&self, memory: &SignedMemory, source: &str) -> bool {
+        if !self.ledger.borrow().policy().verify_before_store {
+            self.ledger.borrow_mut().record_unchecked();
+            return true;
+        }
+
+        let document = if needs_plc_document(&memory.did) {
+            self.document(&memory.did).await
+        } else {
+            None
+        };
+        match verify_memory_with_document(memory, document.as_ref()) {
+            Ok(()) => {
+                self.ledger.borrow_mut().record_verified();
+                true
+            }
+            Err(reason) => {
+                web_sys::console::warn_1(
+                    &format!(
+                        "Rejected memory {} from {}: {}",
+                        memory.id,
+                        source,
+                        reason.as_str()
+                    )
+                    .into(),
+                );
+                self.ledger.borrow_mut().reject(memory, reason, source);
+                false
+            }
+        }
+    }
+
+    async fn document(&self, did: &str) -> Option<PlcDocument> {
+        if let Some(document) = self.documents.borrow().get(did) {
+            return Some(document.clone());
+        }
+
+        let url = format!("{}/{}", self.directory_url.borrow(), did);
+        let window = web_sys::window()?;
+        let response = JsFuture::from(window.fetch_with_str(&url)).await.ok()?;
+        let response: web_sys::Response = response.dyn_into().ok()?;
+        if !response.ok() {
+            return None;
+        }
+        let text = JsFuture::from(response.text().ok()?)
+            .await
+            .ok()?
+            .as_string()?;
+        let document: PlcDocument = serde_json::from_str(&text).ok()?;
+        if document.id != did {
+            return None;
+        }
+        self.documents
+            .borrow_mut()
+            .insert(did.to_string(), document.clone());
+        Some(document)
+    }
+}
@@ -0,0 +1,422 @@
+//! Identity keys at rest in the browser, wrapped so that a same-origin script reading
+//! OPFS or IndexedDB finds nothing it can sign with.
+//!
+//! - `webauthn`: the wrapping key is derived from a passkey's PRF extension output, so
+//!   every unwrap needs the authenticator and a user gesture or biometric.
+//! - `webcrypto`: the wrapping key is a non-extractable AES-GCM `CryptoKey`, kept in
+//!   IndexedDB by the page (`window.ocmKeyStorePut` / `window.ocmKeyStoreGet`). Scripts
+//!   can use it but never read it, so a copied key file is useless elsewhere. No gesture
+//!   is needed.
+
+use base64::{engine::general_purpose, Engine as _};
+use js_sys::{Array, Object, Reflect, Uint8Array};
+use ocm_core::PlcIdentity;
+use serde::{Deserialize, Serialize};
+use wasm_bindgen::prelude::*;
+use wasm_bindgen::JsCast;
+use wasm_bindgen_futures::JsFuture;
+use web_sys::CryptoKey;
+use zeroize::Zeroizing;
+
+// HKDF info turning a passkey's PRF output into a wrapping key
+const PRF_WRAP_INFO: &[u8] = b"ocm-identity-wrap-v1";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum KeyProtection {
+    Webauthn,
+    Webcrypto,
+}
+
+impl KeyProtection {
+    pub fn parse(value: &str) -> Result<Self, String> {
+        match value {
+            "webauthn" => Ok(KeyProtection::Webauthn),
+            "webcrypto" => Ok(KeyProtection::Webcrypto),
+            other => Err(format!(
+                "Unknown key protection {}; use webauthn or webcrypto",
+                other
+            )),
+        }
+    }
+}
+
+/// What the page stores for a protected identity. Useless without the passkey or the
+/// non-extractable key it was wrapped with
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WrappedIdentityKey {
+    pub did: String,
+    pub public_key: String, // Base64 Ed25519 public key
+    pub handle: Option<String>,
+    pub protection: KeyProtection,
+    pub credential_id: Option<String>, // Base64 passkey credential ID (webauthn)
+    pub prf_salt: Option<String>,      // Base64 PRF input (webauthn)
+    pub nonce: String,                 // Base64 AES-GCM nonce
+    pub ciphertext: String,            // Base64 Ed25519 seed, sealed with the DID as AAD
+    pub created_at: String,
+}
+
+/// Wrap a new identity's key, registering a passkey first for `webauthn`
+pub async fn wrap_identity(
+    identity: &PlcIdentity,
+    handle: Option<String>,
+    protection: KeyProtection,
+    rp_name: &str,
+) -> Result<WrappedIdentityKey, String> {
+    let seed = identity
+        .keypair
+        .private_key_bytes()
+        .ok_or("Identity key is not held in memory")?;
+
+    let (wrapping_key, credential_id, prf_salt) = match protection {
+        KeyProtection::Webauthn => {
+            let salt = crate::get_secure_random_bytes(32)?;
+            let (credential_id, prf_output) =
+                create_prf_credential(&identity.did, handle.as_deref(), rp_name, &salt).await?;
+            let prf_output = match prf_output {
+                Some(output) => output,
+                // Not every authenticator evaluates the PRF while registering
+                None => prf_output_for(&credential_id, &salt).await?,
+            };
+            (
+                prf_wrapping_key(&prf_output).await?,
+                Some(general_purpose::STANDARD.encode(&credential_id)),
+                Some(general_purpose::STANDARD.encode(&salt)),
+            )
+        }
+        KeyProtection::Webcrypto => {
+            let key = generate_wrapping_key().await?;
+            store_wrapping_key(&identity.did, &key).await?;
+            (key, None, None)
+        }
+    };
+
+    let nonce = crate::get_secure_random_bytes(12)?;
+    let ciphertext = aes_gcm(&wrapping_key, &nonce, &identity.did, seed, true).await?;
+    Ok(WrappedIdentityKey {
+        did: identity.did.to_string(),
+        public_key: identity.keypair.public_key.clone(),
+        handle,
+        protection,
+        credential_id,
+        prf_salt,
+        nonce: general_purpose::STANDARD.encode(&nonce),
+        ciphertext: general_purpose::STANDARD.encode(ciphertext.as_slice()),
+        created_at: chrono::Utc::now().to_rfc3339(),
+    })
+}
+
+/// Unwrap a stored key into an identity; prompts for the passkey under `webauthn`
+pub async fn unwrap_identity(wrapped: &WrappedIdentityKey) -> Result<PlcIdentity, String> {
+    let wrapping_key = match wrapped.protection {
+        KeyProtection::Webauthn => {
+            let credential_id = wrapped
+                .credential_id
+                .as_deref()
+                .ok_or("Wrapped key has no passkey credential ID")?;
+            let salt = wrapped
+                .prf_salt
+                .as_deref()
+                .ok_or("Wrapped key has no PRF salt")?;
+            let credential_id = decode("credential ID", credential_id)?;
+            let salt = decode("PRF salt", salt)?;
+            prf_wrapping_key(&prf_output_for(&credential_id, &salt).await?).await?
+        }
+        KeyProtection::Webcrypto => load_wrapping_key(&wrapped.did).await?,
+    };
+
+    let nonce = decode("nonce", &wrapped.nonce)?;
+    let ciphertext = decode("ciphertext", &wrapped.ciphertext)?;
+    let seed = aes_gcm(&wrapping_key, &nonce, &wrapped.did, &ciphertext, false).await?;
+    let seed: [u8; 32] = seed
+        .as_slice()
+        .try_into()
+        .map_err(|_| "Unwrapped key is not 32 bytes")?;
+    let seed = Zeroizing::new(seed);
+
+    let identity =
+        PlcIdentity::from_seed(*seed, wrapped.handle.clone()).map_err(|e| e.to_string())?;
+    if identity.did != wrapped.did || identity.keypair.public_key != wrapped.public_key {
+        return Err("Unwrapped key does not belong to this identity".to_string());
+    }
+    Ok(identity)
+}
+
+fn decode(field: &str, value: &str) -> Result<Vec<u8>, String> {
+    general_purpose::STANDARD
+        .decode(value)
+        .map_err(|e| format!("Wrapped key {} is not base64: {}", field, e))
+}
+
+fn js_error(what: &str, error: JsValue) -> String {
+    let message = Reflect::get(&error, &"message".into())
+        .ok()
+        .and_then(|message| message.as_string())
+        .or_else(|| error.as_string())
+        .unwrap_or_else(|| format!("{:?}", error));
+    format!("{} failed: {}", what, message)
+}
+
+async fn resolve(what: &str, promise: Result<js_sys::Promise, JsValue>) -> Result<JsValue, String> {
+    let promise = promise.map_err(|e| js_error(what, e))?;
+    JsFuture::from(promise).await.map_err(|e| js_error(what, e))
+}
+
+fn object(entries: &[(&str, JsValue)]) -> Result<Object, String> {
+    let object = Object::new();
+    for (name, value) in entries {
+        Reflect::set(&object, &(*name).into(), value)
+            .map_err(|e| js_error("Building options", e))?;
+    }
+    Ok(object)
+}
+
+fn key_usages(usages: &[&str]) -> JsValue {
+    usages
+        .iter()
+        .map(|usage| JsValue::from_str(usage))
+        .collect::<Array>()
+        .into()
+}
+
+fn subtle() -> Result<web_sys::SubtleCrypto, String> {
+    let window = web_sys::window().ok_or("No window available")?;
+    let crypto = window.crypto().map_err(|_| "WebCrypto API not available")?;
+    Ok(crypto.subtle())
+}
+
+/// AES-256-GCM with the DID as additional data, so a wrapped key cannot be moved to
+/// another identity's record
+async fn aes_gcm(
+    key: &CryptoKey,
+    nonce: &[u8],
+    did: &str,
+    data: &[u8],
+    encrypt: bool,
+) -> Result<Zeroizing<Vec<u8>>, String> {
+    let params = object(&[
+        ("name", "AES-GCM".into()),
+        ("iv", Uint8Array::from(nonce).into()),
+        ("additionalData", Uint8Array::from(did.as_bytes()).into()),
+    ])?;
+    let data = Uint8Array::from(data);
+    let subtle = subtle()?;
+    let result = if encrypt {
+        resolve(
+            "Wrapping the identity key",
+            subtle.encrypt_with_object_and_buffer_source(&params, key, &data),
+        )
+        .await?
+    } else {
+        resolve(
+            "Unwrapping the identity key",
+            subtle.decrypt_with_object_and_buffer_source(&params, key, &data),
+        )
+        .await?
+    };
+    Ok(Zeroizing::new(Uint8Array::new(&result).to_vec()))
+}
+
+/// A non-extractable AES-GCM key for `webcrypto` protection
+async fn generate_wrapping_key() -> Result<CryptoKey, String> {
+    let algorithm = object(&[("name", "AES-GCM".into()), ("length", 256.into())])?;
+    let key = resolve(
+        "Generating a wrapping key",
+        subtle()?.generate_key_with_object(&algorithm, false, &key_usages(&["encrypt", "decrypt"])),
+    )
+    .await?;
+    key.dyn_into()
+        .map_err(|_| "WebCrypto did not return a key".to_string())
+}
+
+/// Non-extractable AES-GCM key derived from a passkey's PRF output
+async fn prf_wrapping_key(prf_output: &[u8]) -> Result<CryptoKey, String> {
+    let subtle = subtle()?;
+    let base = resolve(
+        "Importing the PRF output",
+        subtle.import_key_with_object(
+            "raw",
+            &Uint8Array::from(prf_output),
+            &object(&[("name", "HKDF".into())])?,
+            false,
+            &key_usages(&["deriveKey"]),
+        ),
+    )
+    .await?
+    .dyn_into::<CryptoKey>()
+    .map_err(|_| "WebCrypto did not return a key".to_string())?;
+
+    let hkdf = object(&[
+        ("name", "HKDF".into()),
+        ("hash", "SHA-256".into()),
+        ("salt", Uint8Array::new_with_length(0).into()),
+        ("info", Uint8Array::from(PRF_WRAP_INFO).into()),
+    ])?;
+    let aes = object(&[("name", "AES-GCM".into()), ("length", 256.into())])?;
+    let key = resolve(
+        "Deriving the wrapping key",
+        subtle.derive_key_with_object_and_object(
+            &hkdf,
+            &base,
+            &aes,
+            false,
+            &key_usages(&["encrypt", "decrypt"]),
+        ),
+    )
+    .await?;
+    key.dyn_into()
+        .map_err(|_| "WebCrypto did not return a key".to_string())
+}
+
+fn credentials() -> Result<JsValue, String> {
+    let window = web_sys::window().ok_or("No window available")?;
+    Reflect::get(&window.navigator(), &"credentials".into())
+        .ok()
+        .filter(|credentials| !credentials.is_undefined())
+        .ok_or_else(|| "WebAuthn is not available in this browser".to_string())
+}
+
+/// Call `navigator.credentials.<method>(options)`
+async fn call_credentials(method: &str, options: &Object) -> Result<JsValue, String> {
+    let credentials = credentials()?;
+    let function: js_sys::Function = Reflect::get(&credentials, &method.into())
+        .ok()
+        .and_then(|function| function.dyn_into().ok())
+        .ok_or_else(|| format!("navigator.credentials.{} is not available", method))?;
+    let promise = function
+        .call1(&credentials, options)
+        .and_then(|promise| promise.dyn_into::<js_sys::Promise>());
+    resolve("Passkey request", promise).await
+}
+
+/// `prf.results.first` from a credential's client extension results, if present
+fn prf_first_result(credential: &JsValue) -> Result<Option<Vec<u8>>, String> {
+    let results: js_sys::Function = Reflect::get(credential, &"getClientExtensionResults".into())
+        .ok()
+        .and_then(|function| function.dyn_into().ok())
+        .ok_or("Credential has no extension results")?;
+    let results = results
+        .call0(credential)
+        .map_err(|e| js_error("Reading extension results", e))?;
+    let first = Reflect::get(&results, &"prf".into())
+        .and_then(|prf| Reflect::get(&prf, &"results".into()))
+        .and_then(|prf_results| Reflect::get(&prf_results, &"first".into()))
+        .unwrap_or(JsValue::UNDEFINED);
+    if first.is_undefined() || first.is_null() {
+        return Ok(None);
+    }
+    Ok(Some(Uint8Array::new(&first).to_vec()))
+}
+
+/// Register a passkey for `did` with the PRF extension, returning its credential ID and,
+/// when the authenticator evaluates it at registration, the PRF output for `salt`
+async fn create_prf_credential(
+    did: &str,
+    handle: Option<&str>,
+    rp_name: &str,
+    salt: &[u8],
+) -> Result<(Vec<u8>, Option<Vec<u8>>), String> {
+    let name = handle.unwrap_or(did);
+    let algorithms: Array = [-8, -7, -257]
+        .iter()
+        .map(|alg| object(&[("type", "public-key".into()), ("alg", (*alg).into())]))
+        .collect::<Result<_, _>>()?;
+    let public_key = object(&[
+        ("rp", object(&[("name", rp_name.into())])?.into()),
+        (
+            "user",
+            object(&[
+                ("id", Uint8Array::from(did.as_bytes()).into()),
+                ("name", name.into()),
+                ("displayName", name.into()),
+            ])?
+            .into(),
+        ),
+        (
+            "challenge",
+            Uint8Array::from(crate::get_secure_random_bytes(32)?.as_slice()).into(),
+        ),
+        ("pubKeyCredParams", algorithms.into()),
+        (
+            "authenticatorSelection",
+            object(&[
+                ("residentKey", "preferred".into()),
+                ("userVerification", "required".into()),
+            ])?
+            .into(),
+        ),
+        ("extensions", prf_extension(salt)?.into()),
+    ])?;
+    let credential =
+        call_credentials("create", &object(&[("publicKey", public_key.into())])?).await?;
+
+    let enabled = Reflect::get(&credential, &"getClientExtensionResults".into())
+        .ok()
+        .and_then(|function| function.dyn_into::<js_sys::Function>().ok())
+        .and_then(|function| function.call0(&credential).ok())
+        .and_then(|results| Reflect::get(&results, &"prf".into()).ok())
+        .and_then(|prf| Reflect::get(&prf, &"enabled".into()).ok())
+        .and_then(|enabled| enabled.as_bool())
+        .unwrap_or(false);
+    if !enabled {
+        return Err("This passkey does not support the PRF extension".to_string());
+    }
+
+    let raw_id = Reflect::get(&credential, &"rawId".into())
+        .map_err(|e| js_error("Reading the credential ID", e))?;
+    Ok((
+        Uint8Array::new(&raw_id).to_vec(),
+        prf_first_result(&credential)?,
+    ))
+}
+
+/// Ask the passkey to evaluate its PRF for `salt`; needs a user gesture or biometric
+async fn prf_output_for(credential_id: &[u8], salt: &[u8]) -> Result<Vec<u8>, String> {
+    let allowed: Array = [object(&[
+        ("type", "public-key".into()),
+        ("id", Uint8Array::from(credential_id).into()),
+    ])?]
+    .into_iter()
+    .collect();
+    let public_key = object(&[
+        (
+            "challenge",
+            Uint8Array::from(crate::get_secure_random_bytes(32)?.as_slice()).into(),
+        ),
+        ("allowCredentials", allowed.into()),
+        ("userVerification", "required".into()),
+        ("extensions", prf_extension(salt)?.into()),
+    ])?;
+    let credential = call_credentials("get", &object(&[("publicKey", public_key.into())])?).await?;
+    prf_first_result(&credential)?
+        .ok_or_else(|| "The passkey did not return a PRF result".to_string())
+}
+
+fn prf_extension(salt: &[u8]) -> Result<Object, String> {
+    let eval = object(&[("first", Uint8Array::from(salt).into())])?;
+    object(&[("prf", object(&[("eval", eval.into())])?.into())])
+}
+
+/// Call a key store function the page sets on `window`, as with `sqlExecute`
+async fn call_key_store(name: &str, args: &Array) -> Result<JsValue, String> {
+    let window = web_sys::window().ok_or("No window available")?;
+    let function: js_sys::Function = Reflect::get(&window, &name.into())
+        .ok()
+        .and_then(|function| function.dyn_into().ok())
+        .ok_or_else(|| format!("{} not found; the page must provide a key store", name))?;
+    let promise = function
+        .apply(&JsValue::NULL, args)
+        .and_then(|promise| promise.dyn_into::<js_sys::Promise>());
+    resolve(name, promise).await
+}
+
+async fn store_wrapping_key(did: &str, key: &CryptoKey) -> Result<(), String> {
+    let args = Array::of2(&did.into(), key);
+    call_key_store("ocmKeyStorePut", &args).await.map(|_| ())
+}
+
+async fn load_wrapping_key(did: &str) -> Result<CryptoKey, String> {
+    let key = call_key_store("ocmKeyStoreGet", &Array::of1(&did.into())).await?;
+    key.dyn_into()
+        .map_err(|_| "No wrapping key is stored for this identity".to_string())
+}
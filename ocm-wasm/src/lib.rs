@@ -1,10 +1,27 @@
+use base64::{engine::general_purpose, Engine as _};
+use std::rc::Rc;
 use wasm_bindgen::prelude::*;
 use web_sys::console;
 
 // Import core OCM functionality
-use ocm_core::{PlcIdentity, SignedMemory};
+use ocm_core::identity::envelope;
+use ocm_core::{
+    DirectMessageData, PlcDocument, PlcIdentity, SignedMemory, DIRECT_MESSAGE_MEMORY_TYPE,
+};
+use ocm_protocol::feed::{FeedCursor, FeedQuery};
+use ocm_protocol::filter::Filter;
+use ocm_protocol::handle;
+use ocm_protocol::ids::Did;
+use ocm_protocol::patch;
+use ocm_protocol::presence::{PresenceStatus, PresenceUpdate};
+use ocm_protocol::safety;
+use ocm_protocol::verification::OrganizationBadge;
+
+use crate::ingest::BrowserIngest;
 
 mod crypto;
+mod ingest;
+mod keywrap;
 mod storage;
 mod utils;
 mod websocket;
@@ -28,6 +45,59 @@ pub fn greet(name: &str) {
     alert(&format!("Hello, {}!", name));
 }
 
+/// Canonical form of a handle typed by a user (`@Alice.Family-OCM` -> `alice.family-ocm`),
+/// or an error describing why it is not a valid handle
+#[wasm_bindgen]
+pub fn normalize_handle(input: &str) -> Result<String, String> {
+    handle::normalize_handle(input)
+}
+
+/// Whether a safety number read out or typed by the user matches the expected one
+#[wasm_bindgen]
+pub fn safety_numbers_match(expected: &str, entered: &str) -> bool {
+    safety::safety_numbers_match(expected, entered)
+}
+
+/// Label for the verified badge of `organization_did`, given the JSON of
+/// GET /api/v1/organizations/{did}/verification or a claim preview's `organization`.
+/// Empty when the organization is not verified; an error if the badge is for another DID
+#[wasm_bindgen]
+pub fn organization_badge_label(
+    badge_json: &str,
+    organization_did: &str,
+) -> Result<String, String> {
+    let badge: OrganizationBadge = serde_json::from_str(badge_json).map_err(|e| e.to_string())?;
+    if badge.organization_did != organization_did {
+        return Err(format!(
+            "Badge is for {}, not {}",
+            badge.organization_did, organization_did
+        ));
+    }
+    Ok(badge
+        .verification
+        .map(|verification| verification.badge_label())
+        .unwrap_or_default())
+}
+
+/// Field-level changes from one memory's data to another's, as JSON: a list of
+/// `{path, kind, old, new}`, for showing what changed between two versions offline
+#[wasm_bindgen]
+pub fn diff_memory_data(old_data: &str, new_data: &str) -> Result<String, String> {
+    let changes = patch::diff_values(
+        &patch::memory_data_value(old_data),
+        &patch::memory_data_value(new_data),
+    );
+    serde_json::to_string(&changes).map_err(|e| e.to_string())
+}
+
+fn decode_public_key(key: &str) -> Result<[u8; 32], String> {
+    general_purpose::STANDARD
+        .decode(key)
+        .map_err(|e| e.to_string())?
+        .try_into()
+        .map_err(|_| "Public key must be 32 bytes".to_string())
+}
+
 // A macro to provide `println!(..)`-style syntax for `console.log` logging
 macro_rules! log {
     ( $( $t:tt )* ) => {
@@ -50,6 +120,9 @@ pub struct OcmWasm {
     storage: BrowserStorage,
     identity: Option<PlcIdentity>,
     websocket: Option<OcmWebSocket>,
+    device_id: String, // Identifies this browser in presence announcements
+    sign_once: bool,   // Lock the identity again after its next signature
+    ingest: Rc<BrowserIngest>,
 }
 
 #[wasm_bindgen]
@@ -62,20 +135,97 @@ impl OcmWasm {
             storage: BrowserStorage::new(),
             identity: None,
             websocket: None,
+            device_id: uuid::Uuid::new_v4().to_string(),
+            sign_once: false,
+            ingest: Rc::new(BrowserIngest::new()),
         }
     }
 
     #[wasm_bindgen]
     pub fn create_identity(&mut self, handle: Option<String>) -> Result<String, String> {
+        let handle = handle
+            .as_deref()
+            .map(handle::normalize_handle)
+            .transpose()?;
         let identity = PlcIdentity::generate(handle).map_err(|e| e.to_string())?;
 
-        let did = identity.did.clone();
+        let did = identity.did.to_string();
         self.identity = Some(identity);
 
         log!("Created identity with DID: {}", did);
         Ok(did)
     }
 
+    /// Create an identity whose key is only stored wrapped, returning the JSON to keep
+    /// (e.g. in OPFS) for `unlock_identity`. `protection` is "webauthn", a passkey with
+    /// the PRF extension whose every unlock needs a user gesture or biometric, or
+    /// "webcrypto", a non-extractable key the page keeps in IndexedDB. The identity is
+    /// unlocked on return
+    #[wasm_bindgen]
+    pub async fn create_protected_identity(
+        &mut self,
+        handle: Option<String>,
+        protection: &str,
+        rp_name: Option<String>,
+    ) -> Result<String, String> {
+        let protection = keywrap::KeyProtection::parse(protection)?;
+        let handle = handle
+            .as_deref()
+            .map(handle::normalize_handle)
+            .transpose()?;
+        let identity = PlcIdentity::generate(handle.clone()).map_err(|e| e.to_string())?;
+        let wrapped = keywrap::wrap_identity(
+            &identity,
+            handle,
+            protection,
+            rp_name.as_deref().unwrap_or("OCM"),
+        )
+        .await?;
+
+        log!(
+            "Created identity with DID {}, key protected by {:?}",
+            identity.did,
+            protection
+        );
+        self.identity = Some(identity);
+        self.sign_once = false;
+        serde_json::to_string(&wrapped).map_err(|e| e.to_string())
+    }
+
+    /// Unwrap an identity stored by `create_protected_identity`, prompting for the
+    /// passkey under "webauthn". With `sign_once`, the identity locks again after its
+    /// next signature, so every signature needs a fresh unlock
+    #[wasm_bindgen]
+    pub async fn unlock_identity(
+        &mut self,
+        wrapped_json: &str,
+        sign_once: Option<bool>,
+    ) -> Result<String, String> {
+        let wrapped: keywrap::WrappedIdentityKey =
+            serde_json::from_str(wrapped_json).map_err(|e| e.to_string())?;
+        let identity = keywrap::unwrap_identity(&wrapped).await?;
+
+        let did = identity.did.to_string();
+        self.identity = Some(identity);
+        self.sign_once = sign_once.unwrap_or(false);
+        log!("Unlocked identity {}", did);
+        Ok(did)
+    }
+
+    /// Drop the unwrapped identity key from memory
+    #[wasm_bindgen]
+    pub fn lock_identity(&mut self) {
+        self.identity = None;
+        self.sign_once = false;
+    }
+
+    fn after_signing(&mut self) {
+        if self.sign_once {
+            self.lock_identity();
+            log!("🔒 Identity locked after signing");
+        }
+    }
+
     #[wasm_bindgen]
     pub async fn init_storage(&mut self) -> Result<(), String> {
         self.storage.init().await.map_err(|e| e.to_string())
@@ -94,8 +244,9 @@ impl OcmWasm {
         identity
             .sign_memory(&mut memory)
             .map_err(|e| e.to_string())?;
+        self.after_signing();
 
-        let memory_id = memory.id.clone();
+        let memory_id = memory.id.to_string();
 
         // Store in browser storage
         self.storage
@@ -107,6 +258,62 @@ impl OcmWasm {
         Ok(memory_id)
     }
 
+    /// Store a memory received from elsewhere, e.g. another tab or an export, once its
+    /// author's signature checks out. Returns false if it was rejected
+    #[wasm_bindgen]
+    pub async fn store_received_memory(
+        &mut self,
+        memory_json: &str,
+        source: &str,
+    ) -> Result<bool, String> {
+        let memory: SignedMemory =
+            serde_json::from_str(memory_json).map_err(|e| format!("JSON parse error: {}", e))?;
+        if !self.ingest.check(&memory, source).await {
+            return Ok(false);
+        }
+
+        self.storage
+            .store_memory(&memory)
+            .await
+            .map_err(|e| format!("Storage error: {:?}", e))?;
+        Ok(true)
+    }
+
+    /// Turn verification of received memories off, for development against unsigned data
+    #[wasm_bindgen]
+    pub fn set_verify_before_store(&mut self, enabled: bool) {
+        let mut policy = self.ingest.policy();
+        policy.verify_before_store = enabled;
+        self.ingest.set_policy(policy);
+    }
+
+    /// PLC directory to fetch did:plc documents from when verifying received memories
+    #[wasm_bindgen]
+    pub fn set_plc_directory(&mut self, url: &str) {
+        self.ingest.set_directory_url(url);
+    }
+
+    /// Verify a DID's memories against this document instead of fetching it
+    #[wasm_bindgen]
+    pub fn add_plc_document(&mut self, document_json: &str) -> Result<(), String> {
+        let document: PlcDocument =
+            serde_json::from_str(document_json).map_err(|e| format!("JSON parse error: {}", e))?;
+        self.ingest.add_document(document);
+        Ok(())
+    }
+
+    /// Counts of received memories verified and rejected, by reason
+    #[wasm_bindgen]
+    pub fn ingest_stats(&self) -> Result<String, String> {
+        serde_json::to_string(self.ingest.ledger().stats()).map_err(|e| e.to_string())
+    }
+
+    /// Received memories rejected as tampered with, oldest first
+    #[wasm_bindgen]
+    pub fn quarantined_memories(&self) -> Result<String, String> {
+        serde_json::to_string(&self.ingest.ledger().quarantined()).map_err(|e| e.to_string())
+    }
+
     #[wasm_bindgen]
     pub async fn list_memories(&self) -> Result<String, String> {
         let memories = self
@@ -118,10 +325,123 @@ impl OcmWasm {
         serde_json::to_string(&memories).map_err(|e| e.to_string())
     }
 
+    /// A page of memories, newest first, optionally by one DID, of comma-separated
+    /// types and matching a filter expression such as "data.cohort='Red'"; pass the
+    /// returned `next_cursor` back as `cursor` for the next page
+    #[wasm_bindgen]
+    pub async fn get_feed(
+        &self,
+        did: Option<String>,
+        cursor: Option<String>,
+        limit: usize,
+        memory_types: Option<String>,
+        filter: Option<String>,
+    ) -> Result<String, String> {
+        let query = FeedQuery {
+            authors: did.map(|did| vec![did]),
+            memory_types: memory_types
+                .unwrap_or_default()
+                .split(',')
+                .map(str::trim)
+                .filter(|memory_type| !memory_type.is_empty())
+                .map(str::to_string)
+                .collect(),
+            cursor: cursor.as_deref().map(FeedCursor::decode).transpose()?,
+            limit,
+            filter: filter.as_deref().map(Filter::parse).transpose()?,
+        };
+        let page = self
+            .storage
+            .list_feed(&query)
+            .await
+            .map_err(|e| format!("Storage error: {:?}", e))?;
+
+        serde_json::to_string(&page).map_err(|e| e.to_string())
+    }
+
+    /// Safety number to compare with another DID out of band, given its base64 public key
+    #[wasm_bindgen]
+    pub fn safety_number(&self, their_did: &str, their_public_key: &str) -> Result<String, String> {
+        let identity = self
+            .identity
+            .as_ref()
+            .ok_or_else(|| "No identity created".to_string())?;
+
+        Ok(safety::safety_number(
+            &identity.did,
+            &decode_public_key(&identity.keypair.public_key)?,
+            their_did,
+            &decode_public_key(their_public_key)?,
+        ))
+    }
+
+    /// Seal `body` to the recipient's base64 public key and our own, returning a signed
+    /// direct_message memory as JSON, ready for `send_memory_to_relay`
+    #[wasm_bindgen]
+    pub fn seal_direct_message(
+        &mut self,
+        recipient_did: &str,
+        recipient_public_key: &str,
+        body: &str,
+    ) -> Result<String, String> {
+        let identity = self
+            .identity
+            .as_ref()
+            .ok_or_else(|| "No identity created".to_string())?;
+        let recipient_did = Did::parse(recipient_did).map_err(|e| e.to_string())?;
+        let envelope = envelope::seal(
+            body.as_bytes(),
+            &[
+                (
+                    recipient_did.clone(),
+                    decode_public_key(recipient_public_key)?,
+                ),
+                (
+                    identity.did.clone(),
+                    decode_public_key(&identity.keypair.public_key)?,
+                ),
+            ],
+        )
+        .map_err(|e| e.to_string())?;
+        let data = DirectMessageData {
+            recipient_did,
+            envelope,
+        };
+
+        let mut memory = SignedMemory::new(
+            &identity.did,
+            DIRECT_MESSAGE_MEMORY_TYPE,
+            &serde_json::to_string(&data).map_err(|e| e.to_string())?,
+        );
+        identity
+            .sign_memory(&mut memory)
+            .map_err(|e| e.to_string())?;
+        self.after_signing();
+        serde_json::to_string(&memory).map_err(|e| e.to_string())
+    }
+
+    /// Decrypt the body of a direct_message memory (as JSON) sent to or by this identity
+    #[wasm_bindgen]
+    pub fn open_direct_message(&self, memory_json: &str) -> Result<String, String> {
+        let identity = self
+            .identity
+            .as_ref()
+            .ok_or_else(|| "No identity created".to_string())?;
+        let memory: SignedMemory = serde_json::from_str(memory_json).map_err(|e| e.to_string())?;
+        if memory.memory_type != DIRECT_MESSAGE_MEMORY_TYPE {
+            return Err(format!("{} is not a direct message", memory.memory_type));
+        }
+        let data: DirectMessageData =
+            serde_json::from_str(&memory.memory_data).map_err(|e| e.to_string())?;
+        let body = envelope::open(&data.envelope, identity).map_err(|e| e.to_string())?;
+        String::from_utf8(body).map_err(|_| "Message body is not UTF-8".to_string())
+    }
+
     // WebSocket methods
     #[wasm_bindgen]
     pub fn connect_relay(&mut self, relay_url: &str) -> Result<(), String> {
         let mut ws = OcmWebSocket::new();
+        ws.set_ingest(self.ingest.clone());
         ws.connect(relay_url)
             .map_err(|e| format!("Connection error: {:?}", e))?;
         self.websocket = Some(ws);
@@ -135,6 +455,45 @@ impl OcmWasm {
         }
     }
 
+    /// Call `callback` with presence announcements from other devices, so the UI can
+    /// show which of them are online and up to date
+    #[wasm_bindgen]
+    pub fn set_presence_callback(&mut self, callback: &js_sys::Function) {
+        if let Some(ws) = &mut self.websocket {
+            ws.set_on_presence(callback.clone());
+        }
+    }
+
+    /// Tell the other devices on the relay that this one is online. Sharing is up to
+    /// the app: call it only when the user has chosen to show their presence
+    #[wasm_bindgen]
+    pub fn announce_presence(
+        &self,
+        syncing: Option<bool>,
+        last_sync_at: Option<String>,
+    ) -> Result<(), String> {
+        let ws = self
+            .websocket
+            .as_ref()
+            .ok_or_else(|| "WebSocket not connected".to_string())?;
+        ws.send_presence(PresenceUpdate {
+            device_id: self.device_id.clone(),
+            did: self
+                .identity
+                .as_ref()
+                .map(|identity| identity.did.to_string()),
+            status: syncing.map(|syncing| {
+                if syncing {
+                    PresenceStatus::Syncing
+                } else {
+                    PresenceStatus::Idle
+                }
+            }),
+            last_sync_at,
+            sent_at: chrono::Utc::now().to_rfc3339(),
+        })
+    }
+
     #[wasm_bindgen]
     pub fn send_memory_to_relay(&self, memory_json: &str) -> Result<(), String> {
         let memory: SignedMemory =
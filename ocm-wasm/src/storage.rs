@@ -1,5 +1,7 @@
 use js_sys::{Array, Object, Reflect};
-use ocm_core::SignedMemory;
+use ocm_protocol::feed::{FeedPage, FeedQuery};
+use ocm_protocol::filter::{JsonColumn, SqlValue};
+use ocm_protocol::SignedMemory;
 use wasm_bindgen::prelude::*;
 use wasm_bindgen::JsCast;
 use wasm_bindgen_futures::JsFuture;
@@ -41,8 +43,8 @@ impl BrowserStorage {
         // Execute SQL INSERT
         let sql = "INSERT INTO signed_memory (id, did, memory_type, memory_data, content_hash, signature, timestamp, updated_on) VALUES (?, ?, ?, ?, ?, ?, ?, ?)";
         let params = Array::new();
-        params.push(&memory.id.clone().into());
-        params.push(&memory.did.clone().into());
+        params.push(&memory.id.as_str().into());
+        params.push(&memory.did.as_str().into());
         params.push(&memory.memory_type.clone().into());
         params.push(&memory.memory_data.clone().into());
         params.push(&memory.content_hash.clone().into());
@@ -71,7 +73,37 @@ impl BrowserStorage {
         let params = Array::new();
 
         let result = self.call_sql_query(sql, &params).await?;
-        let success = Reflect::get(&result, &"success".into())
+        Self::memories_from(&result)
+    }
+
+    /// One page of a feed, newest first, using the same filters as the native database
+    pub async fn list_feed(&self, query: &FeedQuery) -> Result<FeedPage, String> {
+        if !self.sqlite_ready {
+            return Err("SQLite not initialized".to_string());
+        }
+
+        let (clause, values) = query.where_clause(&JsonColumn("memory_data"));
+        let sql = format!(
+            "SELECT * FROM signed_memory WHERE {} ORDER BY timestamp DESC, id DESC LIMIT {}",
+            clause,
+            query.fetch_limit()
+        );
+        let params = Array::new();
+        for value in values {
+            params.push(&match value {
+                SqlValue::Null => JsValue::NULL,
+                SqlValue::Integer(integer) => JsValue::from_f64(integer as f64),
+                SqlValue::Real(real) => JsValue::from_f64(real),
+                SqlValue::Text(text) => JsValue::from(text),
+            });
+        }
+
+        let result = self.call_sql_query(&sql, &params).await?;
+        Ok(query.page(Self::memories_from(&result)?))
+    }
+
+    fn memories_from(result: &Object) -> Result<Vec<SignedMemory>, String> {
+        let success = Reflect::get(result, &"success".into())
             .unwrap()
             .as_bool()
             .unwrap_or(false);
@@ -80,7 +112,7 @@ impl BrowserStorage {
             return Err("Failed to query memories from SQLite".to_string());
         }
 
-        let data = Reflect::get(&result, &"data".into()).unwrap();
+        let data = Reflect::get(result, &"data".into()).unwrap();
         let data_array: Array = data.dyn_into().map_err(|_| "Invalid data format")?;
 
         let mut memories = Vec::new();
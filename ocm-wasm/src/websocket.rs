@@ -1,4 +1,9 @@
-use ocm_core::SignedMemory;
+use crate::ingest::BrowserIngest;
+use ocm_protocol::presence::PresenceUpdate;
+use ocm_protocol::relay::{RelayMessage, RELAY_PROTOCOL_VERSION};
+use ocm_protocol::SignedMemory;
+use std::cell::RefCell;
+use std::rc::Rc;
 use wasm_bindgen::prelude::*;
 use wasm_bindgen::JsCast;
 use web_sys::*;
@@ -7,6 +12,15 @@ use web_sys::*;
 pub struct OcmWebSocket {
     ws: Option<WebSocket>,
     on_message_callback: Option<js_sys::Function>,
+    on_presence_callback: Rc<RefCell<Option<js_sys::Function>>>,
+    ingest: Rc<BrowserIngest>, // Memories reach the callback only once verified
+}
+
+impl OcmWebSocket {
+    /// Share the client's verify-before-store policy and counters
+    pub(crate) fn set_ingest(&mut self, ingest: Rc<BrowserIngest>) {
+        self.ingest = ingest;
+    }
 }
 
 #[wasm_bindgen]
@@ -16,6 +30,8 @@ impl OcmWebSocket {
         Self {
             ws: None,
             on_message_callback: None,
+            on_presence_callback: Rc::new(RefCell::new(None)),
+            ingest: Rc::new(BrowserIngest::new()),
         }
     }
 
@@ -52,41 +68,65 @@ impl OcmWebSocket {
         Ok(())
     }
 
+    /// Call `callback` with each presence announcement other devices send through the
+    /// relay; announcements arrive through the handler `set_on_memory_received` installs
+    #[wasm_bindgen]
+    pub fn set_on_presence(&mut self, callback: js_sys::Function) {
+        *self.on_presence_callback.borrow_mut() = Some(callback);
+    }
+
     #[wasm_bindgen]
     pub fn set_on_memory_received(&mut self, callback: js_sys::Function) {
         if let Some(ws) = &self.ws {
             let callback_clone = callback.clone();
+            let presence_callback = self.on_presence_callback.clone();
+            let ingest = self.ingest.clone();
             self.on_message_callback = Some(callback);
             let onmessage = Closure::wrap(Box::new(move |event: MessageEvent| {
                 if let Ok(text) = event.data().dyn_into::<js_sys::JsString>() {
                     let text_string = String::from(text);
 
                     // Parse relay protocol message
-                    if let Ok(json) = serde_json::from_str::<serde_json::Value>(&text_string) {
-                        if let Some(msg_type) = json.get("type").and_then(|v| v.as_str()) {
-                            match msg_type {
-                                "memory_sync" => {
-                                    if let Some(data) = json.get("data") {
-                                        if let Ok(memory) =
-                                            serde_json::from_value::<SignedMemory>(data.clone())
-                                        {
-                                            let memory_js =
-                                                serde_wasm_bindgen::to_value(&memory).unwrap();
-                                            let _ =
-                                                callback_clone.call1(&JsValue::NULL, &memory_js);
-                                        }
-                                    }
-                                }
-                                "welcome" => {
-                                    web_sys::console::log_1(&"Connected to relay server".into());
-                                }
-                                _ => {
-                                    // Handle other message types if needed
-                                    web_sys::console::log_1(
-                                        &format!("Received message type: {}", msg_type).into(),
-                                    );
+                    match serde_json::from_str::<RelayMessage>(&text_string) {
+                        Ok(RelayMessage::MemorySync { data }) => {
+                            let callback = callback_clone.clone();
+                            let ingest = ingest.clone();
+                            wasm_bindgen_futures::spawn_local(async move {
+                                if ingest.check(&data, "relay").await {
+                                    let memory_js = serde_wasm_bindgen::to_value(&data).unwrap();
+                                    let _ = callback.call1(&JsValue::NULL, &memory_js);
                                 }
+                            });
+                        }
+                        Ok(RelayMessage::Presence(update)) => {
+                            if let Some(callback) = presence_callback.borrow().as_ref() {
+                                let update_js = serde_wasm_bindgen::to_value(&update).unwrap();
+                                let _ = callback.call1(&JsValue::NULL, &update_js);
+                            }
+                        }
+                        Ok(RelayMessage::Welcome {
+                            protocol_version, ..
+                        }) => {
+                            if protocol_version != RELAY_PROTOCOL_VERSION {
+                                web_sys::console::warn_1(
+                                    &format!(
+                                        "Relay speaks protocol v{}, client expects v{}",
+                                        protocol_version, RELAY_PROTOCOL_VERSION
+                                    )
+                                    .into(),
+                                );
                             }
+                            web_sys::console::log_1(&"Connected to relay server".into());
+                        }
+                        Ok(other) => {
+                            web_sys::console::log_1(
+                                &format!("Received relay message: {:?}", other).into(),
+                            );
+                        }
+                        Err(e) => {
+                            web_sys::console::warn_1(
+                                &format!("Ignoring malformed relay message: {}", e).into(),
+                            );
                         }
                     }
                 }
@@ -104,17 +144,12 @@ impl OcmWebSocket {
             let memory: SignedMemory = serde_json::from_str(memory_json)
                 .map_err(|e| format!("Invalid memory JSON: {}", e))?;
 
-            // Wrap memory in the relay protocol format
-            let message = serde_json::json!({
-                "type": "memory_sync",
-                "data": memory
-            });
-
-            let json = serde_json::to_string(&message).map_err(|e| e.to_string())?;
+            let memory_id = memory.id.clone();
+            let json = RelayMessage::MemorySync { data: memory }.to_json();
 
             ws.send_with_str(&json)
                 .map_err(|e| format!("Send error: {:?}", e))?;
-            web_sys::console::log_1(&format!("Sent memory: {}", memory.id).into());
+            web_sys::console::log_1(&format!("Sent memory: {}", memory_id).into());
         }
         Ok(())
     }
@@ -136,3 +171,13 @@ impl OcmWebSocket {
         }
     }
 }
+
+impl OcmWebSocket {
+    pub fn send_presence(&self, update: PresenceUpdate) -> Result<(), String> {
+        if let Some(ws) = &self.ws {
+            ws.send_with_str(&RelayMessage::Presence(update).to_json())
+                .map_err(|e| format!("Send error: {:?}", e))?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,192 @@
+use crate::queue::OverflowPolicy;
+use ocm_protocol::ingest::IngestPolicy;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+/// Relay settings, layered as defaults < TOML file < OCM_RELAY_* environment < CLI flags
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RelayConfig {
+    #[serde(default)]
+    pub server: ServerConfig,
+    #[serde(default)]
+    pub limits: LimitsConfig,
+    #[serde(default)]
+    pub auth: AuthConfig,
+    #[serde(default)]
+    pub directory: DirectoryConfig,
+    #[serde(default)]
+    pub ingest: IngestConfig,
+    pub tls: Option<TlsConfig>, // None serves plain ws://
+    #[serde(default = "default_log_level")]
+    pub log_level: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ServerConfig {
+    pub host: String,
+    pub port: u16,
+    pub max_connections: usize,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LimitsConfig {
+    pub max_frame_size: usize,   // Largest single WebSocket frame, in bytes
+    pub max_message_size: usize, // Largest (possibly fragmented) message, in bytes
+    pub queue_capacity: usize,   // Messages buffered per client before the overflow policy applies
+    pub overflow_policy: OverflowPolicy,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct AuthConfig {
+    pub tokens: Vec<String>, // Accepted `?token=` values; empty leaves the relay open
+}
+
+/// Rendezvous directory where nodes register their DID and reachable endpoints
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DirectoryConfig {
+    pub enabled: bool,
+    pub max_entries: usize,
+    pub default_ttl_secs: u64,
+    pub max_ttl_secs: u64,
+    pub max_endpoints: usize,     // Per registration
+    pub max_query_results: usize, // Per query
+}
+
+/// Verification of memories before they are relayed
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct IngestConfig {
+    pub verify_before_store: bool,
+    pub quarantine_capacity: usize, // Rejected memories kept for the quarantine query
+    pub plc_directory_url: String,  // Where did:plc authors' documents are fetched
+    pub document_ttl_secs: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TlsConfig {
+    pub cert_path: PathBuf,
+    pub key_path: PathBuf,
+}
+
+impl Default for ServerConfig {
+    fn default() -> Self {
+        Self {
+            host: "127.0.0.1".to_string(),
+            port: 8082,
+            max_connections: 1000,
+        }
+    }
+}
+
+impl Default for LimitsConfig {
+    fn default() -> Self {
+        Self {
+            max_frame_size: 1024 * 1024,
+            max_message_size: 4 * 1024 * 1024,
+            queue_capacity: 1000,
+            overflow_policy: OverflowPolicy::DropWithNotice,
+        }
+    }
+}
+
+impl Default for DirectoryConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            max_entries: 10_000,
+            default_ttl_secs: 300,
+            max_ttl_secs: 3600,
+            max_endpoints: 8,
+            max_query_results: 50,
+        }
+    }
+}
+
+impl Default for IngestConfig {
+    fn default() -> Self {
+        let policy = IngestPolicy::default();
+        Self {
+            verify_before_store: policy.verify_before_store,
+            quarantine_capacity: policy.quarantine_capacity,
+            plc_directory_url: "https://plc.directory".to_string(),
+            document_ttl_secs: 3600,
+        }
+    }
+}
+
+impl IngestConfig {
+    pub fn policy(&self) -> IngestPolicy {
+        IngestPolicy {
+            verify_before_store: self.verify_before_store,
+            quarantine_capacity: self.quarantine_capacity,
+        }
+    }
+}
+
+impl Default for RelayConfig {
+    fn default() -> Self {
+        Self {
+            server: ServerConfig::default(),
+            limits: LimitsConfig::default(),
+            auth: AuthConfig::default(),
+            directory: DirectoryConfig::default(),
+            ingest: IngestConfig::default(),
+            tls: None,
+            log_level: default_log_level(),
+        }
+    }
+}
+
+fn default_log_level() -> String {
+    "info".to_string()
+}
+
+impl RelayConfig {
+    /// Load from an optional TOML file, then apply environment overrides such as
+    /// OCM_RELAY_SERVER__PORT=9000 or OCM_RELAY_LIMITS__QUEUE_CAPACITY=500
+    pub fn load(path: Option<&Path>) -> Result<Self, String> {
+        let mut builder = config::Config::builder();
+        if let Some(path) = path {
+            builder = builder.add_source(config::File::from(path).format(config::FileFormat::Toml));
+        }
+        let config = builder
+            .add_source(config::Environment::with_prefix("OCM_RELAY").separator("__"))
+            .build()
+            .map_err(|e| format!("Failed to load relay config: {}", e))?;
+
+        config
+            .try_deserialize()
+            .map_err(|e| format!("Failed to parse relay config: {}", e))
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        if self.server.max_connections == 0 {
+            return Err("max_connections must be at least 1".to_string());
+        }
+        if self.limits.queue_capacity == 0 {
+            return Err("queue_capacity must be at least 1".to_string());
+        }
+        if self.limits.max_frame_size > self.limits.max_message_size {
+            return Err("max_frame_size cannot exceed max_message_size".to_string());
+        }
+        if self.auth.tokens.iter().any(|token| token.is_empty()) {
+            return Err("Auth tokens cannot be empty".to_string());
+        }
+        if self.directory.enabled
+            && (self.directory.default_ttl_secs == 0
+                || self.directory.default_ttl_secs > self.directory.max_ttl_secs)
+        {
+            return Err("Directory default TTL must be between 1 and max_ttl_secs".to_string());
+        }
+        if self.ingest.verify_before_store && self.ingest.plc_directory_url.is_empty() {
+            return Err("Verifying memories needs a PLC directory URL".to_string());
+        }
+        if let Some(tls) = &self.tls {
+            if !tls.cert_path.exists() || !tls.key_path.exists() {
+                return Err("TLS requires an existing certificate and key".to_string());
+            }
+        }
+        Ok(())
+    }
+}
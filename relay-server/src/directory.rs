@@ -0,0 +1,134 @@
+use crate::config::DirectoryConfig;
+use chrono::{DateTime, Duration, Utc};
+use ocm_protocol::relay::DirectoryEntry;
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+struct Registration {
+    node_id: String,
+    endpoints: Vec<String>,
+    observed_addr: String,
+    registered_at: DateTime<Utc>,
+    expires_at: DateTime<Utc>,
+}
+
+impl Registration {
+    fn entry(&self, did: &str) -> DirectoryEntry {
+        DirectoryEntry {
+            did: did.to_string(),
+            node_id: self.node_id.clone(),
+            endpoints: self.endpoints.clone(),
+            observed_addr: Some(self.observed_addr.clone()),
+            registered_at: self.registered_at.to_rfc3339(),
+            expires_at: self.expires_at.to_rfc3339(),
+        }
+    }
+}
+
+/// Rendezvous directory: DID -> where that node says it can be reached.
+/// Registrations outlive the connection that made them and lapse after their TTL
+pub struct Directory {
+    config: DirectoryConfig,
+    registrations: Mutex<HashMap<String, Registration>>,
+}
+
+impl Directory {
+    pub fn new(config: DirectoryConfig) -> Self {
+        Self {
+            config,
+            registrations: Mutex::new(HashMap::new()),
+        }
+    }
+
+    pub fn enabled(&self) -> bool {
+        self.config.enabled
+    }
+
+    pub fn register(
+        &self,
+        did: &str,
+        node_id: &str,
+        endpoints: Vec<String>,
+        ttl_secs: Option<u64>,
+        observed_addr: &str,
+    ) -> Result<DirectoryEntry, String> {
+        if !did.starts_with("did:") {
+            return Err(format!("Not a DID: {}", did));
+        }
+        if node_id.is_empty() {
+            return Err("Registration needs a node ID".to_string());
+        }
+        if endpoints.is_empty() || endpoints.len() > self.config.max_endpoints {
+            return Err(format!(
+                "Registrations need 1 to {} endpoints",
+                self.config.max_endpoints
+            ));
+        }
+        if let Some(endpoint) = endpoints.iter().find(|e| !is_endpoint(e)) {
+            return Err(format!("Invalid endpoint {}, expected host:port", endpoint));
+        }
+
+        let ttl = ttl_secs
+            .unwrap_or(self.config.default_ttl_secs)
+            .min(self.config.max_ttl_secs);
+        let now = Utc::now();
+        let mut registrations = self.registrations.lock().unwrap_or_else(|e| e.into_inner());
+        registrations.retain(|_, registration| registration.expires_at > now);
+        if !registrations.contains_key(did) && registrations.len() >= self.config.max_entries {
+            return Err("Directory is full".to_string());
+        }
+
+        let registration = Registration {
+            node_id: node_id.to_string(),
+            endpoints,
+            observed_addr: observed_addr.to_string(),
+            registered_at: now,
+            expires_at: now + Duration::seconds(ttl as i64),
+        };
+        let entry = registration.entry(did);
+        registrations.insert(did.to_string(), registration);
+        Ok(entry)
+    }
+
+    /// Live entries for the given DIDs, or up to `max_query_results` of any when none are given
+    pub fn lookup(&self, dids: &[String]) -> Vec<DirectoryEntry> {
+        let now = Utc::now();
+        let registrations = self.registrations.lock().unwrap_or_else(|e| e.into_inner());
+        let live = |registration: &&Registration| registration.expires_at > now;
+
+        if dids.is_empty() {
+            registrations
+                .iter()
+                .filter(|(_, registration)| live(registration))
+                .take(self.config.max_query_results)
+                .map(|(did, registration)| registration.entry(did))
+                .collect()
+        } else {
+            dids.iter()
+                .take(self.config.max_query_results)
+                .filter_map(|did| {
+                    registrations
+                        .get(did)
+                        .filter(live)
+                        .map(|registration| registration.entry(did))
+                })
+                .collect()
+        }
+    }
+
+    /// Live registrations, for the metrics log
+    pub fn live_count(&self) -> usize {
+        let now = Utc::now();
+        let registrations = self.registrations.lock().unwrap_or_else(|e| e.into_inner());
+        registrations
+            .values()
+            .filter(|registration| registration.expires_at > now)
+            .count()
+    }
+}
+
+fn is_endpoint(endpoint: &str) -> bool {
+    endpoint
+        .rsplit_once(':')
+        .is_some_and(|(host, port)| !host.is_empty() && port.parse::<u16>().is_ok())
+}
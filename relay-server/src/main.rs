@@ -1,80 +1,281 @@
+mod config;
+mod directory;
+mod queue;
+mod verify;
+
+use crate::config::{RelayConfig, TlsConfig};
+use crate::directory::Directory;
+use crate::verify::MemoryVerifier;
 use clap::Parser;
 use futures_util::{SinkExt, StreamExt};
+use ocm_protocol::redact::Redacted;
+use ocm_protocol::relay::RelayMessage;
+use queue::{ClientQueue, OverflowPolicy, PushOutcome, RelayMetrics};
 use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicUsize, Ordering};
 use std::sync::Arc;
-use tokio::net::{TcpListener, TcpStream};
-use tokio::sync::{broadcast, Mutex};
-use tokio_tungstenite::{accept_async, tungstenite::Message};
+use tokio::io::{AsyncRead, AsyncWrite};
+use tokio::net::TcpListener;
+use tokio::sync::Mutex;
+use tokio_rustls::{rustls, TlsAcceptor};
+use tokio_tungstenite::{
+    accept_hdr_async_with_config,
+    tungstenite::{
+        handshake::server::{ErrorResponse, Request, Response},
+        http::StatusCode,
+        protocol::WebSocketConfig,
+        Message,
+    },
+};
 use tracing::{error, info, warn};
 use uuid::Uuid;
 
+/// Command line flags override the config file and environment
 #[derive(Parser)]
 #[command(name = "ocm-relay")]
 #[command(about = "OCM WebSocket Relay Server for tab-to-tab synchronization")]
 struct Args {
-    #[arg(short = 'H', long, default_value = "127.0.0.1")]
-    host: String,
+    /// TOML config file
+    #[arg(short, long)]
+    config: Option<PathBuf>,
+
+    #[arg(short = 'H', long)]
+    host: Option<String>,
+
+    #[arg(short, long)]
+    port: Option<u16>,
+
+    /// Largest single WebSocket frame accepted from a client, in bytes
+    #[arg(long)]
+    max_frame_size: Option<usize>,
+
+    /// Largest (possibly fragmented) message accepted from a client, in bytes
+    #[arg(long)]
+    max_message_size: Option<usize>,
+
+    /// Messages buffered per client before the overflow policy applies
+    #[arg(long)]
+    queue_capacity: Option<usize>,
+
+    /// What to do when a slow client's queue is full
+    #[arg(long, value_enum)]
+    overflow_policy: Option<OverflowPolicy>,
+
+    #[arg(long)]
+    log_level: Option<String>,
+}
 
-    #[arg(short, long, default_value = "8082")]
-    port: u16,
+impl Args {
+    fn apply(self, config: &mut RelayConfig) {
+        if let Some(host) = self.host {
+            config.server.host = host;
+        }
+        if let Some(port) = self.port {
+            config.server.port = port;
+        }
+        if let Some(max_frame_size) = self.max_frame_size {
+            config.limits.max_frame_size = max_frame_size;
+        }
+        if let Some(max_message_size) = self.max_message_size {
+            config.limits.max_message_size = max_message_size;
+        }
+        if let Some(queue_capacity) = self.queue_capacity {
+            config.limits.queue_capacity = queue_capacity;
+        }
+        if let Some(overflow_policy) = self.overflow_policy {
+            config.limits.overflow_policy = overflow_policy;
+        }
+        if let Some(log_level) = self.log_level {
+            config.log_level = log_level;
+        }
+    }
 }
 
-type Connections = Arc<Mutex<HashMap<String, broadcast::Sender<String>>>>;
+const METRICS_LOG_INTERVAL_SECS: u64 = 60;
+
+type Connections = Arc<Mutex<HashMap<String, Arc<ClientQueue>>>>;
+
+/// Shared state and settings for every client connection
+struct Relay {
+    connections: Connections,
+    metrics: RelayMetrics,
+    ws_config: WebSocketConfig,
+    queue_capacity: usize,
+    overflow_policy: OverflowPolicy,
+    max_connections: usize,
+    active_connections: AtomicUsize,
+    auth_tokens: Vec<String>,
+    directory: Directory,
+    verifier: MemoryVerifier,
+}
+
+// Frees a connection slot however the connection ends
+struct ConnectionSlot(Arc<Relay>);
+
+impl Drop for ConnectionSlot {
+    fn drop(&mut self) {
+        self.0.active_connections.fetch_sub(1, Ordering::SeqCst);
+    }
+}
 
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let args = Args::parse();
+    let mut config = RelayConfig::load(args.config.as_deref())?;
+    args.apply(&mut config);
+    config.validate()?;
+
     tracing_subscriber::fmt()
-        .with_env_filter("relay_server=info")
+        .with_env_filter(format!("relay_server={}", config.log_level))
         .init();
 
-    let args = Args::parse();
-    let addr = format!("{}:{}", args.host, args.port);
+    let addr = format!("{}:{}", config.server.host, config.server.port);
+    let tls_acceptor = config.tls.as_ref().map(tls_acceptor).transpose()?;
 
     let listener = TcpListener::bind(&addr).await?;
-    info!("OCM Relay Server listening on: {}", addr);
+    info!(
+        "OCM Relay Server listening on: {} ({})",
+        addr,
+        if tls_acceptor.is_some() { "wss" } else { "ws" }
+    );
 
-    let connections: Connections = Arc::new(Mutex::new(HashMap::new()));
+    let relay = Arc::new(Relay {
+        connections: Arc::new(Mutex::new(HashMap::new())),
+        metrics: RelayMetrics::default(),
+        // Oversized frames or messages close the connection with a protocol error
+        ws_config: WebSocketConfig {
+            max_frame_size: Some(config.limits.max_frame_size),
+            max_message_size: Some(config.limits.max_message_size),
+            ..Default::default()
+        },
+        queue_capacity: config.limits.queue_capacity,
+        overflow_policy: config.limits.overflow_policy,
+        max_connections: config.server.max_connections,
+        active_connections: AtomicUsize::new(0),
+        auth_tokens: config.auth.tokens.clone(),
+        directory: Directory::new(config.directory.clone()),
+        verifier: MemoryVerifier::new(config.ingest.clone()),
+    });
+
+    let metrics_relay = Arc::clone(&relay);
+    tokio::spawn(async move {
+        let mut interval =
+            tokio::time::interval(tokio::time::Duration::from_secs(METRICS_LOG_INTERVAL_SECS));
+        loop {
+            interval.tick().await;
+            let clients = metrics_relay.connections.lock().await.len();
+            info!(
+                "Relay metrics: {:?}, {} directory entries",
+                metrics_relay
+                    .metrics
+                    .report(clients, 0, metrics_relay.verifier.stats()),
+                metrics_relay.directory.live_count()
+            );
+        }
+    });
 
     while let Ok((stream, addr)) = listener.accept().await {
+        if relay.active_connections.fetch_add(1, Ordering::SeqCst) >= relay.max_connections {
+            relay.active_connections.fetch_sub(1, Ordering::SeqCst);
+            warn!(
+                "Rejecting connection from {}: connection limit reached",
+                addr
+            );
+            continue;
+        }
         info!("New connection from: {}", addr);
-        let connections = Arc::clone(&connections);
+        let slot = ConnectionSlot(Arc::clone(&relay));
+        let relay = Arc::clone(&relay);
+        let tls_acceptor = tls_acceptor.clone();
 
-        tokio::spawn(handle_connection(stream, connections, addr.to_string()));
+        tokio::spawn(async move {
+            let _slot = slot;
+            match tls_acceptor {
+                Some(acceptor) => match acceptor.accept(stream).await {
+                    Ok(tls_stream) => handle_connection(tls_stream, relay, addr.to_string()).await,
+                    Err(e) => warn!("TLS handshake failed for {}: {}", addr, e),
+                },
+                None => handle_connection(stream, relay, addr.to_string()).await,
+            }
+        });
     }
 
     Ok(())
 }
 
-async fn handle_connection(stream: TcpStream, connections: Connections, client_addr: String) {
+fn tls_acceptor(tls: &TlsConfig) -> Result<TlsAcceptor, Box<dyn std::error::Error>> {
+    let mut cert_reader = std::io::BufReader::new(std::fs::File::open(&tls.cert_path)?);
+    let certs = rustls_pemfile::certs(&mut cert_reader).collect::<Result<Vec<_>, _>>()?;
+    let mut key_reader = std::io::BufReader::new(std::fs::File::open(&tls.key_path)?);
+    let key = rustls_pemfile::private_key(&mut key_reader)?
+        .ok_or("No private key found in TLS key file")?;
+
+    let server_config = rustls::ServerConfig::builder()
+        .with_no_client_auth()
+        .with_single_cert(certs, key)?;
+    Ok(TlsAcceptor::from(Arc::new(server_config)))
+}
+
+/// Browsers cannot set headers on a WebSocket, so the token travels as `?token=`
+fn has_valid_token(request: &Request, tokens: &[String]) -> bool {
+    if tokens.is_empty() {
+        return true;
+    }
+    request
+        .uri()
+        .query()
+        .into_iter()
+        .flat_map(|query| query.split('&'))
+        .filter_map(|pair| pair.strip_prefix("token="))
+        .any(|token| tokens.iter().any(|allowed| allowed == token))
+}
+
+async fn handle_connection<S>(stream: S, relay: Arc<Relay>, client_addr: String)
+where
+    S: AsyncRead + AsyncWrite + Unpin + Send + 'static,
+{
     let client_id = Uuid::new_v4().to_string();
+    let connections = &relay.connections;
 
-    let ws_stream = match accept_async(stream).await {
-        Ok(ws) => ws,
-        Err(e) => {
-            error!("WebSocket handshake failed for {}: {}", client_addr, e);
-            return;
+    // tungstenite's handshake callback must return an unboxed ErrorResponse
+    #[allow(clippy::result_large_err)]
+    let authorize = |request: &Request, response: Response| -> Result<Response, ErrorResponse> {
+        if has_valid_token(request, &relay.auth_tokens) {
+            Ok(response)
+        } else {
+            let mut rejection = ErrorResponse::new(Some("Invalid or missing token".to_string()));
+            *rejection.status_mut() = StatusCode::UNAUTHORIZED;
+            Err(rejection)
         }
     };
 
+    let ws_stream =
+        match accept_hdr_async_with_config(stream, authorize, Some(relay.ws_config)).await {
+            Ok(ws) => ws,
+            Err(e) => {
+                error!("WebSocket handshake failed for {}: {}", client_addr, e);
+                return;
+            }
+        };
+
     let (mut ws_sender, mut ws_receiver) = ws_stream.split();
-    let (tx, mut rx) = broadcast::channel(1000);
+    let queue = Arc::new(ClientQueue::new(
+        relay.queue_capacity,
+        relay.overflow_policy,
+    ));
 
     // Store connection
     {
         let mut conns = connections.lock().await;
-        conns.insert(client_id.clone(), tx.clone());
+        conns.insert(client_id.clone(), Arc::clone(&queue));
     }
 
     info!("Client {} connected ({})", client_id, client_addr);
 
     // Send initial welcome message
-    let welcome = serde_json::json!({
-        "type": "welcome",
-        "client_id": client_id,
-        "message": "Connected to OCM relay server"
-    });
-
-    if let Err(e) = ws_sender.send(Message::Text(welcome.to_string())).await {
+    let welcome = RelayMessage::welcome(&client_id);
+    if let Err(e) = ws_sender.send(Message::Text(welcome.to_json())).await {
         warn!("Failed to send welcome message to {}: {}", client_id, e);
     }
 
@@ -82,14 +283,20 @@ async fn handle_connection(stream: TcpStream, connections: Connections, client_a
     let client_id_clone = client_id.clone();
     let ws_sender_arc = Arc::new(Mutex::new(ws_sender));
     let ws_sender_clone = ws_sender_arc.clone();
+    let writer_queue = Arc::clone(&queue);
     tokio::spawn(async move {
-        while let Ok(message) = rx.recv().await {
+        while let Some(message) = writer_queue.pop().await {
             let mut sender = ws_sender_clone.lock().await;
-            if let Err(e) = sender.send(Message::Text(message)).await {
+            if let Err(e) = sender.send(message).await {
                 warn!("Failed to send message to {}: {}", client_id_clone, e);
-                break;
+                writer_queue.close();
+                return;
             }
         }
+
+        // Closed by the overflow policy, or because the client went away
+        let mut sender = ws_sender_clone.lock().await;
+        let _ = sender.send(Message::Close(None)).await;
     });
 
     // Handle incoming messages from this client
@@ -98,37 +305,119 @@ async fn handle_connection(stream: TcpStream, connections: Connections, client_a
             Ok(Message::Text(text)) => {
                 info!("Received message from {}: {}", client_id, text);
 
-                // Try to parse as JSON to determine message type
-                if let Ok(json) = serde_json::from_str::<serde_json::Value>(&text) {
-                    if let Some(msg_type) = json.get("type").and_then(|v| v.as_str()) {
-                        match msg_type {
-                            "memory_sync" => {
-                                // Broadcast memory to all other clients
-                                broadcast_to_others(&connections, &client_id, &text).await;
-                            }
-                            "ping" => {
-                                // Respond with pong
-                                let pong = serde_json::json!({
-                                    "type": "pong",
-                                    "timestamp": chrono::Utc::now().to_rfc3339()
-                                });
+                // Typed messages are handled here; anything else is forwarded unchanged
+                match serde_json::from_str::<RelayMessage>(&text) {
+                    Ok(RelayMessage::Ping) => {
+                        let mut sender = ws_sender_arc.lock().await;
+                        if let Err(e) = sender
+                            .send(Message::Text(RelayMessage::pong().to_json()))
+                            .await
+                        {
+                            warn!("Failed to send pong to {}: {}", client_id, e);
+                        }
+                    }
+                    Ok(RelayMessage::Metrics(_)) => {
+                        let clients = connections.lock().await.len();
+                        let report =
+                            relay
+                                .metrics
+                                .report(clients, queue.dropped(), relay.verifier.stats());
 
+                        let mut sender = ws_sender_arc.lock().await;
+                        if let Err(e) = sender
+                            .send(Message::Text(RelayMessage::Metrics(report).to_json()))
+                            .await
+                        {
+                            warn!("Failed to send metrics to {}: {}", client_id, e);
+                        }
+                    }
+                    Ok(RelayMessage::MemorySync { data }) => {
+                        match relay.verifier.check(&data, &client_addr).await {
+                            Ok(()) => {
+                                broadcast_to_others(&relay, &client_id, Message::Text(text)).await;
+                            }
+                            Err(reason) => {
+                                let reply = RelayMessage::MemoryRejected {
+                                    memory_id: data.id,
+                                    reason,
+                                };
                                 let mut sender = ws_sender_arc.lock().await;
-                                if let Err(e) = sender.send(Message::Text(pong.to_string())).await {
-                                    warn!("Failed to send pong to {}: {}", client_id, e);
+                                if let Err(e) = sender.send(Message::Text(reply.to_json())).await {
+                                    warn!("Failed to send rejection to {}: {}", client_id, e);
                                 }
                             }
-                            _ => {
-                                // Forward unknown message types to all clients
-                                broadcast_to_others(&connections, &client_id, &text).await;
+                        }
+                    }
+                    Ok(RelayMessage::Quarantine { .. }) => {
+                        let reply = RelayMessage::Quarantine {
+                            memories: relay.verifier.quarantined(),
+                        };
+                        let mut sender = ws_sender_arc.lock().await;
+                        if let Err(e) = sender.send(Message::Text(reply.to_json())).await {
+                            warn!("Failed to send quarantine to {}: {}", client_id, e);
+                        }
+                    }
+                    Ok(RelayMessage::DirectoryRegister {
+                        did,
+                        node_id,
+                        endpoints,
+                        ttl_secs,
+                    }) => {
+                        let reply = if !relay.directory.enabled() {
+                            directory_disabled()
+                        } else {
+                            match relay.directory.register(
+                                &did,
+                                &node_id,
+                                endpoints,
+                                ttl_secs,
+                                &client_addr,
+                            ) {
+                                Ok(entry) => {
+                                    info!("Registered {} in the directory", Redacted::did(&did));
+                                    RelayMessage::DirectoryEntries {
+                                        entries: vec![entry],
+                                    }
+                                }
+                                Err(reason) => RelayMessage::DirectoryRejected { reason },
                             }
+                        };
+
+                        let mut sender = ws_sender_arc.lock().await;
+                        if let Err(e) = sender.send(Message::Text(reply.to_json())).await {
+                            warn!("Failed to answer registration from {}: {}", client_id, e);
                         }
                     }
-                } else {
-                    // Forward non-JSON messages as-is
-                    broadcast_to_others(&connections, &client_id, &text).await;
+                    Ok(RelayMessage::DirectoryQuery { dids }) => {
+                        let reply = if relay.directory.enabled() {
+                            RelayMessage::DirectoryEntries {
+                                entries: relay.directory.lookup(&dids),
+                            }
+                        } else {
+                            directory_disabled()
+                        };
+
+                        let mut sender = ws_sender_arc.lock().await;
+                        if let Err(e) = sender.send(Message::Text(reply.to_json())).await {
+                            warn!("Failed to answer directory query from {}: {}", client_id, e);
+                        }
+                    }
+                    _ => {
+                        // Presence, unknown message types and non-JSON go to all other clients
+                        broadcast_to_others(&relay, &client_id, Message::Text(text)).await;
+                    }
                 }
             }
+            Ok(Message::Binary(data)) => {
+                // Binary payloads (CBOR, compressed JSON, ...) are opaque to the relay,
+                // so they are routed like non-JSON text: forwarded unchanged to everyone else
+                info!(
+                    "Received {} byte binary message from {}",
+                    data.len(),
+                    client_id
+                );
+                broadcast_to_others(&relay, &client_id, Message::Binary(data)).await;
+            }
             Ok(Message::Close(_)) => {
                 info!("Client {} disconnected", client_id);
                 break;
@@ -140,7 +429,7 @@ async fn handle_connection(stream: TcpStream, connections: Connections, client_a
                 }
             }
             Ok(_) => {
-                // Handle other message types (Binary, Pong, etc.)
+                // Handle other message types (Pong, raw frames)
             }
             Err(e) => {
                 error!("WebSocket error for {}: {}", client_id, e);
@@ -154,25 +443,44 @@ async fn handle_connection(stream: TcpStream, connections: Connections, client_a
         let mut conns = connections.lock().await;
         conns.remove(&client_id);
     }
+    queue.close();
+
+    info!(
+        "Client {} connection closed ({} messages dropped)",
+        client_id,
+        queue.dropped()
+    );
+}
 
-    info!("Client {} connection closed", client_id);
+fn directory_disabled() -> RelayMessage {
+    RelayMessage::DirectoryRejected {
+        reason: "This relay does not run a directory".to_string(),
+    }
 }
 
-async fn broadcast_to_others(connections: &Connections, sender_id: &str, message: &str) {
-    let conns = connections.lock().await;
+async fn broadcast_to_others(relay: &Relay, sender_id: &str, message: Message) {
+    let mut conns = relay.connections.lock().await;
 
-    let mut failed_clients = Vec::new();
+    let mut dropped = 0;
+    let mut disconnected_clients = Vec::new();
 
-    for (client_id, tx) in conns.iter() {
+    for (client_id, queue) in conns.iter() {
         if client_id != sender_id {
-            if let Err(_) = tx.send(message.to_string()) {
-                failed_clients.push(client_id.clone());
+            let outcome = queue.push(message.clone());
+            relay.metrics.record(outcome);
+            match outcome {
+                PushOutcome::Dropped => dropped += 1,
+                PushOutcome::Disconnected => disconnected_clients.push(client_id.clone()),
+                PushOutcome::Queued | PushOutcome::Closed => {}
             }
         }
     }
 
-    // Note: Failed clients will be cleaned up when their connections close
-    if !failed_clients.is_empty() {
-        warn!("Failed to broadcast to {} clients", failed_clients.len());
+    if dropped > 0 {
+        warn!("Dropped message for {} slow clients", dropped);
+    }
+    for client_id in disconnected_clients {
+        warn!("Disconnecting slow client {}: queue full", client_id);
+        conns.remove(&client_id);
     }
 }
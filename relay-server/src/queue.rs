@@ -0,0 +1,164 @@
+use clap::ValueEnum;
+use ocm_protocol::ingest::IngestStats;
+use ocm_protocol::relay::{RelayMessage, RelayMetricsReport};
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use tokio::sync::Notify;
+use tokio_tungstenite::tungstenite::Message;
+
+/// What to do when a slow client's outgoing queue is full
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum OverflowPolicy {
+    /// Close the client's connection
+    Disconnect,
+    /// Discard the oldest queued message to make room
+    DropOldest,
+    /// Discard the new message and tell the client how many it missed
+    DropWithNotice,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PushOutcome {
+    Queued,
+    Dropped,
+    Disconnected, // This push overflowed the queue and closed it
+    Closed,       // The queue was already closed
+}
+
+struct QueueState {
+    messages: VecDeque<Message>,
+    unreported_drops: u64, // Drops not yet announced to the client (DropWithNotice)
+    closed: bool,
+}
+
+/// Bounded outgoing queue for one client, drained by that client's writer task
+pub struct ClientQueue {
+    state: Mutex<QueueState>,
+    notify: Notify,
+    capacity: usize,
+    policy: OverflowPolicy,
+    dropped: AtomicU64,
+}
+
+impl ClientQueue {
+    pub fn new(capacity: usize, policy: OverflowPolicy) -> Self {
+        Self {
+            state: Mutex::new(QueueState {
+                messages: VecDeque::with_capacity(capacity),
+                unreported_drops: 0,
+                closed: false,
+            }),
+            notify: Notify::new(),
+            capacity,
+            policy,
+            dropped: AtomicU64::new(0),
+        }
+    }
+
+    pub fn push(&self, message: Message) -> PushOutcome {
+        let mut state = self.state.lock().unwrap();
+        if state.closed {
+            return PushOutcome::Closed;
+        }
+
+        let outcome = if state.messages.len() < self.capacity {
+            state.messages.push_back(message);
+            PushOutcome::Queued
+        } else {
+            match self.policy {
+                OverflowPolicy::Disconnect => {
+                    state.closed = true;
+                    state.messages.clear();
+                    PushOutcome::Disconnected
+                }
+                OverflowPolicy::DropOldest => {
+                    state.messages.pop_front();
+                    state.messages.push_back(message);
+                    self.dropped.fetch_add(1, Ordering::Relaxed);
+                    PushOutcome::Dropped
+                }
+                OverflowPolicy::DropWithNotice => {
+                    state.unreported_drops += 1;
+                    self.dropped.fetch_add(1, Ordering::Relaxed);
+                    PushOutcome::Dropped
+                }
+            }
+        };
+        drop(state);
+
+        self.notify.notify_one();
+        outcome
+    }
+
+    /// Next message to send, or None once the queue has been closed
+    pub async fn pop(&self) -> Option<Message> {
+        loop {
+            {
+                let mut state = self.state.lock().unwrap();
+                if state.closed {
+                    return None;
+                }
+                if state.unreported_drops > 0 {
+                    let notice = RelayMessage::MessagesDropped {
+                        count: state.unreported_drops,
+                        timestamp: chrono::Utc::now().to_rfc3339(),
+                    };
+                    state.unreported_drops = 0;
+                    return Some(Message::Text(notice.to_json()));
+                }
+                if let Some(message) = state.messages.pop_front() {
+                    return Some(message);
+                }
+            }
+            self.notify.notified().await;
+        }
+    }
+
+    pub fn close(&self) {
+        self.state.lock().unwrap().closed = true;
+        self.notify.notify_one();
+    }
+
+    pub fn dropped(&self) -> u64 {
+        self.dropped.load(Ordering::Relaxed)
+    }
+}
+
+/// Relay-wide counters, logged periodically and returned to clients that ask
+#[derive(Default)]
+pub struct RelayMetrics {
+    pub messages_relayed: AtomicU64,
+    pub messages_dropped: AtomicU64,
+    pub slow_client_disconnects: AtomicU64,
+}
+
+impl RelayMetrics {
+    pub fn record(&self, outcome: PushOutcome) {
+        let counter = match outcome {
+            PushOutcome::Queued => &self.messages_relayed,
+            PushOutcome::Dropped => &self.messages_dropped,
+            PushOutcome::Disconnected => &self.slow_client_disconnects,
+            PushOutcome::Closed => return,
+        };
+        counter.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn report(
+        &self,
+        connected_clients: usize,
+        client_dropped: u64,
+        ingest: IngestStats,
+    ) -> RelayMetricsReport {
+        RelayMetricsReport {
+            connected_clients,
+            messages_relayed: self.messages_relayed.load(Ordering::Relaxed),
+            messages_dropped: self.messages_dropped.load(Ordering::Relaxed),
+            slow_client_disconnects: self.slow_client_disconnects.load(Ordering::Relaxed),
+            client_dropped,
+            ingest,
+        }
+    }
+}
@@ -0,0 +1,122 @@
+use crate::config::IngestConfig;
+use chrono::{DateTime, Duration, Utc};
+use ocm_protocol::did::{needs_plc_document, verify_memory_with_document, PlcDocument};
+use ocm_protocol::ingest::{IngestLedger, IngestStats, QuarantinedMemory, RejectionReason};
+use ocm_protocol::redact::Redacted;
+use ocm_protocol::SignedMemory;
+use std::collections::HashMap;
+use std::sync::Mutex;
+use tracing::warn;
+
+/// Checks memories against their author's key before they are relayed.
+/// did:plc documents are fetched from the PLC directory and cached for `document_ttl_secs`
+pub struct MemoryVerifier {
+    config: IngestConfig,
+    client: reqwest::Client,
+    ledger: Mutex<IngestLedger>,
+    documents: Mutex<HashMap<String, (PlcDocument, DateTime<Utc>)>>,
+}
+
+impl MemoryVerifier {
+    pub fn new(config: IngestConfig) -> Self {
+        Self {
+            ledger: Mutex::new(IngestLedger::new(config.policy())),
+            client: reqwest::Client::builder()
+                .timeout(std::time::Duration::from_secs(10))
+                .build()
+                .unwrap_or_default(),
+            documents: Mutex::new(HashMap::new()),
+            config,
+        }
+    }
+
+    /// Whether `memory` from client `source` may be relayed; rejections are counted and
+    /// tampered memories quarantined
+    pub async fn check(&self, memory: &SignedMemory, source: &str) -> Result<(), RejectionReason> {
+        if !self.config.verify_before_store {
+            self.ledger().record_unchecked();
+            return Ok(());
+        }
+
+        let document = if needs_plc_document(&memory.did) {
+            self.document(&memory.did).await
+        } else {
+            None
+        };
+        match verify_memory_with_document(memory, document.as_ref()) {
+            Ok(()) => {
+                self.ledger().record_verified();
+                Ok(())
+            }
+            Err(reason) => {
+                warn!(
+                    "Rejected memory {} from {}: {}",
+                    memory.id,
+                    source,
+                    reason.as_str()
+                );
+                self.ledger().reject(memory, reason, source);
+                Err(reason)
+            }
+        }
+    }
+
+    pub fn stats(&self) -> IngestStats {
+        self.ledger().stats().clone()
+    }
+
+    pub fn quarantined(&self) -> Vec<QuarantinedMemory> {
+        self.ledger().quarantined()
+    }
+
+    fn ledger(&self) -> std::sync::MutexGuard<'_, IngestLedger> {
+        self.ledger.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    async fn document(&self, did: &str) -> Option<PlcDocument> {
+        let now = Utc::now();
+        {
+            let documents = self.documents.lock().unwrap_or_else(|e| e.into_inner());
+            if let Some((document, fetched_at)) = documents.get(did) {
+                if now - *fetched_at < Duration::seconds(self.config.document_ttl_secs as i64) {
+                    return Some(document.clone());
+                }
+            }
+        }
+
+        let url = format!(
+            "{}/{}",
+            self.config.plc_directory_url.trim_end_matches('/'),
+            did
+        );
+        let document = match self.client.get(&url).send().await {
+            Ok(response) if response.status().is_success() => {
+                response.json::<PlcDocument>().await.ok()
+            }
+            Ok(response) => {
+                warn!(
+                    "PLC directory answered {} for {}",
+                    response.status(),
+                    Redacted::did(did)
+                );
+                None
+            }
+            Err(e) => {
+                warn!(
+                    "Failed to fetch the PLC document of {}: {}",
+                    Redacted::did(did),
+                    e.without_url() // The URL ends in the DID
+                );
+                None
+            }
+        }
+        .filter(|document| document.id == did)?;
+
+        let mut documents = self.documents.lock().unwrap_or_else(|e| e.into_inner());
+        documents.retain(|_, (_, fetched_at)| {
+            now - *fetched_at < Duration::seconds(self.config.document_ttl_secs as i64)
+        });
+        documents.insert(did.to_string(), (document.clone(), now));
+        Some(document)
+    }
+}